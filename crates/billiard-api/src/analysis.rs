@@ -0,0 +1,77 @@
+use axum::{Json, extract::State, response::IntoResponse};
+use tracing::{info, instrument};
+
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::routes::check_table_size;
+use crate::state::AppState;
+use crate::types::MeanFreePathRequest;
+
+use billiard_core::dynamics::config::SimulationConfig;
+use billiard_core::dynamics::ensemble::ensemble_statistics;
+use billiard_core::dynamics::santalo::{MeanFreePathCheck, check_mean_free_path};
+
+/// Mean free path check endpoint for POST /analysis/mean-free-path.
+///
+/// Follows an ensemble of particles drawn from the invariant measure and
+/// compares their mean free path with Santaló's prediction
+/// `π · area / perimeter`. A relative error of more than a few percent on
+/// a large ensemble means the table is probably not what was intended.
+#[utoipa::path(
+    post,
+    path = "/analysis/mean-free-path",
+    request_body = MeanFreePathRequest,
+    responses(
+        (status = 200, description = "Measured and predicted mean free path", body = MeanFreePathCheck),
+        (status = 400, description = "Invalid request parameters", body = ErrorBody),
+        (status = 422, description = "Request exceeds configured limits or the table is invalid", body = ErrorBody),
+        (status = 429, description = "Rate limit exceeded", body = ErrorBody),
+    )
+)]
+#[instrument(skip(state, req))]
+pub async fn mean_free_path(
+    State(state): State<AppState>,
+    Json(req): Json<MeanFreePathRequest>,
+) -> ApiResult<impl IntoResponse> {
+    if req.particles == 0 || req.collisions == 0 {
+        return Err(ApiError::BadRequest(
+            "particles and collisions must be greater than 0".to_string(),
+        ));
+    }
+    let config = SimulationConfig::builder().epsilon(req.epsilon).build()?;
+
+    let steps = req.particles.checked_mul(req.collisions);
+    if steps.is_none_or(|steps| steps > state.config.max_steps) {
+        return Err(ApiError::LimitExceeded(format!(
+            "particles × collisions exceeds the limit of {} steps",
+            state.config.max_steps
+        )));
+    }
+    check_table_size(&req.table, &state.config)?;
+    let table = req
+        .table
+        .try_to_billiard_table()
+        .map_err(ApiError::InvalidTable)?;
+
+    info!(
+        particles = req.particles,
+        collisions = req.collisions,
+        "Running mean free path check"
+    );
+    let (particles, collisions, seed) = (req.particles, req.collisions, req.seed);
+    let check = state
+        .compute(move || {
+            ensemble_statistics(
+                &table,
+                particles,
+                collisions,
+                config.epsilon(),
+                config.step_options(),
+                seed,
+            )
+            .map(|statistics| check_mean_free_path(&table, &statistics.free_path))
+        })
+        .await
+        .map_err(|e| ApiError::Internal(format!("ensemble task failed: {}", e)))??;
+
+    Ok(Json(check))
+}
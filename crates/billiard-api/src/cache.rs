@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::http::{HeaderMap, header};
+
+use crate::types::{CacheStatsDto, SimulateRequest, SimulateResponse};
+
+use billiard_core::geometry::canonical::{ObstacleOrder, fnv1a};
+
+/// Response header telling whether POST /simulate was answered from the
+/// cache: `hit`, `miss`, or `bypass` when the cache was not consulted.
+pub const X_CACHE: &str = "x-cache";
+
+/// Responses with more collision records than this are not kept: a few of
+/// them would fill the memory the cache is meant to save.
+const MAX_CACHED_COLLISIONS: usize = 100_000;
+
+/// Least-recently-used cache of POST /simulate responses.
+///
+/// Simulations are deterministic, so a response depends on nothing but
+/// its request. Entries are keyed by the
+/// [stable hash](billiard_core::geometry::table_spec::TableSpec::canonical_hash)
+/// of the table in [canonical form](billiard_core::geometry::canonical)
+/// and the JSON of the other parameters with every default filled in, so
+/// the same request spelled differently (key order, whitespace, omitted
+/// defaults, arc angles a turn apart) shares an entry. Each entry keeps
+/// the canonical table it answers, and a hit is only counted when that
+/// matches too: a client able to construct hash collisions could otherwise
+/// plant responses for other clients' requests. A capacity of 0 disables
+/// the cache.
+pub struct TrajectoryCache {
+    inner: Mutex<CacheInner>,
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Identity of a request in the cache.
+#[derive(Clone, Debug)]
+pub struct CacheKey {
+    /// Hash of the canonical table, and the JSON of the rest of the
+    /// request.
+    lookup: (u64, String),
+    /// JSON of the canonical table.
+    table: String,
+}
+
+/// Entries in a doubly linked list from least to most recently used,
+/// threaded through `slots` by index so that finding, touching and
+/// evicting an entry are all O(1).
+struct CacheInner {
+    /// Slot of each entry.
+    index: HashMap<(u64, String), usize>,
+    slots: Vec<Slot>,
+    /// Least recently used slot.
+    oldest: Option<usize>,
+    /// Most recently used slot.
+    newest: Option<usize>,
+}
+
+struct Slot {
+    lookup: (u64, String),
+    table: String,
+    response: Arc<SimulateResponse>,
+    /// The next older and next newer slots.
+    older: Option<usize>,
+    newer: Option<usize>,
+}
+
+impl TrajectoryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(CacheInner {
+                index: HashMap::new(),
+                slots: Vec::new(),
+                oldest: None,
+                newest: None,
+            }),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// The cache key of `req`.
+    pub fn key(req: &SimulateRequest) -> CacheKey {
+        let mut parameters = serde_json::to_value(req).expect("requests serialize to JSON");
+        if let Some(fields) = parameters.as_object_mut() {
+            fields.remove("table");
+        }
+        // Obstacles keep their order: the initial state may name one.
+        let table = req.table.canonical_json(ObstacleOrder::Keep);
+        CacheKey {
+            lookup: (fnv1a(table.as_bytes()), parameters.to_string()),
+            table,
+        }
+    }
+
+    /// The response stored for the request of `key`, counting a hit or a
+    /// miss.
+    pub fn get(&self, key: &CacheKey) -> Option<Arc<SimulateResponse>> {
+        let mut inner = self.inner.lock().expect("cache lock poisoned");
+        let Some(slot) = inner
+            .index
+            .get(&key.lookup)
+            .copied()
+            .filter(|&slot| inner.slots[slot].table == key.table)
+        else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        inner.touch(slot);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(inner.slots[slot].response.clone())
+    }
+
+    /// Store `response` under `key`, evicting the least recently used
+    /// entry if the cache is full. An entry for another table with the
+    /// same hash and parameters is replaced.
+    pub fn insert(&self, key: CacheKey, response: Arc<SimulateResponse>) {
+        if !self.enabled() || response.collisions.len() > MAX_CACHED_COLLISIONS {
+            return;
+        }
+        let mut inner = self.inner.lock().expect("cache lock poisoned");
+        let CacheKey { lookup, table } = key;
+        if let Some(&slot) = inner.index.get(&lookup) {
+            inner.slots[slot].table = table;
+            inner.slots[slot].response = response;
+            inner.touch(slot);
+            return;
+        }
+        let slot = if inner.slots.len() < self.capacity {
+            inner.slots.push(Slot {
+                lookup: lookup.clone(),
+                table,
+                response,
+                older: None,
+                newer: None,
+            });
+            inner.slots.len() - 1
+        } else {
+            // Full: the least recently used slot takes the new entry.
+            let slot = inner.oldest.expect("a full cache has entries");
+            inner.unlink(slot);
+            let evicted = std::mem::replace(&mut inner.slots[slot].lookup, lookup.clone());
+            inner.index.remove(&evicted);
+            inner.slots[slot].table = table;
+            inner.slots[slot].response = response;
+            slot
+        };
+        inner.index.insert(lookup, slot);
+        inner.push_newest(slot);
+    }
+
+    pub fn stats(&self) -> CacheStatsDto {
+        let entries = self.inner.lock().expect("cache lock poisoned").index.len();
+        CacheStatsDto {
+            capacity: self.capacity,
+            entries,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl CacheInner {
+    /// Mark `slot` as the most recently used.
+    fn touch(&mut self, slot: usize) {
+        if self.newest != Some(slot) {
+            self.unlink(slot);
+            self.push_newest(slot);
+        }
+    }
+
+    /// Take `slot` out of the list.
+    fn unlink(&mut self, slot: usize) {
+        let Slot { older, newer, .. } = self.slots[slot];
+        match older {
+            Some(older) => self.slots[older].newer = newer,
+            None => self.oldest = newer,
+        }
+        match newer {
+            Some(newer) => self.slots[newer].older = older,
+            None => self.newest = older,
+        }
+    }
+
+    /// Put `slot`, which is not in the list, at its most recent end.
+    fn push_newest(&mut self, slot: usize) {
+        self.slots[slot].older = self.newest;
+        self.slots[slot].newer = None;
+        match self.newest {
+            Some(newest) => self.slots[newest].newer = Some(slot),
+            None => self.oldest = Some(slot),
+        }
+        self.newest = Some(slot);
+    }
+}
+
+/// Whether the client asked for a fresh result with `Cache-Control:
+/// no-cache` or `no-store`.
+pub fn bypasses_cache(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|directive| {
+            let directive = directive.trim();
+            directive.eq_ignore_ascii_case("no-cache") || directive.eq_ignore_ascii_case("no-store")
+        })
+}
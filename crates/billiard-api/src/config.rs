@@ -0,0 +1,110 @@
+use std::str::FromStr;
+
+/// Server configuration, read from environment variables at startup.
+///
+/// Every setting has a default, so the server runs without any variables set.
+#[derive(Clone, Debug)]
+pub struct ApiConfig {
+    /// Maximum number of jobs kept in the job store (`BOUNCERS_MAX_JOBS`).
+    pub max_jobs: usize,
+
+    /// Maximum number of jobs simulated at once (`BOUNCERS_JOB_CONCURRENCY`).
+    pub job_concurrency: usize,
+
+    /// Maximum number of CPU-bound computations, of any endpoint, run at
+    /// once (`BOUNCERS_COMPUTE_THREADS`); the rest wait their turn. Defaults
+    /// to the number of CPUs.
+    pub compute_threads: usize,
+
+    /// Origins allowed to make cross-origin requests (`BOUNCERS_CORS_ORIGINS`,
+    /// comma-separated). A single `*` allows any origin; empty disables CORS.
+    pub cors_origins: Vec<String>,
+
+    /// Requests allowed per client IP per minute (`BOUNCERS_RATE_LIMIT`);
+    /// 0 disables rate limiting.
+    pub rate_limit_per_minute: u32,
+
+    /// Number of POST /simulate responses kept for repeated requests
+    /// (`BOUNCERS_CACHE_ENTRIES`); 0 disables the cache.
+    pub cache_entries: usize,
+
+    /// Largest accepted `max_steps` (`BOUNCERS_MAX_STEPS`).
+    pub max_steps: usize,
+
+    /// Largest accepted total segment count of a table (`BOUNCERS_MAX_SEGMENTS`).
+    pub max_segments: usize,
+
+    /// Largest accepted request body, in bytes (`BOUNCERS_MAX_BODY_BYTES`).
+    pub max_body_bytes: usize,
+
+    /// Smallest response body, in bytes, that gets compressed
+    /// (`BOUNCERS_COMPRESSION_MIN_BYTES`).
+    pub compression_min_bytes: u16,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            max_jobs: 256,
+            job_concurrency: 4,
+            compute_threads: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            cors_origins: Vec::new(),
+            rate_limit_per_minute: 120,
+            cache_entries: 64,
+            max_steps: 1_000_000,
+            max_segments: 10_000,
+            max_body_bytes: 2 * 1024 * 1024,
+            compression_min_bytes: 1024,
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Build the configuration from environment variables, falling back to
+    /// the defaults for anything unset.
+    pub fn from_env() -> Result<Self, String> {
+        let defaults = Self::default();
+        let compute_threads = env_or("BOUNCERS_COMPUTE_THREADS", defaults.compute_threads)?;
+        if compute_threads == 0 {
+            return Err("BOUNCERS_COMPUTE_THREADS must be at least 1".to_string());
+        }
+        Ok(Self {
+            max_jobs: env_or("BOUNCERS_MAX_JOBS", defaults.max_jobs)?,
+            job_concurrency: env_or("BOUNCERS_JOB_CONCURRENCY", defaults.job_concurrency)?,
+            compute_threads,
+            cors_origins: env_list("BOUNCERS_CORS_ORIGINS").unwrap_or(defaults.cors_origins),
+            rate_limit_per_minute: env_or("BOUNCERS_RATE_LIMIT", defaults.rate_limit_per_minute)?,
+            cache_entries: env_or("BOUNCERS_CACHE_ENTRIES", defaults.cache_entries)?,
+            max_steps: env_or("BOUNCERS_MAX_STEPS", defaults.max_steps)?,
+            max_segments: env_or("BOUNCERS_MAX_SEGMENTS", defaults.max_segments)?,
+            max_body_bytes: env_or("BOUNCERS_MAX_BODY_BYTES", defaults.max_body_bytes)?,
+            compression_min_bytes: env_or(
+                "BOUNCERS_COMPRESSION_MIN_BYTES",
+                defaults.compression_min_bytes,
+            )?,
+        })
+    }
+}
+
+/// Parse environment variable `name`, or return `default` if it is unset.
+fn env_or<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| format!("invalid value for {}: {:?}", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Parse a comma-separated environment variable, or `None` if it is unset.
+fn env_list(name: &str) -> Option<Vec<String>> {
+    let value = std::env::var(name).ok()?;
+    Some(
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect(),
+    )
+}
@@ -3,15 +3,25 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use billiard_core::dynamics::config::ConfigError;
+use billiard_core::dynamics::ensemble::EnsembleError;
+use billiard_core::dynamics::simulation::DynamicsError;
+use billiard_core::geometry::table_spec::TableGeometryError;
 use serde::Serialize;
 use thiserror::Error;
 use tracing::error;
+use utoipa::ToSchema;
 
 /// Unified error type for the HTTP API.
 ///
 /// Variants correspond broadly to HTTP status families:
 /// - BadRequest      → 4xx (client input error)
+/// - NotFound        → 404 (unknown resource, e.g. job id)
+/// - LimitExceeded   → 422 (request exceeds configured size limits)
+/// - InvalidTable    → 422 (degenerate or unclosed table geometry)
 /// - SimulationFailed → 422 (unprocessible input / domain failure)
+/// - TooManyRequests → 429 (client exceeded the rate limit)
+/// - Unavailable     → 503 (server at capacity)
 /// - Internal        → 500 (unexpected server-side issue)
 #[derive(Debug, Error)]
 pub enum ApiError {
@@ -19,34 +29,123 @@ pub enum ApiError {
     #[error("bad request: {0}")]
     BadRequest(String),
 
+    /// The requested resource does not exist.
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// The request is well-formed but exceeds a configured size limit
+    /// (e.g., `max_steps` or table segment count).
+    #[error("limit exceeded: {0}")]
+    LimitExceeded(String),
+
+    /// The table cannot be built; the error is included in the response
+    /// body as `details`.
+    #[error("invalid table: {0}")]
+    InvalidTable(TableGeometryError),
+
+    /// The client has sent too many requests in the current window.
+    #[error("too many requests")]
+    TooManyRequests,
+
+    /// The server is temporarily at capacity (e.g., the job queue is full).
+    #[error("service unavailable: {0}")]
+    Unavailable(String),
+
     /// The request was syntactically valid but the simulation could not be run
-    /// (e.g., degenerate geometry or other domain-level failure).
-    #[allow(dead_code)]
+    /// (e.g., a degenerate state or other domain-level failure).
     #[error("simulation failed: {0}")]
     SimulationFailed(String),
 
     /// Catch-all for unexpected internal server errors.
-    #[allow(dead_code)]
     #[error("internal server error")]
     Internal(String),
 }
 
+impl From<ConfigError> for ApiError {
+    /// Names the request field that set the rejected value.
+    fn from(e: ConfigError) -> Self {
+        let message = match e {
+            ConfigError::InvalidEpsilon(_) => "epsilon must be positive and finite",
+            ConfigError::InvalidRestitution(_) => "restitution must be in (0, 1]",
+            ConfigError::InvalidGravity(_) => "flight_model.g must be finite",
+            ConfigError::EmptyEscapeBox => "escape_box min must be strictly less than max",
+            ConfigError::NoHistogramBins => "histogram_bins must be greater than 0",
+            ConfigError::InvalidMaxTime(_) => "max_time must be positive and finite",
+            ConfigError::InvalidInvariantTolerance(_) => {
+                "invariants tolerances must be non-negative"
+            }
+            ConfigError::InvalidGalleryAngle(_) => {
+                "whispering_gallery.max_angle must be in (0, π/2)"
+            }
+            ConfigError::TooFewGalleryBounces(_) => {
+                "whispering_gallery.min_bounces must be at least 2"
+            }
+            e => return ApiError::BadRequest(e.to_string()),
+        };
+        ApiError::BadRequest(message.to_string())
+    }
+}
+
+impl From<DynamicsError> for ApiError {
+    fn from(e: DynamicsError) -> Self {
+        match e {
+            // A client that went away gets no response, so whoever sees
+            // this is being turned away by a shutdown.
+            DynamicsError::Cancelled => {
+                ApiError::Unavailable("the server is shutting down".to_string())
+            }
+            e => ApiError::SimulationFailed(e.to_string()),
+        }
+    }
+}
+
+impl From<EnsembleError> for ApiError {
+    fn from(e: EnsembleError) -> Self {
+        ApiError::SimulationFailed(e.to_string())
+    }
+}
+
 /// Convenience alias for handler results.
 pub type ApiResult<T> = Result<T, ApiError>;
 
 /// JSON shape for error responses.
-#[derive(Debug, Serialize)]
-struct ErrorBody {
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
     /// Short machine-readable error code.
     error: &'static str,
     /// Human-readable explanatory message.
     message: String,
+    /// Structured description of the problem, for errors that have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    details: Option<serde_json::Value>,
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let details = match &self {
+            ApiError::InvalidTable(e) => serde_json::to_value(e).ok(),
+            _ => None,
+        };
         let (status, error_code, message) = match self {
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "bad_request", msg),
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg),
+            ApiError::LimitExceeded(msg) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "limit_exceeded", msg)
+            }
+            ApiError::InvalidTable(e) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "invalid_table",
+                e.to_string(),
+            ),
+            ApiError::TooManyRequests => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "too_many_requests",
+                "rate limit exceeded; slow down".to_string(),
+            ),
+            ApiError::Unavailable(msg) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "service_unavailable", msg)
+            }
             ApiError::SimulationFailed(msg) => {
                 (StatusCode::UNPROCESSABLE_ENTITY, "simulation_failed", msg)
             }
@@ -64,6 +163,7 @@ impl IntoResponse for ApiError {
         let body = ErrorBody {
             error: error_code,
             message,
+            details,
         };
 
         (status, Json(body)).into_response()
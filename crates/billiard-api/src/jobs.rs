@@ -0,0 +1,185 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use tokio::sync::Semaphore;
+use tracing::info;
+
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::routes::{checked_table, run_simulation, validate_request};
+use crate::state::AppState;
+use crate::types::{JobDto, JobStatus, SimulateRequest};
+
+/// In-memory store of simulation jobs.
+///
+/// The store is bounded: when full, the oldest finished job is evicted to
+/// make room. If every stored job is still queued or running, new jobs are
+/// rejected. A semaphore caps how many jobs simulate concurrently; they
+/// also share the compute threads with the other endpoints.
+pub struct JobStore {
+    inner: Mutex<JobStoreInner>,
+    capacity: usize,
+    permits: Arc<Semaphore>,
+}
+
+struct JobStoreInner {
+    next_id: u64,
+    jobs: HashMap<u64, JobStatus>,
+    /// Job ids in submission order, oldest first.
+    order: VecDeque<u64>,
+}
+
+impl JobStore {
+    pub fn new(capacity: usize, concurrency: usize) -> Self {
+        Self {
+            inner: Mutex::new(JobStoreInner {
+                next_id: 1,
+                jobs: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+            capacity,
+            permits: Arc::new(Semaphore::new(concurrency)),
+        }
+    }
+
+    /// Register a new queued job and return its id.
+    ///
+    /// Returns `None` if the store is full of unfinished jobs.
+    fn insert(&self) -> Option<u64> {
+        let mut inner = self.inner.lock().expect("job store lock poisoned");
+
+        if inner.jobs.len() >= self.capacity {
+            let evict = inner
+                .order
+                .iter()
+                .position(|id| inner.jobs[id].is_finished())?;
+            let id = inner.order.remove(evict).expect("index is in bounds");
+            inner.jobs.remove(&id);
+        }
+
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.jobs.insert(id, JobStatus::Queued);
+        inner.order.push_back(id);
+        Some(id)
+    }
+
+    fn set(&self, id: u64, status: JobStatus) {
+        let mut inner = self.inner.lock().expect("job store lock poisoned");
+        if let Some(entry) = inner.jobs.get_mut(&id) {
+            *entry = status;
+        }
+    }
+
+    /// Count `collisions` more done by the running job `id`.
+    fn advance(&self, id: u64, collisions: usize) {
+        let mut inner = self.inner.lock().expect("job store lock poisoned");
+        if let Some(JobStatus::Running {
+            collisions: done, ..
+        }) = inner.jobs.get_mut(&id)
+        {
+            *done += collisions;
+        }
+    }
+
+    fn get(&self, id: u64) -> Option<JobStatus> {
+        let inner = self.inner.lock().expect("job store lock poisoned");
+        inner.jobs.get(&id).cloned()
+    }
+}
+
+/// Job submission endpoint for POST /jobs.
+///
+/// Validates the request, enqueues it, and returns 202 with the job id.
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    request_body = SimulateRequest,
+    responses(
+        (status = 202, description = "Job accepted", body = JobDto),
+        (status = 400, description = "Invalid request parameters", body = ErrorBody),
+        (status = 422, description = "Request exceeds configured limits or the table is invalid", body = ErrorBody),
+        (status = 503, description = "Job queue is full", body = ErrorBody),
+    )
+)]
+pub async fn create_job(
+    State(state): State<AppState>,
+    Json(req): Json<SimulateRequest>,
+) -> ApiResult<impl IntoResponse> {
+    validate_request(&req, &state.config)?;
+    // Checked before the job is queued, so a bad table is still a 4xx.
+    let (req, table) = state
+        .compute(move || checked_table(&req).map(|table| (req, table)))
+        .await
+        .map_err(|e| ApiError::Internal(format!("table check failed: {}", e)))??;
+
+    let jobs = state.jobs.clone();
+    let id = jobs.insert().ok_or_else(|| {
+        ApiError::Unavailable("job queue is full; retry once running jobs finish".to_string())
+    })?;
+
+    info!(job_id = id, max_steps = req.max_steps, "Job enqueued");
+
+    // Jobs outlive their request; only a shutdown stops them.
+    let shutdown = state.shutdown.clone();
+    tokio::spawn(async move {
+        let _permit = jobs
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("job semaphore is never closed");
+        let max_steps = req.max_steps;
+        jobs.set(
+            id,
+            JobStatus::Running {
+                collisions: 0,
+                max_steps,
+            },
+        );
+
+        let reporter = jobs.clone();
+        let simulate = move || {
+            let progress = |collisions| reporter.advance(id, collisions);
+            run_simulation(req, &table, &shutdown, Some(&progress))
+        };
+        let status = match state.compute(simulate).await {
+            Ok(Ok(response)) => JobStatus::Completed(Box::new(response)),
+            Ok(Err(e)) => JobStatus::Failed(e.to_string()),
+            Err(e) => JobStatus::Failed(format!("simulation task failed: {}", e)),
+        };
+        info!(job_id = id, "Job finished");
+        jobs.set(id, status);
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(JobDto::new(id, JobStatus::Queued)),
+    ))
+}
+
+/// Job polling endpoint for GET /jobs/{id}.
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    params(("id" = u64, Path, description = "Job id returned by POST /jobs")),
+    responses(
+        (status = 200, description = "Job status, with the result once completed", body = JobDto),
+        (status = 404, description = "Unknown job id", body = ErrorBody),
+    )
+)]
+pub async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> ApiResult<impl IntoResponse> {
+    let status = state
+        .jobs
+        .get(id)
+        .ok_or_else(|| ApiError::NotFound(format!("job {} not found", id)))?;
+    Ok(Json(JobDto::new(id, status)))
+}
@@ -1,12 +1,26 @@
+mod analysis;
+mod cache;
+mod config;
 mod error;
+mod jobs;
+mod middleware;
+mod openapi;
 mod routes;
+mod sse;
+mod state;
+mod stream;
+mod sweep;
+mod tables;
 mod types;
 
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
     routing::{get, post},
 };
 use std::net::SocketAddr;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
 use tracing_subscriber::{EnvFilter, fmt};
 
 #[tokio::main]
@@ -20,17 +34,83 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_env_filter(EnvFilter::from_default_env().add_directive("info".parse()?))
         .init();
 
+    let config = config::ApiConfig::from_env()?;
+    let state = state::AppState::new(&config);
+    let shutdown = state.shutdown.clone();
+
     // Build our application with routes
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/health", get(routes::health))
-        .route("/simulate", post(routes::simulate));
+        .route("/info", get(routes::info))
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/docs", get(openapi::docs))
+        .route("/simulate", post(routes::simulate))
+        .route("/simulate/sse", post(sse::simulate_sse))
+        .route("/simulate/stream", get(stream::simulate_stream))
+        .route("/jobs", post(jobs::create_job))
+        .route("/jobs/{id}", get(jobs::get_job))
+        .route("/analysis/mean-free-path", post(analysis::mean_free_path))
+        .route("/sweep", post(sweep::parameter_sweep))
+        .route("/tables", get(tables::list_tables))
+        .route("/tables/validate", post(tables::validate))
+        .route("/tables/{name}", get(tables::get_table))
+        .route("/tables/{name}/polyline", get(tables::get_polyline))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::rate_limit,
+        ))
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+        .with_state(state);
+
+    // Allow cross-origin browser clients, if configured.
+    if let Some(cors) = middleware::cors_layer(&config.cors_origins)? {
+        app = app.layer(cors);
+    }
+
+    // Trajectory JSON compresses very well.
+    app = app.layer(middleware::compression_layer(config.compression_min_bytes));
 
     // Bind and serve
     let addr: SocketAddr = "127.0.0.1:3000".parse()?;
     let listener = tokio::net::TcpListener::bind(addr).await?;
     println!("Listening on http://{}", addr);
 
-    axum::serve(listener, app).await?;
+    // Connection info is needed for per-IP rate limiting.
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(shutdown))
+    .await?;
 
     Ok(())
 }
+
+/// Wait for SIGINT or SIGTERM, then cancel `shutdown`.
+///
+/// The server stops accepting connections and waits for open ones to
+/// finish; cancelling makes running simulations and jobs end early, so
+/// that wait is short.
+async fn shutdown_signal(shutdown: CancellationToken) {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for SIGINT");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+    info!("Shutting down; cancelling running simulations");
+    shutdown.cancel();
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderName, HeaderValue, Method, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{And, DefaultPredicate, Predicate, SizeAbove};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::cache::X_CACHE;
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// Number of tracked clients above which idle buckets are pruned.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Build the CORS layer for the configured origins.
+///
+/// Returns `None` when no origins are configured, in which case browsers
+/// only allow same-origin requests. A single `*` allows any origin.
+pub fn cors_layer(origins: &[String]) -> Result<Option<CorsLayer>, String> {
+    if origins.is_empty() {
+        return Ok(None);
+    }
+
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::from(Any)
+    } else {
+        let values = origins
+            .iter()
+            .map(|o| HeaderValue::from_str(o).map_err(|_| format!("invalid CORS origin: {:?}", o)))
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(values)
+    };
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([header::CONTENT_TYPE, header::ACCEPT, header::CACHE_CONTROL])
+            .expose_headers([HeaderName::from_static(X_CACHE)]),
+    ))
+}
+
+/// Build the response compression layer.
+///
+/// Bodies are gzip- or brotli-encoded according to `Accept-Encoding` once
+/// they exceed `min_bytes`. Event streams and images are left alone, as
+/// in tower-http's default predicate.
+pub fn compression_layer(min_bytes: u16) -> CompressionLayer<And<DefaultPredicate, SizeAbove>> {
+    CompressionLayer::new().compress_when(DefaultPredicate::new().and(SizeAbove::new(min_bytes)))
+}
+
+/// Per-client-IP token bucket rate limiter.
+///
+/// Each client may burst up to the full per-minute allowance, after which
+/// tokens refill continuously at the configured rate.
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    pub fn per_minute(requests: u32) -> Self {
+        let capacity = f64::from(requests);
+        Self {
+            capacity,
+            refill_per_sec: capacity / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take one token for `ip`, returning `false` if its bucket is empty.
+    fn try_acquire(&self, ip: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("rate limiter lock poisoned");
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            let (capacity, rate) = (self.capacity, self.refill_per_sec);
+            buckets.retain(|_, b| {
+                b.tokens + now.duration_since(b.last).as_secs_f64() * rate < capacity
+            });
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: self.capacity,
+            last: now,
+        });
+        let elapsed = now.duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Middleware rejecting requests from clients over their rate limit with 429.
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if let Some(limiter) = &state.rate_limiter
+        && !limiter.try_acquire(addr.ip())
+    {
+        return ApiError::TooManyRequests.into_response();
+    }
+    next.run(req).await
+}
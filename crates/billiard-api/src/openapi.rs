@@ -0,0 +1,136 @@
+use axum::{Json, response::Html};
+use utoipa::OpenApi;
+
+use crate::error::ErrorBody;
+use crate::types::{
+    BoundaryStateDto, CacheStatsDto, CollisionDto, ComponentPolylineDto, EscapeDto,
+    IncidenceHistogramDto, InfoResponse, JobDto, LimitsDto, MeanFreePathRequest, PolylineDto,
+    PresetDto, PresetParameterDto, ProgressDto, SegmentHitsDto, SimulateRequest, SimulateResponse,
+    StatisticsDto, StreamControl, StreamEvent, SweepFamily, SweepRequest, SweepResponse,
+    TerminationDto, ValidationDto, VersionsDto, WorldStateDto,
+};
+
+use billiard_core::dynamics::ensemble::{FreePathHistogram, FreePathStatistics};
+use billiard_core::dynamics::gallery::{GalleryAction, WhisperingGallery};
+use billiard_core::dynamics::invariants::{InvariantCheck, InvariantReport};
+use billiard_core::dynamics::santalo::MeanFreePathCheck;
+use billiard_core::dynamics::simulation::{CornerPolicy, FlightModel};
+use billiard_core::dynamics::sweep::{Observable, SweepGrid, SweepPoint};
+use billiard_core::geometry::boundary::{GeometryError, Hole};
+use billiard_core::geometry::primitives::{BoundingBox, Vec2};
+use billiard_core::geometry::segments::BoundaryCondition;
+use billiard_core::geometry::table_spec::{
+    BoundarySpec, SegmentSpec, TableGeometryError, TableSpec,
+};
+use billiard_core::geometry::table_template::TableTemplate;
+use billiard_core::geometry::validation::{IssueKind, SegmentLocation, Severity, ValidationIssue};
+
+/// OpenAPI description of the HTTP API.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "bouncers API", description = "Billiard simulation service"),
+    paths(
+        crate::routes::health,
+        crate::routes::info,
+        crate::routes::simulate,
+        crate::sse::simulate_sse,
+        crate::stream::simulate_stream,
+        crate::jobs::create_job,
+        crate::jobs::get_job,
+        crate::analysis::mean_free_path,
+        crate::sweep::parameter_sweep,
+        crate::tables::list_tables,
+        crate::tables::get_table,
+        crate::tables::get_polyline,
+        crate::tables::validate,
+    ),
+    components(schemas(
+        InfoResponse,
+        VersionsDto,
+        LimitsDto,
+        CacheStatsDto,
+        Vec2,
+        BoundingBox,
+        BoundaryCondition,
+        SegmentSpec,
+        Hole,
+        BoundarySpec,
+        TableSpec,
+        CornerPolicy,
+        FlightModel,
+        InvariantCheck,
+        WhisperingGallery,
+        GalleryAction,
+        SimulateRequest,
+        BoundaryStateDto,
+        WorldStateDto,
+        CollisionDto,
+        SimulateResponse,
+        TerminationDto,
+        EscapeDto,
+        StatisticsDto,
+        SegmentHitsDto,
+        IncidenceHistogramDto,
+        FreePathHistogram,
+        FreePathStatistics,
+        MeanFreePathCheck,
+        InvariantReport,
+        MeanFreePathRequest,
+        TableTemplate,
+        Observable,
+        SweepGrid,
+        SweepPoint,
+        SweepFamily,
+        SweepRequest,
+        SweepResponse,
+        ProgressDto,
+        StreamControl,
+        StreamEvent,
+        JobDto,
+        PresetDto,
+        PresetParameterDto,
+        PolylineDto,
+        ComponentPolylineDto,
+        Severity,
+        IssueKind,
+        SegmentLocation,
+        ValidationIssue,
+        ValidationDto,
+        GeometryError,
+        TableGeometryError,
+        ErrorBody,
+    ))
+)]
+pub struct ApiDoc;
+
+/// OpenAPI document for GET /openapi.json.
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI page for GET /docs, rendering /openapi.json.
+///
+/// The UI assets are loaded from a CDN so the server does not need to
+/// bundle them.
+pub async fn docs() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
+
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>bouncers API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
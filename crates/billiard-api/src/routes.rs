@@ -1,15 +1,46 @@
-use axum::{Json, response::IntoResponse};
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, HeaderValue, header},
+    response::{IntoResponse, Response},
+};
 use serde::Serialize;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, instrument};
 
-use crate::error::{ApiError, ApiResult};
-use crate::types::{CollisionDto, SimulateRequest, SimulateResponse};
+use crate::cache::{TrajectoryCache, X_CACHE, bypasses_cache};
+use crate::config::ApiConfig;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::state::AppState;
+use crate::types::{
+    InfoResponse, Launch, LimitsDto, SimulateRequest, SimulateResponse, VersionsDto,
+};
+
+use billiard_core::dynamics::simulation::{CornerPolicy, FlightModel, Progress};
+use billiard_core::dynamics::sweep::Observable;
+use billiard_core::geometry::boundary::BilliardTable;
+use billiard_core::geometry::offset::try_offset_for_ball;
+use billiard_core::geometry::presets::PRESETS;
+use billiard_core::geometry::primitives::Vec2;
+use billiard_core::geometry::segments::{BoundaryCondition, SegmentLabel};
+use billiard_core::geometry::table_spec::{SegmentSpec, TableSpec};
 
-use billiard_core::dynamics::simulation::run_trajectory;
+/// Largest free-path histogram a request may ask for.
+const MAX_HISTOGRAM_BINS: usize = 10_000;
+
+/// Media type clients put in `Accept` to get MessagePack instead of JSON.
+const MSGPACK_CONTENT_TYPE: &str = "application/x-msgpack";
 
 /// Health check endpoint for GET /health.
 ///
 /// Returns a small JSON object indicating that the service is up.
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses((status = 200, description = "Service is up"))
+)]
 pub async fn health() -> ApiResult<impl IntoResponse> {
     #[derive(Serialize)]
     struct HealthBody {
@@ -20,61 +51,396 @@ pub async fn health() -> ApiResult<impl IntoResponse> {
     Ok(Json(body))
 }
 
-/// Simulation endpoint for POST /simulate.
+/// Server information endpoint for GET /info.
 ///
-/// Instrumented with tracing to log incoming parameters and timing.
-#[instrument(skip(req))]
-pub async fn simulate(Json(req): Json<SimulateRequest>) -> ApiResult<impl IntoResponse> {
-    info!(
-        max_steps = req.max_steps,
-        epsilon = req.epsilon,
-        "Received simulation request"
-    );
+/// Reports the build, the configured limits and the supported table and
+/// simulation options, so clients can adapt to the server they talk to.
+/// Like /health, it answers whenever the service is up.
+#[utoipa::path(
+    get,
+    path = "/info",
+    responses((status = 200, description = "Server build and capabilities", body = InfoResponse))
+)]
+pub async fn info(State(state): State<AppState>) -> ApiResult<impl IntoResponse> {
+    let config = &state.config;
+    let segment = SegmentSpec::Line {
+        start: Vec2::new(0.0, 0.0),
+        end: Vec2::new(1.0, 0.0),
+        condition: BoundaryCondition::Reflect,
+        label: SegmentLabel::default(),
+    };
+    let arc = SegmentSpec::CircularArc {
+        center: Vec2::new(0.0, 0.0),
+        radius: 1.0,
+        start_angle: 0.0,
+        end_angle: 1.0,
+        ccw: true,
+        condition: BoundaryCondition::Reflect,
+        label: SegmentLabel::default(),
+    };
+    let body = InfoResponse {
+        status: "ok",
+        versions: VersionsDto {
+            api: env!("CARGO_PKG_VERSION"),
+            core: billiard_core::VERSION,
+        },
+        features: billiard_core::features(),
+        limits: LimitsDto {
+            max_steps: config.max_steps,
+            max_segments: config.max_segments,
+            max_body_bytes: config.max_body_bytes,
+            max_histogram_bins: MAX_HISTOGRAM_BINS,
+            rate_limit_per_minute: config.rate_limit_per_minute,
+            max_jobs: config.max_jobs,
+            job_concurrency: config.job_concurrency,
+            compute_threads: config.compute_threads,
+        },
+        cache: state.cache.stats(),
+        segment_kinds: variant_names(&[segment, arc]),
+        boundary_conditions: variant_names(&[
+            BoundaryCondition::Reflect,
+            BoundaryCondition::Absorb,
+            BoundaryCondition::PeriodicPairedWith(0),
+            BoundaryCondition::NoSlipRotate,
+            BoundaryCondition::Thermal { temperature: 1.0 },
+            BoundaryCondition::Oscillating {
+                velocity: 0.0,
+                frequency: 0.0,
+                phase: 0.0,
+            },
+        ]),
+        flight_models: variant_names(&[FlightModel::Straight, FlightModel::Gravity { g: 1.0 }]),
+        corner_policies: variant_names(&[
+            CornerPolicy::Terminate,
+            CornerPolicy::ReflectBisector,
+            CornerPolicy::Report,
+        ]),
+        observables: variant_names(&[
+            Observable::LyapunovExponent,
+            Observable::EscapeRate,
+            Observable::MeanFreePath,
+        ]),
+        presets: PRESETS.iter().map(|preset| preset.name).collect(),
+    };
+    Ok(Json(body))
+}
+
+/// The names `values` go by in requests, as serde writes them: the string
+/// of a unit variant, the key of an externally tagged one, or the `kind`
+/// of an internally tagged one.
+fn variant_names<T: Serialize>(values: &[T]) -> Vec<String> {
+    values
+        .iter()
+        .filter_map(|value| match serde_json::to_value(value).ok()? {
+            serde_json::Value::String(name) => Some(name),
+            serde_json::Value::Object(mut fields) => match fields.remove("kind") {
+                Some(serde_json::Value::String(kind)) => Some(kind),
+                _ => fields.into_iter().next().map(|(name, _)| name),
+            },
+            _ => None,
+        })
+        .collect()
+}
 
-    // Basic validation
+/// Basic validation shared by every endpoint that accepts a `SimulateRequest`.
+///
+/// Besides checking parameter sanity, this enforces the configured size
+/// limits so a single request cannot monopolize the CPU. It is cheap
+/// enough for the async workers; the table and the starting point are
+/// checked by [`checked_table`], on the compute pool.
+pub fn validate_request(req: &SimulateRequest, config: &ApiConfig) -> ApiResult<()> {
     if req.max_steps == 0 {
         return Err(ApiError::BadRequest(
             "max_steps must be greater than 0".to_string(),
         ));
     }
 
-    if !req.epsilon.is_finite() || req.epsilon <= 0.0 {
+    req.config()?;
+
+    if !req.ball_radius.is_finite() || req.ball_radius < 0.0 {
+        return Err(ApiError::BadRequest(
+            "ball_radius must be non-negative and finite".to_string(),
+        ));
+    }
+
+    match (&req.initial_state, &req.initial_world) {
+        (Some(state), None) => {
+            if !state.speed.is_finite() || state.speed <= 0.0 {
+                return Err(ApiError::BadRequest(
+                    "initial_state.speed must be positive and finite".to_string(),
+                ));
+            }
+        }
+        (None, Some(world)) => {
+            if ![world.x, world.y, world.dx, world.dy]
+                .iter()
+                .all(|v| v.is_finite())
+            {
+                return Err(ApiError::BadRequest(
+                    "initial_world coordinates must be finite".to_string(),
+                ));
+            }
+            if world.dx == 0.0 && world.dy == 0.0 {
+                return Err(ApiError::BadRequest(
+                    "initial_world direction must be nonzero".to_string(),
+                ));
+            }
+            if !world.speed.is_finite() || world.speed <= 0.0 {
+                return Err(ApiError::BadRequest(
+                    "initial_world.speed must be positive and finite".to_string(),
+                ));
+            }
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "exactly one of initial_state and initial_world must be given".to_string(),
+            ));
+        }
+    }
+
+    if req.stride == 0 {
         return Err(ApiError::BadRequest(
-            "epsilon must be positive and finite".to_string(),
+            "stride must be greater than 0".to_string(),
         ));
     }
 
-    // Build internal table representation
-    let table = req.table.to_billiard_table();
+    if req.max_steps > config.max_steps {
+        return Err(ApiError::LimitExceeded(format!(
+            "max_steps {} exceeds the limit of {}",
+            req.max_steps, config.max_steps
+        )));
+    }
+
+    if req.histogram_bins > MAX_HISTOGRAM_BINS {
+        return Err(ApiError::LimitExceeded(format!(
+            "histogram_bins {} exceeds the limit of {}",
+            req.histogram_bins, MAX_HISTOGRAM_BINS
+        )));
+    }
+
+    check_table_size(&req.table, config)
+}
 
-    // Convert initial state
-    let initial_state = req.initial_state.into_core();
+/// The core table of a request that passed [`validate_request`], as seen
+/// by the center of the ball, once it and the starting point on it are
+/// known to be valid.
+///
+/// This is CPU-bound, so it is run on the compute pool, where the table
+/// it builds is then simulated on.
+pub fn checked_table(req: &SimulateRequest) -> ApiResult<BilliardTable> {
+    // Also catches a ball too large for the table, which would otherwise
+    // panic when the table is offset. With an escape box the table may be
+    // open, so gaps are allowed.
+    let offset =
+        try_offset_for_ball(&req.table, req.ball_radius).map_err(ApiError::InvalidTable)?;
+    let table = if req.escape_box.is_some() {
+        offset.try_to_open_billiard_table()
+    } else {
+        offset.try_to_billiard_table()
+    }
+    .map_err(ApiError::InvalidTable)?;
 
+    match req.launch(&table) {
+        Launch::Boundary(bs) => {
+            bs.check(&table)
+                .map_err(|e| ApiError::BadRequest(format!("initial_state is invalid: {}", e)))?;
+            if req.initial_world.is_some() && !(bs.theta > 0.0 && bs.theta < std::f64::consts::PI) {
+                return Err(ApiError::BadRequest(
+                    "initial_world lies on the boundary but points out of the table".to_string(),
+                ));
+            }
+        }
+        Launch::Interior(world) => {
+            // An open table has no inside to speak of, only its obstacles.
+            let outside = if req.escape_box.is_some() {
+                table.obstacles.iter().any(|o| o.encloses(world.position))
+            } else {
+                !table.contains(world.position)
+            };
+            if outside {
+                return Err(ApiError::BadRequest(
+                    "initial_world lies outside the table".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+/// Reject tables with more segments in total than the configured limit.
+pub fn check_table_size(table: &TableSpec, config: &ApiConfig) -> ApiResult<()> {
+    let segment_count: usize = std::iter::once(&table.outer)
+        .chain(&table.obstacles)
+        .map(|b| b.segments.len())
+        .sum();
+    if segment_count > config.max_segments {
+        return Err(ApiError::LimitExceeded(format!(
+            "table has {} segments, exceeding the limit of {}",
+            segment_count, config.max_segments
+        )));
+    }
+    Ok(())
+}
+
+/// Simulation endpoint for POST /simulate.
+///
+/// Responds in MessagePack (same field names as the JSON) when the
+/// `Accept` header lists `application/x-msgpack`, and in JSON otherwise.
+/// The simulation stops early if the client disconnects or the server
+/// shuts down. Instrumented with tracing to log incoming parameters and
+/// timing.
+///
+/// Responses are cached, so a repeated request is answered at once; the
+/// `X-Cache` response header says whether it was a `hit` or a `miss`.
+/// Send `Cache-Control: no-cache` (or `no-store`) to have the trajectory
+/// simulated afresh, neither read from the cache nor stored in it (the
+/// header is then `bypass`, as it is when the cache is disabled).
+#[utoipa::path(
+    post,
+    path = "/simulate",
+    request_body = SimulateRequest,
+    responses(
+        (status = 200, description = "Simulated trajectory", content(
+            (SimulateResponse = "application/json"),
+            (SimulateResponse = "application/x-msgpack"),
+        )),
+        (status = 400, description = "Invalid request parameters", body = ErrorBody),
+        (status = 422, description = "Request exceeds configured limits or the table is invalid", body = ErrorBody),
+        (status = 429, description = "Rate limit exceeded", body = ErrorBody),
+        (status = 503, description = "The server shut down during the simulation", body = ErrorBody),
+    )
+)]
+#[instrument(skip(state, headers, req))]
+pub async fn simulate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<SimulateRequest>,
+) -> ApiResult<Response> {
     info!(
-        component_index = initial_state.component_index,
-        s = initial_state.s,
-        theta = initial_state.theta,
-        "Starting trajectory"
+        max_steps = req.max_steps,
+        epsilon = req.epsilon,
+        "Received simulation request"
     );
 
-    // Run the trajectory using the core engine
-    let collisions_core = run_trajectory(&table, &initial_state, req.max_steps, req.epsilon);
+    validate_request(&req, &state.config)?;
 
-    let collision_count = collisions_core.len();
+    let cache = Arc::clone(&state.cache);
+    let cached = cache.enabled() && !bypasses_cache(&headers);
+    // Axum drops this future when the client disconnects, and the guard
+    // with it.
+    let cancel = state.shutdown.child_token();
+    let _guard = cancel.clone().drop_guard();
+    // Keying the request canonicalizes its table, so the cache is
+    // consulted on the compute pool too.
+    let (response, cache_status) = state
+        .compute(move || -> ApiResult<_> {
+            let key = cached.then(|| TrajectoryCache::key(&req));
+            if let Some(response) = key.as_ref().and_then(|key| cache.get(key)) {
+                return Ok((response, "hit"));
+            }
+            let table = checked_table(&req)?;
+            let response = Arc::new(run_simulation(req, &table, &cancel, None)?);
+            Ok(match key {
+                Some(key) => {
+                    cache.insert(key, response.clone());
+                    (response, "miss")
+                }
+                None => (response, "bypass"),
+            })
+        })
+        .await
+        .map_err(|e| ApiError::Internal(format!("simulation task failed: {}", e)))??;
 
-    // Map to DTOs
-    let collisions_dto: Vec<CollisionDto> = collisions_core
+    let mut response = if accepts_msgpack(&headers) {
+        let body = rmp_serde::to_vec_named(&*response)
+            .map_err(|e| ApiError::Internal(format!("MessagePack encoding failed: {}", e)))?;
+        (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(MSGPACK_CONTENT_TYPE),
+            )],
+            body,
+        )
+            .into_response()
+    } else {
+        Json(&*response).into_response()
+    };
+    // Caches must not hand a JSON body to a MessagePack client or vice versa.
+    let headers = response.headers_mut();
+    headers.insert(header::VARY, HeaderValue::from_static("accept"));
+    headers.insert(X_CACHE, HeaderValue::from_static(cache_status));
+
+    Ok(response)
+}
+
+/// Whether any media range in the `Accept` header is MessagePack.
+fn accepts_msgpack(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
         .iter()
-        .enumerate()
-        .map(|(step, c)| CollisionDto::from_core(step, c))
-        .collect();
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|range| range.split(';').next())
+        .any(|media| media.trim().eq_ignore_ascii_case(MSGPACK_CONTENT_TYPE))
+}
 
-    info!(collisions = collision_count, "Simulation completed");
+/// Run a validated simulation request to completion on `table`, from
+/// [`checked_table`].
+///
+/// This is synchronous and CPU-bound; it is shared by every endpoint that
+/// produces a full `SimulateResponse`. It fails soon after `cancel` is
+/// cancelled, and tells `progress`, if given, how far it has got.
+pub fn run_simulation(
+    req: SimulateRequest,
+    table: &BilliardTable,
+    cancel: &CancellationToken,
+    progress: Option<&Progress>,
+) -> ApiResult<SimulateResponse> {
+    let config = req.config()?;
 
-    // Wrap in response type
-    let response = SimulateResponse {
-        collisions: collisions_dto,
-    };
+    // Resolve the starting point against the table
+    let launch = req.launch(table);
+
+    match &launch {
+        Launch::Boundary(bs) => info!(
+            component_index = bs.component_index,
+            s = bs.s,
+            theta = bs.theta,
+            "Starting trajectory"
+        ),
+        Launch::Interior(ws) => info!(
+            x = ws.position.x,
+            y = ws.position.y,
+            "Starting trajectory from the interior"
+        ),
+    }
 
-    Ok(Json(response))
+    // Run the trajectory using the core engine
+    let mut law = req.thermal_law(table);
+    let trajectory = launch.simulate(
+        table,
+        &config,
+        &|| cancel.is_cancelled(),
+        progress,
+        law.as_mut(),
+    )?;
+
+    let collision_count = trajectory.collisions.len();
+
+    info!(
+        collisions = collision_count,
+        termination = ?trajectory.termination,
+        "Simulation completed"
+    );
+
+    // Map to DTOs
+    Ok(SimulateResponse::new(
+        &req,
+        &config,
+        table,
+        &trajectory.collisions,
+        &trajectory.termination,
+        trajectory.invariants,
+    ))
 }
@@ -0,0 +1,158 @@
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, wrappers::ReceiverStream};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::routes::{checked_table, validate_request};
+use crate::state::AppState;
+use crate::types::{ProgressDto, SimulateRequest, SimulateResponse, SseParams};
+
+use billiard_core::geometry::boundary::BilliardTable;
+
+/// Default number of collisions computed between progress events.
+const DEFAULT_CHUNK_SIZE: usize = 1000;
+
+/// Server-sent events variant of POST /simulate.
+///
+/// Emits a `progress` event after about every `chunk_size` collisions (the
+/// run reports its progress every few hundred), one more once it ends,
+/// and a final `result` event whose data is the usual `SimulateResponse`.
+/// If the simulation fails part-way, or the server shuts down, the stream
+/// ends with an `error` event whose data has a `message` instead.
+#[utoipa::path(
+    post,
+    path = "/simulate/sse",
+    params(SseParams),
+    request_body = SimulateRequest,
+    responses(
+        (
+            status = 200,
+            description = "Stream of `progress` events (ProgressDto) followed by one `result` event (SimulateResponse)",
+            content_type = "text/event-stream",
+            body = ProgressDto
+        ),
+        (status = 400, description = "Invalid request parameters", body = ErrorBody),
+        (status = 422, description = "Request exceeds configured limits or the table is invalid", body = ErrorBody),
+    )
+)]
+pub async fn simulate_sse(
+    State(state): State<AppState>,
+    Query(params): Query<SseParams>,
+    Json(req): Json<SimulateRequest>,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    validate_request(&req, &state.config)?;
+
+    let chunk_size = params.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    if chunk_size == 0 {
+        return Err(ApiError::BadRequest(
+            "chunk_size must be greater than 0".to_string(),
+        ));
+    }
+
+    // Checked before the stream starts, so a bad table is still a 4xx.
+    let (req, table) = state
+        .compute(move || checked_table(&req).map(|table| (req, table)))
+        .await
+        .map_err(|e| ApiError::Internal(format!("table check failed: {}", e)))??;
+
+    info!(
+        max_steps = req.max_steps,
+        chunk_size, "Starting SSE simulation"
+    );
+
+    let (tx, rx) = mpsc::channel(16);
+    let shutdown = state.shutdown.clone();
+    tokio::spawn(async move {
+        // A panic drops `tx`, which ends the stream.
+        let _ = state
+            .compute(move || run_reporting(req, &table, chunk_size, tx, &shutdown))
+            .await;
+    });
+
+    Ok(Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default()))
+}
+
+/// Run the simulation on `table`, sending a progress event once every
+/// `chunk_size` collisions as the run reports them, until done or the
+/// client leaves.
+fn run_reporting(
+    req: SimulateRequest,
+    table: &BilliardTable,
+    chunk_size: usize,
+    tx: mpsc::Sender<Result<Event, Infallible>>,
+    shutdown: &CancellationToken,
+) {
+    // A closed channel means the client left.
+    let cancelled = || tx.is_closed() || shutdown.is_cancelled();
+    let config = match req.config() {
+        Ok(config) => config,
+        Err(e) => {
+            let message = ApiError::from(e).to_string();
+            let _ = send(&tx, "error", &serde_json::json!({ "message": message }));
+            return;
+        }
+    };
+    let max_steps = config.max_steps();
+
+    // Steps reported so far, and the count at which the next event is due.
+    let (done, due) = (AtomicUsize::new(0), AtomicUsize::new(chunk_size));
+    let progress = |steps: usize| {
+        let done = done.fetch_add(steps, Ordering::Relaxed) + steps;
+        if done >= due.load(Ordering::Relaxed) {
+            due.store((done / chunk_size + 1) * chunk_size, Ordering::Relaxed);
+            // A failed send closes the channel, which cancels the run.
+            let _ = send(&tx, "progress", &ProgressDto::new(done, max_steps));
+        }
+    };
+    let mut law = req.thermal_law(table);
+    let launch = req.launch(table);
+    let trajectory =
+        match launch.simulate(table, &config, &cancelled, Some(&progress), law.as_mut()) {
+            Ok(trajectory) => trajectory,
+            Err(e) => {
+                warn!(error = %e, "SSE simulation failed");
+                let _ = send(
+                    &tx,
+                    "error",
+                    &serde_json::json!({ "message": e.to_string() }),
+                );
+                return;
+            }
+        };
+
+    let collisions = trajectory.collisions.len();
+    if send(&tx, "progress", &ProgressDto::new(collisions, max_steps)).is_err() {
+        // Client disconnected.
+        return;
+    }
+    let response = SimulateResponse::new(
+        &req,
+        &config,
+        table,
+        &trajectory.collisions,
+        &trajectory.termination,
+        trajectory.invariants,
+    );
+    let _ = send(&tx, "result", &response);
+}
+
+fn send<T: serde::Serialize>(
+    tx: &mpsc::Sender<Result<Event, Infallible>>,
+    name: &str,
+    data: &T,
+) -> Result<(), mpsc::error::SendError<Result<Event, Infallible>>> {
+    let event = Event::default()
+        .event(name)
+        .json_data(data)
+        .expect("SSE payloads are always serializable");
+    tx.blocking_send(Ok(event))
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinError;
+use tokio_util::sync::CancellationToken;
+
+use crate::cache::TrajectoryCache;
+use crate::config::ApiConfig;
+use crate::jobs::JobStore;
+use crate::middleware::RateLimiter;
+
+/// Shared state available to every handler.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<ApiConfig>,
+    pub jobs: Arc<JobStore>,
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    pub cache: Arc<TrajectoryCache>,
+
+    /// Cancelled when the server starts shutting down; running simulations
+    /// watch it, or a child token of it, and stop early.
+    pub shutdown: CancellationToken,
+
+    /// One permit per compute thread; see [`AppState::compute`].
+    compute: Arc<Semaphore>,
+}
+
+impl AppState {
+    pub fn new(config: &ApiConfig) -> Self {
+        let rate_limiter = (config.rate_limit_per_minute > 0)
+            .then(|| Arc::new(RateLimiter::per_minute(config.rate_limit_per_minute)));
+        Self {
+            config: Arc::new(config.clone()),
+            jobs: Arc::new(JobStore::new(config.max_jobs, config.job_concurrency)),
+            rate_limiter,
+            cache: Arc::new(TrajectoryCache::new(config.cache_entries)),
+            shutdown: CancellationToken::new(),
+            compute: Arc::new(Semaphore::new(config.compute_threads)),
+        }
+    }
+
+    /// Run the CPU-bound `f` on tokio's blocking pool, keeping it off the
+    /// async workers, once fewer than `compute_threads` computations are
+    /// running. The permit goes with `f`, so a computation still counts
+    /// after its request has been dropped, until it notices the
+    /// cancellation.
+    ///
+    /// Fails if `f` panics.
+    pub async fn compute<T, F>(&self, f: F) -> Result<T, JoinError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = self
+            .compute
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("compute semaphore is never closed");
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            f()
+        })
+        .await
+    }
+}
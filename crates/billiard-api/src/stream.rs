@@ -0,0 +1,299 @@
+use axum::{
+    extract::{
+        State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use tracing::{info, warn};
+
+use crate::config::ApiConfig;
+use crate::error::{ApiError, ApiResult};
+use crate::routes::{checked_table, validate_request};
+use crate::state::AppState;
+use crate::types::{
+    CollisionDto, Launch, SimulateRequest, StreamControl, StreamEvent, TerminationDto,
+};
+
+use billiard_core::dynamics::config::SimulationConfig;
+use billiard_core::dynamics::lattice::Unfolder;
+use billiard_core::dynamics::reflection::Thermal;
+use billiard_core::dynamics::simulation::{StepOutcome, Termination};
+use billiard_core::geometry::boundary::BilliardTable;
+
+/// Collisions computed per dispatch to the compute pool while the stream
+/// runs freely.
+const STEPS_PER_BATCH: usize = 64;
+
+/// Streaming simulation endpoint for GET /simulate/stream.
+///
+/// Protocol:
+/// 1. The client sends a `SimulateRequest` as the first text message.
+/// 2. The server emits `{"type": "collision", ...}` events as they are computed.
+/// 3. The client may send `pause`, `resume`, or `step` control messages at any time.
+/// 4. The server sends `{"type": "done"}` when the trajectory ends and closes the socket.
+///
+/// If the server shuts down first, it sends an `error` event and closes the
+/// socket.
+#[utoipa::path(
+    get,
+    path = "/simulate/stream",
+    responses((
+        status = 101,
+        description = "WebSocket upgrade. Send a SimulateRequest, then StreamControl messages; receive StreamEvent messages."
+    ))
+)]
+pub async fn simulate_stream(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| handle_stream(socket, state))
+}
+
+async fn handle_stream(mut socket: WebSocket, state: AppState) {
+    let req = match receive_request(&mut socket, &state.config).await {
+        Some(Ok(req)) => req,
+        Some(Err(message)) => {
+            let _ = send_event(&mut socket, &StreamEvent::Error { message }).await;
+            return;
+        }
+        None => return,
+    };
+
+    info!(
+        max_steps = req.max_steps,
+        epsilon = req.epsilon,
+        "Starting streamed simulation"
+    );
+
+    let config = match req.config() {
+        Ok(config) => config,
+        Err(e) => {
+            let message = ApiError::from(e).to_string();
+            let _ = send_event(&mut socket, &StreamEvent::Error { message }).await;
+            return;
+        }
+    };
+    let walk = state
+        .compute(move || -> ApiResult<Walk> {
+            let table = checked_table(&req)?;
+            Ok(Walk {
+                current: req.launch(&table),
+                law: req.thermal_law(&table),
+                table,
+                config,
+                unfolder: Unfolder::default(),
+                step: 0,
+                path_length: 0.0,
+                time: 0.0,
+            })
+        })
+        .await;
+    let mut walk = match walk {
+        Ok(Ok(walk)) => walk,
+        Ok(Err(e)) => {
+            let message = e.to_string();
+            let _ = send_event(&mut socket, &StreamEvent::Error { message }).await;
+            return;
+        }
+        Err(e) => {
+            let message = format!("simulation task failed: {}", e);
+            let _ = send_event(&mut socket, &StreamEvent::Error { message }).await;
+            return;
+        }
+    };
+
+    let mut running = true;
+    let mut pending_steps = 0;
+    let mut termination = None;
+
+    while termination.is_none() {
+        let can_emit = running || pending_steps > 0;
+
+        tokio::select! {
+            biased;
+
+            _ = state.shutdown.cancelled() => {
+                let event = StreamEvent::Error {
+                    message: "the server is shutting down".to_string(),
+                };
+                let _ = send_event(&mut socket, &event).await;
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+
+            msg = socket.recv() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => return,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        warn!(error = %e, "WebSocket receive failed");
+                        return;
+                    }
+                };
+
+                match serde_json::from_str::<StreamControl>(&text) {
+                    Ok(StreamControl::Pause) => running = false,
+                    Ok(StreamControl::Resume) => running = true,
+                    Ok(StreamControl::Step { count }) => {
+                        running = false;
+                        pending_steps += count;
+                    }
+                    Err(e) => {
+                        let event = StreamEvent::Error {
+                            message: format!("invalid control message: {}", e),
+                        };
+                        if send_event(&mut socket, &event).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+
+            _ = std::future::ready(()), if can_emit => {
+                let count = if running {
+                    STEPS_PER_BATCH
+                } else {
+                    pending_steps.min(STEPS_PER_BATCH)
+                };
+                // Stepped on the compute pool, a batch at a time; the walk
+                // goes along and comes back with the collisions.
+                let stepped = state
+                    .compute(move || {
+                        let batch = walk.advance(count);
+                        (walk, batch)
+                    })
+                    .await;
+                let (events, ended) = match stepped {
+                    Ok((stepped, batch)) => {
+                        walk = stepped;
+                        batch
+                    }
+                    Err(e) => {
+                        let message = format!("simulation task failed: {}", e);
+                        warn!(error = %message, "Streamed simulation failed");
+                        let _ = send_event(&mut socket, &StreamEvent::Error { message }).await;
+                        let _ = socket.send(Message::Close(None)).await;
+                        return;
+                    }
+                };
+
+                pending_steps = pending_steps.saturating_sub(events.len());
+                for event in &events {
+                    if send_event(&mut socket, event).await.is_err() {
+                        return;
+                    }
+                }
+                match ended {
+                    Ok(ended) => termination = ended,
+                    Err(message) => {
+                        warn!(error = %message, "Streamed simulation failed");
+                        let event = StreamEvent::Error { message };
+                        let _ = send_event(&mut socket, &event).await;
+                        let _ = socket.send(Message::Close(None)).await;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    let termination = termination.expect("loop exits only once the trajectory has ended");
+    info!(collisions = walk.step, termination = ?termination, "Streamed simulation completed");
+    let event = StreamEvent::Done {
+        collisions: walk.step,
+        termination: TerminationDto::from_core(&termination),
+    };
+    let _ = send_event(&mut socket, &event).await;
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// A streamed trajectory as far as it has got, handed to the compute pool
+/// and back for each batch of steps.
+struct Walk {
+    table: BilliardTable,
+    config: SimulationConfig,
+    current: Launch,
+    law: Option<Thermal>,
+    unfolder: Unfolder,
+    step: usize,
+    path_length: f64,
+    time: f64,
+}
+
+impl Walk {
+    /// Take up to `count` steps, returning a collision event for each, and
+    /// why the trajectory ended if it did.
+    fn advance(&mut self, count: usize) -> (Vec<StreamEvent>, Result<Option<Termination>, String>) {
+        let mut events = Vec::new();
+        for _ in 0..count {
+            if self.step >= self.config.max_steps() {
+                return (events, Ok(Some(Termination::MaxSteps)));
+            }
+            let outcome =
+                self.current
+                    .step(&self.table, &self.config, self.time, self.law.as_mut());
+            let c = match outcome {
+                Ok(StepOutcome::Collision(c)) => c.after(self.path_length, self.time),
+                Ok(StepOutcome::Escaped(escape)) => {
+                    return (events, Ok(Some(Termination::Escaped(escape))));
+                }
+                Ok(StepOutcome::NoCollision) => {
+                    return (events, Ok(Some(Termination::NoCollision)));
+                }
+                Err(e) => return (events, Err(format!("simulation failed: {}", e))),
+            };
+
+            if self
+                .config
+                .max_time()
+                .is_some_and(|max_time| c.time > max_time)
+            {
+                return (events, Ok(Some(Termination::MaxTime)));
+            }
+
+            let unfolded = self.unfolder.unfold(&self.table, &c);
+            events.push(StreamEvent::Collision(CollisionDto::from_core(
+                self.step,
+                &unfolded,
+                &self.table,
+            )));
+
+            self.current = Launch::Boundary(c.outgoing_state());
+            (self.path_length, self.time) = (c.path_length, c.departure_time());
+            self.step += 1;
+            if let Some(termination) = self.config.step_options().stop_reason(&c) {
+                return (events, Ok(Some(termination)));
+            }
+        }
+        (events, Ok(None))
+    }
+}
+
+/// Wait for the initial `SimulateRequest` message.
+///
+/// Returns `None` if the client disconnected first.
+async fn receive_request(
+    socket: &mut WebSocket,
+    config: &ApiConfig,
+) -> Option<Result<SimulateRequest, String>> {
+    loop {
+        match socket.recv().await? {
+            Ok(Message::Text(text)) => {
+                let req = serde_json::from_str::<SimulateRequest>(&text)
+                    .map_err(|e| format!("invalid simulation request: {}", e))
+                    .and_then(|req| {
+                        validate_request(&req, config)
+                            .map(|()| req)
+                            .map_err(|e| e.to_string())
+                    });
+                return Some(req);
+            }
+            Ok(Message::Close(_)) | Err(_) => return None,
+            Ok(_) => continue,
+        }
+    }
+}
+
+async fn send_event(socket: &mut WebSocket, event: &StreamEvent) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(event).expect("stream events are always serializable");
+    socket.send(Message::Text(json.into())).await
+}
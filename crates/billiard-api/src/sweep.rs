@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use axum::{Json, extract::State, response::IntoResponse};
+use tracing::{info, instrument};
+
+use crate::config::ApiConfig;
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::routes::check_table_size;
+use crate::state::AppState;
+use crate::tables::preset_table;
+use crate::types::{SweepFamily, SweepRequest, SweepResponse};
+
+use billiard_core::dynamics::config::SimulationConfig;
+use billiard_core::dynamics::sweep::sweep;
+use billiard_core::geometry::boundary::BilliardTable;
+
+/// Parameter sweep endpoint for POST /sweep.
+///
+/// Builds a table of the family for every value of the parameter on the
+/// grid and measures the observable on each, over the same ensemble of
+/// initial conditions, giving the observable as a function of the
+/// parameter. The first value at which the family has no valid table fails
+/// the whole request.
+#[utoipa::path(
+    post,
+    path = "/sweep",
+    request_body = SweepRequest,
+    responses(
+        (status = 200, description = "Observable at each value of the parameter", body = SweepResponse),
+        (status = 400, description = "Invalid request parameters or table family", body = ErrorBody),
+        (status = 422, description = "Request exceeds configured limits or a table is invalid", body = ErrorBody),
+        (status = 429, description = "Rate limit exceeded", body = ErrorBody),
+    )
+)]
+#[instrument(skip(state, req))]
+pub async fn parameter_sweep(
+    State(state): State<AppState>,
+    Json(req): Json<SweepRequest>,
+) -> ApiResult<impl IntoResponse> {
+    if req.grid.count == 0 || req.particles == 0 || req.collisions == 0 {
+        return Err(ApiError::BadRequest(
+            "grid.count, particles and collisions must be greater than 0".to_string(),
+        ));
+    }
+    if !(req.grid.start.is_finite() && req.grid.end.is_finite()) {
+        return Err(ApiError::BadRequest(
+            "grid.start and grid.end must be finite".to_string(),
+        ));
+    }
+    let steps = req
+        .grid
+        .count
+        .checked_mul(req.particles)
+        .and_then(|n| n.checked_mul(req.collisions));
+    if steps.is_none_or(|steps| steps > state.config.max_steps) {
+        return Err(ApiError::LimitExceeded(format!(
+            "grid.count × particles × collisions exceeds the limit of {} steps",
+            state.config.max_steps
+        )));
+    }
+    let config = SimulationConfig::builder()
+        .max_steps(req.collisions)
+        .epsilon(req.epsilon)
+        .seed(req.seed)
+        .build()?;
+
+    if let SweepFamily::Template { template, .. } = &req.family {
+        let parameters = template
+            .parameters()
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        if !parameters.contains(&req.parameter) {
+            return Err(ApiError::BadRequest(format!(
+                "the template has no parameter {:?}",
+                req.parameter
+            )));
+        }
+    }
+
+    info!(
+        parameter = %req.parameter,
+        values = req.grid.count,
+        observable = ?req.observable,
+        "Running parameter sweep"
+    );
+    let limits = state.config.clone();
+    let response = state
+        .compute(move || {
+            let values = req.grid.values();
+            let table_at = |value| family_table(&req.family, &req.parameter, value, &limits);
+            sweep(&values, table_at, req.observable, req.particles, &config).map(|points| {
+                SweepResponse {
+                    parameter: req.parameter,
+                    observable: req.observable,
+                    points,
+                }
+            })
+        })
+        .await
+        .map_err(|e| ApiError::Internal(format!("sweep task failed: {}", e)))??;
+
+    Ok(Json(response))
+}
+
+/// The table of `family` with `parameter` set to `value`, within the
+/// configured size limits.
+fn family_table(
+    family: &SweepFamily,
+    parameter: &str,
+    value: f64,
+    limits: &ApiConfig,
+) -> ApiResult<BilliardTable> {
+    let with_value = |fixed: &HashMap<String, f64>| {
+        let mut values = fixed.clone();
+        values.insert(parameter.to_string(), value);
+        values
+    };
+    let spec = match family {
+        SweepFamily::Preset { name, parameters } => preset_table(name, &with_value(parameters))
+            .map_err(|e| match e {
+                // The preset is named in the body, not the path.
+                ApiError::NotFound(message) => ApiError::BadRequest(message),
+                e => e,
+            })?,
+        SweepFamily::Template {
+            template,
+            parameters,
+        } => template
+            .instantiate(&with_value(parameters))
+            .map_err(|e| ApiError::BadRequest(format!("{} = {}: {}", parameter, value, e)))?,
+    };
+    check_table_size(&spec, limits)?;
+    spec.try_to_billiard_table().map_err(ApiError::InvalidTable)
+}
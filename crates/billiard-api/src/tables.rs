@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
+
+use crate::error::{ApiError, ApiResult, ErrorBody};
+use crate::routes::check_table_size;
+use crate::state::AppState;
+use crate::types::{
+    ComponentPolylineDto, PolylineDto, PresetDto, PresetParameterDto, ValidationDto,
+};
+
+use billiard_core::geometry::presets::{PRESETS, Preset};
+use billiard_core::geometry::segments::BoundarySegment;
+use billiard_core::geometry::table_spec::TableSpec;
+use billiard_core::geometry::validation::validate_table;
+
+/// Chord error of GET /tables/{name}/polyline when none is given.
+const DEFAULT_CHORD_ERROR: f64 = 1e-3;
+
+/// Finest chord error accepted by GET /tables/{name}/polyline, relative to
+/// the largest arc radius of the table; a full circle then takes at most
+/// about 2,200 points.
+const MIN_RELATIVE_CHORD_ERROR: f64 = 1e-6;
+
+/// The preset `name`, or a 404.
+fn find_preset(name: &str) -> ApiResult<&'static Preset> {
+    Preset::find(name)
+        .ok_or_else(|| ApiError::NotFound(format!("table preset {:?} not found", name)))
+}
+
+/// The preset built from the parameters of a query string.
+fn query_table(preset: &Preset, overrides: &HashMap<String, String>) -> ApiResult<TableSpec> {
+    let overrides = overrides
+        .iter()
+        .map(|(key, raw)| {
+            let value = raw
+                .parse::<f64>()
+                .map_err(|_| ApiError::BadRequest(format!("{} must be a finite number", key)))?;
+            Ok((key.clone(), value))
+        })
+        .collect::<ApiResult<HashMap<_, _>>>()?;
+    preset
+        .table(&overrides)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))
+}
+
+fn preset_dto(preset: &Preset) -> PresetDto {
+    PresetDto {
+        name: preset.name,
+        description: preset.description,
+        parameters: preset
+            .parameters
+            .iter()
+            .map(|&(name, default)| PresetParameterDto { name, default })
+            .collect(),
+        table: preset.default_table(),
+    }
+}
+
+/// The preset `name` with the parameters in `overrides` replaced, as
+/// GET /tables/{name} builds it.
+pub fn preset_table(name: &str, overrides: &HashMap<String, f64>) -> ApiResult<TableSpec> {
+    find_preset(name)?
+        .table(overrides)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))
+}
+
+/// Table library endpoint for GET /tables.
+///
+/// Lists every built-in preset with its parameters and default table.
+/// Each table's metadata gives its title, description and parameters.
+#[utoipa::path(
+    get,
+    path = "/tables",
+    responses((status = 200, description = "Built-in table presets", body = [PresetDto]))
+)]
+pub async fn list_tables() -> impl IntoResponse {
+    Json(PRESETS.iter().map(preset_dto).collect::<Vec<_>>())
+}
+
+/// Table preset endpoint for GET /tables/{name}.
+///
+/// Any preset parameter may be overridden in the query string, e.g.
+/// `/tables/sinai?radius=0.3`; the parameters used are recorded in the
+/// table's metadata.
+#[utoipa::path(
+    get,
+    path = "/tables/{name}",
+    params(("name" = String, Path, description = "Preset name, as listed by GET /tables")),
+    responses(
+        (status = 200, description = "Table built from the preset", body = TableSpec),
+        (status = 400, description = "Unknown or invalid parameter", body = ErrorBody),
+        (status = 404, description = "Unknown preset", body = ErrorBody),
+    )
+)]
+pub async fn get_table(
+    Path(name): Path<String>,
+    Query(overrides): Query<HashMap<String, String>>,
+) -> ApiResult<impl IntoResponse> {
+    let table = query_table(find_preset(&name)?, &overrides)?;
+    Ok(Json(table))
+}
+
+/// Table outline endpoint for GET /tables/{name}/polyline.
+///
+/// Samples every boundary component of the preset into a closed polyline
+/// for plotting. `max_chord_error` in the query string sets how far a
+/// chord may stray from the boundary (default 0.001); any other query
+/// parameter overrides a preset parameter as in GET /tables/{name}.
+#[utoipa::path(
+    get,
+    path = "/tables/{name}/polyline",
+    params(("name" = String, Path, description = "Preset name, as listed by GET /tables")),
+    responses(
+        (status = 200, description = "Boundary components as polylines", body = PolylineDto),
+        (status = 400, description = "Unknown or invalid parameter", body = ErrorBody),
+        (status = 404, description = "Unknown preset", body = ErrorBody),
+        (status = 422, description = "Chord error too fine for the table", body = ErrorBody),
+    )
+)]
+pub async fn get_polyline(
+    Path(name): Path<String>,
+    Query(mut overrides): Query<HashMap<String, String>>,
+) -> ApiResult<impl IntoResponse> {
+    let max_chord_error = match overrides.remove("max_chord_error") {
+        Some(raw) => raw
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && *v > 0.0)
+            .ok_or_else(|| {
+                ApiError::BadRequest("max_chord_error must be a positive number".to_string())
+            })?,
+        None => DEFAULT_CHORD_ERROR,
+    };
+    let table = query_table(find_preset(&name)?, &overrides)?.to_billiard_table();
+
+    let largest_radius = table
+        .components()
+        .flat_map(|c| &c.segments)
+        .filter_map(|segment| match segment {
+            BoundarySegment::CircularArc(arc) => Some(arc.radius),
+            _ => None,
+        })
+        .fold(0.0, f64::max);
+    if max_chord_error < MIN_RELATIVE_CHORD_ERROR * largest_radius {
+        return Err(ApiError::LimitExceeded(format!(
+            "max_chord_error must be at least {:e} for this table",
+            MIN_RELATIVE_CHORD_ERROR * largest_radius
+        )));
+    }
+
+    Ok(Json(PolylineDto {
+        max_chord_error,
+        components: table
+            .components()
+            .map(|c| ComponentPolylineDto {
+                name: c.name.clone(),
+                points: c.discretize(max_chord_error),
+            })
+            .collect(),
+    }))
+}
+
+/// Table validation endpoint for POST /tables/validate.
+///
+/// Runs every geometric check on the submitted table without simulating
+/// it. A table with problems still gets a 200 response: the report lists
+/// them, and `valid` is false if any is an error.
+#[utoipa::path(
+    post,
+    path = "/tables/validate",
+    request_body = TableSpec,
+    responses(
+        (status = 200, description = "Errors and warnings found in the table", body = ValidationDto),
+        (status = 422, description = "Table exceeds configured limits", body = ErrorBody),
+    )
+)]
+pub async fn validate(
+    State(state): State<AppState>,
+    Json(table): Json<TableSpec>,
+) -> ApiResult<impl IntoResponse> {
+    check_table_size(&table, &state.config)?;
+    let report = state
+        .compute(move || validate_table(&table))
+        .await
+        .map_err(|e| ApiError::Internal(format!("validation task failed: {}", e)))?;
+    Ok(Json(ValidationDto::from(report)))
+}
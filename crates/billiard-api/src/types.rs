@@ -1,54 +1,827 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-use billiard_core::dynamics::simulation::CollisionResult;
-use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::dynamics::config::{ConfigError, SimulationConfig};
+use billiard_core::dynamics::ensemble::{FreePathHistogram, free_path_histogram};
+use billiard_core::dynamics::gallery::WhisperingGallery;
+use billiard_core::dynamics::invariants::{InvariantCheck, InvariantReport};
+use billiard_core::dynamics::lattice::{UnfoldedCollision, unfold_trajectory};
+use billiard_core::dynamics::observer::{AngleDistribution, SegmentHits};
+use billiard_core::dynamics::reflection::{Impact, ReflectionLaw, Thermal};
+use billiard_core::dynamics::santalo::{MeanFreePathCheck, check_mean_free_path};
+use billiard_core::dynamics::simulation::{
+    BilliardMap, Cancelled, CollisionResult, CornerPolicy, DynamicsError, FlightModel, Progress,
+    StepOutcome, Termination, Trajectory,
+};
+use billiard_core::dynamics::state::{BoundaryState, WorldState};
+use billiard_core::dynamics::sweep::{Observable, SweepGrid, SweepPoint};
+use billiard_core::geometry::boundary::BilliardTable;
+use billiard_core::geometry::primitives::{BoundingBox, Vec2};
+use billiard_core::geometry::segments::BoundaryCondition;
 use billiard_core::geometry::table_spec::TableSpec;
+use billiard_core::geometry::table_template::TableTemplate;
+use billiard_core::geometry::validation::{ValidationIssue, ValidationReport};
 
 /// Request payload for POST /simulate.
 ///
 /// - `table`: geometric description of the billiard table.
 /// - `initial_state`: starting collision state (boundary component, arc-length s, angle).
+/// - `initial_world`: alternatively, a starting position and direction in
+///   world coordinates. Exactly one of `initial_state` and `initial_world`
+///   must be given.
 /// - `max_steps`: maximum number of collisions to simulate.
-/// - `epsilon`: small threshold to skip self-intersections near the current bounce.
-#[derive(Debug, Deserialize)]
+/// - `epsilon`: distance within which a segment is taken to pass through the
+///   current bounce point, so that it is not hit again.
+/// - `corner_policy`: how to handle hits on corners (defaults to `report`).
+/// - `escape_box`: optional region outside of which the particle is declared
+///   escaped (for open tables: with one, gaps between segments are
+///   allowed).
+/// - `restitution`: coefficient of restitution in (0, 1] (defaults to 1,
+///   elastic).
+/// - `ball_radius`: radius of the ball; the table is offset inward by this
+///   amount (defaults to 0, a point particle).
+/// - `flight_model`: free flight between bounces, `{"kind": "straight"}`
+///   (default) or `{"kind": "gravity", "g": ...}`.
+/// - `invariants`: check every collision against the conserved quantities
+///   (speed or energy, hit point on the boundary, mirror law) and report
+///   the worst violations; with `abort`, the trajectory ends at the first
+///   one beyond tolerance. The WebSocket stream does not check them.
+/// - `whispering_gallery`: watch for particles creeping along a circular
+///   arc at a grazing angle, and either skip to their last bounce on the
+///   arc (`"action": "aggregate"`, the default) or stop there
+///   (`"terminate"`). The WebSocket stream steps every bounce.
+/// - `max_time`: also stop at this time since the start, counted as in
+///   the collisions' `time`; the trajectory then ends with `max_time`
+///   (defaults to no limit).
+/// - `seed`: seed of the velocities heated (`thermal`) walls send the
+///   particle off with (defaults to 0). Tables without heated walls
+///   reflect specularly and ignore it.
+/// - `include_statistics`: also return histograms of free path lengths
+///   and of the angles of the bounces to the normal, with
+///   `histogram_bins` bins (defaults to 50), and the number of collisions
+///   on each segment.
+/// - `include_collisions`: set to `false` to leave out the collision list,
+///   e.g. when only the statistics are needed (defaults to `true`).
+/// - `offset`, `stride`, `limit`: return only collisions `offset`,
+///   `offset + stride`, `offset + 2·stride`, ..., at most `limit` of them
+///   (defaults: 0, 1, no limit). Statistics still cover every collision.
+///
+/// The streaming WebSocket always sends every collision and ignores the
+/// last six fields.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SimulateRequest {
     pub table: TableSpec,
-    pub initial_state: BoundaryStateDto,
+    #[serde(default)]
+    pub initial_state: Option<BoundaryStateDto>,
+    #[serde(default)]
+    pub initial_world: Option<WorldStateDto>,
     pub max_steps: usize,
     pub epsilon: f64,
+    #[serde(default)]
+    pub corner_policy: CornerPolicy,
+    #[serde(default)]
+    pub escape_box: Option<BoundingBox>,
+    #[serde(default = "default_one")]
+    pub restitution: f64,
+    #[serde(default)]
+    pub ball_radius: f64,
+    #[serde(default)]
+    pub flight_model: FlightModel,
+    #[serde(default)]
+    pub invariants: Option<InvariantCheck>,
+    #[serde(default)]
+    pub whispering_gallery: Option<WhisperingGallery>,
+    #[serde(default)]
+    pub max_time: Option<f64>,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub include_statistics: bool,
+    #[serde(default = "default_histogram_bins")]
+    pub histogram_bins: usize,
+    #[serde(default = "default_true")]
+    pub include_collisions: bool,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_stride")]
+    pub stride: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
 }
 
 /// API representation of a boundary-based state.
 ///
-/// This mirrors billiard_core::dynamics::state::BoundaryState.
-#[derive(Debug, Deserialize)]
+/// This mirrors billiard_core::dynamics::state::BoundaryState. `speed`
+/// defaults to 1.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema)]
 pub struct BoundaryStateDto {
     pub component_index: usize,
     pub s: f64,
     pub theta: f64,
+    #[serde(default = "default_one")]
+    pub speed: f64,
+}
+
+/// API representation of a world-space starting state.
+///
+/// `(x, y)` must lie inside the table (or on its boundary) and `(dx, dy)` is
+/// the direction of travel, of any nonzero length. `speed` defaults to 1.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema)]
+pub struct WorldStateDto {
+    pub x: f64,
+    pub y: f64,
+    pub dx: f64,
+    pub dy: f64,
+    #[serde(default = "default_one")]
+    pub speed: f64,
+}
+
+/// Where a requested trajectory starts.
+#[derive(Clone, Copy)]
+pub enum Launch {
+    /// On the boundary: the collision map applies from the start.
+    Boundary(BoundaryState),
+
+    /// In the interior: the first flight ends at the first collision.
+    Interior(WorldState),
+}
+
+impl Launch {
+    /// Advance one step from this starting point, reached at `time` on the
+    /// clock of moving walls, leaving heated walls by `law` if given (see
+    /// [`SimulateRequest::thermal_law`]).
+    pub fn step(
+        &self,
+        table: &BilliardTable,
+        config: &SimulationConfig,
+        time: f64,
+        law: Option<&mut Thermal>,
+    ) -> Result<StepOutcome, DynamicsError> {
+        let map = BilliardMap::from_config(table, config).starting_at(time);
+        match law {
+            Some(law) => self.step_on(map.with_law(|impact: &Impact| law.reflect(impact))),
+            None => self.step_on(map),
+        }
+    }
+
+    fn step_on<L: ReflectionLaw>(
+        &self,
+        mut map: BilliardMap<L>,
+    ) -> Result<StepOutcome, DynamicsError> {
+        match self {
+            Launch::Boundary(bs) => map.step(bs),
+            Launch::Interior(ws) => map.step_from_world(ws),
+        }
+    }
+
+    /// Simulate up to `config.max_steps()` collisions from this starting
+    /// point, and until `config.max_time()` if set, giving up with [`DynamicsError::Cancelled`] soon after
+    /// `cancelled` returns true, and telling `progress`, if given, how many
+    /// collisions have been simulated as it goes. Heated walls are left by
+    /// `law`, as in [`Launch::step`].
+    pub fn simulate(
+        &self,
+        table: &BilliardTable,
+        config: &SimulationConfig,
+        cancelled: &Cancelled,
+        progress: Option<&Progress>,
+        law: Option<&mut Thermal>,
+    ) -> Result<Trajectory, DynamicsError> {
+        let mut map = BilliardMap::from_config(table, config).cancelled_by(cancelled);
+        if let Some(progress) = progress {
+            map = map.reporting_to(progress);
+        }
+        match law {
+            Some(law) => {
+                self.simulate_on(map.with_law(|impact: &Impact| law.reflect(impact)), config)
+            }
+            None => self.simulate_on(map, config),
+        }
+    }
+
+    fn simulate_on<L: ReflectionLaw>(
+        &self,
+        mut map: BilliardMap<L>,
+        config: &SimulationConfig,
+    ) -> Result<Trajectory, DynamicsError> {
+        let max_steps = config.max_steps();
+        match (self, config.max_time()) {
+            (Launch::Boundary(bs), Some(max_time)) => map.simulate_for(bs, max_steps, max_time),
+            (Launch::Boundary(bs), None) => map.simulate(bs, max_steps),
+            (Launch::Interior(ws), Some(max_time)) => {
+                map.simulate_from_world_for(ws, max_steps, max_time)
+            }
+            (Launch::Interior(ws), None) => map.simulate_from_world(ws, max_steps),
+        }
+    }
+}
+
+fn default_one() -> f64 {
+    1.0
+}
+
+fn default_histogram_bins() -> usize {
+    50
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_stride() -> usize {
+    1
 }
 
 /// Collision information returned by the simulation.
 ///
 /// Mirrors billiard_core::dynamics::simulation::CollisionResult, but tailored
-/// for JSON responses (no Vec2, just x/y).
-#[derive(Debug, Serialize)]
+/// for JSON responses (no Vec2, just x/y). `x`/`y` is the hit point in the
+/// table; `abs_x`/`abs_y` is the same point unfolded into the lattice of a
+/// periodic table (equal to `x`/`y` when no periodic edge has been crossed).
+/// `chord` is the straight-line length of the flight ending at this hit,
+/// `path_length` the sum of the chords so far and `time` the time of the
+/// hit since the start (flight times plus any delays at the wall).
+/// `tangent` is set when the flight only touched the boundary, as a ray
+/// grazing a disk does. `skipped` counts the bounces along a circular arc
+/// that a whispering-gallery skip stepped over to reach this one; `chord`
+/// then covers all of their flights. `segment_id` and `segment_name` are
+/// those given to the segment hit in the table spec, if any; unlike the
+/// indices they survive edits to the rest of the geometry.
+#[derive(Clone, Debug, Serialize, ToSchema)]
 pub struct CollisionDto {
     pub step: usize,
     pub component_index: usize,
     pub segment_index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_name: Option<String>,
     pub s: f64,
     pub theta: f64,
+    pub speed: f64,
+    pub x: f64,
+    pub y: f64,
+    pub abs_x: f64,
+    pub abs_y: f64,
+    pub chord: f64,
+    pub path_length: f64,
+    pub time: f64,
+    pub corner: bool,
+    pub tangent: bool,
+    pub skipped: usize,
+}
+
+/// Why a trajectory ended.
+///
+/// `reason` is one of `max_steps`, `corner`, `absorbed`, `escaped`,
+/// `no_collision`, `stop_condition`, `max_time`, `invariant_violation` or
+/// `whispering_gallery`.
+/// For escapes, the exit point and unit direction are included.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct TerminationDto {
+    pub reason: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub escape: Option<EscapeDto>,
+}
+
+/// Exit point and direction of an escaped particle.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct EscapeDto {
     pub x: f64,
     pub y: f64,
+    pub dx: f64,
+    pub dy: f64,
+}
+
+/// Summary statistics of a trajectory, returned when
+/// `include_statistics` is set.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct StatisticsDto {
+    pub free_path: FreePathHistogram,
+
+    /// The mean free path against Santaló's formula; it agrees only if
+    /// the trajectory explores the whole table.
+    pub mean_free_path: MeanFreePathCheck,
+
+    /// Collisions on every segment of the table, obstacles included.
+    pub segment_hits: Vec<SegmentHitsDto>,
+
+    pub incidence: IncidenceHistogramDto,
+}
+
+/// Outgoing angles of the bounces, binned, next to the counts the
+/// equilibrium distribution predicts.
+///
+/// `θ` is the angle between the outgoing direction and the tangent,
+/// binned over `[0, π]`; at equilibrium its density is `sin θ / 2`.
+/// `cos θ` is binned over `[-1, 1]` and is then uniform. Both run from the
+/// low end, so grazing bounces with `θ` near 0 are in the first angle bin
+/// and the last cosine bin. Passes through periodic segments are not
+/// bounces and are left out of `samples`.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct IncidenceHistogramDto {
+    pub samples: usize,
+    pub angle_counts: Vec<usize>,
+    pub angle_expected: Vec<f64>,
+    pub cos_counts: Vec<usize>,
+    pub cos_expected: Vec<f64>,
+}
+
+impl IncidenceHistogramDto {
+    pub fn from_core(distribution: AngleDistribution) -> Self {
+        IncidenceHistogramDto {
+            samples: distribution.samples,
+            angle_expected: distribution.expected_counts(),
+            cos_expected: distribution.expected_cos_counts(),
+            angle_counts: distribution.counts,
+            cos_counts: distribution.cos_counts,
+        }
+    }
+}
+
+/// Collisions on one segment over the whole trajectory.
+///
+/// `relative_usage` is the segment's share of the hits over its share of
+/// the boundary length: a trajectory exploring the table evenly scores
+/// about 1 on every segment, while one confined to part of phase space
+/// leaves some far from 1 or at 0. It is 0 everywhere when there were no
+/// collisions. Passes through periodic segments count as hits.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct SegmentHitsDto {
+    pub component: usize,
+    pub segment: usize,
+    pub hits: usize,
+    pub relative_usage: f64,
+}
+
+impl SegmentHitsDto {
+    /// One record per segment of `table`, components in order.
+    pub fn from_core(table: &BilliardTable, hits: &SegmentHits) -> Vec<Self> {
+        let usage = hits.relative_usage(table);
+        hits.counts
+            .iter()
+            .enumerate()
+            .flat_map(|(component, counts)| {
+                let usage = usage.as_ref();
+                counts
+                    .iter()
+                    .enumerate()
+                    .map(move |(segment, &hits)| SegmentHitsDto {
+                        component,
+                        segment,
+                        hits,
+                        relative_usage: usage.map_or(0.0, |usage| usage[component][segment]),
+                    })
+            })
+            .collect()
+    }
 }
 
 /// Response payload for POST /simulate.
 ///
-/// A trajectory is just a list of collision records.
-#[derive(Debug, Serialize)]
+/// A trajectory is a list of collision records plus the reason it ended.
+/// `collisions` holds the slice selected by `offset`, `stride` and `limit`
+/// (each keeps its `step` in the full trajectory), or nothing if
+/// `include_collisions` was `false`; `total_collisions` counts them all.
+/// `invariants` is present when the request asked for the checks.
+#[derive(Clone, Debug, Serialize, ToSchema)]
 pub struct SimulateResponse {
     pub collisions: Vec<CollisionDto>,
+    pub total_collisions: usize,
+    pub termination: TerminationDto,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<StatisticsDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invariants: Option<InvariantReport>,
+}
+
+impl SimulateResponse {
+    /// Response to `req`, run with `config`, for a finished trajectory on
+    /// `table`.
+    pub fn new(
+        req: &SimulateRequest,
+        config: &SimulationConfig,
+        table: &BilliardTable,
+        collisions: &[CollisionResult],
+        termination: &Termination,
+        invariants: Option<InvariantReport>,
+    ) -> Self {
+        let collision_dtos = if req.include_collisions {
+            unfold_trajectory(table, collisions)
+                .iter()
+                .enumerate()
+                .skip(req.offset)
+                .step_by(req.stride)
+                .take(req.limit.unwrap_or(usize::MAX))
+                .map(|(step, c)| CollisionDto::from_core(step, c, table))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let statistics = config.free_path_bins().map(|bins| {
+            let free_path = free_path_histogram(table, collisions, bins, None);
+            StatisticsDto {
+                mean_free_path: check_mean_free_path(table, &free_path.statistics),
+                free_path,
+                segment_hits: SegmentHitsDto::from_core(
+                    table,
+                    &SegmentHits::from_collisions(table, collisions),
+                ),
+                incidence: IncidenceHistogramDto::from_core(AngleDistribution::from_collisions(
+                    table, collisions, bins,
+                )),
+            }
+        });
+
+        SimulateResponse {
+            collisions: collision_dtos,
+            total_collisions: collisions.len(),
+            termination: TerminationDto::from_core(termination),
+            statistics,
+            invariants,
+        }
+    }
+}
+
+impl SimulateRequest {
+    /// Core simulation configuration described by this request.
+    pub fn config(&self) -> Result<SimulationConfig, ConfigError> {
+        SimulationConfig::builder()
+            .max_steps(self.max_steps)
+            .max_time(self.max_time)
+            .epsilon(self.epsilon)
+            .corner_policy(self.corner_policy)
+            .escape_box(self.escape_box)
+            .restitution(self.restitution)
+            .flight(self.flight_model)
+            .invariants(self.invariants)
+            .whispering_gallery(self.whispering_gallery)
+            .free_path_histogram(self.include_statistics.then_some(self.histogram_bins))
+            .build()
+    }
+
+    /// The law heated walls of `table` send the particle off by: a
+    /// [`Thermal`] law seeded with `seed` if the table has any, `None` if
+    /// every wall reflects specularly.
+    pub fn thermal_law(&self, table: &BilliardTable) -> Option<Thermal> {
+        let heated = table.components().any(|component| {
+            component
+                .segments
+                .iter()
+                .any(|seg| matches!(seg.condition(), BoundaryCondition::Thermal { .. }))
+        });
+        heated.then(|| Thermal::new(self.seed))
+    }
+
+    /// Starting point of the trajectory on `table`.
+    ///
+    /// A world position within `epsilon` of the boundary is treated as a
+    /// boundary state, so launching from a wall behaves exactly like the
+    /// equivalent `initial_state`.
+    ///
+    /// # Panics
+    /// Panics if neither `initial_state` nor `initial_world` is set; see
+    /// `validate_request`.
+    pub fn launch(&self, table: &BilliardTable) -> Launch {
+        if let Some(state) = &self.initial_state {
+            return Launch::Boundary(state.into_core());
+        }
+
+        let world = self
+            .initial_world
+            .as_ref()
+            .expect("a validated request has an initial state")
+            .into_core();
+        match world.locate_on_table(table, self.epsilon) {
+            Some(bs) => Launch::Boundary(bs),
+            None => Launch::Interior(world),
+        }
+    }
+}
+
+/// Control messages a client may send on the streaming WebSocket.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamControl {
+    /// Stop emitting collisions until `resume` or `step` is received.
+    Pause,
+    /// Emit collisions continuously.
+    Resume,
+    /// Emit `count` more collisions, then pause.
+    Step {
+        #[serde(default = "default_step_count")]
+        count: usize,
+    },
+}
+
+fn default_step_count() -> usize {
+    1
+}
+
+/// Events sent by the server on the streaming WebSocket.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// A single collision, in the same shape as in `SimulateResponse`.
+    Collision(CollisionDto),
+    /// The trajectory has ended after `collisions` collisions.
+    Done {
+        collisions: usize,
+        termination: TerminationDto,
+    },
+    /// The request or a control message was rejected, or the simulation
+    /// failed.
+    Error { message: String },
+}
+
+/// Lifecycle of a queued simulation job.
+#[derive(Clone, Debug)]
+pub enum JobStatus {
+    Queued,
+    /// Simulating; `collisions` of at most `max_steps` are done so far.
+    Running {
+        collisions: usize,
+        max_steps: usize,
+    },
+    Completed(Box<SimulateResponse>),
+    Failed(String),
+}
+
+impl JobStatus {
+    /// Whether the job has stopped running (successfully or not).
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Completed(_) | JobStatus::Failed(_))
+    }
+}
+
+/// Response payload for POST /jobs and GET /jobs/{id}.
+///
+/// `progress` is present while the job is running, `result` once it has
+/// completed and `error` once it has failed.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobDto {
+    pub id: u64,
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<ProgressDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<SimulateResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl JobDto {
+    pub fn new(id: u64, status: JobStatus) -> Self {
+        let (label, progress, result, error) = match status {
+            JobStatus::Queued => ("queued", None, None, None),
+            JobStatus::Running {
+                collisions,
+                max_steps,
+            } => {
+                let progress = ProgressDto::new(collisions, max_steps);
+                ("running", Some(progress), None, None)
+            }
+            JobStatus::Completed(response) => ("completed", None, Some(*response), None),
+            JobStatus::Failed(message) => ("failed", None, None, Some(message)),
+        };
+        JobDto {
+            id,
+            status: label,
+            progress,
+            result,
+            error,
+        }
+    }
+}
+
+/// A built-in table preset, as listed by GET /tables.
+///
+/// `table` is the preset built with the default parameters.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PresetDto {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: Vec<PresetParameterDto>,
+    pub table: TableSpec,
+}
+
+/// A preset parameter that can be overridden in GET /tables/{name}.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PresetParameterDto {
+    pub name: &'static str,
+    pub default: f64,
+}
+
+/// Response payload for GET /tables/{name}/polyline.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PolylineDto {
+    /// Largest distance between a chord and the boundary it replaces.
+    pub max_chord_error: f64,
+
+    /// The outer boundary first, then the obstacles.
+    pub components: Vec<ComponentPolylineDto>,
+}
+
+/// One boundary component as a closed polyline; the first point is not
+/// repeated at the end.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ComponentPolylineDto {
+    pub name: String,
+    pub points: Vec<Vec2>,
+}
+
+/// Response payload for POST /tables/validate.
+///
+/// `valid` is false if any issue is an error; warnings alone leave it true.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidationDto {
+    pub valid: bool,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl From<ValidationReport> for ValidationDto {
+    fn from(report: ValidationReport) -> Self {
+        ValidationDto {
+            valid: report.is_valid(),
+            issues: report.issues,
+        }
+    }
+}
+
+/// Request payload for POST /analysis/mean-free-path.
+///
+/// - `table`: the table to check.
+/// - `particles`: initial conditions drawn from the invariant measure
+///   (defaults to 100).
+/// - `collisions`: collisions each particle is followed for (defaults to
+///   100). `particles · collisions` counts against the step limit.
+/// - `epsilon`: as in POST /simulate.
+/// - `seed`: seed for drawing the initial conditions (defaults to 0).
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MeanFreePathRequest {
+    pub table: TableSpec,
+    #[serde(default = "default_ensemble_size")]
+    pub particles: usize,
+    #[serde(default = "default_ensemble_size")]
+    pub collisions: usize,
+    pub epsilon: f64,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+fn default_ensemble_size() -> usize {
+    100
+}
+
+/// Family of tables swept over by POST /sweep; the swept parameter takes
+/// each value of the grid, and `parameters` fixes any others.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SweepFamily {
+    /// A built-in preset, as listed by GET /tables. Parameters left out
+    /// keep their defaults.
+    Preset {
+        name: String,
+        #[serde(default)]
+        parameters: HashMap<String, f64>,
+    },
+
+    /// A table spec with `{"param": name, "scale": a, "offset": b}` objects
+    /// in place of numbers, standing for `b + a · value` (`scale` defaults
+    /// to 1 and `offset` to 0).
+    Template {
+        template: TableTemplate,
+        #[serde(default)]
+        parameters: HashMap<String, f64>,
+    },
+}
+
+/// Request payload for POST /sweep.
+///
+/// - `family`: the tables to sweep over.
+/// - `parameter`: the parameter of the family that is swept.
+/// - `grid`: its values, from `start` to `end` in `count` steps.
+/// - `observable`: what is measured on each table.
+/// - `particles`, `collisions`, `epsilon`, `seed`: the ensemble the
+///   observable is measured over, as in POST /analysis/mean-free-path.
+///   `count · particles · collisions` counts against the step limit.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SweepRequest {
+    pub family: SweepFamily,
+    pub parameter: String,
+    pub grid: SweepGrid,
+    pub observable: Observable,
+    #[serde(default = "default_ensemble_size")]
+    pub particles: usize,
+    #[serde(default = "default_ensemble_size")]
+    pub collisions: usize,
+    pub epsilon: f64,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// Response payload for POST /sweep: the observable at each value of the
+/// parameter, in grid order.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SweepResponse {
+    pub parameter: String,
+    pub observable: Observable,
+    pub points: Vec<SweepPoint>,
+}
+
+/// Query parameters for POST /simulate/sse.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SseParams {
+    /// Number of collisions computed between progress events, rounded
+    /// up to the few hundred between progress reports of the run.
+    pub chunk_size: Option<usize>,
+}
+
+/// How far a simulation has got: `collisions` simulated so far, updated
+/// every few hundred, out of at most `max_steps`.
+///
+/// Progress event payload for POST /simulate/sse, and the progress of a
+/// running job.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProgressDto {
+    pub collisions: usize,
+    pub max_steps: usize,
+    pub percent: f64,
+}
+
+impl ProgressDto {
+    /// Progress after `collisions` steps; passes through periodic segments
+    /// count as steps, so the count is capped at `max_steps`.
+    pub fn new(collisions: usize, max_steps: usize) -> Self {
+        let collisions = collisions.min(max_steps);
+        ProgressDto {
+            collisions,
+            max_steps,
+            percent: 100.0 * collisions as f64 / max_steps as f64,
+        }
+    }
+}
+
+/// Response payload for GET /info.
+///
+/// What the deployed server can do, for frontends to feature-detect:
+/// `features` lists the Cargo features of billiard-core in this build, and
+/// the name lists give the accepted values of the tagged fields of a
+/// request (`kind` of a segment or flight model, and so on) and the names
+/// of the built-in presets.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InfoResponse {
+    pub status: &'static str,
+    pub versions: VersionsDto,
+    pub features: Vec<&'static str>,
+    pub limits: LimitsDto,
+    pub cache: CacheStatsDto,
+    pub segment_kinds: Vec<String>,
+    pub boundary_conditions: Vec<String>,
+    pub flight_models: Vec<String>,
+    pub corner_policies: Vec<String>,
+    pub observables: Vec<String>,
+    pub presets: Vec<&'static str>,
+}
+
+/// State of the POST /simulate response cache since the server started.
+///
+/// `capacity` is 0 when the cache is disabled. Requests that bypass the
+/// cache count as neither hits nor misses.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheStatsDto {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Versions of the crates the server was built from.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VersionsDto {
+    pub api: &'static str,
+    pub core: &'static str,
+}
+
+/// Limits the server enforces on requests, as configured at startup.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LimitsDto {
+    /// Largest `max_steps`; also bounds the total collisions of a sweep.
+    pub max_steps: usize,
+    /// Largest total segment count of a table.
+    pub max_segments: usize,
+    /// Largest request body, in bytes.
+    pub max_body_bytes: usize,
+    pub max_histogram_bins: usize,
+    /// Requests allowed per client IP per minute; 0 for no limit.
+    pub rate_limit_per_minute: u32,
+    pub max_jobs: usize,
+    pub job_concurrency: usize,
+    /// Computations run at once; further requests wait for a free thread.
+    pub compute_threads: usize,
 }
 
 /// Convert API boundary state into core type.
@@ -58,21 +831,71 @@ impl BoundaryStateDto {
             component_index: self.component_index,
             s: self.s,
             theta: self.theta,
+            speed: self.speed,
+        }
+    }
+}
+
+/// Convert API world state into core type.
+impl WorldStateDto {
+    pub fn into_core(self) -> WorldState {
+        WorldState {
+            position: Vec2::new(self.x, self.y),
+            direction: Vec2::new(self.dx, self.dy),
+            speed: self.speed,
         }
     }
 }
 
 /// Convert core collision result into API DTO.
 impl CollisionDto {
-    pub fn from_core(step: usize, c: &CollisionResult) -> Self {
+    pub fn from_core(step: usize, unfolded: &UnfoldedCollision, table: &BilliardTable) -> Self {
+        let c = &unfolded.collision;
         CollisionDto {
             step,
             component_index: c.component_index,
             segment_index: c.segment_index,
+            segment_id: c.segment_id,
+            segment_name: c.segment_name(table).map(str::to_string),
             s: c.s,
             theta: c.theta,
+            speed: c.speed,
             x: c.hit_point.x,
             y: c.hit_point.y,
+            abs_x: unfolded.absolute_position.x,
+            abs_y: unfolded.absolute_position.y,
+            chord: c.chord,
+            path_length: c.path_length,
+            time: c.time,
+            corner: c.corner.is_some(),
+            tangent: c.tangent,
+            skipped: c.skipped,
         }
     }
 }
+
+/// Convert core termination reason into API DTO.
+impl TerminationDto {
+    pub fn from_core(termination: &Termination) -> Self {
+        let (reason, escape) = match termination {
+            Termination::MaxSteps => ("max_steps", None),
+            Termination::Corner => ("corner", None),
+            Termination::Absorbed => ("absorbed", None),
+            Termination::Escaped(e) => (
+                "escaped",
+                Some(EscapeDto {
+                    x: e.position.x,
+                    y: e.position.y,
+                    dx: e.direction.x,
+                    dy: e.direction.y,
+                }),
+            ),
+            Termination::NoCollision => ("no_collision", None),
+            Termination::StopCondition => ("stop_condition", None),
+            Termination::MaxTime => ("max_time", None),
+            Termination::InvariantViolation => ("invariant_violation", None),
+            Termination::WhisperingGallery => ("whispering_gallery", None),
+        };
+        TerminationDto { reason, escape }
+    }
+}
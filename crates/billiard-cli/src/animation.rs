@@ -0,0 +1,214 @@
+//! Animated GIFs of a particle moving along its trajectory.
+//!
+//! Each frame is drawn on an indexed canvas with a small fixed palette: the
+//! boundary, the recent path and the particle itself. The particle moves
+//! along the chords at constant speed in simulated time, so the whole
+//! trajectory plays out over the requested duration.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use gif::{Encoder, Frame, Repeat};
+
+use billiard_core::geometry::boundary::BilliardTable;
+use billiard_core::geometry::primitives::Vec2;
+
+use crate::output::table_outlines;
+use crate::watch::Flight;
+
+/// Background, outer boundary, obstacles, path and particle.
+const PALETTE: [[u8; 3]; 5] = [
+    [255, 255, 255],
+    [0, 0, 0],
+    [105, 105, 105],
+    [70, 130, 180],
+    [220, 20, 60],
+];
+const BACKGROUND: u8 = 0;
+const BOUNDARY: u8 = 1;
+const OBSTACLE: u8 = 2;
+const PATH: u8 = 3;
+const PARTICLE: u8 = 4;
+
+/// Largest distance between a drawn boundary and the true one, relative to
+/// the size of the table.
+const RELATIVE_CHORD_ERROR: f64 = 1e-3;
+
+/// Blank space around the table, relative to its size.
+const MARGIN: f64 = 0.05;
+
+/// Line widths and particle radius, in pixels.
+const BOUNDARY_WIDTH: f64 = 2.0;
+const PATH_WIDTH: f64 = 1.0;
+const PARTICLE_RADIUS: f64 = 4.0;
+
+/// What to draw and how fast.
+#[derive(Clone, Copy, Debug)]
+pub struct AnimationOptions {
+    pub width: u16,
+    pub height: u16,
+    pub fps: u16,
+
+    /// Length of the animation in seconds.
+    pub duration: f64,
+
+    /// Flights drawn behind the particle.
+    pub trail: usize,
+}
+
+impl AnimationOptions {
+    /// Number of frames, at least two so the animation has a start and an
+    /// end.
+    pub fn frames(&self) -> usize {
+        ((self.duration * f64::from(self.fps)).round() as usize).max(2)
+    }
+}
+
+/// Pixels of a frame as palette indices, row by row from the top.
+struct Raster {
+    width: usize,
+    height: usize,
+    origin: Vec2,
+    scale: f64,
+    pixels: Vec<u8>,
+}
+
+impl Raster {
+    /// A blank raster fitting the box from `min` to `max`, centered, with
+    /// `y` pointing up.
+    fn new(width: usize, height: usize, min: Vec2, max: Vec2) -> Self {
+        let size = (max.x - min.x).max(max.y - min.y).max(f64::MIN_POSITIVE);
+        let (min, max) = (
+            min - Vec2::new(MARGIN * size, MARGIN * size),
+            max + Vec2::new(MARGIN * size, MARGIN * size),
+        );
+        let scale = (width as f64 / (max.x - min.x)).min(height as f64 / (max.y - min.y));
+        let pad = Vec2::new(
+            (width as f64 / scale - (max.x - min.x)) / 2.0,
+            (height as f64 / scale - (max.y - min.y)) / 2.0,
+        );
+        Raster {
+            width,
+            height,
+            origin: Vec2::new(min.x - pad.x, max.y + pad.y),
+            scale,
+            pixels: vec![BACKGROUND; width * height],
+        }
+    }
+
+    /// Pixel coordinates of `p`, not necessarily on the raster.
+    fn to_pixel(&self, p: Vec2) -> (f64, f64) {
+        (
+            (p.x - self.origin.x) * self.scale,
+            (self.origin.y - p.y) * self.scale,
+        )
+    }
+
+    /// Fill the pixels whose centers are within `radius` of `(x, y)`.
+    fn disk(&mut self, (x, y): (f64, f64), radius: f64, color: u8) {
+        let r = radius.max(0.5);
+        let rows =
+            (y - r).floor().max(0.0) as usize..((y + r).ceil().max(0.0) as usize).min(self.height);
+        let cols =
+            (x - r).floor().max(0.0) as usize..((x + r).ceil().max(0.0) as usize).min(self.width);
+        for j in rows {
+            for i in cols.clone() {
+                let (dx, dy) = (i as f64 + 0.5 - x, j as f64 + 0.5 - y);
+                if dx * dx + dy * dy <= r * r {
+                    self.pixels[j * self.width + i] = color;
+                }
+            }
+        }
+    }
+
+    /// Draw the segment from `a` to `b`, `width` pixels wide.
+    fn line(&mut self, a: Vec2, b: Vec2, width: f64, color: u8) {
+        let (a, b) = (self.to_pixel(a), self.to_pixel(b));
+        let steps = (b.0 - a.0).hypot(b.1 - a.1).ceil().max(1.0) as usize;
+        for k in 0..=steps {
+            let f = k as f64 / steps as f64;
+            let p = (a.0 + (b.0 - a.0) * f, a.1 + (b.1 - a.1) * f);
+            self.disk(p, width / 2.0, color);
+        }
+    }
+
+    /// Draw the closed polygon through `points`.
+    fn outline(&mut self, points: &[Vec2], width: f64, color: u8) {
+        for (k, &a) in points.iter().enumerate() {
+            self.line(a, points[(k + 1) % points.len()], width, color);
+        }
+    }
+}
+
+/// Draw `flights` on `table` as an animated GIF, looping forever.
+pub fn write_gif<W: Write>(
+    w: W,
+    table: &BilliardTable,
+    flights: &[Flight],
+    options: &AnimationOptions,
+) -> Result<(), gif::EncodingError> {
+    let (width, height) = (usize::from(options.width), usize::from(options.height));
+
+    let (outlines, min, max) = table_outlines(table, RELATIVE_CHORD_ERROR);
+
+    // The boundary is the same in every frame.
+    let mut background = Raster::new(width, height, min, max);
+    for (i, points) in outlines.iter().enumerate() {
+        let color = if i == 0 { BOUNDARY } else { OBSTACLE };
+        background.outline(points, BOUNDARY_WIDTH, color);
+    }
+
+    let mut encoder = Encoder::new(w, options.width, options.height, PALETTE.as_flattened())?;
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = (100.0 / f64::from(options.fps)).round() as u16;
+    let end = flights.last().map_or(0.0, |f| f.end);
+    let frames = options.frames();
+
+    let mut raster = Raster::new(width, height, min, max);
+    for k in 0..frames {
+        let time = end * k as f64 / (frames - 1) as f64;
+        raster.pixels.copy_from_slice(&background.pixels);
+
+        // Flights that have started, the current one last.
+        let current = flights.partition_point(|f| f.start <= time).max(1);
+        let start = current.saturating_sub(options.trail + 1);
+        for f in &flights[start..current.min(flights.len())] {
+            let to = if f.end > time {
+                f.position_at(time)
+            } else {
+                f.to
+            };
+            raster.line(f.from, to, PATH_WIDTH, PATH);
+        }
+        if let Some(f) = flights.get(current - 1) {
+            let p = raster.to_pixel(f.position_at(time));
+            raster.disk(p, PARTICLE_RADIUS, PARTICLE);
+        }
+
+        let mut frame = Frame::from_indexed_pixels(
+            options.width,
+            options.height,
+            raster.pixels.as_slice(),
+            None,
+        );
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+    }
+    encoder.into_inner()?.flush()?;
+    Ok(())
+}
+
+/// Write the animation to the file at `path`.
+pub fn write_gif_file(
+    path: &Path,
+    table: &BilliardTable,
+    flights: &[Flight],
+    options: &AnimationOptions,
+) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    write_gif(file, table, flights, options).map_err(|e| match e {
+        gif::EncodingError::Io(e) => e,
+        e => io::Error::other(e),
+    })
+}
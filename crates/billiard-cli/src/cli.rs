@@ -0,0 +1,579 @@
+use std::path::PathBuf;
+
+use billiard_core::dynamics::gallery::GalleryAction;
+use billiard_core::dynamics::simulation::CornerPolicy;
+use billiard_core::geometry::primitives::{BoundingBox, Vec2};
+use billiard_core::geometry::table_spec::TableSpec;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+
+/// Billiard simulation tools.
+#[derive(Debug, Parser)]
+#[command(name = "bouncers", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Simulate a trajectory and print its collisions.
+    Simulate(SimulateArgs),
+
+    /// Simulate a trajectory and draw it on the table as an SVG file.
+    Render(RenderArgs),
+
+    /// Draw the density of bounces on the Poincaré section `(s, cos θ)` as
+    /// a PNG heatmap.
+    Heatmap(HeatmapArgs),
+
+    /// Animate a trajectory in the terminal.
+    Watch(WatchArgs),
+
+    /// Simulate a trajectory and save the particle moving along it as an
+    /// animated GIF.
+    Animate(AnimateArgs),
+
+    /// Follow one trajectory across a family of tables and record where it
+    /// settles on the Poincaré section at each value of a parameter: the
+    /// data of a bifurcation diagram.
+    Sweep(SweepArgs),
+
+    /// Run a seeded ensemble of trajectories from the invariant measure in
+    /// parallel and write aggregate statistics as JSON or CSV.
+    Ensemble(EnsembleArgs),
+
+    /// Keep a table loaded and step a trajectory on it interactively,
+    /// changing its state between steps.
+    Repl(ReplArgs),
+
+    /// Inspect the built-in table presets.
+    #[command(subcommand)]
+    Tables(TablesCommand),
+
+    /// Check that a table file describes valid geometry.
+    Validate {
+        /// Path to a `TableSpec` JSON, TOML or YAML file.
+        path: PathBuf,
+
+        /// Allow gaps between segments (open tables).
+        #[arg(long)]
+        open: bool,
+    },
+}
+
+impl Command {
+    /// The trajectory options of the commands that simulate one.
+    pub fn trajectory_mut(&mut self) -> Option<&mut TrajectoryArgs> {
+        match self {
+            Command::Simulate(args) => Some(&mut args.trajectory),
+            Command::Render(args) => Some(&mut args.trajectory),
+            Command::Heatmap(args) => Some(&mut args.trajectory),
+            Command::Watch(args) => Some(&mut args.trajectory),
+            Command::Animate(args) => Some(&mut args.trajectory),
+            Command::Repl(args) => Some(&mut args.trajectory),
+            Command::Sweep(_)
+            | Command::Ensemble(_)
+            | Command::Tables(_)
+            | Command::Validate { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TablesCommand {
+    /// List the available table presets.
+    List,
+}
+
+/// The table, initial state and run options shared by the commands that
+/// simulate a trajectory.
+#[derive(Debug, Args)]
+pub struct TrajectoryArgs {
+    /// Read any of these options from a JSON, TOML or YAML file, keyed by
+    /// their long names (`ball-radius = 0.05`); options on the command line
+    /// take precedence. Its `table` may also be a whole `TableSpec`.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Preset name (see `bouncers tables list`) or path to a `TableSpec`
+    /// JSON, TOML or YAML file.
+    #[arg(long, default_value = "sinai")]
+    pub table: String,
+
+    /// Table given inline in the `--config` file, used in place of
+    /// `--table`.
+    #[arg(skip)]
+    pub spec: Option<TableSpec>,
+
+    /// Boundary component to start on (0 = outer boundary).
+    #[arg(long, default_value_t = 0)]
+    pub component: usize,
+
+    /// Initial arc-length position on the starting component.
+    #[arg(long, default_value_t = 0.3)]
+    pub s: f64,
+
+    /// Initial outgoing angle relative to the boundary tangent, in radians.
+    #[arg(long, default_value_t = std::f64::consts::FRAC_PI_3)]
+    pub theta: f64,
+
+    /// Initial speed of the particle.
+    #[arg(long, default_value_t = 1.0)]
+    pub speed: f64,
+
+    /// Coefficient of restitution in (0, 1]; values below 1 damp the normal
+    /// velocity at each bounce.
+    #[arg(long, default_value_t = 1.0)]
+    pub restitution: f64,
+
+    /// Downward gravitational acceleration; when set, the particle follows
+    /// parabolic arcs between bounces.
+    #[arg(long)]
+    pub gravity: Option<f64>,
+
+    /// Radius of the ball; the table is offset inward so that collisions
+    /// happen when the ball touches the wall. Positions refer to the offset
+    /// boundary traced by the ball's center.
+    #[arg(long, default_value_t = 0.0)]
+    pub ball_radius: f64,
+
+    /// Maximum number of collisions to simulate.
+    #[arg(long, default_value_t = 50)]
+    pub steps: usize,
+
+    /// Also stop at this time (flight times plus any delays at the wall),
+    /// whatever the number of collisions.
+    #[arg(long)]
+    pub max_time: Option<f64>,
+
+    /// Distance within which a segment is taken to pass through the
+    /// current bounce point and is not hit.
+    #[arg(long, default_value_t = 1e-8)]
+    pub epsilon: f64,
+
+    /// How hits on corners are handled.
+    #[arg(long, value_enum, default_value_t = CornerPolicyArg::Report)]
+    pub corner_policy: CornerPolicyArg,
+
+    /// Declare the particle escaped once it leaves this box, given as
+    /// `XMIN,YMIN,XMAX,YMAX` (for open tables).
+    #[arg(long, value_parser = parse_bounding_box, allow_hyphen_values = true)]
+    pub escape_box: Option<BoundingBox>,
+
+    /// Watch for the particle creeping along a circular arc at a grazing
+    /// angle, and aggregate its bounces into a jump to the last one on the
+    /// arc, or terminate the trajectory there.
+    #[arg(long, value_enum, value_name = "ACTION")]
+    pub whispering_gallery: Option<GalleryActionArg>,
+}
+
+#[derive(Debug, Args)]
+pub struct SimulateArgs {
+    #[command(flatten)]
+    pub trajectory: TrajectoryArgs,
+
+    /// How collisions are printed to stdout.
+    #[arg(long, value_enum, default_value_t = Format::Pretty)]
+    pub format: Format,
+
+    /// Write collisions to a `.csv` or `.json` file instead of stdout.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Also write the path of the particle, unfolded through periodic
+    /// edges, to a `.csv` file of vertices or an `.svg` drawing.
+    #[arg(long)]
+    pub polyline: Option<PathBuf>,
+
+    /// Check every collision against the conserved quantities (speed or
+    /// energy, hit point on the boundary, mirror law) and report the worst
+    /// violations on stderr; TOL is the tolerance of each.
+    #[arg(
+        long,
+        value_name = "TOL",
+        num_args = 0..=1,
+        default_missing_value = "1e-9"
+    )]
+    pub check_invariants: Option<f64>,
+
+    /// Stop at the first collision beyond the invariant tolerance.
+    #[arg(long, requires = "check_invariants")]
+    pub abort_on_violation: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RenderArgs {
+    #[command(flatten)]
+    pub trajectory: TrajectoryArgs,
+
+    /// Path of the SVG file to write.
+    #[arg(long, default_value = "trajectory.svg")]
+    pub output: PathBuf,
+
+    /// Color of the outer boundary, as any SVG color (`black`, `#336699`).
+    #[arg(long, value_parser = parse_color, default_value = "black")]
+    pub boundary_color: String,
+
+    /// Color of the obstacles.
+    #[arg(long, value_parser = parse_color, default_value = "dimgray")]
+    pub obstacle_color: String,
+
+    /// Color of the trajectory.
+    #[arg(long, value_parser = parse_color, default_value = "steelblue")]
+    pub trajectory_color: String,
+
+    /// Stroke width of the boundary and obstacles, in pixels.
+    #[arg(long, value_parser = parse_width, default_value_t = 2.0)]
+    pub boundary_width: f64,
+
+    /// Stroke width of the trajectory, in pixels.
+    #[arg(long, value_parser = parse_width, default_value_t = 1.0)]
+    pub trajectory_width: f64,
+
+    /// Fill color of the background; transparent if not given.
+    #[arg(long, value_parser = parse_color)]
+    pub background: Option<String>,
+}
+
+/// Command-line spelling of [`CornerPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CornerPolicyArg {
+    /// Stop the trajectory at the corner.
+    Terminate,
+    /// Reflect off the bisector of the two segment normals.
+    Bisector,
+    /// Reflect off the segment that was hit and flag the corner.
+    Report,
+}
+
+impl From<CornerPolicyArg> for CornerPolicy {
+    fn from(arg: CornerPolicyArg) -> Self {
+        match arg {
+            CornerPolicyArg::Terminate => CornerPolicy::Terminate,
+            CornerPolicyArg::Bisector => CornerPolicy::ReflectBisector,
+            CornerPolicyArg::Report => CornerPolicy::Report,
+        }
+    }
+}
+
+/// Command-line spelling of [`GalleryAction`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GalleryActionArg {
+    /// Jump to the last bounce on the arc in one exact step.
+    Aggregate,
+    /// Stop the trajectory.
+    Terminate,
+}
+
+impl From<GalleryActionArg> for GalleryAction {
+    fn from(arg: GalleryActionArg) -> Self {
+        match arg {
+            GalleryActionArg::Aggregate => GalleryAction::Aggregate,
+            GalleryActionArg::Terminate => GalleryAction::Terminate,
+        }
+    }
+}
+
+/// Parse `XMIN,YMIN,XMAX,YMAX` into a bounding box.
+fn parse_bounding_box(value: &str) -> Result<BoundingBox, String> {
+    let coords = value
+        .split(',')
+        .map(|v| {
+            v.trim()
+                .parse::<f64>()
+                .map_err(|e| format!("'{}': {}", v, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    bounding_box(&coords)
+}
+
+/// The box `[XMIN, YMIN, XMAX, YMAX]`, if it has four coordinates and is
+/// not empty.
+pub fn bounding_box(coords: &[f64]) -> Result<BoundingBox, String> {
+    let [x0, y0, x1, y1] = coords[..] else {
+        return Err("expected four numbers XMIN,YMIN,XMAX,YMAX".to_string());
+    };
+    if !(x0 < x1 && y0 < y1) {
+        return Err("XMIN must be less than XMAX and YMIN less than YMAX".to_string());
+    }
+    Ok(BoundingBox::new(Vec2::new(x0, y0), Vec2::new(x1, y1)))
+}
+
+#[derive(Debug, Args)]
+pub struct HeatmapArgs {
+    #[command(flatten)]
+    pub trajectory: TrajectoryArgs,
+
+    /// Number of trajectories to overlay; all but the first start from
+    /// random states.
+    #[arg(long, default_value_t = 1)]
+    pub trajectories: usize,
+
+    /// Seed for the random starting states.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    /// Bins (pixels) along the boundary, all components end to end.
+    #[arg(long, default_value_t = 800)]
+    pub width: usize,
+
+    /// Bins (pixels) in `cos θ`, from -1 at the bottom to 1 at the top.
+    #[arg(long, default_value_t = 400)]
+    pub height: usize,
+
+    /// Color scale from empty to the fullest bin.
+    #[arg(long, value_enum, default_value_t = Colormap::Viridis)]
+    pub colormap: Colormap,
+
+    /// Color by the logarithm of the count, to bring out sparse regions.
+    #[arg(long)]
+    pub log: bool,
+
+    /// Path of the PNG file to write.
+    #[arg(long, default_value = "phase_space.png")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub trajectory: TrajectoryArgs,
+
+    /// Simulated time per second; `+` and `-` double and halve it while
+    /// watching.
+    #[arg(long, default_value_t = 1.0)]
+    pub rate: f64,
+
+    /// Frames drawn per second.
+    #[arg(long, default_value_t = 30.0)]
+    pub fps: f64,
+
+    /// Number of past flights drawn behind the ball.
+    #[arg(long, default_value_t = 20)]
+    pub trail: usize,
+
+    /// Width of the drawing in characters [default: the terminal's].
+    #[arg(long, requires = "rows")]
+    pub cols: Option<usize>,
+
+    /// Height of the drawing in lines, with the status line
+    /// [default: the terminal's].
+    #[arg(long, requires = "cols")]
+    pub rows: Option<usize>,
+}
+
+#[derive(Debug, Args)]
+pub struct AnimateArgs {
+    #[command(flatten)]
+    pub trajectory: TrajectoryArgs,
+
+    /// Length of the animation in seconds; the whole trajectory plays out
+    /// over it.
+    #[arg(long, default_value_t = 10.0)]
+    pub duration: f64,
+
+    /// Frames per second, at most 50 (GIF delays are in hundredths of a
+    /// second and viewers slow down shorter ones).
+    #[arg(long, default_value_t = 25)]
+    pub fps: u16,
+
+    /// Width of the image in pixels.
+    #[arg(long, default_value_t = 480)]
+    pub width: u16,
+
+    /// Height of the image in pixels.
+    #[arg(long, default_value_t = 480)]
+    pub height: u16,
+
+    /// Number of past flights drawn behind the particle.
+    #[arg(long, default_value_t = 20)]
+    pub trail: usize,
+
+    /// Path of the GIF file to write.
+    #[arg(long, default_value = "trajectory.gif")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct EnsembleArgs {
+    /// Preset name (see `bouncers tables list`) or path to a `TableSpec`
+    /// JSON, TOML or YAML file.
+    #[arg(long, default_value = "stadium")]
+    pub table: String,
+
+    /// Number of particles, drawn from the invariant measure of the
+    /// billiard map.
+    #[arg(long, default_value_t = 1000)]
+    pub n: usize,
+
+    /// Collisions simulated for each particle.
+    #[arg(long, default_value_t = 1000)]
+    pub steps: usize,
+
+    /// Seed of the starting states; the statistics depend on nothing else,
+    /// whatever the number of threads.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    /// Statistics to compute, separated by commas.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "lyapunov,mfp,angles"
+    )]
+    pub stats: Vec<Statistic>,
+
+    /// Bins of the angle histograms.
+    #[arg(long, default_value_t = 18)]
+    pub bins: usize,
+
+    /// Distance within which a segment is taken to pass through the
+    /// current bounce point and is not hit.
+    #[arg(long, default_value_t = 1e-8)]
+    pub epsilon: f64,
+
+    /// Worker threads [default: one per CPU].
+    #[arg(long)]
+    pub threads: Option<usize>,
+
+    /// Write the statistics to a `.json` or `.csv` file instead of printing
+    /// them as JSON.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+/// Aggregate statistics of `bouncers ensemble`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Statistic {
+    /// Largest Lyapunov exponent per collision, over the particles that
+    /// miss every corner.
+    Lyapunov,
+    /// Mean free path between collisions, against Santaló's formula.
+    Mfp,
+    /// Histograms of the outgoing angles to the tangent, against their
+    /// equilibrium distribution.
+    Angles,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplArgs {
+    /// The table and starting state; `--steps` and `--max-time` are
+    /// ignored, as each `step` says how far to go.
+    #[command(flatten)]
+    pub trajectory: TrajectoryArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct SweepArgs {
+    /// Preset name (see `bouncers tables list`) or path to a `TableTemplate`
+    /// JSON file: a `TableSpec` in which numbers may be replaced by
+    /// references `{"param": NAME, "scale": A, "offset": B}` to
+    /// `B + A·NAME`.
+    #[arg(long, default_value = "stadium")]
+    pub table: String,
+
+    /// Parameter of the preset or template to vary.
+    #[arg(long, default_value = "length")]
+    pub parameter: String,
+
+    /// First value of the parameter.
+    #[arg(long, allow_hyphen_values = true, default_value_t = 0.1)]
+    pub from: f64,
+
+    /// Last value of the parameter.
+    #[arg(long, allow_hyphen_values = true, default_value_t = 2.0)]
+    pub to: f64,
+
+    /// Number of evenly spaced values, both ends included.
+    #[arg(long, default_value_t = 200)]
+    pub count: usize,
+
+    /// Boundary component the reference trajectory starts on.
+    #[arg(long, default_value_t = 0)]
+    pub component: usize,
+
+    /// Initial arc-length position of the reference trajectory.
+    #[arg(long, default_value_t = 0.3)]
+    pub s: f64,
+
+    /// Initial angle of the reference trajectory to the boundary tangent,
+    /// in radians.
+    #[arg(long, default_value_t = std::f64::consts::FRAC_PI_3)]
+    pub theta: f64,
+
+    /// Collisions left out at the start of each run, before the trajectory
+    /// has settled.
+    #[arg(long, default_value_t = 500)]
+    pub transient: usize,
+
+    /// Collisions recorded after the transient.
+    #[arg(long, default_value_t = 2000)]
+    pub steps: usize,
+
+    /// Distance within which a segment is taken to pass through the
+    /// current bounce point and is not hit.
+    #[arg(long, default_value_t = 1e-8)]
+    pub epsilon: f64,
+
+    /// Cells along each axis of the Poincaré section; only the first bounce
+    /// in each cell is kept.
+    #[arg(long, default_value_t = 400)]
+    pub resolution: usize,
+
+    /// Draw the diagram to this PNG file, one column per value and `cos θ`
+    /// upwards, instead of printing the bounces as CSV.
+    #[arg(long)]
+    pub plot: Option<PathBuf>,
+
+    /// Height of the plot in pixels.
+    #[arg(long, default_value_t = 400, requires = "plot")]
+    pub height: usize,
+}
+
+/// Color scales for heatmaps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Colormap {
+    /// Perceptually uniform, dark blue to yellow.
+    Viridis,
+    /// Perceptually uniform, black to pale yellow through purple.
+    Magma,
+    /// Black through red and yellow to white.
+    Hot,
+    /// Black to white.
+    Gray,
+}
+
+/// Accept a color for an SVG attribute, refusing characters that would
+/// break out of it.
+fn parse_color(value: &str) -> Result<String, String> {
+    if value.is_empty() || value.contains(['"', '<', '>', '&']) {
+        return Err(format!("'{}' is not a valid color", value));
+    }
+    Ok(value.to_string())
+}
+
+/// Parse a positive, finite stroke width.
+fn parse_width(value: &str) -> Result<f64, String> {
+    let width = value
+        .parse::<f64>()
+        .map_err(|e| format!("'{}': {}", value, e))?;
+    if !(width.is_finite() && width > 0.0) {
+        return Err("width must be positive and finite".to_string());
+    }
+    Ok(width)
+}
+
+/// Output format for collision listings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns, color-coded by boundary component.
+    Pretty,
+    /// Comma-separated values with a header row.
+    Csv,
+    /// A JSON array of collision records.
+    Json,
+}
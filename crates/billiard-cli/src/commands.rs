@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use billiard_core::dynamics::config::{ConfigError, SimulationConfig};
+use billiard_core::dynamics::gallery::WhisperingGallery;
+use billiard_core::dynamics::invariants::{InvariantCheck, InvariantReport};
+use billiard_core::dynamics::lattice::{trajectory_to_polyline, unfold_trajectory};
+use billiard_core::dynamics::phase_space::PhaseSpaceHistogram;
+use billiard_core::dynamics::sampling::{Sampling, sample_boundary_states};
+use billiard_core::dynamics::simulation::{
+    BilliardMap, DynamicsError, FlightModel, Progress, Termination, Trajectory,
+};
+use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::dynamics::sweep::{SweepGrid, bifurcation_diagram};
+use billiard_core::geometry::boundary::BilliardTable;
+use billiard_core::geometry::offset::try_offset_for_ball;
+use billiard_core::geometry::presets::{PRESETS, Preset};
+use billiard_core::geometry::table_spec::{TableGeometryError, TableSpec};
+use billiard_core::geometry::table_template::TableTemplate;
+
+use crate::animation::{self, AnimationOptions};
+use crate::cli::{
+    AnimateArgs, Format, HeatmapArgs, RenderArgs, SimulateArgs, SweepArgs, TrajectoryArgs,
+    WatchArgs,
+};
+use crate::config::read_document;
+use crate::output::{self, FileFormat, PolylineFormat, SvgStyle};
+use crate::progress;
+use crate::watch::{self, WatchOptions};
+
+/// Resolve a `--table` argument: a preset name, or else a path to a JSON,
+/// TOML or YAML file.
+pub fn load_table_spec(table: &str) -> Result<TableSpec, Box<dyn std::error::Error>> {
+    if let Some(preset) = Preset::find(table) {
+        return Ok(preset.default_table());
+    }
+    let path = Path::new(table);
+    if !path.is_file() {
+        return Err(format!("'{}' is neither a preset nor a readable file", table).into());
+    }
+    Ok(read_document(path)?)
+}
+
+/// Load the table and simulate the trajectory described by `args`.
+fn run(
+    args: &TrajectoryArgs,
+) -> Result<(BilliardTable, BoundaryState, Trajectory), Box<dyn std::error::Error>> {
+    let (table, initial, config) = setup(args)?;
+    let trajectory = simulate_from(&table, &initial, &config, None)?;
+    Ok((table, initial, trajectory))
+}
+
+/// `e` in terms of the command-line option that set the rejected value.
+pub fn config_error(e: ConfigError) -> String {
+    match e {
+        ConfigError::InvalidEpsilon(_) => "--epsilon must be positive and finite".to_string(),
+        ConfigError::InvalidRestitution(_) => "--restitution must be in (0, 1]".to_string(),
+        ConfigError::InvalidGravity(_) => "--gravity must be finite".to_string(),
+        ConfigError::EmptyEscapeBox => {
+            "--escape-box min must be strictly less than max".to_string()
+        }
+        ConfigError::InvalidMaxTime(_) => "--max-time must be positive and finite".to_string(),
+        ConfigError::InvalidInvariantTolerance(_) => {
+            "--check-invariants must be non-negative".to_string()
+        }
+        e => e.to_string(),
+    }
+}
+
+/// Load the table, initial state and configuration described by `args`.
+pub fn setup(
+    args: &TrajectoryArgs,
+) -> Result<(BilliardTable, BoundaryState, SimulationConfig), Box<dyn std::error::Error>> {
+    let spec = match &args.spec {
+        Some(spec) => spec.clone(),
+        None => load_table_spec(&args.table)?,
+    };
+    if !args.ball_radius.is_finite() || args.ball_radius < 0.0 {
+        return Err("--ball-radius must be non-negative and finite".into());
+    }
+    // With an escape box the table may be open, so gaps are allowed.
+    let table = build_table(&spec, args.ball_radius, args.escape_box.is_some())?;
+
+    if args.component >= table.component_count() {
+        return Err(format!(
+            "component {} does not exist (table has {} components)",
+            args.component,
+            table.component_count()
+        )
+        .into());
+    }
+
+    if !args.speed.is_finite() || args.speed <= 0.0 {
+        return Err("--speed must be positive and finite".into());
+    }
+
+    let initial = BoundaryState {
+        component_index: args.component,
+        s: args.s,
+        theta: args.theta,
+        speed: args.speed,
+    };
+
+    let config = SimulationConfig::builder()
+        .max_steps(args.steps)
+        .max_time(args.max_time)
+        .epsilon(args.epsilon)
+        .corner_policy(args.corner_policy.into())
+        .escape_box(args.escape_box)
+        .whispering_gallery(
+            args.whispering_gallery
+                .map(|action| WhisperingGallery::new(action.into())),
+        )
+        .restitution(args.restitution)
+        .flight(
+            args.gravity
+                .map_or(FlightModel::Straight, |g| FlightModel::Gravity { g }),
+        )
+        .build()
+        .map_err(config_error)?;
+    Ok((table, initial, config))
+}
+
+/// Simulate from `initial` for the steps, and time if set, of `config`,
+/// telling `progress` how far the run has got.
+fn simulate_from(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    config: &SimulationConfig,
+    progress: Option<&Progress>,
+) -> Result<Trajectory, DynamicsError> {
+    let mut map = BilliardMap::from_config(table, config);
+    if let Some(progress) = progress {
+        map = map.reporting_to(progress);
+    }
+    match config.max_time() {
+        Some(max_time) => map.simulate_for(initial, config.max_steps(), max_time),
+        None => map.simulate(initial, config.max_steps()),
+    }
+}
+
+/// Run a trajectory on the requested table and print its collisions.
+pub fn simulate(args: &SimulateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (table, initial, config) = setup(&args.trajectory)?;
+    let check = args.check_invariants.map(|tolerance| InvariantCheck {
+        speed_tolerance: tolerance,
+        boundary_tolerance: tolerance,
+        reflection_tolerance: tolerance,
+        abort: args.abort_on_violation,
+    });
+    let config = config
+        .to_builder()
+        .invariants(check)
+        .build()
+        .map_err(config_error)?;
+    let bar = progress::bounce_bar(config.max_steps() as u64, "simulate");
+    let trajectory = simulate_from(&table, &initial, &config, Some(&|n| bar.inc(n as u64)));
+    bar.finish_and_clear();
+    let trajectory = trajectory?;
+    let collisions = unfold_trajectory(&table, &trajectory.collisions);
+
+    if let Some(path) = &args.output {
+        let format = FileFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "cannot infer output format of '{}' (expected .csv or .json)",
+                path.display()
+            )
+        })?;
+        output::write_file(path, format, &table, &collisions)?;
+        eprintln!(
+            "wrote {} collisions to {}",
+            collisions.len(),
+            path.display()
+        );
+    } else {
+        match args.format {
+            Format::Pretty => output::print_pretty(&table, &collisions),
+            Format::Csv => output::write_csv(std::io::stdout().lock(), &table, &collisions)?,
+            Format::Json => output::write_json(std::io::stdout().lock(), &table, &collisions)?,
+        }
+    }
+
+    if let Some(path) = &args.polyline {
+        let format = PolylineFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "cannot infer polyline format of '{}' (expected .csv or .svg)",
+                path.display()
+            )
+        })?;
+        let points = trajectory_to_polyline(&table, &initial, &trajectory.collisions);
+        output::write_polyline_file(path, format, &table, &points, &SvgStyle::default())?;
+        eprintln!("wrote trajectory polyline to {}", path.display());
+    }
+
+    if let Some(report) = &trajectory.invariants {
+        report_invariants(report);
+    }
+    report_termination(&trajectory.termination);
+    Ok(())
+}
+
+/// Print the worst invariant violations of a run on stderr.
+fn report_invariants(report: &InvariantReport) {
+    eprintln!(
+        "invariants over {} collisions: speed {:.3e}, boundary {:.3e}, reflection {:.3e}",
+        report.collisions,
+        report.max_speed_error,
+        report.max_boundary_error,
+        report.max_reflection_error
+    );
+    if let Some(index) = report.first_breach {
+        eprintln!("first collision beyond tolerance: {}", index);
+    }
+}
+
+/// Run a trajectory on the requested table and draw it as an SVG file.
+pub fn render(args: &RenderArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (table, initial, trajectory) = run(&args.trajectory)?;
+    let style = SvgStyle {
+        boundary_color: args.boundary_color.clone(),
+        obstacle_color: args.obstacle_color.clone(),
+        trajectory_color: args.trajectory_color.clone(),
+        boundary_width: args.boundary_width,
+        trajectory_width: args.trajectory_width,
+        background: args.background.clone(),
+    };
+    let points = trajectory_to_polyline(&table, &initial, &trajectory.collisions);
+    output::write_polyline_file(&args.output, PolylineFormat::Svg, &table, &points, &style)?;
+    eprintln!(
+        "drew {} collisions to {}",
+        trajectory.collisions.len(),
+        args.output.display()
+    );
+
+    report_termination(&trajectory.termination);
+    Ok(())
+}
+
+/// Simulate one or more trajectories and draw the density of their bounces
+/// on the Poincaré section as a PNG heatmap.
+///
+/// The first trajectory starts from the given initial state and the others
+/// from random states drawn from the invariant measure.
+pub fn heatmap(args: &HeatmapArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.trajectories == 0 {
+        return Err("--trajectories must be at least 1".into());
+    }
+    if args.width == 0 || args.height == 0 {
+        return Err("--width and --height must be at least 1".into());
+    }
+    let (table, initial, config) = setup(&args.trajectory)?;
+    let random = Sampling::Random {
+        count: args.trajectories - 1,
+    };
+    let initials =
+        std::iter::once(initial).chain(sample_boundary_states(&table, &random, args.seed));
+
+    let mut histogram = PhaseSpaceHistogram::new(args.width, args.height);
+    for (k, initial) in initials.enumerate() {
+        let trajectory = simulate_from(&table, &initial, &config, None)?;
+        histogram.add(&table, &trajectory.collisions);
+        if trajectory.termination != Termination::MaxSteps {
+            eprint!("trajectory {}: ", k);
+            report_termination(&trajectory.termination);
+        }
+    }
+
+    output::write_heatmap_png(&args.output, &histogram, args.colormap, args.log)?;
+    eprintln!(
+        "binned {} bounces into {}",
+        histogram.samples,
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// Simulate a trajectory and play it back in the terminal.
+pub fn watch(args: &WatchArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if !watch::is_interactive() {
+        return Err("watch needs an interactive terminal".into());
+    }
+    if !(args.rate.is_finite() && args.rate > 0.0) {
+        return Err("--rate must be positive".into());
+    }
+    if !(args.fps.is_finite() && args.fps > 0.0) {
+        return Err("--fps must be positive".into());
+    }
+    let size = args.cols.zip(args.rows);
+    if size.is_some_and(|(cols, rows)| cols == 0 || rows < 2) {
+        return Err("--cols must be at least 1 and --rows at least 2".into());
+    }
+    let (table, initial, trajectory) = run(&args.trajectory)?;
+    let flights = watch::flights(&table, &initial, &trajectory.collisions);
+    let options = WatchOptions {
+        rate: args.rate,
+        fps: args.fps,
+        trail: args.trail,
+        size,
+    };
+    watch::animate(&table, &flights, &options)?;
+
+    report_termination(&trajectory.termination);
+    Ok(())
+}
+
+/// Simulate a trajectory and write it as an animated GIF.
+pub fn animate(args: &AnimateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if !(args.duration.is_finite() && args.duration > 0.0) {
+        return Err("--duration must be positive".into());
+    }
+    if !(1..=50).contains(&args.fps) {
+        return Err("--fps must be between 1 and 50".into());
+    }
+    if args.width == 0 || args.height == 0 {
+        return Err("--width and --height must be at least 1".into());
+    }
+    let (table, initial, trajectory) = run(&args.trajectory)?;
+    let flights = watch::flights(&table, &initial, &trajectory.collisions);
+    let options = AnimationOptions {
+        width: args.width,
+        height: args.height,
+        fps: args.fps,
+        duration: args.duration,
+        trail: args.trail,
+    };
+    animation::write_gif_file(&args.output, &table, &flights, &options)?;
+    eprintln!(
+        "animated {} collisions in {} frames to {}",
+        trajectory.collisions.len(),
+        options.frames(),
+        args.output.display()
+    );
+
+    report_termination(&trajectory.termination);
+    Ok(())
+}
+
+/// Follow a reference trajectory across a family of tables and print its
+/// asymptotic bounces at each parameter value as CSV, or draw them as a
+/// bifurcation diagram.
+pub fn sweep(args: &SweepArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.count == 0 || args.steps == 0 {
+        return Err("--count and --steps must be at least 1".into());
+    }
+    if !(args.from.is_finite() && args.to.is_finite()) {
+        return Err("--from and --to must be finite".into());
+    }
+    if args.resolution == 0 || args.height == 0 {
+        return Err("--resolution and --height must be at least 1".into());
+    }
+
+    let family = load_family(&args.table, &args.parameter)?;
+    let table_at = |value: f64| -> Result<BilliardTable, Box<dyn std::error::Error>> {
+        let spec = family(value)?;
+        let table = build_table(&spec, 0.0, false)
+            .map_err(|e| format!("{} = {}: {}", args.parameter, value, e))?;
+        Ok(table)
+    };
+    let config = SimulationConfig::builder()
+        .max_steps(args.transient.saturating_add(args.steps))
+        .epsilon(args.epsilon)
+        .build()
+        .map_err(config_error)?;
+    let initial = BoundaryState::new(args.component, args.s, args.theta);
+    let grid = SweepGrid {
+        start: args.from,
+        end: args.to,
+        count: args.count,
+    };
+    let slices = bifurcation_diagram(
+        &grid.values(),
+        table_at,
+        &initial,
+        args.transient,
+        args.resolution,
+        &config,
+    )?;
+
+    let empty = slices.iter().filter(|s| s.points.is_empty()).count();
+    if empty > 0 {
+        eprintln!(
+            "{} of {} values gave no bounces after the transient",
+            empty,
+            slices.len()
+        );
+    }
+    match &args.plot {
+        Some(path) => {
+            output::write_bifurcation_png(path, &slices, args.height)?;
+            eprintln!(
+                "drew {} bounces at {} values to {}",
+                slices.iter().map(|s| s.points.len()).sum::<usize>(),
+                slices.len(),
+                path.display()
+            );
+        }
+        None => output::write_bifurcation_csv(std::io::stdout().lock(), &slices)?,
+    }
+    Ok(())
+}
+
+/// The table of a `bouncers sweep` family at a value of its parameter.
+type Family = Box<dyn Fn(f64) -> Result<TableSpec, String>>;
+
+/// The family of a `--table` argument, a preset or a template file, as a
+/// function of `parameter`.
+fn load_family(table: &str, parameter: &str) -> Result<Family, Box<dyn std::error::Error>> {
+    if let Some(preset) = Preset::find(table) {
+        if !preset.parameter_names().any(|name| name == parameter) {
+            return Err(format!(
+                "preset '{}' has no parameter '{}' (try {})",
+                table,
+                parameter,
+                preset.parameter_names().collect::<Vec<_>>().join(", ")
+            )
+            .into());
+        }
+        let parameter = parameter.to_string();
+        return Ok(Box::new(move |value| {
+            preset
+                .table(&HashMap::from([(parameter.clone(), value)]))
+                .map_err(|e| format!("{} = {}: {}", parameter, value, e))
+        }));
+    }
+
+    let path = Path::new(table);
+    if !path.is_file() {
+        return Err(format!("'{}' is neither a preset nor a readable file", table).into());
+    }
+    let template: TableTemplate = read_document(path)?;
+    if !template.parameters()?.contains(parameter) {
+        return Err(format!("the template has no parameter '{}'", parameter).into());
+    }
+    let parameter = parameter.to_string();
+    Ok(Box::new(move |value| {
+        template
+            .instantiate(&HashMap::from([(parameter.clone(), value)]))
+            .map_err(|e| format!("{} = {}: {}", parameter, value, e))
+    }))
+}
+
+/// Tell the user on stderr why a trajectory stopped before `--steps`.
+pub fn report_termination(termination: &Termination) {
+    match termination {
+        Termination::MaxSteps => {}
+        Termination::Corner => eprintln!("trajectory terminated at a corner"),
+        Termination::Absorbed => eprintln!("particle was absorbed by a hole"),
+        Termination::Escaped(e) => eprintln!(
+            "particle escaped at ({:.6}, {:.6}) heading ({:.6}, {:.6})",
+            e.position.x, e.position.y, e.direction.x, e.direction.y
+        ),
+        Termination::NoCollision => eprintln!("ray left the table without a collision"),
+        Termination::StopCondition => eprintln!("stop condition was met"),
+        Termination::MaxTime => eprintln!("time limit reached before --steps collisions"),
+        Termination::InvariantViolation => {
+            eprintln!("trajectory stopped at a collision breaking the invariant tolerances")
+        }
+        Termination::WhisperingGallery => {
+            eprintln!("trajectory stopped creeping along a circular arc (whispering gallery)")
+        }
+    }
+}
+
+/// Print the built-in table presets.
+pub fn list_tables() {
+    for preset in PRESETS {
+        println!("{:<16} {}", preset.name, preset.description);
+        if !preset.parameters.is_empty() {
+            let parameters: Vec<String> = preset
+                .parameters
+                .iter()
+                .map(|(name, default)| format!("{} = {}", name, default))
+                .collect();
+            println!("{:<16} parameters: {}", "", parameters.join(", "));
+        }
+    }
+}
+
+/// Validate a table file and print a short summary.
+pub fn validate(path: &Path, open: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let spec: TableSpec = read_document(path)?;
+    let table = build_table(&spec, 0.0, open)?;
+    for (i, component) in table.components().enumerate() {
+        println!(
+            "component {:<3} {:<16} {:>4} segments, length {:.6}",
+            i,
+            component.name,
+            component.segments.len(),
+            component.length()
+        );
+    }
+    println!("OK");
+    Ok(())
+}
+
+/// Build the table of `spec` as seen by the center of a ball of radius
+/// `ball_radius`, rejecting degenerate geometry and, unless `open` is set,
+/// gaps between consecutive segments.
+pub fn build_table(
+    spec: &TableSpec,
+    ball_radius: f64,
+    open: bool,
+) -> Result<BilliardTable, TableGeometryError> {
+    let spec = try_offset_for_ball(spec, ball_radius)?;
+    if open {
+        spec.try_to_open_billiard_table()
+    } else {
+        spec.try_to_billiard_table()
+    }
+}
@@ -0,0 +1,186 @@
+//! Tables, templates and run configurations read from JSON, TOML or YAML
+//! files.
+//!
+//! A run configuration, given with `--config`, holds trajectory options
+//! under the long names of their flags:
+//!
+//! ```toml
+//! table = "stadium"        # preset, path, or an inline TableSpec
+//! theta = 0.7
+//! steps = 1000
+//! corner-policy = "bisector"
+//! escape-box = [-2.0, -2.0, 2.0, 2.0]
+//! ```
+//!
+//! Errors name the file and the offending key, and unknown keys are
+//! errors rather than silently ignored.
+
+use std::path::{Path, PathBuf};
+
+use billiard_core::geometry::presets::Preset;
+use billiard_core::geometry::table_spec::TableSpec;
+use billiard_core::geometry::table_version::migrate;
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+
+use crate::cli::{CornerPolicyArg, GalleryActionArg, TrajectoryArgs, bounding_box};
+
+/// Formats of the files read here, told apart by their extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl DocumentFormat {
+    /// Format of the file at `path`: TOML for `.toml`, YAML for `.yaml` and
+    /// `.yml`, and JSON otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("toml") => DocumentFormat::Toml,
+            Some("yaml" | "yml") => DocumentFormat::Yaml,
+            _ => DocumentFormat::Json,
+        }
+    }
+}
+
+/// Read and parse the file at `path`, in the format of its extension.
+///
+/// Errors start with the path of the file and point at the offending key.
+pub fn read_document<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let data = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read '{}': {}", path.display(), e))?;
+    parse_document(&data, DocumentFormat::from_path(path))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_document<T: DeserializeOwned>(data: &str, format: DocumentFormat) -> Result<T, String> {
+    match format {
+        // TOML and YAML errors already say where they are; JSON errors only
+        // give a line and column, so track the path of the key.
+        DocumentFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_str(data);
+            let value =
+                serde_path_to_error::deserialize(&mut deserializer).map_err(|e| e.to_string())?;
+            deserializer.end().map_err(|e| e.to_string())?;
+            Ok(value)
+        }
+        DocumentFormat::Toml => toml::from_str(data).map_err(|e| e.to_string()),
+        DocumentFormat::Yaml => serde_yaml::from_str(data).map_err(|e| e.to_string()),
+    }
+}
+
+/// Trajectory options from a `--config` file; absent keys keep the
+/// command-line value or default.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RunConfig {
+    pub table: Option<TableSource>,
+    pub component: Option<usize>,
+    pub s: Option<f64>,
+    pub theta: Option<f64>,
+    pub speed: Option<f64>,
+    pub restitution: Option<f64>,
+    pub gravity: Option<f64>,
+    pub ball_radius: Option<f64>,
+    pub steps: Option<usize>,
+    pub max_time: Option<f64>,
+    pub epsilon: Option<f64>,
+    pub corner_policy: Option<CornerPolicyArg>,
+    /// `[XMIN, YMIN, XMAX, YMAX]`.
+    pub escape_box: Option<Vec<f64>>,
+    pub whispering_gallery: Option<GalleryActionArg>,
+}
+
+/// The `table` of a run configuration.
+#[derive(Debug)]
+pub enum TableSource {
+    /// A preset name or the path of a table file, relative to the
+    /// configuration file.
+    Named(String),
+    /// A table written out in the configuration itself, in any version of
+    /// the `TableSpec` format.
+    Inline(TableSpec),
+}
+
+impl<'de> Deserialize<'de> for TableSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(name) => Ok(TableSource::Named(name)),
+            spec => migrate(spec)
+                .map(TableSource::Inline)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Fill in the options of `args` from its `--config` file, if it has one,
+/// leaving those given on the command line, as recorded in `given`.
+pub fn apply_config(args: &mut TrajectoryArgs, given: &ArgMatches) -> Result<(), String> {
+    let Some(path) = args.config.clone() else {
+        return Ok(());
+    };
+    let config: RunConfig = read_document(&path)?;
+    let from_file = |id: &str| given.value_source(id) != Some(ValueSource::CommandLine);
+
+    if let Some(table) = config.table
+        && from_file("table")
+    {
+        match table {
+            TableSource::Named(name) => args.table = resolve(&path, name),
+            TableSource::Inline(spec) => args.spec = Some(spec),
+        }
+    }
+    let escape_box = config
+        .escape_box
+        .map(|coords| bounding_box(&coords))
+        .transpose()
+        .map_err(|e| format!("{}: escape-box: {}", path.display(), e))?;
+
+    // Arguments are identified by the names of their fields.
+    macro_rules! fill {
+        ($($field:ident => $value:expr),* $(,)?) => {$(
+            if let Some(value) = $value
+                && from_file(stringify!($field))
+            {
+                args.$field = value;
+            }
+        )*};
+    }
+    fill! {
+        component => config.component,
+        s => config.s,
+        theta => config.theta,
+        speed => config.speed,
+        restitution => config.restitution,
+        gravity => config.gravity.map(Some),
+        ball_radius => config.ball_radius,
+        steps => config.steps,
+        max_time => config.max_time.map(Some),
+        epsilon => config.epsilon,
+        corner_policy => config.corner_policy,
+        escape_box => escape_box.map(Some),
+        whispering_gallery => config.whispering_gallery.map(Some),
+    }
+    Ok(())
+}
+
+/// A table named in the configuration at `config`: presets stay as they
+/// are and relative paths are taken from the configuration's directory.
+fn resolve(config: &Path, name: String) -> String {
+    let path = PathBuf::from(&name);
+    if Preset::find(&name).is_some() || path.is_absolute() {
+        return name;
+    }
+    match config.parent() {
+        Some(dir) => dir.join(path).to_string_lossy().into_owned(),
+        None => name,
+    }
+}
@@ -0,0 +1,307 @@
+//! `bouncers ensemble`: aggregate statistics over many trajectories.
+//!
+//! The starting states are drawn up front from the seed, and the particles
+//! are simulated on several threads by
+//! [`simulate_particles`]. Each particle's results are pooled in particle
+//! order afterwards, so the output is the same whatever the number of
+//! threads.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use billiard_core::dynamics::config::SimulationConfig;
+use billiard_core::dynamics::ensemble::{
+    FreePathStatistics, free_path_histogram, simulate_particles,
+};
+use billiard_core::dynamics::jacobian::lyapunov_exponent;
+use billiard_core::dynamics::observer::AngleDistribution;
+use billiard_core::dynamics::sampling::{Sampling, try_sample_boundary_states};
+use billiard_core::dynamics::santalo::check_mean_free_path;
+use billiard_core::dynamics::simulation::Trajectory;
+use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::geometry::boundary::BilliardTable;
+use serde::Serialize;
+
+use crate::cli::{EnsembleArgs, Statistic};
+use crate::commands::{build_table, config_error, load_table_spec};
+use crate::output::FileFormat;
+use crate::progress;
+
+/// Statistics of an ensemble, as written by `bouncers ensemble`.
+#[derive(Debug, Serialize)]
+pub struct EnsembleReport {
+    pub table: String,
+    pub particles: usize,
+    pub steps: usize,
+    pub seed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lyapunov: Option<LyapunovSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_free_path: Option<MeanFreePathSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub angles: Option<AnglesSummary>,
+}
+
+/// Largest Lyapunov exponents per collision across the ensemble.
+#[derive(Debug, Serialize)]
+pub struct LyapunovSummary {
+    /// `None` if every particle hit a corner.
+    pub mean: Option<f64>,
+    /// Standard error of the mean, from the spread between particles.
+    pub std_error: Option<f64>,
+    /// Particles measured, and those left out for hitting a corner or
+    /// having no collision.
+    pub measured: usize,
+    pub skipped: usize,
+}
+
+/// Free paths of every particle pooled, next to Santaló's prediction.
+#[derive(Debug, Serialize)]
+pub struct MeanFreePathSummary {
+    pub mean: f64,
+    pub variance: f64,
+    pub flights: usize,
+    pub predicted: f64,
+    pub relative_error: f64,
+}
+
+/// Outgoing angle histograms of every bounce of the ensemble.
+#[derive(Debug, Serialize)]
+pub struct AnglesSummary {
+    #[serde(flatten)]
+    pub histogram: AngleDistribution,
+    pub expected_counts: Vec<f64>,
+    pub expected_cos_counts: Vec<f64>,
+}
+
+/// What was measured on one particle; `None` for statistics not asked for.
+struct Particle {
+    exponent: Option<f64>,
+    free_path: Option<FreePathStatistics>,
+    angles: Option<AngleDistribution>,
+    /// Collisions simulated.
+    bounces: usize,
+}
+
+/// Run the ensemble described by `args` and write its statistics.
+pub fn run(args: &EnsembleArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.n == 0 || args.steps == 0 {
+        return Err("--n and --steps must be at least 1".into());
+    }
+    if args.bins == 0 {
+        return Err("--bins must be at least 1".into());
+    }
+    let threads = match args.threads {
+        Some(0) => return Err("--threads must be at least 1".into()),
+        Some(threads) => threads,
+        None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    let format = match &args.output {
+        Some(path) => FileFormat::from_path(path).ok_or_else(|| {
+            format!(
+                "cannot infer output format of '{}' (expected .csv or .json)",
+                path.display()
+            )
+        })?,
+        None => FileFormat::Json,
+    };
+
+    let spec = load_table_spec(&args.table)?;
+    let table = build_table(&spec, 0.0, false)?;
+    let config = SimulationConfig::builder()
+        .max_steps(args.steps)
+        .epsilon(args.epsilon)
+        .build()
+        .map_err(config_error)?;
+    let initials =
+        try_sample_boundary_states(&table, &Sampling::Random { count: args.n }, args.seed)
+            .map_err(|e| format!("ensemble could not be run: {}", e))?;
+
+    let started = Instant::now();
+    let bar = progress::bounce_bar(
+        (args.n as u64).saturating_mul(args.steps as u64),
+        "ensemble",
+    );
+    let done = AtomicUsize::new(0);
+    let progress = |n: usize| bar.inc(n as u64);
+    let particles = simulate_particles(
+        &table,
+        &initials,
+        &config,
+        threads,
+        Some(&progress),
+        |initial, trajectory| {
+            let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+            bar.set_message(format!("{}/{} particles", done, args.n));
+            measure(&table, args, initial, trajectory)
+        },
+    );
+    bar.finish_and_clear();
+    let particles = particles.map_err(|e| format!("ensemble could not be run: {}", e))?;
+    let elapsed = started.elapsed();
+    let bounces: usize = particles.iter().map(|p| p.bounces).sum();
+    eprintln!(
+        "simulated {} particles for {} collisions on {} threads in {:.2?} ({:.0} bounces/s)",
+        args.n,
+        args.steps,
+        threads.min(args.n),
+        elapsed,
+        bounces as f64 / elapsed.as_secs_f64()
+    );
+
+    let report = summarize(&table, args, particles);
+    match &args.output {
+        Some(path) => {
+            let w = BufWriter::new(File::create(path)?);
+            write_report(w, format, &report)?;
+            eprintln!("wrote ensemble statistics to {}", path.display());
+        }
+        None => write_report(io::stdout().lock(), format, &report)?,
+    }
+    Ok(())
+}
+
+/// Measure what `args` asks on the trajectory of one particle.
+fn measure(
+    table: &BilliardTable,
+    args: &EnsembleArgs,
+    initial: &BoundaryState,
+    trajectory: &Trajectory,
+) -> Particle {
+    let wants = |statistic| args.stats.contains(&statistic);
+    let collisions = &trajectory.collisions;
+    Particle {
+        exponent: wants(Statistic::Lyapunov)
+            .then(|| lyapunov_exponent(table, initial, collisions))
+            .flatten(),
+        free_path: wants(Statistic::Mfp)
+            .then(|| free_path_histogram(table, collisions, 1, None).statistics),
+        angles: wants(Statistic::Angles)
+            .then(|| AngleDistribution::from_collisions(table, collisions, args.bins)),
+        bounces: collisions.len(),
+    }
+}
+
+/// Pool the measurements of the particles, in order.
+fn summarize(
+    table: &BilliardTable,
+    args: &EnsembleArgs,
+    particles: Vec<Particle>,
+) -> EnsembleReport {
+    let wants = |statistic| args.stats.contains(&statistic);
+
+    let exponents: Vec<f64> = particles.iter().filter_map(|p| p.exponent).collect();
+    let lyapunov = wants(Statistic::Lyapunov).then(|| {
+        let n = exponents.len() as f64;
+        let mean = (!exponents.is_empty()).then(|| exponents.iter().sum::<f64>() / n);
+        let std_error = mean.filter(|_| exponents.len() > 1).map(|mean| {
+            let variance = exponents
+                .iter()
+                .map(|x| (x - mean) * (x - mean))
+                .sum::<f64>()
+                / (n - 1.0);
+            (variance / n).sqrt()
+        });
+        LyapunovSummary {
+            mean,
+            std_error,
+            measured: exponents.len(),
+            skipped: args.n - exponents.len(),
+        }
+    });
+
+    let mean_free_path = wants(Statistic::Mfp).then(|| {
+        let none = FreePathStatistics {
+            mean: 0.0,
+            variance: 0.0,
+            flights: 0,
+        };
+        let statistics = particles
+            .iter()
+            .filter_map(|p| p.free_path.as_ref())
+            .fold(none, |pooled, s| pooled.combine(s));
+        let check = check_mean_free_path(table, &statistics);
+        MeanFreePathSummary {
+            mean: statistics.mean,
+            variance: statistics.variance,
+            flights: statistics.flights,
+            predicted: check.predicted,
+            relative_error: check.relative_error,
+        }
+    });
+
+    let angles = wants(Statistic::Angles).then(|| {
+        let mut histogram = AngleDistribution::new(args.bins);
+        for angles in particles.iter().filter_map(|p| p.angles.as_ref()) {
+            histogram.merge(angles);
+        }
+        AnglesSummary {
+            expected_counts: histogram.expected_counts(),
+            expected_cos_counts: histogram.expected_cos_counts(),
+            histogram,
+        }
+    });
+
+    EnsembleReport {
+        table: args.table.clone(),
+        particles: args.n,
+        steps: args.steps,
+        seed: args.seed,
+        lyapunov,
+        mean_free_path,
+        angles,
+    }
+}
+
+/// Write `report` as pretty JSON, or as CSV rows
+/// `statistic,quantity,value`.
+fn write_report<W: Write>(mut w: W, format: FileFormat, report: &EnsembleReport) -> io::Result<()> {
+    if format == FileFormat::Json {
+        serde_json::to_writer_pretty(&mut w, report)?;
+        writeln!(w)?;
+        return w.flush();
+    }
+
+    let optional = |x: Option<f64>| x.map_or(String::new(), |x| x.to_string());
+    writeln!(w, "statistic,quantity,value")?;
+    writeln!(w, "ensemble,particles,{}", report.particles)?;
+    writeln!(w, "ensemble,steps,{}", report.steps)?;
+    writeln!(w, "ensemble,seed,{}", report.seed)?;
+    if let Some(l) = &report.lyapunov {
+        writeln!(w, "lyapunov,mean,{}", optional(l.mean))?;
+        writeln!(w, "lyapunov,std_error,{}", optional(l.std_error))?;
+        writeln!(w, "lyapunov,measured,{}", l.measured)?;
+        writeln!(w, "lyapunov,skipped,{}", l.skipped)?;
+    }
+    if let Some(m) = &report.mean_free_path {
+        writeln!(w, "mean_free_path,mean,{}", m.mean)?;
+        writeln!(w, "mean_free_path,variance,{}", m.variance)?;
+        writeln!(w, "mean_free_path,flights,{}", m.flights)?;
+        writeln!(w, "mean_free_path,predicted,{}", m.predicted)?;
+        writeln!(w, "mean_free_path,relative_error,{}", m.relative_error)?;
+    }
+    if let Some(a) = &report.angles {
+        writeln!(w, "angles,samples,{}", a.histogram.samples)?;
+        let columns = [
+            ("angle_count", &a.histogram.counts),
+            ("cos_count", &a.histogram.cos_counts),
+        ];
+        for (name, counts) in columns {
+            for (k, count) in counts.iter().enumerate() {
+                writeln!(w, "angles,{}_{},{}", name, k, count)?;
+            }
+        }
+        let columns = [
+            ("angle_expected", &a.expected_counts),
+            ("cos_expected", &a.expected_cos_counts),
+        ];
+        for (name, expected) in columns {
+            for (k, expected) in expected.iter().enumerate() {
+                writeln!(w, "angles,{}_{},{}", name, k, expected)?;
+            }
+        }
+    }
+    w.flush()
+}
@@ -1,8 +1,53 @@
+mod animation;
+mod cli;
+mod commands;
+mod config;
 mod demo_tables;
-mod demos;
+mod ensemble;
+mod output;
+mod png;
+mod progress;
+mod repl;
+mod watch;
+
+use std::process::ExitCode;
+
+use clap::{CommandFactory, FromArgMatches};
+
+use crate::cli::{Cli, Command, TablesCommand};
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // Displayed rather than debug-printed, so that multi-line
+            // errors (a TOML error under its source line) read as written.
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // `--config` fills in the options that were not given explicitly.
+    if let (Some(args), Some((_, given))) = (cli.command.trajectory_mut(), matches.subcommand()) {
+        config::apply_config(args, given)?;
+    }
+
+    match cli.command {
+        Command::Simulate(args) => commands::simulate(&args)?,
+        Command::Render(args) => commands::render(&args)?,
+        Command::Heatmap(args) => commands::heatmap(&args)?,
+        Command::Watch(args) => commands::watch(&args)?,
+        Command::Animate(args) => commands::animate(&args)?,
+        Command::Sweep(args) => commands::sweep(&args)?,
+        Command::Ensemble(args) => ensemble::run(&args)?,
+        Command::Repl(args) => repl::run(&args)?,
+        Command::Tables(TablesCommand::List) => commands::list_tables(),
+        Command::Validate { path, open } => commands::validate(&path, open)?,
+    }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // For now, just run a hard-coded demo.
-    demos::run_sinai_demo()?;
     Ok(())
 }
@@ -0,0 +1,490 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use billiard_core::dynamics::lattice::UnfoldedCollision;
+use billiard_core::dynamics::phase_space::PhaseSpaceHistogram;
+use billiard_core::dynamics::sweep::BifurcationSlice;
+use billiard_core::geometry::boundary::BilliardTable;
+use billiard_core::geometry::primitives::Vec2;
+use serde::Serialize;
+
+use crate::cli::Colormap;
+use crate::png;
+
+/// Largest distance between a drawn boundary and the true one, relative to
+/// the size of the picture.
+const SVG_RELATIVE_CHORD_ERROR: f64 = 1e-3;
+
+/// Blank space around an SVG drawing, relative to its size.
+const SVG_MARGIN: f64 = 0.05;
+
+/// Print collisions as aligned columns, color-coded by component.
+///
+/// The unfolded position is shown as two extra columns once the trajectory
+/// has crossed a periodic edge, and segments named in the table spec have
+/// their name at the end of the line.
+pub fn print_pretty(table: &BilliardTable, collisions: &[UnfoldedCollision]) {
+    let lattice = collisions.iter().any(|u| u.periodic_pass);
+    print!(
+        "{:<6} {:<6} {:<8} {:>10} {:>12} {:>12} {:>12}",
+        "step", "comp", "seg", "s", "theta", "x", "y"
+    );
+    if lattice {
+        print!(" {:>12} {:>12}", "abs_x", "abs_y");
+    }
+    println!();
+
+    for (step, u) in collisions.iter().enumerate() {
+        let c = &u.collision;
+        // Color coding:
+        // - Outer boundary (component 0) = bright white (default)
+        // - Internal obstacles           = bright cyan
+        let color = if c.component_index == 0 {
+            "\x1b[97m" // bright white
+        } else {
+            "\x1b[96m" // bright cyan
+        };
+        let reset = "\x1b[0m";
+
+        print!(
+            "{}{:<6} {:<6} {:<8} {:>10.6} {:>12.6} {:>12.6} {:>12.6}",
+            color,
+            step,
+            c.component_index,
+            c.segment_index,
+            c.s,
+            c.theta,
+            c.hit_point.x,
+            c.hit_point.y,
+        );
+        if lattice {
+            print!(
+                " {:>12.6} {:>12.6}",
+                u.absolute_position.x, u.absolute_position.y
+            );
+        }
+        if let Some(name) = c.segment_name(table) {
+            print!("  {}", name);
+        }
+        println!("{}", reset);
+    }
+}
+
+/// Machine-readable collision record for CSV and JSON output.
+///
+/// `segment_id` and `segment_name` are what the table spec calls the
+/// segment; they are only written to JSON.
+#[derive(Debug, Serialize)]
+pub struct CollisionRecord {
+    pub step: usize,
+    pub component: usize,
+    pub segment: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_name: Option<String>,
+    pub s: f64,
+    pub theta: f64,
+    pub speed: f64,
+    pub x: f64,
+    pub y: f64,
+    pub abs_x: f64,
+    pub abs_y: f64,
+    pub path_length: f64,
+    pub time: f64,
+}
+
+impl CollisionRecord {
+    pub fn from_core(step: usize, u: &UnfoldedCollision, table: &BilliardTable) -> Self {
+        let c = &u.collision;
+        CollisionRecord {
+            step,
+            component: c.component_index,
+            segment: c.segment_index,
+            segment_id: c.segment_id,
+            segment_name: c.segment_name(table).map(str::to_string),
+            s: c.s,
+            theta: c.theta,
+            speed: c.speed,
+            x: c.hit_point.x,
+            y: c.hit_point.y,
+            abs_x: u.absolute_position.x,
+            abs_y: u.absolute_position.y,
+            path_length: c.path_length,
+            time: c.time,
+        }
+    }
+}
+
+/// File formats accepted by `--output`, chosen by file extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    Csv,
+    Json,
+}
+
+impl FileFormat {
+    /// Infer the format from the extension of `path` (`.csv` or `.json`).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(FileFormat::Csv),
+            "json" => Some(FileFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Write collisions as CSV with a header row.
+pub fn write_csv<W: Write>(
+    mut w: W,
+    table: &BilliardTable,
+    collisions: &[UnfoldedCollision],
+) -> io::Result<()> {
+    writeln!(
+        w,
+        "step,component,segment,s,theta,speed,x,y,abs_x,abs_y,path_length,time"
+    )?;
+    for (step, c) in collisions.iter().enumerate() {
+        let r = CollisionRecord::from_core(step, c, table);
+        writeln!(
+            w,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            r.step,
+            r.component,
+            r.segment,
+            r.s,
+            r.theta,
+            r.speed,
+            r.x,
+            r.y,
+            r.abs_x,
+            r.abs_y,
+            r.path_length,
+            r.time
+        )?;
+    }
+    Ok(())
+}
+
+/// Write collisions as a pretty-printed JSON array of records.
+pub fn write_json<W: Write>(
+    mut w: W,
+    table: &BilliardTable,
+    collisions: &[UnfoldedCollision],
+) -> io::Result<()> {
+    let records: Vec<CollisionRecord> = collisions
+        .iter()
+        .enumerate()
+        .map(|(step, c)| CollisionRecord::from_core(step, c, table))
+        .collect();
+    serde_json::to_writer_pretty(&mut w, &records)?;
+    writeln!(w)
+}
+
+/// File formats accepted by `--polyline`, chosen by file extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolylineFormat {
+    Csv,
+    Svg,
+}
+
+impl PolylineFormat {
+    /// Infer the format from the extension of `path` (`.csv` or `.svg`).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(PolylineFormat::Csv),
+            "svg" => Some(PolylineFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+/// Write the vertices of a trajectory polyline as CSV with a header row.
+pub fn write_polyline_csv<W: Write>(mut w: W, points: &[Vec2]) -> io::Result<()> {
+    writeln!(w, "x,y")?;
+    for p in points {
+        writeln!(w, "{},{}", p.x, p.y)?;
+    }
+    Ok(())
+}
+
+/// Colors and stroke widths of an SVG drawing. Colors are written into
+/// the SVG as given; widths are in pixels, whatever the zoom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgStyle {
+    pub boundary_color: String,
+    pub obstacle_color: String,
+    pub trajectory_color: String,
+    pub boundary_width: f64,
+    pub trajectory_width: f64,
+
+    /// Background fill; transparent if `None`.
+    pub background: Option<String>,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle {
+            boundary_color: "black".to_string(),
+            obstacle_color: "dimgray".to_string(),
+            trajectory_color: "steelblue".to_string(),
+            boundary_width: 2.0,
+            trajectory_width: 1.0,
+            background: None,
+        }
+    }
+}
+
+/// Outlines of the components of `table`, within `relative_chord_error`
+/// of the size of the table, and the lower-left and upper-right corners
+/// of the box around them.
+pub fn table_outlines(
+    table: &BilliardTable,
+    relative_chord_error: f64,
+) -> (Vec<Vec<Vec2>>, Vec2, Vec2) {
+    let extent = |points: &mut dyn Iterator<Item = Vec2>| {
+        points.fold(
+            (
+                Vec2::new(f64::INFINITY, f64::INFINITY),
+                Vec2::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), p| {
+                (
+                    Vec2::new(min.x.min(p.x), min.y.min(p.y)),
+                    Vec2::new(max.x.max(p.x), max.y.max(p.y)),
+                )
+            },
+        )
+    };
+
+    // Size the tolerance from a coarse outline of the table, then fit the
+    // box to the finer one.
+    let (min, max) = extent(
+        &mut table
+            .components()
+            .flat_map(|c| c.segments.iter().map(|s| s.point_at(0.0))),
+    );
+    let size = (max.x - min.x).max(max.y - min.y).max(f64::MIN_POSITIVE);
+    let outlines: Vec<Vec<Vec2>> = table
+        .components()
+        .map(|c| c.discretize(relative_chord_error * size))
+        .collect();
+    let (min, max) = extent(&mut outlines.iter().flatten().copied());
+    (outlines, min, max)
+}
+
+/// Draw the boundary of `table` and a trajectory polyline as an SVG image,
+/// with `y` pointing up.
+pub fn write_polyline_svg<W: Write>(
+    mut w: W,
+    table: &BilliardTable,
+    points: &[Vec2],
+    style: &SvgStyle,
+) -> io::Result<()> {
+    let extent = |points: &[Vec2]| {
+        points.iter().fold(
+            (
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(x0, y0, x1, y1), p| (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y)),
+        )
+    };
+
+    // Size the boundary tolerance from a coarse outline of the table.
+    let coarse: Vec<Vec2> = table
+        .components()
+        .flat_map(|c| c.segments.iter().map(|s| s.point_at(0.0)))
+        .chain(points.iter().copied())
+        .collect();
+    let (x0, y0, x1, y1) = extent(&coarse);
+    let size = (x1 - x0).max(y1 - y0).max(f64::MIN_POSITIVE);
+    let boundaries: Vec<Vec<Vec2>> = table
+        .components()
+        .map(|c| c.discretize(SVG_RELATIVE_CHORD_ERROR * size))
+        .collect();
+
+    let all: Vec<Vec2> = boundaries.iter().flatten().chain(points).copied().collect();
+    let (x0, y0, x1, y1) = extent(&all);
+    let margin = SVG_MARGIN * (x1 - x0).max(y1 - y0).max(f64::MIN_POSITIVE);
+    let coordinates = |points: &[Vec2]| {
+        points
+            .iter()
+            .map(|p| format!("{},{}", p.x, p.y))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let (left, top) = (x0 - margin, -y1 - margin);
+    let (width, height) = (x1 - x0 + 2.0 * margin, y1 - y0 + 2.0 * margin);
+    writeln!(
+        w,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        left, top, width, height
+    )?;
+    if let Some(background) = &style.background {
+        writeln!(
+            w,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            left, top, width, height, background
+        )?;
+    }
+    writeln!(w, r#"  <g transform="scale(1,-1)" fill="none">"#)?;
+    for (i, boundary) in boundaries.iter().enumerate() {
+        let color = if i == 0 {
+            &style.boundary_color
+        } else {
+            &style.obstacle_color
+        };
+        writeln!(
+            w,
+            r#"    <polygon points="{}" stroke="{}" stroke-width="{}" vector-effect="non-scaling-stroke"/>"#,
+            coordinates(boundary),
+            color,
+            style.boundary_width
+        )?;
+    }
+    writeln!(
+        w,
+        r#"    <polyline points="{}" stroke="{}" stroke-width="{}" vector-effect="non-scaling-stroke"/>"#,
+        coordinates(points),
+        style.trajectory_color,
+        style.trajectory_width
+    )?;
+    writeln!(w, "  </g>")?;
+    writeln!(w, "</svg>")
+}
+
+/// Write a trajectory polyline on `table` to `path` in the given format;
+/// `style` is used for SVG drawings.
+pub fn write_polyline_file(
+    path: &Path,
+    format: PolylineFormat,
+    table: &BilliardTable,
+    points: &[Vec2],
+    style: &SvgStyle,
+) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    match format {
+        PolylineFormat::Csv => write_polyline_csv(&mut w, points)?,
+        PolylineFormat::Svg => write_polyline_svg(&mut w, table, points, style)?,
+    }
+    w.flush()
+}
+
+/// Write collisions to `path` in the given file format.
+pub fn write_file(
+    path: &Path,
+    format: FileFormat,
+    table: &BilliardTable,
+    collisions: &[UnfoldedCollision],
+) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    match format {
+        FileFormat::Csv => write_csv(&mut w, table, collisions)?,
+        FileFormat::Json => write_json(&mut w, table, collisions)?,
+    }
+    w.flush()
+}
+
+/// Evenly spaced stops of each colormap, from 0 to 1.
+fn colormap_stops(colormap: Colormap) -> &'static [[u8; 3]] {
+    match colormap {
+        Colormap::Viridis => &[
+            [68, 1, 84],
+            [59, 82, 139],
+            [33, 145, 140],
+            [94, 201, 98],
+            [253, 231, 37],
+        ],
+        Colormap::Magma => &[
+            [0, 0, 4],
+            [59, 15, 112],
+            [140, 41, 129],
+            [222, 73, 104],
+            [254, 159, 109],
+            [252, 253, 191],
+        ],
+        Colormap::Hot => &[[0, 0, 0], [255, 0, 0], [255, 255, 0], [255, 255, 255]],
+        Colormap::Gray => &[[0, 0, 0], [255, 255, 255]],
+    }
+}
+
+/// Color of `t` in `[0, 1]` on `colormap`, interpolating linearly between
+/// its stops.
+pub fn colormap_rgb(colormap: Colormap, t: f64) -> [u8; 3] {
+    let stops = colormap_stops(colormap);
+    let x = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let k = (x as usize).min(stops.len() - 2);
+    let f = x - k as f64;
+    std::array::from_fn(|c| {
+        let (a, b) = (stops[k][c] as f64, stops[k + 1][c] as f64);
+        (a + (b - a) * f).round() as u8
+    })
+}
+
+/// Draw `histogram` as a PNG with one pixel per cell: arc length to the
+/// right and `cos θ` upwards. Counts are scaled to the fullest cell,
+/// through `ln(1 + count)` if `log` is set.
+pub fn write_heatmap_png(
+    path: &Path,
+    histogram: &PhaseSpaceHistogram,
+    colormap: Colormap,
+    log: bool,
+) -> io::Result<()> {
+    let scale = |count: usize| {
+        if log {
+            (count as f64).ln_1p()
+        } else {
+            count as f64
+        }
+    };
+    let max = scale(histogram.max_count()).max(f64::MIN_POSITIVE);
+
+    let (width, height) = (histogram.s_bins, histogram.p_bins);
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in 0..height {
+        let j = height - 1 - row;
+        for i in 0..width {
+            pixels.push(colormap_rgb(colormap, scale(histogram.count(i, j)) / max));
+        }
+    }
+    png::write_rgb(BufWriter::new(File::create(path)?), width, height, &pixels)
+}
+
+/// Write the bounces of a bifurcation diagram as CSV with a header row, one
+/// line per bounce.
+pub fn write_bifurcation_csv<W: Write>(mut w: W, slices: &[BifurcationSlice]) -> io::Result<()> {
+    writeln!(w, "value,s,cos_theta")?;
+    for slice in slices {
+        for (s, p) in &slice.points {
+            writeln!(w, "{},{},{}", slice.value, s, p)?;
+        }
+    }
+    Ok(())
+}
+
+/// Draw a bifurcation diagram as a PNG with one column per slice, left to
+/// right, and `cos θ` from -1 at the bottom to 1 at the top: black where a
+/// bounce lands, white elsewhere.
+///
+/// # Panics
+/// Panics if `slices` is empty or `height` is zero.
+pub fn write_bifurcation_png(
+    path: &Path,
+    slices: &[BifurcationSlice],
+    height: usize,
+) -> io::Result<()> {
+    let width = slices.len();
+    let mut pixels = vec![[255; 3]; width * height];
+    for (i, slice) in slices.iter().enumerate() {
+        for &(_, p) in &slice.points {
+            let row = ((1.0 - p) / 2.0 * height as f64) as usize;
+            pixels[row.min(height - 1) * width + i] = [0; 3];
+        }
+    }
+    png::write_rgb(BufWriter::new(File::create(path)?), width, height, &pixels)
+}
@@ -0,0 +1,68 @@
+//! A minimal PNG encoder for 8-bit RGB images.
+//!
+//! Enough for the heatmaps the CLI draws: one IHDR, one zlib-compressed
+//! IDAT with no row filtering, and IEND.
+
+use std::io::{self, Write};
+
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Bit depth 8, color type 2 (RGB), default compression and filtering, no
+/// interlacing.
+const RGB8: [u8; 5] = [8, 2, 0, 0, 0];
+
+/// Write `pixels`, row by row from the top, as a `width × height` PNG.
+///
+/// # Panics
+/// Panics if `pixels` does not hold `width * height` pixels, or if either
+/// dimension is zero or does not fit in 31 bits.
+pub fn write_rgb<W: Write>(
+    mut w: W,
+    width: usize,
+    height: usize,
+    pixels: &[[u8; 3]],
+) -> io::Result<()> {
+    assert_eq!(pixels.len(), width * height, "Pixel count does not match.");
+    let dimension = |n: usize| {
+        u32::try_from(n)
+            .ok()
+            .filter(|&n| n > 0 && n < 1 << 31)
+            .expect("PNG dimensions must be between 1 and 2³¹ - 1.")
+    };
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(dimension(width).to_be_bytes());
+    header.extend(dimension(height).to_be_bytes());
+    header.extend(RGB8);
+
+    // Each row starts with its filter type, 0 (none).
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in pixels.chunks(width) {
+        encoder.write_all(&[0])?;
+        encoder.write_all(row.as_flattened())?;
+    }
+    let data = encoder.finish()?;
+
+    w.write_all(&SIGNATURE)?;
+    write_chunk(&mut w, b"IHDR", &header)?;
+    write_chunk(&mut w, b"IDAT", &data)?;
+    write_chunk(&mut w, b"IEND", &[])?;
+    w.flush()
+}
+
+/// Length, type, data and CRC of the type and data.
+fn write_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let length = u32::try_from(data.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "PNG chunk too large"))?;
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+
+    w.write_all(&length.to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    w.write_all(&crc.finalize().to_be_bytes())
+}
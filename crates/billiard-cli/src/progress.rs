@@ -0,0 +1,28 @@
+//! Progress bars with throughput and ETA on stderr, for long runs.
+
+use std::fmt::Write;
+
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+
+/// Runs of fewer bounces than this finish too soon for a bar to help.
+const MIN_BOUNCES: u64 = 200_000;
+
+/// A bar counting up to `total` bounces, hidden for short runs and when
+/// stderr is not a terminal. `label` is shown in front of it, and any
+/// message after it.
+pub fn bounce_bar(total: u64, label: &str) -> ProgressBar {
+    if total < MIN_BOUNCES {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template(
+        "{prefix} [{wide_bar}] {human_pos}/{human_len} bounces  {rate}  ETA {eta}  {msg}",
+    )
+    .expect("the progress template is valid")
+    .with_key("rate", |state: &ProgressState, w: &mut dyn Write| {
+        let _ = write!(w, "{:.0}/s", state.per_sec());
+    })
+    .progress_chars("=> ");
+    ProgressBar::new(total)
+        .with_style(style)
+        .with_prefix(label.to_string())
+}
@@ -0,0 +1,275 @@
+//! `bouncers repl`: a table kept loaded while the user steps a trajectory
+//! and changes its state one command at a time.
+//!
+//! Commands are read a line at a time from stdin, so a session can also be
+//! scripted, `bouncers repl --table stadium < commands.txt`; the prompt is
+//! only shown on a terminal.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use billiard_core::dynamics::config::SimulationConfig;
+use billiard_core::dynamics::lattice::{trajectory_to_polyline, unfold_trajectory};
+use billiard_core::dynamics::simulation::{BilliardMap, CollisionResult, Termination};
+use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::geometry::boundary::BilliardTable;
+
+use crate::cli::ReplArgs;
+use crate::commands::{build_table, load_table_spec, report_termination, setup};
+use crate::output::{self, FileFormat, PolylineFormat, SvgStyle};
+
+const HELP: &str = "\
+commands:
+  step [N]                   simulate N collisions (default 1) from the current state
+  point                      print the current phase point
+  state COMPONENT S THETA    start a new trajectory from this state
+  component N | s X | theta X | speed X
+                             change one coordinate and start a new trajectory
+  reset                      go back to the start of the trajectory
+  table NAME                 load a preset or table file, keeping the state
+  export PATH                write the trajectory so far to .csv or .json
+                             (collisions) or .svg (drawing)
+  help                       show this message
+  quit                       leave (also Ctrl-D)";
+
+/// The loaded table and the trajectory being stepped on it.
+struct Session {
+    table: BilliardTable,
+    config: SimulationConfig,
+    /// Ball radius and openness the table was built with, for `table`.
+    ball_radius: f64,
+    open: bool,
+    /// Where the trajectory started.
+    start: BoundaryState,
+    /// Collisions since the start, with running totals over all of them.
+    collisions: Vec<CollisionResult>,
+}
+
+/// Run the read-eval-print loop until `quit` or the end of input.
+pub fn run(args: &ReplArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (table, start, config) = setup(&args.trajectory)?;
+    let mut session = Session {
+        table,
+        config,
+        ball_radius: args.trajectory.ball_radius,
+        open: args.trajectory.escape_box.is_some(),
+        start,
+        collisions: Vec::new(),
+    };
+
+    let interactive = io::stdin().is_terminal();
+    if interactive {
+        println!(
+            "table with {} components loaded; type `help` for commands",
+            session.table.component_count()
+        );
+    }
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] => {}
+            ["quit" | "exit"] => break,
+            _ => {
+                if let Err(e) = session.execute(&words) {
+                    eprintln!("error: {}", e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Session {
+    /// The state the particle is in now: leaving its last collision, or at
+    /// the start.
+    fn state(&self) -> BoundaryState {
+        self.collisions
+            .last()
+            .map_or(self.start, CollisionResult::outgoing_state)
+    }
+
+    fn execute(&mut self, words: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+        match *words {
+            ["help"] => println!("{}", HELP),
+            ["step"] => self.step(1)?,
+            ["step", n] => self.step(parse(n, "N")?)?,
+            ["point"] => self.print_point()?,
+            ["state", component, s, theta] => {
+                let state = BoundaryState {
+                    component_index: parse(component, "COMPONENT")?,
+                    s: parse(s, "S")?,
+                    theta: parse(theta, "THETA")?,
+                    speed: self.state().speed,
+                };
+                self.restart(state)?;
+            }
+            ["component", n] => self.restart(BoundaryState {
+                component_index: parse(n, "N")?,
+                ..self.state()
+            })?,
+            ["s", x] => self.restart(BoundaryState {
+                s: parse(x, "X")?,
+                ..self.state()
+            })?,
+            ["theta", x] => self.restart(BoundaryState {
+                theta: parse(x, "X")?,
+                ..self.state()
+            })?,
+            ["speed", x] => {
+                let speed: f64 = parse(x, "X")?;
+                if !(speed.is_finite() && speed > 0.0) {
+                    return Err("speed must be positive and finite".into());
+                }
+                self.restart(BoundaryState {
+                    speed,
+                    ..self.state()
+                })?
+            }
+            ["reset"] => {
+                self.collisions.clear();
+                self.print_point()?;
+            }
+            ["table", name] => self.load_table(name)?,
+            ["export", path] => self.export(Path::new(path))?,
+            _ => {
+                return Err(format!(
+                    "unknown command '{}'; type `help` for commands",
+                    words.join(" ")
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Simulate `n` more collisions and print them.
+    fn step(&mut self, n: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let (path_length, time) = self
+            .collisions
+            .last()
+            .map_or((0.0, 0.0), |c| (c.path_length, c.departure_time()));
+        let mut map = BilliardMap::from_config(&self.table, &self.config).starting_at(time);
+        let trajectory = map.simulate(&self.state(), n)?;
+
+        let first = self.collisions.len();
+        self.collisions.extend(
+            trajectory
+                .collisions
+                .iter()
+                .map(|c| c.after(path_length, time)),
+        );
+        for (step, c) in self.collisions.iter().enumerate().skip(first) {
+            print!(
+                "{:<6} comp {:<3} seg {:<4} s {:>10.6}  theta {:>9.6}  at ({:.6}, {:.6})  t {:.6}",
+                step,
+                c.component_index,
+                c.segment_index,
+                c.s,
+                c.theta,
+                c.hit_point.x,
+                c.hit_point.y,
+                c.time
+            );
+            match c.segment_name(&self.table) {
+                Some(name) => println!("  {}", name),
+                None => println!(),
+            }
+        }
+        if trajectory.termination != Termination::MaxSteps {
+            report_termination(&trajectory.termination);
+        }
+        Ok(())
+    }
+
+    /// Print where the particle is on the boundary, in phase space and in
+    /// the table.
+    fn print_point(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let state = self.state();
+        let world = state.try_to_world(&self.table)?;
+        println!(
+            "after {} collisions: component {}, s = {:.9}, theta = {:.9} (cos θ = {:.9}), speed {:.6}",
+            self.collisions.len(),
+            state.component_index,
+            state.s,
+            state.theta,
+            state.theta.cos(),
+            state.speed
+        );
+        println!(
+            "at ({:.9}, {:.9}) heading ({:.9}, {:.9})",
+            world.position.x, world.position.y, world.direction.x, world.direction.y
+        );
+        Ok(())
+    }
+
+    /// Start a new trajectory from `state`.
+    fn restart(&mut self, state: BoundaryState) -> Result<(), Box<dyn std::error::Error>> {
+        state.check(&self.table)?;
+        self.start = state;
+        self.collisions.clear();
+        self.print_point()
+    }
+
+    /// Replace the table, starting over from the current state.
+    fn load_table(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let spec = load_table_spec(name)?;
+        let table = build_table(&spec, self.ball_radius, self.open)?;
+        let state = self.state();
+        state.check(&table)?;
+
+        self.table = table;
+        self.start = state;
+        self.collisions.clear();
+        println!(
+            "table with {} components loaded",
+            self.table.component_count()
+        );
+        Ok(())
+    }
+
+    /// Write the collisions since the start to `path`, or draw them.
+    fn export(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(format) = FileFormat::from_path(path) {
+            let collisions = unfold_trajectory(&self.table, &self.collisions);
+            output::write_file(path, format, &self.table, &collisions)?;
+        } else if let Some(PolylineFormat::Svg) = PolylineFormat::from_path(path) {
+            let points = trajectory_to_polyline(&self.table, &self.start, &self.collisions);
+            output::write_polyline_file(
+                path,
+                PolylineFormat::Svg,
+                &self.table,
+                &points,
+                &SvgStyle::default(),
+            )?;
+        } else {
+            return Err(format!(
+                "cannot infer export format of '{}' (expected .csv, .json or .svg)",
+                path.display()
+            )
+            .into());
+        }
+        println!(
+            "wrote {} collisions to {}",
+            self.collisions.len(),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Parse the argument `word` named `name` in the help.
+fn parse<T: std::str::FromStr>(word: &str, name: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    word.parse()
+        .map_err(|e| format!("{} '{}': {}", name, word, e))
+}
@@ -0,0 +1,337 @@
+//! Live animation of a trajectory in the terminal.
+//!
+//! The table and the recent path are drawn with braille characters, each
+//! cell holding a 2 × 4 grid of dots, using plain ANSI escapes. Keys are
+//! read with the terminal switched to non-canonical mode through `stty`,
+//! so this needs a Unix terminal but no extra dependencies.
+
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use billiard_core::dynamics::simulation::CollisionResult;
+use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::geometry::boundary::BilliardTable;
+use billiard_core::geometry::primitives::Vec2;
+
+use crate::output::table_outlines;
+
+/// Bit of each dot of a braille cell, by column and row.
+const BRAILLE_BITS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// Largest gap between a drawn boundary and the true one, relative to the
+/// size of the table; well under a dot on any terminal.
+const BOUNDARY_RELATIVE_CHORD_ERROR: f64 = 1e-3;
+
+/// Terminal size used when it cannot be read.
+const FALLBACK_SIZE: (usize, usize) = (80, 24);
+
+/// One straight flight of the particle, in the table, from leaving one
+/// wall to reaching the next.
+#[derive(Clone, Copy, Debug)]
+pub struct Flight {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Flight {
+    /// Where the particle is at time `t`; at `from` while it waits at the
+    /// wall before `start`.
+    pub fn position_at(&self, t: f64) -> Vec2 {
+        let duration = self.end - self.start;
+        let f = if duration > 0.0 {
+            ((t - self.start) / duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        self.from + (self.to - self.from) * f
+    }
+}
+
+/// The flights of a trajectory from `initial`. After a pass through a
+/// periodic edge the next flight starts on the paired edge, so every
+/// flight stays in the table.
+pub fn flights(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    collisions: &[CollisionResult],
+) -> Vec<Flight> {
+    let mut from = initial.to_world(table).position;
+    let mut start = 0.0;
+    collisions
+        .iter()
+        .map(|c| {
+            let flight = Flight {
+                from,
+                to: c.hit_point,
+                start,
+                end: c.time,
+            };
+            from = c.outgoing_state().to_world(table).position;
+            start = c.departure_time();
+            flight
+        })
+        .collect()
+}
+
+/// How the animation runs.
+#[derive(Clone, Copy, Debug)]
+pub struct WatchOptions {
+    /// Simulated time per second of real time.
+    pub rate: f64,
+    pub fps: f64,
+
+    /// Flights drawn behind the ball.
+    pub trail: usize,
+
+    /// Size of the drawing in characters; the terminal size if `None`.
+    pub size: Option<(usize, usize)>,
+}
+
+/// A braille drawing with two layers of dots: the boundary and the trail.
+struct Canvas {
+    cols: usize,
+    rows: usize,
+    origin: Vec2,
+    scale: f64,
+    boundary: Vec<u8>,
+    trail: Vec<u8>,
+}
+
+impl Canvas {
+    /// A canvas of `cols × rows` characters fitting the box from `min` to
+    /// `max`, with square dots and `y` pointing up.
+    fn new(cols: usize, rows: usize, min: Vec2, max: Vec2) -> Self {
+        let (w, h) = ((max.x - min.x).max(1e-12), (max.y - min.y).max(1e-12));
+        let scale = ((2 * cols - 1) as f64 / w).min((4 * rows - 1) as f64 / h);
+        // Center the drawing.
+        let pad = Vec2::new(
+            ((2 * cols - 1) as f64 / scale - w) / 2.0,
+            ((4 * rows - 1) as f64 / scale - h) / 2.0,
+        );
+        Canvas {
+            cols,
+            rows,
+            origin: Vec2::new(min.x - pad.x, max.y + pad.y),
+            scale,
+            boundary: vec![0; cols * rows],
+            trail: vec![0; cols * rows],
+        }
+    }
+
+    /// Dot coordinates of `p`, if it is on the canvas.
+    fn dot(&self, p: Vec2) -> Option<(usize, usize)> {
+        let x = ((p.x - self.origin.x) * self.scale).round();
+        let y = ((self.origin.y - p.y) * self.scale).round();
+        let inside = x >= 0.0 && y >= 0.0 && x < (2 * self.cols) as f64;
+        (inside && y < (4 * self.rows) as f64).then_some((x as usize, y as usize))
+    }
+
+    /// Cell index of `p`, if it is on the canvas.
+    fn cell(&self, p: Vec2) -> Option<usize> {
+        self.dot(p).map(|(x, y)| (y / 4) * self.cols + x / 2)
+    }
+
+    /// Set the dots along the segment from `a` to `b` in `layer`.
+    fn line(&self, layer: &mut [u8], a: Vec2, b: Vec2) {
+        let steps = ((b - a).length() * self.scale * 2.0).ceil().max(1.0) as usize;
+        for k in 0..=steps {
+            let p = a + (b - a) * (k as f64 / steps as f64);
+            if let Some((x, y)) = self.dot(p) {
+                layer[(y / 4) * self.cols + x / 2] |= BRAILLE_BITS[x % 2][y % 4];
+            }
+        }
+    }
+}
+
+/// Whether both standard input and output are a terminal, as
+/// [`animate`] needs.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Restores the terminal when dropped.
+struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    /// Turn off line buffering, echo and signal keys, make reads return
+    /// at once, and switch to the alternate screen.
+    ///
+    /// Ctrl-C then reaches [`animate`] as a key rather than killing the
+    /// process before the terminal is restored.
+    fn enter() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "0", "time", "0"])?;
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(RawTerminal {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Run `stty` on the terminal and return what it prints.
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Columns and rows of the terminal.
+fn terminal_size() -> (usize, usize) {
+    stty(&["size"])
+        .ok()
+        .and_then(|size| {
+            let mut parts = size.split_whitespace().map(str::parse::<usize>);
+            let rows = parts.next()?.ok()?;
+            let cols = parts.next()?.ok()?;
+            (rows > 0 && cols > 0).then_some((cols, rows))
+        })
+        .unwrap_or(FALLBACK_SIZE)
+}
+
+/// Animate `flights` on `table` until the user quits.
+///
+/// Keys: space plays or pauses, `n` jumps to the next bounce, `+` and `-`
+/// change the speed, `q` or Ctrl-C quits. Standard input and output must
+/// be a terminal; see [`is_interactive`].
+pub fn animate(
+    table: &BilliardTable,
+    flights: &[Flight],
+    options: &WatchOptions,
+) -> io::Result<()> {
+    let (cols, rows) = options.size.unwrap_or_else(terminal_size);
+    // Leave a line for the status.
+    let rows = rows.saturating_sub(1).max(1);
+
+    let (outlines, min, max) = table_outlines(table, BOUNDARY_RELATIVE_CHORD_ERROR);
+
+    let mut canvas = Canvas::new(cols, rows, min, max);
+    let mut boundary = vec![0; cols * rows];
+    for points in &outlines {
+        for (k, &a) in points.iter().enumerate() {
+            canvas.line(&mut boundary, a, points[(k + 1) % points.len()]);
+        }
+    }
+    canvas.boundary = boundary;
+
+    let _terminal = RawTerminal::enter()?;
+    let end = flights.last().map_or(0.0, |f| f.end);
+    let frame = Duration::from_secs_f64(1.0 / options.fps);
+    let mut rate = options.rate;
+    let mut time = 0.0;
+    let mut playing = true;
+    let mut last = Instant::now();
+    let mut keys = [0; 16];
+
+    loop {
+        let n = io::stdin().read(&mut keys)?;
+        for &key in &keys[..n] {
+            match key {
+                // Ctrl-C arrives as ETX with signals off.
+                b'q' | b'Q' | 0x03 => return Ok(()),
+                b' ' => playing = !playing,
+                b'n' | b'N' => {
+                    time = flights
+                        .iter()
+                        .map(|f| f.end)
+                        .find(|&t| t > time)
+                        .unwrap_or(end);
+                    playing = false;
+                }
+                b'+' | b'=' => rate *= 2.0,
+                b'-' | b'_' => rate /= 2.0,
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        if playing {
+            time = (time + (now - last).as_secs_f64() * rate).min(end);
+        }
+        last = now;
+
+        // Flights that have started, the current one last.
+        let current = flights.partition_point(|f| f.start <= time).max(1);
+        let mut trail = vec![0; cols * rows];
+        let ball = flights.get(current - 1).map(|f| f.position_at(time));
+        for f in &flights[current.saturating_sub(options.trail + 1)..current.min(flights.len())] {
+            let to = if f.end > time {
+                f.position_at(time)
+            } else {
+                f.to
+            };
+            canvas.line(&mut trail, f.from, to);
+        }
+        canvas.trail = trail;
+
+        let bounces = flights.partition_point(|f| f.end <= time);
+        let status = format!(
+            "t = {:.3}  bounce {}/{}  ×{}  {}  [space] play/pause  [n] next bounce  [+/-] speed  [q] quit",
+            time,
+            bounces,
+            flights.len(),
+            rate,
+            if time >= end {
+                "done"
+            } else if playing {
+                "playing"
+            } else {
+                "paused"
+            },
+        );
+        draw(&canvas, ball, &status)?;
+
+        std::thread::sleep(frame);
+    }
+}
+
+/// Draw one frame: the boundary in white, the trail in cyan and the ball in
+/// yellow, then the status line.
+fn draw(canvas: &Canvas, ball: Option<Vec2>, status: &str) -> io::Result<()> {
+    let ball_cell = ball.and_then(|p| canvas.cell(p));
+    let mut out = String::from("\x1b[H");
+    for row in 0..canvas.rows {
+        for col in 0..canvas.cols {
+            let k = row * canvas.cols + col;
+            let bits = canvas.boundary[k] | canvas.trail[k];
+            if Some(k) == ball_cell {
+                out.push_str("\x1b[93m●");
+            } else if bits == 0 {
+                out.push(' ');
+            } else {
+                out.push_str(if canvas.trail[k] != 0 {
+                    "\x1b[96m"
+                } else {
+                    "\x1b[97m"
+                });
+                out.push(char::from_u32(0x2800 + bits as u32).unwrap_or(' '));
+            }
+        }
+        out.push_str("\x1b[0m\r\n");
+    }
+    out.push_str("\x1b[2K");
+    out.extend(status.chars().take(canvas.cols));
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(out.as_bytes())?;
+    stdout.flush()
+}
@@ -0,0 +1,82 @@
+//! Ray tests against polygons with many edges, one segment at a time and
+//! batched, and whole trajectories on such a table.
+//!
+//! Run with `cargo bench -p billiard-core --bench intersection`.
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use billiard_core::dynamics::intersection::Ray;
+use billiard_core::dynamics::simulation::{BilliardMap, StepOptions};
+use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::geometry::primitives::Vec2;
+use billiard_core::geometry::segments::BoundarySegment;
+use billiard_core::geometry::standard_tables::regular_polygon;
+
+/// Rays from near the center in evenly spread directions.
+fn rays(n: usize) -> Vec<Ray> {
+    (0..n)
+        .map(|k| {
+            let angle = k as f64 * 2.399;
+            Ray {
+                origin: Vec2::new(0.1 * angle.sin(), 0.1 * angle.cos()),
+                direction: Vec2::new(angle.cos(), angle.sin()),
+            }
+        })
+        .collect()
+}
+
+fn ray_tests(c: &mut Criterion) {
+    let rays = rays(64);
+    let mut group = c.benchmark_group("nearest_line_hit");
+    for edges in [8, 64, 512] {
+        let table = regular_polygon(edges, 1.0).to_billiard_table();
+        let component = table.component(0);
+
+        group.bench_with_input(BenchmarkId::new("scalar", edges), &edges, |b, _| {
+            b.iter(|| {
+                for ray in &rays {
+                    let nearest = component
+                        .segments
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, segment)| match segment {
+                            BoundarySegment::Line(line) => ray
+                                .intersect_line_segment(line, 1e-10)
+                                .map(|(t, local_t)| (i, t, local_t)),
+                            _ => None,
+                        })
+                        .min_by(|a, b| a.1.total_cmp(&b.1));
+                    black_box(nearest);
+                }
+            })
+        });
+
+        let batch = component.line_batch();
+        group.bench_with_input(BenchmarkId::new("batched", edges), &edges, |b, _| {
+            b.iter(|| {
+                for ray in &rays {
+                    black_box(batch.nearest_hit(ray.origin, ray.direction, 1e-10, None));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn trajectories(c: &mut Criterion) {
+    let table = regular_polygon(512, 1.0).to_billiard_table();
+    let initial = BoundaryState::new(0, 0.01, 0.7);
+    let options = StepOptions::default();
+    c.bench_function("trajectory_512_gon_1000_steps", |b| {
+        b.iter(|| {
+            BilliardMap::new(&table, 1e-10, options)
+                .simulate(&initial, 1000)
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, ray_tests, trajectories);
+criterion_main!(benches);
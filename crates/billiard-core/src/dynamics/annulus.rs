@@ -0,0 +1,313 @@
+//! Phase portraits and resonances of annular billiards.
+//!
+//! In the annulus between a circle of radius `R` and a disk of radius `r`
+//! whose centers are `d` apart (see [`standard_tables::annulus`]), a chord
+//! leaving the outer wall at angle `θ` to the tangent passes at distance
+//! `R |cos θ|` from the outer center. When the circles are concentric the
+//! angular momentum `R cos θ` is conserved, bounces off the disk included,
+//! and every orbit is regular. Off center only the whispering orbits with
+//! `R |cos θ| > d + r`, which never reach the disk, keep a constant `θ`; the
+//! rest of phase space breaks up into a chaotic sea around chains of
+//! islands, each chain surrounding a resonant periodic orbit.
+//!
+//! [`phase_portrait`] follows a grid of orbits from the outer wall and
+//! records their bounces on it; [`detect_resonance`] finds the rational
+//! rotation number `p/q` an orbit is locked to, if any.
+//!
+//! [`standard_tables::annulus`]: crate::geometry::standard_tables::annulus
+
+use crate::dynamics::circle_map::full_circle;
+use crate::dynamics::config::SimulationConfig;
+use crate::dynamics::rotation::outer_rotation_number;
+use crate::dynamics::sampling::outer_wall_grid;
+use crate::dynamics::simulation::{DynamicsError, run_trajectory};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+
+/// Shape of an annular table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Annulus {
+    pub outer_radius: f64,
+    pub inner_radius: f64,
+
+    /// Distance between the centers of the two circles.
+    pub offset: f64,
+}
+
+impl Annulus {
+    /// The annulus `table` is, if its outer wall and its single obstacle
+    /// are both full circles.
+    pub fn from_table(table: &BilliardTable) -> Option<Self> {
+        let [obstacle] = table.obstacles.as_slice() else {
+            return None;
+        };
+        let outer = full_circle(&table.outer)?;
+        let inner = full_circle(obstacle)?;
+        Some(Annulus {
+            outer_radius: outer.radius,
+            inner_radius: inner.radius,
+            offset: (inner.center - outer.center).length(),
+        })
+    }
+
+    /// Whether chords leaving the outer wall at `theta` miss the inner
+    /// disk wherever they start.
+    pub fn misses_inner(&self, theta: f64) -> bool {
+        self.outer_radius * theta.cos().abs() > self.offset + self.inner_radius
+    }
+}
+
+/// One orbit of a [`phase_portrait`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortraitOrbit {
+    pub initial: BoundaryState,
+
+    /// Coordinates `(s, cos θ)` of its bounces on the outer wall, beginning
+    /// with its launch.
+    pub points: Vec<(f64, f64)>,
+
+    /// Whether the orbit reached an obstacle.
+    pub hits_obstacle: bool,
+}
+
+/// Follow orbits launched from the outer wall of `table` on an
+/// `s_count × p_count` grid, as `config` says, for a portrait of the
+/// billiard map on the outer wall.
+///
+/// Launches are at the cell midpoints of `[0, L) × (-1, 1)` in `s` and
+/// `p = cos θ`, at unit speed, row by row in `s`. Bounces on obstacles are
+/// left out of the points, so on the annulus each orbit draws its
+/// section through the outer circle.
+///
+/// # Errors
+/// Fails if any orbit fails to simulate.
+pub fn phase_portrait(
+    table: &BilliardTable,
+    s_count: usize,
+    p_count: usize,
+    config: &SimulationConfig,
+) -> Result<Vec<PortraitOrbit>, DynamicsError> {
+    outer_wall_grid(table, s_count, p_count)
+        .into_iter()
+        .map(|initial| {
+            let collisions = run_trajectory(table, &initial, config)?;
+            let points = std::iter::once((initial.s, initial.theta.cos()))
+                .chain(
+                    collisions
+                        .iter()
+                        .filter(|c| c.component_index == 0)
+                        .map(|c| (c.s, c.theta.cos())),
+                )
+                .collect();
+            Ok(PortraitOrbit {
+                initial,
+                points,
+                hits_obstacle: collisions.iter().any(|c| c.component_index != 0),
+            })
+        })
+        .collect()
+}
+
+/// An orbit locked to the rotation number `winding / period`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resonance {
+    /// Turns around the outer wall in `period` bounces on it, in
+    /// `[0, period)`.
+    pub winding: usize,
+
+    /// Bounces on the outer wall before the orbit comes back round.
+    pub period: usize,
+
+    /// The measured rotation number; see [`outer_rotation_number`].
+    pub rotation: f64,
+}
+
+/// The resonance the orbit from `initial` is on, if its rotation number
+/// settles to within `tolerance` of a fraction `p/q` with
+/// `q <= max_period`; the smallest such `q` is reported.
+///
+/// Chaotic orbits, whose running rotation number wanders by more than
+/// `tolerance` over the second half of the run, and quasi-periodic ones
+/// are not resonant. Returns `None` as [`outer_rotation_number`] does too.
+pub fn detect_resonance(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    max_period: usize,
+    tolerance: f64,
+    config: &SimulationConfig,
+) -> Option<Resonance> {
+    let rotation = outer_rotation_number(table, initial, config)?;
+    if !rotation.converged(tolerance) {
+        return None;
+    }
+    (1..=max_period).find_map(|period| {
+        let turns = rotation.value * period as f64;
+        let nearest = turns.round();
+        ((turns - nearest).abs() <= tolerance * period as f64).then_some(Resonance {
+            winding: nearest as usize % period,
+            period,
+            rotation: rotation.value,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use super::{Annulus, detect_resonance, phase_portrait};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::rotation::outer_rotation_number;
+    use crate::dynamics::simulation::{CollisionResult, run_trajectory};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::standard_tables::{annulus, sinai};
+
+    fn config(max_steps: usize) -> SimulationConfig {
+        SimulationConfig::builder()
+            .max_steps(max_steps)
+            .build()
+            .unwrap()
+    }
+
+    /// Check every bounce on the disk centered at `center` against the law
+    /// of reflection about its outward normal.
+    fn assert_reflects_off_disk(start: Vec2, collisions: &[CollisionResult], center: Vec2) {
+        let points: Vec<Vec2> = std::iter::once(start)
+            .chain(collisions.iter().map(|c| c.hit_point))
+            .collect();
+        let mut bounces = 0;
+        for (k, c) in collisions.iter().enumerate() {
+            if c.component_index != 1 || k + 1 == collisions.len() {
+                continue;
+            }
+            let incoming = (points[k + 1] - points[k]).normalized();
+            let outgoing = (points[k + 2] - points[k + 1]).normalized();
+            let normal = (c.hit_point - center).normalized();
+            let mirrored = incoming - normal * (2.0 * incoming.dot(normal));
+            assert!((outgoing - mirrored).length() < 1e-9, "bounce {}", k);
+            assert!(outgoing.dot(normal) > 0.0);
+            bounces += 1;
+        }
+        assert!(bounces > 0);
+    }
+
+    #[test]
+    fn annuli_are_recognised_from_their_tables() {
+        let table = annulus(2.0, 0.5, 0.75).to_billiard_table();
+        assert_eq!(
+            Annulus::from_table(&table),
+            Some(Annulus {
+                outer_radius: 2.0,
+                inner_radius: 0.5,
+                offset: 0.75,
+            })
+        );
+        assert!(Annulus::from_table(&sinai(1.0, 0.2).to_billiard_table()).is_none());
+    }
+
+    #[test]
+    fn concentric_orbits_conserve_angular_momentum() {
+        let table = annulus(1.0, 0.5, 0.0).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.2);
+        let collisions = run_trajectory(&table, &initial, &config(100)).unwrap();
+        assert_reflects_off_disk(
+            initial.to_world(&table).position,
+            &collisions,
+            Vec2::new(0.0, 0.0),
+        );
+
+        let momentum = 1.2f64.cos();
+        for c in &collisions {
+            let radius = if c.component_index == 0 { 1.0 } else { 0.5 };
+            assert!((radius * c.theta.cos().abs() - momentum).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn offset_disks_reflect_about_their_own_normal() {
+        let table = annulus(1.0, 0.3, 0.4).to_billiard_table();
+
+        // Aimed at the center along the x-axis, the particle hits the disk
+        // head on at (0.7, 0) and comes straight back.
+        let radial = BoundaryState::new(0, 0.0, FRAC_PI_2);
+        let collisions = run_trajectory(&table, &radial, &config(2)).unwrap();
+        assert_eq!(collisions[0].component_index, 1);
+        assert!((collisions[0].hit_point - Vec2::new(0.7, 0.0)).length() < 1e-12);
+        assert!((collisions[1].hit_point - Vec2::new(1.0, 0.0)).length() < 1e-12);
+        assert!((collisions[1].theta - FRAC_PI_2).abs() < 1e-12);
+
+        let initial = BoundaryState::new(0, 2.0, 1.1);
+        let collisions = run_trajectory(&table, &initial, &config(200)).unwrap();
+        assert_reflects_off_disk(
+            initial.to_world(&table).position,
+            &collisions,
+            Vec2::new(0.4, 0.0),
+        );
+    }
+
+    #[test]
+    fn whispering_orbits_trace_flat_lines_in_the_portrait() {
+        let table = annulus(1.0, 0.3, 0.4).to_billiard_table();
+        let geometry = Annulus::from_table(&table).unwrap();
+        let orbits = phase_portrait(&table, 4, 6, &config(50)).unwrap();
+        assert_eq!(orbits.len(), 24);
+
+        for orbit in &orbits {
+            let (_, p) = orbit.points[0];
+            if geometry.misses_inner(orbit.initial.theta) {
+                assert!(!orbit.hits_obstacle);
+                assert_eq!(orbit.points.len(), 51);
+                assert!(orbit.points.iter().all(|&(_, q)| (q - p).abs() < 1e-12));
+            }
+        }
+        // Only p = ±5/6 clears the disk; every other row reaches it.
+        assert_eq!(orbits.iter().filter(|o| !o.hits_obstacle).count(), 8);
+    }
+
+    #[test]
+    fn resonant_orbits_lock_to_their_fraction() {
+        // A whispering orbit at θ = π/5 goes round the offset annulus in
+        // five bounces, as in the circle.
+        let offset = annulus(1.0, 0.3, 0.4).to_billiard_table();
+        let whispering = BoundaryState::new(0, 1.0, PI / 5.0);
+        let resonance = detect_resonance(&offset, &whispering, 10, 1e-9, &config(200)).unwrap();
+        assert_eq!((resonance.winding, resonance.period), (1, 5));
+
+        // In the concentric annulus an orbit bouncing off the disk advances
+        // θ + asin(R cos θ / r) - π/2 round the center per bounce on
+        // the outer wall; with θ = π/3 and this r it is a quarter turn.
+        let r = 0.5 / (5.0 * PI / 12.0).sin();
+        let concentric = annulus(1.0, r, 0.0).to_billiard_table();
+        let square = BoundaryState::new(0, 0.0, PI / 3.0);
+        let resonance = detect_resonance(&concentric, &square, 10, 1e-9, &config(400)).unwrap();
+        assert_eq!((resonance.winding, resonance.period), (1, 4));
+
+        let generic = BoundaryState::new(0, 0.0, 1.2);
+        let rotation = outer_rotation_number(&concentric, &generic, &config(400)).unwrap();
+        let advance = 1.2 + (1.2f64.cos() / r).asin() - FRAC_PI_2;
+        assert!((rotation.value - advance / PI).abs() < 1e-9);
+
+        // Radial orbits bounce back and forth: rotation number 0.
+        let radial = BoundaryState::new(0, 0.0, FRAC_PI_2);
+        let resonance = detect_resonance(&offset, &radial, 10, 1e-9, &config(20)).unwrap();
+        assert_eq!((resonance.winding, resonance.period), (0, 1));
+    }
+
+    #[test]
+    fn chaotic_orbits_have_no_resonance() {
+        let table = annulus(1.0, 0.3, 0.4).to_billiard_table();
+        let initial = BoundaryState::new(0, 1.0, 1.3);
+        assert!(detect_resonance(&table, &initial, 10, 1e-6, &config(2000)).is_none());
+        assert!(
+            detect_resonance(
+                &table,
+                &BoundaryState::new(1, 0.0, 1.0),
+                10,
+                1e-6,
+                &config(10)
+            )
+            .is_none()
+        );
+    }
+}
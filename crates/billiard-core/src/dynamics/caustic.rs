@@ -0,0 +1,269 @@
+//! Caustic detection for convex billiards.
+//!
+//! An orbit on a convex table has a caustic when all its chords are tangent
+//! to a common curve. Two tests are provided:
+//!
+//! - for the ellipse `x²/a² + y²/b² = 1`, every chord of an orbit is tangent
+//!   to the same confocal conic `x²/(a²-λ) + y²/(b²-λ) = 1`, so checking
+//!   that `λ` is conserved identifies the caustic exactly;
+//! - for any convex table, the chords are sorted by direction and
+//!   neighbouring chords intersected. For an orbit with a convex caustic the
+//!   intersections trace the caustic and every chord supports them all; a
+//!   chaotic orbit has chords crossing through the cloud of intersections.
+
+use std::f64::consts::TAU;
+
+use crate::dynamics::lattice::trajectory_to_polyline;
+use crate::dynamics::simulation::{BilliardMap, StepOptions};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::primitives::Vec2;
+
+/// What the chords of an orbit envelope.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Caustic {
+    /// Confocal ellipse `x²/(a²-λ) + y²/(b²-λ) = 1` with `λ < b²`.
+    ConfocalEllipse { lambda: f64, semi_axes: (f64, f64) },
+
+    /// Confocal hyperbola `x²/(a²-λ) - y²/(λ-b²) = 1` with `b² < λ < a²`.
+    ConfocalHyperbola { lambda: f64 },
+
+    /// A convex caustic, approximated by the intersections of chords that
+    /// are neighbours in direction (sorted by direction).
+    Envelope { points: Vec<Vec2> },
+
+    /// No caustic was found.
+    Chaotic,
+}
+
+/// Bounce points of the orbit starting at `initial`, beginning with its
+/// launch point, or `None` if the orbit hits an obstacle or the simulation
+/// fails.
+pub fn orbit_points(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    bounces: usize,
+    epsilon: f64,
+    options: &StepOptions,
+) -> Option<Vec<Vec2>> {
+    let collisions = BilliardMap::new(table, epsilon, *options)
+        .simulate(initial, bounces)
+        .map(|trajectory| trajectory.collisions)
+        .ok()?;
+    if collisions.iter().any(|c| c.component_index != 0) {
+        return None;
+    }
+    Some(trajectory_to_polyline(table, initial, &collisions))
+}
+
+/// Check the orbit through `points` (consecutive bounces on the ellipse with
+/// semi-axes `a > b`, centered at the origin) for a confocal caustic.
+///
+/// The orbit has one if the confocal parameter `λ` of every chord agrees
+/// to within `tolerance · a²`. The polygonal
+/// [`ellipse`](crate::geometry::standard_tables::ellipse) table is not
+/// integrable, and `λ` drifts by about a percent per bounce on it.
+pub fn confocal_caustic(a: f64, b: f64, points: &[Vec2], tolerance: f64) -> Caustic {
+    let lambdas: Vec<f64> = points
+        .windows(2)
+        .filter_map(|w| {
+            let n = (w[1] - w[0]).try_normalized()?.perp();
+            let c = n.dot(w[0]);
+            Some(a * a * n.x * n.x + b * b * n.y * n.y - c * c)
+        })
+        .collect();
+    if lambdas.is_empty() {
+        return Caustic::Chaotic;
+    }
+
+    let (lo, hi) = lambdas
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &l| {
+            (lo.min(l), hi.max(l))
+        });
+    if hi - lo > tolerance * a * a {
+        return Caustic::Chaotic;
+    }
+
+    let lambda = lambdas.iter().sum::<f64>() / lambdas.len() as f64;
+    if lambda < b * b {
+        Caustic::ConfocalEllipse {
+            lambda,
+            semi_axes: ((a * a - lambda).sqrt(), (b * b - lambda).sqrt()),
+        }
+    } else {
+        Caustic::ConfocalHyperbola { lambda }
+    }
+}
+
+/// Look for a convex caustic enveloped by the chords between consecutive
+/// `points`.
+///
+/// `tolerance` is relative to the size of the orbit. Orbits whose chords
+/// envelope a non-convex curve (such as the hyperbolic caustics of the
+/// ellipse) are reported as [`Caustic::Chaotic`].
+pub fn envelope_caustic(points: &[Vec2], tolerance: f64) -> Caustic {
+    // Directed chords, sorted by direction.
+    let mut chords: Vec<(f64, Vec2, Vec2)> = points
+        .windows(2)
+        .filter_map(|w| {
+            let d = (w[1] - w[0]).try_normalized()?;
+            Some((d.y.atan2(d.x).rem_euclid(TAU), w[0], d))
+        })
+        .collect();
+    if chords.len() < 3 {
+        return Caustic::Chaotic;
+    }
+    chords.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let envelope: Vec<Vec2> = (0..chords.len())
+        .filter_map(|i| {
+            let (_, p, d) = chords[i];
+            let (_, q, e) = chords[(i + 1) % chords.len()];
+            let denom = d.cross(e);
+            // Skip (nearly) repeated chords.
+            if denom.abs() < 1e-12 {
+                return None;
+            }
+            Some(p + d * ((q - p).cross(e) / denom))
+        })
+        .collect();
+    if envelope.len() < 3 {
+        return Caustic::Chaotic;
+    }
+
+    let scale = points
+        .iter()
+        .flat_map(|p| points.iter().map(move |q| (*p - *q).length()))
+        .fold(0.0, f64::max);
+    let slack = tolerance * scale;
+
+    // Every chord must have the whole envelope on the same side, and that
+    // side must be the same for all chords.
+    let side_of = |(_, p, d): (f64, Vec2, Vec2)| {
+        let sides = envelope.iter().map(|&q| d.cross(q - p));
+        if sides.clone().all(|s| s >= -slack) {
+            Some(1)
+        } else if sides.into_iter().all(|s| s <= slack) {
+            Some(-1)
+        } else {
+            None
+        }
+    };
+    let first = side_of(chords[0]);
+    if first.is_none() || chords.iter().any(|&c| side_of(c) != first) {
+        return Caustic::Chaotic;
+    }
+
+    Caustic::Envelope { points: envelope }
+}
+
+/// Detect a convex caustic of the orbit starting at `initial` on a convex
+/// table, from `bounces` chords.
+///
+/// See [`envelope_caustic`]; for the ellipse use [`confocal_caustic`] on the
+/// [`orbit_points`] instead. Orbits that hit an obstacle are reported as chaotic.
+pub fn detect_caustic(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    bounces: usize,
+    epsilon: f64,
+    options: &StepOptions,
+    tolerance: f64,
+) -> Caustic {
+    match orbit_points(table, initial, bounces, epsilon, options) {
+        Some(points) => envelope_caustic(&points, tolerance),
+        None => Caustic::Chaotic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Caustic, confocal_caustic, detect_caustic, orbit_points};
+    use crate::dynamics::simulation::StepOptions;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundarySegment, CircularArcSegment};
+    use crate::geometry::standard_tables::{ELLIPSE_SEGMENTS, ellipse, stadium};
+    use std::f64::consts::TAU;
+
+    #[test]
+    fn disk_orbit_envelopes_concentric_circle() {
+        let circle = CircularArcSegment::new(Vec2::new(0.0, 0.0), 1.0, 0.0, TAU, true);
+        let table = BilliardTable::new(
+            BoundaryComponent::new("disk", vec![BoundarySegment::CircularArc(circle)]),
+            Vec::new(),
+        );
+        let theta = 0.7;
+        let initial = BoundaryState::new(0, 0.0, theta);
+
+        let caustic = detect_caustic(&table, &initial, 300, 1e-8, &StepOptions::default(), 1e-6);
+        let Caustic::Envelope { points } = caustic else {
+            panic!("expected an envelope, got {:?}", caustic);
+        };
+        for p in points {
+            assert!((p.length() - theta.cos()).abs() < 1e-2, "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn stadium_orbit_is_chaotic() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+
+        let caustic = detect_caustic(&table, &initial, 300, 1e-8, &StepOptions::default(), 1e-6);
+        assert_eq!(caustic, Caustic::Chaotic);
+    }
+
+    /// Bounce points on the exact ellipse `x²/a² + y²/b² = 1`.
+    fn exact_ellipse_orbit(a: f64, b: f64, start: Vec2, direction: Vec2, n: usize) -> Vec<Vec2> {
+        let (mut p, mut d) = (start, direction);
+        let mut points = vec![p];
+        for _ in 0..n {
+            let t = -2.0 * (p.x * d.x / (a * a) + p.y * d.y / (b * b))
+                / (d.x * d.x / (a * a) + d.y * d.y / (b * b));
+            p = p + d * t;
+            let normal = Vec2::new(p.x / (a * a), p.y / (b * b)).normalized();
+            d = d - normal * (2.0 * d.dot(normal));
+            points.push(p);
+        }
+        points
+    }
+
+    #[test]
+    fn ellipse_orbits_conserve_confocal_parameter() {
+        let (a, b) = (2.0, 1.0);
+        // From the minor vertex (0, b) at angle θ to the tangent, λ = a² sin²θ.
+        let detect = |theta: f64| {
+            let direction = Vec2::new(-theta.cos(), -theta.sin());
+            let points = exact_ellipse_orbit(a, b, Vec2::new(0.0, b), direction, 200);
+            confocal_caustic(a, b, &points, 1e-9)
+        };
+
+        let Caustic::ConfocalEllipse { lambda, semi_axes } = detect(0.3) else {
+            panic!("expected an elliptic caustic");
+        };
+        assert!((lambda - a * a * 0.3_f64.sin().powi(2)).abs() < 1e-9);
+        assert!(semi_axes.1 > 0.0 && semi_axes.1 < b);
+
+        let Caustic::ConfocalHyperbola { lambda } = detect(1.2) else {
+            panic!("expected a hyperbolic caustic");
+        };
+        assert!((lambda - a * a * 1.2_f64.sin().powi(2)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn polygonal_ellipse_orbit_bounces_stay_on_outer_boundary() {
+        let table = ellipse(2.0, 1.0).to_billiard_table();
+        let s = table
+            .outer
+            .global_s_from_segment_local(ELLIPSE_SEGMENTS / 4, 0.0);
+        let initial = BoundaryState::new(0, s, 0.3);
+
+        let points = orbit_points(&table, &initial, 50, 1e-8, &StepOptions::default())
+            .expect("the ellipse has no obstacles");
+        assert_eq!(points.len(), 51);
+        assert!((points[0] - Vec2::new(0.0, 1.0)).length() < 1e-12);
+    }
+}
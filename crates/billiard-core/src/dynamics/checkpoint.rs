@@ -0,0 +1,280 @@
+//! Pausing and resuming long trajectories.
+//!
+//! A [`SimulationCheckpoint`] holds everything a run needs to carry on
+//! exactly where it stopped: the outgoing state of the last bounce, the
+//! number of collisions so far, the position of the random number
+//! generator, the elapsed path length and time, and the running free-path
+//! sums. It serializes to a few hundred
+//! bytes of JSON, so a long run can be split into chunks with
+//! [`run_trajectory_from_checkpoint`], saved between them and resumed in
+//! another process. Resuming is bit-for-bit: the chunks together give the
+//! same collisions as one uninterrupted run.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::config::SimulationConfig;
+use crate::dynamics::ensemble::FreePathStatistics;
+use crate::dynamics::lattice::periodic_shift;
+use crate::dynamics::reflection::{Impact, Reflection, ReflectionLaw, Specular};
+use crate::dynamics::simulation::{
+    BilliardMap, CollisionResult, DynamicsError, Termination, Trajectory,
+};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+
+/// Serializable position of a [`ChaCha8Rng`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RngState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+
+    /// Number of 32-bit words drawn so far.
+    pub word_pos: u128,
+}
+
+impl RngState {
+    /// Record where `rng` is.
+    pub fn from_rng(rng: &ChaCha8Rng) -> Self {
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    }
+
+    /// A generator at the recorded position.
+    pub fn to_rng(&self) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(self.word_pos);
+        rng
+    }
+}
+
+/// A paused trajectory; see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SimulationCheckpoint {
+    /// Outgoing state of the last collision, or the initial state.
+    pub state: BoundaryState,
+
+    /// Collisions simulated so far.
+    pub steps: usize,
+
+    /// Generator for the stochastic parts of the run.
+    pub rng: RngState,
+
+    /// Total chord length so far.
+    pub path_length: f64,
+
+    /// Time so far, up to leaving the last collision.
+    pub time: f64,
+
+    /// Sum of the completed free paths.
+    pub free_path_sum: f64,
+
+    /// Sum of the squares of the completed free paths.
+    pub free_path_sum_sq: f64,
+
+    /// Number of completed free flights.
+    pub flights: usize,
+
+    /// Length so far of a flight still passing through periodic segments.
+    pub open_flight: f64,
+
+    /// Set once the trajectory has ended other than by running out of
+    /// steps (a hole, a corner under `Terminate`, an escape); resuming it
+    /// does nothing.
+    pub finished: bool,
+}
+
+impl SimulationCheckpoint {
+    /// Checkpoint at the start of a run from `initial`, with the generator
+    /// seeded from [`SimulationConfig::seed`].
+    pub fn new(initial: &BoundaryState, config: &SimulationConfig) -> Self {
+        SimulationCheckpoint {
+            state: *initial,
+            steps: 0,
+            rng: RngState::from_rng(&ChaCha8Rng::seed_from_u64(config.seed())),
+            path_length: 0.0,
+            time: 0.0,
+            free_path_sum: 0.0,
+            free_path_sum_sq: 0.0,
+            flights: 0,
+            open_flight: 0.0,
+            finished: false,
+        }
+    }
+
+    /// Statistics of the free flights completed so far, as
+    /// [`free_path_histogram`](crate::dynamics::ensemble::free_path_histogram)
+    /// would give for the whole trajectory.
+    pub fn free_path_statistics(&self) -> FreePathStatistics {
+        FreePathStatistics::from_sums(self.free_path_sum, self.free_path_sum_sq, self.flights)
+    }
+
+    /// The checkpoint after `trajectory`, run from this one on `table`.
+    fn advance(&self, table: &BilliardTable, trajectory: &Trajectory, rng: RngState) -> Self {
+        let mut next = SimulationCheckpoint {
+            steps: self.steps + trajectory.collisions.len(),
+            rng,
+            finished: trajectory.termination != Termination::MaxSteps,
+            ..self.clone()
+        };
+        for c in &trajectory.collisions {
+            next.open_flight += c.chord;
+            if periodic_shift(table, c).is_none() {
+                next.free_path_sum += next.open_flight;
+                next.free_path_sum_sq += next.open_flight * next.open_flight;
+                next.flights += 1;
+                next.open_flight = 0.0;
+            }
+        }
+        if let Some(last) = trajectory.collisions.last() {
+            next.state = last.outgoing_state();
+            next.path_length = last.path_length;
+            next.time = last.departure_time();
+        }
+        next
+    }
+}
+
+/// Continue a specular trajectory from `checkpoint` for up to
+/// [`SimulationConfig::max_steps`] further collisions.
+///
+/// Returns the new collisions and the checkpoint to resume from next. The
+/// generator is carried along unchanged; see
+/// [`run_trajectory_from_checkpoint_with_law`] for runs that draw from it.
+pub fn run_trajectory_from_checkpoint(
+    table: &BilliardTable,
+    checkpoint: &SimulationCheckpoint,
+    config: &SimulationConfig,
+) -> Result<(Vec<CollisionResult>, SimulationCheckpoint), DynamicsError> {
+    run_trajectory_from_checkpoint_with_law(table, checkpoint, config, |impact, _| {
+        Specular.reflect(impact)
+    })
+}
+
+/// Like [`run_trajectory_from_checkpoint`], but leaving the wall by `law`,
+/// which draws its randomness from the checkpoint's generator so that the
+/// run resumes with the same random numbers it would have drawn unpaused.
+pub fn run_trajectory_from_checkpoint_with_law<F>(
+    table: &BilliardTable,
+    checkpoint: &SimulationCheckpoint,
+    config: &SimulationConfig,
+    mut law: F,
+) -> Result<(Vec<CollisionResult>, SimulationCheckpoint), DynamicsError>
+where
+    F: FnMut(&Impact, &mut ChaCha8Rng) -> Reflection,
+{
+    if checkpoint.finished {
+        return Ok((Vec::new(), checkpoint.clone()));
+    }
+    let mut rng = checkpoint.rng.to_rng();
+    let mut map =
+        BilliardMap::from_config(table, config).with_law(|impact: &Impact| law(impact, &mut rng));
+    let mut iter = map
+        .iter_from(&checkpoint.state)
+        .continuing(checkpoint.path_length, checkpoint.time);
+    let collisions = iter
+        .by_ref()
+        .take(config.max_steps())
+        .collect::<Result<Vec<_>, _>>()?;
+    let trajectory = Trajectory {
+        collisions,
+        termination: iter.termination().unwrap_or(Termination::MaxSteps),
+        invariants: iter.invariants().copied(),
+    };
+    // The iterator borrows `rng` through the law until it is dropped.
+    drop(iter);
+    let next = checkpoint.advance(table, &trajectory, RngState::from_rng(&rng));
+    Ok((trajectory.collisions, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        SimulationCheckpoint, run_trajectory_from_checkpoint,
+        run_trajectory_from_checkpoint_with_law,
+    };
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::ensemble::free_path_histogram;
+    use crate::dynamics::reflection::{Impact, Reflection};
+    use crate::dynamics::simulation::{CollisionResult, run_trajectory};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{lorentz_gas, stadium};
+    use rand::RngExt;
+    use rand_chacha::ChaCha8Rng;
+
+    fn same(a: &[CollisionResult], b: &[CollisionResult]) -> bool {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|(a, b)| a.outgoing_state() == b.outgoing_state() && a.time == b.time)
+    }
+
+    #[test]
+    fn chunked_runs_match_an_uninterrupted_one() {
+        let initial = BoundaryState::new(0, 0.1, 0.9);
+        for table in [
+            stadium(1.0, 0.5).to_billiard_table(),
+            lorentz_gas(1.0, 0.3).to_billiard_table(),
+        ] {
+            let whole_config = SimulationConfig::builder().max_steps(300).build().unwrap();
+            let whole = run_trajectory(&table, &initial, &whole_config).unwrap();
+
+            let config = whole_config.with_max_steps(100);
+            let mut checkpoint = SimulationCheckpoint::new(&initial, &config);
+            let mut chunks = Vec::new();
+            for _ in 0..3 {
+                let (collisions, next) =
+                    run_trajectory_from_checkpoint(&table, &checkpoint, &config).unwrap();
+                chunks.extend(collisions);
+                let json = serde_json::to_string(&next).unwrap();
+                checkpoint = serde_json::from_str(&json).unwrap();
+                assert_eq!(checkpoint, next);
+            }
+            assert!(same(&whole, &chunks));
+            assert_eq!(checkpoint.steps, 300);
+
+            let expected = free_path_histogram(&table, &whole, 1, None).statistics;
+            let statistics = checkpoint.free_path_statistics();
+            assert_eq!(statistics.flights, expected.flights);
+            assert!((statistics.mean - expected.mean).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn random_laws_resume_with_the_same_random_numbers() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.1, 0.9);
+        // Specular, with the tangential part scaled by a random factor.
+        let law = |impact: &Impact, rng: &mut ChaCha8Rng| {
+            let (normal, tangential) = impact.split();
+            let k = rng.random_range(0.5_f64..1.0);
+            Reflection::immediate((tangential * k - normal).normalized())
+        };
+
+        let config = SimulationConfig::builder()
+            .max_steps(200)
+            .seed(3)
+            .build()
+            .unwrap();
+        let start = SimulationCheckpoint::new(&initial, &config);
+        let (whole, end) =
+            run_trajectory_from_checkpoint_with_law(&table, &start, &config, law).unwrap();
+
+        let half = config.with_max_steps(100);
+        let (mut chunks, middle) =
+            run_trajectory_from_checkpoint_with_law(&table, &start, &half, law).unwrap();
+        let (rest, resumed_end) =
+            run_trajectory_from_checkpoint_with_law(&table, &middle, &half, law).unwrap();
+        chunks.extend(rest);
+        assert!(same(&whole, &chunks));
+        assert_eq!(resumed_end, end);
+        assert_ne!(end.rng, start.rng);
+    }
+}
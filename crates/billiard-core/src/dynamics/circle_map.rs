@@ -0,0 +1,284 @@
+//! The billiard map of a circle in closed form.
+//!
+//! Inside a circle of radius `r`, a chord leaving the wall at angle `θ` to
+//! the tangent ends `2rθ` further round and leaves again at `θ`: in `(s, θ)`
+//! the map is the rigid rotation `(s, θ) ↦ (s + 2rθ mod L, θ)`. A
+//! [`CircleMap`] evaluates its `n`-th iterate directly, so that
+//! [`run_trajectory`] can skip the ray casts altogether on circular tables,
+//! and on annuli and other circles with obstacles for orbits whose chords
+//! pass clear of them.
+//!
+//! [`run_trajectory`]: crate::dynamics::simulation::run_trajectory
+
+use std::f64::consts::{PI, TAU};
+
+use crate::dynamics::config::SimulationConfig;
+use crate::dynamics::simulation::{CollisionResult, FlightModel, StepOptions};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::{BoundaryCondition, BoundarySegment, CircularArcSegment};
+
+/// Relative tolerance on the length of an arc closing into a full circle.
+const FULL_CIRCLE_TOLERANCE: f64 = 1e-12;
+
+/// A trajectory bouncing inside a full circle without meeting anything else;
+/// see the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct CircleMap {
+    arc: CircularArcSegment,
+    initial: BoundaryState,
+
+    /// Arc length travelled between bounces, `2rθ`.
+    step: f64,
+    chord: f64,
+}
+
+impl CircleMap {
+    /// The closed-form map for a trajectory from `initial` simulated as
+    /// `config` says, if it applies. It does when:
+    ///
+    /// - the component of `initial` is a single, reflecting, concave circular
+    ///   arc closing into a full circle, with no holes;
+    /// - `initial` leaves it into the table, with `0 < θ < π`;
+    /// - every other component lies strictly inside the circle of radius
+    ///   `r |cos θ|` that the chords are tangent to, with `epsilon` to spare;
+    /// - flights are straight and elastic, without an escape box that cuts
+    ///   the circle, invariant checks or a whispering-gallery guard.
+    ///
+    /// Otherwise the trajectory has to be stepped and `None` is returned.
+    pub fn new(
+        table: &BilliardTable,
+        initial: &BoundaryState,
+        config: &SimulationConfig,
+    ) -> Option<Self> {
+        Self::with_options(table, initial, config.epsilon(), config.step_options())
+    }
+
+    /// [`CircleMap::new`] for a map stepped with `epsilon` and `options`.
+    pub(crate) fn with_options(
+        table: &BilliardTable,
+        initial: &BoundaryState,
+        epsilon: f64,
+        options: &StepOptions,
+    ) -> Option<Self> {
+        if options.flight != FlightModel::Straight
+            || options.restitution != 1.0
+            || options.invariants.is_some()
+            || options.whispering_gallery.is_some()
+        {
+            return None;
+        }
+        initial.check(table).ok()?;
+        if !(initial.theta > 0.0 && initial.theta < PI) {
+            return None;
+        }
+
+        let component = table.component(initial.component_index);
+        let arc = full_circle(component)?;
+        if arc.condition != BoundaryCondition::Reflect || !component.holes.is_empty() {
+            return None;
+        }
+        let (point, inward_normal) = component.point_and_inward_normal_at(0.0);
+        if inward_normal.dot(arc.center - point) <= 0.0 {
+            return None;
+        }
+
+        if let Some(escape_box) = options.escape_box {
+            let corner = Vec2::new(arc.radius, arc.radius);
+            if !(escape_box.contains(arc.center - corner)
+                && escape_box.contains(arc.center + corner))
+            {
+                return None;
+            }
+        }
+        let clearance = arc.radius * initial.theta.cos().abs() - epsilon;
+        for (index, other) in table.components().enumerate() {
+            if index == initial.component_index {
+                continue;
+            }
+            for segment in &other.segments {
+                if reach(segment, arc.center)? >= clearance {
+                    return None;
+                }
+            }
+        }
+
+        Some(CircleMap {
+            arc: arc.clone(),
+            initial: *initial,
+            step: 2.0 * arc.radius * initial.theta,
+            chord: 2.0 * arc.radius * initial.theta.sin(),
+        })
+    }
+
+    /// The `n`-th collision from the initial state, `n ≥ 1`, with running
+    /// totals over the `n` chords before it.
+    pub fn collision(&self, n: usize) -> CollisionResult {
+        let s = (self.initial.s + n as f64 * self.step).rem_euclid(self.arc.length());
+        let outgoing = BoundaryState { s, ..self.initial };
+        let path_length = n as f64 * self.chord;
+        CollisionResult {
+            path_length,
+            time: path_length / self.initial.speed,
+            segment_id: self.arc.label.id,
+            ..CollisionResult::new(outgoing, 0, self.arc.point_at(s), self.chord, None, false)
+        }
+    }
+
+    /// The first `max_steps` collisions, up to time `max_time` if one is
+    /// given, as [`BilliardMap::simulate_for`] would return them.
+    ///
+    /// [`BilliardMap::simulate_for`]: crate::dynamics::simulation::BilliardMap::simulate_for
+    pub fn collisions(&self, max_steps: usize, max_time: Option<f64>) -> Vec<CollisionResult> {
+        (1..=max_steps)
+            .map(|n| self.collision(n))
+            .take_while(|c| max_time.is_none_or(|max_time| c.time <= max_time))
+            .collect()
+    }
+}
+
+/// The circle `component` consists of, if it is a single circular arc going
+/// all the way round.
+pub(crate) fn full_circle(component: &BoundaryComponent) -> Option<&CircularArcSegment> {
+    let [BoundarySegment::CircularArc(arc)] = component.segments.as_slice() else {
+        return None;
+    };
+    let full = (arc.length() - TAU * arc.radius).abs() <= FULL_CIRCLE_TOLERANCE * arc.length();
+    full.then_some(arc)
+}
+
+/// Largest distance from `center` of a point of `segment`, or an upper
+/// bound on it; `None` for external curves, which are not bounded here.
+pub(crate) fn reach(segment: &BoundarySegment, center: Vec2) -> Option<f64> {
+    match segment {
+        BoundarySegment::Line(line) => Some(
+            (line.start - center)
+                .length()
+                .max((line.end - center).length()),
+        ),
+        BoundarySegment::CircularArc(arc) => Some((arc.center - center).length() + arc.radius),
+        BoundarySegment::External(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use super::CircleMap;
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::simulation::{BilliardMap, FlightModel, run_trajectory};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{circle, sinai, stadium};
+
+    fn config(max_steps: usize) -> SimulationConfig {
+        SimulationConfig::builder()
+            .max_steps(max_steps)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn matches_the_stepped_map() {
+        let table = circle(1.5).to_billiard_table();
+        let config = config(500);
+        for theta in [0.05, 0.7, 1.3, 2.2, 3.0] {
+            let initial = BoundaryState::new(0, 0.4, theta);
+            assert!(CircleMap::new(&table, &initial, &config).is_some());
+
+            let stepped = BilliardMap::from_config(&table, &config)
+                .simulate(&initial, config.max_steps())
+                .unwrap()
+                .collisions;
+            let fast = run_trajectory(&table, &initial, &config).unwrap();
+            assert_eq!(fast.len(), stepped.len());
+            for (a, b) in fast.iter().zip(&stepped) {
+                let ds = (a.s - b.s).abs();
+                assert!(
+                    ds.min(TAU * 1.5 - ds) < 1e-9,
+                    "theta {}: {} vs {}",
+                    theta,
+                    a.s,
+                    b.s
+                );
+                assert!((a.theta - b.theta).abs() < 1e-9);
+                assert!((a.hit_point - b.hit_point).length() < 1e-9);
+                assert!((a.chord - b.chord).abs() < 1e-12);
+                assert!((a.time - b.time).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn chords_clear_of_an_obstacle_skip_it() {
+        // A disk of radius 0.25 centred at (0.5, 0.5) reaches out to about
+        // 0.957 from the centre of the unit circle.
+        let mut spec = circle(1.0);
+        spec.obstacles = sinai(1.0, 0.25).obstacles;
+        let table = spec.to_billiard_table();
+        let config = config(300);
+
+        let clear = BoundaryState::new(0, 0.4, 0.2);
+        assert!(CircleMap::new(&table, &clear, &config).is_some());
+        let stepped = BilliardMap::from_config(&table, &config)
+            .simulate(&clear, config.max_steps())
+            .unwrap()
+            .collisions;
+        let fast = run_trajectory(&table, &clear, &config).unwrap();
+        assert!(stepped.iter().all(|c| c.component_index == 0));
+        assert_eq!(fast.len(), stepped.len());
+        for (a, b) in fast.iter().zip(&stepped) {
+            assert!((a.hit_point - b.hit_point).length() < 1e-9);
+        }
+
+        let blocked = BoundaryState::new(0, 0.4, 1.0);
+        assert!(CircleMap::new(&table, &blocked, &config).is_none());
+        let collisions = run_trajectory(&table, &blocked, &config).unwrap();
+        assert!(collisions.iter().any(|c| c.component_index == 1));
+    }
+
+    #[test]
+    fn far_bounces_come_in_constant_time() {
+        let table = circle(1.0).to_billiard_table();
+        let map = CircleMap::new(&table, &BoundaryState::new(0, 0.0, 0.5), &config(1)).unwrap();
+
+        let n = 1_000_000_007;
+        let c = map.collision(n);
+        assert!((c.s - (n as f64).rem_euclid(TAU)).abs() < 1e-6);
+        assert_eq!(c.theta, 0.5);
+        assert!((c.path_length - n as f64 * 2.0 * 0.5f64.sin()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn max_time_cuts_the_run_short() {
+        let table = circle(1.0).to_billiard_table();
+        let config = SimulationConfig::builder()
+            .max_steps(100)
+            .max_time(10.0)
+            .build()
+            .unwrap();
+        let collisions = run_trajectory(&table, &BoundaryState::new(0, 0.0, 1.0), &config).unwrap();
+        // Chords of 2 sin 1 ≈ 1.683.
+        assert_eq!(collisions.len(), 5);
+        assert!(collisions.iter().all(|c| c.time <= 10.0));
+    }
+
+    #[test]
+    fn obstacles_and_other_walls_need_stepping() {
+        let config = config(10);
+        let sinai = sinai(1.0, 0.25).to_billiard_table();
+        let stadium = stadium(1.0, 0.5).to_billiard_table();
+        // The outer wall of the Sinai table is a square; its disk is convex.
+        assert!(CircleMap::new(&sinai, &BoundaryState::new(0, 0.3, 1.0), &config).is_none());
+        assert!(CircleMap::new(&sinai, &BoundaryState::new(1, 0.3, 1.0), &config).is_none());
+        assert!(CircleMap::new(&stadium, &BoundaryState::new(0, 0.3, 1.0), &config).is_none());
+
+        let gravity = SimulationConfig::builder()
+            .flight(FlightModel::Gravity { g: 1.0 })
+            .build()
+            .unwrap();
+        let table = circle(1.0).to_billiard_table();
+        assert!(CircleMap::new(&table, &BoundaryState::new(0, 0.3, 1.0), &gravity).is_none());
+    }
+}
@@ -0,0 +1,390 @@
+//! Everything that configures a simulation run, in one value.
+//!
+//! A [`SimulationConfig`] is assembled with [`SimulationConfig::builder`],
+//! which starts from the defaults and checks the values once, in
+//! [`SimulationConfigBuilder::build`]. The entry points that take a config
+//! can then rely on it being sane.
+
+use std::fmt;
+
+use crate::dynamics::gallery::WhisperingGallery;
+use crate::dynamics::invariants::InvariantCheck;
+use crate::dynamics::simulation::{CornerPolicy, DEFAULT_EPSILON, FlightModel, StepOptions};
+use crate::geometry::primitives::BoundingBox;
+
+/// Step budget of a configuration that does not set one.
+pub const DEFAULT_MAX_STEPS: usize = 1000;
+
+/// A value rejected by [`SimulationConfigBuilder::build`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigError {
+    /// The ray offset is not positive and finite.
+    InvalidEpsilon(f64),
+
+    /// The corner tolerance is negative or not finite.
+    InvalidCornerTolerance(f64),
+
+    /// The coefficient of restitution is outside `(0, 1]`.
+    InvalidRestitution(f64),
+
+    /// The gravitational acceleration is not finite.
+    InvalidGravity(f64),
+
+    /// The escape box is empty: its minimum is not below its maximum.
+    EmptyEscapeBox,
+
+    /// A free-path histogram was requested with no bins.
+    NoHistogramBins,
+
+    /// The time limit is not positive and finite.
+    InvalidMaxTime(f64),
+
+    /// An invariant tolerance is negative or NaN.
+    InvalidInvariantTolerance(f64),
+
+    /// The largest angle of a whispering-gallery bounce is outside
+    /// `(0, π/2)`.
+    InvalidGalleryAngle(f64),
+
+    /// A whispering gallery was asked to take fewer than two bounces.
+    TooFewGalleryBounces(usize),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidEpsilon(v) => {
+                write!(f, "epsilon must be positive and finite, got {}", v)
+            }
+            ConfigError::InvalidCornerTolerance(v) => {
+                write!(
+                    f,
+                    "corner tolerance must be non-negative and finite, got {}",
+                    v
+                )
+            }
+            ConfigError::InvalidRestitution(v) => {
+                write!(f, "restitution must be in (0, 1], got {}", v)
+            }
+            ConfigError::InvalidGravity(v) => write!(f, "gravity must be finite, got {}", v),
+            ConfigError::EmptyEscapeBox => {
+                write!(f, "escape box min must be strictly less than max")
+            }
+            ConfigError::NoHistogramBins => {
+                write!(f, "histogram bins must be greater than 0")
+            }
+            ConfigError::InvalidMaxTime(v) => {
+                write!(f, "max time must be positive and finite, got {}", v)
+            }
+            ConfigError::InvalidInvariantTolerance(v) => {
+                write!(f, "invariant tolerances must be non-negative, got {}", v)
+            }
+            ConfigError::InvalidGalleryAngle(v) => {
+                write!(f, "whispering-gallery angle must be in (0, π/2), got {}", v)
+            }
+            ConfigError::TooFewGalleryBounces(n) => {
+                write!(f, "whispering galleries need at least 2 bounces, got {}", n)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A checked simulation configuration; see [`SimulationConfig::builder`].
+#[derive(Clone, Copy, Debug)]
+pub struct SimulationConfig {
+    max_steps: usize,
+    max_time: Option<f64>,
+    epsilon: f64,
+    options: StepOptions,
+    free_path_bins: Option<usize>,
+    seed: u64,
+}
+
+impl SimulationConfig {
+    /// Builder starting from the defaults: [`DEFAULT_MAX_STEPS`] steps,
+    /// [`DEFAULT_EPSILON`], the default [`StepOptions`], no statistics and
+    /// seed 0.
+    pub fn builder() -> SimulationConfigBuilder {
+        SimulationConfigBuilder::default()
+    }
+
+    /// Maximum number of collisions to simulate.
+    pub fn max_steps(&self) -> usize {
+        self.max_steps
+    }
+
+    /// Time after which to stop, whatever the number of collisions, if any.
+    pub fn max_time(&self) -> Option<f64> {
+        self.max_time
+    }
+
+    /// Distance a ray must travel before a hit counts.
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Options for each step of the billiard map.
+    pub fn step_options(&self) -> &StepOptions {
+        &self.options
+    }
+
+    /// Number of bins of the free-path histogram, if one was requested.
+    pub fn free_path_bins(&self) -> Option<usize> {
+        self.free_path_bins
+    }
+
+    /// Seed for the stochastic parts of a run (random reflection laws,
+    /// angle noise, sampled initial conditions).
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The same configuration with a different step budget, e.g. to run a
+    /// trajectory in chunks.
+    pub fn with_max_steps(self, max_steps: usize) -> Self {
+        SimulationConfig { max_steps, ..self }
+    }
+
+    /// A builder holding this configuration, to change some of its values.
+    pub fn to_builder(&self) -> SimulationConfigBuilder {
+        SimulationConfigBuilder { config: *self }
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            max_steps: DEFAULT_MAX_STEPS,
+            max_time: None,
+            epsilon: DEFAULT_EPSILON,
+            options: StepOptions::default(),
+            free_path_bins: None,
+            seed: 0,
+        }
+    }
+}
+
+/// Builder for [`SimulationConfig`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SimulationConfigBuilder {
+    config: SimulationConfig,
+}
+
+impl SimulationConfigBuilder {
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.config.max_steps = max_steps;
+        self
+    }
+
+    /// Also stop at time `max_time`; `None` (the default) to run for
+    /// [`SimulationConfig::max_steps`] collisions only.
+    pub fn max_time(mut self, max_time: impl Into<Option<f64>>) -> Self {
+        self.config.max_time = max_time.into();
+        self
+    }
+
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.config.epsilon = epsilon;
+        self
+    }
+
+    pub fn corner_policy(mut self, policy: CornerPolicy) -> Self {
+        self.config.options.corner_policy = policy;
+        self
+    }
+
+    /// Defaults to
+    /// [`DEFAULT_CORNER_TOLERANCE`](crate::dynamics::simulation::DEFAULT_CORNER_TOLERANCE).
+    pub fn corner_tolerance(mut self, tolerance: f64) -> Self {
+        self.config.options.corner_tolerance = tolerance;
+        self
+    }
+
+    /// Declare the particle escaped once it leaves `escape_box`; `None`
+    /// (the default) for a closed table.
+    pub fn escape_box(mut self, escape_box: impl Into<Option<BoundingBox>>) -> Self {
+        self.config.options.escape_box = escape_box.into();
+        self
+    }
+
+    pub fn restitution(mut self, restitution: f64) -> Self {
+        self.config.options.restitution = restitution;
+        self
+    }
+
+    pub fn flight(mut self, flight: FlightModel) -> Self {
+        self.config.options.flight = flight;
+        self
+    }
+
+    /// Check each collision against the conserved quantities of the
+    /// dynamics; `None` (the default) for no checks.
+    pub fn invariants(mut self, check: impl Into<Option<InvariantCheck>>) -> Self {
+        self.config.options.invariants = check.into();
+        self
+    }
+
+    /// Watch for particles creeping along circular arcs; `None` (the
+    /// default) to step every bounce.
+    pub fn whispering_gallery(mut self, gallery: impl Into<Option<WhisperingGallery>>) -> Self {
+        self.config.options.whispering_gallery = gallery.into();
+        self
+    }
+
+    /// Collect a free-path histogram with `bins` bins; `None` (the
+    /// default) for none.
+    pub fn free_path_histogram(mut self, bins: impl Into<Option<usize>>) -> Self {
+        self.config.free_path_bins = bins.into();
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = seed;
+        self
+    }
+
+    /// Check the values and return the configuration.
+    pub fn build(self) -> Result<SimulationConfig, ConfigError> {
+        let config = self.config;
+        let options = &config.options;
+
+        if !(config.epsilon.is_finite() && config.epsilon > 0.0) {
+            return Err(ConfigError::InvalidEpsilon(config.epsilon));
+        }
+        if !(options.corner_tolerance.is_finite() && options.corner_tolerance >= 0.0) {
+            return Err(ConfigError::InvalidCornerTolerance(
+                options.corner_tolerance,
+            ));
+        }
+        if !(options.restitution > 0.0 && options.restitution <= 1.0) {
+            return Err(ConfigError::InvalidRestitution(options.restitution));
+        }
+        if let FlightModel::Gravity { g } = options.flight
+            && !g.is_finite()
+        {
+            return Err(ConfigError::InvalidGravity(g));
+        }
+        if let Some(b) = &options.escape_box
+            && !(b.min.x < b.max.x && b.min.y < b.max.y)
+        {
+            return Err(ConfigError::EmptyEscapeBox);
+        }
+        if config.free_path_bins == Some(0) {
+            return Err(ConfigError::NoHistogramBins);
+        }
+        if let Some(t) = config.max_time
+            && !(t.is_finite() && t > 0.0)
+        {
+            return Err(ConfigError::InvalidMaxTime(t));
+        }
+        if let Some(check) = &options.invariants
+            && let Some(&t) = check.tolerances().iter().find(|t| t.is_nan() || **t < 0.0)
+        {
+            return Err(ConfigError::InvalidInvariantTolerance(t));
+        }
+        if let Some(gallery) = &options.whispering_gallery {
+            let angle = gallery.max_angle;
+            if !(angle > 0.0 && angle < std::f64::consts::FRAC_PI_2) {
+                return Err(ConfigError::InvalidGalleryAngle(angle));
+            }
+            if gallery.min_bounces < 2 {
+                return Err(ConfigError::TooFewGalleryBounces(gallery.min_bounces));
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigError, DEFAULT_MAX_STEPS, SimulationConfig};
+    use crate::dynamics::gallery::WhisperingGallery;
+    use crate::dynamics::invariants::InvariantCheck;
+    use crate::dynamics::simulation::{CornerPolicy, DEFAULT_EPSILON, FlightModel};
+    use crate::geometry::primitives::{BoundingBox, Vec2};
+
+    #[test]
+    fn builder_starts_from_the_defaults() {
+        let config = SimulationConfig::builder().build().unwrap();
+        assert_eq!(config.max_steps(), DEFAULT_MAX_STEPS);
+        assert_eq!(config.epsilon(), DEFAULT_EPSILON);
+        assert_eq!(config.step_options().corner_policy, CornerPolicy::Report);
+        assert_eq!(config.free_path_bins(), None);
+
+        let config = SimulationConfig::builder()
+            .max_steps(50)
+            .corner_policy(CornerPolicy::Terminate)
+            .restitution(0.5)
+            .free_path_histogram(20)
+            .seed(7)
+            .build()
+            .unwrap();
+        assert_eq!(config.max_steps(), 50);
+        assert_eq!(config.step_options().corner_policy, CornerPolicy::Terminate);
+        assert_eq!(config.step_options().restitution, 0.5);
+        assert_eq!(config.free_path_bins(), Some(20));
+        assert_eq!(config.seed(), 7);
+        assert_eq!(config.with_max_steps(3).max_steps(), 3);
+        assert_eq!(config.to_builder().build().unwrap().seed(), 7);
+    }
+
+    #[test]
+    fn build_rejects_bad_values() {
+        let build = |builder: super::SimulationConfigBuilder| builder.build().unwrap_err();
+        let builder = SimulationConfig::builder();
+
+        assert_eq!(
+            build(builder.epsilon(0.0)),
+            ConfigError::InvalidEpsilon(0.0)
+        );
+        assert_eq!(
+            build(builder.restitution(1.5)),
+            ConfigError::InvalidRestitution(1.5)
+        );
+        assert!(matches!(
+            build(builder.flight(FlightModel::Gravity { g: f64::NAN })),
+            ConfigError::InvalidGravity(_)
+        ));
+        // Boxes read from JSON skip the check in `BoundingBox::new`.
+        let flat = BoundingBox {
+            min: Vec2::new(0.0, 0.0),
+            max: Vec2::new(1.0, 0.0),
+        };
+        assert_eq!(build(builder.escape_box(flat)), ConfigError::EmptyEscapeBox);
+        assert_eq!(
+            build(builder.free_path_histogram(0)),
+            ConfigError::NoHistogramBins
+        );
+        assert_eq!(
+            build(builder.max_time(-1.0)),
+            ConfigError::InvalidMaxTime(-1.0)
+        );
+        let check = InvariantCheck {
+            boundary_tolerance: -1.0,
+            ..InvariantCheck::default()
+        };
+        assert_eq!(
+            build(builder.invariants(check)),
+            ConfigError::InvalidInvariantTolerance(-1.0)
+        );
+        let gallery = WhisperingGallery {
+            max_angle: 2.0,
+            ..WhisperingGallery::default()
+        };
+        assert_eq!(
+            build(builder.whispering_gallery(gallery)),
+            ConfigError::InvalidGalleryAngle(2.0)
+        );
+        let gallery = WhisperingGallery {
+            min_bounces: 1,
+            ..WhisperingGallery::default()
+        };
+        assert_eq!(
+            build(builder.whispering_gallery(gallery)),
+            ConfigError::TooFewGalleryBounces(1)
+        );
+    }
+}
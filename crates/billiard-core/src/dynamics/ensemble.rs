@@ -0,0 +1,593 @@
+//! Transport statistics over ensembles of trajectories.
+//!
+//! Particles start from the invariant measure of the billiard map on the
+//! reflecting part of the boundary (see
+//! [`sampling`](crate::dynamics::sampling)), and are followed for a fixed
+//! number of collisions.
+//!
+//! Passing through a periodic segment is not a collision. On a table whose
+//! walls are paired periodically (a unit cell of a Lorentz gas, say) the
+//! particle is followed in the unfolded plane instead (see
+//! [`lattice`](crate::dynamics::lattice)), so its position can wander
+//! arbitrarily far from the cell and the mean-squared displacement measures
+//! diffusion.
+
+use std::fmt;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::config::SimulationConfig;
+use crate::dynamics::lattice::periodic_shift;
+use crate::dynamics::sampling::{SamplingError, try_random_boundary_state};
+use crate::dynamics::simulation::{
+    BilliardMap, CollisionResult, DynamicsError, FlightModel, Progress, ProgressCounter,
+    StepOptions, StepOutcome, Trajectory,
+};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::BoundaryCondition;
+
+/// Consecutive periodic passes after which a flight is abandoned (a ray
+/// running along an infinite corridor never collides).
+const MAX_PASSES_PER_FLIGHT: usize = 1_000;
+
+/// Why the statistics of an ensemble could not be measured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnsembleError {
+    /// No initial conditions could be drawn.
+    Sampling(SamplingError),
+
+    /// The statistics assume straight flights, but under
+    /// [`FlightModel::Gravity`] flights are parabolic.
+    CurvedFlights,
+}
+
+impl fmt::Display for EnsembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnsembleError::Sampling(e) => e.fmt(f),
+            EnsembleError::CurvedFlights => {
+                write!(f, "ensemble statistics need straight flights, not gravity")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnsembleError {}
+
+impl From<SamplingError> for EnsembleError {
+    fn from(e: SamplingError) -> Self {
+        EnsembleError::Sampling(e)
+    }
+}
+
+/// Distribution of the free path between consecutive collisions.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FreePathStatistics {
+    pub mean: f64,
+    pub variance: f64,
+
+    /// Number of free flights measured.
+    pub flights: usize,
+}
+
+impl FreePathStatistics {
+    pub(crate) fn from_sums(sum: f64, sum_sq: f64, flights: usize) -> Self {
+        if flights == 0 {
+            return FreePathStatistics {
+                mean: 0.0,
+                variance: 0.0,
+                flights: 0,
+            };
+        }
+        let mean = sum / flights as f64;
+        FreePathStatistics {
+            mean,
+            variance: (sum_sq / flights as f64 - mean * mean).max(0.0),
+            flights,
+        }
+    }
+
+    /// The statistics of the flights of `self` and `other` together.
+    pub fn combine(&self, other: &FreePathStatistics) -> FreePathStatistics {
+        let sums = |s: &FreePathStatistics| {
+            let n = s.flights as f64;
+            (s.mean * n, (s.variance + s.mean * s.mean) * n)
+        };
+        let (sum, sum_sq) = sums(self);
+        let (other_sum, other_sum_sq) = sums(other);
+        FreePathStatistics::from_sums(
+            sum + other_sum,
+            sum_sq + other_sum_sq,
+            self.flights + other.flights,
+        )
+    }
+}
+
+/// Free paths of a single trajectory, binned.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FreePathHistogram {
+    pub statistics: FreePathStatistics,
+
+    /// Bin `k` counts flights with length in `[k·bin_width, (k+1)·bin_width)`.
+    pub bin_width: f64,
+    pub counts: Vec<usize>,
+
+    /// Flights too long for the last bin.
+    pub overflow: usize,
+}
+
+/// Summary of an ensemble of trajectories, indexed by collision count.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EnsembleStatistics {
+    pub particles: usize,
+
+    /// Collision budget each particle was given.
+    pub collisions: usize,
+
+    /// Distance between consecutive collisions (in the unfolded plane).
+    /// Under [`FlightModel::Gravity`](crate::dynamics::simulation::FlightModel)
+    /// this is the chord, not the length of the parabolic arc.
+    pub free_path: FreePathStatistics,
+
+    /// `⟨v₀ · vₙ⟩` for `n = 0..=collisions`, where `vₙ` is the velocity
+    /// leaving the `n`-th collision and `v₀` the launch velocity.
+    pub velocity_autocorrelation: Vec<f64>,
+
+    /// `⟨|xₙ - x₀|²⟩` for `n = 0..=collisions` over unfolded collision
+    /// points, or `None` if the table has no periodic segments.
+    pub mean_squared_displacement: Option<Vec<f64>>,
+
+    /// Number of particles averaged over at each collision count. Particles
+    /// whose trajectory ends early (by escaping, hitting a terminating
+    /// corner or falling into a hole) only contribute up to their last
+    /// collision.
+    pub samples: Vec<usize>,
+}
+
+/// Collision points and outgoing velocities of one particle, with periodic
+/// passes unfolded.
+struct UnfoldedPath {
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    free_paths: Vec<f64>,
+}
+
+fn unfolded_path(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    collisions: usize,
+    epsilon: f64,
+    options: &StepOptions,
+    counter: &mut ProgressCounter,
+) -> UnfoldedPath {
+    let launch = initial.to_world(table);
+    let mut path = UnfoldedPath {
+        positions: vec![launch.position],
+        velocities: vec![launch.direction * launch.speed],
+        free_paths: Vec::with_capacity(collisions),
+    };
+
+    let mut current = *initial;
+    let mut shift = Vec2::new(0.0, 0.0);
+    let mut flight = 0.0;
+    let mut passes = 0;
+
+    while path.free_paths.len() < collisions && passes < MAX_PASSES_PER_FLIGHT {
+        let Ok(StepOutcome::Collision(c)) =
+            BilliardMap::new(table, epsilon, *options).step(&current)
+        else {
+            break;
+        };
+        counter.tick();
+        flight += c.chord;
+        current = c.outgoing_state();
+        let outgoing = current.to_world(table);
+
+        if let Some(step) = periodic_shift(table, &c) {
+            shift = shift + step;
+            passes += 1;
+        } else {
+            path.positions.push(c.hit_point + shift);
+            path.velocities.push(outgoing.direction * outgoing.speed);
+            path.free_paths.push(flight);
+            flight = 0.0;
+            passes = 0;
+        }
+
+        if options.stops_at(&c) {
+            break;
+        }
+    }
+    path
+}
+
+/// Simulate each of `initials` for up to [`SimulationConfig::max_steps`]
+/// collisions as `config` says, and `measure` its trajectory.
+///
+/// The particles are split between `threads` threads (at least one) in
+/// contiguous runs, and the measurements come back in particle order, so
+/// the result does not depend on the number of threads. Every thread
+/// reports its steps to `progress`, if given. Fails with the first
+/// particle, in order, that cannot be simulated.
+pub fn simulate_particles<T: Send>(
+    table: &BilliardTable,
+    initials: &[BoundaryState],
+    config: &SimulationConfig,
+    threads: usize,
+    progress: Option<&Progress>,
+    measure: impl Fn(&BoundaryState, &Trajectory) -> T + Sync,
+) -> Result<Vec<T>, DynamicsError> {
+    let chunk = initials.len().div_ceil(threads.max(1)).max(1);
+    let measure = &measure;
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = initials
+            .chunks(chunk)
+            .map(|initials| {
+                scope.spawn(move || {
+                    let mut map = BilliardMap::from_config(table, config);
+                    if let Some(progress) = progress {
+                        map = map.reporting_to(progress);
+                    }
+                    initials
+                        .iter()
+                        .map(|initial| {
+                            let trajectory = map.simulate(initial, config.max_steps())?;
+                            Ok(measure(initial, &trajectory))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+            })
+            .collect();
+        let mut measured = Vec::with_capacity(initials.len());
+        for worker in workers {
+            measured.extend(worker.join().expect("ensemble worker panicked")?);
+        }
+        Ok(measured)
+    })
+}
+
+/// Simulate `particles` random initial conditions for up to `collisions`
+/// collisions each and summarize their transport.
+///
+/// The ensemble is reproducible for a given `seed`. Fails if (almost) every
+/// point of the boundary is absorbing or periodic, or if `options` ask for
+/// [`FlightModel::Gravity`]: free paths are measured along straight chords.
+pub fn ensemble_statistics(
+    table: &BilliardTable,
+    particles: usize,
+    collisions: usize,
+    epsilon: f64,
+    options: &StepOptions,
+    seed: u64,
+) -> Result<EnsembleStatistics, EnsembleError> {
+    ensemble_statistics_with_progress(table, particles, collisions, epsilon, options, seed, None)
+}
+
+/// Like [`ensemble_statistics`], telling `progress`, if given, how many
+/// steps have been simulated, over all particles, as the run goes; see
+/// [`BilliardMap::reporting_to`](crate::dynamics::simulation::BilliardMap::reporting_to).
+///
+/// Passes through periodic segments count as steps, so the total may
+/// exceed `particles * collisions`.
+pub fn ensemble_statistics_with_progress(
+    table: &BilliardTable,
+    particles: usize,
+    collisions: usize,
+    epsilon: f64,
+    options: &StepOptions,
+    seed: u64,
+    progress: Option<&Progress>,
+) -> Result<EnsembleStatistics, EnsembleError> {
+    if options.flight != FlightModel::Straight {
+        return Err(EnsembleError::CurvedFlights);
+    }
+    let periodic = table.components().any(|component| {
+        component
+            .segments
+            .iter()
+            .any(|seg| matches!(seg.condition(), BoundaryCondition::PeriodicPairedWith(_)))
+    });
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut samples = vec![0; collisions + 1];
+    let mut correlation = vec![0.0; collisions + 1];
+    let mut displacement = vec![0.0; collisions + 1];
+    let (mut path_sum, mut path_sum_sq, mut flights) = (0.0, 0.0, 0);
+    let mut counter = ProgressCounter::new(progress);
+
+    for _ in 0..particles {
+        let initial = try_random_boundary_state(table, &mut rng)?;
+        let path = unfolded_path(table, &initial, collisions, epsilon, options, &mut counter);
+
+        let (x0, v0) = (path.positions[0], path.velocities[0]);
+        for (n, (x, v)) in path.positions.iter().zip(&path.velocities).enumerate() {
+            samples[n] += 1;
+            correlation[n] += v0.dot(*v);
+            displacement[n] += (*x - x0).length_squared();
+        }
+        for l in &path.free_paths {
+            path_sum += l;
+            path_sum_sq += l * l;
+        }
+        flights += path.free_paths.len();
+    }
+
+    let average = |sums: Vec<f64>| -> Vec<f64> {
+        sums.into_iter()
+            .zip(&samples)
+            .map(|(sum, &count)| if count > 0 { sum / count as f64 } else { 0.0 })
+            .collect()
+    };
+
+    Ok(EnsembleStatistics {
+        particles,
+        collisions,
+        free_path: FreePathStatistics::from_sums(path_sum, path_sum_sq, flights),
+        velocity_autocorrelation: average(correlation),
+        mean_squared_displacement: periodic.then(|| average(displacement)),
+        samples,
+    })
+}
+
+/// Histogram of the free paths along `collisions`, using the
+/// [`chord`](CollisionResult::chord) of each flight.
+///
+/// As in [`ensemble_statistics`], flights interrupted by passes through
+/// periodic segments count as one; a flight still in progress after the
+/// last collision is left out. The `bins` bins cover `[0, max_length)`, or
+/// `[0, longest flight]` if `max_length` is `None`.
+///
+/// # Panics
+/// Panics if `bins` is zero or `max_length` is not strictly positive and
+/// finite.
+pub fn free_path_histogram(
+    table: &BilliardTable,
+    collisions: &[CollisionResult],
+    bins: usize,
+    max_length: Option<f64>,
+) -> FreePathHistogram {
+    assert!(bins > 0, "Histogram needs at least one bin.");
+    if let Some(max) = max_length {
+        assert!(
+            max.is_finite() && max > 0.0,
+            "Histogram range must be positive and finite."
+        );
+    }
+
+    let mut flights = Vec::new();
+    let mut flight = 0.0;
+    for c in collisions {
+        flight += c.chord;
+        if periodic_shift(table, c).is_none() {
+            flights.push(flight);
+            flight = 0.0;
+        }
+    }
+
+    let longest = flights.iter().copied().fold(0.0, f64::max);
+    let bin_width = max_length.unwrap_or(longest) / bins as f64;
+    let mut counts = vec![0; bins];
+    let mut overflow = 0;
+    for &length in &flights {
+        let k = if bin_width > 0.0 {
+            (length / bin_width) as usize
+        } else {
+            0
+        };
+        match (k < bins, max_length) {
+            (true, _) => counts[k] += 1,
+            // Without an explicit range the longest flight closes the last bin.
+            (false, None) => counts[bins - 1] += 1,
+            (false, Some(_)) => overflow += 1,
+        }
+    }
+
+    let sum = flights.iter().sum();
+    let sum_sq = flights.iter().map(|l| l * l).sum();
+    FreePathHistogram {
+        statistics: FreePathStatistics::from_sums(sum, sum_sq, flights.len()),
+        bin_width,
+        counts,
+        overflow,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        EnsembleError, FreePathStatistics, ensemble_statistics, ensemble_statistics_with_progress,
+        free_path_histogram, simulate_particles,
+    };
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::sampling::{Sampling, sample_boundary_states};
+    use crate::dynamics::simulation::{BilliardMap, FlightModel, StepOptions, Trajectory};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{lorentz_gas, regular_polygon, sinai};
+    use std::f64::consts::PI;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn sinai_mean_free_path_matches_santalo_formula() {
+        // ⟨ℓ⟩ = π |Q| / |∂Q| for the billiard map's invariant measure.
+        let r = 0.2;
+        let table = sinai(1.0, r).to_billiard_table();
+        let stats =
+            ensemble_statistics(&table, 200, 200, 1e-8, &StepOptions::default(), 3).unwrap();
+
+        let expected = PI * (1.0 - PI * r * r) / (4.0 + 2.0 * PI * r);
+        assert!(
+            (stats.free_path.mean - expected).abs() < 0.03 * expected,
+            "mean free path {} vs {}",
+            stats.free_path.mean,
+            expected
+        );
+        assert!(stats.mean_squared_displacement.is_none());
+        assert!(stats.samples.iter().all(|&n| n == 200));
+        assert!((stats.velocity_autocorrelation[0] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn free_paths_are_not_measured_under_gravity() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let options = StepOptions {
+            flight: FlightModel::Gravity { g: 1.0 },
+            ..StepOptions::default()
+        };
+        assert_eq!(
+            ensemble_statistics(&table, 10, 10, 1e-8, &options, 3),
+            Err(EnsembleError::CurvedFlights)
+        );
+    }
+
+    #[test]
+    fn progress_counts_every_step_of_the_ensemble() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let steps = AtomicUsize::new(0);
+        let progress = |n: usize| {
+            steps.fetch_add(n, Ordering::Relaxed);
+        };
+        let options = StepOptions::default();
+        let stats =
+            ensemble_statistics_with_progress(&table, 20, 300, 1e-8, &options, 3, Some(&progress))
+                .unwrap();
+
+        // Without periodic walls, every step is a collision.
+        assert_eq!(steps.load(Ordering::Relaxed), 20 * 300);
+        assert_eq!(
+            stats,
+            ensemble_statistics(&table, 20, 300, 1e-8, &options, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn particles_are_measured_in_order_whatever_the_threads() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initials = sample_boundary_states(&table, &Sampling::Random { count: 7 }, 1);
+        let config = SimulationConfig::builder()
+            .max_steps(50)
+            .epsilon(1e-8)
+            .build()
+            .unwrap();
+        let last_hit = |_: &BoundaryState, trajectory: &Trajectory| {
+            trajectory.collisions.last().map(|c| c.hit_point)
+        };
+
+        let one = simulate_particles(&table, &initials, &config, 1, None, last_hit).unwrap();
+        let three = simulate_particles(&table, &initials, &config, 3, None, last_hit).unwrap();
+        assert_eq!(one.len(), 7);
+        assert_eq!(one, three);
+        for (initial, hit) in initials.iter().zip(&one) {
+            let trajectory = BilliardMap::new(&table, 1e-8, StepOptions::default())
+                .simulate(initial, 50)
+                .unwrap();
+            assert_eq!(*hit, trajectory.collisions.last().map(|c| c.hit_point));
+        }
+    }
+
+    #[test]
+    fn lorentz_gas_diffuses_in_the_unfolded_plane() {
+        // The horizon is infinite, but with r = 0.4 only the corridors of
+        // width 0.2 along the rows and columns stay open.
+        let r = 0.4;
+        let table = lorentz_gas(1.0, r).to_billiard_table();
+        let stats =
+            ensemble_statistics(&table, 200, 200, 1e-8, &StepOptions::default(), 5).unwrap();
+
+        // Only the scatterer counts toward the boundary length.
+        let expected = PI * (1.0 - PI * r * r) / (2.0 * PI * r);
+        assert!(
+            (stats.free_path.mean - expected).abs() < 0.03 * expected,
+            "mean free path {} vs {}",
+            stats.free_path.mean,
+            expected
+        );
+
+        let msd = stats
+            .mean_squared_displacement
+            .clone()
+            .expect("the cell has periodic walls");
+        assert_eq!(msd[0], 0.0);
+        // Far beyond the cell, and growing roughly linearly.
+        assert!(msd[200] > 10.0, "MSD(200) = {}", msd[200]);
+        assert!(msd[200] > 1.5 * msd[100]);
+
+        // Velocities decorrelate after a few collisions.
+        assert!(stats.velocity_autocorrelation[20].abs() < 0.1);
+
+        let again =
+            ensemble_statistics(&table, 200, 200, 1e-8, &StepOptions::default(), 5).unwrap();
+        assert_eq!(stats, again);
+    }
+
+    #[test]
+    fn square_free_paths_are_binned() {
+        // Straight up and down in a square of side √2: every flight is √2.
+        let table = regular_polygon(4, 1.0).to_billiard_table();
+        let side = 2.0_f64.sqrt();
+        let initial = BoundaryState::new(0, 0.5 * side, std::f64::consts::FRAC_PI_2);
+        let collisions = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 10)
+            .unwrap()
+            .collisions;
+
+        let histogram = free_path_histogram(&table, &collisions, 4, None);
+        assert_eq!(histogram.counts, vec![0, 0, 0, 10]);
+        assert!((histogram.bin_width - side / 4.0).abs() < 1e-12);
+        assert!((histogram.statistics.mean - side).abs() < 1e-12);
+        assert!(histogram.statistics.variance < 1e-20);
+
+        let clipped = free_path_histogram(&table, &collisions, 2, Some(1.0));
+        assert_eq!(clipped.counts, vec![0, 0]);
+        assert_eq!(clipped.overflow, 10);
+    }
+
+    #[test]
+    fn combined_statistics_pool_the_flights() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let collisions = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&BoundaryState::new(0, 0.3, 1.0), 300)
+            .unwrap()
+            .collisions;
+        let whole = free_path_histogram(&table, &collisions, 1, None).statistics;
+        let first = free_path_histogram(&table, &collisions[..100], 1, None).statistics;
+        let rest = free_path_histogram(&table, &collisions[100..], 1, None).statistics;
+
+        let combined = first.combine(&rest);
+        assert_eq!(combined.flights, whole.flights);
+        assert!((combined.mean - whole.mean).abs() < 1e-12);
+        assert!((combined.variance - whole.variance).abs() < 1e-12);
+        let empty = FreePathStatistics::from_sums(0.0, 0.0, 0);
+        assert_eq!(empty.combine(&whole), whole);
+    }
+
+    #[test]
+    fn periodic_passes_merge_into_one_flight() {
+        let table = lorentz_gas(1.0, 0.4).to_billiard_table();
+        let initial = BoundaryState::new(1, 0.3, 1.0);
+        let collisions = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 2_000)
+            .unwrap()
+            .collisions;
+
+        let histogram = free_path_histogram(&table, &collisions, 20, None);
+        let stats = histogram.statistics;
+        assert_eq!(histogram.counts.iter().sum::<usize>(), stats.flights);
+        assert!(stats.flights < collisions.len());
+
+        // Close to the ensemble mean free path π|Q| / |∂Q|.
+        let r = 0.4;
+        let expected = PI * (1.0 - PI * r * r) / (2.0 * PI * r);
+        assert!(
+            (stats.mean - expected).abs() < 0.1 * expected,
+            "mean {}",
+            stats.mean
+        );
+    }
+}
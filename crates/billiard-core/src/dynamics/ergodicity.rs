@@ -0,0 +1,227 @@
+//! Quantitative equidistribution checks for long trajectories.
+//!
+//! For an ergodic table, the bounces of a typical trajectory fill the
+//! phase space `(s, cos θ)` uniformly (see
+//! [`sampling`](crate::dynamics::sampling) for why `cos θ` rather than
+//! `θ`). Binning them on a grid and comparing with the uniform measure
+//! turns a Poincaré plot into numbers.
+//!
+//! Consecutive bounces are correlated, so the χ² statistic does not follow
+//! the χ² distribution exactly; treat it as a relative measure. For a
+//! chaotic table it stays of the order of the number of cells, while
+//! integrable tables give values orders of magnitude larger.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::phase_space::{PhaseSpaceHistogram, phase_space_histogram};
+use crate::dynamics::sampling::{boundary_point, reflects_at};
+use crate::dynamics::simulation::CollisionResult;
+use crate::geometry::boundary::BilliardTable;
+
+/// Points per arc-length bin used to measure how much of it can be hit.
+const COVERAGE_SAMPLES: usize = 64;
+
+/// How evenly a trajectory covers phase space.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EquidistributionReport {
+    /// Bins in total arc length (all components laid end to end).
+    pub s_bins: usize,
+
+    /// Bins in `cos θ` over `(-1, 1)`.
+    pub p_bins: usize,
+
+    /// Bounces in each cell; cell `(i, j)` is at `i * p_bins + j`.
+    pub counts: Vec<usize>,
+
+    /// Bounces binned (passes through periodic segments are skipped).
+    pub samples: usize,
+
+    /// Pearson's χ² against the expected counts of the invariant measure.
+    pub chi_squared: f64,
+
+    /// Cells that can be hit, minus one.
+    pub degrees_of_freedom: usize,
+
+    /// Largest difference between the observed and expected fraction of
+    /// bounces in a box `[0, s] × [-1, p]` with corners on the grid (the
+    /// star discrepancy restricted to the grid).
+    pub discrepancy: f64,
+}
+
+impl EquidistributionReport {
+    /// `(χ² - dof) / √(2 dof)`: roughly a standard normal for independent
+    /// uniform samples, and large for trajectories stuck on an invariant
+    /// curve or in an island. `None` with no degrees of freedom, when at
+    /// most one cell can be hit.
+    pub fn z_score(&self) -> Option<f64> {
+        if self.degrees_of_freedom == 0 {
+            return None;
+        }
+        let dof = self.degrees_of_freedom as f64;
+        Some((self.chi_squared - dof) / (2.0 * dof).sqrt())
+    }
+}
+
+/// Bin the outgoing states of `collisions` on an `s_bins × p_bins` grid
+/// and compare with the invariant measure.
+///
+/// Arc-length bins partly covered by holes or periodic segments expect
+/// proportionally fewer bounces.
+///
+/// # Panics
+/// Panics if either bin count is zero.
+pub fn equidistribution(
+    table: &BilliardTable,
+    collisions: &[CollisionResult],
+    s_bins: usize,
+    p_bins: usize,
+) -> EquidistributionReport {
+    assert!(
+        s_bins > 0 && p_bins > 0,
+        "Equidistribution needs at least one bin in each direction."
+    );
+
+    let total = table.perimeter();
+    let PhaseSpaceHistogram {
+        counts, samples, ..
+    } = phase_space_histogram(table, [collisions], s_bins, p_bins);
+
+    // Fraction of each arc-length bin a bounce can land on.
+    let coverage: Vec<f64> = (0..s_bins)
+        .map(|i| {
+            let hits = (0..COVERAGE_SAMPLES)
+                .filter(|&k| {
+                    let u = (i as f64 + (k as f64 + 0.5) / COVERAGE_SAMPLES as f64) * total
+                        / s_bins as f64;
+                    let (component_index, s) = boundary_point(table, u);
+                    reflects_at(table, component_index, s)
+                })
+                .count();
+            hits as f64 / COVERAGE_SAMPLES as f64
+        })
+        .collect();
+    let covered: f64 = coverage.iter().sum();
+
+    let expected_fraction = |i: usize| coverage[i] / (covered * p_bins as f64);
+
+    let mut chi_squared = 0.0;
+    let mut cells = 0;
+    for i in 0..s_bins {
+        let expected = samples as f64 * expected_fraction(i);
+        if expected == 0.0 {
+            continue;
+        }
+        for j in 0..p_bins {
+            let observed = counts[i * p_bins + j] as f64;
+            chi_squared += (observed - expected).powi(2) / expected;
+            cells += 1;
+        }
+    }
+
+    // Cumulative observed and expected fractions over anchored boxes.
+    let mut discrepancy: f64 = 0.0;
+    let mut column = vec![0usize; p_bins];
+    let mut expected_columns = 0.0;
+    for i in 0..s_bins {
+        for (j, total_below) in column.iter_mut().enumerate() {
+            *total_below += counts[i * p_bins + j];
+        }
+        expected_columns += expected_fraction(i) * p_bins as f64;
+
+        let mut observed = 0;
+        for (j, &count) in column.iter().enumerate() {
+            observed += count;
+            let expected = expected_columns * (j + 1) as f64 / p_bins as f64;
+            let fraction = observed as f64 / samples.max(1) as f64;
+            discrepancy = discrepancy.max((fraction - expected).abs());
+        }
+    }
+
+    EquidistributionReport {
+        s_bins,
+        p_bins,
+        counts,
+        samples,
+        chi_squared,
+        degrees_of_freedom: cells.max(1) - 1,
+        discrepancy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::equidistribution;
+    use crate::dynamics::simulation::{BilliardMap, StepOptions};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{lorentz_gas, regular_polygon, sinai};
+
+    #[test]
+    fn chaotic_table_fills_phase_space() {
+        let table = sinai(1.0, 0.25).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let collisions = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 100_000)
+            .unwrap()
+            .collisions;
+
+        let report = equidistribution(&table, &collisions, 10, 5);
+        assert_eq!(report.samples, 100_000);
+        assert_eq!(report.counts.iter().sum::<usize>(), 100_000);
+        assert_eq!(report.degrees_of_freedom, 49);
+        assert!(
+            report.discrepancy < 0.02,
+            "discrepancy {}",
+            report.discrepancy
+        );
+        let z = report.z_score().unwrap();
+        assert!(z < 20.0, "z = {}", z);
+    }
+
+    #[test]
+    fn integrable_table_fails_the_check() {
+        // In a square only four directions ever occur.
+        let table = regular_polygon(4, 1.0).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let collisions = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 10_000)
+            .unwrap()
+            .collisions;
+
+        let report = equidistribution(&table, &collisions, 8, 8);
+        let z = report.z_score().unwrap();
+        assert!(z > 100.0, "z = {}", z);
+        assert!(report.discrepancy > 0.1);
+    }
+
+    #[test]
+    fn periodic_walls_are_excluded() {
+        let table = lorentz_gas(1.0, 0.4).to_billiard_table();
+        let initial = BoundaryState::new(1, 0.1, 1.0);
+        let collisions = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 5_000)
+            .unwrap()
+            .collisions;
+
+        // The walls take the first 4 of the 4 + 0.8π ≈ 6.5 total arc
+        // length: the first six of ten bins expect (and get) nothing.
+        let report = equidistribution(&table, &collisions, 10, 4);
+        assert!(report.samples < collisions.len());
+        assert!(report.counts[..6 * 4].iter().all(|&n| n == 0));
+        assert_eq!(report.degrees_of_freedom, 4 * 4 - 1);
+    }
+
+    #[test]
+    fn a_single_cell_has_no_z_score() {
+        let table = sinai(1.0, 0.25).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let collisions = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 100)
+            .unwrap()
+            .collisions;
+
+        let report = equidistribution(&table, &collisions, 1, 1);
+        assert_eq!(report.degrees_of_freedom, 0);
+        assert_eq!(report.z_score(), None);
+    }
+}
@@ -0,0 +1,369 @@
+//! Detecting, and stepping over, whispering-gallery orbits.
+//!
+//! A particle that leaves a concave circular arc at a small angle `θ` to
+//! the tangent creeps along it in chords of length `2r sin θ`, leaving every
+//! bounce at the same angle: a whispering-gallery orbit. As `θ → 0` it takes
+//! millions of ray tests to get around a bend, each losing a little
+//! accuracy. A [`GalleryGuard`], configured through
+//! [`StepOptions::whispering_gallery`], counts such bounces in a row; once
+//! there are enough it either ends the trajectory, or has the simulation
+//! skip to the last bounce on the arc with [`skip_along_arc`], which is
+//! exact on a circle.
+//!
+//! [`StepOptions::whispering_gallery`]: crate::dynamics::simulation::StepOptions::whispering_gallery
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::circle_map::reach;
+use crate::dynamics::simulation::CollisionResult;
+use crate::dynamics::state::{BoundaryState, WorldState};
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::segments::{BoundaryCondition, BoundarySegment};
+
+/// Largest angle to the tangent of a creeping bounce, unless set otherwise.
+pub const DEFAULT_GALLERY_ANGLE: f64 = 1e-3;
+
+/// Creeping bounces in a row that make a whispering gallery, unless set
+/// otherwise.
+pub const DEFAULT_GALLERY_BOUNCES: usize = 64;
+
+/// What the simulation does about a whispering gallery once it has found
+/// one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum GalleryAction {
+    /// Jump to the last bounce on the arc in one step, with
+    /// [`skip_along_arc`]. Only straight, elastic, specular flights are
+    /// skipped; others carry on bounce by bounce.
+    #[default]
+    Aggregate,
+
+    /// End the trajectory with
+    /// [`Termination::WhisperingGallery`](crate::dynamics::simulation::Termination::WhisperingGallery).
+    Terminate,
+}
+
+/// When a run of bounces counts as a whispering gallery, and what to do
+/// about it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WhisperingGallery {
+    /// Largest angle, in radians, between the outgoing direction and the
+    /// tangent (either way along it) of a creeping bounce.
+    #[serde(default = "default_angle")]
+    pub max_angle: f64,
+
+    /// Creeping bounces in a row on one circular arc that make a gallery.
+    #[serde(default = "default_bounces")]
+    pub min_bounces: usize,
+
+    #[serde(default)]
+    pub action: GalleryAction,
+}
+
+fn default_angle() -> f64 {
+    DEFAULT_GALLERY_ANGLE
+}
+
+fn default_bounces() -> usize {
+    DEFAULT_GALLERY_BOUNCES
+}
+
+impl WhisperingGallery {
+    /// The default thresholds, with `action`.
+    pub fn new(action: GalleryAction) -> Self {
+        WhisperingGallery {
+            max_angle: DEFAULT_GALLERY_ANGLE,
+            min_bounces: DEFAULT_GALLERY_BOUNCES,
+            action,
+        }
+    }
+}
+
+impl Default for WhisperingGallery {
+    fn default() -> Self {
+        WhisperingGallery::new(GalleryAction::default())
+    }
+}
+
+/// Angle between an outgoing direction at `theta` and the nearer direction
+/// along the tangent.
+fn grazing_angle(theta: f64) -> f64 {
+    theta.min(PI - theta)
+}
+
+/// Counts creeping bounces in a row on one arc.
+#[derive(Clone, Copy, Debug)]
+pub struct GalleryGuard {
+    gallery: WhisperingGallery,
+
+    /// Component and segment of the arc of the current run.
+    arc: Option<(usize, usize)>,
+    run: usize,
+}
+
+impl GalleryGuard {
+    pub fn new(gallery: WhisperingGallery) -> Self {
+        GalleryGuard {
+            gallery,
+            arc: None,
+            run: 0,
+        }
+    }
+
+    pub fn gallery(&self) -> &WhisperingGallery {
+        &self.gallery
+    }
+
+    /// Count `collision`, and return whether it completes a run of
+    /// [`WhisperingGallery::min_bounces`] creeping bounces on one circular
+    /// arc. The count then starts over.
+    pub fn observe(&mut self, table: &BilliardTable, collision: &CollisionResult) -> bool {
+        let arc = (collision.component_index, collision.segment_index);
+        let on_arc = matches!(
+            table.component(arc.0).segments[arc.1],
+            BoundarySegment::CircularArc(_)
+        );
+        if !on_arc || grazing_angle(collision.theta) > self.gallery.max_angle {
+            self.arc = None;
+            self.run = 0;
+            return false;
+        }
+
+        if self.arc == Some(arc) {
+            self.run += 1;
+        } else {
+            self.arc = Some(arc);
+            self.run = 1;
+        }
+        if self.run < self.gallery.min_bounces {
+            return false;
+        }
+        self.run = 0;
+        true
+    }
+}
+
+/// The last bounce on the arc of a creeping particle, reached in one step;
+/// see [`skip_along_arc`].
+pub struct ArcSkip {
+    /// The last bounce, whose `chord`, running totals and
+    /// [`skipped`](CollisionResult::skipped) count cover every bounce
+    /// stepped over.
+    pub collision: CollisionResult,
+
+    /// The particle leaving the bounce before the last one.
+    pub last_launch: WorldState,
+
+    /// Flight time of the last chord.
+    pub last_flight_time: f64,
+}
+
+/// Step a particle leaving the boundary at `state` along the circular arc
+/// it lies on, up to its last bounce on the arc, at least one chord short
+/// of either end.
+///
+/// Inside a circle, a chord leaving at angle `θ` to the tangent ends `2θ`
+/// further round and leaves again at `θ`, so the bounces are known in
+/// closed form. Returns `None` unless the arc is concave and reflecting,
+/// its component has no holes, every other component lies inside the
+/// caustic `r cos θ` the chords are tangent to, with `epsilon` to spare,
+/// and at least two bounces are left to skip; the flight is taken to be
+/// straight, elastic and specular.
+pub fn skip_along_arc(
+    table: &BilliardTable,
+    state: &BoundaryState,
+    epsilon: f64,
+) -> Option<ArcSkip> {
+    let component = table.get_component(state.component_index)?;
+    let (segment_index, local_t) = component.checked_locate(state.s)?;
+    let segment = &component.segments[segment_index];
+    let BoundarySegment::CircularArc(arc) = segment else {
+        return None;
+    };
+    if segment.condition() != BoundaryCondition::Reflect || !component.holes.is_empty() {
+        return None;
+    }
+    let (point, inward_normal) = component.point_and_inward_normal_at(state.s);
+    if inward_normal.dot(arc.center - point) <= 0.0 {
+        return None;
+    }
+
+    let angle = grazing_angle(state.theta);
+    let clearance = arc.radius * angle.cos() - epsilon;
+    for (index, other) in table.components().enumerate() {
+        if index == state.component_index {
+            continue;
+        }
+        for segment in &other.segments {
+            if reach(segment, arc.center)? >= clearance {
+                return None;
+            }
+        }
+    }
+
+    let step = 2.0 * arc.radius * angle;
+    let (forward, room) = if state.theta < FRAC_PI_2 {
+        (1.0, arc.length() - local_t)
+    } else {
+        (-1.0, local_t)
+    };
+    if step.is_nan() || step <= 0.0 || !room.is_finite() {
+        return None;
+    }
+    let bounces = (room / step).floor() - 1.0;
+    if bounces < 2.0 || bounces > usize::MAX as f64 {
+        return None;
+    }
+
+    let at = |k: f64| BoundaryState {
+        s: component.global_s_from_segment_local(segment_index, local_t + forward * k * step),
+        ..*state
+    };
+    let chord = 2.0 * arc.radius * angle.sin();
+    let path = bounces * chord;
+    let last = at(bounces);
+    let hit_point = segment.point_at(local_t + forward * bounces * step);
+
+    Some(ArcSkip {
+        collision: CollisionResult {
+            time: path / state.speed,
+            skipped: bounces as usize - 1,
+            ..CollisionResult::new(last, segment_index, hit_point, path, None, false)
+                .labelled(table)
+        },
+        last_launch: at(bounces - 1.0).to_world(table),
+        last_flight_time: chord / state.speed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use super::{GalleryAction, GalleryGuard, WhisperingGallery, skip_along_arc};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::invariants::InvariantCheck;
+    use crate::dynamics::simulation::{BilliardMap, Termination};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{annulus, circle, sinai, stadium};
+
+    fn config(action: GalleryAction) -> SimulationConfig {
+        SimulationConfig::builder()
+            .max_steps(1000)
+            .whispering_gallery(WhisperingGallery {
+                min_bounces: 20,
+                ..WhisperingGallery::new(action)
+            })
+            .invariants(InvariantCheck::default())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn skipping_matches_stepping_bounce_by_bounce() {
+        let table = circle(1.0).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.5, 1e-4);
+
+        let stepped = BilliardMap::new(&table, 1e-8, Default::default())
+            .simulate(&initial, 200)
+            .unwrap();
+        let skip = skip_along_arc(&table, &stepped.collisions[0].outgoing_state(), 1e-8).unwrap();
+
+        // Room for (2π - 0.5 - 2e-4) / 2e-4 chords; one is left over.
+        let bounces = skip.collision.skipped + 1;
+        assert_eq!(bounces, 28913);
+        let expected = 0.5 + 2e-4 * (bounces + 1) as f64;
+        assert!((skip.collision.s - expected).abs() < 1e-9);
+        assert!((skip.collision.theta - 1e-4).abs() < 1e-15);
+        assert!((skip.collision.chord - bounces as f64 * 2.0 * 1e-4_f64.sin()).abs() < 1e-9);
+
+        // A short run agrees with the stepped bounces, up to their rounding.
+        let near = BoundaryState::new(0, TAU - 0.01, 1e-4);
+        let skip = skip_along_arc(&table, &near, 1e-8).unwrap();
+        let stepped = BilliardMap::new(&table, 1e-8, Default::default())
+            .simulate(&near, skip.collision.skipped + 1)
+            .unwrap();
+        let last = stepped.collisions.last().unwrap();
+        assert!((last.s - skip.collision.s).abs() < 1e-10);
+        assert!((last.hit_point - skip.collision.hit_point).length() < 1e-10);
+        assert!((last.path_length - skip.collision.path_length).abs() < 1e-10);
+    }
+
+    #[test]
+    fn only_concave_arcs_are_skipped() {
+        // The Sinai disk is convex, seen from the table.
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        assert!(skip_along_arc(&table, &BoundaryState::new(1, 0.3, 1e-4), 1e-8).is_none());
+        // Lines are not arcs.
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        assert!(skip_along_arc(&table, &BoundaryState::new(0, 0.3, 1e-4), 1e-8).is_none());
+    }
+
+    #[test]
+    fn obstacles_in_the_band_stop_the_skip() {
+        // At 0.1 from the tangent the chords stay beyond r cos 0.1 ≈ 0.995
+        // from the center, where this disk reaches 0.998.
+        let near = BoundaryState::new(0, 0.5, 0.1);
+        let table = annulus(1.0, 0.058, 0.94).to_billiard_table();
+        assert!(skip_along_arc(&table, &near, 1e-8).is_none());
+
+        // Well inside the caustic, the disk is never hit.
+        let table = annulus(1.0, 0.3, 0.2).to_billiard_table();
+        assert!(skip_along_arc(&table, &near, 1e-8).is_some());
+    }
+
+    #[test]
+    fn galleries_are_aggregated_or_end_the_trajectory() {
+        let table = circle(1.0).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.5, 1e-5);
+
+        let stopped = BilliardMap::from_config(&table, &config(GalleryAction::Terminate))
+            .simulate(&initial, 1000)
+            .unwrap();
+        assert_eq!(stopped.termination, Termination::WhisperingGallery);
+        assert_eq!(stopped.collisions.len(), 20);
+
+        // Each round of the circle: 20 bounces, one skip to the end of the
+        // arc, and a few more across the junction.
+        let skipped = BilliardMap::from_config(&table, &config(GalleryAction::Aggregate))
+            .simulate(&initial, 100)
+            .unwrap();
+        assert_eq!(skipped.termination, Termination::MaxSteps);
+        let skips: Vec<_> = skipped
+            .collisions
+            .iter()
+            .filter(|c| c.skipped > 0)
+            .collect();
+        assert!(skips.len() >= 3, "{} skips", skips.len());
+        let last = skipped.collisions.last().unwrap();
+        let bounces: usize = skipped.collisions.iter().map(|c| c.skipped + 1).sum();
+        assert!((last.path_length - bounces as f64 * 2.0 * 1e-5_f64.sin()).abs() < 1e-9);
+        assert!((last.theta - 1e-5).abs() < 1e-12);
+
+        let report = skipped.invariants.unwrap();
+        assert!(!report.breached(), "{:?}", report);
+    }
+
+    #[test]
+    fn steep_bounces_break_a_run() {
+        let table = circle(1.0).to_billiard_table();
+        let gallery = WhisperingGallery {
+            min_bounces: 3,
+            ..WhisperingGallery::default()
+        };
+        let mut guard = GalleryGuard::new(gallery);
+        let trajectory = BilliardMap::new(&table, 1e-8, Default::default())
+            .simulate(&BoundaryState::new(0, 0.5, 1.0), 10)
+            .unwrap();
+        assert!(
+            trajectory
+                .collisions
+                .iter()
+                .all(|c| !guard.observe(&table, c))
+        );
+    }
+}
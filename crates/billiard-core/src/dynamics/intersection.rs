@@ -1,7 +1,12 @@
 use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
-use crate::geometry::primitives::Vec2;
+use crate::geometry::primitives::{BoundingBox, Vec2};
 use crate::geometry::segments::{BoundarySegment, CircularArcSegment, LineSegment};
 
+/// Relative size below which `b² − c` for a ray and a circle (or `b`, for a
+/// ray leaving a point of the circle) is taken for rounding error, so that
+/// the ray touches the circle instead of crossing it or missing it.
+const TANGENCY_TOLERANCE: f64 = 1e-15;
+
 /// A half-line (ray) in ℝ² originating at `origin` and extending in direction `direction`.
 pub struct Ray {
     /// Origin point of the ray.
@@ -14,6 +19,58 @@ pub struct Ray {
     pub direction: Vec2,
 }
 
+/// What counts as a hit when intersecting a flight with the table.
+///
+/// A flight that starts with a bounce starts on the boundary, a rounding
+/// error to one side or the other of the segment it leaves. A fixed
+/// minimum distance along the flight cannot tell that segment from the
+/// next hit: large enough to skip the origin at steep angles, it also
+/// skips the short chords of grazing bounces. Instead, the segment the
+/// flight leaves is identified by index and only its crossings away from
+/// the origin are kept; a straight flight never meets its own line segment
+/// again, and on an arc only the far end of the chord counts. Any other
+/// segment is hit only if the origin lies more than `tolerance` from it,
+/// measured along its normal at the hit, so that a bounce next to a corner
+/// does not count the neighbouring segment while a long grazing flight
+/// still does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IntersectionOptions {
+    /// Distance below which the origin is taken to lie on a segment, and
+    /// hits on that segment are ignored. Under gravity, and on external
+    /// curves, it is the path length flown before any hit counts.
+    pub tolerance: f64,
+
+    /// The segment the flight leaves, as `(component_index,
+    /// segment_index)`, or `None` for a flight from the interior.
+    pub leaving: Option<(usize, usize)>,
+}
+
+impl IntersectionOptions {
+    /// A flight from the interior of the table.
+    pub fn new(tolerance: f64) -> Self {
+        IntersectionOptions {
+            tolerance,
+            leaving: None,
+        }
+    }
+
+    /// The same options for a flight that leaves the boundary at segment
+    /// `segment_index` of component `component_index`.
+    pub fn leaving(self, component_index: usize, segment_index: usize) -> Self {
+        IntersectionOptions {
+            leaving: Some((component_index, segment_index)),
+            ..self
+        }
+    }
+
+    /// The segment left on component `component_index`, if any.
+    fn leaving_segment(&self, component_index: usize) -> Option<usize> {
+        self.leaving
+            .filter(|&(c, _)| c == component_index)
+            .map(|(_, segment)| segment)
+    }
+}
+
 /// Result of intersecting a ray with the billiard boundary.
 ///
 /// This describes:
@@ -34,19 +91,24 @@ pub struct Intersection {
     ///
     /// If `direction` is unit-length, this is also the Euclidean distance.
     pub ray_parameter: f64,
+
+    /// Whether the ray only touches the segment there, as a ray tangent to
+    /// a circular arc does, instead of crossing it.
+    pub tangent: bool,
 }
 
 impl Ray {
     /// Intersect this ray with a single line segment.
     ///
     /// Returns the intersection that is:
-    /// - in front of the ray origin (t > epsilon),
+    /// - in front of the ray origin (t > 0), with the origin more than
+    ///   `tolerance` away from the segment's line,
     /// - between the segment endpoints (segment parameter between 0 and segment length),
     ///   or `None` if there is no such point.
     pub fn intersect_line_segment(
         &self,
         segment: &LineSegment,
-        epsilon: f64,
+        tolerance: f64,
     ) -> Option<(f64, f64)> {
         let p = self.origin;
         let mut r = self.direction;
@@ -63,7 +125,7 @@ impl Ray {
         let s_vec = segment.end - segment.start;
         let seg_len = s_vec.length();
 
-        if seg_len <= epsilon {
+        if seg_len <= tolerance {
             // Degenerate segment
             return None;
         }
@@ -83,45 +145,67 @@ impl Ray {
 
         let q_minus_p = q - p;
 
-        let t = cross(q_minus_p, s) / denom;
-        let u = cross(q_minus_p, r) / denom;
+        // Signed distance of the origin from the segment's line.
+        let offset = cross(q_minus_p, s);
+        let t = offset / denom;
 
-        // u is the fraction along the segment direction; local arc-length is u * seg_len.
-        let local_t = u * seg_len;
+        // With `s` normalized this is already the arc length along the segment.
+        let local_t = cross(q_minus_p, r) / denom;
 
-        if t > epsilon && (0.0..=1.0).contains(&u) {
+        if offset.abs() > tolerance && t > 0.0 && (0.0..=seg_len).contains(&local_t) {
             Some((t, local_t))
         } else {
             None
         }
     }
 
+    /// Coefficients of `t² + 2bt + c = 0`, the ray parameters at which
+    /// `origin + t * d` lies on the circle, for the unit direction `d`.
+    ///
+    /// Returns `(b, c, tangent)`, where `tangent` is set when the
+    /// discriminant `b² − c` is within rounding error of zero, relative to
+    /// `|origin − center|²`. `c` is formed as a product of differences,
+    /// which stays accurate for an origin near the circle.
+    fn circle_terms(&self, d: Vec2, center: Vec2, radius: f64) -> (f64, f64, bool) {
+        let m = self.origin - center;
+        let b = m.dot(d);
+        let mm = m.dot(m);
+        let distance = mm.sqrt();
+        let c = (distance - radius) * (distance + radius);
+
+        let discriminant = b * b - c;
+        (b, c, discriminant.abs() <= TANGENCY_TOLERANCE * mm)
+    }
+
+    /// Whether this ray touches the circle of `arc` without crossing it.
+    pub fn is_tangent_to(&self, arc: &CircularArcSegment) -> bool {
+        self.direction
+            .try_normalized()
+            .is_some_and(|d| self.circle_terms(d, arc.center, arc.radius).2)
+    }
+
     /// Intersect this ray with a circle defined by `center` and `radius`.
     ///
     /// Returns all positive ray parameters `t` such that:
     ///   origin + t * direction lies on the circle,
-    /// with `t > epsilon`, sorted ascending.
+    /// sorted ascending, where the origin is more than `tolerance` from the
+    /// circle's tangent line at the crossing. A tangent ray touches the
+    /// circle once, at `t = -b`, which is reported if `t > tolerance`.
     ///
     /// This does *not* restrict to a specific arc; it is a full-circle test.
-    fn intersect_circle(&self, center: Vec2, radius: f64, epsilon: f64) -> Vec<f64> {
-        let p = self.origin;
-        let mut d = self.direction;
-
+    fn intersect_circle(&self, center: Vec2, radius: f64, tolerance: f64) -> Vec<f64> {
         // Normalize ray direction so that ray_parameter ≈ Euclidean distance.
-        if let Some(d_hat) = d.try_normalized() {
-            d = d_hat;
-        } else {
+        let Some(d) = self.direction.try_normalized() else {
             // Degenerate direction; treat as no intersection.
             return Vec::new();
-        }
-
-        let m = p - center;
+        };
 
         // Solve |m + t d|^2 = r^2
         // => t^2 + 2 (m·d) t + (m·m - r^2) = 0
-        let b = m.dot(d); // m·d
-        let c = m.dot(m) - radius * radius;
-
+        let (b, c, tangent) = self.circle_terms(d, center, radius);
+        if tangent {
+            return if -b > tolerance { vec![-b] } else { Vec::new() };
+        }
         let discriminant = b * b - c;
         if discriminant < 0.0 {
             return Vec::new();
@@ -129,26 +213,25 @@ impl Ray {
 
         let sqrt_disc = discriminant.sqrt();
 
-        let t1 = -b - sqrt_disc;
-        let t2 = -b + sqrt_disc;
-
-        let mut ts = Vec::new();
-
-        if t1 > epsilon {
-            ts.push(t1);
-        }
-        if t2 > epsilon && (t2 - t1).abs() > 1e-14 {
-            ts.push(t2);
-        }
-
-        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        ts
+        // The root of larger magnitude from the sum that does not cancel,
+        // the other from the product of the roots, c = q · (c / q).
+        let q = -(b + sqrt_disc.copysign(b));
+        let (t1, t2) = if q < c / q { (q, c / q) } else { (c / q, q) };
+
+        // At either crossing, d·n = ±sqrt_disc / radius for the unit
+        // normal n, so the origin is t · sqrt_disc / radius from the
+        // tangent line there.
+        [t1, t2]
+            .into_iter()
+            .filter(|&t| t > 0.0 && t * sqrt_disc > tolerance * radius)
+            .collect()
     }
 
     /// Intersect this ray with a circular arc segment.
     ///
     /// Returns (ray_t, arc_local_t) where:
-    /// - ray_t is the distance along the ray (>= epsilon),
+    /// - ray_t is the distance along the ray, with the origin more than
+    ///   `tolerance` from the circle's tangent line at the hit,
     /// - arc_local_t is the arc-length parameter in [0, arc.length()].
     ///
     /// Returns `None` if the ray misses the arc or hits the circle outside
@@ -156,98 +239,36 @@ impl Ray {
     pub fn intersect_circular_arc(
         &self,
         arc: &CircularArcSegment,
-        epsilon: f64,
+        tolerance: f64,
     ) -> Option<(f64, f64)> {
         let d = self.direction.try_normalized()?;
 
-        let t_candidates = self.intersect_circle(arc.center, arc.radius, epsilon);
+        let t_candidates = self.intersect_circle(arc.center, arc.radius, tolerance);
         if t_candidates.is_empty() {
             return None;
         }
 
-        let arc_len = arc.length();
-        let tol = 1e-9;
-
-        let mut best: Option<(f64, f64)> = None;
-
-        for t in t_candidates {
-            let p = self.origin + d * t;
-            let rel = p - arc.center;
-            let theta = rel.y.atan2(rel.x);
-
-            // Compute angular span of the arc in the direction of its parameterization.
-            let span = if arc.ccw {
-                // CCW sweep from start_angle to end_angle
-                let s = arc.start_angle;
-                let mut e = arc.end_angle;
-                // Normalize angles so that e is ahead of s in CCW direction
-                let two_pi = 2.0 * std::f64::consts::PI;
-                while e < s {
-                    e += two_pi;
-                }
-                // Bring theta into the same "band"
-                let mut th = theta;
-                while th < s {
-                    th += two_pi;
-                }
-                while th > e {
-                    th -= two_pi;
-                }
-                // If theta is now outside [s, e] by more than tol, it's not on the arc.
-                if th < s - tol || th > e + tol {
-                    continue;
-                }
-                // local_t = radius * (th - s)
-                let local_t = arc.radius * (th - s);
-                (local_t, e - s)
-            } else {
-                // CW sweep from start_angle to end_angle
-                let mut s = arc.start_angle;
-                let e = arc.end_angle;
-                let two_pi = 2.0 * std::f64::consts::PI;
-                // For CW, we want s to be "ahead" of e when going CW,
-                // which is equivalent to e being ahead of s in CCW if we swap.
-                while s < e {
-                    s += two_pi;
-                }
-                let mut th = theta;
-                while th > s {
-                    th -= two_pi;
-                }
-                while th < e {
-                    th += two_pi;
-                }
-                if th > s + tol || th < e - tol {
-                    continue;
-                }
-                // In CW direction, local_t = radius * (s - th)
-                let local_t = arc.radius * (s - th);
-                (local_t, s - e)
-            };
-
-            let (mut local_t, _) = span;
-
-            if local_t < -tol || local_t > arc_len + tol {
-                continue;
-            }
-
-            if local_t < 0.0 {
-                local_t = 0.0;
-            } else if local_t > arc_len {
-                local_t = arc_len;
-            }
+        t_candidates
+            .into_iter()
+            .find_map(|t| arc_local_t(arc, self.origin + d * t).map(|local_t| (t, local_t)))
+    }
 
-            match best {
-                None => best = Some((t, local_t)),
-                Some((best_t, _)) => {
-                    if t < best_t {
-                        best = Some((t, local_t));
-                    }
-                }
-            }
+    /// Intersect a ray leaving a point of `arc` with the rest of the arc.
+    ///
+    /// The origin is taken to lie on the circle exactly, so one crossing
+    /// is at `t = 0` and the other, the far end of the chord, at
+    /// `t = -2 (origin - center)·d`; only the latter is reported, however
+    /// short the chord, unless the ray leaves along the tangent.
+    fn intersect_circular_arc_leaving(&self, arc: &CircularArcSegment) -> Option<(f64, f64)> {
+        let d = self.direction.try_normalized()?;
+        let m = self.origin - arc.center;
+        let b = m.dot(d);
+        let t = -2.0 * b;
+        if t > 0.0 && b.abs() > TANGENCY_TOLERANCE * m.length() {
+            arc_local_t(arc, self.origin + d * t).map(|local_t| (t, local_t))
+        } else {
+            None
         }
-
-        best
     }
 
     /// Intersect this ray with a single boundary component.
@@ -256,43 +277,69 @@ impl Ray {
     /// - there are no segments,
     /// - or no segment is hit with a positive ray parameter.
     ///
-    /// `epsilon` is used to ignore intersections that are "too close" to the origin,
-    /// which helps avoid hitting the current bounce point again.
+    /// `leaving` is the segment of this component the ray leaves from, if
+    /// any; see [`IntersectionOptions`] for how it and `tolerance` keep the
+    /// ray from hitting its own bounce point again.
     pub fn intersect_component(
         &self,
         component: &BoundaryComponent,
-        epsilon: f64,
+        tolerance: f64,
+        leaving: Option<usize>,
     ) -> Option<(usize, f64, f64)> {
         if component.segments.is_empty() {
             return None;
         }
 
+        // The line segments go through the batched test, which agrees with
+        // `intersect_line_segment` exactly; ties go to the lower index, as
+        // they would in a single pass over the segments.
+        let lines = component.line_batch();
+        let line_hit = self
+            .direction
+            .try_normalized()
+            .and_then(|d| lines.nearest_hit(self.origin, d, tolerance, leaving));
+        if lines.len() == component.segments.len() {
+            return line_hit;
+        }
+
         component
             .segments
             .iter()
             .enumerate()
-            .filter_map(|(i, &seg)| match seg {
-                BoundarySegment::Line(line_seg) => self
-                    .intersect_line_segment(&line_seg, epsilon)
+            .filter_map(|(i, seg)| match seg {
+                BoundarySegment::Line(_) => None,
+                BoundarySegment::CircularArc(arc_seg) if leaving == Some(i) => self
+                    .intersect_circular_arc_leaving(arc_seg)
                     .map(|(ray_t, local_t)| (i, ray_t, local_t)),
                 BoundarySegment::CircularArc(arc_seg) => self
-                    .intersect_circular_arc(&arc_seg, epsilon)
+                    .intersect_circular_arc(arc_seg, tolerance)
+                    .map(|(ray_t, local_t)| (i, ray_t, local_t)),
+                BoundarySegment::External(ext) => self
+                    .direction
+                    .try_normalized()
+                    .and_then(|d| ext.curve.ray_intersect(self.origin, d, tolerance))
                     .map(|(ray_t, local_t)| (i, ray_t, local_t)),
             })
+            .chain(line_hit)
             // Choose the smallest ray_t (closest intersection)
-            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)))
     }
 
     /// Intersect this ray with the full billiard table (outer boundary and obstacles).
     ///
     /// Returns the closest valid intersection along the ray, with all indices and
     /// parameters filled in, or `None` if no intersection is found.
-    pub fn intersect_table(&self, table: &BilliardTable, epsilon: f64) -> Option<Intersection> {
+    pub fn intersect_table(
+        &self,
+        table: &BilliardTable,
+        options: &IntersectionOptions,
+    ) -> Option<Intersection> {
         table
             .components()
             .enumerate()
             .filter_map(|(comp_idx, comp)| {
-                self.intersect_component(comp, epsilon)
+                let leaving = options.leaving_segment(comp_idx);
+                self.intersect_component(comp, options.tolerance, leaving)
                     .map(|(seg_idx, ray_t, local_t)| (comp_idx, seg_idx, ray_t, local_t))
             })
             .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap()) // compare on ray_t
@@ -302,17 +349,387 @@ impl Ray {
                     segment_index,
                     local_t,
                     ray_parameter,
+                    // On the arc left, only the far end of a chord counts.
+                    tangent: match &table.component(component_index).segments[segment_index] {
+                        BoundarySegment::CircularArc(arc) => {
+                            options.leaving != Some((component_index, segment_index))
+                                && self.is_tangent_to(arc)
+                        }
+                        _ => false,
+                    },
                 },
             )
     }
 }
 
+/// A free-flight arc under constant acceleration:
+///   origin + t * velocity + ½ t² * gravity, for t >= 0.
+///
+/// Unlike [`Ray`], the parameter `t` is a time, not a distance, and the
+/// speed changes along the arc as height is traded for kinetic energy.
+pub struct Parabola {
+    /// Launch point.
+    pub origin: Vec2,
+
+    /// Velocity at `t = 0`.
+    pub velocity: Vec2,
+
+    /// Constant acceleration, e.g. `(0, -g)`.
+    pub gravity: Vec2,
+}
+
+impl Parabola {
+    /// Position at time `t`.
+    pub fn point_at(&self, t: f64) -> Vec2 {
+        self.origin + self.velocity * t + self.gravity * (0.5 * t * t)
+    }
+
+    /// Velocity at time `t`.
+    pub fn velocity_at(&self, t: f64) -> Vec2 {
+        self.velocity + self.gravity * t
+    }
+
+    /// Earliest time a hit is accepted, so that roughly the first `epsilon`
+    /// of path length is ignored (as for [`Ray`]).
+    fn min_time(&self, epsilon: f64) -> Option<f64> {
+        let speed = self.velocity.length();
+        (speed > 0.0).then(|| epsilon / speed)
+    }
+
+    /// Intersect this parabola with a single line segment.
+    ///
+    /// Returns `(time, local_t)` for the earliest crossing after the
+    /// launch, or `None` if the segment is never reached.
+    pub fn intersect_line_segment(
+        &self,
+        segment: &LineSegment,
+        epsilon: f64,
+    ) -> Option<(f64, f64)> {
+        self.intersect_line(segment, epsilon, false)
+    }
+
+    /// [`Parabola::intersect_line_segment`], or with `leaving` for a
+    /// parabola launched from the segment: the origin is then taken to lie
+    /// on its line exactly, and the crossing at `t = 0` is divided out of
+    /// the equation so that only the return to the line remains.
+    fn intersect_line(
+        &self,
+        segment: &LineSegment,
+        epsilon: f64,
+        leaving: bool,
+    ) -> Option<(f64, f64)> {
+        let t_min = self.min_time(epsilon)?;
+        let seg_len = segment.length();
+        if seg_len <= epsilon {
+            return None;
+        }
+
+        let u = (segment.end - segment.start) / seg_len;
+        let m = u.perp();
+
+        // Signed distance from the segment's line along m.
+        let offset = self.origin - segment.start;
+        let coeffs = [
+            m.dot(offset),
+            m.dot(self.velocity),
+            0.5 * m.dot(self.gravity),
+        ];
+
+        let roots = if leaving {
+            real_roots(&coeffs[1..], 0.0)
+        } else {
+            real_roots(&coeffs, t_min)
+        };
+        roots.into_iter().find_map(|t| {
+            let local_t = u.dot(self.point_at(t) - segment.start);
+            (0.0..=seg_len).contains(&local_t).then_some((t, local_t))
+        })
+    }
+
+    /// Intersect this parabola with a circular arc segment.
+    ///
+    /// Returns `(time, arc_local_t)` for the earliest crossing within the
+    /// arc's angular span.
+    pub fn intersect_circular_arc(
+        &self,
+        arc: &CircularArcSegment,
+        epsilon: f64,
+    ) -> Option<(f64, f64)> {
+        self.intersect_arc(arc, epsilon, false)
+    }
+
+    /// [`Parabola::intersect_circular_arc`], or with `leaving` for a
+    /// parabola launched from the arc, as in [`Parabola::intersect_line`].
+    fn intersect_arc(
+        &self,
+        arc: &CircularArcSegment,
+        epsilon: f64,
+        leaving: bool,
+    ) -> Option<(f64, f64)> {
+        let t_min = self.min_time(epsilon)?;
+
+        // |d + v t + ½ g t²|² = r², with d measured from the center.
+        let d = self.origin - arc.center;
+        let (v, g) = (self.velocity, self.gravity);
+        let coeffs = [
+            d.dot(d) - arc.radius * arc.radius,
+            2.0 * d.dot(v),
+            v.dot(v) + d.dot(g),
+            v.dot(g),
+            0.25 * g.dot(g),
+        ];
+
+        let roots = if leaving {
+            real_roots(&coeffs[1..], 0.0)
+        } else {
+            real_roots(&coeffs, t_min)
+        };
+        roots
+            .into_iter()
+            .find_map(|t| arc_local_t(arc, self.point_at(t)).map(|local_t| (t, local_t)))
+    }
+
+    /// Intersect this parabola with a single boundary component.
+    ///
+    /// Returns `(segment_index, time, local_t)` for the earliest hit.
+    /// Hits within roughly `tolerance` of path length after the launch are
+    /// ignored, except on `leaving`, the segment of this component the
+    /// parabola is launched from, where only the launch itself is.
+    /// External segments are never hit; the billiard map refuses to fly a
+    /// parabola on a table that has any (see
+    /// [`DynamicsError::ExternalSegmentUnderGravity`](crate::dynamics::simulation::DynamicsError::ExternalSegmentUnderGravity)).
+    pub fn intersect_component(
+        &self,
+        component: &BoundaryComponent,
+        tolerance: f64,
+        leaving: Option<usize>,
+    ) -> Option<(usize, f64, f64)> {
+        component
+            .segments
+            .iter()
+            .enumerate()
+            .filter_map(|(i, seg)| {
+                let leaving = leaving == Some(i);
+                match seg {
+                    BoundarySegment::Line(line_seg) => {
+                        self.intersect_line(line_seg, tolerance, leaving)
+                    }
+                    BoundarySegment::CircularArc(arc_seg) => {
+                        self.intersect_arc(arc_seg, tolerance, leaving)
+                    }
+                    // External curves only intersect straight rays.
+                    BoundarySegment::External(_) => None,
+                }
+                .map(|(t, local_t)| (i, t, local_t))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Intersect this parabola with the full billiard table.
+    ///
+    /// The returned [`Intersection::ray_parameter`] is the flight time.
+    pub fn intersect_table(
+        &self,
+        table: &BilliardTable,
+        options: &IntersectionOptions,
+    ) -> Option<Intersection> {
+        table
+            .components()
+            .enumerate()
+            .filter_map(|(comp_idx, comp)| {
+                let leaving = options.leaving_segment(comp_idx);
+                self.intersect_component(comp, options.tolerance, leaving)
+                    .map(|(seg_idx, t, local_t)| (comp_idx, seg_idx, t, local_t))
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(
+                |(component_index, segment_index, ray_parameter, local_t)| Intersection {
+                    component_index,
+                    segment_index,
+                    local_t,
+                    ray_parameter,
+                    // Grazing roots are not reported, see `real_roots`.
+                    tangent: false,
+                },
+            )
+    }
+
+    /// Time at which the parabola leaves `bounds`, starting from inside.
+    ///
+    /// Returns `None` if it never does (zero gravity and zero velocity).
+    pub fn exit_time(&self, bounds: &BoundingBox) -> Option<f64> {
+        let walls = [
+            (
+                self.origin.x - bounds.min.x,
+                self.velocity.x,
+                self.gravity.x,
+            ),
+            (
+                self.origin.x - bounds.max.x,
+                self.velocity.x,
+                self.gravity.x,
+            ),
+            (
+                self.origin.y - bounds.min.y,
+                self.velocity.y,
+                self.gravity.y,
+            ),
+            (
+                self.origin.y - bounds.max.y,
+                self.velocity.y,
+                self.gravity.y,
+            ),
+        ];
+
+        walls
+            .iter()
+            .filter_map(|&(p, v, g)| real_roots(&[p, v, 0.5 * g], 0.0).first().copied())
+            .min_by(f64::total_cmp)
+    }
+}
+
+/// Real roots of `Σ coeffs[i] tⁱ` in `(t_min, ∞)`, ascending.
+///
+/// Roots are isolated between the critical points (the roots of the
+/// derivative, found recursively) and refined by bisection. Double roots,
+/// i.e. tangencies, are not reported.
+fn real_roots(coeffs: &[f64], t_min: f64) -> Vec<f64> {
+    // Drop leading coefficients that are negligible next to the others.
+    let scale = coeffs.iter().fold(0.0_f64, |m, c| m.max(c.abs()));
+    let mut degree = coeffs.len();
+    while degree > 0 && coeffs[degree - 1].abs() <= 1e-14 * scale {
+        degree -= 1;
+    }
+    let coeffs = &coeffs[..degree];
+
+    match degree {
+        0 | 1 => return Vec::new(),
+        2 => {
+            let t = -coeffs[0] / coeffs[1];
+            return if t > t_min { vec![t] } else { Vec::new() };
+        }
+        _ => {}
+    }
+
+    let eval = |t: f64| coeffs.iter().rev().fold(0.0, |acc, &c| acc * t + c);
+
+    // Cauchy bound: every real root lies in (-bound, bound).
+    let lead = coeffs[degree - 1];
+    let bound = 1.0
+        + coeffs[..degree - 1]
+            .iter()
+            .fold(0.0_f64, |m, c| m.max((c / lead).abs()));
+    if t_min >= bound {
+        return Vec::new();
+    }
+
+    let derivative: Vec<f64> = coeffs
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| i as f64 * c)
+        .collect();
+
+    let mut knots = vec![t_min];
+    knots.extend(real_roots(&derivative, t_min));
+    knots.push(bound);
+
+    knots
+        .windows(2)
+        .filter_map(|w| {
+            let (mut lo, mut hi) = (w[0], w[1]);
+            let (f_lo, f_hi) = (eval(lo), eval(hi));
+            if f_lo == 0.0 && lo > t_min {
+                return Some(lo);
+            }
+            if f_lo.signum() == f_hi.signum() {
+                return None;
+            }
+            // Monotone between knots, so bisection converges to the root.
+            for _ in 0..200 {
+                let mid = 0.5 * (lo + hi);
+                if mid <= lo || mid >= hi {
+                    break;
+                }
+                if eval(mid).signum() == f_lo.signum() {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            Some(0.5 * (lo + hi))
+        })
+        .collect()
+}
+
+/// Arc-length parameter of `point` on `arc`, if its angle lies within the
+/// arc's angular span (points are assumed to lie on the arc's circle).
+pub(crate) fn arc_local_t(arc: &CircularArcSegment, point: Vec2) -> Option<f64> {
+    let arc_len = arc.length();
+    let tol = 1e-9;
+
+    let rel = point - arc.center;
+    let theta = rel.y.atan2(rel.x);
+    let two_pi = 2.0 * std::f64::consts::PI;
+
+    let local_t = if arc.ccw {
+        // CCW sweep from start_angle to end_angle
+        let s = arc.start_angle;
+        let mut e = arc.end_angle;
+        // Normalize angles so that e is ahead of s in CCW direction
+        while e < s {
+            e += two_pi;
+        }
+        // Bring theta into the same "band"
+        let mut th = theta;
+        while th < s {
+            th += two_pi;
+        }
+        while th > e {
+            th -= two_pi;
+        }
+        // If theta is now outside [s, e] by more than tol, it's not on the arc.
+        if th < s - tol || th > e + tol {
+            return None;
+        }
+        arc.radius * (th - s)
+    } else {
+        // CW sweep from start_angle to end_angle
+        let mut s = arc.start_angle;
+        let e = arc.end_angle;
+        // For CW, we want s to be "ahead" of e when going CW,
+        // which is equivalent to e being ahead of s in CCW if we swap.
+        while s < e {
+            s += two_pi;
+        }
+        let mut th = theta;
+        while th > s {
+            th -= two_pi;
+        }
+        while th < e {
+            th += two_pi;
+        }
+        if th > s + tol || th < e - tol {
+            return None;
+        }
+        // In CW direction, local_t = radius * (s - th)
+        arc.radius * (s - th)
+    };
+
+    if local_t < -tol || local_t > arc_len + tol {
+        return None;
+    }
+
+    Some(local_t.clamp(0.0, arc_len))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Ray;
+    use super::{IntersectionOptions, Ray};
     use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
     use crate::geometry::primitives::Vec2;
     use crate::geometry::segments::{BoundarySegment, LineSegment};
+    use crate::geometry::standard_tables::{circle, regular_polygon};
 
     fn simple_horizontal_table() -> BilliardTable {
         let seg = BoundarySegment::Line(LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)));
@@ -335,7 +752,7 @@ mod tests {
         };
 
         let epsilon = 1e-8;
-        let hit = ray.intersect_table(&table, epsilon);
+        let hit = ray.intersect_table(&table, &IntersectionOptions::new(epsilon));
 
         assert!(hit.is_some(), "Expected intersection but got None");
         let hit = hit.unwrap();
@@ -384,21 +801,124 @@ mod tests {
         };
 
         let epsilon = 1e-8;
-        let hit = ray.intersect_table(&table, epsilon);
+        let hit = ray.intersect_table(&table, &IntersectionOptions::new(epsilon));
 
         assert!(
             hit.is_none(),
             "Expected no intersection when ray points away from the segment"
         );
     }
+
+    #[test]
+    fn local_t_is_arc_length_on_segments_of_any_length() {
+        let ray = Ray {
+            origin: Vec2::new(1.5, -1.0),
+            direction: Vec2::new(0.0, 1.0),
+        };
+
+        let long = LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0));
+        let (_, local_t) = ray.intersect_line_segment(&long, 1e-8).unwrap();
+        assert!((local_t - 1.5).abs() < 1e-12);
+
+        let short = LineSegment::new(Vec2::new(1.0, 0.0), Vec2::new(1.25, 0.0));
+        assert!(ray.intersect_line_segment(&short, 1e-8).is_none());
+    }
+
+    #[test]
+    fn grazing_chords_count_only_on_the_segment_left() {
+        let table = circle(1.0).to_billiard_table();
+
+        // Leave the circle at angle 0.5, a nanoradian inside the tangent:
+        // the chord is 2e-9 long, shorter than the tolerance.
+        let (a, theta) = (0.5_f64, 1e-9_f64);
+        let tangent = Vec2::new(-a.sin(), a.cos());
+        let inward = Vec2::new(-a.cos(), -a.sin());
+        let ray = Ray {
+            origin: Vec2::new(a.cos(), a.sin()),
+            direction: tangent * theta.cos() + inward * theta.sin(),
+        };
+
+        let options = IntersectionOptions::new(1e-8);
+        let hit = ray
+            .intersect_table(&table, &options.leaving(0, 0))
+            .expect("the far end of the chord");
+        assert!((hit.ray_parameter - 2.0 * theta.sin()).abs() < 1e-15);
+        assert!((hit.local_t - (a + 2.0 * theta)).abs() < 1e-12);
+
+        // From an unknown segment, the origin is within the tolerance of
+        // the circle at both ends of the chord.
+        assert!(ray.intersect_table(&table, &options).is_none());
+    }
+
+    #[test]
+    fn corners_do_not_count_the_neighbouring_segment() {
+        let table = regular_polygon(4, 1.0).to_billiard_table();
+        let component = table.component(0);
+
+        // Leave the first edge a rounding error from its end; the next
+        // edge passes through the origin.
+        let s = component.segments[0].length() * (1.0 - 1e-15);
+        let (origin, normal) = component.point_and_inward_normal_at(s);
+        let ray = Ray {
+            origin,
+            direction: normal,
+        };
+        let hit = ray
+            .intersect_table(&table, &IntersectionOptions::new(1e-8).leaving(0, 0))
+            .expect("the opposite side");
+        assert_eq!(hit.segment_index, 2);
+    }
 }
 
 #[cfg(test)]
 mod arc_intersection_tests {
-    use super::Ray;
+    use super::{IntersectionOptions, Ray};
     use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
     use crate::geometry::primitives::Vec2;
     use crate::geometry::segments::{BoundarySegment, CircularArcSegment};
+    use crate::geometry::standard_tables::circle;
+
+    #[test]
+    fn tangent_rays_touch_the_circle_exactly_once() {
+        let table = circle(0.7).to_billiard_table();
+        let options = IntersectionOptions::new(1e-8);
+
+        // Rays along the tangent at angle a, from 2.5 before the touch
+        // point; rounding leaves the discriminant a few ulps either side
+        // of zero.
+        for k in 0..500 {
+            let a = 0.0123 * k as f64;
+            let touch = Vec2::new(a.cos(), a.sin()) * 0.7;
+            let direction = Vec2::new(-a.sin(), a.cos());
+            let ray = Ray {
+                origin: touch - direction * 2.5,
+                direction,
+            };
+
+            let hit = ray
+                .intersect_table(&table, &options)
+                .unwrap_or_else(|| panic!("tangent ray {} missed", k));
+            assert!(hit.tangent, "ray {}", k);
+            assert!((hit.ray_parameter - 2.5).abs() < 1e-6, "ray {}", k);
+        }
+    }
+
+    #[test]
+    fn short_chords_from_just_outside_are_accurate() {
+        let disk = CircularArcSegment::new(Vec2::new(0.0, 0.0), 1.0, 0.0, 6.0, true);
+
+        // 1e-12 outside the circle, heading for the center; subtracting
+        // the squared radius from the squared distance would lose all
+        // but a few digits of the near root.
+        let ray = Ray {
+            origin: Vec2::new(1.0 + 1e-12, 0.0),
+            direction: Vec2::new(-1.0, 0.0),
+        };
+        let (t, _) = ray.intersect_circular_arc(&disk, 1e-20).unwrap();
+        let exact = (1.0 + 1e-12) - 1.0;
+        assert!((t - exact).abs() < 1e-12 * exact, "near root {}", t);
+        assert!(!ray.is_tangent_to(&disk));
+    }
 
     fn quarter_circle_table() -> BilliardTable {
         // Single boundary: quarter-circle from (1,0) to (0,1), CCW.
@@ -427,7 +947,7 @@ mod arc_intersection_tests {
         };
 
         let epsilon = 1e-8;
-        let hit = ray.intersect_table(&table, epsilon);
+        let hit = ray.intersect_table(&table, &IntersectionOptions::new(epsilon));
 
         assert!(hit.is_some(), "Expected intersection with quarter-circle");
         let hit = hit.unwrap();
@@ -456,3 +976,84 @@ mod arc_intersection_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod parabola_tests {
+    use super::Parabola;
+    use crate::geometry::boundary::BoundaryComponent;
+    use crate::geometry::primitives::{BoundingBox, Vec2};
+    use crate::geometry::segments::{BoundarySegment, CircularArcSegment, LineSegment};
+
+    /// Thrown horizontally from the origin, falling as y = -t².
+    fn falling() -> Parabola {
+        Parabola {
+            origin: Vec2::new(0.0, 0.0),
+            velocity: Vec2::new(1.0, 0.0),
+            gravity: Vec2::new(0.0, -2.0),
+        }
+    }
+
+    #[test]
+    fn parabola_lands_on_floor() {
+        let floor = LineSegment::new(Vec2::new(2.0, -1.0), Vec2::new(-2.0, -1.0));
+        let (t, local_t) = falling()
+            .intersect_line_segment(&floor, 1e-9)
+            .expect("should land on the floor");
+
+        assert!((t - 1.0).abs() < 1e-12);
+        assert!((local_t - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn parabola_misses_short_floor() {
+        let floor = LineSegment::new(Vec2::new(-0.5, -1.0), Vec2::new(-2.0, -1.0));
+        assert!(falling().intersect_line_segment(&floor, 1e-9).is_none());
+    }
+
+    #[test]
+    fn parabola_hits_lower_half_of_circle() {
+        // Unit circle around the origin: x² + t⁴ = 1 with x = t.
+        let circle = CircularArcSegment::new(
+            Vec2::new(0.0, 0.0),
+            1.0,
+            0.0,
+            2.0 * std::f64::consts::PI,
+            true,
+        );
+        let (t, _) = falling()
+            .intersect_circular_arc(&circle, 1e-9)
+            .expect("should hit the circle");
+
+        let expected = ((5.0_f64.sqrt() - 1.0) / 2.0).sqrt();
+        assert!((t - expected).abs() < 1e-12);
+        assert!((falling().point_at(t).length() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn grazing_hops_land_on_the_floor_they_leave() {
+        let floor =
+            BoundarySegment::Line(LineSegment::new(Vec2::new(2.0, 0.0), Vec2::new(-2.0, 0.0)));
+        let component = BoundaryComponent::new("floor", vec![floor]);
+
+        // Airborne for 1e-10, so the hop is shorter than the tolerance.
+        let hop = Parabola {
+            origin: Vec2::new(0.0, 0.0),
+            velocity: Vec2::new(1.0, 1e-10),
+            gravity: Vec2::new(0.0, -2.0),
+        };
+        let (i, t, _) = hop
+            .intersect_component(&component, 1e-9, Some(0))
+            .expect("should land again");
+        assert_eq!(i, 0);
+        assert!((t - 1e-10).abs() < 1e-22);
+
+        assert!(hop.intersect_component(&component, 1e-9, None).is_none());
+    }
+
+    #[test]
+    fn parabola_leaves_box_through_the_bottom() {
+        let bounds = BoundingBox::new(Vec2::new(-2.0, -4.0), Vec2::new(4.0, 1.0));
+        let t = falling().exit_time(&bounds).expect("should leave the box");
+        assert!((t - 2.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,374 @@
+//! Auditing the numerical quality of a trajectory as it is simulated.
+//!
+//! The exact dynamics keeps a few things exactly: the hit point lies on the
+//! boundary at the recorded arc length, an elastic particle keeps its
+//! energy (its speed, in straight flight), and the outgoing velocity
+//! mirrors the incoming one in the wall. Floating point only keeps them
+//! approximately. An [`InvariantMonitor`] measures how far each collision
+//! is off and keeps the worst values in an [`InvariantReport`]; configured
+//! through [`StepOptions::invariants`], it runs inside the simulation loop
+//! and can end the trajectory at the first breach.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::simulation::{CollisionResult, CornerPolicy, StepOptions};
+use crate::dynamics::state::WorldState;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::BoundaryCondition;
+
+/// Tolerance of each invariant unless set otherwise.
+pub const DEFAULT_INVARIANT_TOLERANCE: f64 = 1e-9;
+
+/// Which violations an [`InvariantMonitor`] tolerates, and whether a
+/// breach ends the trajectory.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InvariantCheck {
+    /// Largest relative change of speed, against what energy conservation
+    /// predicts from the starting speed.
+    #[serde(default = "default_tolerance")]
+    pub speed_tolerance: f64,
+
+    /// Largest distance from a hit point to the boundary point at its `s`.
+    #[serde(default = "default_tolerance")]
+    pub boundary_tolerance: f64,
+
+    /// Largest difference, in units of the incoming speed, between the
+    /// outgoing velocity and the mirror image of the incoming one.
+    #[serde(default = "default_tolerance")]
+    pub reflection_tolerance: f64,
+
+    /// End the trajectory at the first collision beyond a tolerance.
+    #[serde(default)]
+    pub abort: bool,
+}
+
+fn default_tolerance() -> f64 {
+    DEFAULT_INVARIANT_TOLERANCE
+}
+
+impl Default for InvariantCheck {
+    fn default() -> Self {
+        InvariantCheck {
+            speed_tolerance: DEFAULT_INVARIANT_TOLERANCE,
+            boundary_tolerance: DEFAULT_INVARIANT_TOLERANCE,
+            reflection_tolerance: DEFAULT_INVARIANT_TOLERANCE,
+            abort: false,
+        }
+    }
+}
+
+impl InvariantCheck {
+    /// The speed, boundary and reflection tolerances. An infinite one
+    /// switches its check off.
+    pub fn tolerances(&self) -> [f64; 3] {
+        [
+            self.speed_tolerance,
+            self.boundary_tolerance,
+            self.reflection_tolerance,
+        ]
+    }
+}
+
+/// Largest violations seen by an [`InvariantMonitor`].
+///
+/// The speed is only checked on elastic runs (restitution 1), and the
+/// reflection law only under the [`Specular`] law, away from no-slip
+/// segments; the corresponding maxima stay 0 otherwise. Passes through
+/// periodic segments are counted but not checked.
+///
+/// [`Specular`]: crate::dynamics::reflection::Specular
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InvariantReport {
+    /// Number of collisions checked.
+    pub collisions: usize,
+
+    pub max_speed_error: f64,
+    pub max_boundary_error: f64,
+    pub max_reflection_error: f64,
+
+    /// Index of the first collision beyond a tolerance, if any.
+    pub first_breach: Option<usize>,
+}
+
+impl InvariantReport {
+    /// Whether any collision went beyond a tolerance.
+    pub fn breached(&self) -> bool {
+        self.first_breach.is_some()
+    }
+
+    /// Fold in the report on a later stretch of the same trajectory,
+    /// simulated separately.
+    pub fn extend(&mut self, later: &InvariantReport) {
+        if self.first_breach.is_none() {
+            self.first_breach = later.first_breach.map(|i| self.collisions + i);
+        }
+        self.collisions += later.collisions;
+        self.max_speed_error = self.max_speed_error.max(later.max_speed_error);
+        self.max_boundary_error = self.max_boundary_error.max(later.max_boundary_error);
+        self.max_reflection_error = self.max_reflection_error.max(later.max_reflection_error);
+    }
+}
+
+/// Checks each collision of one trajectory; see the
+/// [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct InvariantMonitor {
+    check: InvariantCheck,
+    report: InvariantReport,
+
+    /// Position and speed at the start, for energy conservation.
+    start: Vec2,
+    start_speed: f64,
+}
+
+impl InvariantMonitor {
+    /// Monitor a trajectory that starts at `initial`.
+    pub fn new(check: InvariantCheck, initial: &WorldState) -> Self {
+        InvariantMonitor {
+            check,
+            report: InvariantReport::default(),
+            start: initial.position,
+            start_speed: initial.speed,
+        }
+    }
+
+    pub fn check(&self) -> &InvariantCheck {
+        &self.check
+    }
+
+    pub fn report(&self) -> &InvariantReport {
+        &self.report
+    }
+
+    /// Check `collision`, reached from the world state `from` after a
+    /// flight of `flight_time` under `options`; `specular` says whether
+    /// the reflection law is the mirror law. Returns whether a tolerance
+    /// was broken.
+    pub fn observe(
+        &mut self,
+        table: &BilliardTable,
+        options: &StepOptions,
+        specular: bool,
+        from: &WorldState,
+        flight_time: f64,
+        collision: &CollisionResult,
+    ) -> bool {
+        let component = table.component(collision.component_index);
+        let segment = &component.segments[collision.segment_index];
+        let (point, _) = component.point_and_tangent_at(collision.s);
+        let acceleration = options.flight.acceleration();
+
+        // Heated and moving walls change the energy: it is conserved again
+        // from the bounce on.
+        let condition = segment.condition();
+        let speed_error = if condition.does_work() {
+            self.start = point;
+            self.start_speed = collision.speed;
+            0.0
+        } else if options.restitution == 1.0 {
+            let expected_squared =
+                self.start_speed * self.start_speed + 2.0 * acceleration.dot(point - self.start);
+            (collision.speed - expected_squared.max(0.0).sqrt()).abs() / self.start_speed
+        } else {
+            0.0
+        };
+
+        let periodic = matches!(condition, BoundaryCondition::PeriodicPairedWith(_));
+        let boundary_error = if periodic {
+            0.0
+        } else {
+            (collision.hit_point - point).length()
+        };
+
+        let reflects = specular
+            && !periodic
+            && !matches!(
+                condition,
+                BoundaryCondition::NoSlipRotate | BoundaryCondition::Oscillating { .. }
+            );
+        let reflection_error = if reflects {
+            let v_in = from.direction.normalized() * from.speed + acceleration * flight_time;
+            let speed_in = v_in.length();
+            let d_in = v_in / speed_in;
+            let outgoing = collision.outgoing_state().to_world(table);
+            let (_, inward_normal) = component.point_and_inward_normal_at(collision.s);
+            let normal = match (collision.corner, options.corner_policy) {
+                (Some(corner), CornerPolicy::ReflectBisector) => corner.bisector_normal,
+                _ => inward_normal,
+            };
+            let mirrored = d_in - normal * ((1.0 + options.restitution) * d_in.dot(normal));
+            (outgoing.direction * (collision.speed / speed_in) - mirrored).length()
+        } else {
+            0.0
+        };
+
+        let report = &mut self.report;
+        report.max_speed_error = report.max_speed_error.max(speed_error);
+        report.max_boundary_error = report.max_boundary_error.max(boundary_error);
+        report.max_reflection_error = report.max_reflection_error.max(reflection_error);
+        // NaN errors count as breaches.
+        let within = speed_error <= self.check.speed_tolerance
+            && boundary_error <= self.check.boundary_tolerance
+            && reflection_error <= self.check.reflection_tolerance;
+        if !within && report.first_breach.is_none() {
+            report.first_breach = Some(report.collisions);
+        }
+        report.collisions += 1;
+        !within
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InvariantCheck, InvariantReport};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::reflection::{Impact, Lambertian, Reflection, ReflectionLaw};
+    use crate::dynamics::simulation::{BilliardMap, CornerPolicy, FlightModel, Termination};
+    use crate::dynamics::state::{BoundaryState, WorldState};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::standard_tables::{sinai, stadium};
+
+    fn config(check: InvariantCheck) -> SimulationConfig {
+        SimulationConfig::builder()
+            .max_steps(200)
+            .invariants(check)
+            .build()
+            .unwrap()
+    }
+
+    fn assert_clean(report: &InvariantReport, collisions: usize) {
+        assert_eq!(report.collisions, collisions);
+        assert!(!report.breached(), "{:?}", report);
+        assert!(report.max_speed_error < 1e-12, "{:?}", report);
+        assert!(report.max_boundary_error < 1e-9, "{:?}", report);
+        assert!(report.max_reflection_error < 1e-9, "{:?}", report);
+    }
+
+    #[test]
+    fn accurate_runs_stay_within_tolerance() {
+        let initial = BoundaryState::new(0, 0.3, 1.1);
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let trajectory = BilliardMap::from_config(&table, &config(InvariantCheck::default()))
+            .simulate(&initial, 200)
+            .unwrap();
+        assert_clean(&trajectory.invariants.unwrap(), 200);
+
+        // Energy, not speed, is conserved under gravity.
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let falling = config(InvariantCheck::default())
+            .to_builder()
+            .flight(FlightModel::Gravity { g: 3.0 })
+            .build()
+            .unwrap();
+        let initial = BoundaryState {
+            speed: 2.0,
+            ..initial
+        };
+        let trajectory = BilliardMap::from_config(&table, &falling)
+            .simulate(&initial, 200)
+            .unwrap();
+        let report = trajectory.invariants.unwrap();
+        assert!(report.max_speed_error < 1e-9, "{:?}", report);
+        assert!(report.max_reflection_error < 1e-9, "{:?}", report);
+
+        // Corners reflect off the bisector under that policy.
+        let square = sinai(1.0, 0.2).to_billiard_table();
+        let bisector = config(InvariantCheck::default())
+            .to_builder()
+            .corner_policy(CornerPolicy::ReflectBisector)
+            .build()
+            .unwrap();
+        let into_corner = BoundaryState::new(0, 0.5, 1.0_f64.atan2(0.5));
+        let trajectory = BilliardMap::from_config(&square, &bisector)
+            .simulate(&into_corner, 5)
+            .unwrap();
+        assert!(trajectory.collisions[0].corner.is_some());
+        assert_clean(&trajectory.invariants.unwrap(), 5);
+    }
+
+    #[test]
+    fn launches_from_the_interior_are_checked_from_the_launch() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let launch = WorldState {
+            position: Vec2::new(0.1, 0.05),
+            direction: Vec2::new(0.6, 0.8),
+            speed: 1.5,
+        };
+        let trajectory = BilliardMap::from_config(&table, &config(InvariantCheck::default()))
+            .simulate_from_world(&launch, 50)
+            .unwrap();
+        assert_clean(&trajectory.invariants.unwrap(), 50);
+    }
+
+    #[test]
+    fn breaches_are_recorded_and_can_abort() {
+        // Claims to be specular but sends the particle straight back.
+        struct Reverse;
+        impl ReflectionLaw for Reverse {
+            fn reflect(&mut self, impact: &Impact) -> Reflection {
+                Reflection::immediate(-impact.direction)
+            }
+
+            fn is_specular(&self) -> bool {
+                true
+            }
+        }
+
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.1);
+        let recorded = BilliardMap::from_config(&table, &config(InvariantCheck::default()))
+            .with_law(Reverse)
+            .simulate(&initial, 10)
+            .unwrap();
+        assert_eq!(recorded.collisions.len(), 10);
+        assert_eq!(recorded.termination, Termination::MaxSteps);
+        let report = recorded.invariants.unwrap();
+        assert_eq!(report.first_breach, Some(0));
+        assert!(report.max_reflection_error > 0.1);
+
+        let aborting = config(InvariantCheck {
+            abort: true,
+            ..InvariantCheck::default()
+        });
+        let aborted = BilliardMap::from_config(&table, &aborting)
+            .with_law(Reverse)
+            .simulate(&initial, 10)
+            .unwrap();
+        assert_eq!(aborted.collisions.len(), 1);
+        assert_eq!(aborted.termination, Termination::InvariantViolation);
+
+        let clean = InvariantReport {
+            collisions: 4,
+            ..InvariantReport::default()
+        };
+        let mut joined = clean;
+        joined.extend(&report);
+        assert_eq!(joined.collisions, 14);
+        assert_eq!(joined.first_breach, Some(4));
+        assert_eq!(joined.max_reflection_error, report.max_reflection_error);
+    }
+
+    #[test]
+    fn other_laws_and_unmonitored_runs() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.1);
+
+        // Random reflections keep the speed but are not held to the
+        // mirror law.
+        let report = BilliardMap::from_config(&table, &config(InvariantCheck::default()))
+            .with_law(Lambertian::new(4))
+            .simulate(&initial, 100)
+            .unwrap()
+            .invariants
+            .unwrap();
+        assert_clean(&report, 100);
+
+        let unmonitored = BilliardMap::from_config(&table, &SimulationConfig::default())
+            .simulate(&initial, 10)
+            .unwrap();
+        assert_eq!(unmonitored.invariants, None);
+    }
+}
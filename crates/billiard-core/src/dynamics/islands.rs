@@ -0,0 +1,310 @@
+//! Telling regular orbits from chaotic ones on the Poincaré section.
+//!
+//! In a mixed phase space, like that of the mushroom, invariant curves and
+//! KAM islands sit inside a chaotic sea. An orbit on an invariant curve
+//! winds round at a definite rate, so the running average of its advance
+//! along the boundary, its rotation number, settles like `1/n`; and its
+//! bounces trace a curve, so at a given `s` the angle hardly varies. A
+//! chaotic orbit fills an area: its rotation number wanders like `1/√n`
+//! and its angles at any one `s` are spread out.
+//!
+//! [`classify_orbit`] measures both for one orbit and [`island_map`] labels
+//! a grid of launches from the outer wall.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::config::SimulationConfig;
+use crate::dynamics::rotation::outer_rotation_of;
+use crate::dynamics::sampling::outer_wall_grid;
+use crate::dynamics::simulation::run_trajectory;
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+
+/// Thresholds of [`classify_orbit`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RegularityCriteria {
+    /// Largest [`OrbitClassification::rotation_spread`] of a regular orbit.
+    #[serde(default = "default_rotation_tolerance")]
+    pub rotation_tolerance: f64,
+
+    /// Largest [`OrbitClassification::dispersion`] of a regular orbit.
+    #[serde(default = "default_dispersion_tolerance")]
+    pub dispersion_tolerance: f64,
+
+    /// Cells the outer wall is cut into to measure the dispersion.
+    #[serde(default = "default_s_bins")]
+    pub s_bins: usize,
+
+    /// Fewest bounces on the outer wall an orbit needs to be classified.
+    #[serde(default = "default_min_bounces")]
+    pub min_bounces: usize,
+}
+
+// Over 2000 bounces on the mushroom, regular orbits have spreads near 5e-4
+// and dispersions near 0.03, chaotic ones 1e-2 and 0.16; orbits sticking to
+// the bouncing-ball orbits of the stem come in between.
+fn default_rotation_tolerance() -> f64 {
+    1e-3
+}
+
+fn default_dispersion_tolerance() -> f64 {
+    0.08
+}
+
+fn default_s_bins() -> usize {
+    100
+}
+
+fn default_min_bounces() -> usize {
+    100
+}
+
+impl Default for RegularityCriteria {
+    fn default() -> Self {
+        RegularityCriteria {
+            rotation_tolerance: default_rotation_tolerance(),
+            dispersion_tolerance: default_dispersion_tolerance(),
+            s_bins: default_s_bins(),
+            min_bounces: default_min_bounces(),
+        }
+    }
+}
+
+/// Verdict of [`classify_orbit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum OrbitClass {
+    /// On an invariant curve or in an island.
+    Regular,
+    /// In the chaotic sea.
+    Chaotic,
+    /// Too short to tell: the simulation failed, or the orbit stopped (at a
+    /// corner or a hole, say) before `min_bounces` bounces on the outer wall.
+    Unclassified,
+}
+
+/// One orbit and the measurements it was classified by.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OrbitClassification {
+    pub initial: BoundaryState,
+    pub class: OrbitClass,
+
+    /// Rotation number around the outer wall, in `[0, 1)`; see
+    /// [`outer_rotation_number`](crate::dynamics::rotation::outer_rotation_number).
+    pub rotation: Option<f64>,
+
+    /// Largest minus smallest running estimate of the rotation number over
+    /// the second half of the orbit.
+    pub rotation_spread: Option<f64>,
+
+    /// Standard deviation of `|cos θ|` over the bounces on the outer wall
+    /// that fall in the same `s` cell, averaged over the bounces. About
+    /// `0.29` for an orbit filling phase space uniformly.
+    pub dispersion: Option<f64>,
+}
+
+/// Follow the orbit from `initial` as `config` says and classify it by its
+/// bounces on the outer wall: regular if both its
+/// [`rotation_spread`](OrbitClassification::rotation_spread) and its
+/// [`dispersion`](OrbitClassification::dispersion) are within `criteria`,
+/// chaotic otherwise.
+///
+/// Both measures shrink as orbits get longer, regular ones faster, so the
+/// tolerances go with the number of collisions; the defaults suit 2000 to
+/// 5000. Sticky chaotic orbits that linger near an island may pass for
+/// regular over a short run.
+///
+/// # Panics
+/// Panics if `criteria.s_bins` is zero.
+pub fn classify_orbit(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    criteria: &RegularityCriteria,
+    config: &SimulationConfig,
+) -> OrbitClassification {
+    let unclassified = OrbitClassification {
+        initial: *initial,
+        class: OrbitClass::Unclassified,
+        rotation: None,
+        rotation_spread: None,
+        dispersion: None,
+    };
+    let Ok(collisions) = run_trajectory(table, initial, config) else {
+        return unclassified;
+    };
+    let Some(rotation) = outer_rotation_of(table, initial, &collisions)
+        .filter(|rotation| rotation.bounces >= criteria.min_bounces.max(1))
+    else {
+        return unclassified;
+    };
+
+    assert!(criteria.s_bins > 0, "Dispersion needs at least one cell.");
+    let length = table.outer.length();
+    let bins = criteria.s_bins;
+    let mut sums = vec![(0usize, 0.0, 0.0); bins];
+    for c in collisions.iter().filter(|c| c.component_index == 0) {
+        let p = c.theta.cos().abs();
+        let cell = &mut sums[((c.s / length * bins as f64) as usize).min(bins - 1)];
+        *cell = (cell.0 + 1, cell.1 + p, cell.2 + p * p);
+    }
+    let bounces = rotation.bounces as f64;
+    let dispersion = sums
+        .iter()
+        .filter(|&&(n, _, _)| n > 0)
+        .map(|&(n, sum, squares)| {
+            let mean = sum / n as f64;
+            n as f64 * (squares / n as f64 - mean * mean).max(0.0).sqrt()
+        })
+        .sum::<f64>()
+        / bounces;
+
+    OrbitClassification {
+        class: if rotation.spread <= criteria.rotation_tolerance
+            && dispersion <= criteria.dispersion_tolerance
+        {
+            OrbitClass::Regular
+        } else {
+            OrbitClass::Chaotic
+        },
+        rotation: Some(rotation.value),
+        rotation_spread: Some(rotation.spread),
+        dispersion: Some(dispersion),
+        ..unclassified
+    }
+}
+
+/// Orbits launched from the outer wall on a grid, each classified; see
+/// [`island_map`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct IslandMap {
+    pub s_count: usize,
+    pub p_count: usize,
+
+    /// The orbit launched from cell `(i, j)` is at `i * p_count + j`.
+    pub orbits: Vec<OrbitClassification>,
+}
+
+impl IslandMap {
+    /// Class of the orbit launched from cell `(i, j)`.
+    pub fn class(&self, i: usize, j: usize) -> OrbitClass {
+        self.orbits[i * self.p_count + j].class
+    }
+
+    /// Fraction of the classified orbits that are regular, an estimate of
+    /// the area of the islands; `None` if no orbit could be classified.
+    pub fn regular_fraction(&self) -> Option<f64> {
+        let count = |class| self.orbits.iter().filter(|o| o.class == class).count();
+        let regular = count(OrbitClass::Regular);
+        let classified = regular + count(OrbitClass::Chaotic);
+        (classified > 0).then(|| regular as f64 / classified as f64)
+    }
+}
+
+/// Classify orbits launched from the outer wall of `table` on an
+/// `s_count × p_count` grid with [`classify_orbit`], for a map of the
+/// islands and invariant curves of its phase space.
+///
+/// Launches are at the cell midpoints of `[0, L) × (-1, 1)` in `s` and
+/// `p = cos θ`, at unit speed, as in
+/// [`phase_portrait`](crate::dynamics::annulus::phase_portrait).
+///
+/// # Panics
+/// Panics if `criteria.s_bins` is zero.
+pub fn island_map(
+    table: &BilliardTable,
+    s_count: usize,
+    p_count: usize,
+    criteria: &RegularityCriteria,
+    config: &SimulationConfig,
+) -> IslandMap {
+    IslandMap {
+        s_count,
+        p_count,
+        orbits: outer_wall_grid(table, s_count, p_count)
+            .iter()
+            .map(|initial| classify_orbit(table, initial, criteria, config))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrbitClass, RegularityCriteria, classify_orbit, island_map};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{circle, mushroom};
+
+    fn config(max_steps: usize) -> SimulationConfig {
+        SimulationConfig::builder()
+            .max_steps(max_steps)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn cap_orbits_are_regular_and_stem_orbits_chaotic() {
+        // The cap arc of this mushroom runs from s = 2.25 to 2.25 + π.
+        let table = mushroom(1.0, 0.5, 1.0).to_billiard_table();
+        let criteria = RegularityCriteria::default();
+        let classify = |s: f64, p: f64| {
+            classify_orbit(
+                &table,
+                &BoundaryState::new(0, s, p.acos()),
+                &criteria,
+                &config(2000),
+            )
+        };
+
+        // Chords at |cos θ| = 0.6 stay 0.6 from the center of the cap, clear
+        // of the stem of half-width 0.25.
+        let cap = classify(2.5, 0.6);
+        assert_eq!(cap.class, OrbitClass::Regular);
+        assert!(cap.dispersion.unwrap() < 0.05);
+        assert_eq!(classify(4.0, -0.7).class, OrbitClass::Regular);
+
+        let stem = classify(0.9, 0.3);
+        assert_eq!(stem.class, OrbitClass::Chaotic);
+        assert!(stem.dispersion.unwrap() > 0.1);
+        assert_eq!(classify(2.5, 0.2).class, OrbitClass::Chaotic);
+    }
+
+    #[test]
+    fn mushroom_map_finds_islands_only_in_the_cap() {
+        let table = mushroom(1.0, 0.5, 1.0).to_billiard_table();
+        let length = table.outer.length();
+        let map = island_map(&table, 12, 8, &RegularityCriteria::default(), &config(2000));
+        assert_eq!(map.orbits.len(), 96);
+
+        for orbit in &map.orbits {
+            let (s, p) = (orbit.initial.s, orbit.initial.theta.cos());
+            let in_stem = s < 1.5 || s > length - 1.0;
+            let on_cap = s > 2.25 && s < 2.25 + std::f64::consts::PI;
+            if in_stem {
+                assert_ne!(orbit.class, OrbitClass::Regular, "{:?}", orbit);
+            } else if on_cap && p.abs() > 0.5 {
+                assert_eq!(orbit.class, OrbitClass::Regular, "s = {}, p = {}", s, p);
+            }
+        }
+        let fraction = map.regular_fraction().unwrap();
+        assert!(fraction > 0.1 && fraction < 0.9, "{}", fraction);
+    }
+
+    #[test]
+    fn circles_are_regular_everywhere() {
+        let table = circle(1.0).to_billiard_table();
+        let map = island_map(&table, 4, 6, &RegularityCriteria::default(), &config(1000));
+        assert!((0..4).all(|i| (0..6).all(|j| map.class(i, j) == OrbitClass::Regular)));
+        assert_eq!(map.regular_fraction(), Some(1.0));
+
+        let short = RegularityCriteria {
+            min_bounces: 2000,
+            ..RegularityCriteria::default()
+        };
+        let orbit = classify_orbit(&table, &map.orbits[0].initial, &short, &config(1000));
+        assert_eq!(orbit.class, OrbitClass::Unclassified);
+    }
+}
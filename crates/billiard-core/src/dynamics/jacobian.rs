@@ -0,0 +1,264 @@
+//! Derivative of the billiard map in Birkhoff coordinates.
+//!
+//! Birkhoff coordinates are `(s, p)` with `p = cos θ` the tangential
+//! component of the unit outgoing velocity. In them the billiard map
+//! preserves area, so every Jacobian has determinant 1.
+//!
+//! The chord length `τ(s₀, s₁)` generates the map: `p₀ = -∂τ/∂s₀` and
+//! `p₁ = ∂τ/∂s₁`. Differentiating once more gives
+//!
+//! ```text
+//! τ₀₀ = sin²θ₀ / τ - κ₀ sin θ₀
+//! τ₁₁ = sin²θ₁ / τ - κ₁ sin θ₁
+//! τ₀₁ = σ₀σ₁ sin θ₀ sin θ₁ / τ
+//! ```
+//!
+//! where `κ` is the curvature of the wall toward the domain (positive for
+//! focusing walls such as the inside of a circle, negative for dispersing
+//! ones such as a Sinai scatterer), `σ` is `+1` on the outer boundary and
+//! `-1` on obstacles (whose domain lies to the right of increasing `s`), and
+//! the derivative follows by solving for `(s₁, p₁)`.
+//!
+//! The formulas assume straight flight and elastic specular reflection at a
+//! smooth point of the boundary; they do not apply at corners.
+
+use crate::dynamics::simulation::CollisionResult;
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::{BilliardTable, ComponentRole};
+
+/// A 2×2 derivative `∂(s₁, p₁)/∂(s₀, p₀)`, stored row-major.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Jacobian(pub [[f64; 2]; 2]);
+
+impl Jacobian {
+    pub fn identity() -> Self {
+        Jacobian([[1.0, 0.0], [0.0, 1.0]])
+    }
+
+    /// The derivative of applying `self` and then `next`.
+    pub fn then(&self, next: &Jacobian) -> Jacobian {
+        let (a, b) = (next.0, self.0);
+        let entry = |i: usize, j: usize| a[i][0] * b[0][j] + a[i][1] * b[1][j];
+        Jacobian([[entry(0, 0), entry(0, 1)], [entry(1, 0), entry(1, 1)]])
+    }
+
+    pub fn trace(&self) -> f64 {
+        self.0[0][0] + self.0[1][1]
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.0[0][0] * self.0[1][1] - self.0[0][1] * self.0[1][0]
+    }
+
+    /// Whether a periodic orbit with this monodromy matrix is hyperbolic
+    /// (`|tr| > 2`), and hence unstable.
+    pub fn is_hyperbolic(&self) -> bool {
+        self.trace().abs() > 2.0
+    }
+}
+
+/// Which side of increasing `s` the domain lies on: `1` for the left.
+fn domain_side(table: &BilliardTable, component_index: usize) -> f64 {
+    match table.component(component_index).role {
+        ComponentRole::Outer => 1.0,
+        ComponentRole::Obstacle => -1.0,
+    }
+}
+
+/// Curvature of component `component_index` at `s`, signed toward the domain.
+fn domain_curvature(table: &BilliardTable, component_index: usize, s: f64) -> f64 {
+    domain_side(table, component_index) * table.component(component_index).curvature_at(s)
+}
+
+/// Derivative of the billiard map at the step from `from` to `to`, where
+/// `to` is the collision reached from `from`.
+pub fn collision_jacobian(
+    table: &BilliardTable,
+    from: &BoundaryState,
+    to: &CollisionResult,
+) -> Jacobian {
+    let start = from.to_world(table).position;
+    let tau = (to.hit_point - start).length();
+
+    let (sin0, sin1) = (from.theta.sin(), to.theta.sin());
+    let k0 = domain_curvature(table, from.component_index, from.s);
+    let k1 = domain_curvature(table, to.component_index, to.s);
+
+    let t00 = sin0 * sin0 / tau - k0 * sin0;
+    let t11 = sin1 * sin1 / tau - k1 * sin1;
+    let sides = domain_side(table, from.component_index) * domain_side(table, to.component_index);
+    let t01 = sides * sin0 * sin1 / tau;
+
+    Jacobian([
+        [-t00 / t01, -1.0 / t01],
+        [t01 - t11 * t00 / t01, -t11 / t01],
+    ])
+}
+
+/// Derivative of the billiard map at every step of a trajectory starting
+/// at `initial`.
+pub fn trajectory_jacobians(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    collisions: &[CollisionResult],
+) -> Vec<Jacobian> {
+    let mut from = *initial;
+    collisions
+        .iter()
+        .map(|c| {
+            let jacobian = collision_jacobian(table, &from, c);
+            from = c.outgoing_state();
+            jacobian
+        })
+        .collect()
+}
+
+/// Largest Lyapunov exponent of the billiard map along a trajectory from
+/// `initial`, per collision: the mean of `ln |Jₖ vₖ|` over a tangent vector
+/// renormalized after every step.
+///
+/// Positive for chaotic orbits; for regular ones it decays like
+/// `ln n / n`. Returns `None` for an empty trajectory or one through a
+/// corner, where the map has no derivative.
+pub fn lyapunov_exponent(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    collisions: &[CollisionResult],
+) -> Option<f64> {
+    if collisions.is_empty() || collisions.iter().any(|c| c.corner.is_some()) {
+        return None;
+    }
+
+    let mut v = [1.0, 0.0];
+    let mut sum = 0.0;
+    for jacobian in trajectory_jacobians(table, initial, collisions) {
+        let j = jacobian.0;
+        let w = [
+            j[0][0] * v[0] + j[0][1] * v[1],
+            j[1][0] * v[0] + j[1][1] * v[1],
+        ];
+        let norm = w[0].hypot(w[1]);
+        sum += norm.ln();
+        v = [w[0] / norm, w[1] / norm];
+    }
+    Some(sum / collisions.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Jacobian, collision_jacobian, lyapunov_exponent, trajectory_jacobians};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::simulation::{BilliardMap, StepOptions, run_trajectory};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::BilliardTable;
+    use crate::geometry::standard_tables::{circle, sinai, stadium};
+
+    /// Central finite-difference derivative of one bounce in `(s, cos θ)`.
+    fn numerical_jacobian(table: &BilliardTable, state: &BoundaryState) -> Jacobian {
+        let h = 1e-6;
+        let image = |ds: f64, dp: f64| {
+            let p = state.theta.cos() + dp;
+            let perturbed = BoundaryState {
+                s: state.s + ds,
+                theta: p.acos(),
+                ..*state
+            };
+            let c = BilliardMap::new(table, 1e-10, StepOptions::default())
+                .collision(&perturbed)
+                .unwrap()
+                .expect("perturbed orbit should still hit the table");
+            (
+                c.s,
+                c.theta.cos(),
+                table.component(c.component_index).length(),
+            )
+        };
+        let diff = |a: (f64, f64, f64), b: (f64, f64, f64)| {
+            // Unwrap s across the seam of the target component.
+            let ds = (a.0 - b.0 + 0.5 * a.2).rem_euclid(a.2) - 0.5 * a.2;
+            (ds / (2.0 * h), (a.1 - b.1) / (2.0 * h))
+        };
+
+        let (ds_ds, dp_ds) = diff(image(h, 0.0), image(-h, 0.0));
+        let (ds_dp, dp_dp) = diff(image(0.0, h), image(0.0, -h));
+        Jacobian([[ds_ds, ds_dp], [dp_ds, dp_dp]])
+    }
+
+    fn assert_close(a: &Jacobian, b: &Jacobian) {
+        for i in 0..2 {
+            for j in 0..2 {
+                let (x, y) = (a.0[i][j], b.0[i][j]);
+                assert!((x - y).abs() < 1e-4 * (1.0 + y.abs()), "{:?} vs {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_finite_differences_on_lines_arcs_and_scatterers() {
+        let cases = [
+            (
+                stadium(1.0, 0.5).to_billiard_table(),
+                BoundaryState::new(0, 0.3, 1.0),
+            ),
+            (
+                stadium(1.0, 0.5).to_billiard_table(),
+                BoundaryState::new(0, 1.2, 0.8),
+            ),
+            (
+                sinai(1.0, 0.2).to_billiard_table(),
+                BoundaryState::new(0, 0.4, 1.3),
+            ),
+            (
+                sinai(1.0, 0.2).to_billiard_table(),
+                BoundaryState::new(1, 0.3, 1.1),
+            ),
+        ];
+
+        for (table, state) in &cases {
+            let c = BilliardMap::new(table, 1e-10, StepOptions::default())
+                .collision(state)
+                .unwrap()
+                .unwrap();
+            let analytic = collision_jacobian(table, state, &c);
+            assert!((analytic.determinant() - 1.0).abs() < 1e-9);
+            assert_close(&analytic, &numerical_jacobian(table, state));
+        }
+    }
+
+    #[test]
+    fn sinai_orbits_are_hyperbolic_over_many_bounces() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let config = SimulationConfig::builder()
+            .max_steps(40)
+            .epsilon(1e-10)
+            .build()
+            .unwrap();
+        let collisions = run_trajectory(&table, &initial, &config).unwrap();
+
+        let product = trajectory_jacobians(&table, &initial, &collisions)
+            .iter()
+            .fold(Jacobian::identity(), |acc, j| acc.then(j));
+        assert!(product.is_hyperbolic());
+    }
+
+    #[test]
+    fn lyapunov_exponents_separate_chaos_from_order() {
+        let config = SimulationConfig::builder()
+            .max_steps(2000)
+            .epsilon(1e-10)
+            .build()
+            .unwrap();
+        let exponent = |table: &BilliardTable, initial: BoundaryState| {
+            let collisions = run_trajectory(table, &initial, &config).unwrap();
+            lyapunov_exponent(table, &initial, &collisions).unwrap()
+        };
+
+        let stadium = stadium(1.0, 0.5).to_billiard_table();
+        assert!(exponent(&stadium, BoundaryState::new(0, 0.3, 1.0)) > 0.1);
+
+        // A shear: tangent vectors grow linearly, not exponentially.
+        let circle = circle(1.0).to_billiard_table();
+        assert!(exponent(&circle, BoundaryState::new(0, 0.3, 1.0)).abs() < 0.01);
+    }
+}
@@ -0,0 +1,222 @@
+//! Following trajectories through the lattice of a periodic table.
+//!
+//! A table whose edges are paired with
+//! [`PeriodicPairedWith`](crate::geometry::segments::BoundaryCondition::PeriodicPairedWith)
+//! (such as [`lorentz_gas`](crate::geometry::standard_tables::lorentz_gas))
+//! is a single cell of an infinite periodic array. The simulation stays in
+//! that cell, teleporting the particle between paired edges; unfolding adds
+//! back the translation of every pass so the trajectory can be drawn, and
+//! its displacement measured, in the plane.
+
+use crate::dynamics::simulation::CollisionResult;
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::BoundaryCondition;
+
+/// A collision together with where it happened in the lattice.
+#[derive(Clone, Copy, Debug)]
+pub struct UnfoldedCollision {
+    pub collision: CollisionResult,
+
+    /// Hit point in the table itself.
+    pub cell_position: Vec2,
+
+    /// Hit point in the plane, with the starting cell at its original
+    /// position.
+    pub absolute_position: Vec2,
+
+    /// Translation from the starting cell to the cell the particle is in
+    /// after this collision.
+    pub cell_offset: Vec2,
+
+    /// Whether this was a pass through a periodic edge rather than a bounce.
+    pub periodic_pass: bool,
+}
+
+/// Accumulates the lattice translation of a trajectory, one collision at a
+/// time.
+#[derive(Clone, Copy, Debug)]
+pub struct Unfolder {
+    offset: Vec2,
+}
+
+impl Default for Unfolder {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::new(0.0, 0.0),
+        }
+    }
+}
+
+impl Unfolder {
+    /// Translation from the starting cell to the current one.
+    pub fn offset(&self) -> Vec2 {
+        self.offset
+    }
+
+    /// Place the next collision of the trajectory in the lattice.
+    pub fn unfold(
+        &mut self,
+        table: &BilliardTable,
+        collision: &CollisionResult,
+    ) -> UnfoldedCollision {
+        let absolute_position = collision.hit_point + self.offset;
+        let shift = periodic_shift(table, collision);
+        if let Some(shift) = shift {
+            self.offset = self.offset + shift;
+        }
+
+        UnfoldedCollision {
+            collision: *collision,
+            cell_position: collision.hit_point,
+            absolute_position,
+            cell_offset: self.offset,
+            periodic_pass: shift.is_some(),
+        }
+    }
+}
+
+/// If `collision` is a pass through a periodic edge, the translation from
+/// the cell the particle left to the one it entered.
+pub fn periodic_shift(table: &BilliardTable, collision: &CollisionResult) -> Option<Vec2> {
+    let component = table.component(collision.component_index);
+    let condition = component.segments[collision.segment_index].condition();
+    if !matches!(condition, BoundaryCondition::PeriodicPairedWith(_)) {
+        return None;
+    }
+
+    // The particle left through `hit_point` and re-entered at its outgoing
+    // position on the paired edge, which is the same point of the plane.
+    let entry = collision.outgoing_state().to_world(table).position;
+    Some(collision.hit_point - entry)
+}
+
+/// Unfold a whole trajectory; see [`Unfolder`].
+pub fn unfold_trajectory(
+    table: &BilliardTable,
+    collisions: &[CollisionResult],
+) -> Vec<UnfoldedCollision> {
+    let mut unfolder = Unfolder::default();
+    collisions
+        .iter()
+        .map(|c| unfolder.unfold(table, c))
+        .collect()
+}
+
+/// The path of a trajectory from `initial` as a polyline in the plane: the
+/// starting point followed by every hit point, unfolded through periodic
+/// edges so the line is unbroken. On a table without periodic edges the
+/// vertices are just the hit points.
+///
+/// Flights are drawn as straight chords, which is exact except under
+/// [`FlightModel::Gravity`](crate::dynamics::simulation::FlightModel::Gravity).
+pub fn trajectory_to_polyline(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    collisions: &[CollisionResult],
+) -> Vec<Vec2> {
+    std::iter::once(initial.to_world(table).position)
+        .chain(
+            unfold_trajectory(table, collisions)
+                .iter()
+                .map(|u| u.absolute_position),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{trajectory_to_polyline, unfold_trajectory};
+    use crate::dynamics::simulation::{BilliardMap, StepOptions};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::standard_tables::{lorentz_gas, sawtooth_channel, sinai};
+
+    #[test]
+    fn straight_flight_is_unfolded_into_a_line() {
+        // Slope 1/3 from (0.35, 0) passes at least 0.11 from every
+        // scatterer center, so the particle runs down a corridor forever.
+        let table = lorentz_gas(1.0, 0.05).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.35, (1.0_f64 / 3.0).atan());
+        let collisions = BilliardMap::new(&table, 1e-10, StepOptions::default())
+            .simulate(&initial, 6)
+            .unwrap()
+            .collisions;
+        let unfolded = unfold_trajectory(&table, &collisions);
+
+        let start = Vec2::new(0.35, 0.0);
+        assert!(unfolded.iter().all(|u| u.periodic_pass));
+        for u in &unfolded {
+            let d = u.absolute_position - start;
+            assert!((d.y - d.x / 3.0).abs() < 1e-9, "{:?}", u.absolute_position);
+            let cell = u.cell_position;
+            assert!((0.0..=1.0).contains(&cell.x) && (0.0..=1.0).contains(&cell.y));
+        }
+        let last = unfolded.last().unwrap();
+        assert!(last.absolute_position.x > 1.0);
+        assert_eq!(last.cell_offset.x.fract(), 0.0);
+
+        // The polyline starts at the launch point and stays on the line.
+        let polyline = trajectory_to_polyline(&table, &initial, &collisions);
+        assert_eq!(polyline.len(), 7);
+        assert!((polyline[0] - start).length() < 1e-12);
+        assert_eq!(polyline[6], last.absolute_position);
+    }
+
+    #[test]
+    fn bounces_in_the_starting_cell_are_not_shifted() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let collisions = BilliardMap::new(&table, 1e-10, StepOptions::default())
+            .simulate(&initial, 20)
+            .unwrap()
+            .collisions;
+
+        for u in unfold_trajectory(&table, &collisions) {
+            assert!(!u.periodic_pass);
+            assert_eq!(u.absolute_position, u.cell_position);
+        }
+    }
+
+    #[test]
+    fn scatterer_bounce_in_a_neighbouring_cell() {
+        // Straight up from (0.5, 0): hits the scatterer of the starting cell
+        // from below, comes back down, and enters the cell below.
+        let table = lorentz_gas(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.5, std::f64::consts::FRAC_PI_2);
+        let collisions = BilliardMap::new(&table, 1e-10, StepOptions::default())
+            .simulate(&initial, 3)
+            .unwrap()
+            .collisions;
+        let unfolded = unfold_trajectory(&table, &collisions);
+
+        assert!(!unfolded[0].periodic_pass);
+        assert!((unfolded[0].absolute_position - Vec2::new(0.5, 0.3)).length() < 1e-9);
+        assert!(unfolded[1].periodic_pass);
+        assert!((unfolded[1].cell_offset - Vec2::new(0.0, -1.0)).length() < 1e-12);
+        assert!((unfolded[2].absolute_position - Vec2::new(0.5, -0.3)).length() < 1e-9);
+        assert!((unfolded[2].cell_position - Vec2::new(0.5, 0.7)).length() < 1e-9);
+    }
+
+    #[test]
+    fn particles_wander_along_a_sawtooth_channel() {
+        // Three teeth per period of length 3; the particle only ever leaves
+        // the period sideways, and soon drifts several periods away.
+        let table = sawtooth_channel(3, 1.0, 1.0, 0.25, 0.3).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.2, 1.2);
+        let collisions = BilliardMap::new(&table, 1e-10, StepOptions::default())
+            .simulate(&initial, 2000)
+            .unwrap()
+            .collisions;
+        let unfolded = unfold_trajectory(&table, &collisions);
+
+        let mut farthest: f64 = 0.0;
+        for u in &unfolded {
+            assert!(u.cell_offset.y.abs() < 1e-9);
+            assert!((0.0..=1.0).contains(&u.absolute_position.y));
+            farthest = farthest.max(u.absolute_position.x.abs());
+        }
+        assert!(farthest > 9.0, "stayed within {} of the start", farthest);
+    }
+}
@@ -0,0 +1,390 @@
+//! Stable and unstable manifolds of hyperbolic periodic orbits.
+//!
+//! A periodic point `x₀` of period `n` is a fixed point of `Fⁿ`, the
+//! billiard map taken `n` times. If the orbit is hyperbolic its monodromy
+//! has eigenvalues `λ` and `1/λ` with `|λ| > 1`, and the points that `Fⁿ`
+//! pulls into `x₀` (the stable manifold) or pushes out of it (the unstable
+//! manifold) form curves through `x₀` along the two eigenvectors.
+//!
+//! Each curve is grown from a fundamental segment: the piece of the
+//! eigenvector line from `x₀ + δv` to `x₀ + δλv`, which `Fⁿ` maps onto the
+//! next piece of the curve. Iterating the segment `k` times gives the curve
+//! out to `δλᵏ⁺¹`; the unstable manifold uses the map forwards and the
+//! stable manifold backwards. Where the unstable manifold of an orbit
+//! crosses its stable one, the two fold over each other into a homoclinic
+//! tangle.
+//!
+//! The map is inverted by time reversal: reversing the direction of flight,
+//! `(s, p) ↦ (s, -p)`, turns `F` into `F⁻¹`.
+
+use crate::dynamics::periodic::PeriodicOrbit;
+use crate::dynamics::phase_space::component_offsets;
+use crate::dynamics::simulation::{BilliardMap, StepOptions};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+
+/// Controls for [`invariant_manifolds`].
+#[derive(Clone, Copy, Debug)]
+pub struct ManifoldOptions {
+    /// Distance `δ` from the periodic point at which the fundamental
+    /// segment starts, small enough for the eigenvector to be a good
+    /// approximation of the manifold.
+    pub initial_distance: f64,
+
+    /// Times the fundamental segment is mapped; each adds a factor `|λ|` to
+    /// the length of the curve.
+    pub segments: usize,
+
+    /// Points at which each branch stops growing, whatever `segments` says;
+    /// the length of a manifold grows exponentially with the segments.
+    pub max_points: usize,
+
+    /// Points sampled on the fundamental segment before refinement.
+    pub points_per_segment: usize,
+
+    /// Largest distance in `(s, cos θ)` between consecutive points of a
+    /// polyline; longer gaps are bisected.
+    pub max_gap: f64,
+
+    /// Deepest bisection of a gap; one still too long after it breaks the
+    /// polyline, as at a corner or where the manifold runs off an edge.
+    pub max_refinements: usize,
+
+    /// Distance within which a segment is taken to pass through the
+    /// current bounce point and is not hit.
+    pub epsilon: f64,
+}
+
+impl Default for ManifoldOptions {
+    fn default() -> Self {
+        Self {
+            initial_distance: 1e-6,
+            segments: 20,
+            max_points: 20_000,
+            points_per_segment: 50,
+            max_gap: 0.02,
+            max_refinements: 10,
+            epsilon: 1e-10,
+        }
+    }
+}
+
+/// The stable and unstable manifolds of a periodic point, as polylines in
+/// `(s, cos θ)` with `s` the total arc length (all components laid end to
+/// end, as in [`phase_space_points`]).
+///
+/// [`phase_space_points`]: crate::dynamics::phase_space::phase_space_points
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvariantManifolds {
+    /// The periodic point the manifolds go through.
+    pub point: (f64, f64),
+
+    /// Eigenvalue of the monodromy along the unstable manifold; `1/λ` is
+    /// the one along the stable manifold.
+    pub eigenvalue: f64,
+
+    /// Both branches of the unstable manifold, each starting next to the
+    /// periodic point and broken into several polylines where it cannot be
+    /// followed continuously.
+    pub unstable: Vec<Vec<(f64, f64)>>,
+
+    /// Both branches of the stable manifold, likewise.
+    pub stable: Vec<Vec<(f64, f64)>>,
+}
+
+/// Whether the map runs forwards or backwards in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Time {
+    Forward,
+    Backward,
+}
+
+/// Grow the stable and unstable manifolds of `orbit` at its first state,
+/// as `options` says.
+///
+/// Returns `None` if the orbit is not hyperbolic (see
+/// [`PeriodicOrbit::monodromy`]). When `λ < 0` the branches swap sides at
+/// every period, and are grown with `F²ⁿ` instead.
+pub fn invariant_manifolds(
+    table: &BilliardTable,
+    orbit: &PeriodicOrbit,
+    options: &ManifoldOptions,
+) -> Option<InvariantManifolds> {
+    let start = *orbit.states.first()?;
+    let monodromy = orbit.monodromy(table)?;
+    if !monodromy.is_hyperbolic() {
+        return None;
+    }
+
+    let m = monodromy.0;
+    let trace = monodromy.trace();
+    let eigenvalue = 0.5 * (trace + trace.signum() * (trace * trace - 4.0).sqrt());
+    let eigenvector = |lambda: f64| {
+        let a = [m[0][1], lambda - m[0][0]];
+        let b = [lambda - m[1][1], m[1][0]];
+        let v = if a[0].hypot(a[1]) >= b[0].hypot(b[1]) {
+            a
+        } else {
+            b
+        };
+        let norm = v[0].hypot(v[1]);
+        [v[0] / norm, v[1] / norm]
+    };
+    let (steps, expansion) = if eigenvalue > 0.0 {
+        (orbit.states.len(), eigenvalue)
+    } else {
+        (2 * orbit.states.len(), eigenvalue * eigenvalue)
+    };
+
+    let offsets = component_offsets(table);
+    let branches = |direction: [f64; 2], time: Time| {
+        [1.0, -1.0]
+            .into_iter()
+            .flat_map(|sign| {
+                let branch = Branch {
+                    table,
+                    start,
+                    direction: [sign * direction[0], sign * direction[1]],
+                    expansion,
+                    steps,
+                    time,
+                    offsets: &offsets,
+                    options,
+                };
+                branch.polylines()
+            })
+            .collect()
+    };
+
+    Some(InvariantManifolds {
+        point: (offsets[start.component_index] + start.s, start.theta.cos()),
+        eigenvalue,
+        unstable: branches(eigenvector(eigenvalue), Time::Forward),
+        stable: branches(eigenvector(1.0 / eigenvalue), Time::Backward),
+    })
+}
+
+/// One branch of a manifold: the images of the fundamental segment on one
+/// side of the periodic point.
+struct Branch<'a> {
+    table: &'a BilliardTable,
+    start: BoundaryState,
+    direction: [f64; 2],
+    expansion: f64,
+
+    /// Bounces in one application of the period map.
+    steps: usize,
+    time: Time,
+    offsets: &'a [f64],
+    options: &'a ManifoldOptions,
+}
+
+impl Branch<'_> {
+    /// The point at parameter `u`: the point a fraction `u - ⌊u⌋` of the way
+    /// (geometrically) along the fundamental segment, mapped `⌊u⌋` times.
+    fn point(&self, u: f64) -> Option<(f64, f64)> {
+        let k = u.floor();
+        let d = self.options.initial_distance * self.expansion.powf(u - k);
+        let p = self.start.theta.cos() + d * self.direction[1];
+        if !(p > -1.0 && p < 1.0) {
+            return None;
+        }
+        let length = self.table.component(self.start.component_index).length();
+        let state = BoundaryState {
+            s: (self.start.s + d * self.direction[0]).rem_euclid(length),
+            theta: p.acos(),
+            ..self.start
+        };
+        let end = iterate(
+            self.table,
+            &state,
+            k as usize * self.steps,
+            self.time,
+            self.options.epsilon,
+        )?;
+        Some((self.offsets[end.component_index] + end.s, end.theta.cos()))
+    }
+
+    /// Sample the branch and join the samples into polylines.
+    fn polylines(&self) -> Vec<Vec<(f64, f64)>> {
+        let per_segment = self.options.points_per_segment.max(1);
+        let mut lines = vec![Vec::new()];
+        let mut previous = None;
+        for i in 0..=self.options.segments * per_segment {
+            if lines.iter().map(Vec::len).sum::<usize>() >= self.options.max_points {
+                break;
+            }
+            let u = i as f64 / per_segment as f64;
+            match self.point(u) {
+                Some(q) => {
+                    match previous {
+                        Some(a) => {
+                            self.connect(a, (u, q), self.options.max_refinements, &mut lines)
+                        }
+                        None => lines.last_mut().expect("never empty").push(q),
+                    }
+                    previous = Some((u, q));
+                }
+                None => {
+                    previous = None;
+                    lines.push(Vec::new());
+                }
+            }
+        }
+        lines.retain(|line| line.len() >= 2);
+        lines
+    }
+
+    /// Append the points from `a` (already in the last line) to `b`,
+    /// bisecting gaps longer than the options allow, and start a new line
+    /// at `b` where a gap cannot be closed.
+    fn connect(
+        &self,
+        (ua, qa): (f64, (f64, f64)),
+        (ub, qb): (f64, (f64, f64)),
+        depth: usize,
+        lines: &mut Vec<Vec<(f64, f64)>>,
+    ) {
+        if (qa.0 - qb.0).hypot(qa.1 - qb.1) <= self.options.max_gap {
+            lines.last_mut().expect("never empty").push(qb);
+            return;
+        }
+        let um = 0.5 * (ua + ub);
+        match (depth > 0).then(|| self.point(um)).flatten() {
+            Some(qm) => {
+                self.connect((ua, qa), (um, qm), depth - 1, lines);
+                self.connect((um, qm), (ub, qb), depth - 1, lines);
+            }
+            None => lines.push(vec![qb]),
+        }
+    }
+}
+
+/// The state `count` bounces after `state`, or before it going backwards;
+/// `None` if the particle is absorbed or leaves the table on the way.
+fn iterate(
+    table: &BilliardTable,
+    state: &BoundaryState,
+    count: usize,
+    time: Time,
+    epsilon: f64,
+) -> Option<BoundaryState> {
+    let reverse = |state: BoundaryState| match time {
+        Time::Forward => state,
+        Time::Backward => BoundaryState {
+            theta: std::f64::consts::PI - state.theta,
+            ..state
+        },
+    };
+    let mut state = reverse(*state);
+    for _ in 0..count {
+        let collision = BilliardMap::new(table, epsilon, StepOptions::default())
+            .collision(&state)
+            .ok()??;
+        if collision.absorbed {
+            return None;
+        }
+        state = collision.outgoing_state();
+    }
+    Some(reverse(state))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use super::{ManifoldOptions, Time, invariant_manifolds, iterate};
+    use crate::dynamics::periodic::{NewtonOptions, PeriodicOrbit, refine_periodic_orbit};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::BilliardTable;
+    use crate::geometry::standard_tables::{sinai, stadium};
+
+    /// The period-2 orbit between the bottom wall of the Sinai table, at
+    /// `x = 0.5`, and the bottom of the disk.
+    fn sinai_orbit(table: &BilliardTable) -> PeriodicOrbit {
+        let approximate = [
+            BoundaryState::new(0, 0.5, FRAC_PI_2),
+            BoundaryState::new(1, 1.5 * PI * 0.2, FRAC_PI_2),
+        ];
+        refine_periodic_orbit(table, &approximate, &NewtonOptions::default())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+        (a.0 - b.0).hypot(a.1 - b.1)
+    }
+
+    #[test]
+    fn manifolds_flow_into_and_out_of_the_periodic_point() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let options = ManifoldOptions {
+            segments: 4,
+            ..ManifoldOptions::default()
+        };
+        let manifolds = invariant_manifolds(&table, &sinai_orbit(&table), &options).unwrap();
+        assert!((manifolds.point.0 - 0.5).abs() < 1e-9 && manifolds.point.1.abs() < 1e-9);
+        // Trace 2 + 4L/r = 8.
+        assert!((manifolds.eigenvalue - (4.0 + 15f64.sqrt())).abs() < 1e-6);
+        assert_eq!(manifolds.unstable.len(), 2);
+        assert_eq!(manifolds.stable.len(), 2);
+
+        // Points well away from the periodic point, still on the bottom
+        // wall, are pulled back to it by the map run the other way.
+        let back_to_point = |lines: &[Vec<(f64, f64)>], time: Time| {
+            for line in lines {
+                assert!(distance(line[0], manifolds.point) < 1e-5);
+                let &(s, p) = line
+                    .iter()
+                    .find(|&&q| distance(q, manifolds.point) > 1e-3)
+                    .unwrap();
+                let state = BoundaryState::new(0, s, p.acos());
+                let end = iterate(&table, &state, 2 * 4, time, 1e-10).unwrap();
+                let end = (end.s, end.theta.cos());
+                assert!(distance(end, manifolds.point) < 1e-5, "{:?}", end);
+            }
+        };
+        back_to_point(&manifolds.unstable, Time::Backward);
+        back_to_point(&manifolds.stable, Time::Forward);
+    }
+
+    #[test]
+    fn manifolds_stretch_across_phase_space() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let options = ManifoldOptions {
+            max_points: 2000,
+            ..ManifoldOptions::default()
+        };
+        let manifolds = invariant_manifolds(&table, &sinai_orbit(&table), &options).unwrap();
+
+        let points: Vec<(f64, f64)> = manifolds.unstable.iter().flatten().copied().collect();
+        let (lo, hi) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), q| {
+                (lo.min(q.1), hi.max(q.1))
+            });
+        assert!(hi - lo > 1.0, "cos θ from {} to {}", lo, hi);
+        for line in manifolds.unstable.iter().chain(&manifolds.stable) {
+            assert!(
+                line.windows(2)
+                    .all(|w| distance(w[0], w[1]) <= options.max_gap)
+            );
+        }
+    }
+
+    #[test]
+    fn parabolic_orbits_have_no_manifolds() {
+        // Bouncing straight up and down between the flat sides of the
+        // stadium: the monodromy is a shear, with trace 2.
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let approximate = [
+            BoundaryState::new(0, 0.5, FRAC_PI_2),
+            BoundaryState::new(0, 1.5 + 0.5 * PI, FRAC_PI_2),
+        ];
+        let orbit = refine_periodic_orbit(&table, &approximate, &NewtonOptions::default())
+            .unwrap()
+            .unwrap();
+        assert!((orbit.monodromy(&table).unwrap().trace() - 2.0).abs() < 1e-9);
+        assert!(invariant_manifolds(&table, &orbit, &ManifoldOptions::default()).is_none());
+    }
+}
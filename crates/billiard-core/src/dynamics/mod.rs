@@ -1,5 +1,34 @@
 //! Billiard dynamics: state representations and evolution.
 
+pub mod annulus;
+pub mod caustic;
+pub mod checkpoint;
+pub mod circle_map;
+pub mod config;
+pub mod ensemble;
+pub mod ergodicity;
+pub mod flow;
+pub mod gallery;
 pub mod intersection;
+pub mod invariants;
+pub mod islands;
+pub mod jacobian;
+pub mod lattice;
+pub mod manifolds;
+pub mod multibody;
+pub mod noise;
+pub mod observer;
+pub mod periodic;
+pub mod phase_space;
+pub mod precise;
+pub mod reflection;
+pub mod rotation;
+pub mod sampling;
+pub mod santalo;
+pub mod scattering;
+pub mod shadowing;
 pub mod simulation;
 pub mod state;
+pub mod survival;
+pub mod sweep;
+pub mod unfolding;
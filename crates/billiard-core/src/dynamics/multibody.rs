@@ -0,0 +1,482 @@
+//! Event-driven simulation of several hard disks inside a table.
+//!
+//! Unlike the single-particle billiard map, the disks here have a finite
+//! radius and a mass, and collide elastically with the boundary and with
+//! each other. Between events every disk moves in a straight line, so the
+//! next event of each kind can be predicted exactly; the predictions are
+//! kept in a priority queue and discarded once a disk they involve has
+//! collided with something else.
+//!
+//! Boundary conditions and holes are not consulted: every wall reflects.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::dynamics::intersection::arc_local_t;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::{BoundarySegment, CircularArcSegment, LineSegment};
+
+/// A hard disk moving freely between collisions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Disk {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub radius: f64,
+    pub mass: f64,
+}
+
+impl Disk {
+    pub fn kinetic_energy(&self) -> f64 {
+        0.5 * self.mass * self.velocity.dot(self.velocity)
+    }
+}
+
+/// What happened at a multibody event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultibodyEvent {
+    /// Disk `disk` bounced off a boundary segment.
+    Wall {
+        disk: usize,
+        component_index: usize,
+        segment_index: usize,
+    },
+
+    /// Disks `a` and `b` collided (`a < b`).
+    Pair { a: usize, b: usize },
+}
+
+/// A processed event and the time at which it happened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EventRecord {
+    pub time: f64,
+    pub event: MultibodyEvent,
+}
+
+/// A predicted event, valid while the involved disks have not collided
+/// since it was scheduled.
+#[derive(Clone, Copy, Debug)]
+struct Scheduled {
+    time: f64,
+    event: MultibodyEvent,
+    /// Collision counts of the involved disks when predicted.
+    counts: (u64, u64),
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    /// Reversed on time so that `BinaryHeap` pops the earliest event.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.time.total_cmp(&self.time)
+    }
+}
+
+/// A gas of hard disks in a billiard table.
+pub struct DiskGas<'a> {
+    table: &'a BilliardTable,
+    disks: Vec<Disk>,
+    time: f64,
+
+    /// Number of collisions each disk has taken part in.
+    counts: Vec<u64>,
+    queue: BinaryHeap<Scheduled>,
+}
+
+impl<'a> DiskGas<'a> {
+    /// Start a gas from the given disks at time 0.
+    ///
+    /// Disks must start inside the table without touching the boundary.
+    ///
+    /// # Panics
+    /// Panics if a radius or mass is not strictly positive, if two disks
+    /// overlap, or if the table has external segments.
+    pub fn new(table: &'a BilliardTable, disks: Vec<Disk>) -> Self {
+        assert!(
+            table
+                .components()
+                .flat_map(|component| &component.segments)
+                .all(|seg| !matches!(seg, BoundarySegment::External(_))),
+            "Disk gases support only built-in segments."
+        );
+        for (i, d) in disks.iter().enumerate() {
+            assert!(d.radius > 0.0, "Disk radius must be positive.");
+            assert!(d.mass > 0.0, "Disk mass must be positive.");
+            for other in &disks[i + 1..] {
+                assert!(
+                    (other.position - d.position).length() >= d.radius + other.radius,
+                    "Disks must not overlap."
+                );
+            }
+        }
+
+        let mut gas = Self {
+            table,
+            counts: vec![0; disks.len()],
+            disks,
+            time: 0.0,
+            queue: BinaryHeap::new(),
+        };
+        for i in 0..gas.disks.len() {
+            gas.predict(i);
+        }
+        gas
+    }
+
+    pub fn disks(&self) -> &[Disk] {
+        &self.disks
+    }
+
+    /// Current simulation time.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn kinetic_energy(&self) -> f64 {
+        self.disks.iter().map(Disk::kinetic_energy).sum()
+    }
+
+    /// Advance to the next collision and resolve it.
+    ///
+    /// Returns `None` if no further collision will ever happen (e.g. a single
+    /// disk escaping an open table).
+    pub fn next_event(&mut self) -> Option<EventRecord> {
+        let scheduled = self.pop_valid()?;
+        self.drift(scheduled.time - self.time);
+        self.resolve(scheduled.event);
+        Some(EventRecord {
+            time: scheduled.time,
+            event: scheduled.event,
+        })
+    }
+
+    /// Process every event up to time `t`, then move the disks to time `t`.
+    ///
+    /// Returns the events processed, in order.
+    pub fn advance_to(&mut self, t: f64) -> Vec<EventRecord> {
+        let mut events = Vec::new();
+        while let Some(scheduled) = self.pop_valid() {
+            if scheduled.time > t {
+                // Still valid; put it back for later.
+                self.queue.push(scheduled);
+                break;
+            }
+            self.drift(scheduled.time - self.time);
+            self.resolve(scheduled.event);
+            events.push(EventRecord {
+                time: scheduled.time,
+                event: scheduled.event,
+            });
+        }
+        if t > self.time {
+            self.drift(t - self.time);
+        }
+        events
+    }
+
+    /// Pop the earliest prediction that has not been invalidated.
+    fn pop_valid(&mut self) -> Option<Scheduled> {
+        while let Some(scheduled) = self.queue.pop() {
+            let current = match scheduled.event {
+                MultibodyEvent::Wall { disk, .. } => (self.counts[disk], 0),
+                MultibodyEvent::Pair { a, b } => (self.counts[a], self.counts[b]),
+            };
+            if current == scheduled.counts {
+                return Some(scheduled);
+            }
+        }
+        None
+    }
+
+    /// Move every disk in a straight line for `dt`.
+    fn drift(&mut self, dt: f64) {
+        for d in &mut self.disks {
+            d.position = d.position + d.velocity * dt;
+        }
+        self.time += dt;
+    }
+
+    fn resolve(&mut self, event: MultibodyEvent) {
+        match event {
+            MultibodyEvent::Wall {
+                disk,
+                component_index,
+                segment_index,
+            } => {
+                let d = &mut self.disks[disk];
+                let segment = &self.table.component(component_index).segments[segment_index];
+                let contact = closest_point(segment, d.position);
+                let n = (d.position - contact).normalized();
+                d.velocity = d.velocity - n * (2.0 * d.velocity.dot(n));
+
+                self.counts[disk] += 1;
+                self.predict(disk);
+            }
+            MultibodyEvent::Pair { a, b } => {
+                let (da, db) = (self.disks[a], self.disks[b]);
+                let n = (db.position - da.position).normalized();
+                let impulse = 2.0 * da.mass * db.mass / (da.mass + db.mass)
+                    * (da.velocity - db.velocity).dot(n);
+                self.disks[a].velocity = da.velocity - n * (impulse / da.mass);
+                self.disks[b].velocity = db.velocity + n * (impulse / db.mass);
+
+                self.counts[a] += 1;
+                self.counts[b] += 1;
+                self.predict(a);
+                self.predict(b);
+            }
+        }
+    }
+
+    /// Schedule the next wall hit of disk `i` and its next collision with
+    /// every other disk.
+    fn predict(&mut self, i: usize) {
+        let d = self.disks[i];
+
+        let wall = self
+            .table
+            .components()
+            .enumerate()
+            .flat_map(|(c, component)| {
+                (0..component.segments.len()).map(move |s| (c, s, component))
+            })
+            .filter_map(|(c, s, component)| {
+                let segment = &component.segments[s];
+                // Which side of the segment the table lies on.
+                let mid = component.global_s_from_segment_local(s, 0.5 * segment.length());
+                let (point, inward) = component.point_and_inward_normal_at(mid);
+                wall_time(segment, point, inward, &d).map(|t| (c, s, t))
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+        if let Some((component_index, segment_index, t)) = wall {
+            self.queue.push(Scheduled {
+                time: self.time + t,
+                event: MultibodyEvent::Wall {
+                    disk: i,
+                    component_index,
+                    segment_index,
+                },
+                counts: (self.counts[i], 0),
+            });
+        }
+
+        for j in (0..self.disks.len()).filter(|&j| j != i) {
+            if let Some(t) = pair_time(&d, &self.disks[j]) {
+                let (a, b) = (i.min(j), i.max(j));
+                self.queue.push(Scheduled {
+                    time: self.time + t,
+                    event: MultibodyEvent::Pair { a, b },
+                    counts: (self.counts[a], self.counts[b]),
+                });
+            }
+        }
+    }
+}
+
+/// Earliest `t >= 0` at which a point moving from `p` with velocity `v`
+/// reaches distance `radius` from `center`, approaching it from outside
+/// (`outside = true`) or from inside.
+fn circle_time(p: Vec2, v: Vec2, center: Vec2, radius: f64, outside: bool) -> Option<f64> {
+    let m = p - center;
+    let a = v.dot(v);
+    let b = m.dot(v);
+    let c = m.dot(m) - radius * radius;
+    let disc = b * b - a * c;
+    if a == 0.0 || disc < 0.0 {
+        return None;
+    }
+
+    if outside {
+        // Must be closing in on the circle.
+        (b < 0.0).then(|| ((-b - disc.sqrt()) / a).max(0.0))
+    } else {
+        Some(((-b + disc.sqrt()) / a).max(0.0))
+    }
+}
+
+/// Time until disk `d` touches `segment`, or `None` if it never does while
+/// moving in a straight line.
+///
+/// `inward` is the domain-side normal of the segment at `point`.
+fn wall_time(segment: &BoundarySegment, point: Vec2, inward: Vec2, d: &Disk) -> Option<f64> {
+    let (start, end) = (segment.point_at(0.0), segment.point_at(segment.length()));
+
+    // Rounded segment ends, which also handle convex corners.
+    let ends = [start, end]
+        .into_iter()
+        .filter_map(|q| circle_time(d.position, d.velocity, q, d.radius, true));
+
+    let body = match segment {
+        BoundarySegment::Line(line) => line_time(line, d),
+        BoundarySegment::CircularArc(arc) => {
+            let concave = inward.dot(arc.center - point) > 0.0;
+            arc_time(arc, concave, d)
+        }
+        BoundarySegment::External(_) => unreachable!("external segments are rejected"),
+    };
+
+    ends.chain(body).min_by(f64::total_cmp)
+}
+
+/// Time until disk `d` touches the interior of `line` from either side.
+fn line_time(line: &LineSegment, d: &Disk) -> Option<f64> {
+    let len = line.length();
+    let u = (line.end - line.start) / len;
+    let n = u.perp();
+
+    let distance = n.dot(d.position - line.start);
+    let closing = n.dot(d.velocity);
+    // Moving toward the line from whichever side the disk is on.
+    if distance * closing >= 0.0 {
+        return None;
+    }
+
+    let t = ((distance.abs() - d.radius) / closing.abs()).max(0.0);
+    let along = u.dot(d.position + d.velocity * t - line.start);
+    (0.0..=len).contains(&along).then_some(t)
+}
+
+/// Time until disk `d` touches `arc` from the domain side: from inside the
+/// circle if the arc is `concave` (as seen from the table), else from outside.
+fn arc_time(arc: &CircularArcSegment, concave: bool, d: &Disk) -> Option<f64> {
+    let contact_radius = if concave {
+        arc.radius - d.radius
+    } else {
+        arc.radius + d.radius
+    };
+    if contact_radius <= 0.0 {
+        return None;
+    }
+
+    let t = circle_time(d.position, d.velocity, arc.center, contact_radius, !concave)?;
+    let center = d.position + d.velocity * t;
+    let on_arc = arc.center + (center - arc.center).normalized() * arc.radius;
+    arc_local_t(arc, on_arc).map(|_| t)
+}
+
+/// Time until disks `a` and `b` touch, or `None` if they never do.
+fn pair_time(a: &Disk, b: &Disk) -> Option<f64> {
+    circle_time(
+        b.position - a.position,
+        b.velocity - a.velocity,
+        Vec2::new(0.0, 0.0),
+        a.radius + b.radius,
+        true,
+    )
+}
+
+/// Point of `segment` closest to `p`.
+fn closest_point(segment: &BoundarySegment, p: Vec2) -> Vec2 {
+    match segment {
+        BoundarySegment::Line(line) => {
+            let len = line.length();
+            let u = (line.end - line.start) / len;
+            line.point_at(u.dot(p - line.start).clamp(0.0, len))
+        }
+        BoundarySegment::CircularArc(arc) => {
+            let on_circle = arc.center + (p - arc.center).normalized() * arc.radius;
+            arc_local_t(arc, on_circle).map_or_else(
+                || {
+                    // Beyond the arc's span: the nearer endpoint.
+                    let (a, b) = (arc.point_at(0.0), arc.point_at(arc.length()));
+                    if (p - a).length() <= (p - b).length() {
+                        a
+                    } else {
+                        b
+                    }
+                },
+                |_| on_circle,
+            )
+        }
+        BoundarySegment::External(ext) => ext.curve.point_at(ext.curve.closest_point(p).0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Disk, DiskGas, MultibodyEvent};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::standard_tables::{sinai, stadium};
+
+    fn disk(x: f64, y: f64, vx: f64, vy: f64) -> Disk {
+        Disk {
+            position: Vec2::new(x, y),
+            velocity: Vec2::new(vx, vy),
+            radius: 0.05,
+            mass: 1.0,
+        }
+    }
+
+    #[test]
+    fn head_on_equal_masses_swap_velocities() {
+        let table = sinai(1.0, 0.1).to_billiard_table();
+        // Both on the line y = 0.2, below the central obstacle.
+        let disks = vec![disk(0.2, 0.2, 1.0, 0.0), disk(0.8, 0.2, -1.0, 0.0)];
+        let mut gas = DiskGas::new(&table, disks);
+
+        let event = gas.next_event().expect("disks should collide");
+        assert_eq!(event.event, MultibodyEvent::Pair { a: 0, b: 1 });
+        // Gap of 0.6 minus two radii, closed at relative speed 2.
+        assert!((event.time - 0.25).abs() < 1e-12);
+
+        let d = gas.disks();
+        assert!((d[0].velocity.x + 1.0).abs() < 1e-12);
+        assert!((d[1].velocity.x - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn disk_bounces_off_wall_at_one_radius() {
+        let table = sinai(1.0, 0.1).to_billiard_table();
+        let mut gas = DiskGas::new(&table, vec![disk(0.2, 0.5, -1.0, 0.0)]);
+
+        let event = gas.next_event().expect("disk should hit the left wall");
+        assert!(matches!(event.event, MultibodyEvent::Wall { disk: 0, .. }));
+        assert!((event.time - 0.15).abs() < 1e-12);
+        assert!((gas.disks()[0].position.x - 0.05).abs() < 1e-12);
+        assert!((gas.disks()[0].velocity.x - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gas_conserves_energy_and_stays_inside() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let disks = vec![
+            disk(-0.3, 0.0, 1.0, 0.3),
+            disk(0.0, 0.2, -0.4, 0.8),
+            disk(0.3, -0.1, 0.2, -1.1),
+            disk(0.1, -0.3, -0.9, -0.2),
+        ];
+        let mut gas = DiskGas::new(&table, disks);
+        let energy = gas.kinetic_energy();
+
+        let events = gas.advance_to(20.0);
+        assert!(events.len() > 50);
+        assert!(events.windows(2).all(|w| w[0].time <= w[1].time));
+        assert!((gas.time() - 20.0).abs() < 1e-12);
+        assert!((gas.kinetic_energy() - energy).abs() < 1e-9);
+
+        for d in gas.disks() {
+            // Inside the stadium shrunk by one disk radius.
+            let p = d.position;
+            let dx = (p.x.abs() - 0.5).max(0.0);
+            assert!(dx.hypot(p.y) <= 0.5 - 0.05 + 1e-9, "{:?}", p);
+        }
+        let d = gas.disks();
+        for i in 0..d.len() {
+            for j in i + 1..d.len() {
+                assert!((d[i].position - d[j].position).length() >= 0.1 - 1e-9);
+            }
+        }
+    }
+}
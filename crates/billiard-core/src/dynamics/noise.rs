@@ -0,0 +1,236 @@
+//! Random kicks to the outgoing angle, for noise-driven billiards.
+//!
+//! At every bounce the angle between the outgoing direction and the normal
+//! is shifted by a random amount, then kept short of grazing so that the
+//! particle always leaves into the table. The kicks actually applied are
+//! returned with the trajectory, so an experiment can be replayed or
+//! analysed (e.g. diffusion against the kick variance).
+
+use rand::{RngExt, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use crate::dynamics::reflection::{Impact, Reflection, ReflectionLaw, Specular, exponential};
+use crate::dynamics::simulation::{BilliardMap, DynamicsError, StepOptions, Trajectory};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::segments::BoundaryCondition;
+
+/// Closest a kicked outgoing direction may come to the wall, in radians.
+const GRAZING_MARGIN: f64 = 1e-9;
+
+/// Distribution of the angle kicks, in radians.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum AngleNoise {
+    /// Normal with mean zero and standard deviation `sigma`.
+    Gaussian { sigma: f64 },
+
+    /// Uniform on `[-half_width, half_width]`.
+    Uniform { half_width: f64 },
+}
+
+impl AngleNoise {
+    /// Draw one kick.
+    pub fn sample(&self, rng: &mut ChaCha8Rng) -> f64 {
+        match *self {
+            AngleNoise::Gaussian { sigma } => {
+                // Box–Muller.
+                let radius = (2.0 * exponential(rng)).sqrt();
+                let v = rng.random_range(0.0_f64..1.0);
+                sigma * radius * (TAU * v).cos()
+            }
+            AngleNoise::Uniform { half_width } => {
+                if half_width > 0.0 {
+                    rng.random_range(-half_width..=half_width)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// A reflection law that kicks the outgoing angle of another law.
+///
+/// Every kick applied (after the grazing limit) is recorded, in order.
+#[derive(Clone, Debug)]
+pub struct Kicked<L> {
+    inner: L,
+    noise: AngleNoise,
+    rng: ChaCha8Rng,
+    kicks: Vec<f64>,
+}
+
+impl<L: ReflectionLaw> Kicked<L> {
+    /// # Panics
+    /// Panics if the width of `noise` is negative or not finite.
+    pub fn new(inner: L, noise: AngleNoise, seed: u64) -> Self {
+        let width = match noise {
+            AngleNoise::Gaussian { sigma } => sigma,
+            AngleNoise::Uniform { half_width } => half_width,
+        };
+        assert!(
+            width.is_finite() && width >= 0.0,
+            "Noise width must be non-negative and finite."
+        );
+        Kicked {
+            inner,
+            noise,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            kicks: Vec::new(),
+        }
+    }
+
+    /// Kicks applied so far, one per reflection.
+    pub fn kicks(&self) -> &[f64] {
+        &self.kicks
+    }
+}
+
+impl<L: ReflectionLaw> ReflectionLaw for Kicked<L> {
+    fn reflect(&mut self, impact: &Impact) -> Reflection {
+        let reflection = self.inner.reflect(impact);
+        let n = impact.normal;
+        let t = n.perp();
+        let v = reflection.velocity;
+
+        let angle = v.dot(t).atan2(v.dot(n));
+        let limit = FRAC_PI_2 - GRAZING_MARGIN;
+        let kicked = (angle + self.noise.sample(&mut self.rng)).clamp(-limit, limit);
+        self.kicks.push(kicked - angle);
+
+        Reflection {
+            velocity: (n * kicked.cos() + t * kicked.sin()) * v.length(),
+            ..reflection
+        }
+    }
+}
+
+/// A trajectory with kicked reflections.
+#[derive(Clone, Debug)]
+pub struct NoisyTrajectory {
+    pub trajectory: Trajectory,
+
+    /// `kicks[i]` is the angle kick applied at `trajectory.collisions[i]`;
+    /// 0 for a pass through a periodic segment.
+    pub kicks: Vec<f64>,
+}
+
+/// Simulate like
+/// [`BilliardMap::simulate`](crate::dynamics::simulation::BilliardMap::simulate),
+/// but kick the outgoing angle of every specular reflection by a draw from
+/// `noise`.
+///
+/// The kicks are reproducible for a given `seed`.
+///
+/// # Panics
+/// Panics if the width of `noise` is negative or not finite.
+pub fn simulate_noisy(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    max_steps: usize,
+    epsilon: f64,
+    options: &StepOptions,
+    noise: AngleNoise,
+    seed: u64,
+) -> Result<NoisyTrajectory, DynamicsError> {
+    let mut law = Kicked::new(Specular, noise, seed);
+    let trajectory = BilliardMap::new(table, epsilon, *options)
+        .with_law(|impact: &Impact| law.reflect(impact))
+        .simulate(initial, max_steps)?;
+
+    // The law is not consulted for periodic passes.
+    let mut applied = law.kicks.into_iter();
+    let kicks = trajectory
+        .collisions
+        .iter()
+        .map(|c| {
+            let segment = &table.component(c.component_index).segments[c.segment_index];
+            match segment.condition() {
+                BoundaryCondition::PeriodicPairedWith(_) => 0.0,
+                _ => applied.next().expect("one kick per reflection"),
+            }
+        })
+        .collect();
+
+    Ok(NoisyTrajectory { trajectory, kicks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AngleNoise, simulate_noisy};
+    use crate::dynamics::simulation::{BilliardMap, StepOptions};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::sinai;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn zero_noise_is_the_ordinary_simulation() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let options = StepOptions::default();
+
+        // Few enough bounces that rebuilding the direction from its angle
+        // does not visibly change the chaotic orbit.
+        let plain = BilliardMap::new(&table, 1e-8, options)
+            .simulate(&initial, 8)
+            .unwrap();
+        let noisy = simulate_noisy(
+            &table,
+            &initial,
+            8,
+            1e-8,
+            &options,
+            AngleNoise::Uniform { half_width: 0.0 },
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(noisy.kicks, vec![0.0; 8]);
+        for (a, b) in plain.collisions.iter().zip(&noisy.trajectory.collisions) {
+            assert!((a.s - b.s).abs() < 1e-9);
+            assert!((a.theta - b.theta).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn kicks_are_recorded_bounded_and_reproducible() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let options = StepOptions::default();
+        let noise = AngleNoise::Uniform { half_width: 0.1 };
+
+        let run =
+            |seed| simulate_noisy(&table, &initial, 200, 1e-8, &options, noise, seed).unwrap();
+        let noisy = run(4);
+        let collisions = &noisy.trajectory.collisions;
+
+        assert_eq!(noisy.kicks.len(), collisions.len());
+        assert!(noisy.kicks.iter().all(|k| k.abs() <= 0.1 + 1e-12));
+        assert!(noisy.kicks.iter().any(|k| k.abs() > 0.01));
+        assert!(
+            collisions
+                .iter()
+                .all(|c| c.theta > 0.0 && c.theta < std::f64::consts::PI)
+        );
+        assert_eq!(noisy.kicks, run(4).kicks);
+        assert_ne!(noisy.kicks, run(5).kicks);
+    }
+
+    #[test]
+    fn gaussian_kicks_have_the_requested_spread() {
+        let mut rng = ChaCha8Rng::seed_from_u64(9);
+        let noise = AngleNoise::Gaussian { sigma: 0.05 };
+        let draws: Vec<f64> = (0..20_000).map(|_| noise.sample(&mut rng)).collect();
+
+        let n = draws.len() as f64;
+        let mean = draws.iter().sum::<f64>() / n;
+        let sd = (draws.iter().map(|k| (k - mean).powi(2)).sum::<f64>() / n).sqrt();
+        assert!(mean.abs() < 2e-3, "mean {}", mean);
+        assert!((sd - 0.05).abs() < 2e-3, "standard deviation {}", sd);
+    }
+}
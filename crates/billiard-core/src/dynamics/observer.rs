@@ -0,0 +1,484 @@
+//! Statistics gathered collision by collision.
+//!
+//! An [`Observer`] sees every collision of a trajectory as it is simulated
+//! by [`BilliardMap::observe`], so long runs can be summarized without
+//! keeping the collisions: memory stays at the size of the statistics.
+//! Observers combine as pairs, `(a, b)`, and [`from_fn`] makes one of a
+//! closure.
+//!
+//! Built in are the [`PhaseSpaceHistogram`] of the bounces, the
+//! [`AngleDistribution`] of their angles and the [`SegmentHits`] on each
+//! segment.
+//!
+//! [`BilliardMap::observe`]: crate::dynamics::simulation::BilliardMap::observe
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::phase_space::{PhaseSpaceHistogram, passes_through};
+use crate::dynamics::simulation::CollisionResult;
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+
+/// Something that accumulates statistics of a trajectory on the fly.
+pub trait Observer {
+    /// Record `collision`, the `step`-th of a trajectory on `table`
+    /// (counting from 0), reached by the particle leaving `from`.
+    fn observe(
+        &mut self,
+        table: &BilliardTable,
+        step: usize,
+        from: &BoundaryState,
+        collision: &CollisionResult,
+    );
+}
+
+impl<O: Observer + ?Sized> Observer for &mut O {
+    fn observe(
+        &mut self,
+        table: &BilliardTable,
+        step: usize,
+        from: &BoundaryState,
+        collision: &CollisionResult,
+    ) {
+        (**self).observe(table, step, from, collision)
+    }
+}
+
+impl<A: Observer, B: Observer> Observer for (A, B) {
+    fn observe(
+        &mut self,
+        table: &BilliardTable,
+        step: usize,
+        from: &BoundaryState,
+        collision: &CollisionResult,
+    ) {
+        self.0.observe(table, step, from, collision);
+        self.1.observe(table, step, from, collision);
+    }
+}
+
+/// An observer calling `f` with the arguments of [`Observer::observe`].
+pub fn from_fn<F>(f: F) -> FromFn<F>
+where
+    F: FnMut(&BilliardTable, usize, &BoundaryState, &CollisionResult),
+{
+    FromFn(f)
+}
+
+/// The observer of [`from_fn`].
+#[derive(Clone, Copy, Debug)]
+pub struct FromFn<F>(F);
+
+impl<F> Observer for FromFn<F>
+where
+    F: FnMut(&BilliardTable, usize, &BoundaryState, &CollisionResult),
+{
+    fn observe(
+        &mut self,
+        table: &BilliardTable,
+        step: usize,
+        from: &BoundaryState,
+        collision: &CollisionResult,
+    ) {
+        (self.0)(table, step, from, collision)
+    }
+}
+
+/// Bins each bounce as [`PhaseSpaceHistogram::add`] does.
+impl Observer for PhaseSpaceHistogram {
+    fn observe(
+        &mut self,
+        table: &BilliardTable,
+        _step: usize,
+        _from: &BoundaryState,
+        collision: &CollisionResult,
+    ) {
+        if passes_through(table, collision) {
+            return;
+        }
+        let offset: f64 = table
+            .components()
+            .take(collision.component_index)
+            .map(|component| component.length())
+            .sum();
+        let x = (offset + collision.s) / table.perimeter();
+        self.insert(x, collision.theta.cos());
+    }
+}
+
+/// Histograms of the outgoing angle `θ` to the tangent, over `[0, π]`, and
+/// of `cos θ`, the momentum of the Birkhoff map, over `[-1, 1]`. Passes
+/// through periodic segments are not bounces and are skipped.
+///
+/// Under the invariant measure `θ` has density `sin θ / 2`, so bounces
+/// near the normal are the most common, and `cos θ` is uniform. Deviations
+/// from [`expected_counts`](AngleDistribution::expected_counts) and
+/// [`expected_cos_counts`](AngleDistribution::expected_cos_counts) show a
+/// trajectory that has not equilibrated, or a table that is not ergodic.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AngleDistribution {
+    /// Bounces in each of the equal bins of `θ`, from `θ = 0`.
+    pub counts: Vec<usize>,
+
+    /// Bounces in each of the equal bins of `cos θ`, from `-1`, so in the
+    /// opposite order to [`counts`](AngleDistribution::counts): grazing
+    /// bounces with `θ` near 0 land in the last bin.
+    pub cos_counts: Vec<usize>,
+
+    pub samples: usize,
+}
+
+impl AngleDistribution {
+    /// Empty histograms of `bins` bins each.
+    ///
+    /// # Panics
+    /// Panics if `bins` is zero.
+    pub fn new(bins: usize) -> Self {
+        assert!(bins > 0, "Angle distribution needs at least one bin.");
+        AngleDistribution {
+            counts: vec![0; bins],
+            cos_counts: vec![0; bins],
+            samples: 0,
+        }
+    }
+
+    /// The histograms of the bounces of `collisions`, a trajectory on
+    /// `table`.
+    ///
+    /// # Panics
+    /// Panics if `bins` is zero.
+    pub fn from_collisions(
+        table: &BilliardTable,
+        collisions: &[CollisionResult],
+        bins: usize,
+    ) -> Self {
+        let mut distribution = AngleDistribution::new(bins);
+        for c in collisions {
+            if !passes_through(table, c) {
+                distribution.insert(c.theta);
+            }
+        }
+        distribution
+    }
+
+    /// Add the bounces counted by `other` to these histograms.
+    ///
+    /// # Panics
+    /// Panics if `other` has a different number of bins.
+    pub fn merge(&mut self, other: &AngleDistribution) {
+        assert_eq!(
+            self.counts.len(),
+            other.counts.len(),
+            "Only angle distributions with the same bins can be merged."
+        );
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        for (count, other) in self.cos_counts.iter_mut().zip(&other.cos_counts) {
+            *count += other;
+        }
+        self.samples += other.samples;
+    }
+
+    fn insert(&mut self, theta: f64) {
+        let bins = self.counts.len();
+        let bin = |x: f64| (x * bins as f64).clamp(0.0, (bins - 1) as f64) as usize;
+        self.counts[bin(theta / PI)] += 1;
+        self.cos_counts[bin((theta.cos() + 1.0) / 2.0)] += 1;
+        self.samples += 1;
+    }
+
+    /// Fraction of the bounces in each bin divided by the bin width: an
+    /// estimate of the density of `θ`. All zero if there are no samples.
+    pub fn density(&self) -> Vec<f64> {
+        let width = PI / self.counts.len() as f64;
+        let total = self.samples.max(1) as f64;
+        self.counts
+            .iter()
+            .map(|&count| count as f64 / total / width)
+            .collect()
+    }
+
+    /// Bounces each bin of [`counts`](AngleDistribution::counts) would
+    /// hold at equilibrium, for the same number of samples.
+    pub fn expected_counts(&self) -> Vec<f64> {
+        let bins = self.counts.len();
+        let edge = |k: usize| (k as f64 / bins as f64 * PI).cos();
+        (0..bins)
+            .map(|k| self.samples as f64 * (edge(k) - edge(k + 1)) / 2.0)
+            .collect()
+    }
+
+    /// Bounces each bin of [`cos_counts`](AngleDistribution::cos_counts)
+    /// would hold at equilibrium, for the same number of samples.
+    pub fn expected_cos_counts(&self) -> Vec<f64> {
+        let bins = self.cos_counts.len();
+        vec![self.samples as f64 / bins as f64; bins]
+    }
+}
+
+impl Observer for AngleDistribution {
+    fn observe(
+        &mut self,
+        table: &BilliardTable,
+        _step: usize,
+        _from: &BoundaryState,
+        collision: &CollisionResult,
+    ) {
+        if !passes_through(table, collision) {
+            self.insert(collision.theta);
+        }
+    }
+}
+
+/// Collisions on each segment of each component, passes through periodic
+/// segments included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentHits {
+    /// `counts[component][segment]`, components numbered as in
+    /// [`BilliardTable::component`].
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl SegmentHits {
+    /// No hits yet on any segment of `table`.
+    pub fn new(table: &BilliardTable) -> Self {
+        SegmentHits {
+            counts: table
+                .components()
+                .map(|component| vec![0; component.segments.len()])
+                .collect(),
+        }
+    }
+
+    /// Hits on segment `segment_index` of component `component_index`.
+    pub fn count(&self, component_index: usize, segment_index: usize) -> usize {
+        self.counts[component_index][segment_index]
+    }
+
+    /// The hits of `collisions`, a trajectory on `table`.
+    pub fn from_collisions(table: &BilliardTable, collisions: &[CollisionResult]) -> Self {
+        let mut hits = SegmentHits::new(table);
+        for c in collisions {
+            hits.counts[c.component_index][c.segment_index] += 1;
+        }
+        hits
+    }
+
+    /// Hits on every segment together.
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    /// Share of the hits on each segment of `table` over its share of the
+    /// boundary length, indexed like [`SegmentHits::counts`].
+    ///
+    /// The invariant measure hits every piece of the boundary in proportion
+    /// to its length, so an ergodic trajectory uses each segment about
+    /// equally and scores near 1 on all of them; segments well above or
+    /// below 1, or never hit, point to an orbit confined to part of phase
+    /// space. `None` before the first hit.
+    pub fn relative_usage(&self, table: &BilliardTable) -> Option<Vec<Vec<f64>>> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let perimeter = table.perimeter();
+        let usage = table
+            .components()
+            .zip(&self.counts)
+            .map(|(component, counts)| {
+                component
+                    .segments
+                    .iter()
+                    .zip(counts)
+                    .map(|(segment, &count)| {
+                        (count as f64 / total as f64) / (segment.length() / perimeter)
+                    })
+                    .collect()
+            })
+            .collect();
+        Some(usage)
+    }
+}
+
+impl Observer for SegmentHits {
+    fn observe(
+        &mut self,
+        _table: &BilliardTable,
+        _step: usize,
+        _from: &BoundaryState,
+        collision: &CollisionResult,
+    ) {
+        self.counts[collision.component_index][collision.segment_index] += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AngleDistribution, SegmentHits, from_fn};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::phase_space::{PhaseSpaceHistogram, phase_space_histogram};
+    use crate::dynamics::simulation::{BilliardMap, CollisionResult, Termination, run_trajectory};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::BilliardTable;
+    use crate::geometry::standard_tables::{circle, lorentz_gas, rectangle, stadium};
+
+    fn config(max_steps: usize) -> SimulationConfig {
+        SimulationConfig::builder()
+            .max_steps(max_steps)
+            .epsilon(1e-10)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn observers_agree_with_the_collected_trajectory() {
+        let table = lorentz_gas(1.0, 0.3).to_billiard_table();
+        let initial = BoundaryState::new(1, 0.2, 1.1);
+        let config = config(500);
+        let collisions = run_trajectory(&table, &initial, &config).unwrap();
+
+        let mut histogram = PhaseSpaceHistogram::new(8, 4);
+        let mut hits = SegmentHits::new(&table);
+        let mut steps = Vec::new();
+        let record = from_fn(
+            |_: &BilliardTable, step, from: &BoundaryState, c: &CollisionResult| {
+                steps.push((step, *from, c.s));
+            },
+        );
+        let mut observers = ((&mut histogram, &mut hits), record);
+        let termination = BilliardMap::from_config(&table, &config)
+            .observe(&initial, config.max_steps(), &mut observers)
+            .unwrap();
+        assert_eq!(termination, Termination::MaxSteps);
+
+        assert_eq!(
+            histogram,
+            phase_space_histogram(&table, [&collisions[..]], 8, 4)
+        );
+        assert_eq!(hits.total(), collisions.len());
+        // The periodic edges are passed through, and counted.
+        assert!(hits.counts[0].iter().all(|&n| n > 0));
+        assert_eq!(steps.len(), collisions.len());
+        assert_eq!(steps[0].1, initial);
+        for (k, (step, from, s)) in steps.iter().enumerate().skip(1) {
+            assert_eq!(*step, k);
+            assert_eq!(*from, collisions[k - 1].outgoing_state());
+            assert_eq!(*s, collisions[k].s);
+        }
+    }
+
+    #[test]
+    fn chaotic_angles_follow_the_sine_law() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let mut angles = AngleDistribution::new(6);
+        BilliardMap::from_config(&table, &config(20_000))
+            .observe(&BoundaryState::new(0, 0.3, 1.0), 20_000, &mut angles)
+            .unwrap();
+        assert_eq!(angles.samples, 20_000);
+
+        let width = std::f64::consts::PI / 6.0;
+        for (i, density) in angles.density().into_iter().enumerate() {
+            let expected =
+                ((i as f64 * width).cos() - ((i + 1) as f64 * width).cos()) / 2.0 / width;
+            assert!((density - expected).abs() < 0.05, "bin {}: {}", i, density);
+        }
+    }
+
+    #[test]
+    fn incidence_angles_reach_equilibrium_in_the_stadium() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let collisions =
+            run_trajectory(&table, &BoundaryState::new(0, 0.3, 1.0), &config(20_000)).unwrap();
+        let histogram = AngleDistribution::from_collisions(&table, &collisions, 8);
+        assert_eq!(histogram.samples, 20_000);
+        assert_eq!(histogram.counts.iter().sum::<usize>(), 20_000);
+
+        let expected = histogram.expected_counts();
+        assert!((expected.iter().sum::<f64>() - 20_000.0).abs() < 1e-6);
+        // Symmetric about the normal, and largest next to it.
+        assert!((expected[0] - expected[7]).abs() < 1e-9);
+        assert!(expected[3] > 4.0 * expected[0]);
+        for (count, expected) in histogram.counts.iter().zip(expected).chain(
+            histogram
+                .cos_counts
+                .iter()
+                .zip(histogram.expected_cos_counts()),
+        ) {
+            let error = (*count as f64 - expected).abs() / expected;
+            assert!(error < 0.1, "{} against {}", count, expected);
+        }
+
+        // Bouncing straight back along the normal, into the middle bins.
+        let mut normal = AngleDistribution::new(3);
+        let bounce = BoundaryState::new(0, 0.5, std::f64::consts::FRAC_PI_2);
+        BilliardMap::from_config(&table, &config(10))
+            .observe(&bounce, 10, &mut normal)
+            .unwrap();
+        assert_eq!(normal.samples, 10);
+        assert_eq!(normal.counts, [0, 10, 0]);
+        assert_eq!(normal.cos_counts, [0, 10, 0]);
+
+        // Counting in two halves and merging gives the same histograms.
+        let mut halves = AngleDistribution::from_collisions(&table, &collisions[..5_000], 8);
+        halves.merge(&AngleDistribution::from_collisions(
+            &table,
+            &collisions[5_000..],
+            8,
+        ));
+        assert_eq!(halves, histogram);
+    }
+
+    #[test]
+    fn angle_and_cosine_bins_run_in_opposite_directions() {
+        let mut angles = AngleDistribution::new(4);
+        for theta in [0.1, 0.1, 0.1, 1.0, 3.0] {
+            angles.insert(theta);
+        }
+        // θ = 1 is in the second bin of θ, and cos 1 ≈ 0.54 in the fourth
+        // of cos θ, next to the grazing bounces.
+        assert_eq!(angles.counts, [3, 1, 0, 1]);
+        assert_eq!(angles.cos_counts, [1, 0, 0, 4]);
+        assert_eq!(angles.expected_cos_counts(), [1.25; 4]);
+    }
+
+    #[test]
+    fn circles_are_observed_in_closed_form_up_to_max_time() {
+        let table = circle(1.0).to_billiard_table();
+        let config = SimulationConfig::builder().max_steps(100).build().unwrap();
+        let mut hits = SegmentHits::new(&table);
+        let termination = BilliardMap::from_config(&table, &config)
+            .observe_for(&BoundaryState::new(0, 0.0, 1.0), 100, 10.0, &mut hits)
+            .unwrap();
+        // Chords of 2 sin 1 ≈ 1.683.
+        assert_eq!(termination, Termination::MaxTime);
+        assert_eq!(hits.count(0, 0), 5);
+    }
+
+    #[test]
+    fn segment_usage_tells_ergodic_orbits_from_confined_ones() {
+        let stadium = stadium(1.0, 0.5).to_billiard_table();
+        let collisions =
+            run_trajectory(&stadium, &BoundaryState::new(0, 0.3, 1.0), &config(20_000)).unwrap();
+        let hits = SegmentHits::from_collisions(&stadium, &collisions);
+        for usage in hits.relative_usage(&stadium).unwrap().concat() {
+            assert!((usage - 1.0).abs() < 0.1, "{}", usage);
+        }
+
+        // Straight up and down between the long sides of a 2 × 1 box.
+        let box_table = rectangle(2.0, 1.0).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.7, std::f64::consts::FRAC_PI_2);
+        let collisions = run_trajectory(&box_table, &initial, &config(100)).unwrap();
+        let hits = SegmentHits::from_collisions(&box_table, &collisions);
+        assert_eq!(hits.counts, [[50, 0, 50, 0]]);
+        let usage = hits.relative_usage(&box_table).unwrap();
+        assert_eq!(usage, [[1.5, 0.0, 1.5, 0.0]]);
+        assert_eq!(
+            SegmentHits::new(&box_table).relative_usage(&box_table),
+            None
+        );
+    }
+}
@@ -0,0 +1,411 @@
+//! Newton refinement of periodic orbits.
+//!
+//! A periodic orbit bouncing on the segments of an itinerary, in order, is a
+//! critical point of the total path length
+//! `L = Σ |γ(tᵢ₊₁) - γ(tᵢ)|` as a function of the local positions `tᵢ` on
+//! those segments: setting `∂L/∂tᵢ = 0` is exactly the law of reflection at
+//! bounce `i`. The Hessian of `L` is cyclic tridiagonal and cheap to build
+//! from chord lengths, tangents and curvatures, so Newton's method converges
+//! quadratically from a reasonable starting guess.
+
+use crate::dynamics::jacobian::{Jacobian, collision_jacobian};
+use crate::dynamics::simulation::{BilliardMap, DynamicsError, StepOptions};
+use crate::dynamics::state::{BoundaryState, WorldState};
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::BoundarySegment;
+
+/// Distance within which a re-simulated bounce must land on the refined one.
+const ORBIT_CHECK_TOLERANCE: f64 = 1e-7;
+
+/// One bounce of a symbolic itinerary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItineraryStep {
+    pub component_index: usize,
+    pub segment_index: usize,
+}
+
+/// Controls for the Newton iteration.
+#[derive(Clone, Copy, Debug)]
+pub struct NewtonOptions {
+    pub max_iterations: usize,
+
+    /// Converged once every `|∂L/∂tᵢ|` is below this.
+    pub tolerance: f64,
+}
+
+impl Default for NewtonOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 50,
+            tolerance: 1e-12,
+        }
+    }
+}
+
+/// A refined periodic orbit.
+#[derive(Clone, Debug)]
+pub struct PeriodicOrbit {
+    /// Outgoing state at each bounce, in itinerary order.
+    pub states: Vec<BoundaryState>,
+
+    /// Total length of one period.
+    pub length: f64,
+
+    /// Newton iterations used.
+    pub iterations: usize,
+}
+
+impl PeriodicOrbit {
+    /// Derivative of the billiard map over one period at the first state,
+    /// in `(s, cos θ)`; the orbit is hyperbolic if this is (see
+    /// [`Jacobian::is_hyperbolic`]). `None` if a bounce cannot be
+    /// re-simulated on `table`.
+    pub fn monodromy(&self, table: &BilliardTable) -> Option<Jacobian> {
+        self.states
+            .iter()
+            .try_fold(Jacobian::identity(), |product, state| {
+                let next = BilliardMap::new(table, ORBIT_CHECK_TOLERANCE, StepOptions::default())
+                    .collision(state)
+                    .ok()??;
+                Some(product.then(&collision_jacobian(table, state, &next)))
+            })
+    }
+}
+
+/// Refine the periodic orbit with the given `itinerary`, starting from the
+/// midpoint of each segment.
+///
+/// Returns `None` if Newton's method does not converge, a bounce leaves its
+/// segment, or the converged chords are not a real orbit of the table (e.g.
+/// a chord is blocked by an obstacle).
+///
+/// # Errors
+/// Fails if a step names a component or segment the table does not have.
+pub fn periodic_orbit_from_itinerary(
+    table: &BilliardTable,
+    itinerary: &[ItineraryStep],
+    options: &NewtonOptions,
+) -> Result<Option<PeriodicOrbit>, DynamicsError> {
+    let guess = itinerary
+        .iter()
+        .map(|step| Ok(0.5 * try_segment_of(table, step)?.length()))
+        .collect::<Result<Vec<f64>, DynamicsError>>()?;
+    Ok(refine(table, itinerary, guess, options))
+}
+
+/// Refine an approximately periodic orbit, given as its outgoing boundary
+/// states (e.g. the states of a near-return found by simulation).
+///
+/// The itinerary is read off the states. See
+/// [`periodic_orbit_from_itinerary`] for when `None` is returned.
+///
+/// # Errors
+/// Fails if a state cannot be placed on `table`; see
+/// [`BoundaryState::check`].
+pub fn refine_periodic_orbit(
+    table: &BilliardTable,
+    approximate: &[BoundaryState],
+    options: &NewtonOptions,
+) -> Result<Option<PeriodicOrbit>, DynamicsError> {
+    let mut itinerary = Vec::with_capacity(approximate.len());
+    let mut guess = Vec::with_capacity(approximate.len());
+    for state in approximate {
+        state.check(table)?;
+        let (segment_index, t) = table.component(state.component_index).locate(state.s);
+        itinerary.push(ItineraryStep {
+            component_index: state.component_index,
+            segment_index,
+        });
+        guess.push(t);
+    }
+    Ok(refine(table, &itinerary, guess, options))
+}
+
+/// The segment of `step`, or an error if the table does not have it.
+fn try_segment_of<'a>(
+    table: &'a BilliardTable,
+    step: &ItineraryStep,
+) -> Result<&'a BoundarySegment, DynamicsError> {
+    let Some(component) = table.get_component(step.component_index) else {
+        return Err(DynamicsError::UnknownComponent {
+            component_index: step.component_index,
+        });
+    };
+    component
+        .segments
+        .get(step.segment_index)
+        .ok_or(DynamicsError::UnknownSegment {
+            component_index: step.component_index,
+            segment_index: step.segment_index,
+        })
+}
+
+fn segment_of<'a>(table: &'a BilliardTable, step: &ItineraryStep) -> &'a BoundarySegment {
+    &table.component(step.component_index).segments[step.segment_index]
+}
+
+fn refine(
+    table: &BilliardTable,
+    itinerary: &[ItineraryStep],
+    mut t: Vec<f64>,
+    options: &NewtonOptions,
+) -> Option<PeriodicOrbit> {
+    let n = itinerary.len();
+    if n < 2 {
+        return None;
+    }
+
+    for iteration in 0..=options.max_iterations {
+        let (gradient, hessian) = length_derivatives(table, itinerary, &t);
+        if gradient.iter().all(|g| g.abs() < options.tolerance) {
+            return finish(table, itinerary, &t, iteration);
+        }
+        if iteration == options.max_iterations {
+            break;
+        }
+
+        let step = solve(hessian, gradient.iter().map(|g| -g).collect())?;
+        for (i, dt) in step.into_iter().enumerate() {
+            let length = segment_of(table, &itinerary[i]).length();
+            t[i] = (t[i] + dt).clamp(0.0, length);
+        }
+    }
+    None
+}
+
+/// Gradient and Hessian of the total path length in the local positions.
+fn length_derivatives(
+    table: &BilliardTable,
+    itinerary: &[ItineraryStep],
+    t: &[f64],
+) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = itinerary.len();
+    let geometry: Vec<(Vec2, Vec2, Vec2)> = itinerary
+        .iter()
+        .zip(t)
+        .map(|(step, &t)| {
+            let seg = segment_of(table, step);
+            let tangent = seg.tangent_at(t);
+            let turn = tangent.perp() * seg.curvature_at(t);
+            (seg.point_at(t), tangent, turn)
+        })
+        .collect();
+
+    let mut gradient = vec![0.0; n];
+    let mut hessian = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        let j = (i + 1) % n;
+        let (xi, ti, ki) = geometry[i];
+        let (xj, tj, kj) = geometry[j];
+        let chord = xj - xi;
+        let tau = chord.length();
+        let u = chord / tau;
+        let (ui, uj) = (u.dot(ti), u.dot(tj));
+
+        gradient[i] -= ui;
+        gradient[j] += uj;
+        hessian[i][i] += (1.0 - ui * ui) / tau - u.dot(ki);
+        hessian[j][j] += (1.0 - uj * uj) / tau + u.dot(kj);
+        let mixed = -(ti.dot(tj) - ui * uj) / tau;
+        hessian[i][j] += mixed;
+        hessian[j][i] += mixed;
+    }
+    (gradient, hessian)
+}
+
+/// Solve `a x = b` by Gaussian elimination with partial pivoting, or `None`
+/// if `a` is (numerically) singular.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    let scale = a.iter().flatten().fold(0.0_f64, |m, x| m.max(x.abs()));
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&r, &s| a[r][col].abs().total_cmp(&a[s][col].abs()))?;
+        if a[pivot][col].abs() <= 1e-12 * scale {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            for (x, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * p;
+            }
+            b[col + 1 + offset] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Build the orbit from converged positions and check that the table
+/// really produces it.
+fn finish(
+    table: &BilliardTable,
+    itinerary: &[ItineraryStep],
+    t: &[f64],
+    iterations: usize,
+) -> Option<PeriodicOrbit> {
+    let n = itinerary.len();
+    let points: Vec<Vec2> = itinerary
+        .iter()
+        .zip(t)
+        .map(|(step, &t)| segment_of(table, step).point_at(t))
+        .collect();
+
+    let states: Vec<BoundaryState> = (0..n)
+        .map(|i| {
+            let step = &itinerary[i];
+            let s = table
+                .component(step.component_index)
+                .global_s_from_segment_local(step.segment_index, t[i]);
+            WorldState::new(points[i], points[(i + 1) % n] - points[i]).to_boundary(
+                table,
+                step.component_index,
+                s,
+            )
+        })
+        .collect();
+
+    // Every chord must point into the table and reach the next bounce
+    // unobstructed.
+    for (i, state) in states.iter().enumerate() {
+        if !(state.theta > 0.0 && state.theta < std::f64::consts::PI) {
+            return None;
+        }
+        let hit =
+            BilliardMap::new(table, ORBIT_CHECK_TOLERANCE, StepOptions::default()).collision(state);
+        let next = points[(i + 1) % n];
+        if hit
+            .ok()
+            .flatten()
+            .is_none_or(|c| (c.hit_point - next).length() > ORBIT_CHECK_TOLERANCE)
+        {
+            return None;
+        }
+    }
+
+    let length = (0..n)
+        .map(|i| (points[(i + 1) % n] - points[i]).length())
+        .sum();
+    Some(PeriodicOrbit {
+        states,
+        length,
+        iterations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ItineraryStep, NewtonOptions, periodic_orbit_from_itinerary, refine_periodic_orbit,
+    };
+    use crate::dynamics::simulation::DynamicsError;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{sinai, stadium};
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn stadium_major_axis_orbit_from_itinerary() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let itinerary = [
+            ItineraryStep {
+                component_index: 0,
+                segment_index: 1,
+            },
+            ItineraryStep {
+                component_index: 0,
+                segment_index: 3,
+            },
+        ];
+
+        let orbit = periodic_orbit_from_itinerary(&table, &itinerary, &NewtonOptions::default())
+            .unwrap()
+            .expect("the major axis is a period-2 orbit");
+        assert!((orbit.length - 4.0).abs() < 1e-12);
+        for state in &orbit.states {
+            assert!((state.theta - FRAC_PI_2).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn perturbed_sinai_orbit_converges_to_normal_bounce() {
+        // Period 2 between the bottom wall at x = 0.5 and the bottom of the
+        // disk, started off by a little.
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let disk_bottom = 1.5 * std::f64::consts::PI * 0.2;
+        let approximate = [
+            BoundaryState::new(0, 0.47, 1.5),
+            BoundaryState::new(1, disk_bottom + 0.02, 1.6),
+        ];
+
+        let orbit = refine_periodic_orbit(&table, &approximate, &NewtonOptions::default())
+            .unwrap()
+            .expect("Newton should converge");
+        assert!(orbit.iterations > 0);
+        assert!((orbit.states[0].s - 0.5).abs() < 1e-10);
+        assert!((orbit.states[1].s - disk_bottom).abs() < 1e-10);
+        assert!((orbit.length - 2.0 * 0.3).abs() < 1e-12);
+
+        // Flat wall against a disk of radius r at distance L: the trace is
+        // 2 + 4L/r.
+        let monodromy = orbit.monodromy(&table).unwrap();
+        assert!((monodromy.determinant() - 1.0).abs() < 1e-9);
+        assert!((monodromy.trace() - (2.0 + 4.0 * 1.5)).abs() < 1e-6);
+        assert!(monodromy.is_hyperbolic());
+    }
+
+    #[test]
+    fn blocked_orbit_is_rejected() {
+        // Bottom wall to the top of the disk passes through the disk.
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let approximate = [
+            BoundaryState::new(0, 0.5, FRAC_PI_2),
+            BoundaryState::new(1, 0.5 * std::f64::consts::PI * 0.2, FRAC_PI_2),
+        ];
+        let orbit = refine_periodic_orbit(&table, &approximate, &NewtonOptions::default());
+        assert!(orbit.unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_segment_is_an_error() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let itinerary = [
+            ItineraryStep {
+                component_index: 0,
+                segment_index: 1,
+            },
+            ItineraryStep {
+                component_index: 0,
+                segment_index: 9,
+            },
+        ];
+        let result = periodic_orbit_from_itinerary(&table, &itinerary, &NewtonOptions::default());
+        assert_eq!(
+            result.unwrap_err(),
+            DynamicsError::UnknownSegment {
+                component_index: 0,
+                segment_index: 9,
+            }
+        );
+
+        let approximate = [
+            BoundaryState::new(0, 0.5, 1.0),
+            BoundaryState::new(3, 0.5, 1.0),
+        ];
+        let result = refine_periodic_orbit(&table, &approximate, &NewtonOptions::default());
+        assert_eq!(
+            result.unwrap_err(),
+            DynamicsError::UnknownComponent { component_index: 3 }
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Binning bounces on the Poincaré section.
+//!
+//! The phase space of the billiard map is the set of pairs `(s, p)` with
+//! `s` the total arc length (all components laid end to end) and
+//! `p = cos θ` the tangential component of the outgoing direction, i.e.
+//! the sine of its angle to the normal. In these coordinates the invariant
+//! measure is uniform, so a density plot of the bounces of a chaotic
+//! trajectory is flat, while regular orbits trace curves and islands.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::simulation::CollisionResult;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::segments::BoundaryCondition;
+
+/// Bounce counts on an `s_bins × p_bins` grid over `[0, |∂Q|) × (-1, 1)`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PhaseSpaceHistogram {
+    pub s_bins: usize,
+    pub p_bins: usize,
+
+    /// Bounces in each cell; cell `(i, j)` is at `i * p_bins + j`.
+    pub counts: Vec<usize>,
+
+    /// Bounces binned (passes through periodic segments are skipped).
+    pub samples: usize,
+}
+
+impl PhaseSpaceHistogram {
+    /// An empty histogram.
+    ///
+    /// # Panics
+    /// Panics if either bin count is zero.
+    pub fn new(s_bins: usize, p_bins: usize) -> Self {
+        assert!(
+            s_bins > 0 && p_bins > 0,
+            "Phase space histogram needs at least one bin in each direction."
+        );
+        PhaseSpaceHistogram {
+            s_bins,
+            p_bins,
+            counts: vec![0; s_bins * p_bins],
+            samples: 0,
+        }
+    }
+
+    /// Bin the outgoing states of `collisions`, a trajectory on `table`.
+    /// Call once per trajectory to overlay several.
+    pub fn add(&mut self, table: &BilliardTable, collisions: &[CollisionResult]) {
+        let total = table.perimeter();
+        for (u, p) in phase_space_points(table, collisions) {
+            self.insert(u / total, p);
+        }
+    }
+
+    /// Bin one bounce a fraction `x` of the way along the boundary, with
+    /// `p = cos θ`.
+    pub(crate) fn insert(&mut self, x: f64, p: f64) {
+        let i = ((x * self.s_bins as f64) as usize).min(self.s_bins - 1);
+        let j = (((p + 1.0) / 2.0 * self.p_bins as f64) as usize).min(self.p_bins - 1);
+        self.counts[i * self.p_bins + j] += 1;
+        self.samples += 1;
+    }
+
+    /// Number of bounces in cell `(i, j)`.
+    pub fn count(&self, i: usize, j: usize) -> usize {
+        self.counts[i * self.p_bins + j]
+    }
+
+    /// Largest count of any cell.
+    pub fn max_count(&self) -> usize {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Coordinates `(s, cos θ)` of the outgoing state of each bounce in
+/// `collisions`, a trajectory on `table`, with `s` the total arc length;
+/// passes through periodic segments are skipped.
+pub fn phase_space_points(
+    table: &BilliardTable,
+    collisions: &[CollisionResult],
+) -> Vec<(f64, f64)> {
+    let offsets = component_offsets(table);
+    collisions
+        .iter()
+        .filter(|c| !passes_through(table, c))
+        .map(|c| (offsets[c.component_index] + c.s, c.theta.cos()))
+        .collect()
+}
+
+/// Whether `collision` is a pass through a periodic segment of `table`
+/// rather than a bounce.
+pub(crate) fn passes_through(table: &BilliardTable, collision: &CollisionResult) -> bool {
+    let segment = &table.component(collision.component_index).segments[collision.segment_index];
+    matches!(
+        segment.condition(),
+        BoundaryCondition::PeriodicPairedWith(_)
+    )
+}
+
+/// Total arc length at which each component of `table` starts, with the
+/// components laid end to end.
+pub(crate) fn component_offsets(table: &BilliardTable) -> Vec<f64> {
+    table
+        .components()
+        .scan(0.0, |start, component| {
+            let offset = *start;
+            *start += component.length();
+            Some(offset)
+        })
+        .collect()
+}
+
+/// Bin the bounces of every trajectory in `trajectories`, all on `table`;
+/// see [`PhaseSpaceHistogram`].
+///
+/// # Panics
+/// Panics if either bin count is zero.
+pub fn phase_space_histogram<'c>(
+    table: &BilliardTable,
+    trajectories: impl IntoIterator<Item = &'c [CollisionResult]>,
+    s_bins: usize,
+    p_bins: usize,
+) -> PhaseSpaceHistogram {
+    let mut histogram = PhaseSpaceHistogram::new(s_bins, p_bins);
+    for collisions in trajectories {
+        histogram.add(table, collisions);
+    }
+    histogram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{phase_space_histogram, phase_space_points};
+    use crate::dynamics::simulation::{BilliardMap, StepOptions};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{lorentz_gas, rectangle};
+
+    #[test]
+    fn bounces_land_in_the_cell_of_their_coordinates() {
+        // A diagonal orbit in the unit square bounces at the midpoints of
+        // the sides, at 45° to each wall: s = 0.5 + k and p = cos(π/4).
+        let table = rectangle(1.0, 1.0).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.5, std::f64::consts::FRAC_PI_4);
+        let options = StepOptions::default();
+        let collisions = BilliardMap::new(&table, 1e-10, options)
+            .simulate(&initial, 8)
+            .unwrap()
+            .collisions;
+
+        let (s, p) = phase_space_points(&table, &collisions)[0];
+        assert!((s - 1.5).abs() < 1e-9 && (p - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-9);
+
+        let histogram = phase_space_histogram(&table, [&collisions[..]; 2], 4, 4);
+        assert_eq!(histogram.samples, 16);
+        assert_eq!(histogram.max_count(), 4);
+        for i in 0..4 {
+            assert_eq!(histogram.count(i, 3), 4);
+        }
+
+        // Passes through periodic edges are not bounces.
+        let cell = lorentz_gas(1.0, 0.3).to_billiard_table();
+        let collisions = BilliardMap::new(&cell, 1e-10, options)
+            .simulate(&initial, 50)
+            .unwrap()
+            .collisions;
+        let histogram = phase_space_histogram(&cell, [&collisions[..]], 10, 10);
+        assert!(histogram.samples < 50);
+        assert_eq!(histogram.counts.iter().sum::<usize>(), histogram.samples);
+    }
+}
@@ -0,0 +1,333 @@
+//! Elastic billiard trajectories in extended (or reduced) precision.
+//!
+//! The state carried from bounce to bounce is the hit point and unit
+//! direction in a generic [`Scalar`], never the `f64` arc-length and angle,
+//! so running in [`DoubleDouble`](crate::geometry::scalar::DoubleDouble)
+//! keeps about 32 digits along the whole trajectory. Running in `f32`
+//! goes the other way: the collisions take half the memory, and the
+//! results match what a single-precision GPU kernel would compute, with
+//! an `epsilon` of around `1e-5` instead of `1e-8`. Only the straight,
+//! elastic, specular model is supported; the boundary itself is still
+//! described in `f64`, which is exact for the endpoints, centers and radii
+//! it is built from.
+
+use crate::dynamics::intersection::arc_local_t;
+use crate::dynamics::simulation::{CollisionResult, DEFAULT_CORNER_TOLERANCE, Termination};
+use crate::dynamics::state::{BoundaryState, WorldState};
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::scalar::{Scalar, Vector};
+use crate::geometry::segments::{BoundaryCondition, BoundarySegment};
+
+/// Position and unit direction of a particle in scalar type `T`.
+#[derive(Clone, Copy, Debug)]
+pub struct PreciseState<T> {
+    pub position: Vector<T>,
+    pub direction: Vector<T>,
+}
+
+impl<T: Scalar> PreciseState<T> {
+    /// Exact copy of a world state, with the direction normalized in `T`.
+    /// The speed is ignored: elastic bounces preserve it.
+    pub fn from_world(ws: &WorldState) -> Self {
+        PreciseState {
+            position: Vector::from_vec2(ws.position),
+            direction: Vector::from_vec2(ws.direction).normalized(),
+        }
+    }
+
+    /// The world state of a boundary state, computed in `f64` and then
+    /// carried on in `T`.
+    pub fn from_boundary(table: &BilliardTable, bs: &BoundaryState) -> Self {
+        Self::from_world(&bs.to_world(table))
+    }
+}
+
+/// One bounce of a precise trajectory.
+#[derive(Clone, Copy, Debug)]
+pub struct PreciseCollision<T> {
+    pub component_index: usize,
+    pub segment_index: usize,
+
+    /// Length of the flight ending here.
+    pub chord: T,
+
+    /// Hit point and outgoing direction.
+    pub outgoing: PreciseState<T>,
+}
+
+impl<T: Scalar> PreciseCollision<T> {
+    /// Round to an ordinary collision record, e.g. to compare with
+    /// [`BilliardMap::simulate`](crate::dynamics::simulation::BilliardMap::simulate)
+    /// or to feed the usual output formats.
+    pub fn to_collision_result(&self, table: &BilliardTable) -> CollisionResult {
+        let component = table.component(self.component_index);
+        let segment = &component.segments[self.segment_index];
+        let hit_point = self.outgoing.position.to_vec2();
+        let (local_t, _) = segment.closest_point(hit_point);
+        let s = component.global_s_from_segment_local(self.segment_index, local_t);
+
+        let outgoing = WorldState::new(hit_point, self.outgoing.direction.to_vec2()).to_boundary(
+            table,
+            self.component_index,
+            s,
+        );
+
+        CollisionResult::new(
+            outgoing,
+            self.segment_index,
+            hit_point,
+            self.chord.to_f64(),
+            component.corner_near(self.segment_index, local_t, DEFAULT_CORNER_TOLERANCE),
+            segment.condition() == BoundaryCondition::Absorb || component.is_absorbing(s),
+        )
+        .labelled(table)
+    }
+}
+
+/// A precise trajectory together with the reason it ended: `MaxSteps`,
+/// `NoCollision` or `Absorbed`.
+#[derive(Clone, Debug)]
+pub struct PreciseTrajectory<T> {
+    pub collisions: Vec<PreciseCollision<T>>,
+    pub termination: Termination,
+}
+
+/// Simulate up to `max_steps` elastic bounces in scalar type `T`.
+///
+/// `epsilon` is the distance a ray must travel before a hit counts, so that
+/// it leaves its own bounce, and can be much smaller in higher precision. Corners are reflected off
+/// the segment that was hit; the trajectory stops at an absorbing segment
+/// or hole, whose hit is the last collision returned.
+///
+/// # Panics
+/// Panics if the table has periodic, no-slip or external segments.
+pub fn simulate_precise<T: Scalar>(
+    table: &BilliardTable,
+    initial: &PreciseState<T>,
+    max_steps: usize,
+    epsilon: T,
+) -> PreciseTrajectory<T> {
+    assert!(
+        table.components().all(|component| {
+            component.segments.iter().all(|seg| {
+                matches!(
+                    seg.condition(),
+                    BoundaryCondition::Reflect | BoundaryCondition::Absorb
+                )
+            })
+        }),
+        "Precise simulation supports only reflecting and absorbing segments."
+    );
+    assert!(
+        table.components().all(|component| {
+            component
+                .segments
+                .iter()
+                .all(|seg| !matches!(seg, BoundarySegment::External(_)))
+        }),
+        "Precise simulation supports only built-in segments."
+    );
+
+    let mut collisions = Vec::with_capacity(max_steps);
+    let mut current = *initial;
+
+    for _ in 0..max_steps {
+        let Some((component_index, segment_index, t)) = next_hit(table, &current, epsilon) else {
+            return PreciseTrajectory {
+                collisions,
+                termination: Termination::NoCollision,
+            };
+        };
+
+        let hit = current.position + current.direction.scale(t);
+        let normal = match &table.component(component_index).segments[segment_index] {
+            BoundarySegment::Line(line) => {
+                let edge = Vector::<T>::from_vec2(line.end - line.start);
+                Vector::new(-edge.y, edge.x).normalized()
+            }
+            BoundarySegment::CircularArc(arc) => (hit - Vector::from_vec2(arc.center)).normalized(),
+            BoundarySegment::External(_) => unreachable!("external segments are rejected above"),
+        };
+        let d = current.direction;
+        let reflected = d - normal.scale(T::from_f64(2.0) * d.dot(normal));
+
+        current = PreciseState {
+            position: hit,
+            direction: reflected.normalized(),
+        };
+        let collision = PreciseCollision {
+            component_index,
+            segment_index,
+            chord: t,
+            outgoing: current,
+        };
+        collisions.push(collision);
+
+        if collision.to_collision_result(table).absorbed {
+            return PreciseTrajectory {
+                collisions,
+                termination: Termination::Absorbed,
+            };
+        }
+    }
+
+    PreciseTrajectory {
+        collisions,
+        termination: Termination::MaxSteps,
+    }
+}
+
+/// Nearest hit `(component_index, segment_index, distance)` beyond
+/// `epsilon` along the ray from `state`.
+fn next_hit<T: Scalar>(
+    table: &BilliardTable,
+    state: &PreciseState<T>,
+    epsilon: T,
+) -> Option<(usize, usize, T)> {
+    let (p, d) = (state.position, state.direction);
+    let mut best: Option<(usize, usize, T)> = None;
+
+    for (component_index, component) in table.components().enumerate() {
+        for (segment_index, segment) in component.segments.iter().enumerate() {
+            let hit = match segment {
+                BoundarySegment::Line(line) => {
+                    // p + t d = a + u e, solved with cross products.
+                    let a = Vector::from_vec2(line.start);
+                    let e = Vector::from_vec2(line.end) - a;
+                    let denom = d.cross(e);
+                    if denom == T::zero() {
+                        continue;
+                    }
+                    let w = a - p;
+                    let t = w.cross(e) / denom;
+                    let u = w.cross(d) / denom;
+                    let on_segment = u >= T::zero() && u <= T::from_f64(1.0);
+                    (t > epsilon && on_segment).then_some(t)
+                }
+                BoundarySegment::CircularArc(arc) => {
+                    // |m + t d|² = r² with m = p - c and |d| = 1.
+                    let m = p - Vector::from_vec2(arc.center);
+                    let b = m.dot(d);
+                    let r = T::from_f64(arc.radius);
+                    let c = m.dot(m) - r * r;
+                    let discriminant = b * b - c;
+                    if discriminant < T::zero() {
+                        continue;
+                    }
+                    // Cancellation-free roots: the one near zero right after
+                    // a bounce on this circle comes out accurately tiny.
+                    let root = discriminant.sqrt();
+                    let q = if b < T::zero() { root - b } else { -(b + root) };
+                    if q == T::zero() {
+                        continue;
+                    }
+                    let (t1, t2) = (q, c / q);
+                    let (near, far) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+                    [near, far].into_iter().find(|&t| {
+                        t > epsilon && arc_local_t(arc, (p + d.scale(t)).to_vec2()).is_some()
+                    })
+                }
+                BoundarySegment::External(_) => unreachable!("external segments are rejected"),
+            };
+
+            if let Some(t) = hit
+                && best.is_none_or(|(_, _, best_t)| t < best_t)
+            {
+                best = Some((component_index, segment_index, t));
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PreciseState, simulate_precise};
+    use crate::dynamics::simulation::{BilliardMap, StepOptions, Termination};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::scalar::{DoubleDouble, Scalar, Vector};
+    use crate::geometry::standard_tables::{sinai, stadium};
+
+    #[test]
+    fn f64_instance_matches_the_ordinary_simulation() {
+        for table in [sinai(1.0, 0.2), stadium(1.0, 0.5)] {
+            let table = table.to_billiard_table();
+            let initial = BoundaryState::new(0, 0.3, 1.0);
+            // Short enough that the two roundoff histories stay close.
+            let expected = BilliardMap::new(&table, 1e-8, StepOptions::default())
+                .simulate(&initial, 10)
+                .unwrap();
+
+            let start = PreciseState::<f64>::from_boundary(&table, &initial);
+            let precise = simulate_precise(&table, &start, 10, 1e-8);
+            assert_eq!(precise.termination, Termination::MaxSteps);
+
+            for (p, c) in precise.collisions.iter().zip(&expected.collisions) {
+                let rounded = p.to_collision_result(&table);
+                assert_eq!(rounded.component_index, c.component_index);
+                assert_eq!(rounded.segment_index, c.segment_index);
+                assert!((rounded.hit_point - c.hit_point).length() < 1e-9);
+                assert!((rounded.s - c.s).abs() < 1e-9);
+                assert!((rounded.theta - c.theta).abs() < 1e-9);
+            }
+        }
+    }
+
+    /// Run `n` bounces, turn around, run `n` back, and return how far from
+    /// the launch point (on the boundary) the particle ends up.
+    fn retrace_error<T: Scalar>(n: usize, epsilon: f64) -> f64 {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let start = PreciseState::<T>::from_boundary(&table, &BoundaryState::new(0, 0.3, 1.0));
+
+        // Leave the last hit point the way the particle came in.
+        let forward = simulate_precise(&table, &start, n, T::from_f64(epsilon));
+        let (last, before) = (forward.collisions[n - 1], forward.collisions[n - 2]);
+        let incoming = before.outgoing.direction;
+        let reversed = PreciseState {
+            position: last.outgoing.position,
+            direction: Vector::new(-incoming.x, -incoming.y),
+        };
+        let back = simulate_precise(&table, &reversed, n, T::from_f64(epsilon));
+        let end = back.collisions.last().unwrap().outgoing.position;
+        (end - start.position).length().to_f64()
+    }
+
+    #[test]
+    fn double_double_retraces_chaotic_orbits_that_f64_cannot() {
+        let n = 40;
+        let f64_error = retrace_error::<f64>(n, 1e-10);
+        let dd_error = retrace_error::<DoubleDouble>(n, 1e-20);
+        // Roundoff grows by roughly e per bounce here: 40 bounces each way
+        // leave f64 with a few digits and double-double with twenty.
+        assert!(dd_error < 1e-15, "double-double error {}", dd_error);
+        assert!(f64_error > 1e-6, "f64 error {}", f64_error);
+    }
+
+    #[test]
+    fn f32_follows_f64_for_a_few_bounces() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let wide = simulate_precise(
+            &table,
+            &PreciseState::<f64>::from_boundary(&table, &initial),
+            5,
+            1e-8,
+        );
+        let narrow = simulate_precise(
+            &table,
+            &PreciseState::<f32>::from_boundary(&table, &initial),
+            5,
+            1e-5,
+        );
+        assert_eq!(narrow.collisions.len(), 5);
+        for (a, b) in wide.collisions.iter().zip(&narrow.collisions) {
+            assert_eq!(
+                (a.component_index, a.segment_index),
+                (b.component_index, b.segment_index)
+            );
+            let gap = a.outgoing.position - Vector::from_vec2(b.outgoing.position.to_vec2());
+            assert!(gap.length() < 1e-4, "f32 drifted by {}", gap.length());
+        }
+        assert!(size_of_val(&narrow.collisions[0]) < size_of_val(&wide.collisions[0]));
+    }
+}
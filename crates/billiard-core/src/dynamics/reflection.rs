@@ -0,0 +1,433 @@
+//! Laws deciding how a particle leaves the wall it hits.
+//!
+//! The intersection machinery finds where the particle hits the boundary;
+//! a [`ReflectionLaw`] then picks the outgoing velocity (and, for sticky
+//! walls, how long the particle rests there). [`Specular`] is the law used
+//! everywhere by default. The stochastic laws carry their own seeded RNG,
+//! so a trajectory is reproducible for a given seed; [`Thermal`] draws
+//! fresh velocities only at the heated walls of a table.
+
+use rand::{RngExt, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::BoundaryCondition;
+
+/// An exponential variate of mean 1, `-ln(1 - u)` for `u` uniform in
+/// `[0, 1)`; `1 - u` lies in `(0, 1]`, so the logarithm is finite.
+pub(crate) fn exponential(rng: &mut ChaCha8Rng) -> f64 {
+    -(1.0 - rng.random_range(0.0_f64..1.0)).ln()
+}
+
+/// What a reflection law knows about a bounce.
+#[derive(Clone, Copy, Debug)]
+pub struct Impact {
+    /// Unit incoming direction of travel.
+    pub direction: Vec2,
+
+    /// Incoming speed; the outgoing [`Reflection::velocity`] is in units
+    /// of it.
+    pub speed: f64,
+
+    /// Unit inward normal at the hit point (the corner bisector under
+    /// [`CornerPolicy::ReflectBisector`](crate::dynamics::simulation::CornerPolicy)).
+    pub normal: Vec2,
+
+    /// Boundary condition of the segment that was hit.
+    pub condition: BoundaryCondition,
+
+    /// Coefficient of restitution from the step options.
+    pub restitution: f64,
+
+    /// Time of the hit on the clock of moving walls; see
+    /// [`BilliardMap::starting_at`](crate::dynamics::simulation::BilliardMap::starting_at).
+    pub time: f64,
+
+    pub component_index: usize,
+    pub segment_index: usize,
+}
+
+impl Impact {
+    /// Normal and tangential parts of the incoming direction.
+    pub fn split(&self) -> (Vec2, Vec2) {
+        let normal = self.normal * self.direction.dot(self.normal);
+        (normal, self.direction - normal)
+    }
+}
+
+/// How the particle leaves the wall.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reflection {
+    /// Outgoing velocity in units of the incoming speed: its length is the
+    /// ratio of outgoing to incoming speed.
+    pub velocity: Vec2,
+
+    /// Time the particle rests at the hit point before leaving.
+    pub delay: f64,
+}
+
+impl Reflection {
+    /// Leave immediately with `velocity`.
+    pub fn immediate(velocity: Vec2) -> Self {
+        Reflection {
+            velocity,
+            delay: 0.0,
+        }
+    }
+}
+
+/// A rule turning an [`Impact`] into a [`Reflection`].
+///
+/// Implemented by the laws in this module and by any
+/// `FnMut(&Impact) -> Reflection` closure.
+pub trait ReflectionLaw {
+    fn reflect(&mut self, impact: &Impact) -> Reflection;
+
+    /// Whether this is the mirror law of [`Specular`], which the
+    /// [invariant checks](crate::dynamics::invariants) then hold bounces
+    /// to.
+    fn is_specular(&self) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(&Impact) -> Reflection> ReflectionLaw for F {
+    fn reflect(&mut self, impact: &Impact) -> Reflection {
+        self(impact)
+    }
+}
+
+/// Mirror reflection, with the normal part damped by the restitution
+/// coefficient. Honours [`BoundaryCondition::NoSlipRotate`] and
+/// [`BoundaryCondition::Oscillating`] segments.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Specular;
+
+impl ReflectionLaw for Specular {
+    fn reflect(&mut self, impact: &Impact) -> Reflection {
+        match impact.condition {
+            BoundaryCondition::NoSlipRotate => NoSlip.reflect(impact),
+            BoundaryCondition::Oscillating { .. } => moving_wall(impact),
+            _ => {
+                let (normal, tangential) = impact.split();
+                Reflection::immediate(tangential - normal * impact.restitution)
+            }
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+}
+
+/// Mirror reflection in the frame of a wall moving along its normal: the
+/// normal velocity relative to the wall reverses, damped by the
+/// restitution coefficient.
+///
+/// A wall receding faster than the particle could not be hit at all; as
+/// in the simplified Fermi–Ulam model, the particle then leaves with the
+/// normal speed it would have had, turned back into the table.
+fn moving_wall(impact: &Impact) -> Reflection {
+    let (_, tangential) = impact.split();
+    // In units of the incoming speed, along the inward normal.
+    let wall = impact.condition.wall_velocity(impact.time) / impact.speed;
+    let incoming = impact.direction.dot(impact.normal);
+    let outgoing = (1.0 + impact.restitution) * wall - impact.restitution * incoming;
+    Reflection::immediate(tangential + impact.normal * outgoing.abs())
+}
+
+/// No-slip reflection on every segment: both velocity components reverse
+/// (the normal one damped by the restitution coefficient).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoSlip;
+
+impl ReflectionLaw for NoSlip {
+    fn reflect(&mut self, impact: &Impact) -> Reflection {
+        let (normal, tangential) = impact.split();
+        Reflection::immediate(-tangential - normal * impact.restitution)
+    }
+}
+
+/// Diffuse (Lambertian) reflection: the outgoing direction forgets the
+/// incoming one, with the sine of its angle to the normal uniform in
+/// `(-1, 1)`. This is the cosine law, which preserves the invariant
+/// measure of the billiard map. Speed is kept.
+#[derive(Clone, Debug)]
+pub struct Lambertian {
+    rng: ChaCha8Rng,
+}
+
+impl Lambertian {
+    pub fn new(seed: u64) -> Self {
+        Lambertian {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl ReflectionLaw for Lambertian {
+    fn reflect(&mut self, impact: &Impact) -> Reflection {
+        let sin = self.rng.random_range(-1.0_f64..1.0);
+        let cos = (1.0 - sin * sin).sqrt();
+        let tangent = Vec2::new(-impact.normal.y, impact.normal.x);
+        Reflection::immediate(impact.normal * cos + tangent * sin)
+    }
+}
+
+/// Specular reflection after resting at the wall for an exponentially
+/// distributed time with mean `mean_delay`.
+#[derive(Clone, Debug)]
+pub struct Sticky {
+    mean_delay: f64,
+    rng: ChaCha8Rng,
+}
+
+impl Sticky {
+    /// # Panics
+    /// Panics if `mean_delay` is negative or not finite.
+    pub fn new(mean_delay: f64, seed: u64) -> Self {
+        assert!(
+            mean_delay.is_finite() && mean_delay >= 0.0,
+            "Mean delay must be non-negative and finite."
+        );
+        Sticky {
+            mean_delay,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl ReflectionLaw for Sticky {
+    fn reflect(&mut self, impact: &Impact) -> Reflection {
+        Reflection {
+            delay: self.mean_delay * exponential(&mut self.rng),
+            ..Specular.reflect(impact)
+        }
+    }
+}
+
+/// Heated walls: at [`BoundaryCondition::Thermal`] segments the particle
+/// leaves with a velocity drawn from the wall's Maxwellian, whatever it
+/// came in with; every other segment reflects as under [`Specular`].
+///
+/// The outgoing velocity has a Gaussian tangential part of variance `T`
+/// and a Rayleigh-distributed normal part, `v_n ∝ v_n e^{-v_n² / 2T}`:
+/// the flux of particles leaving a gas at temperature `T` through the
+/// wall. Restitution does not apply at heated walls.
+#[derive(Clone, Debug)]
+pub struct Thermal {
+    rng: ChaCha8Rng,
+}
+
+impl Thermal {
+    pub fn new(seed: u64) -> Self {
+        Thermal {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl ReflectionLaw for Thermal {
+    fn reflect(&mut self, impact: &Impact) -> Reflection {
+        let BoundaryCondition::Thermal { temperature } = impact.condition else {
+            return Specular.reflect(impact);
+        };
+        let scale = temperature.sqrt();
+        let normal_speed = scale * (2.0 * exponential(&mut self.rng)).sqrt();
+        // Box–Muller.
+        let angle = self.rng.random_range(0.0..std::f64::consts::TAU);
+        let tangential_speed = scale * (2.0 * exponential(&mut self.rng)).sqrt() * angle.cos();
+
+        let tangent = Vec2::new(-impact.normal.y, impact.normal.x);
+        let velocity = impact.normal * normal_speed + tangent * tangential_speed;
+        Reflection::immediate(velocity / impact.speed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Impact, Lambertian, NoSlip, Reflection, ReflectionLaw, Specular, Sticky, Thermal};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::BoundaryCondition;
+    use proptest::prelude::*;
+    use std::f64::consts::{PI, TAU};
+
+    fn impact() -> Impact {
+        Impact {
+            direction: Vec2::new(0.6, -0.8),
+            speed: 1.0,
+            normal: Vec2::new(0.0, 1.0),
+            condition: BoundaryCondition::Reflect,
+            restitution: 1.0,
+            time: 0.0,
+            component_index: 0,
+            segment_index: 0,
+        }
+    }
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        (a - b).length() < 1e-12
+    }
+
+    #[test]
+    fn deterministic_laws() {
+        let hit = impact();
+        assert!(close(Specular.reflect(&hit).velocity, Vec2::new(0.6, 0.8)));
+        assert!(close(NoSlip.reflect(&hit).velocity, Vec2::new(-0.6, 0.8)));
+
+        let damped = Impact {
+            restitution: 0.5,
+            ..hit
+        };
+        assert!(close(
+            Specular.reflect(&damped).velocity,
+            Vec2::new(0.6, 0.4)
+        ));
+
+        let mut reverse = |impact: &Impact| Reflection::immediate(-impact.direction);
+        assert!(close(reverse.reflect(&hit).velocity, Vec2::new(-0.6, 0.8)));
+    }
+
+    #[test]
+    fn moving_walls_kick_the_normal_velocity() {
+        let moving = |velocity, time| Impact {
+            speed: 2.0,
+            condition: BoundaryCondition::Oscillating {
+                velocity,
+                frequency: PI,
+                phase: 0.0,
+            },
+            time,
+            ..impact()
+        };
+        // Advancing at 0.2 (speed 0.1 in units of the incoming 2), the wall
+        // returns 0.8 + 2 · 0.1 of normal velocity.
+        let out = Specular.reflect(&moving(0.2, 0.0)).velocity;
+        assert!(close(out, Vec2::new(0.6, 1.0)));
+        // Half a period later it recedes.
+        let out = Specular.reflect(&moving(0.2, 1.0)).velocity;
+        assert!(close(out, Vec2::new(0.6, 0.6)));
+        // Too fast to have been caught up with: turned back into the table.
+        let out = Specular.reflect(&moving(2.0, 1.0)).velocity;
+        assert!(close(out, Vec2::new(0.6, 1.2)));
+        // A wall at rest is a mirror.
+        let out = Specular.reflect(&moving(0.0, 0.3)).velocity;
+        assert!(close(out, Specular.reflect(&impact()).velocity));
+    }
+
+    #[test]
+    fn stochastic_laws_are_reproducible_and_leave_inward() {
+        let hit = impact();
+        let draws = |seed| {
+            let mut law = Lambertian::new(seed);
+            (0..100)
+                .map(|_| law.reflect(&hit).velocity)
+                .collect::<Vec<_>>()
+        };
+        let velocities = draws(3);
+        assert_eq!(velocities, draws(3));
+        for v in &velocities {
+            assert!((v.length() - 1.0).abs() < 1e-12);
+            assert!(v.y >= 0.0);
+        }
+        let mean_sin = velocities.iter().map(|v| v.x).sum::<f64>() / 100.0;
+        assert!(mean_sin.abs() < 0.2);
+
+        let mut sticky = Sticky::new(2.0, 5);
+        let delays: Vec<f64> = (0..2000).map(|_| sticky.reflect(&hit).delay).collect();
+        assert!(delays.iter().all(|&d| d >= 0.0 && d.is_finite()));
+        let mean = delays.iter().sum::<f64>() / delays.len() as f64;
+        assert!((mean - 2.0).abs() < 0.2, "mean delay {}", mean);
+    }
+
+    #[test]
+    fn thermal_walls_draw_from_the_wall_maxwellian() {
+        let temperature = 2.0;
+        let heated = Impact {
+            speed: 3.0,
+            condition: BoundaryCondition::Thermal { temperature },
+            ..impact()
+        };
+        let mut law = Thermal::new(11);
+        let n = 20_000;
+        let (mut normal_sq, mut tangential_sq) = (0.0, 0.0);
+        for _ in 0..n {
+            // In units of the incoming speed.
+            let v = law.reflect(&heated).velocity * heated.speed;
+            assert!(v.y > 0.0);
+            normal_sq += v.y * v.y;
+            tangential_sq += v.x * v.x;
+        }
+        // ⟨v_t²⟩ = T and ⟨v_n²⟩ = 2T for the flux leaving the wall.
+        let (normal_sq, tangential_sq) = (normal_sq / n as f64, tangential_sq / n as f64);
+        assert!((tangential_sq - temperature).abs() < 0.05 * temperature);
+        assert!((normal_sq - 2.0 * temperature).abs() < 0.05 * 2.0 * temperature);
+
+        // Other walls are mirrors.
+        let hit = impact();
+        assert_eq!(law.reflect(&hit), Specular.reflect(&hit));
+    }
+
+    /// An incoming unit direction heading into the wall whose unit inward
+    /// normal is at angle `normal_angle`, at angle `incidence` (in
+    /// `(0, π)`) to the wall.
+    fn oblique_impact(normal_angle: f64, incidence: f64, condition: BoundaryCondition) -> Impact {
+        let normal = Vec2::new(normal_angle.cos(), normal_angle.sin());
+        let tangent = -normal.perp();
+        Impact {
+            direction: tangent * incidence.cos() - normal * incidence.sin(),
+            speed: 1.0,
+            normal,
+            condition,
+            restitution: 1.0,
+            time: 0.0,
+            component_index: 0,
+            segment_index: 0,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn elastic_reflection_preserves_speed_and_sin_theta(
+            normal_angle in 0.0..TAU,
+            incidence in 1e-6..PI - 1e-6,
+            no_slip in any::<bool>(),
+        ) {
+            let condition = if no_slip {
+                BoundaryCondition::NoSlipRotate
+            } else {
+                BoundaryCondition::Reflect
+            };
+            let hit = oblique_impact(normal_angle, incidence, condition);
+            let out = Specular.reflect(&hit).velocity;
+
+            prop_assert!((out.length() - 1.0).abs() < 1e-12);
+            // The angle to the wall keeps its sine; the particle now
+            // leaves into the domain.
+            let sin_in = -hit.direction.dot(hit.normal);
+            let sin_out = out.dot(hit.normal);
+            prop_assert!((sin_out - sin_in).abs() < 1e-12);
+            prop_assert!(sin_out > 0.0);
+        }
+
+        #[test]
+        fn restitution_scales_only_the_normal_speed(
+            normal_angle in 0.0..TAU,
+            incidence in 1e-6..PI - 1e-6,
+            restitution in 0.01..1.0f64,
+        ) {
+            let hit = Impact {
+                restitution,
+                ..oblique_impact(normal_angle, incidence, BoundaryCondition::Reflect)
+            };
+            let (normal_in, tangential_in) = hit.split();
+            let out = Specular.reflect(&hit).velocity;
+            let tangential_out = out - hit.normal * out.dot(hit.normal);
+
+            prop_assert!((tangential_out - tangential_in).length() < 1e-12);
+            prop_assert!(
+                (out.dot(hit.normal) - restitution * normal_in.length()).abs() < 1e-12
+            );
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Rotation numbers of orbits on convex tables.
+//!
+//! On a convex table every bounce advances the particle some fraction of the
+//! way around the boundary. The rotation number is the average of that
+//! advance over the orbit. Orbits on an invariant curve (a caustic or KAM
+//! torus) have a well-defined rotation number, and the running average
+//! settles quickly; chaotic orbits keep drifting.
+
+use crate::dynamics::config::SimulationConfig;
+use crate::dynamics::simulation::{BilliardMap, CollisionResult, StepOptions, run_trajectory};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+
+/// Rotation number of an orbit together with convergence diagnostics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RotationNumber {
+    /// Mean advance per bounce, in units of the boundary length, in `[0, 1)`.
+    pub value: f64,
+
+    /// Number of bounces averaged over.
+    pub bounces: usize,
+
+    /// Running estimate after each bounce; the last entry is `value`.
+    pub estimates: Vec<f64>,
+
+    /// Largest minus smallest running estimate over the second half of the
+    /// orbit. Small values indicate convergence.
+    pub spread: f64,
+}
+
+impl RotationNumber {
+    /// Whether the running estimate stayed within `tolerance` over the
+    /// second half of the orbit.
+    pub fn converged(&self, tolerance: f64) -> bool {
+        self.spread <= tolerance
+    }
+}
+
+/// Compute the rotation number of the orbit starting at `initial` on the
+/// outer boundary, over `bounces` collisions.
+///
+/// Advances are measured in normalized arc length and taken in `[0, 1)`, so
+/// an orbit running clockwise has a rotation number close to 1.
+///
+/// Returns `None` if `initial` is not on the outer boundary, if the
+/// simulation fails, or if the orbit hits an obstacle or ends before its
+/// first bounce. An orbit that
+/// ends early is averaged over the bounces it made.
+pub fn rotation_number(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    bounces: usize,
+    epsilon: f64,
+    options: &StepOptions,
+) -> Option<RotationNumber> {
+    if initial.component_index != 0 {
+        return None;
+    }
+
+    let length = table.outer.length();
+    let collisions = BilliardMap::new(table, epsilon, *options)
+        .simulate(initial, bounces)
+        .map(|trajectory| trajectory.collisions)
+        .ok()?;
+    if collisions.is_empty() || collisions.iter().any(|c| c.component_index != 0) {
+        return None;
+    }
+
+    let mut s = initial.s;
+    let mut total = 0.0;
+    let estimates: Vec<f64> = collisions
+        .iter()
+        .enumerate()
+        .map(|(k, c)| {
+            total += ((c.s - s) / length).rem_euclid(1.0);
+            s = c.s;
+            total / (k + 1) as f64
+        })
+        .collect();
+
+    let tail = &estimates[estimates.len() / 2..];
+    let (lo, hi) = tail
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &e| {
+            (lo.min(e), hi.max(e))
+        });
+
+    Some(RotationNumber {
+        value: *estimates.last().expect("at least one bounce"),
+        bounces: estimates.len(),
+        spread: hi - lo,
+        estimates,
+    })
+}
+
+/// Rotation number of the orbit from `initial` around the outer wall,
+/// counting only the bounces on it, over the collisions `config` allows.
+///
+/// Unlike [`rotation_number`], bounces on obstacles are stepped over
+/// rather than fatal, so orbits of the annulus that reach the inner disk
+/// have one too; the advance from one outer bounce to the next is taken
+/// in `[-1/2, 1/2)` turns before averaging, and the value reduced to
+/// `[0, 1)`.
+///
+/// Returns `None` if `initial` is not on the outer wall, if the simulation
+/// fails, or if the orbit never comes back to the outer wall.
+///
+pub fn outer_rotation_number(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    config: &SimulationConfig,
+) -> Option<RotationNumber> {
+    if initial.component_index != 0 {
+        return None;
+    }
+    let collisions = run_trajectory(table, initial, config).ok()?;
+    outer_rotation_of(table, initial, &collisions)
+}
+
+/// [`outer_rotation_number`] of the orbit from `initial`, already simulated
+/// as `collisions`.
+pub(crate) fn outer_rotation_of(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    collisions: &[CollisionResult],
+) -> Option<RotationNumber> {
+    let length = table.outer.length();
+    let mut s = initial.s;
+    let mut total = 0.0;
+    let estimates: Vec<f64> = collisions
+        .iter()
+        .filter(|c| c.component_index == 0)
+        .enumerate()
+        .map(|(k, c)| {
+            total += ((c.s - s) / length + 0.5).rem_euclid(1.0) - 0.5;
+            s = c.s;
+            total / (k + 1) as f64
+        })
+        .collect();
+    let last = *estimates.last()?;
+
+    let tail = &estimates[estimates.len() / 2..];
+    let (lo, hi) = tail
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &e| {
+            (lo.min(e), hi.max(e))
+        });
+
+    Some(RotationNumber {
+        value: last.rem_euclid(1.0),
+        bounces: estimates.len(),
+        spread: hi - lo,
+        estimates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rotation_number;
+    use crate::dynamics::simulation::StepOptions;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundarySegment, CircularArcSegment};
+    use crate::geometry::standard_tables::sinai;
+    use std::f64::consts::{PI, TAU};
+
+    fn unit_disk() -> BilliardTable {
+        let circle = CircularArcSegment::new(Vec2::new(0.0, 0.0), 1.0, 0.0, TAU, true);
+        BilliardTable::new(
+            BoundaryComponent::new("disk", vec![BoundarySegment::CircularArc(circle)]),
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn disk_rotation_number_is_theta_over_pi() {
+        let table = unit_disk();
+        let theta = 0.7;
+        let initial = BoundaryState::new(0, 0.0, theta);
+
+        let rho = rotation_number(&table, &initial, 400, 1e-8, &StepOptions::default())
+            .expect("orbit stays on the outer boundary");
+
+        assert_eq!(rho.bounces, 400);
+        assert!((rho.value - theta / PI).abs() < 1e-9);
+        assert!(rho.converged(1e-9));
+    }
+
+    #[test]
+    fn orbits_hitting_obstacles_have_no_rotation_number() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.5, std::f64::consts::FRAC_PI_2);
+
+        assert!(rotation_number(&table, &initial, 10, 1e-8, &StepOptions::default()).is_none());
+    }
+}
@@ -0,0 +1,313 @@
+//! Initial conditions drawn from the invariant measure of the billiard map.
+//!
+//! In the coordinates of [`BoundaryState`] (θ measured from the tangent)
+//! the invariant measure is `sin θ dθ ds = ds d(cos θ)`: uniform in
+//! arc length and in the tangential velocity `cos θ ∈ (-1, 1)`. (With the
+//! angle measured from the normal, as in most texts, this is the familiar
+//! `ds d(sin φ)`.) All components are sampled together, each in proportion
+//! to its length.
+//!
+//! Points in holes and on periodic segments are never returned: the
+//! particle passes through those rather than bouncing off them. Every
+//! sampler is deterministic for a given seed.
+
+use std::fmt;
+
+use rand::{RngExt, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::segments::BoundaryCondition;
+
+/// Draws allowed when looking for a starting point outside every hole.
+const MAX_SAMPLING_ATTEMPTS: usize = 10_000;
+
+/// How initial conditions are spread over phase space.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum Sampling {
+    /// `count` independent draws.
+    Random { count: usize },
+
+    /// One random draw in each cell of a grid with `s_cells` columns in
+    /// (total) arc length and `p_cells` rows in `cos θ`. Cells lying
+    /// entirely in a hole or on a periodic segment are skipped.
+    Stratified { s_cells: usize, p_cells: usize },
+
+    /// The centers of the same grid, with no randomness at all. Centers in
+    /// a hole or on a periodic segment are skipped.
+    Grid { s_cells: usize, p_cells: usize },
+}
+
+/// Why no initial conditions could be drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SamplingError {
+    /// (Almost) every point of the boundary is absorbing or periodic, so
+    /// there is nowhere to start a bounce from.
+    NoReflectingBoundary,
+}
+
+impl fmt::Display for SamplingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SamplingError::NoReflectingBoundary => write!(
+                f,
+                "could not find a reflecting boundary point to start from"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SamplingError {}
+
+/// Component and arc length of the point at distance `u` along the
+/// boundary, with the components laid end to end in order.
+pub(crate) fn boundary_point(table: &BilliardTable, mut u: f64) -> (usize, f64) {
+    let mut component_index = 0;
+    for (i, component) in table.components().enumerate() {
+        component_index = i;
+        if u < component.length() {
+            break;
+        }
+        u -= component.length();
+    }
+    (component_index, u)
+}
+
+/// Whether a particle can bounce off the boundary at `s`.
+pub(crate) fn reflects_at(table: &BilliardTable, component_index: usize, s: f64) -> bool {
+    let component = table.component(component_index);
+    let (segment_index, _) = component.locate(s);
+    let periodic = matches!(
+        component.segments[segment_index].condition(),
+        BoundaryCondition::PeriodicPairedWith(_)
+    );
+    !periodic && !component.is_absorbing(s)
+}
+
+/// Draw a single boundary state from the invariant measure.
+///
+/// # Panics
+/// Panics if (almost) every point of the boundary is absorbing or periodic;
+/// see [`try_random_boundary_state`].
+pub fn random_boundary_state(table: &BilliardTable, rng: &mut ChaCha8Rng) -> BoundaryState {
+    try_random_boundary_state(table, rng).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Draw a single boundary state from the invariant measure, failing if
+/// (almost) every point of the boundary is absorbing or periodic.
+pub fn try_random_boundary_state(
+    table: &BilliardTable,
+    rng: &mut ChaCha8Rng,
+) -> Result<BoundaryState, SamplingError> {
+    let total = table.perimeter();
+
+    for _ in 0..MAX_SAMPLING_ATTEMPTS {
+        let (component_index, s) = boundary_point(table, rng.random_range(0.0..total));
+        if !reflects_at(table, component_index, s) {
+            continue;
+        }
+
+        let theta = rng.random_range(-1.0_f64..1.0).acos();
+        return Ok(BoundaryState::new(component_index, s, theta));
+    }
+
+    Err(SamplingError::NoReflectingBoundary)
+}
+
+/// Sample initial boundary states according to `sampling`.
+///
+/// Grid-based schemes list their points column by column (increasing arc
+/// length, then increasing `cos θ`).
+///
+/// # Panics
+/// Panics under [`Sampling::Random`] if (almost) every point of the
+/// boundary is absorbing or periodic; see [`try_sample_boundary_states`].
+pub fn sample_boundary_states(
+    table: &BilliardTable,
+    sampling: &Sampling,
+    seed: u64,
+) -> Vec<BoundaryState> {
+    try_sample_boundary_states(table, sampling, seed).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Sample initial boundary states according to `sampling`, as
+/// [`sample_boundary_states`] does, failing under [`Sampling::Random`] if
+/// (almost) every point of the boundary is absorbing or periodic.
+pub fn try_sample_boundary_states(
+    table: &BilliardTable,
+    sampling: &Sampling,
+    seed: u64,
+) -> Result<Vec<BoundaryState>, SamplingError> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let (s_cells, p_cells, jitter) = match *sampling {
+        Sampling::Random { count } => {
+            return (0..count)
+                .map(|_| try_random_boundary_state(table, &mut rng))
+                .collect();
+        }
+        Sampling::Stratified { s_cells, p_cells } => (s_cells, p_cells, true),
+        Sampling::Grid { s_cells, p_cells } => (s_cells, p_cells, false),
+    };
+
+    let ds = table.perimeter() / s_cells as f64;
+    let dp = 2.0 / p_cells as f64;
+    let mut states = Vec::with_capacity(s_cells * p_cells);
+
+    for i in 0..s_cells {
+        for j in 0..p_cells {
+            let cell = |a: f64, b: f64| {
+                let u = (i as f64 + a) * ds;
+                let p = -1.0 + (j as f64 + b) * dp;
+                (boundary_point(table, u), p)
+            };
+
+            let point = if jitter {
+                (0..MAX_SAMPLING_ATTEMPTS)
+                    .map(|_| cell(rng.random_range(0.0..1.0), rng.random_range(0.0..1.0)))
+                    .find(|&((c, s), _)| reflects_at(table, c, s))
+            } else {
+                Some(cell(0.5, 0.5)).filter(|&((c, s), _)| reflects_at(table, c, s))
+            };
+
+            if let Some(((component_index, s), p)) = point {
+                states.push(BoundaryState::new(component_index, s, p.acos()));
+            }
+        }
+    }
+    Ok(states)
+}
+
+/// States leaving the outer wall of `table` from the cell midpoints of an
+/// `s_count × p_count` grid over `[0, L) × (-1, 1)` in `s` and
+/// `p = cos θ`, at unit speed, row by row in `s`.
+///
+/// Unlike [`Sampling::Grid`], which covers every component and skips the
+/// centers nothing bounces from, every cell gives a state: the one from
+/// cell `(i, j)` is at `i * p_count + j`.
+pub(crate) fn outer_wall_grid(
+    table: &BilliardTable,
+    s_count: usize,
+    p_count: usize,
+) -> Vec<BoundaryState> {
+    let length = table.outer.length();
+    let midpoint = |i: usize, n: usize| (i as f64 + 0.5) / n as f64;
+    (0..s_count)
+        .flat_map(|i| {
+            (0..p_count).map(move |j| {
+                let p = 2.0 * midpoint(j, p_count) - 1.0;
+                BoundaryState::new(0, length * midpoint(i, s_count), p.acos())
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Sampling, SamplingError, sample_boundary_states, try_random_boundary_state};
+    use crate::geometry::boundary::Hole;
+    use crate::geometry::standard_tables::{lorentz_gas, regular_polygon, sinai};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn random_samples_are_reproducible_and_cover_every_component() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let sampling = Sampling::Random { count: 2000 };
+        let a = sample_boundary_states(&table, &sampling, 7);
+        let b = sample_boundary_states(&table, &sampling, 7);
+        assert_eq!(a.len(), 2000);
+        assert!(
+            a.iter()
+                .zip(&b)
+                .all(|(x, y)| x.s == y.s && x.theta == y.theta)
+        );
+
+        // Components in proportion to length: the disk is 2π·0.2 of 4 + 2π·0.2.
+        let on_disk = a.iter().filter(|bs| bs.component_index == 1).count() as f64;
+        let expected = 0.4 * std::f64::consts::PI / (4.0 + 0.4 * std::f64::consts::PI);
+        assert!((on_disk / 2000.0 - expected).abs() < 0.03);
+
+        // cos θ is uniform, so its mean is near zero.
+        let mean_p: f64 = a.iter().map(|bs| bs.theta.cos()).sum::<f64>() / 2000.0;
+        assert!(mean_p.abs() < 0.05);
+    }
+
+    #[test]
+    fn grid_places_one_point_at_each_cell_center() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let states = sample_boundary_states(
+            &table,
+            &Sampling::Grid {
+                s_cells: 4,
+                p_cells: 2,
+            },
+            0,
+        );
+        assert_eq!(states.len(), 8);
+
+        let total = 4.0 + 0.4 * std::f64::consts::PI;
+        assert_eq!(states[0].component_index, 0);
+        assert!((states[0].s - total / 8.0).abs() < 1e-12);
+        assert!((states[0].theta.cos() + 0.5).abs() < 1e-12);
+        assert!((states[1].theta.cos() - 0.5).abs() < 1e-12);
+        // The last column falls on the disk.
+        assert_eq!(states[7].component_index, 1);
+    }
+
+    #[test]
+    fn stratified_samples_stay_in_their_cells_and_avoid_holes() {
+        let mut table = sinai(1.0, 0.2).to_billiard_table();
+        // The whole bottom wall is a hole.
+        table.outer.holes.push(Hole {
+            start: 0.0,
+            end: 1.0,
+        });
+        let (s_cells, p_cells) = (10, 5);
+        let states = sample_boundary_states(&table, &Sampling::Stratified { s_cells, p_cells }, 3);
+
+        // Columns are about 0.53 wide: only the first lies inside [0, 1].
+        assert_eq!(states.len(), (s_cells - 1) * p_cells);
+        for (k, bs) in states.iter().enumerate() {
+            assert!(bs.component_index == 1 || bs.s > 1.0);
+            let row = k % p_cells;
+            let p = bs.theta.cos();
+            assert!(p >= -1.0 + row as f64 * 0.4 - 1e-12 && p <= -1.0 + (row + 1) as f64 * 0.4);
+        }
+    }
+
+    #[test]
+    fn periodic_walls_are_never_sampled() {
+        let table = lorentz_gas(1.0, 0.3).to_billiard_table();
+        for sampling in [
+            Sampling::Random { count: 100 },
+            Sampling::Grid {
+                s_cells: 20,
+                p_cells: 3,
+            },
+        ] {
+            let states = sample_boundary_states(&table, &sampling, 1);
+            assert!(!states.is_empty());
+            assert!(states.iter().all(|bs| bs.component_index == 1));
+        }
+    }
+
+    #[test]
+    fn a_boundary_with_nowhere_to_bounce_cannot_be_sampled() {
+        let mut table = regular_polygon(4, 1.0).to_billiard_table();
+        let length = table.outer.length();
+        table.outer.holes.push(Hole {
+            start: 0.0,
+            end: length,
+        });
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert_eq!(
+            try_random_boundary_state(&table, &mut rng),
+            Err(SamplingError::NoReflectingBoundary)
+        );
+    }
+}
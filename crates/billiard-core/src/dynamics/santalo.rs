@@ -0,0 +1,111 @@
+//! Santaló's formula as a check on measured free paths.
+//!
+//! Averaged over the invariant measure of the billiard map, the free path
+//! between collisions is `π |Q| / |∂Q|`, where `|Q|` is the area of the
+//! table and `|∂Q|` the length of its boundary, whatever the shape. An
+//! ensemble drawn from that measure (see
+//! [`ensemble_statistics`](crate::dynamics::ensemble::ensemble_statistics)),
+//! or a long trajectory on an ergodic table, should reproduce it; a large
+//! discrepancy points to a table that is not what was intended (a gap in
+//! the boundary, an obstacle outside the outer wall) or to orbits stuck on
+//! a small invariant set.
+//!
+//! Periodic segments are not walls: flights through them count as one (as
+//! in [`ensemble`](crate::dynamics::ensemble)) and their length is left out
+//! of `|∂Q|`. The formula is for closed tables; holes are counted as wall.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::ensemble::FreePathStatistics;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::segments::BoundaryCondition;
+
+/// A measured mean free path next to Santaló's prediction.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MeanFreePathCheck {
+    pub measured: f64,
+
+    /// `π · area / perimeter`.
+    pub predicted: f64,
+
+    /// `|measured - predicted| / predicted`.
+    pub relative_error: f64,
+
+    /// Number of free flights the measurement averages over.
+    pub flights: usize,
+}
+
+/// Santaló's mean free path `π |Q| / |∂Q|` of `table`, with periodic
+/// segments left out of the perimeter.
+pub fn santalo_mean_free_path(table: &BilliardTable) -> f64 {
+    let walls: f64 = table
+        .components()
+        .flat_map(|component| &component.segments)
+        .filter(|segment| {
+            !matches!(
+                segment.condition(),
+                BoundaryCondition::PeriodicPairedWith(_)
+            )
+        })
+        .map(|segment| segment.length())
+        .sum();
+    PI * table.domain_area() / walls
+}
+
+/// Compare the mean of `free_paths`, measured on `table`, with
+/// [`santalo_mean_free_path`].
+pub fn check_mean_free_path(
+    table: &BilliardTable,
+    free_paths: &FreePathStatistics,
+) -> MeanFreePathCheck {
+    let predicted = santalo_mean_free_path(table);
+    MeanFreePathCheck {
+        measured: free_paths.mean,
+        predicted,
+        relative_error: (free_paths.mean - predicted).abs() / predicted,
+        flights: free_paths.flights,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_mean_free_path, santalo_mean_free_path};
+    use crate::dynamics::ensemble::{ensemble_statistics, free_path_histogram};
+    use crate::dynamics::simulation::{BilliardMap, StepOptions};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{lorentz_gas, rectangle, stadium};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn prediction_leaves_out_periodic_walls() {
+        let square = rectangle(2.0, 2.0).to_billiard_table();
+        assert!((santalo_mean_free_path(&square) - PI * 4.0 / 8.0).abs() < 1e-12);
+
+        let r = 0.4;
+        let cell = lorentz_gas(1.0, r).to_billiard_table();
+        let expected = PI * (1.0 - PI * r * r) / (2.0 * PI * r);
+        assert!((santalo_mean_free_path(&cell) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ensembles_and_chaotic_trajectories_agree_with_the_prediction() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let options = StepOptions::default();
+
+        let ensemble = ensemble_statistics(&table, 200, 100, 1e-8, &options, 2).unwrap();
+        let check = check_mean_free_path(&table, &ensemble.free_path);
+        assert_eq!(check.flights, 20_000);
+        assert!(check.relative_error < 0.03, "{:?}", check);
+
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let trajectory = BilliardMap::new(&table, 1e-8, options)
+            .simulate(&initial, 20_000)
+            .unwrap();
+        let histogram = free_path_histogram(&table, &trajectory.collisions, 10, None);
+        let check = check_mean_free_path(&table, &histogram.statistics);
+        assert!(check.relative_error < 0.05, "{:?}", check);
+    }
+}
@@ -0,0 +1,384 @@
+//! Scattering maps of open billiards.
+//!
+//! An open table has gaps in its boundary, called apertures, through which
+//! particles enter and leave. The scattering map sends an incoming condition
+//! (where on the entry aperture the particle crosses it, and at what angle)
+//! to the corresponding outgoing condition on whichever aperture it leaves
+//! through, together with the time spent inside and the number of bounces.
+
+use crate::dynamics::intersection::Parabola;
+use crate::dynamics::simulation::{BilliardMap, StepOptions};
+use crate::dynamics::state::WorldState;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::LineSegment;
+
+/// A straight opening in the boundary of an open table.
+///
+/// Like boundary segments, apertures are oriented so that the table
+/// interior lies to the left of `start → end`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aperture {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Aperture {
+    pub fn new(start: Vec2, end: Vec2) -> Self {
+        Self { start, end }
+    }
+
+    /// Point at relative offset `offset ∈ [0, 1]` from `start` to `end`.
+    pub fn point_at(&self, offset: f64) -> Vec2 {
+        self.start + (self.end - self.start) * offset
+    }
+
+    /// Unit tangent from `start` to `end`.
+    pub fn tangent(&self) -> Vec2 {
+        (self.end - self.start).normalized()
+    }
+
+    /// Unit normal pointing into the table.
+    pub fn inward_normal(&self) -> Vec2 {
+        self.tangent().perp()
+    }
+
+    /// Direction entering the table at `angle` from the inward normal,
+    /// positive angles tilting toward the tangent.
+    pub fn incoming_direction(&self, angle: f64) -> Vec2 {
+        self.inward_normal() * angle.cos() + self.tangent() * angle.sin()
+    }
+
+    /// Angle of an outgoing `direction` from the outward normal, positive
+    /// angles tilting toward the tangent.
+    pub fn outgoing_angle(&self, direction: Vec2) -> f64 {
+        let d = direction.normalized();
+        d.dot(self.tangent()).atan2(-d.dot(self.inward_normal()))
+    }
+
+    /// When `flight` crosses this aperture, as `(time, offset)`.
+    fn crossing(&self, flight: &Parabola, epsilon: f64) -> Option<(f64, f64)> {
+        let segment = LineSegment::new(self.start, self.end);
+        let (t, local_t) = flight.intersect_line_segment(&segment, epsilon)?;
+        Some((t, local_t / segment.length()))
+    }
+}
+
+/// A table whose boundary has gaps, together with the apertures spanning them.
+pub struct OpenTable {
+    pub table: BilliardTable,
+    pub apertures: Vec<Aperture>,
+}
+
+/// Uniform grid of incoming conditions on the entry aperture.
+///
+/// Offsets and angles are sampled at cell midpoints of `(0, 1)` and
+/// `(-π/2, π/2)` respectively, avoiding grazing incidence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScatteringGrid {
+    pub offsets: usize,
+    pub angles: usize,
+}
+
+/// How a scattered particle left the table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScatteringOutcome {
+    /// The particle crossed aperture `aperture` at `offset` and `angle`.
+    Exited {
+        aperture: usize,
+        offset: f64,
+        angle: f64,
+    },
+
+    /// The particle was still inside after the bounce limit.
+    Trapped,
+
+    /// The particle left without crossing an aperture, fell into a hole,
+    /// or stopped at a corner under [`CornerPolicy::Terminate`](crate::dynamics::simulation::CornerPolicy::Terminate).
+    Lost,
+}
+
+/// One point of the scattering map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScatteringSample {
+    /// Relative position on the entry aperture, in `(0, 1)`.
+    pub incoming_offset: f64,
+
+    /// Angle from the entry aperture's inward normal, in `(-π/2, π/2)`.
+    pub incoming_angle: f64,
+
+    pub outcome: ScatteringOutcome,
+
+    /// Number of boundary collisions before leaving.
+    pub bounces: usize,
+
+    /// Time spent inside the table. Particles enter at unit speed, so this
+    /// is the path length unless bounces are inelastic or gravity bends
+    /// the flights.
+    pub dwell_time: f64,
+}
+
+/// Follow one particle entering through aperture `entry` until it leaves
+/// through any aperture or `max_bounces` is exceeded.
+///
+/// # Panics
+/// Panics if `entry` is out of range.
+pub fn scatter(
+    open: &OpenTable,
+    entry: usize,
+    incoming_offset: f64,
+    incoming_angle: f64,
+    max_bounces: usize,
+    epsilon: f64,
+    options: &StepOptions,
+) -> ScatteringSample {
+    let OpenTable { table, apertures } = open;
+    let entrance = &apertures[entry];
+    let mut ws = WorldState::new(
+        entrance.point_at(incoming_offset),
+        entrance.incoming_direction(incoming_angle),
+    );
+    let mut bounces = 0;
+    let mut dwell_time = 0.0;
+
+    let sample = |outcome, bounces, dwell_time| ScatteringSample {
+        incoming_offset,
+        incoming_angle,
+        outcome,
+        bounces,
+        dwell_time,
+    };
+
+    loop {
+        // A step that breaks down numerically counts as lost.
+        let collision = BilliardMap::new(table, epsilon, *options)
+            .collision_from_world(&ws)
+            .ok()
+            .flatten();
+        let hit_time = collision.as_ref().map_or(f64::INFINITY, |c| c.time);
+
+        // Leave through the nearest aperture crossed before the next hit,
+        // along the same flight as the hit (straight without gravity).
+        let flight = Parabola {
+            origin: ws.position,
+            velocity: ws.direction.normalized() * ws.speed,
+            gravity: options.flight.acceleration(),
+        };
+        let exit = apertures
+            .iter()
+            .enumerate()
+            .filter_map(|(i, a)| {
+                a.crossing(&flight, epsilon)
+                    .map(|(t, offset)| (i, t, offset))
+            })
+            .filter(|&(_, t, _)| t < hit_time)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((aperture, t, offset)) = exit {
+            let outcome = ScatteringOutcome::Exited {
+                aperture,
+                offset,
+                angle: apertures[aperture].outgoing_angle(flight.velocity_at(t)),
+            };
+            return sample(outcome, bounces, dwell_time + t);
+        }
+
+        let Some(c) = collision else {
+            return sample(ScatteringOutcome::Lost, bounces, dwell_time);
+        };
+        if options.stops_at(&c) {
+            return sample(ScatteringOutcome::Lost, bounces + 1, dwell_time + hit_time);
+        }
+        if bounces == max_bounces {
+            return sample(ScatteringOutcome::Trapped, bounces, dwell_time);
+        }
+
+        bounces += 1;
+        dwell_time += hit_time;
+        ws = c.outgoing_state().to_world(table);
+    }
+}
+
+/// Compute the scattering map over a grid of incoming conditions on
+/// aperture `entry`.
+///
+/// Samples are ordered by offset, then angle.
+pub fn scattering_map(
+    open: &OpenTable,
+    entry: usize,
+    grid: &ScatteringGrid,
+    max_bounces: usize,
+    epsilon: f64,
+    options: &StepOptions,
+) -> Vec<ScatteringSample> {
+    let half_pi = std::f64::consts::FRAC_PI_2;
+    let mut samples = Vec::with_capacity(grid.offsets * grid.angles);
+
+    for i in 0..grid.offsets {
+        let offset = (i as f64 + 0.5) / grid.offsets as f64;
+        for j in 0..grid.angles {
+            let angle = -half_pi + (j as f64 + 0.5) * std::f64::consts::PI / grid.angles as f64;
+            samples.push(scatter(
+                open,
+                entry,
+                offset,
+                angle,
+                max_bounces,
+                epsilon,
+                options,
+            ));
+        }
+    }
+
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aperture, OpenTable, ScatteringGrid, ScatteringOutcome, scatter, scattering_map};
+    use crate::dynamics::simulation::{FlightModel, StepOptions};
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundarySegment, LineSegment};
+
+    /// Unit-square channel with walls at y = 0 and y = 1, open at both ends.
+    fn channel() -> OpenTable {
+        let bottom =
+            BoundarySegment::Line(LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)));
+        let top = BoundarySegment::Line(LineSegment::new(Vec2::new(1.0, 1.0), Vec2::new(0.0, 1.0)));
+        let table = BilliardTable::new(
+            BoundaryComponent::new("walls", vec![bottom, top]),
+            Vec::new(),
+        );
+
+        let apertures = vec![
+            Aperture::new(Vec2::new(0.0, 1.0), Vec2::new(0.0, 0.0)),
+            Aperture::new(Vec2::new(1.0, 0.0), Vec2::new(1.0, 1.0)),
+        ];
+        OpenTable { table, apertures }
+    }
+
+    /// Channel of width 2 and height 3, so apertures are longer than 1.
+    fn wide_channel() -> OpenTable {
+        let bottom =
+            BoundarySegment::Line(LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0)));
+        let top = BoundarySegment::Line(LineSegment::new(Vec2::new(2.0, 3.0), Vec2::new(0.0, 3.0)));
+        let table = BilliardTable::new(
+            BoundaryComponent::new("walls", vec![bottom, top]),
+            Vec::new(),
+        );
+
+        let apertures = vec![
+            Aperture::new(Vec2::new(0.0, 3.0), Vec2::new(0.0, 0.0)),
+            Aperture::new(Vec2::new(2.0, 0.0), Vec2::new(2.0, 3.0)),
+        ];
+        OpenTable { table, apertures }
+    }
+
+    #[test]
+    fn exits_are_found_anywhere_along_long_apertures() {
+        // Enters at (0, 2.4): the exit lies 2.4 along an aperture of
+        // length 3, not a fraction of it.
+        let open = wide_channel();
+        let sample = scatter(&open, 0, 0.2, 0.0, 10, 1e-8, &StepOptions::default());
+
+        assert_eq!(sample.bounces, 0);
+        assert!((sample.dwell_time - 2.0).abs() < 1e-12);
+        let ScatteringOutcome::Exited {
+            aperture, offset, ..
+        } = sample.outcome
+        else {
+            panic!("Expected an exit, got {:?}", sample.outcome);
+        };
+        assert_eq!(aperture, 1);
+        assert!((offset - 0.8).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gravity_bends_the_flight_to_the_exit() {
+        // Enters level at (0, 2.4) and falls 2 by the time it is across.
+        let open = wide_channel();
+        let options = StepOptions {
+            flight: FlightModel::Gravity { g: 1.0 },
+            ..StepOptions::default()
+        };
+        let sample = scatter(&open, 0, 0.2, 0.0, 10, 1e-8, &options);
+
+        assert_eq!(sample.bounces, 0);
+        assert!((sample.dwell_time - 2.0).abs() < 1e-12);
+        let ScatteringOutcome::Exited {
+            aperture,
+            offset,
+            angle,
+        } = sample.outcome
+        else {
+            panic!("Expected an exit, got {:?}", sample.outcome);
+        };
+        assert_eq!(aperture, 1);
+        assert!((offset - 0.4 / 3.0).abs() < 1e-12);
+        assert!((angle + 2.0_f64.atan()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn head_on_particle_crosses_channel() {
+        let open = channel();
+        let sample = scatter(&open, 0, 0.5, 0.0, 10, 1e-8, &StepOptions::default());
+
+        assert_eq!(sample.bounces, 0);
+        assert!((sample.dwell_time - 1.0).abs() < 1e-12);
+        let ScatteringOutcome::Exited {
+            aperture,
+            offset,
+            angle,
+        } = sample.outcome
+        else {
+            panic!("Expected an exit, got {:?}", sample.outcome);
+        };
+        assert_eq!(aperture, 1);
+        assert!((offset - 0.5).abs() < 1e-12);
+        assert!(angle.abs() < 1e-12);
+    }
+
+    #[test]
+    fn oblique_particle_bounces_once() {
+        let open = channel();
+        let angle_in = std::f64::consts::FRAC_PI_4;
+        let sample = scatter(&open, 0, 0.5, angle_in, 10, 1e-8, &StepOptions::default());
+
+        // Enters at (0, 0.5) heading down-right, bounces at (0.5, 0) and
+        // leaves through (1, 0.5) heading up-right.
+        assert_eq!(sample.bounces, 1);
+        assert!((sample.dwell_time - 2.0_f64.sqrt()).abs() < 1e-10);
+        let ScatteringOutcome::Exited {
+            aperture,
+            offset,
+            angle,
+        } = sample.outcome
+        else {
+            panic!("Expected an exit, got {:?}", sample.outcome);
+        };
+        assert_eq!(aperture, 1);
+        assert!((offset - 0.5).abs() < 1e-10);
+        assert!((angle - std::f64::consts::FRAC_PI_4).abs() < 1e-10);
+    }
+
+    #[test]
+    fn straight_channel_transmits_whole_grid() {
+        let open = channel();
+        let grid = ScatteringGrid {
+            offsets: 4,
+            angles: 6,
+        };
+        let samples = scattering_map(&open, 0, &grid, 1000, 1e-8, &StepOptions::default());
+
+        assert_eq!(samples.len(), 24);
+        // A straight channel transmits every particle.
+        for s in &samples {
+            assert!(
+                matches!(s.outcome, ScatteringOutcome::Exited { aperture: 1, .. }),
+                "{:?}",
+                s
+            );
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Side-by-side runs of two nearby orbits.
+//!
+//! Running two initial conditions a tiny distance apart shows sensitive
+//! dependence directly: on a chaotic table their separation grows
+//! exponentially (at the rate a Lyapunov exponent measures) until it is of
+//! the order of the table, while on an integrable one it grows at most
+//! linearly. Comparing the segments hit tells whether the two orbits still
+//! share a symbolic itinerary.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::simulation::{BilliardMap, DynamicsError, StepOptions};
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+
+/// How two orbits launched close together drifted apart.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DivergenceReport {
+    /// Separation after each bounce both orbits made: the distance between
+    /// the hit points plus the distance between the outgoing unit
+    /// directions, added in quadrature.
+    pub separations: Vec<f64>,
+
+    /// First bounce (0-based) whose separation exceeds the threshold.
+    pub first_divergence: Option<usize>,
+
+    /// First bounce at which the orbits hit different segments.
+    pub first_symbol_mismatch: Option<usize>,
+
+    /// Whether the orbits hit the same segments at every compared bounce.
+    pub symbols_agree: bool,
+}
+
+/// Run the orbits from `a` and `b` for up to `bounces` collisions each and
+/// compare them bounce by bounce.
+///
+/// The comparison stops when either orbit ends. A separation above
+/// `threshold` counts as divergence.
+pub fn compare_trajectories(
+    table: &BilliardTable,
+    a: &BoundaryState,
+    b: &BoundaryState,
+    bounces: usize,
+    epsilon: f64,
+    options: &StepOptions,
+    threshold: f64,
+) -> Result<DivergenceReport, DynamicsError> {
+    let first = BilliardMap::new(table, epsilon, *options)
+        .simulate(a, bounces)?
+        .collisions;
+    let second = BilliardMap::new(table, epsilon, *options)
+        .simulate(b, bounces)?
+        .collisions;
+
+    let mut separations = Vec::with_capacity(first.len().min(second.len()));
+    let mut first_divergence = None;
+    let mut first_symbol_mismatch = None;
+
+    for (k, (p, q)) in first.iter().zip(&second).enumerate() {
+        let dp = p.outgoing_state().to_world(table);
+        let dq = q.outgoing_state().to_world(table);
+        let position = (p.hit_point - q.hit_point).length();
+        let direction = (dp.direction.normalized() - dq.direction.normalized()).length();
+        let separation = position.hypot(direction);
+        separations.push(separation);
+
+        if first_divergence.is_none() && separation > threshold {
+            first_divergence = Some(k);
+        }
+        let same_symbol =
+            (p.component_index, p.segment_index) == (q.component_index, q.segment_index);
+        if first_symbol_mismatch.is_none() && !same_symbol {
+            first_symbol_mismatch = Some(k);
+        }
+    }
+
+    Ok(DivergenceReport {
+        separations,
+        first_divergence,
+        first_symbol_mismatch,
+        symbols_agree: first_symbol_mismatch.is_none(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare_trajectories;
+    use crate::dynamics::simulation::StepOptions;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{rectangle, sinai};
+
+    #[test]
+    fn identical_orbits_never_separate() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let a = BoundaryState::new(0, 0.3, 1.0);
+        let report =
+            compare_trajectories(&table, &a, &a, 50, 1e-8, &StepOptions::default(), 1e-6).unwrap();
+
+        assert_eq!(report.separations, vec![0.0; 50]);
+        assert_eq!(report.first_divergence, None);
+        assert!(report.symbols_agree);
+    }
+
+    #[test]
+    fn chaotic_orbits_diverge_and_integrable_ones_do_not() {
+        let a = BoundaryState::new(0, 0.3, 1.0);
+        let b = BoundaryState::new(0, 0.3 + 1e-10, 1.0);
+        let options = StepOptions::default();
+
+        let sinai = sinai(1.0, 0.2).to_billiard_table();
+        let chaotic = compare_trajectories(&sinai, &a, &b, 200, 1e-8, &options, 0.1).unwrap();
+        let k = chaotic.first_divergence.expect("Sinai orbits separate");
+        assert!(k > 5, "diverged after only {} bounces", k);
+        assert!(!chaotic.symbols_agree);
+        assert!(chaotic.first_symbol_mismatch.unwrap() >= k.saturating_sub(5));
+
+        let square = rectangle(1.0, 1.0).to_billiard_table();
+        let regular = compare_trajectories(&square, &a, &b, 200, 1e-8, &options, 1e-6).unwrap();
+        assert_eq!(regular.first_divergence, None);
+        assert!(regular.symbols_agree);
+    }
+}
@@ -1,140 +1,1288 @@
-use crate::dynamics::intersection::Ray;
+use crate::dynamics::circle_map::CircleMap;
+use crate::dynamics::config::SimulationConfig;
+use crate::dynamics::gallery::{GalleryAction, GalleryGuard, WhisperingGallery, skip_along_arc};
+use crate::dynamics::intersection::{IntersectionOptions, Parabola, Ray};
+use crate::dynamics::invariants::{InvariantCheck, InvariantMonitor, InvariantReport};
+use crate::dynamics::observer::Observer;
+use crate::dynamics::reflection::{Impact, ReflectionLaw, Specular};
 use crate::dynamics::state::{BoundaryState, WorldState};
-use crate::geometry::boundary::BilliardTable;
-use crate::geometry::primitives::Vec2;
+use crate::geometry::boundary::{BilliardTable, Corner};
+use crate::geometry::primitives::{BoundingBox, Vec2};
+use crate::geometry::segments::{BoundaryCondition, BoundarySegment};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::iter::FusedIterator;
+
+/// Intersection tolerance used when a caller does not supply its own
+/// epsilon; see [`IntersectionOptions::tolerance`].
+pub const DEFAULT_EPSILON: f64 = 1e-8;
+
+/// Default arc-length tolerance for treating a hit as a corner hit.
+pub const DEFAULT_CORNER_TOLERANCE: f64 = 1e-9;
+
+/// How the simulation handles a collision that lands on a corner.
+///
+/// The billiard map is undefined at a corner, so some convention is needed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum CornerPolicy {
+    /// Record the corner hit and stop the trajectory there.
+    Terminate,
+
+    /// Reflect off the line whose normal bisects the normals of the two
+    /// segments meeting at the corner.
+    ReflectBisector,
+
+    /// Reflect off whichever segment was hit, and flag the corner in the
+    /// collision result. At an exact corner the reflected ray may leave the
+    /// table, ending the trajectory.
+    #[default]
+    Report,
+}
+
+/// How the particle moves between bounces.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum FlightModel {
+    /// Straight lines at constant speed.
+    #[default]
+    Straight,
+
+    /// Parabolic arcs under a constant downward acceleration `g` (along
+    /// `-y`), as in the wedge and other gravitational billiards.
+    Gravity { g: f64 },
+}
+
+impl FlightModel {
+    /// Constant acceleration during free flight.
+    pub fn acceleration(&self) -> Vec2 {
+        match *self {
+            FlightModel::Straight => Vec2::new(0.0, 0.0),
+            FlightModel::Gravity { g } => Vec2::new(0.0, -g),
+        }
+    }
+}
+
+/// Options controlling a single step of the billiard map.
+#[derive(Clone, Copy, Debug)]
+pub struct StepOptions {
+    /// What to do when a collision lands on a corner.
+    pub corner_policy: CornerPolicy,
+
+    /// Arc-length distance from a segment junction within which a hit is
+    /// considered a corner hit.
+    pub corner_tolerance: f64,
+
+    /// Region outside of which the particle is declared escaped. Without a
+    /// box, a ray that misses the table simply ends the trajectory.
+    pub escape_box: Option<BoundingBox>,
+
+    /// Coefficient of restitution `e ∈ (0, 1]`: the normal velocity
+    /// component is scaled by `e` at each bounce. `1.0` is elastic.
+    pub restitution: f64,
+
+    /// Free-flight model between bounces.
+    pub flight: FlightModel,
+
+    /// Check every collision against the conserved quantities of the
+    /// dynamics; see [`crate::dynamics::invariants`]. `None` (the default)
+    /// for no checks.
+    pub invariants: Option<InvariantCheck>,
+
+    /// Watch for particles creeping along a circular arc, and skip ahead
+    /// or stop when one does; see [`crate::dynamics::gallery`]. `None`
+    /// (the default) to step every bounce.
+    pub whispering_gallery: Option<WhisperingGallery>,
+}
+
+impl StepOptions {
+    /// Whether a trajectory must stop after recording `collision`.
+    pub fn stops_at(&self, collision: &CollisionResult) -> bool {
+        self.stop_reason(collision).is_some()
+    }
+
+    /// Why a trajectory must stop after recording `collision`, if it must.
+    pub fn stop_reason(&self, collision: &CollisionResult) -> Option<Termination> {
+        if collision.absorbed {
+            Some(Termination::Absorbed)
+        } else if collision.corner.is_some() && self.corner_policy == CornerPolicy::Terminate {
+            Some(Termination::Corner)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for StepOptions {
+    fn default() -> Self {
+        Self {
+            corner_policy: CornerPolicy::default(),
+            corner_tolerance: DEFAULT_CORNER_TOLERANCE,
+            escape_box: None,
+            restitution: 1.0,
+            flight: FlightModel::Straight,
+            invariants: None,
+            whispering_gallery: None,
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct CollisionResult {
     pub component_index: usize,
     pub segment_index: usize,
-    pub s: f64,     // new boundary arc-length parameter
-    pub theta: f64, // new outgoing angle after reflection
+    pub segment_id: Option<u32>, // id of that segment in the table spec, if it has one
+    pub s: f64,                  // new boundary arc-length parameter
+    pub theta: f64,              // new outgoing angle after reflection
+    pub speed: f64,              // outgoing speed after reflection
     pub hit_point: Vec2,
+    pub chord: f64,             // straight-line length of the flight ending here
+    pub corner: Option<Corner>, // set when the hit landed on a corner
+    pub absorbed: bool,         // set when the hit landed in a hole
+    pub tangent: bool,          // set when the flight only touched the boundary
+    pub skipped: usize,         // bounces along an arc stepped over to reach this one
+    pub delay: f64,             // time spent at the wall before leaving
+    pub path_length: f64,       // total chord length since the start of the run
+    pub time: f64,              // time of the hit since the start of the run
 }
 
 impl CollisionResult {
+    /// A collision whose running totals cover its own flight alone, taken
+    /// at unit speed; see [`CollisionResult::after`].
     pub fn new(
-        component_index: usize,
+        outgoing: BoundaryState,
         segment_index: usize,
-        s: f64,
-        theta: f64,
         hit_point: Vec2,
+        chord: f64,
+        corner: Option<Corner>,
+        absorbed: bool,
     ) -> Self {
         Self {
-            component_index,
+            component_index: outgoing.component_index,
             segment_index,
-            s,
-            theta,
+            segment_id: None,
+            s: outgoing.s,
+            theta: outgoing.theta,
+            speed: outgoing.speed,
             hit_point,
+            chord,
+            corner,
+            absorbed,
+            tangent: false,
+            skipped: 0,
+            delay: 0.0,
+            path_length: chord,
+            time: chord,
+        }
+    }
+
+    /// The same collision with the id of its segment in `table`.
+    pub fn labelled(self, table: &BilliardTable) -> Self {
+        let segment = &table.component(self.component_index).segments[self.segment_index];
+        CollisionResult {
+            segment_id: segment.label().id,
+            ..self
+        }
+    }
+
+    /// Name of the segment of `table` this collision left from, if it was
+    /// given one.
+    pub fn segment_name<'a>(&self, table: &'a BilliardTable) -> Option<&'a str> {
+        let segment = &table.component(self.component_index).segments[self.segment_index];
+        segment.label().name.as_deref()
+    }
+
+    /// When the particle leaves this collision, after any delay at the
+    /// wall.
+    pub fn departure_time(&self) -> f64 {
+        self.time + self.delay
+    }
+
+    /// The same collision in a run that had already covered `path_length`
+    /// and lasted `time` before it started, e.g. to join up trajectories
+    /// simulated in chunks.
+    pub fn after(self, path_length: f64, time: f64) -> Self {
+        CollisionResult {
+            path_length: self.path_length + path_length,
+            time: self.time + time,
+            ..self
+        }
+    }
+
+    /// The boundary state the particle leaves this collision in.
+    pub fn outgoing_state(&self) -> BoundaryState {
+        BoundaryState {
+            component_index: self.component_index,
+            s: self.s,
+            theta: self.theta,
+            speed: self.speed,
         }
     }
 }
 
-/// Find the next collision on the table from the boundary state.
-///
-/// Steps:
-/// 1. Convert the boundary state to a world-space state (position + direction).
-/// 2. Cast a ray from that position along the direction.
-/// 3. Intersect the ray with the table to find the nearest collision.
-/// 4. Compute the reflection using the inward normal at the hit point.
-/// 5. Convert the reflected world state back into a boundary-based state.
-/// 6. Return the new boundary state and the collision point.
-pub fn next_collision_from_boundary_state(
-    table: &BilliardTable,
-    bs: &BoundaryState,
-    epsilon: f64,
-) -> Option<CollisionResult> {
-    let ws = bs.to_world(table);
+/// Where a particle crossed the escape box of an open table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Escape {
+    /// Point at which the final free flight leaves the escape box.
+    pub position: Vec2,
 
-    let ray = Ray {
-        origin: ws.position,
-        direction: ws.direction,
-    };
+    /// Unit direction of travel at escape.
+    pub direction: Vec2,
+}
+
+/// Result of a single step of the billiard map.
+#[derive(Clone, Copy, Debug)]
+pub enum StepOutcome {
+    /// The particle bounced off the boundary.
+    Collision(CollisionResult),
 
-    let intersection = ray.intersect_table(table, epsilon)?;
-    let component_index = intersection.component_index;
-    let segment_index = intersection.segment_index;
-    let local_t = intersection.local_t;
-    let ray_t = intersection.ray_parameter;
+    /// The particle left the escape box before hitting the boundary.
+    Escaped(Escape),
 
-    let component = table.component(component_index);
-    let new_s = component.global_s_from_segment_local(segment_index, local_t);
+    /// The ray missed the table and no escape box is configured.
+    NoCollision,
+}
 
-    // Hit point from ray parameter
-    let v_in = ws
-        .direction
-        .try_normalized()
-        .expect("World direction should not be near-zero.");
-    let hit_point = ws.position + v_in * ray_t;
+/// Why a trajectory stopped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Termination {
+    /// `max_steps` collisions were generated.
+    MaxSteps,
 
-    // Get inward normal from boundary at that s
-    let (_check_point, inward_normal) = component.point_and_inward_normal_at(new_s);
+    /// A corner was hit under [`CornerPolicy::Terminate`].
+    Corner,
 
-    let n = inward_normal
-        .try_normalized()
-        .expect("Inward normal should not be near-zero.");
+    /// The particle landed in a [`Hole`](crate::geometry::boundary::Hole).
+    Absorbed,
 
-    let dot_vn = v_in.dot(n);
-    let v_out = v_in - n * (2.0 * dot_vn);
+    /// The particle left the escape box.
+    Escaped(Escape),
 
-    let outgoing_world = WorldState {
-        position: hit_point,
-        direction: v_out,
-    };
+    /// The ray missed the table and no escape box is configured.
+    NoCollision,
+
+    /// The caller's stop condition held; see [`BilliardMap::simulate_until`].
+    StopCondition,
+
+    /// The next collision would come after the time limit; see
+    /// [`BilliardMap::simulate_for`].
+    MaxTime,
 
-    let outgoing_bs = outgoing_world.to_boundary(table, component_index, new_s);
+    /// A collision broke the tolerances of [`StepOptions::invariants`],
+    /// which asked to abort.
+    InvariantViolation,
 
-    Some(CollisionResult::new(
-        outgoing_bs.component_index,
-        segment_index,
-        outgoing_bs.s,
-        outgoing_bs.theta,
-        hit_point,
-    ))
+    /// The particle crept along a circular arc, and
+    /// [`StepOptions::whispering_gallery`] asked to stop there.
+    WhisperingGallery,
 }
 
-/// Simulate a billiard trajectory by iterating boundary collisions.
-///
-/// Starts from an initial boundary state and repeatedly applies
-/// `next_collision_from_boundary_state`, collecting each collision.
+/// Why a step of the billiard map could not be computed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DynamicsError {
+    /// The direction of travel is zero or not finite.
+    DegenerateDirection,
+
+    /// The boundary normal at a hit point is zero or not finite.
+    DegenerateNormal {
+        component_index: usize,
+        segment_index: usize,
+    },
+
+    /// A position, speed or outgoing state is not finite.
+    NumericalBreakdown,
+
+    /// Flights are parabolic under [`FlightModel::Gravity`], but an
+    /// [`ExternalSegment`](crate::geometry::segments::ExternalSegment) can
+    /// only be intersected with straight rays.
+    ExternalSegmentUnderGravity {
+        component_index: usize,
+        segment_index: usize,
+    },
+
+    /// A boundary state names a component the table does not have.
+    UnknownComponent { component_index: usize },
+
+    /// An itinerary names a segment its component does not have.
+    UnknownSegment {
+        component_index: usize,
+        segment_index: usize,
+    },
+
+    /// A boundary state has a non-finite `s`, `theta` or `speed`, or a
+    /// speed that is not positive.
+    InvalidState,
+
+    /// The run was stopped from outside; see [`BilliardMap::cancelled_by`].
+    Cancelled,
+}
+
+impl fmt::Display for DynamicsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicsError::DegenerateDirection => {
+                write!(f, "direction of travel is zero or not finite")
+            }
+            DynamicsError::DegenerateNormal {
+                component_index,
+                segment_index,
+            } => write!(
+                f,
+                "degenerate normal on segment {} of component {}",
+                segment_index, component_index
+            ),
+            DynamicsError::NumericalBreakdown => {
+                write!(f, "state became non-finite during the simulation")
+            }
+            DynamicsError::ExternalSegmentUnderGravity {
+                component_index,
+                segment_index,
+            } => write!(
+                f,
+                "segment {} of component {} is an external curve, which parabolic flights cannot hit",
+                segment_index, component_index
+            ),
+            DynamicsError::UnknownComponent { component_index } => {
+                write!(f, "the table has no component {}", component_index)
+            }
+            DynamicsError::UnknownSegment {
+                component_index,
+                segment_index,
+            } => write!(
+                f,
+                "component {} has no segment {}",
+                component_index, segment_index
+            ),
+            DynamicsError::InvalidState => {
+                write!(f, "boundary state is not finite or not moving")
+            }
+            DynamicsError::Cancelled => write!(f, "the simulation was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for DynamicsError {}
+
+fn is_finite(v: Vec2) -> bool {
+    v.x.is_finite() && v.y.is_finite()
+}
+
+/// A simulated trajectory together with the reason it ended.
+#[derive(Clone, Debug)]
+pub struct Trajectory {
+    pub collisions: Vec<CollisionResult>,
+    pub termination: Termination,
+
+    /// What the invariant checks found, if [`StepOptions::invariants`]
+    /// asked for them.
+    pub invariants: Option<InvariantReport>,
+}
+
+/// The billiard map of a table: everything needed to get from one bounce
+/// to the next, configured once and then stepped as often as needed.
 ///
-/// Stops early if:
-/// - `next_collision_from_boundary_state` returns `None`, or
-/// - `max_steps` collisions have been generated.
-pub fn run_trajectory(
-    table: &BilliardTable,
-    initial: &BoundaryState,
-    max_steps: usize,
+/// Build one to step or [simulate](BilliardMap::simulate) a table, to
+/// iterate lazily with [`BilliardMap::iter_from`], or to leave the wall by
+/// a [`ReflectionLaw`] other than [`Specular`]; [`run_trajectory`] builds
+/// one on the spot for a [`SimulationConfig`].
+pub struct BilliardMap<'a, L = Specular> {
+    table: &'a BilliardTable,
     epsilon: f64,
-) -> Vec<CollisionResult> {
-    let mut collisions = Vec::with_capacity(max_steps);
-    let mut current = *initial;
-
-    for _ in 0..max_steps {
-        let collision = match next_collision_from_boundary_state(table, &current, epsilon) {
-            Some(c) => c,
-            None => break,
+    options: StepOptions,
+    law: L,
+    cancelled: Option<&'a Cancelled<'a>>,
+    progress: Option<&'a Progress<'a>>,
+
+    /// Time on the clock of moving walls at which runs start.
+    start_time: f64,
+
+    /// The first external segment of the table, found once when the map
+    /// is built under [`FlightModel::Gravity`], which cannot hit it.
+    external_under_gravity: Option<(usize, usize)>,
+}
+
+/// A check whether a run should stop early; see
+/// [`BilliardMap::cancelled_by`].
+pub type Cancelled<'a> = dyn Fn() -> bool + Sync + 'a;
+
+/// A callback told how many more steps of the map a run has taken; see
+/// [`BilliardMap::reporting_to`].
+pub type Progress<'a> = dyn Fn(usize) + Sync + 'a;
+
+/// Collisions an iterator computes between two calls of the
+/// [`Cancelled`] check, and of the [`Progress`] callback.
+const CANCEL_CHECK_INTERVAL: usize = 256;
+
+/// Counts the steps of a run, passing them on to a [`Progress`] callback
+/// every [`CANCEL_CHECK_INTERVAL`] steps, and the rest once dropped.
+pub(crate) struct ProgressCounter<'a> {
+    progress: Option<&'a Progress<'a>>,
+    steps: usize,
+    reported: usize,
+}
+
+impl<'a> ProgressCounter<'a> {
+    pub(crate) fn new(progress: Option<&'a Progress<'a>>) -> Self {
+        ProgressCounter {
+            progress,
+            steps: 0,
+            reported: 0,
+        }
+    }
+
+    /// Steps counted so far.
+    pub(crate) fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Count one more step.
+    pub(crate) fn tick(&mut self) {
+        self.steps += 1;
+        if self.steps.is_multiple_of(CANCEL_CHECK_INTERVAL) {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(progress) = self.progress
+            && self.steps > self.reported
+        {
+            progress(self.steps - self.reported);
+            self.reported = self.steps;
+        }
+    }
+}
+
+impl Drop for ProgressCounter<'_> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<'a> BilliardMap<'a> {
+    /// Specular billiard map on `table`; `epsilon` is the
+    /// [tolerance](IntersectionOptions::tolerance) within which a segment
+    /// passes through the point a flight leaves from and is not hit.
+    pub fn new(table: &'a BilliardTable, epsilon: f64, options: StepOptions) -> Self {
+        let external_under_gravity = match options.flight {
+            FlightModel::Straight => None,
+            FlightModel::Gravity { .. } => {
+                table.components().enumerate().find_map(|(c, component)| {
+                    let s = component
+                        .segments
+                        .iter()
+                        .position(|seg| matches!(seg, BoundarySegment::External(_)))?;
+                    Some((c, s))
+                })
+            }
+        };
+        BilliardMap {
+            table,
+            epsilon,
+            options,
+            law: Specular,
+            cancelled: None,
+            progress: None,
+            start_time: 0.0,
+            external_under_gravity,
+        }
+    }
+
+    /// Specular billiard map on `table` with the epsilon and step options
+    /// of `config`.
+    pub fn from_config(table: &'a BilliardTable, config: &SimulationConfig) -> Self {
+        BilliardMap::new(table, config.epsilon(), *config.step_options())
+    }
+}
+
+impl<'a, L: ReflectionLaw> BilliardMap<'a, L> {
+    /// The same map, leaving the wall according to `law`.
+    pub fn with_law<M: ReflectionLaw>(self, law: M) -> BilliardMap<'a, M> {
+        BilliardMap {
+            table: self.table,
+            epsilon: self.epsilon,
+            options: self.options,
+            law,
+            cancelled: self.cancelled,
+            progress: self.progress,
+            start_time: self.start_time,
+            external_under_gravity: self.external_under_gravity,
+        }
+    }
+
+    /// The same map, with its runs stopping once `cancelled` returns true.
+    ///
+    /// The check is made before the first collision of a run and then
+    /// every few hundred, so a run ends soon after it is cancelled, with
+    /// [`DynamicsError::Cancelled`]. Single steps are not checked.
+    pub fn cancelled_by(self, cancelled: &'a Cancelled<'a>) -> Self {
+        BilliardMap {
+            cancelled: Some(cancelled),
+            ..self
+        }
+    }
+
+    /// The same map, telling `progress` how far its runs have got.
+    ///
+    /// `progress` is called with the number of steps taken since its last
+    /// call, every few hundred steps and once more when the run is over,
+    /// so the counts of a run add up to its length, and those of runs on
+    /// several threads to their total. Single steps are not reported.
+    pub fn reporting_to(self, progress: &'a Progress<'a>) -> Self {
+        BilliardMap {
+            progress: Some(progress),
+            ..self
+        }
+    }
+
+    /// The same map, with its runs and single steps starting at `time` on
+    /// the clock of [`Oscillating`](BoundaryCondition::Oscillating) walls
+    /// rather than at 0, to carry on a run from where an earlier one left
+    /// off. The times of the collisions still count from the start.
+    pub fn starting_at(self, time: f64) -> Self {
+        BilliardMap {
+            start_time: time,
+            ..self
+        }
+    }
+
+    pub fn table(&self) -> &'a BilliardTable {
+        self.table
+    }
+
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    pub fn options(&self) -> &StepOptions {
+        &self.options
+    }
+
+    /// The reflection law, e.g. to read what a recording law has recorded.
+    pub fn law(&self) -> &L {
+        &self.law
+    }
+
+    pub fn into_law(self) -> L {
+        self.law
+    }
+
+    /// Find the next collision from a boundary state.
+    ///
+    /// Fails if `bs` cannot be placed on the table; see
+    /// [`BoundaryState::check`].
+    pub fn collision(
+        &mut self,
+        bs: &BoundaryState,
+    ) -> Result<Option<CollisionResult>, DynamicsError> {
+        let ws = bs.try_to_world(self.table)?;
+        let intersection = self.leaving(bs);
+        self.collide(&ws, &intersection, 0.0)
+    }
+
+    /// Intersection options for a flight from `bs`, which leaves the
+    /// segment `bs` lies on.
+    fn leaving(&self, bs: &BoundaryState) -> IntersectionOptions {
+        let (segment_index, _) = self.table.component(bs.component_index).locate(bs.s);
+        IntersectionOptions::new(self.epsilon).leaving(bs.component_index, segment_index)
+    }
+
+    /// Find the first collision of a particle at an arbitrary world-space
+    /// state.
+    ///
+    /// Steps:
+    /// 1. Cast a ray (or parabola) from the position along the direction.
+    /// 2. Intersect it with the table to find the nearest collision.
+    /// 3. Apply the [`BoundaryCondition`] of the hit segment. For a
+    ///    reflecting (or absorbing) segment, the reflection law picks the
+    ///    outgoing velocity from the inward normal at the hit point (or the
+    ///    corner bisector normal, depending on `options.corner_policy`),
+    ///    and its delay is recorded in the result.
+    /// 4. Convert the outgoing world state back into a boundary-based state.
+    /// 5. Return the new boundary state and the collision point.
+    ///
+    /// For a periodic segment, `segment_index`, `s` and `theta` of the
+    /// result describe the re-entry on the paired segment, while
+    /// `hit_point` is where the particle crossed the segment it hit.
+    ///
+    /// Returns `Ok(None)` if the particle hits nothing, and an error if the
+    /// state is degenerate or the collision cannot be computed.
+    pub fn collision_from_world(
+        &mut self,
+        ws: &WorldState,
+    ) -> Result<Option<CollisionResult>, DynamicsError> {
+        self.collide(ws, &IntersectionOptions::new(self.epsilon), 0.0)
+    }
+
+    /// [`BilliardMap::collision_from_world`], with the segment the flight
+    /// leaves (if any) in `intersection`, and the flight leaving
+    /// `departure` after the start of the run.
+    fn collide(
+        &mut self,
+        ws: &WorldState,
+        intersection: &IntersectionOptions,
+        departure: f64,
+    ) -> Result<Option<CollisionResult>, DynamicsError> {
+        if !is_finite(ws.position) || !ws.speed.is_finite() {
+            return Err(DynamicsError::NumericalBreakdown);
+        }
+        let unit_direction = ws
+            .direction
+            .try_normalized()
+            .filter(|d| is_finite(*d))
+            .ok_or(DynamicsError::DegenerateDirection)?;
+
+        // Hit point, incoming velocity at the hit and flight time, per
+        // flight model.
+        let (intersection, hit_point, v_hit, flight_time) = match self.options.flight {
+            FlightModel::Straight => {
+                let ray = Ray {
+                    origin: ws.position,
+                    direction: ws.direction,
+                };
+                let Some(intersection) = ray.intersect_table(self.table, intersection) else {
+                    return Ok(None);
+                };
+                let hit_point = ws.position + unit_direction * intersection.ray_parameter;
+                let flight_time = intersection.ray_parameter / ws.speed;
+                (
+                    intersection,
+                    hit_point,
+                    unit_direction * ws.speed,
+                    flight_time,
+                )
+            }
+            FlightModel::Gravity { .. } => {
+                if let Some((component_index, segment_index)) = self.external_under_gravity {
+                    return Err(DynamicsError::ExternalSegmentUnderGravity {
+                        component_index,
+                        segment_index,
+                    });
+                }
+                let parabola = Parabola {
+                    origin: ws.position,
+                    velocity: unit_direction * ws.speed,
+                    gravity: self.options.flight.acceleration(),
+                };
+                let Some(intersection) = parabola.intersect_table(self.table, intersection) else {
+                    return Ok(None);
+                };
+                let t = intersection.ray_parameter;
+                (
+                    intersection,
+                    parabola.point_at(t),
+                    parabola.velocity_at(t),
+                    t,
+                )
+            }
+        };
+        let component_index = intersection.component_index;
+        let segment_index = intersection.segment_index;
+        let local_t = intersection.local_t;
+        let tangent = intersection.tangent;
+        let chord = (hit_point - ws.position).length();
+
+        let component = self.table.component(component_index);
+        let new_s = component.global_s_from_segment_local(segment_index, local_t);
+
+        let speed_in = v_hit.length();
+        if !(speed_in.is_finite() && speed_in > 0.0 && is_finite(hit_point)) {
+            return Err(DynamicsError::NumericalBreakdown);
+        }
+        let v_in = v_hit / speed_in;
+
+        let corner = component.corner_near(segment_index, local_t, self.options.corner_tolerance);
+        let condition = component.segments[segment_index].condition();
+
+        if let BoundaryCondition::PeriodicPairedWith(paired) = condition {
+            // Re-enter through the paired segment, keeping the direction.
+            let from = &component.segments[segment_index];
+            let to = &component.segments[paired];
+            let paired_t = (1.0 - local_t / from.length()) * to.length();
+            let paired_s = component.global_s_from_segment_local(paired, paired_t);
+
+            let outgoing_world = WorldState {
+                position: to.point_at(paired_t),
+                direction: v_in,
+                speed: speed_in,
+            };
+            let outgoing_bs = outgoing_world.to_boundary(self.table, component_index, paired_s);
+
+            return Ok(Some(CollisionResult {
+                time: flight_time,
+                tangent,
+                ..CollisionResult::new(outgoing_bs, paired, hit_point, chord, corner, false)
+                    .labelled(self.table)
+            }));
+        }
+
+        // Get inward normal from boundary at that s
+        let (_check_point, inward_normal) = component.point_and_inward_normal_at(new_s);
+
+        let normal = match (corner, self.options.corner_policy) {
+            (Some(c), CornerPolicy::ReflectBisector) => c.bisector_normal,
+            _ => inward_normal,
+        };
+
+        let n = normal.try_normalized().filter(|n| is_finite(*n)).ok_or(
+            DynamicsError::DegenerateNormal {
+                component_index,
+                segment_index,
+            },
+        )?;
+
+        let reflection = self.law.reflect(&Impact {
+            direction: v_in,
+            speed: speed_in,
+            normal: n,
+            condition,
+            restitution: self.options.restitution,
+            time: self.start_time + departure + flight_time,
+            component_index,
+            segment_index,
+        });
+        let v_out = reflection.velocity;
+
+        let speed_out = speed_in * v_out.length();
+        let delay_ok = reflection.delay.is_finite() && reflection.delay >= 0.0;
+        if !(speed_out.is_finite() && speed_out > 0.0 && delay_ok) {
+            return Err(DynamicsError::NumericalBreakdown);
+        }
+        let outgoing_world = WorldState {
+            position: hit_point,
+            direction: v_out,
+            speed: speed_out,
+        };
+
+        let outgoing_bs = outgoing_world.to_boundary(self.table, component_index, new_s);
+
+        Ok(Some(CollisionResult {
+            delay: reflection.delay,
+            time: flight_time,
+            tangent,
+            ..CollisionResult::new(
+                outgoing_bs,
+                segment_index,
+                hit_point,
+                chord,
+                corner,
+                condition == BoundaryCondition::Absorb || component.is_absorbing(new_s),
+            )
+            .labelled(self.table)
+        }))
+    }
+
+    /// Advance one step of the billiard map, taking escapes into account.
+    ///
+    /// With an escape box configured, a ray that misses the table, or whose
+    /// next hit lies outside the box, is reported as
+    /// [`StepOutcome::Escaped`] at the point where it crosses the box (along
+    /// the parabola under [`FlightModel::Gravity`]).
+    pub fn step(&mut self, bs: &BoundaryState) -> Result<StepOutcome, DynamicsError> {
+        self.step_at(bs, 0.0)
+    }
+
+    /// [`BilliardMap::step`] for a flight leaving `departure` after the
+    /// start of the run.
+    fn step_at(
+        &mut self,
+        bs: &BoundaryState,
+        departure: f64,
+    ) -> Result<StepOutcome, DynamicsError> {
+        let ws = bs.try_to_world(self.table)?;
+        let intersection = self.leaving(bs);
+        self.advance(&ws, &intersection, departure)
+    }
+
+    /// Like [`BilliardMap::step`], but for a particle at an arbitrary
+    /// world-space state, e.g. launched from the interior of the table.
+    pub fn step_from_world(&mut self, ws: &WorldState) -> Result<StepOutcome, DynamicsError> {
+        self.advance(ws, &IntersectionOptions::new(self.epsilon), 0.0)
+    }
+
+    /// [`BilliardMap::step_from_world`], with the segment the flight leaves
+    /// (if any) in `intersection`, and the flight leaving `departure` after
+    /// the start of the run.
+    fn advance(
+        &mut self,
+        ws: &WorldState,
+        intersection: &IntersectionOptions,
+        departure: f64,
+    ) -> Result<StepOutcome, DynamicsError> {
+        let collision = self.collide(ws, intersection, departure)?;
+
+        let Some(escape_box) = self.options.escape_box else {
+            return Ok(collision.map_or(StepOutcome::NoCollision, StepOutcome::Collision));
+        };
+
+        Ok(match collision {
+            Some(c) if escape_box.contains(c.hit_point) => StepOutcome::Collision(c),
+            _ => {
+                let direction = ws.direction.normalized();
+                match self.options.flight {
+                    FlightModel::Gravity { .. } => {
+                        let parabola = Parabola {
+                            origin: ws.position,
+                            velocity: direction * ws.speed,
+                            gravity: self.options.flight.acceleration(),
+                        };
+                        parabola
+                            .exit_time(&escape_box)
+                            .map_or(StepOutcome::NoCollision, |t| {
+                                StepOutcome::Escaped(Escape {
+                                    position: parabola.point_at(t),
+                                    // A crawling particle without gravity
+                                    // has too short a velocity to normalize.
+                                    direction: parabola
+                                        .velocity_at(t)
+                                        .try_normalized()
+                                        .unwrap_or(direction),
+                                })
+                            })
+                    }
+                    FlightModel::Straight => StepOutcome::Escaped(Escape {
+                        position: escape_box.exit_point(ws.position, direction),
+                        direction,
+                    }),
+                }
+            }
+        })
+    }
+
+    /// Lazy iterator over the collisions of the trajectory from `initial`.
+    ///
+    /// It ends after a collision that stops the trajectory (see
+    /// [`StepOptions::stop_reason`]), when the particle escapes or misses
+    /// the table, or after yielding an error; otherwise it runs forever, so
+    /// bound it with e.g. [`Iterator::take`].
+    pub fn iter_from(&mut self, initial: &BoundaryState) -> Collisions<'_, 'a, L> {
+        // An invalid initial state fails the first step; nothing to check.
+        let monitor = self.options.invariants.and_then(|check| {
+            let start = initial.try_to_world(self.table).ok()?;
+            Some(InvariantMonitor::new(check, &start))
+        });
+        let gallery = self.options.whispering_gallery.map(GalleryGuard::new);
+        let counter = ProgressCounter::new(self.progress);
+        Collisions {
+            map: self,
+            monitor,
+            gallery,
+            skip_arc: false,
+            current: *initial,
+            path_length: 0.0,
+            time: 0.0,
+            termination: None,
+            failed: false,
+            counter,
+        }
+    }
+
+    /// Simulate a billiard trajectory and report how it ended.
+    ///
+    /// Starts from an initial boundary state and repeatedly applies
+    /// [`BilliardMap::step`], collecting each collision.
+    ///
+    /// Stops early if:
+    /// - the particle escapes or the ray misses the table,
+    /// - a corner is hit under [`CornerPolicy::Terminate`] or the particle
+    ///   lands in a hole (that hit is the last collision returned), or
+    /// - `max_steps` collisions have been generated.
+    ///
+    /// Fails if any step does; the collisions before it are discarded.
+    pub fn simulate(
+        &mut self,
+        initial: &BoundaryState,
+        max_steps: usize,
+    ) -> Result<Trajectory, DynamicsError> {
+        let mut collisions = Vec::with_capacity(max_steps);
+        let mut iter = self.iter_from(initial);
+        for collision in iter.by_ref().take(max_steps) {
+            collisions.push(collision?);
+        }
+        let termination = iter.termination().unwrap_or(Termination::MaxSteps);
+        Ok(iter.trajectory(collisions, termination))
+    }
+
+    /// Like [`BilliardMap::simulate`], but also stopping after the first
+    /// collision for which `stop` returns true.
+    ///
+    /// `stop` is called with each new collision and the collisions before
+    /// it, so it can look at the latest bounce alone (a region of phase
+    /// space, a grazing angle) or at the whole history (total path length).
+    /// The collision that meets the condition is the last one returned. If
+    /// the trajectory also ends there for another reason, that reason is
+    /// reported instead of [`Termination::StopCondition`].
+    pub fn simulate_until<P>(
+        &mut self,
+        initial: &BoundaryState,
+        max_steps: usize,
+        mut stop: P,
+    ) -> Result<Trajectory, DynamicsError>
+    where
+        P: FnMut(&CollisionResult, &[CollisionResult]) -> bool,
+    {
+        let mut collisions = Vec::new();
+        let mut iter = self.iter_from(initial);
+        for collision in iter.by_ref().take(max_steps) {
+            let collision = collision?;
+            let done = stop(&collision, &collisions);
+            collisions.push(collision);
+            if done {
+                let termination = iter.termination().unwrap_or(Termination::StopCondition);
+                return Ok(iter.trajectory(collisions, termination));
+            }
+        }
+        let termination = iter.termination().unwrap_or(Termination::MaxSteps);
+        Ok(iter.trajectory(collisions, termination))
+    }
+
+    /// Like [`BilliardMap::simulate`], but also stopping at time
+    /// `max_time`: only collisions at or before it are returned, and the
+    /// trajectory ends with [`Termination::MaxTime`] if the next one would
+    /// come later. Time is counted as in [`CollisionResult::time`]: flight
+    /// times at the actual speed, plus delays at the wall.
+    pub fn simulate_for(
+        &mut self,
+        initial: &BoundaryState,
+        max_steps: usize,
+        max_time: f64,
+    ) -> Result<Trajectory, DynamicsError> {
+        let mut collisions = Vec::new();
+        let mut iter = self.iter_from(initial);
+        for collision in iter.by_ref().take(max_steps) {
+            let collision = collision?;
+            if collision.time > max_time {
+                return Ok(iter.trajectory(collisions, Termination::MaxTime));
+            }
+            collisions.push(collision);
+        }
+        let termination = iter.termination().unwrap_or(Termination::MaxSteps);
+        Ok(iter.trajectory(collisions, termination))
+    }
+
+    /// Hand each collision of the trajectory from `initial` to `observer`
+    /// instead of collecting them, and report how it ended. At most
+    /// `max_steps` collisions are simulated; if a step fails, the
+    /// collisions before it have been observed already.
+    ///
+    /// Under the [`Specular`] law, trajectories bouncing around a full
+    /// circle clear of everything else are not stepped at all, but computed
+    /// in closed form by a [`CircleMap`].
+    pub fn observe<O: Observer + ?Sized>(
+        &mut self,
+        initial: &BoundaryState,
+        max_steps: usize,
+        observer: &mut O,
+    ) -> Result<Termination, DynamicsError> {
+        self.observe_for(initial, max_steps, f64::INFINITY, observer)
+    }
+
+    /// Like [`BilliardMap::observe`], but also stopping at time `max_time`,
+    /// as in [`BilliardMap::simulate_for`]: a collision after it is not
+    /// observed.
+    pub fn observe_for<O: Observer + ?Sized>(
+        &mut self,
+        initial: &BoundaryState,
+        max_steps: usize,
+        max_time: f64,
+        observer: &mut O,
+    ) -> Result<Termination, DynamicsError> {
+        let table = self.table;
+        let mut from = *initial;
+        let mut observe = |step: usize, collision: &CollisionResult| {
+            if collision.time > max_time {
+                return false;
+            }
+            observer.observe(table, step, &from, collision);
+            from = collision.outgoing_state();
+            true
+        };
+
+        let circle = self
+            .law
+            .is_specular()
+            .then(|| CircleMap::with_options(table, initial, self.epsilon, &self.options))
+            .flatten();
+        if let Some(circle) = circle {
+            for step in 0..max_steps {
+                if !observe(step, &circle.collision(step + 1)) {
+                    return Ok(Termination::MaxTime);
+                }
+            }
+            return Ok(Termination::MaxSteps);
+        }
+        let mut iter = self.iter_from(initial);
+        for (step, collision) in iter.by_ref().take(max_steps).enumerate() {
+            if !observe(step, &collision?) {
+                return Ok(Termination::MaxTime);
+            }
+        }
+        Ok(iter.termination().unwrap_or(Termination::MaxSteps))
+    }
+
+    /// Simulate a billiard trajectory launched from a world-space state.
+    ///
+    /// The position need not lie on the boundary: the first free flight is
+    /// followed with [`BilliardMap::step_from_world`], after which the
+    /// trajectory continues as in [`BilliardMap::simulate`]. The position is
+    /// assumed to lie inside the table.
+    pub fn simulate_from_world(
+        &mut self,
+        initial: &WorldState,
+        max_steps: usize,
+    ) -> Result<Trajectory, DynamicsError> {
+        self.simulate_from_world_for(initial, max_steps, f64::INFINITY)
+    }
+
+    /// Like [`BilliardMap::simulate_from_world`], but also stopping at time
+    /// `max_time`, as in [`BilliardMap::simulate_for`].
+    pub fn simulate_from_world_for(
+        &mut self,
+        initial: &WorldState,
+        max_steps: usize,
+        max_time: f64,
+    ) -> Result<Trajectory, DynamicsError> {
+        let mut monitor = self
+            .options
+            .invariants
+            .map(|check| InvariantMonitor::new(check, initial));
+        let end = |collisions, termination, monitor: &Option<InvariantMonitor>| {
+            Ok(Trajectory {
+                collisions,
+                termination,
+                invariants: monitor.as_ref().map(|m| *m.report()),
+            })
         };
+        if max_steps == 0 {
+            return end(Vec::new(), Termination::MaxSteps, &monitor);
+        }
 
-        current = BoundaryState {
-            component_index: collision.component_index,
-            s: collision.s,
-            theta: collision.theta,
+        let first = self.step_from_world(initial)?;
+        // Counted like the steps of the iterator below, reported on drop.
+        ProgressCounter::new(self.progress).tick();
+        let first = match first {
+            StepOutcome::Collision(c) => c,
+            StepOutcome::Escaped(escape) => {
+                return end(Vec::new(), Termination::Escaped(escape), &monitor);
+            }
+            StepOutcome::NoCollision => {
+                return end(Vec::new(), Termination::NoCollision, &monitor);
+            }
         };
+        if first.time > max_time {
+            return end(Vec::new(), Termination::MaxTime, &monitor);
+        }
+
+        let mut termination = self.options.stop_reason(&first);
+        if let Some(monitor) = &mut monitor {
+            let specular = self.law.is_specular();
+            let breached = monitor.observe(
+                self.table,
+                &self.options,
+                specular,
+                initial,
+                first.time,
+                &first,
+            );
+            if breached && monitor.check().abort {
+                termination = termination.or(Some(Termination::InvariantViolation));
+            }
+        }
+        if let Some(termination) = termination {
+            return end(vec![first], termination, &monitor);
+        }
+
+        let mut iter = self
+            .iter_from(&first.outgoing_state())
+            .continuing(first.path_length, first.departure_time());
+        // Keep checking against the launch, not the first bounce.
+        iter.monitor = monitor;
+        let mut collisions = vec![first];
+        for collision in iter.by_ref().take(max_steps - 1) {
+            let collision = collision?;
+            if collision.time > max_time {
+                return Ok(iter.trajectory(collisions, Termination::MaxTime));
+            }
+            collisions.push(collision);
+        }
+        let termination = iter.termination().unwrap_or(Termination::MaxSteps);
+        Ok(iter.trajectory(collisions, termination))
+    }
+}
+
+/// Iterator over the collisions of a trajectory; see
+/// [`BilliardMap::iter_from`].
+pub struct Collisions<'m, 'a, L> {
+    map: &'m mut BilliardMap<'a, L>,
+    current: BoundaryState,
+    path_length: f64,
+    time: f64,
+    termination: Option<Termination>,
+    failed: bool,
+    monitor: Option<InvariantMonitor>,
+
+    /// Collisions computed so far, to time the cancellation checks and
+    /// report progress.
+    counter: ProgressCounter<'a>,
+    gallery: Option<GalleryGuard>,
+
+    /// Whether the next step may skip along the arc of a whispering
+    /// gallery.
+    skip_arc: bool,
+}
+
+impl<L> Collisions<'_, '_, L> {
+    /// Count the running totals of the collisions on from an earlier run
+    /// that covered `path_length` and lasted `time`.
+    pub fn continuing(self, path_length: f64, time: f64) -> Self {
+        Collisions {
+            path_length,
+            time,
+            ..self
+        }
+    }
+
+    /// Why the trajectory ended, once it has; `None` while it may still
+    /// continue, and after an error.
+    pub fn termination(&self) -> Option<Termination> {
+        self.termination
+    }
 
-        collisions.push(collision);
+    /// What the invariant checks have found so far, if
+    /// [`StepOptions::invariants`] asked for them.
+    pub fn invariants(&self) -> Option<&InvariantReport> {
+        self.monitor.as_ref().map(InvariantMonitor::report)
     }
 
-    collisions
+    /// The trajectory of `collisions`, those of this run, ending with
+    /// `termination`.
+    fn trajectory(&self, collisions: Vec<CollisionResult>, termination: Termination) -> Trajectory {
+        Trajectory {
+            collisions,
+            termination,
+            invariants: self.invariants().copied(),
+        }
+    }
+}
+
+impl<L: ReflectionLaw> Iterator for Collisions<'_, '_, L> {
+    type Item = Result<CollisionResult, DynamicsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.termination.is_some() {
+            return None;
+        }
+        if self.counter.steps().is_multiple_of(CANCEL_CHECK_INTERVAL)
+            && self.map.cancelled.is_some_and(|cancelled| cancelled())
+        {
+            self.failed = true;
+            return Some(Err(DynamicsError::Cancelled));
+        }
+        self.counter.tick();
+        let table = self.map.table;
+        let skip = std::mem::take(&mut self.skip_arc)
+            .then(|| skip_along_arc(table, &self.current, self.map.epsilon))
+            .flatten();
+        let (outcome, last_flight) = match skip {
+            Some(skip) => (
+                Ok(StepOutcome::Collision(skip.collision)),
+                Some((skip.last_launch, skip.last_flight_time)),
+            ),
+            None => (self.map.step_at(&self.current, self.time), None),
+        };
+        match outcome {
+            Ok(StepOutcome::Collision(c)) => {
+                let c = c.after(self.path_length, self.time);
+                if let Some(monitor) = &mut self.monitor {
+                    // A skip is checked on its last chord.
+                    let (from, flight_time) = last_flight
+                        .unwrap_or_else(|| (self.current.to_world(table), c.time - self.time));
+                    let breached = monitor.observe(
+                        table,
+                        &self.map.options,
+                        self.map.law.is_specular(),
+                        &from,
+                        flight_time,
+                        &c,
+                    );
+                    if breached && monitor.check().abort {
+                        self.termination = Some(Termination::InvariantViolation);
+                    }
+                }
+                if let Some(guard) = &mut self.gallery
+                    && guard.observe(table, &c)
+                {
+                    let options = &self.map.options;
+                    match guard.gallery().action {
+                        GalleryAction::Terminate => {
+                            self.termination = Some(Termination::WhisperingGallery);
+                        }
+                        GalleryAction::Aggregate => {
+                            self.skip_arc = options.flight == FlightModel::Straight
+                                && options.restitution == 1.0
+                                && self.map.law.is_specular();
+                        }
+                    }
+                }
+                self.current = c.outgoing_state();
+                self.path_length = c.path_length;
+                self.time = c.departure_time();
+                self.termination = self.map.options.stop_reason(&c).or(self.termination);
+                Some(Ok(c))
+            }
+            Ok(StepOutcome::Escaped(escape)) => {
+                self.termination = Some(Termination::Escaped(escape));
+                None
+            }
+            Ok(StepOutcome::NoCollision) => {
+                self.termination = Some(Termination::NoCollision);
+                None
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<L: ReflectionLaw> FusedIterator for Collisions<'_, '_, L> {}
+
+/// Simulate a billiard trajectory by iterating boundary collisions, as
+/// configured by `config`.
+///
+/// Stops at [`SimulationConfig::max_time`] if one is set (see
+/// [`BilliardMap::simulate_for`]). Returns only the collisions; see
+/// [`BilliardMap::simulate`] for the reason the trajectory ended.
+///
+/// Trajectories bouncing around a full circle clear of everything else are
+/// not stepped at all, but computed in closed form by a [`CircleMap`].
+pub fn run_trajectory(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    config: &SimulationConfig,
+) -> Result<Vec<CollisionResult>, DynamicsError> {
+    if let Some(circle) = CircleMap::new(table, initial, config) {
+        return Ok(circle.collisions(config.max_steps(), config.max_time()));
+    }
+    let mut map = BilliardMap::from_config(table, config);
+    let trajectory = match config.max_time() {
+        Some(max_time) => map.simulate_for(initial, config.max_steps(), max_time)?,
+        None => map.simulate(initial, config.max_steps())?,
+    };
+    Ok(trajectory.collisions)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::next_collision_from_boundary_state;
-    use crate::dynamics::state::BoundaryState;
+    use super::{BilliardMap, DynamicsError, StepOptions};
+    use crate::dynamics::state::{BoundaryState, WorldState};
     use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
     use crate::geometry::primitives::Vec2;
     use crate::geometry::segments::{BoundarySegment, LineSegment};
+    use proptest::prelude::*;
 
     fn unit_square_table() -> BilliardTable {
         // Bottom: (0,0) -> (1,0)
@@ -167,14 +1315,12 @@ mod tests {
 
         // BoundaryState on bottom edge (component 0), at s=0.5,
         // theta = +π/2 → direction straight inward (upwards).
-        let bs0 = BoundaryState {
-            component_index: 0,
-            s: s0,
-            theta: std::f64::consts::FRAC_PI_2,
-        };
+        let bs0 = BoundaryState::new(0, s0, std::f64::consts::FRAC_PI_2);
 
         let epsilon = 1e-8;
-        let result = next_collision_from_boundary_state(&table, &bs0, epsilon);
+        let result = BilliardMap::new(&table, epsilon, StepOptions::default())
+            .collision(&bs0)
+            .unwrap();
 
         assert!(
             result.is_some(),
@@ -205,15 +1351,145 @@ mod tests {
             hp.y
         );
     }
-}
+    #[test]
+    fn degenerate_world_states_are_errors() {
+        let table = unit_square_table();
+        let options = StepOptions::default();
+        let at = |position, direction| WorldState {
+            position,
+            direction,
+            speed: 1.0,
+        };
 
-#[cfg(test)]
-mod trajectory_tests {
-    use super::run_trajectory;
-    use crate::dynamics::state::BoundaryState;
-    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
-    use crate::geometry::primitives::Vec2;
-    use crate::geometry::segments::{BoundarySegment, LineSegment};
+        let still = at(Vec2::new(0.5, 0.5), Vec2::new(0.0, 0.0));
+        assert_eq!(
+            BilliardMap::new(&table, 1e-8, options)
+                .collision_from_world(&still)
+                .err(),
+            Some(DynamicsError::DegenerateDirection)
+        );
+
+        let lost = at(Vec2::new(f64::NAN, 0.5), Vec2::new(1.0, 0.0));
+        assert_eq!(
+            BilliardMap::new(&table, 1e-8, options)
+                .collision_from_world(&lost)
+                .err(),
+            Some(DynamicsError::NumericalBreakdown)
+        );
+    }
+
+    #[test]
+    fn invalid_initial_states_are_errors() {
+        let table = unit_square_table();
+        let map_step = |state: BoundaryState| {
+            super::BilliardMap::new(&table, 1e-8, StepOptions::default()).step(&state)
+        };
+        assert_eq!(
+            map_step(BoundaryState::new(1, 0.5, 1.0)).unwrap_err(),
+            DynamicsError::UnknownComponent { component_index: 1 }
+        );
+        for state in [
+            BoundaryState::new(0, f64::NAN, 1.0),
+            BoundaryState::new(0, 0.5, f64::INFINITY),
+            BoundaryState {
+                speed: 0.0,
+                ..BoundaryState::new(0, 0.5, 1.0)
+            },
+        ] {
+            assert_eq!(state.check(&table), Err(DynamicsError::InvalidState));
+            assert_eq!(map_step(state).unwrap_err(), DynamicsError::InvalidState);
+        }
+        assert!(map_step(BoundaryState::new(0, 0.5, 1.0)).is_ok());
+    }
+
+    #[test]
+    fn invalid_states_are_errors_for_single_collisions() {
+        let table = unit_square_table();
+        let mut map = super::BilliardMap::new(&table, 1e-8, StepOptions::default());
+        assert_eq!(
+            map.collision(&BoundaryState::new(3, 0.5, 1.0)).unwrap_err(),
+            DynamicsError::UnknownComponent { component_index: 3 }
+        );
+        assert_eq!(
+            map.collision(&BoundaryState::new(0, f64::NAN, 1.0))
+                .unwrap_err(),
+            DynamicsError::InvalidState
+        );
+        assert!(map.collision(&BoundaryState::new(0, 0.5, 1.0)).is_ok());
+    }
+
+    /// Closed polygons with random, possibly self-intersecting vertices
+    /// and random boundary conditions.
+    fn polygon_spec() -> impl Strategy<Value = crate::geometry::table_spec::TableSpec> {
+        use crate::geometry::segments::{BoundaryCondition, SegmentLabel};
+        use crate::geometry::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+        prop::collection::vec(((-2.0..2.0f64, -2.0..2.0f64), 0..8usize), 3..8).prop_map(
+            |vertices| {
+                let n = vertices.len();
+                let segments = (0..n)
+                    .map(|i| {
+                        let ((x0, y0), condition) = vertices[i];
+                        let ((x1, y1), _) = vertices[(i + 1) % n];
+                        SegmentSpec::Line {
+                            start: Vec2::new(x0, y0),
+                            end: Vec2::new(x1, y1),
+                            condition: match condition {
+                                0 => BoundaryCondition::Absorb,
+                                1 => BoundaryCondition::NoSlipRotate,
+                                2 => BoundaryCondition::PeriodicPairedWith((i + n / 2) % n),
+                                _ => BoundaryCondition::Reflect,
+                            },
+                            label: SegmentLabel::default(),
+                        }
+                    })
+                    .collect();
+                TableSpec {
+                    outer: BoundarySpec {
+                        name: "polygon".to_string(),
+                        segments,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            },
+        )
+    }
+
+    proptest! {
+        /// Whatever table gets past validation, simulating on it either
+        /// works or fails with an error; it never panics.
+        #[test]
+        fn simulation_on_any_valid_polygon_does_not_panic(
+            spec in polygon_spec(),
+            component_index in 0..2usize,
+            s in -10.0..10.0f64,
+            theta in -4.0..4.0f64,
+        ) {
+            let Ok(table) = spec.try_to_billiard_table() else {
+                return Ok(());
+            };
+            let initial = BoundaryState::new(component_index, s, theta);
+            let result = BilliardMap::new(&table, 1e-8, StepOptions::default()).simulate(&initial, 200);
+            if component_index > 0 {
+                prop_assert_eq!(
+                    result.unwrap_err(),
+                    DynamicsError::UnknownComponent { component_index }
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod trajectory_tests {
+    use super::{BilliardMap, StepOptions, Termination, run_trajectory};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::state::{BoundaryState, WorldState};
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundarySegment, LineSegment};
+    use crate::geometry::standard_tables::circle;
 
     fn unit_square_table() -> BilliardTable {
         let bottom =
@@ -238,14 +1514,15 @@ mod trajectory_tests {
         // Start on the bottom edge at x = 0.5, pointing straight up.
         // bottom edge tangent = (1,0), inward = (0,1),
         // theta = +π/2 => direction (0,1).
-        let initial = BoundaryState {
-            component_index: 0,
-            s: 0.5,
-            theta: std::f64::consts::FRAC_PI_2,
-        };
+        let initial = BoundaryState::new(0, 0.5, std::f64::consts::FRAC_PI_2);
 
         let epsilon = 1e-8;
-        let traj = run_trajectory(&table, &initial, 4, epsilon);
+        let config = SimulationConfig::builder()
+            .max_steps(4)
+            .epsilon(epsilon)
+            .build()
+            .unwrap();
+        let traj = run_trajectory(&table, &initial, &config).unwrap();
 
         // We asked for 4 steps; there should be exactly 4 collisions in a closed square.
         assert_eq!(traj.len(), 4, "Expected 4 collisions, got {}", traj.len());
@@ -275,4 +1552,1006 @@ mod trajectory_tests {
         assert!((c4.hit_point.x - 0.5).abs() < 1e-10);
         assert!((c4.hit_point.y - 0.0).abs() < 1e-10);
     }
+
+    #[test]
+    fn launch_from_the_interior() {
+        let table = unit_square_table();
+
+        // From the center, heading right along y = 0.5.
+        let launch = WorldState {
+            position: Vec2::new(0.5, 0.5),
+            direction: Vec2::new(1.0, 0.0),
+            speed: 1.0,
+        };
+        let traj = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate_from_world(&launch, 3)
+            .unwrap();
+        assert_eq!(traj.termination, Termination::MaxSteps);
+
+        let hits: Vec<(usize, f64)> = traj
+            .collisions
+            .iter()
+            .map(|c| (c.segment_index, c.hit_point.x))
+            .collect();
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].0, 1);
+        assert_eq!(hits[1].0, 3);
+        assert_eq!(hits[2].0, 1);
+        assert!((hits[0].1 - 1.0).abs() < 1e-10 && hits[1].1.abs() < 1e-10);
+        for c in &traj.collisions {
+            assert!((c.hit_point.y - 0.5).abs() < 1e-10);
+        }
+
+        let none = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate_from_world(&launch, 0)
+            .unwrap();
+        assert!(none.collisions.is_empty());
+    }
+
+    #[test]
+    fn grazing_orbits_creep_along_the_circle() {
+        // Chords of 2e-9, well under the epsilon of 1e-8.
+        let table = circle(1.0).to_billiard_table();
+        let theta = 1e-9;
+        let initial = BoundaryState::new(0, 1.0, theta);
+        let config = SimulationConfig::builder()
+            .max_steps(100)
+            .epsilon(1e-8)
+            .build()
+            .unwrap();
+
+        let traj = run_trajectory(&table, &initial, &config).unwrap();
+        assert_eq!(traj.len(), 100);
+        for (k, c) in traj.iter().enumerate() {
+            let expected = 1.0 + 2.0 * theta * (k + 1) as f64;
+            assert!((c.s - expected).abs() < 1e-12, "bounce {}", k);
+            assert!((c.theta - theta).abs() < 1e-12);
+        }
+    }
+}
+
+#[cfg(test)]
+mod obstacle_tests {
+    use super::{BilliardMap, StepOptions, run_trajectory};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::state::{BoundaryState, WorldState};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::standard_tables::sinai;
+
+    #[test]
+    fn sinai_outgoing_angles_point_into_domain() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, std::f64::consts::FRAC_PI_3);
+
+        let config = SimulationConfig::builder()
+            .max_steps(200)
+            .epsilon(1e-8)
+            .build()
+            .unwrap();
+
+        let traj = run_trajectory(&table, &initial, &config).unwrap();
+        assert_eq!(traj.len(), 200);
+        assert!(traj.iter().any(|c| c.component_index == 1));
+
+        for c in &traj {
+            assert!(
+                c.theta > 0.0 && c.theta < std::f64::consts::PI,
+                "Outgoing theta {} on component {} does not point into the domain",
+                c.theta,
+                c.component_index
+            );
+
+            // The hit point must lie outside the disk or on its rim.
+            let r = ((c.hit_point.x - 0.5).powi(2) + (c.hit_point.y - 0.5).powi(2)).sqrt();
+            assert!(r > 0.2 - 1e-9, "Hit point inside the obstacle: r = {}", r);
+        }
+    }
+
+    #[test]
+    fn tangential_hits_on_the_disk_are_reported_once() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+
+        // Along y = 0.7, touching the top of the disk.
+        let launch = WorldState {
+            position: Vec2::new(0.1, 0.7),
+            direction: Vec2::new(1.0, 0.0),
+            speed: 1.0,
+        };
+        let traj = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate_from_world(&launch, 2)
+            .unwrap();
+
+        let [touch, wall] = &traj.collisions[..] else {
+            panic!("expected two collisions, got {:?}", traj.collisions);
+        };
+        assert!(touch.tangent && touch.component_index == 1);
+        assert!((touch.hit_point - Vec2::new(0.5, 0.7)).length() < 1e-12);
+        assert!(!wall.tangent && (wall.component_index, wall.segment_index) == (0, 1));
+        assert!((wall.hit_point.y - 0.7).abs() < 1e-12);
+    }
+}
+
+#[cfg(test)]
+mod corner_tests {
+    use super::{BilliardMap, CornerPolicy, StepOptions};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundaryCondition, SegmentLabel};
+    use crate::geometry::standard_tables::sinai;
+    use crate::geometry::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+    fn unit_square_spec() -> TableSpec {
+        let corners = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ];
+        TableSpec {
+            outer: BoundarySpec {
+                name: "outer".to_string(),
+                segments: (0..4)
+                    .map(|i| SegmentSpec::Line {
+                        start: corners[i],
+                        end: corners[(i + 1) % 4],
+                        condition: BoundaryCondition::Reflect,
+                        label: SegmentLabel::default(),
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// From the bottom midpoint, aim exactly at the top-right corner (1, 1).
+    fn aimed_at_corner() -> BoundaryState {
+        BoundaryState::new(0, 0.5, 1.0_f64.atan2(0.5))
+    }
+
+    fn options(corner_policy: CornerPolicy) -> StepOptions {
+        StepOptions {
+            corner_policy,
+            ..StepOptions::default()
+        }
+    }
+
+    #[test]
+    fn report_policy_flags_corner() {
+        let table = unit_square_spec().to_billiard_table();
+        let traj = BilliardMap::new(&table, 1e-8, options(CornerPolicy::Report))
+            .simulate(&aimed_at_corner(), 1)
+            .map(|trajectory| trajectory.collisions)
+            .unwrap();
+
+        assert_eq!(traj.len(), 1);
+        let corner = traj[0]
+            .corner
+            .expect("Expected the first hit to be a corner");
+        assert_eq!((corner.before, corner.after), (1, 2));
+        assert!((corner.point.x - 1.0).abs() < 1e-12);
+        assert!((corner.point.y - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn terminate_policy_stops_at_corner() {
+        let table = unit_square_spec().to_billiard_table();
+        let traj = BilliardMap::new(&table, 1e-8, options(CornerPolicy::Terminate))
+            .simulate(&aimed_at_corner(), 10)
+            .map(|trajectory| trajectory.collisions)
+            .unwrap();
+
+        assert_eq!(traj.len(), 1);
+        assert!(traj[0].corner.is_some());
+    }
+
+    #[test]
+    fn bisector_policy_reflects_off_diagonal() {
+        let table = unit_square_spec().to_billiard_table();
+        let traj = BilliardMap::new(&table, 1e-8, options(CornerPolicy::ReflectBisector))
+            .simulate(&aimed_at_corner(), 2)
+            .map(|trajectory| trajectory.collisions)
+            .unwrap();
+
+        // The corner acts like a mirror along the anti-diagonal: incoming
+        // (1, 2)/√5 leaves as (−2, −1)/√5 and next hits the left edge.
+        assert_eq!(traj.len(), 2);
+        assert!(traj[1].corner.is_none());
+        let next = traj[1].hit_point;
+        assert!(next.x.abs() < 1e-8, "x = {}", next.x);
+        assert!((next.y - 0.5).abs() < 1e-8, "y = {}", next.y);
+    }
+
+    #[test]
+    fn smooth_junctions_are_not_corners() {
+        // A full circle has a single, smooth junction at s = 0.
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let disk = &table.obstacles[0];
+        assert!(disk.corner_near(0, 0.0, 1e-9).is_none());
+
+        // The square's vertices are genuine corners.
+        assert!(table.outer.corner_near(0, 1.0, 1e-9).is_some());
+    }
+}
+
+#[cfg(test)]
+mod escape_tests {
+    use super::{BilliardMap, Escape, StepOptions, Termination};
+    use crate::dynamics::state::{BoundaryState, WorldState};
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use crate::geometry::primitives::{BoundingBox, Vec2};
+    use crate::geometry::segments::{BoundarySegment, LineSegment};
+    use crate::geometry::standard_tables::sinai;
+
+    /// A single wall from (0, 0) to (1, 0): every trajectory leaves it.
+    fn single_wall_table() -> BilliardTable {
+        let wall =
+            BoundarySegment::Line(LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)));
+        BilliardTable::new(BoundaryComponent::new("wall", vec![wall]), Vec::new())
+    }
+
+    fn escape_options() -> StepOptions {
+        StepOptions {
+            escape_box: Some(BoundingBox::new(
+                Vec2::new(-10.0, -10.0),
+                Vec2::new(10.0, 10.0),
+            )),
+            ..StepOptions::default()
+        }
+    }
+
+    #[test]
+    fn open_table_reports_escape() {
+        let table = single_wall_table();
+        let initial = BoundaryState::new(0, 0.5, std::f64::consts::FRAC_PI_3);
+
+        let traj = BilliardMap::new(&table, 1e-8, escape_options())
+            .simulate(&initial, 10)
+            .unwrap();
+        assert!(traj.collisions.is_empty());
+
+        let Termination::Escaped(escape) = traj.termination else {
+            panic!("Expected an escape, got {:?}", traj.termination);
+        };
+        let expected_x = 0.5 + 10.0 / 3.0_f64.sqrt();
+        assert!((escape.position.x - expected_x).abs() < 1e-10);
+        assert!((escape.position.y - 10.0).abs() < 1e-10);
+        assert!((escape.direction.x - 0.5).abs() < 1e-12);
+        assert!((escape.direction.y - 3.0_f64.sqrt() / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn open_table_without_escape_box_ends_with_no_collision() {
+        let table = single_wall_table();
+        let initial = BoundaryState::new(0, 0.5, 1.0);
+
+        let traj = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 10)
+            .unwrap();
+        assert!(traj.collisions.is_empty());
+        assert_eq!(traj.termination, Termination::NoCollision);
+    }
+
+    #[test]
+    fn interior_launch_can_escape_before_the_first_bounce() {
+        let table = single_wall_table();
+        let launch = WorldState {
+            position: Vec2::new(0.5, 1.0),
+            direction: Vec2::new(0.0, 1.0),
+            speed: 1.0,
+        };
+
+        let traj = BilliardMap::new(&table, 1e-8, escape_options())
+            .simulate_from_world(&launch, 10)
+            .unwrap();
+        assert!(traj.collisions.is_empty());
+        assert_eq!(
+            traj.termination,
+            Termination::Escaped(Escape {
+                position: Vec2::new(0.5, 10.0),
+                direction: Vec2::new(0.0, 1.0),
+            })
+        );
+    }
+
+    #[test]
+    fn closed_table_never_escapes() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+
+        let traj = BilliardMap::new(&table, 1e-8, escape_options())
+            .simulate(&initial, 100)
+            .unwrap();
+        assert_eq!(traj.collisions.len(), 100);
+        assert_eq!(traj.termination, Termination::MaxSteps);
+    }
+}
+
+#[cfg(test)]
+mod boundary_condition_tests {
+    use super::{BilliardMap, StepOptions, Termination};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{
+        BoundaryCondition, BoundarySegment, LineSegment, SegmentLabel,
+    };
+
+    /// Unit square whose four edges (bottom, right, top, left) carry the
+    /// given boundary conditions.
+    fn unit_square(conditions: [BoundaryCondition; 4]) -> BilliardTable {
+        let corners = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ];
+        let segments = (0..4)
+            .map(|i| {
+                BoundarySegment::Line(LineSegment::new(corners[i], corners[(i + 1) % 4]))
+                    .with_condition(conditions[i])
+            })
+            .collect();
+        BilliardTable::new(BoundaryComponent::new("square", segments), Vec::new())
+    }
+
+    fn from_bottom(s: f64, theta: f64) -> BoundaryState {
+        BoundaryState::new(0, s, theta)
+    }
+
+    #[test]
+    fn periodic_square_is_a_torus() {
+        use BoundaryCondition::PeriodicPairedWith;
+        let table = unit_square([
+            PeriodicPairedWith(2),
+            PeriodicPairedWith(3),
+            PeriodicPairedWith(0),
+            PeriodicPairedWith(1),
+        ]);
+
+        // Heading up and to the right with slope 2: crosses the top at
+        // x = 0.75, re-enters at the bottom, then crosses the right edge
+        // at y = 0.5 and re-enters on the left.
+        let initial = from_bottom(0.25, 2.0_f64.atan());
+        let traj = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 2)
+            .unwrap();
+        assert_eq!(traj.termination, Termination::MaxSteps);
+
+        let top = traj.collisions[0];
+        assert!((top.hit_point.x - 0.75).abs() < 1e-10);
+        assert!((top.hit_point.y - 1.0).abs() < 1e-10);
+        assert_eq!(top.segment_index, 0);
+        assert!((top.s - 0.75).abs() < 1e-10);
+        assert!((top.theta - 2.0_f64.atan()).abs() < 1e-10);
+
+        let right = traj.collisions[1];
+        assert!((right.hit_point.x - 1.0).abs() < 1e-10);
+        assert!((right.hit_point.y - 0.5).abs() < 1e-10);
+        assert_eq!(right.segment_index, 3);
+        // Left edge runs (0, 1) -> (0, 0), so y = 0.5 is halfway along it.
+        assert!((right.s - 3.5).abs() < 1e-10);
+    }
+
+    #[test]
+    fn absorbing_segment_ends_trajectory() {
+        use BoundaryCondition::{Absorb, Reflect};
+        let table = unit_square([Reflect, Reflect, Absorb, Reflect]);
+
+        let traj = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&from_bottom(0.5, std::f64::consts::FRAC_PI_2), 10)
+            .unwrap();
+        assert_eq!(traj.termination, Termination::Absorbed);
+        assert_eq!(traj.collisions.len(), 1);
+        assert!(traj.collisions[0].absorbed);
+    }
+
+    #[test]
+    fn no_slip_segment_sends_particle_back() {
+        use BoundaryCondition::{NoSlipRotate, Reflect};
+        let table = unit_square([Reflect, Reflect, NoSlipRotate, Reflect]);
+
+        let traj = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&from_bottom(0.3, 1.2), 2)
+            .unwrap();
+        assert_eq!(traj.collisions[0].segment_index, 2);
+        let back = traj.collisions[1].hit_point;
+        assert!((back.x - 0.3).abs() < 1e-10, "x = {}", back.x);
+        assert!(back.y.abs() < 1e-10);
+    }
+
+    #[test]
+    fn collisions_name_the_segment_they_hit() {
+        let mut table = unit_square([BoundaryCondition::Reflect; 4]);
+        table.outer.segments[2] = table.outer.segments[2].clone().with_label(SegmentLabel {
+            id: Some(31),
+            name: Some("top cushion".into()),
+        });
+
+        let traj = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&from_bottom(0.5, std::f64::consts::FRAC_PI_2), 2)
+            .unwrap();
+        let [top, bottom] = [traj.collisions[0], traj.collisions[1]];
+        assert_eq!(top.segment_id, Some(31));
+        assert_eq!(top.segment_name(&table), Some("top cushion"));
+        assert_eq!(bottom.segment_id, None);
+        assert_eq!(bottom.segment_name(&table), None);
+    }
+}
+
+#[cfg(test)]
+mod restitution_tests {
+    use super::{BilliardMap, StepOptions};
+    use crate::dynamics::reflection::{Impact, ReflectionLaw, Specular};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::sinai;
+
+    fn damped(restitution: f64) -> StepOptions {
+        StepOptions {
+            restitution,
+            ..StepOptions::default()
+        }
+    }
+
+    #[test]
+    fn head_on_bounces_lose_speed_geometrically() {
+        // Vertical orbit at x = 0.1 misses the central disk.
+        let table = sinai(1.0, 0.05).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.1, std::f64::consts::FRAC_PI_2);
+
+        let traj = BilliardMap::new(&table, 1e-8, damped(0.5))
+            .simulate(&initial, 4)
+            .map(|trajectory| trajectory.collisions)
+            .unwrap();
+        for (i, c) in traj.iter().enumerate() {
+            let expected = 0.5_f64.powi(i as i32 + 1);
+            assert!((c.speed - expected).abs() < 1e-12, "speed {}", c.speed);
+            assert!((c.theta - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn oblique_bounce_flattens_outgoing_angle() {
+        let table = sinai(1.0, 0.05).to_billiard_table();
+        let theta = std::f64::consts::FRAC_PI_4;
+        let initial = BoundaryState::new(0, 0.1, theta);
+
+        let traj = BilliardMap::new(&table, 1e-8, damped(0.5))
+            .simulate(&initial, 1)
+            .map(|trajectory| trajectory.collisions)
+            .unwrap();
+        let c = traj[0];
+
+        // Tangential component kept, normal component halved.
+        let (v_t, v_n) = (theta.cos(), 0.5 * theta.sin());
+        assert!((c.speed - v_t.hypot(v_n)).abs() < 1e-12);
+        assert!((c.theta - v_n.atan2(v_t)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn elastic_bounces_preserve_speed() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState {
+            speed: 2.5,
+            ..BoundaryState::new(0, 0.3, 1.0)
+        };
+
+        let traj = BilliardMap::new(&table, 1e-8, damped(1.0))
+            .simulate(&initial, 50)
+            .map(|trajectory| trajectory.collisions)
+            .unwrap();
+        assert!(traj.iter().all(|c| (c.speed - 2.5).abs() < 1e-9));
+
+        // Laws are told the incoming speed, and answer relative to it.
+        let mut speeds = Vec::new();
+        let mut law = |impact: &Impact| {
+            speeds.push(impact.speed);
+            Specular.reflect(impact)
+        };
+        let options = damped(0.5);
+        let traj = BilliardMap::new(&table, 1e-8, options)
+            .with_law(&mut law)
+            .simulate(&initial, 5)
+            .unwrap();
+        assert_eq!(speeds[0], 2.5);
+        for (c, next) in traj.collisions.iter().zip(&speeds[1..]) {
+            assert!((c.speed - next).abs() < 1e-12);
+        }
+    }
+}
+
+#[cfg(test)]
+mod gravity_tests {
+    use super::{BilliardMap, FlightModel, StepOptions};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::sinai;
+
+    fn gravity(g: f64) -> StepOptions {
+        StepOptions {
+            flight: FlightModel::Gravity { g },
+            ..StepOptions::default()
+        }
+    }
+
+    #[test]
+    fn vertical_throw_falls_back_to_launch_point() {
+        // Apex at v²/2g = 0.5, below the top wall and away from the disk.
+        let table = sinai(1.0, 0.05).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.1, std::f64::consts::FRAC_PI_2);
+
+        let traj = BilliardMap::new(&table, 1e-8, gravity(1.0))
+            .simulate(&initial, 5)
+            .map(|trajectory| trajectory.collisions)
+            .unwrap();
+        assert_eq!(traj.len(), 5);
+        for c in &traj {
+            assert!((c.hit_point.x - 0.1).abs() < 1e-9);
+            assert!(c.hit_point.y.abs() < 1e-9);
+            assert!((c.speed - 1.0).abs() < 1e-9);
+            assert!((c.theta - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn elastic_bounces_conserve_energy() {
+        let g = 3.0;
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState {
+            speed: 2.0,
+            ..BoundaryState::new(0, 0.3, 1.0)
+        };
+
+        let traj = BilliardMap::new(&table, 1e-8, gravity(g))
+            .simulate(&initial, 100)
+            .map(|trajectory| trajectory.collisions)
+            .unwrap();
+        assert_eq!(traj.len(), 100);
+
+        // Starts on the bottom wall, at height 0.
+        let energy = 0.5 * 2.0 * 2.0;
+        for c in &traj {
+            let e = 0.5 * c.speed * c.speed + g * c.hit_point.y;
+            assert!((e - energy).abs() < 1e-8, "energy {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod reflection_law_tests {
+    use super::{BilliardMap, StepOptions};
+    use crate::dynamics::reflection::{Impact, Lambertian, Reflection, Specular, Sticky, Thermal};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::segments::BoundaryCondition;
+    use crate::geometry::standard_tables::{fermi_ulam, sinai};
+    use crate::geometry::table_spec::SegmentSpec;
+
+    #[test]
+    fn specular_law_matches_the_default_simulation() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let options = StepOptions::default();
+
+        let plain = BilliardMap::new(&table, 1e-8, options)
+            .simulate(&initial, 50)
+            .unwrap();
+        let with_law = BilliardMap::new(&table, 1e-8, options)
+            .with_law(Specular)
+            .simulate(&initial, 50)
+            .unwrap();
+        for (a, b) in plain.collisions.iter().zip(&with_law.collisions) {
+            assert_eq!((a.s, a.theta, a.delay), (b.s, b.theta, b.delay));
+        }
+    }
+
+    #[test]
+    fn stochastic_laws_reuse_the_intersection_machinery() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let options = StepOptions::default();
+        let run = |seed| {
+            BilliardMap::new(&table, 1e-8, options)
+                .with_law(Lambertian::new(seed))
+                .simulate(&initial, 200)
+                .unwrap()
+                .collisions
+        };
+
+        let collisions = run(11);
+        assert_eq!(collisions.len(), 200);
+        assert!(
+            collisions
+                .iter()
+                .all(|c| c.theta > 0.0 && c.theta < std::f64::consts::PI)
+        );
+        let again = run(11);
+        assert!(collisions.iter().zip(&again).all(|(a, b)| a.s == b.s));
+
+        let sticky = BilliardMap::new(&table, 1e-8, options)
+            .with_law(Sticky::new(0.5, 1))
+            .simulate(&initial, 20)
+            .unwrap();
+        assert!(sticky.collisions.iter().all(|c| c.delay >= 0.0));
+        assert!(sticky.collisions.iter().any(|c| c.delay > 0.0));
+
+        // A closure that sends the particle straight back along the normal.
+        let mut bounce_back = |impact: &Impact| Reflection::immediate(impact.normal);
+        let traj = BilliardMap::new(&table, 1e-8, options)
+            .with_law(&mut bounce_back)
+            .simulate(&initial, 4)
+            .unwrap();
+        for c in &traj.collisions {
+            assert!((c.theta - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn heated_walls_change_the_speed_only_where_they_are() {
+        let mut spec = sinai(1.0, 0.2);
+        for segment in &mut spec.obstacles[0].segments {
+            let (SegmentSpec::Line { condition, .. } | SegmentSpec::CircularArc { condition, .. }) =
+                segment;
+            *condition = BoundaryCondition::Thermal { temperature: 4.0 };
+        }
+        let table = spec.to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let collisions = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .with_law(Thermal::new(2))
+            .simulate(&initial, 400)
+            .unwrap()
+            .collisions;
+
+        let mut speed = initial.speed;
+        let mut heated = 0;
+        for c in &collisions {
+            if c.component_index == 1 {
+                heated += 1;
+            } else {
+                assert!((c.speed - speed).abs() < 1e-9);
+            }
+            speed = c.speed;
+        }
+        assert!(heated > 20);
+        // Speeds settle around the wall's, 3T on average in v².
+        let mean_sq = collisions.iter().map(|c| c.speed * c.speed).sum::<f64>() / 400.0;
+        assert!(mean_sq > 4.0 && mean_sq < 20.0, "⟨v²⟩ = {}", mean_sq);
+    }
+
+    #[test]
+    fn moving_walls_follow_the_clock_of_the_run() {
+        let (velocity, frequency) = (0.1, 2.0);
+        let table = fermi_ulam(1.0, 1.0, velocity, frequency).to_billiard_table();
+        // Straight up from the floor: the top wall is hit at t = 1.
+        let initial = BoundaryState::new(0, 0.5, std::f64::consts::FRAC_PI_2);
+        let options = StepOptions::default();
+        let mut map = BilliardMap::new(&table, 1e-9, options);
+
+        let collisions = map.simulate(&initial, 10).unwrap().collisions;
+        let kicked = 1.0 + 2.0 * velocity * frequency.cos();
+        assert!((collisions[0].speed - kicked).abs() < 1e-12);
+        assert!((collisions[1].speed - kicked).abs() < 1e-12);
+        let top = collisions[2].time;
+        assert!((top - (1.0 + 2.0 / kicked)).abs() < 1e-12);
+        let kicked = kicked + 2.0 * velocity * (frequency * top).cos();
+        assert!((collisions[2].speed - kicked).abs() < 1e-12);
+
+        // A run carried on from the fifth bounce keeps the wall's phase.
+        let fifth = &collisions[4];
+        let rest = BilliardMap::new(&table, 1e-9, options)
+            .starting_at(fifth.departure_time())
+            .simulate(&fifth.outgoing_state(), 5)
+            .unwrap()
+            .collisions;
+        for (a, b) in collisions[5..].iter().zip(&rest) {
+            assert!((a.speed - b.speed).abs() < 1e-12);
+            assert!((a.time - fifth.departure_time() - b.time).abs() < 1e-9);
+        }
+    }
+}
+
+#[cfg(test)]
+mod billiard_map_tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{
+        BilliardMap, CANCEL_CHECK_INTERVAL, DynamicsError, StepOptions, Termination, run_trajectory,
+    };
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::reflection::NoSlip;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundarySegment, LineSegment};
+    use crate::geometry::standard_tables::sinai;
+
+    #[test]
+    fn iterator_matches_simulate() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let options = StepOptions::default();
+
+        let traj = BilliardMap::new(&table, 1e-8, options)
+            .simulate(&initial, 50)
+            .unwrap();
+        let mut map = BilliardMap::new(&table, 1e-8, options);
+        let lazy: Vec<_> = map
+            .iter_from(&initial)
+            .take(50)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lazy.len(), traj.collisions.len());
+        for (a, b) in lazy.iter().zip(&traj.collisions) {
+            assert_eq!((a.s, a.theta), (b.s, b.theta));
+        }
+
+        // The map can be reused; a longer run extends the shorter one.
+        let longer = map.simulate(&initial, 80).unwrap();
+        assert_eq!(longer.termination, Termination::MaxSteps);
+        assert_eq!(longer.collisions[49].s, traj.collisions[49].s);
+    }
+
+    #[test]
+    fn iterator_ends_with_the_termination() {
+        let wall =
+            BoundarySegment::Line(LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)));
+        let table = BilliardTable::new(BoundaryComponent::new("wall", vec![wall]), Vec::new());
+        let mut map = BilliardMap::new(&table, 1e-8, StepOptions::default());
+
+        let mut iter = map.iter_from(&BoundaryState::new(0, 0.5, 1.0));
+        assert_eq!(iter.termination(), None);
+        assert!(iter.next().is_none());
+        assert_eq!(iter.termination(), Some(Termination::NoCollision));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn custom_law_is_used_by_every_step() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let mut map = BilliardMap::new(&table, 1e-8, StepOptions::default()).with_law(NoSlip);
+
+        // No-slip reflection sends the particle back the way it came, so the
+        // orbit alternates between two points.
+        let collisions = map.simulate(&initial, 4).unwrap().collisions;
+        assert!((collisions[0].s - collisions[2].s).abs() < 1e-9);
+        assert!((collisions[1].s - collisions[3].s).abs() < 1e-9);
+    }
+
+    #[test]
+    fn path_length_and_time_accumulate() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let mut initial = BoundaryState::new(0, 0.3, 1.0);
+        initial.speed = 2.0;
+        let mut map = BilliardMap::new(&table, 1e-8, StepOptions::default());
+        let full = map.simulate(&initial, 200).unwrap().collisions;
+
+        let mut length = 0.0;
+        for c in &full {
+            length += c.chord;
+            assert!((c.path_length - length).abs() < 1e-9);
+            assert!((c.time - length / 2.0).abs() < 1e-9);
+        }
+
+        // Stopping at a time keeps exactly the collisions up to it.
+        let max_time = full[119].time + 1e-6;
+        let timed = map.simulate_for(&initial, 200, max_time).unwrap();
+        assert_eq!(timed.termination, Termination::MaxTime);
+        assert_eq!(timed.collisions.len(), 120);
+        let world = initial.to_world(&table);
+        let timed = map.simulate_from_world_for(&world, 200, max_time).unwrap();
+        assert_eq!(timed.termination, Termination::MaxTime);
+        assert_eq!(timed.collisions.len(), 120);
+        let config = SimulationConfig::builder()
+            .max_steps(200)
+            .max_time(max_time)
+            .build()
+            .unwrap();
+        assert_eq!(
+            run_trajectory(&table, &initial, &config).unwrap().len(),
+            120
+        );
+    }
+
+    #[test]
+    fn stop_conditions_end_the_run_at_the_first_match() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let config = SimulationConfig::builder().max_steps(500).build().unwrap();
+        let full = BilliardMap::new(&table, 1e-8, StepOptions::default())
+            .simulate(&initial, 500)
+            .unwrap()
+            .collisions;
+
+        // On the latest collision alone: the first bounce off the scatterer.
+        let hit = BilliardMap::from_config(&table, &config)
+            .simulate_until(&initial, config.max_steps(), |c, _| c.component_index == 1)
+            .unwrap();
+        let first = full.iter().position(|c| c.component_index == 1).unwrap();
+        assert_eq!(hit.termination, Termination::StopCondition);
+        assert_eq!(hit.collisions.len(), first + 1);
+
+        // On the history: stop once the path length passes 10.
+        let long = BilliardMap::from_config(&table, &config)
+            .simulate_until(&initial, config.max_steps(), |c, history| {
+                c.chord + history.iter().map(|h| h.chord).sum::<f64>() > 10.0
+            })
+            .unwrap();
+        let length: f64 = long.collisions.iter().map(|c| c.chord).sum();
+        assert!(length > 10.0 && length - long.collisions.last().unwrap().chord <= 10.0);
+
+        // A condition that never holds runs to the step limit.
+        let never = BilliardMap::from_config(&table, &config)
+            .simulate_until(&initial, config.max_steps(), |c, _| c.theta < 0.0)
+            .unwrap();
+        assert_eq!(never.termination, Termination::MaxSteps);
+        assert_eq!(never.collisions.len(), 500);
+    }
+
+    #[test]
+    fn cancelled_runs_stop_at_the_next_check() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let checks = AtomicUsize::new(0);
+        // Cancelled from the third check on.
+        let cancelled = || checks.fetch_add(1, Ordering::Relaxed) >= 2;
+        let mut map =
+            BilliardMap::new(&table, 1e-8, StepOptions::default()).cancelled_by(&cancelled);
+
+        let collisions: Vec<_> = map.iter_from(&initial).take(10_000).collect();
+        assert_eq!(collisions.len(), 2 * CANCEL_CHECK_INTERVAL + 1);
+        assert!(
+            collisions[..2 * CANCEL_CHECK_INTERVAL]
+                .iter()
+                .all(Result::is_ok)
+        );
+        assert!(matches!(
+            collisions.last(),
+            Some(Err(DynamicsError::Cancelled))
+        ));
+
+        // Already cancelled: nothing is computed.
+        assert_eq!(
+            map.simulate(&initial, 10).unwrap_err(),
+            DynamicsError::Cancelled
+        );
+        let world = initial.to_world(&table);
+        assert_eq!(
+            map.simulate_from_world(&world, 10).unwrap_err(),
+            DynamicsError::Cancelled
+        );
+    }
+
+    #[test]
+    fn progress_adds_up_to_the_steps_taken() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let (calls, steps) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let progress = |n: usize| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            steps.fetch_add(n, Ordering::Relaxed);
+        };
+        let mut map =
+            BilliardMap::new(&table, 1e-8, StepOptions::default()).reporting_to(&progress);
+
+        map.simulate(&initial, 1000).unwrap();
+        assert_eq!(steps.load(Ordering::Relaxed), 1000);
+        assert_eq!(
+            calls.load(Ordering::Relaxed),
+            1000_usize.div_ceil(CANCEL_CHECK_INTERVAL)
+        );
+
+        // Runs cut short by their consumer are counted when dropped.
+        assert_eq!(map.iter_from(&initial).take(10).count(), 10);
+        assert_eq!(steps.load(Ordering::Relaxed), 1010);
+        map.simulate_from_world(&initial.to_world(&table), 5)
+            .unwrap();
+        assert_eq!(steps.load(Ordering::Relaxed), 1015);
+    }
+}
+
+#[cfg(test)]
+mod external_segment_tests {
+    use super::{BilliardMap, DynamicsError, FlightModel, StepOptions};
+    use crate::dynamics::intersection::Ray;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundarySegment, ExternalSegment, LineSegment, Segment};
+
+    /// A downstream curve type: a straight edge implemented from scratch.
+    #[derive(Debug)]
+    struct Edge {
+        start: Vec2,
+        end: Vec2,
+    }
+
+    impl Segment for Edge {
+        fn length(&self) -> f64 {
+            (self.end - self.start).length()
+        }
+
+        fn point_at(&self, t: f64) -> Vec2 {
+            self.start + self.tangent_at(t) * t
+        }
+
+        fn tangent_at(&self, _t: f64) -> Vec2 {
+            (self.end - self.start).normalized()
+        }
+
+        fn curvature_at(&self, _t: f64) -> f64 {
+            0.0
+        }
+
+        fn ray_intersect(&self, origin: Vec2, direction: Vec2, epsilon: f64) -> Option<(f64, f64)> {
+            let line = LineSegment::new(self.start, self.end);
+            Ray { origin, direction }.intersect_line_segment(&line, epsilon)
+        }
+    }
+
+    fn square(external: bool) -> BilliardTable {
+        let corners = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ];
+        let segments = (0..4)
+            .map(|i| {
+                let (start, end) = (corners[i], corners[(i + 1) % 4]);
+                if external {
+                    BoundarySegment::External(ExternalSegment::new(Edge { start, end }))
+                } else {
+                    BoundarySegment::Line(LineSegment::new(start, end))
+                }
+            })
+            .collect();
+        BilliardTable::new(BoundaryComponent::new("square", segments), Vec::new())
+    }
+
+    #[test]
+    fn external_segments_behave_like_built_in_ones() {
+        let initial = BoundaryState::new(0, 0.3, 1.1);
+        let options = StepOptions::default();
+        let built_in = BilliardMap::new(&square(false), 1e-8, options)
+            .simulate(&initial, 40)
+            .unwrap();
+        let external = BilliardMap::new(&square(true), 1e-8, options)
+            .simulate(&initial, 40)
+            .unwrap();
+
+        assert_eq!(external.collisions.len(), 40);
+        for (a, b) in built_in.collisions.iter().zip(&external.collisions) {
+            assert_eq!(a.segment_index, b.segment_index);
+            assert!((a.s - b.s).abs() < 1e-9);
+            assert!((a.theta - b.theta).abs() < 1e-9);
+        }
+
+        // The default closest point search finds the foot of the
+        // perpendicular, to the accuracy a distance minimum allows.
+        let edge = Edge {
+            start: Vec2::new(0.0, 0.0),
+            end: Vec2::new(2.0, 0.0),
+        };
+        let (t, d) = edge.closest_point(Vec2::new(0.7, 0.5));
+        assert!((t - 0.7).abs() < 1e-7 && (d - 0.5).abs() < 1e-12);
+
+        // Their enclosed area is integrated numerically.
+        assert!((square(true).domain_area() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gravity_does_not_fly_through_external_segments() {
+        let initial = BoundaryState::new(0, 0.3, 1.1);
+        let options = StepOptions {
+            flight: FlightModel::Gravity { g: 1.0 },
+            ..StepOptions::default()
+        };
+        let result = BilliardMap::new(&square(true), 1e-8, options).simulate(&initial, 10);
+        assert_eq!(
+            result.unwrap_err(),
+            DynamicsError::ExternalSegmentUnderGravity {
+                component_index: 0,
+                segment_index: 0,
+            }
+        );
+    }
 }
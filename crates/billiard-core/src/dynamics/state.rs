@@ -1,13 +1,17 @@
+use crate::dynamics::simulation::DynamicsError;
 use crate::geometry::boundary::BilliardTable;
 use crate::geometry::primitives::Vec2;
+use serde::{Deserialize, Serialize};
 
 /// A collision state on the billiard boundary (Poincaré section).
 ///
 /// This encodes one bounce:
 /// - which boundary component we are on,
 /// - where along that component (arc-length),
-/// - and the outgoing angle relative to the local tangent.
-#[derive(Clone, Copy, Debug)]
+/// - the outgoing angle relative to the local tangent,
+/// - and the outgoing speed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BoundaryState {
     /// Index of the boundary component:
     /// 0 = outer boundary, 1.. = obstacles.
@@ -18,27 +22,83 @@ pub struct BoundaryState {
 
     /// Outgoing angle relative to the local tangent, in radians.
     ///
-    /// Convention suggestion:
+    /// Convention:
     /// - theta = 0: along the tangent direction,
-    /// - theta > 0: rotate from tangent toward inward normal,
+    /// - theta > 0: rotate from tangent toward the domain-inward normal,
     /// - theta < 0: rotate from tangent toward outward side.
+    ///
+    /// Outgoing states produced by the simulation therefore have theta in
+    /// (0, π) on every component, obstacles included.
     pub theta: f64,
+
+    /// Outgoing speed. Constant for elastic billiards; decays with each
+    /// bounce when the restitution coefficient is below 1. Defaults to 1
+    /// when deserializing.
+    #[serde(default = "unit_speed")]
+    pub speed: f64,
 }
 
 /// World-space representation of a moving billiard particle.
 ///
 /// This does not itself know which boundary component it comes from; it is
-/// just the instantaneous position and direction in ℝ².
+/// just the instantaneous position, direction and speed in ℝ².
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct WorldState {
     /// World-space position of the particle.
     pub position: Vec2,
 
     /// World-space direction of motion (ideally unit-length).
     pub direction: Vec2,
+
+    /// Speed along `direction`; defaults to 1 when deserializing.
+    #[serde(default = "unit_speed")]
+    pub speed: f64,
+}
+
+fn unit_speed() -> f64 {
+    1.0
 }
 
 impl BoundaryState {
+    /// Construct a boundary state moving at unit speed.
+    pub fn new(component_index: usize, s: f64, theta: f64) -> Self {
+        Self {
+            component_index,
+            s,
+            theta,
+            speed: 1.0,
+        }
+    }
+
+    /// Check that this state can be placed on `table`: its component
+    /// exists, `s`, `theta` and `speed` are finite and `speed` is
+    /// positive.
+    pub fn check(&self, table: &BilliardTable) -> Result<(), DynamicsError> {
+        if table.get_component(self.component_index).is_none() {
+            return Err(DynamicsError::UnknownComponent {
+                component_index: self.component_index,
+            });
+        }
+        let finite = self.s.is_finite() && self.theta.is_finite() && self.speed.is_finite();
+        if finite && self.speed > 0.0 {
+            Ok(())
+        } else {
+            Err(DynamicsError::InvalidState)
+        }
+    }
+
+    /// Fallible version of [`BoundaryState::to_world`]; see
+    /// [`BoundaryState::check`].
+    pub fn try_to_world(&self, table: &BilliardTable) -> Result<WorldState, DynamicsError> {
+        self.check(table)?;
+        Ok(self.to_world(table))
+    }
+
     /// Convert this boundary state to a world-space state using the table geometry.
+    ///
+    /// # Panics
+    /// Panics if the table has no component `component_index`.
     pub fn to_world(&self, table: &BilliardTable) -> WorldState {
         let component = table.component(self.component_index);
 
@@ -54,11 +114,21 @@ impl BoundaryState {
         WorldState {
             position,
             direction,
+            speed: self.speed,
         }
     }
 }
 
 impl WorldState {
+    /// Construct a world state moving at unit speed.
+    pub fn new(position: Vec2, direction: Vec2) -> Self {
+        WorldState {
+            position,
+            direction,
+            speed: 1.0,
+        }
+    }
+
     /// Construct a boundary-based state from this world state, given:
     /// - which component and arc-length parameter its position corresponds to,
     /// - and a sign convention for theta.
@@ -70,6 +140,7 @@ impl WorldState {
     ) -> BoundaryState {
         let component = table.component(component_index);
         let (_point, tangent) = component.point_and_tangent_at(s);
+        let (_p, inward_normal) = component.point_and_inward_normal_at(s);
 
         let t_hat = tangent
             .try_normalized()
@@ -79,17 +150,30 @@ impl WorldState {
             .try_normalized()
             .expect("Direction should not be near-zero.");
 
-        // Signed angle between t_hat and d_hat.
-        let dot = (t_hat.dot(d_hat)).clamp(-1.0, 1.0); // avoid NaN from rounding
-        let cross_z = t_hat.x * d_hat.y - t_hat.y * d_hat.x;
-        let theta = cross_z.atan2(dot); // atan2(y, x) → angle in (-π, π]
+        // Signed angle from t_hat toward the domain-inward normal, so that
+        // theta > 0 always points into the billiard domain (for obstacles
+        // the inward normal lies to the right of the tangent).
+        let along = (t_hat.dot(d_hat)).clamp(-1.0, 1.0); // avoid NaN from rounding
+        let across = inward_normal.dot(d_hat);
+        let theta = across.atan2(along); // atan2(y, x) → angle in (-π, π]
 
         BoundaryState {
             component_index,
             s,
             theta,
+            speed: self.speed,
         }
     }
+
+    /// Like [`to_boundary`](Self::to_boundary), but finds the component and
+    /// arc-length itself from the boundary point nearest to `position`.
+    ///
+    /// Returns `None` if that point is farther than `tol` away, i.e. the
+    /// particle is not on the boundary.
+    pub fn locate_on_table(&self, table: &BilliardTable, tol: f64) -> Option<BoundaryState> {
+        let (component_index, s, distance) = table.closest_boundary_point(self.position);
+        (distance <= tol).then(|| self.to_boundary(table, component_index, s))
+    }
 }
 
 #[cfg(test)]
@@ -122,11 +206,7 @@ mod tests {
         let thetas = [0.0, 0.3, -0.5, 1.0];
 
         for &theta in &thetas {
-            let bs = BoundaryState {
-                component_index,
-                s,
-                theta,
-            };
+            let bs = BoundaryState::new(component_index, s, theta);
 
             let ws = bs.to_world(&table);
             let bs2 = ws.to_boundary(&table, component_index, s);
@@ -142,4 +222,65 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn states_without_a_speed_move_at_unit_speed() {
+        use super::WorldState;
+
+        let state: BoundaryState =
+            serde_json::from_str(r#"{"component_index": 1, "s": 0.5, "theta": 1.0}"#).unwrap();
+        assert_eq!(state, BoundaryState::new(1, 0.5, 1.0));
+
+        let world: WorldState = serde_json::from_str(
+            r#"{"position": {"x": 0.1, "y": 0.2}, "direction": {"x": 0.0, "y": 1.0}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            world,
+            WorldState::new(Vec2::new(0.1, 0.2), Vec2::new(0.0, 1.0))
+        );
+
+        let fast = WorldState {
+            speed: 3.0,
+            ..world
+        };
+        let json = serde_json::to_string(&fast).unwrap();
+        assert_eq!(serde_json::from_str::<WorldState>(&json).unwrap(), fast);
+    }
+
+    #[test]
+    fn locate_on_table_finds_component_and_arc_length() {
+        use super::WorldState;
+        use crate::geometry::standard_tables::sinai;
+
+        let table = sinai(1.0, 0.2).to_billiard_table();
+
+        // Leaving the right wall at (1, 0.25), heading straight left.
+        let on_wall = WorldState {
+            position: Vec2::new(1.0, 0.25),
+            direction: Vec2::new(-1.0, 0.0),
+            speed: 2.0,
+        };
+        let state = on_wall.locate_on_table(&table, 1e-9).unwrap();
+        assert_eq!(state.component_index, 0);
+        assert!((state.s - 1.25).abs() < 1e-12);
+        assert!((state.theta - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert_eq!(state.speed, 2.0);
+
+        // Round trip through an obstacle state.
+        let original = BoundaryState::new(1, 0.4, 0.7);
+        let recovered = original
+            .to_world(&table)
+            .locate_on_table(&table, 1e-9)
+            .unwrap();
+        assert_eq!(recovered.component_index, 1);
+        assert!((recovered.s - original.s).abs() < 1e-12);
+        assert!((recovered.theta - original.theta).abs() < 1e-12);
+
+        let interior = WorldState {
+            position: Vec2::new(0.2, 0.2),
+            ..on_wall
+        };
+        assert!(interior.locate_on_table(&table, 1e-9).is_none());
+    }
 }
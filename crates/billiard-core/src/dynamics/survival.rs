@@ -0,0 +1,241 @@
+//! Escape-time statistics for tables with holes.
+//!
+//! An ensemble of particles is started from random boundary states drawn
+//! from the invariant measure of the billiard map (see
+//! [`sampling`](crate::dynamics::sampling)), and each is followed until it falls into
+//! a [`Hole`](crate::geometry::boundary::Hole) or the step budget runs out.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::dynamics::sampling::{SamplingError, try_random_boundary_state};
+use crate::dynamics::simulation::{BilliardMap, StepOptions, Termination};
+use crate::geometry::boundary::BilliardTable;
+
+/// When an absorbed particle left the table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EscapeRecord {
+    /// Number of collisions up to and including the absorbing one.
+    pub collisions: usize,
+
+    /// Time of flight before absorption (particles start at unit speed).
+    pub time: f64,
+}
+
+/// Outcome of an escape-time ensemble.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurvivalStatistics {
+    /// Absorbed particles, sorted by collision count.
+    pub escapes: Vec<EscapeRecord>,
+
+    /// Particles still inside after `max_steps` collisions.
+    pub survivors: usize,
+
+    /// Particles whose trajectory ended for another reason (a terminating
+    /// corner, a ray leaving the table or a failed simulation). They are
+    /// excluded from `P(n)`.
+    pub lost: usize,
+
+    /// Collision budget each particle was given.
+    pub max_steps: usize,
+}
+
+impl SurvivalStatistics {
+    /// Survival probability `P(n)` for `n = 0..=max_steps`: the fraction of
+    /// particles not yet absorbed after `n` collisions.
+    pub fn survival_probability(&self) -> Vec<f64> {
+        let total = self.escapes.len() + self.survivors;
+        if total == 0 {
+            return vec![1.0; self.max_steps + 1];
+        }
+
+        let mut absorbed = 0;
+        (0..=self.max_steps)
+            .map(|n| {
+                while absorbed < self.escapes.len() && self.escapes[absorbed].collisions <= n {
+                    absorbed += 1;
+                }
+                (total - absorbed) as f64 / total as f64
+            })
+            .collect()
+    }
+
+    /// Escape rate `γ` per collision, fitting `P(n) ∝ e^{-γn}` by least
+    /// squares on `ln P(n)` over every `n` with `P(n) > 0`.
+    ///
+    /// Closed tables have rate 0. Returns `None` if `P(1)` is already 0,
+    /// leaving nothing to fit.
+    pub fn escape_rate(&self) -> Option<f64> {
+        let points: Vec<(f64, f64)> = self
+            .survival_probability()
+            .into_iter()
+            .enumerate()
+            .take_while(|&(_, p)| p > 0.0)
+            .map(|(n, p)| (n as f64, p.ln()))
+            .collect();
+        if points.len() < 2 {
+            return None;
+        }
+
+        let count = points.len() as f64;
+        let (mean_n, mean_log) = points
+            .iter()
+            .fold((0.0, 0.0), |(a, b), &(n, l)| (a + n / count, b + l / count));
+        let (covariance, variance) = points.iter().fold((0.0, 0.0), |(c, v), &(n, l)| {
+            (c + (n - mean_n) * (l - mean_log), v + (n - mean_n).powi(2))
+        });
+        // P(n) never increases, so neither does the fit; this only clears
+        // the sign of -0.
+        Some((-covariance / variance).max(0.0))
+    }
+
+    /// Histogram of escape times with bins `[k·bin_width, (k+1)·bin_width)`,
+    /// up to the latest escape.
+    ///
+    /// # Panics
+    /// Panics if `bin_width` is not strictly positive and finite.
+    pub fn escape_time_histogram(&self, bin_width: f64) -> Vec<usize> {
+        assert!(
+            bin_width.is_finite() && bin_width > 0.0,
+            "Histogram bin width must be positive and finite."
+        );
+
+        let bin = |t: f64| (t / bin_width).floor() as usize;
+        let bins = self
+            .escapes
+            .iter()
+            .map(|e| bin(e.time) + 1)
+            .max()
+            .unwrap_or(0);
+        let mut histogram = vec![0; bins];
+        for e in &self.escapes {
+            histogram[bin(e.time)] += 1;
+        }
+        histogram
+    }
+}
+
+/// Simulate `particles` random initial conditions and collect their escape
+/// times.
+///
+/// The ensemble is reproducible for a given `seed`. Fails if (almost) every
+/// point of the boundary is absorbing or periodic.
+pub fn survival_ensemble(
+    table: &BilliardTable,
+    particles: usize,
+    max_steps: usize,
+    epsilon: f64,
+    options: &StepOptions,
+    seed: u64,
+) -> Result<SurvivalStatistics, SamplingError> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut stats = SurvivalStatistics {
+        escapes: Vec::new(),
+        survivors: 0,
+        lost: 0,
+        max_steps,
+    };
+
+    for _ in 0..particles {
+        let initial = try_random_boundary_state(table, &mut rng)?;
+        let Ok(trajectory) =
+            BilliardMap::new(table, epsilon, *options).simulate(&initial, max_steps)
+        else {
+            stats.lost += 1;
+            continue;
+        };
+
+        match trajectory.termination {
+            Termination::Absorbed => {
+                // The run's clock, so that parabolic flights under gravity
+                // are timed along the arc rather than the chord.
+                let time = trajectory.collisions.last().map_or(0.0, |c| c.time);
+                stats.escapes.push(EscapeRecord {
+                    collisions: trajectory.collisions.len(),
+                    time,
+                });
+            }
+            Termination::MaxSteps => stats.survivors += 1,
+            _ => stats.lost += 1,
+        }
+    }
+
+    stats.escapes.sort_by_key(|e| e.collisions);
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::survival_ensemble;
+    use crate::dynamics::simulation::{FlightModel, StepOptions};
+    use crate::geometry::boundary::Hole;
+    use crate::geometry::standard_tables::{regular_polygon, sinai};
+
+    #[test]
+    fn table_without_holes_keeps_every_particle() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let stats = survival_ensemble(&table, 20, 50, 1e-8, &StepOptions::default(), 1).unwrap();
+
+        assert!(stats.escapes.is_empty());
+        assert_eq!(stats.survivors + stats.lost, 20);
+        assert!(stats.survival_probability().iter().all(|&p| p == 1.0));
+        assert_eq!(stats.escape_rate(), Some(0.0));
+    }
+
+    #[test]
+    fn survival_decays_through_a_hole() {
+        let mut spec = sinai(1.0, 0.2);
+        // Open a quarter of the bottom wall.
+        spec.outer.holes = vec![Hole {
+            start: 0.25,
+            end: 0.5,
+        }];
+        let table = spec.to_billiard_table();
+
+        let stats = survival_ensemble(&table, 200, 500, 1e-8, &StepOptions::default(), 7).unwrap();
+        let p = stats.survival_probability();
+
+        assert_eq!(p.len(), 501);
+        assert_eq!(p[0], 1.0);
+        assert!(p.windows(2).all(|w| w[1] <= w[0]), "P(n) must not increase");
+        assert!(p[500] < 0.1, "P(500) = {}", p[500]);
+
+        // Roughly the hole's share of the boundary, 0.25 / (4 + 0.4π).
+        let rate = stats.escape_rate().unwrap();
+        assert!((0.02..0.08).contains(&rate), "escape rate {}", rate);
+
+        let histogram = stats.escape_time_histogram(1.0);
+        assert_eq!(histogram.iter().sum::<usize>(), stats.escapes.len());
+
+        // Same seed, same ensemble.
+        let again = survival_ensemble(&table, 200, 500, 1e-8, &StepOptions::default(), 7).unwrap();
+        assert_eq!(stats, again);
+    }
+
+    #[test]
+    fn gravity_drops_every_particle_through_the_floor() {
+        // A square of side √2 whose floor is a hole. Side walls keep the
+        // vertical velocity and the ceiling only turns it downward, so a
+        // particle at unit speed is out within (1 + √(1 + 2g√2)) / g.
+        let mut spec = regular_polygon(4, 1.0);
+        let side = 2.0_f64.sqrt();
+        spec.outer.holes = vec![Hole {
+            start: 0.0,
+            end: side,
+        }];
+        let table = spec.to_billiard_table();
+        let g = 10.0;
+        let options = StepOptions {
+            flight: FlightModel::Gravity { g },
+            ..StepOptions::default()
+        };
+
+        let stats = survival_ensemble(&table, 100, 1000, 1e-8, &options, 2).unwrap();
+
+        assert_eq!(stats.escapes.len(), 100);
+        let bound = (1.0 + (1.0 + 2.0 * g * side).sqrt()) / g;
+        for escape in &stats.escapes {
+            assert!(escape.time > 0.0 && escape.time < bound, "{:?}", escape);
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! Observables of a family of tables, as functions of a parameter.
+//!
+//! A sweep builds the table at each value on a grid, typically from a
+//! [`TableTemplate`](crate::geometry::table_template::TableTemplate), and
+//! measures an [`Observable`] over an ensemble of particles drawn from the
+//! invariant measure (see [`sampling`](crate::dynamics::sampling)). Every
+//! value uses the same seed, so neighbouring points of the curve differ by
+//! the table rather than by sampling noise.
+//!
+//! [`bifurcation_diagram`] follows a single reference trajectory instead,
+//! and keeps where it ends up on the Poincaré section at each value: the
+//! billiard analogue of a bifurcation diagram, in which regular orbits show
+//! as a few points or curves and chaotic ones fill a band.
+
+use std::collections::HashSet;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::dynamics::config::SimulationConfig;
+use crate::dynamics::ensemble::{EnsembleError, ensemble_statistics};
+use crate::dynamics::jacobian::lyapunov_exponent;
+use crate::dynamics::phase_space::phase_space_points;
+use crate::dynamics::sampling::try_random_boundary_state;
+use crate::dynamics::simulation::{BilliardMap, FlightModel, run_trajectory};
+use crate::dynamics::state::BoundaryState;
+use crate::dynamics::survival::survival_ensemble;
+use crate::geometry::boundary::BilliardTable;
+
+/// What a sweep measures at each parameter value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum Observable {
+    /// Largest Lyapunov exponent per collision, averaged over the particles
+    /// that miss every corner; see [`lyapunov_exponent`].
+    LyapunovExponent,
+
+    /// Rate per collision at which particles fall into the holes of the
+    /// table; see
+    /// [`SurvivalStatistics::escape_rate`](crate::dynamics::survival::SurvivalStatistics::escape_rate).
+    EscapeRate,
+
+    /// Mean distance between collisions; see [`ensemble_statistics`].
+    MeanFreePath,
+}
+
+/// `count` evenly spaced values from `start` to `end`, both included.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SweepGrid {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+impl SweepGrid {
+    /// The values of the grid; a grid of one is just `start`.
+    pub fn values(&self) -> Vec<f64> {
+        let step = if self.count > 1 {
+            (self.end - self.start) / (self.count - 1) as f64
+        } else {
+            0.0
+        };
+        (0..self.count)
+            .map(|i| self.start + step * i as f64)
+            .collect()
+    }
+}
+
+/// An observable measured at one parameter value.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SweepPoint {
+    pub value: f64,
+
+    /// `None` where the ensemble gave nothing to measure, e.g. every
+    /// particle hit a corner before its Lyapunov exponent was known.
+    pub observable: Option<f64>,
+}
+
+/// Measure `observable` on `table` over `particles` particles from the
+/// invariant measure, each followed for up to
+/// [`SimulationConfig::max_steps`] collisions as `config` says, with
+/// [`SimulationConfig::seed`].
+///
+/// Fails if (almost) every point of the boundary is absorbing or periodic,
+/// or if a Lyapunov exponent or mean free path is asked for under
+/// [`FlightModel::Gravity`]: both assume straight flights.
+pub fn measure(
+    table: &BilliardTable,
+    observable: Observable,
+    particles: usize,
+    config: &SimulationConfig,
+) -> Result<Option<f64>, EnsembleError> {
+    let (steps, epsilon, options) = (config.max_steps(), config.epsilon(), config.step_options());
+    Ok(match observable {
+        Observable::LyapunovExponent => {
+            if options.flight != FlightModel::Straight {
+                return Err(EnsembleError::CurvedFlights);
+            }
+            let mut rng = ChaCha8Rng::seed_from_u64(config.seed());
+            let mut map = BilliardMap::from_config(table, config);
+            let mut exponents = Vec::with_capacity(particles);
+            for _ in 0..particles {
+                let initial = try_random_boundary_state(table, &mut rng)?;
+                let exponent = map.simulate(&initial, steps).ok().and_then(|trajectory| {
+                    lyapunov_exponent(table, &initial, &trajectory.collisions)
+                });
+                exponents.extend(exponent);
+            }
+            (!exponents.is_empty()).then(|| exponents.iter().sum::<f64>() / exponents.len() as f64)
+        }
+        Observable::EscapeRate => {
+            survival_ensemble(table, particles, steps, epsilon, options, config.seed())?
+                .escape_rate()
+        }
+        Observable::MeanFreePath => {
+            let free_path =
+                ensemble_statistics(table, particles, steps, epsilon, options, config.seed())?
+                    .free_path;
+            (free_path.flights > 0).then_some(free_path.mean)
+        }
+    })
+}
+
+/// Measure `observable` on the table `table_at` builds for each of
+/// `values`, as [`measure`] does; the first table that cannot be built or
+/// sampled ends the sweep.
+pub fn sweep<E: From<EnsembleError>>(
+    values: &[f64],
+    mut table_at: impl FnMut(f64) -> Result<BilliardTable, E>,
+    observable: Observable,
+    particles: usize,
+    config: &SimulationConfig,
+) -> Result<Vec<SweepPoint>, E> {
+    values
+        .iter()
+        .map(|&value| {
+            let table = table_at(value)?;
+            Ok(SweepPoint {
+                value,
+                observable: measure(&table, observable, particles, config)?,
+            })
+        })
+        .collect()
+}
+
+/// The asymptotic bounces of the reference trajectory of a
+/// [`bifurcation_diagram`] at one parameter value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BifurcationSlice {
+    pub value: f64,
+
+    /// Coordinates `(s, cos θ)` of the bounces kept, as in
+    /// [`phase_space_points`]: `s` is the total arc length and `cos θ` the
+    /// sine of the angle to the normal. Empty if the trajectory could not
+    /// be simulated, e.g. because `initial` is not on this table.
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Follow the trajectory from `initial` on the table `table_at` builds for
+/// each of `values`, as `config` says, and keep its bounces after the first
+/// `transient` collisions.
+///
+/// The bounces are thinned for plotting: the Poincaré section
+/// `[0, |∂Q|) × [-1, 1]` is cut into `resolution × resolution` cells and
+/// only the first bounce in each cell is kept, so a periodic orbit gives
+/// its few points and a chaotic one at most `resolution²`, however long
+/// the run. The first table that cannot be built ends the diagram.
+///
+/// # Panics
+/// Panics if `resolution` is zero.
+pub fn bifurcation_diagram<E>(
+    values: &[f64],
+    mut table_at: impl FnMut(f64) -> Result<BilliardTable, E>,
+    initial: &BoundaryState,
+    transient: usize,
+    resolution: usize,
+    config: &SimulationConfig,
+) -> Result<Vec<BifurcationSlice>, E> {
+    assert!(
+        resolution > 0,
+        "Bifurcation diagram needs at least one cell."
+    );
+    values
+        .iter()
+        .map(|&value| {
+            let table = table_at(value)?;
+            let collisions = run_trajectory(&table, initial, config).unwrap_or_default();
+            let asymptotic = collisions.get(transient..).unwrap_or_default();
+
+            let perimeter = table.perimeter();
+            let cell = |x: f64| ((x * resolution as f64) as usize).min(resolution - 1);
+            let mut visited = HashSet::new();
+            let points = phase_space_points(&table, asymptotic)
+                .into_iter()
+                .filter(|&(s, p)| visited.insert((cell(s / perimeter), cell((p + 1.0) / 2.0))))
+                .collect();
+            Ok(BifurcationSlice { value, points })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::{Observable, SweepGrid, bifurcation_diagram, measure, sweep};
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::ensemble::EnsembleError;
+    use crate::dynamics::santalo::santalo_mean_free_path;
+    use crate::dynamics::simulation::run_trajectory;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::standard_tables::{circle, sinai, stadium};
+
+    fn config(max_steps: usize) -> SimulationConfig {
+        SimulationConfig::builder()
+            .max_steps(max_steps)
+            .epsilon(1e-10)
+            .seed(3)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn grids_include_both_ends() {
+        let grid = SweepGrid {
+            start: 0.5,
+            end: 2.0,
+            count: 4,
+        };
+        assert_eq!(grid.values(), [0.5, 1.0, 1.5, 2.0]);
+        assert_eq!(SweepGrid { count: 1, ..grid }.values(), [0.5]);
+        assert!(SweepGrid { count: 0, ..grid }.values().is_empty());
+    }
+
+    #[test]
+    fn mean_free_paths_follow_santalo_along_the_family() {
+        let points = sweep(
+            &[0.5, 1.0, 2.0],
+            |l| Ok::<_, EnsembleError>(stadium(l, 0.5).to_billiard_table()),
+            Observable::MeanFreePath,
+            100,
+            &config(200),
+        )
+        .unwrap();
+
+        for point in &points {
+            let table = stadium(point.value, 0.5).to_billiard_table();
+            let predicted = santalo_mean_free_path(&table);
+            let measured = point.observable.unwrap();
+            assert!(
+                (measured - predicted).abs() < 0.05 * predicted,
+                "l = {}",
+                point.value
+            );
+        }
+        // Longer stadiums have longer flights.
+        assert!(points.windows(2).all(|w| w[0].observable < w[1].observable));
+    }
+
+    #[test]
+    fn observables_tell_tables_apart() {
+        let lyapunov =
+            |table| measure(&table, Observable::LyapunovExponent, 10, &config(500)).unwrap();
+        let circle = lyapunov(circle(1.0).to_billiard_table()).unwrap();
+        let stadium = lyapunov(stadium(1.0, 0.5).to_billiard_table()).unwrap();
+        assert!(
+            circle.abs() < 0.05 && stadium > 0.1,
+            "{} vs {}",
+            circle,
+            stadium
+        );
+
+        let closed = sinai(1.0, 0.2).to_billiard_table();
+        let rate = measure(&closed, Observable::EscapeRate, 10, &config(50)).unwrap();
+        assert_eq!(rate, Some(0.0));
+    }
+
+    #[test]
+    fn circles_keep_one_angle_and_stadiums_spread_out() {
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let config = config(2000);
+
+        let circles = bifurcation_diagram(
+            &[0.5, 1.0],
+            |r| Ok::<_, Infallible>(circle(r).to_billiard_table()),
+            &initial,
+            100,
+            20,
+            &config,
+        )
+        .unwrap();
+        for slice in &circles {
+            // The angle is conserved, so the orbit stays in one row of
+            // cells and fills at most its 20 cells along the boundary.
+            assert!(!slice.points.is_empty() && slice.points.len() <= 20);
+            assert!(
+                slice
+                    .points
+                    .iter()
+                    .all(|&(_, p)| (p - 1f64.cos()).abs() < 1e-9)
+            );
+        }
+
+        let stadium = bifurcation_diagram(
+            &[1.0],
+            |l| Ok::<_, Infallible>(stadium(l, 0.5).to_billiard_table()),
+            &initial,
+            100,
+            20,
+            &config,
+        )
+        .unwrap();
+        assert!(stadium[0].points.len() > 200, "{}", stadium[0].points.len());
+    }
+
+    #[test]
+    fn transients_are_dropped_and_failures_stop_the_diagram() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let config = config(50);
+        let slices = bifurcation_diagram(
+            &[0.2],
+            |r| Ok::<_, Infallible>(sinai(1.0, r).to_billiard_table()),
+            &initial,
+            30,
+            1_000_000,
+            &config,
+        )
+        .unwrap();
+        let collisions = run_trajectory(&table, &initial, &config).unwrap();
+        assert_eq!(slices[0].points.len(), 20);
+        assert!((slices[0].points[0].1 - collisions[30].theta.cos()).abs() < 1e-12);
+
+        let mut built = 0;
+        let result = bifurcation_diagram(
+            &[0.1, 0.2, 0.3],
+            |r| {
+                built += 1;
+                if r < 0.25 {
+                    Ok(sinai(1.0, r).to_billiard_table())
+                } else {
+                    Err(r)
+                }
+            },
+            &initial,
+            0,
+            10,
+            &config,
+        );
+        assert_eq!(result, Err(0.3));
+        assert_eq!(built, 3);
+    }
+}
@@ -0,0 +1,213 @@
+//! Unfolding of trajectories in polygonal tables.
+//!
+//! Instead of reflecting the trajectory at each bounce, reflect the table
+//! across the edge that was hit: the particle then continues in a straight
+//! line through a chain of mirrored copies of the polygon. For rational
+//! polygons the copies close up into a translation surface, which is the
+//! standard route to their periodic orbits and ergodic properties.
+
+use crate::dynamics::periodic::ItineraryStep;
+use crate::dynamics::simulation::CollisionResult;
+use crate::dynamics::state::BoundaryState;
+use crate::geometry::boundary::BilliardTable;
+use crate::geometry::primitives::Vec2;
+use crate::geometry::segments::BoundarySegment;
+
+/// A trajectory straightened by reflecting the table at each bounce.
+#[derive(Clone, Debug)]
+pub struct PolygonUnfolding {
+    /// Launch point followed by every hit point, in the plane of the
+    /// unfolded copies. For elastic bounces these all lie on one line.
+    pub points: Vec<Vec2>,
+
+    /// Edge hit at each bounce, i.e. the edge each new copy was reflected
+    /// across.
+    pub reflections: Vec<ItineraryStep>,
+
+    /// Vertices of the outer boundary of the copy the particle occupies
+    /// during each free flight; `copies[0]` is the original table.
+    pub copies: Vec<Vec<Vec2>>,
+}
+
+/// An orientation-preserving or -reversing rigid motion `x ↦ A x + b`.
+#[derive(Clone, Copy, Debug)]
+struct Isometry {
+    a: [[f64; 2]; 2],
+    b: Vec2,
+}
+
+impl Isometry {
+    fn identity() -> Self {
+        Isometry {
+            a: [[1.0, 0.0], [0.0, 1.0]],
+            b: Vec2::new(0.0, 0.0),
+        }
+    }
+
+    /// Reflection across the line through `p` and `q`.
+    fn reflection(p: Vec2, q: Vec2) -> Self {
+        let d = (q - p).normalized();
+        let a = [
+            [2.0 * d.x * d.x - 1.0, 2.0 * d.x * d.y],
+            [2.0 * d.x * d.y, 2.0 * d.y * d.y - 1.0],
+        ];
+        let linear =
+            |v: Vec2| Vec2::new(a[0][0] * v.x + a[0][1] * v.y, a[1][0] * v.x + a[1][1] * v.y);
+        Isometry {
+            a,
+            b: p - linear(p),
+        }
+    }
+
+    fn apply(&self, v: Vec2) -> Vec2 {
+        Vec2::new(
+            self.a[0][0] * v.x + self.a[0][1] * v.y,
+            self.a[1][0] * v.x + self.a[1][1] * v.y,
+        ) + self.b
+    }
+
+    /// `self` followed by `next`.
+    fn then(&self, next: &Isometry) -> Isometry {
+        let (m, n) = (next.a, self.a);
+        let entry = |i: usize, j: usize| m[i][0] * n[0][j] + m[i][1] * n[1][j];
+        Isometry {
+            a: [[entry(0, 0), entry(0, 1)], [entry(1, 0), entry(1, 1)]],
+            b: next.apply(self.b),
+        }
+    }
+}
+
+/// Unfold the trajectory that starts at `initial` and makes `collisions`.
+///
+/// Returns `None` if any bounce lands on a curved segment. Only elastic
+/// specular reflections unfold to a straight line; periodic passes, damped
+/// bounces and bisector reflections at corners are unfolded as if they were
+/// ordinary reflections off the edge that was hit.
+pub fn unfold_polygon_trajectory(
+    table: &BilliardTable,
+    initial: &BoundaryState,
+    collisions: &[CollisionResult],
+) -> Option<PolygonUnfolding> {
+    let outer: Vec<Vec2> = table
+        .outer
+        .segments
+        .iter()
+        .map(|seg| seg.point_at(0.0))
+        .collect();
+
+    let mut placement = Isometry::identity();
+    let mut unfolding = PolygonUnfolding {
+        points: vec![initial.to_world(table).position],
+        reflections: Vec::with_capacity(collisions.len()),
+        copies: vec![outer.clone()],
+    };
+
+    for c in collisions {
+        let segment = &table.component(c.component_index).segments[c.segment_index];
+        let BoundarySegment::Line(line) = segment else {
+            return None;
+        };
+
+        unfolding.points.push(placement.apply(c.hit_point));
+        unfolding.reflections.push(ItineraryStep {
+            component_index: c.component_index,
+            segment_index: c.segment_index,
+        });
+
+        // Reflecting the original table across `line` and then placing it
+        // like the current copy puts the new copy across the placed edge.
+        placement = Isometry::reflection(line.start, line.end).then(&placement);
+        unfolding
+            .copies
+            .push(outer.iter().map(|&v| placement.apply(v)).collect());
+    }
+
+    Some(unfolding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unfold_polygon_trajectory;
+    use crate::dynamics::config::SimulationConfig;
+    use crate::dynamics::simulation::run_trajectory;
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::standard_tables::{regular_polygon, stadium, triangle};
+    use std::f64::consts::PI;
+
+    fn assert_collinear(points: &[Vec2]) {
+        let d = (points[1] - points[0]).normalized();
+        for p in points {
+            let offset = *p - points[0];
+            assert!((offset.x * d.y - offset.y * d.x).abs() < 1e-9, "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn square_orbit_unfolds_to_a_line_of_equal_length() {
+        let table = regular_polygon(4, 1.0).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 0.9);
+        let config = SimulationConfig::builder()
+            .max_steps(25)
+            .epsilon(1e-10)
+            .build()
+            .unwrap();
+        let collisions = run_trajectory(&table, &initial, &config).unwrap();
+
+        let unfolding = unfold_polygon_trajectory(&table, &initial, &collisions).unwrap();
+        assert_eq!(unfolding.points.len(), 26);
+        assert_eq!(unfolding.copies.len(), 26);
+        assert_collinear(&unfolding.points);
+
+        // Unfolding is an isometry on each flight.
+        let mut start = initial.to_world(&table).position;
+        for (k, c) in collisions.iter().enumerate() {
+            let folded = (c.hit_point - start).length();
+            let unfolded = (unfolding.points[k + 1] - unfolding.points[k]).length();
+            assert!((folded - unfolded).abs() < 1e-9);
+            start = c.hit_point;
+        }
+    }
+
+    #[test]
+    fn consecutive_triangle_copies_share_the_reflecting_edge() {
+        let table = triangle(PI / 4.0, PI / 3.0).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.4, 1.1);
+        let config = SimulationConfig::builder()
+            .max_steps(12)
+            .epsilon(1e-10)
+            .build()
+            .unwrap();
+        let collisions = run_trajectory(&table, &initial, &config).unwrap();
+
+        let unfolding = unfold_polygon_trajectory(&table, &initial, &collisions).unwrap();
+        assert_collinear(&unfolding.points);
+
+        for (k, step) in unfolding.reflections.iter().enumerate() {
+            let (before, after) = (&unfolding.copies[k], &unfolding.copies[k + 1]);
+            let i = step.segment_index;
+            let j = (i + 1) % before.len();
+            assert!((before[i] - after[i]).length() < 1e-9);
+            assert!((before[j] - after[j]).length() < 1e-9);
+            // The hit point lies on that shared edge.
+            let p = unfolding.points[k + 1];
+            let (a, b) = (before[i], before[j]);
+            let cross = (b - a).x * (p - a).y - (b - a).y * (p - a).x;
+            assert!(cross.abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn curved_tables_cannot_be_unfolded() {
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let config = SimulationConfig::builder()
+            .max_steps(10)
+            .epsilon(1e-10)
+            .build()
+            .unwrap();
+        let collisions = run_trajectory(&table, &initial, &config).unwrap();
+
+        assert!(unfold_polygon_trajectory(&table, &initial, &collisions).is_none());
+    }
+}
@@ -0,0 +1,201 @@
+//! Arc-length reparameterization of curves without a closed form for it.
+//!
+//! Segments are parameterized by arc length, which lines and circular arcs
+//! get for free. A general curve `c(u)` (a Bézier curve, an ellipse) only
+//! has its natural parameter `u`, so its `point_at(s)` first has to find the
+//! `u` at which the length from the start reaches `s`. An
+//! [`ArcLengthTable`] tabulates the cumulative length on a grid once, then
+//! answers that query with a binary search and a few Newton steps on the
+//! exact length, keeping each lookup O(log n) and accurate to rounding.
+//!
+//! The table stores only numbers; the curve's speed `|c'(u)|` is passed to
+//! each query, so a segment can embed a table next to its own control data.
+
+/// Gauss–Legendre nodes on `[-1, 1]` and their weights (5 points, exact for
+/// polynomials of degree 9).
+const GAUSS_NODES: [(f64, f64); 5] = [
+    (0.0, 0.568_888_888_888_888_9),
+    (-0.538_469_310_105_683, 0.478_628_670_499_366_5),
+    (0.538_469_310_105_683, 0.478_628_670_499_366_5),
+    (-0.906_179_845_938_664, 0.236_926_885_056_189_1),
+    (0.906_179_845_938_664, 0.236_926_885_056_189_1),
+];
+
+/// Newton steps after which [`ArcLengthTable::parameter_at`] gives up
+/// refining; it converges in two or three on a smooth curve.
+const MAX_NEWTON_STEPS: usize = 8;
+
+/// Length of the curve with speed `speed` between parameters `a` and `b`.
+pub(crate) fn integrate(speed: &impl Fn(f64) -> f64, a: f64, b: f64) -> f64 {
+    let mid = 0.5 * (a + b);
+    let half = 0.5 * (b - a);
+    half * GAUSS_NODES
+        .iter()
+        .map(|&(x, w)| w * speed(mid + half * x))
+        .sum::<f64>()
+}
+
+/// Cumulative arc length of a curve on a uniform grid of its parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArcLengthTable {
+    /// Grid of the curve parameter, from its start to its end value.
+    params: Vec<f64>,
+
+    /// `lengths[i]` is the arc length from the start to `params[i]`.
+    lengths: Vec<f64>,
+}
+
+impl ArcLengthTable {
+    /// Tabulate the curve with speed `speed` over the parameter range
+    /// `start..=end`, on `intervals` equal intervals.
+    ///
+    /// The speed must be positive except at isolated points; more
+    /// intervals only make the initial guesses better, since queries
+    /// integrate exactly within an interval.
+    ///
+    /// # Panics
+    /// Panics if `intervals` is zero or the range is empty or not finite.
+    pub fn new(speed: impl Fn(f64) -> f64, start: f64, end: f64, intervals: usize) -> Self {
+        assert!(
+            intervals > 0,
+            "Arc-length table needs at least one interval."
+        );
+        assert!(
+            start.is_finite() && end.is_finite() && start < end,
+            "Arc-length table needs a finite, non-empty parameter range."
+        );
+
+        let step = (end - start) / intervals as f64;
+        let params: Vec<f64> = (0..=intervals)
+            .map(|i| {
+                if i == intervals {
+                    end
+                } else {
+                    start + i as f64 * step
+                }
+            })
+            .collect();
+        let mut lengths = Vec::with_capacity(params.len());
+        lengths.push(0.0);
+        for pair in params.windows(2) {
+            let previous = *lengths.last().unwrap();
+            lengths.push(previous + integrate(&speed, pair[0], pair[1]));
+        }
+
+        ArcLengthTable { params, lengths }
+    }
+
+    /// Total arc length of the curve.
+    pub fn length(&self) -> f64 {
+        *self.lengths.last().unwrap()
+    }
+
+    /// Curve parameter range covered by the table.
+    pub fn parameter_range(&self) -> (f64, f64) {
+        (self.params[0], *self.params.last().unwrap())
+    }
+
+    /// Index `i` of the grid interval `params[i]..=params[i + 1]` holding
+    /// the curve parameter `u`.
+    fn interval_of_parameter(&self, u: f64) -> usize {
+        let i = self.params.partition_point(|&p| p <= u);
+        i.clamp(1, self.params.len() - 1) - 1
+    }
+
+    /// Index `i` of the grid interval whose lengths bracket `s`.
+    fn interval_of_length(&self, s: f64) -> usize {
+        let i = self.lengths.partition_point(|&l| l <= s);
+        i.clamp(1, self.lengths.len() - 1) - 1
+    }
+
+    /// Arc length from the start of the curve to parameter `u`, which is
+    /// clamped to the parameter range.
+    pub fn length_at(&self, speed: impl Fn(f64) -> f64, u: f64) -> f64 {
+        let (start, end) = self.parameter_range();
+        let u = u.clamp(start, end);
+        let i = self.interval_of_parameter(u);
+        self.lengths[i] + integrate(&speed, self.params[i], u)
+    }
+
+    /// Curve parameter at which the arc length from the start is `s`,
+    /// which is clamped to `0..=length()`.
+    ///
+    /// The bracketing grid interval is found by binary search; within it,
+    /// Newton's method on the exact length (safeguarded by bisection, so a
+    /// vanishing speed cannot throw it out) refines a linear guess.
+    pub fn parameter_at(&self, speed: impl Fn(f64) -> f64, s: f64) -> f64 {
+        let s = s.clamp(0.0, self.length());
+        let i = self.interval_of_length(s);
+        let (mut lo, mut hi) = (self.params[i], self.params[i + 1]);
+        let (l0, l1) = (self.lengths[i], self.lengths[i + 1]);
+        if l1 <= l0 {
+            return lo;
+        }
+
+        let mut u = lo + (hi - lo) * (s - l0) / (l1 - l0);
+        for _ in 0..MAX_NEWTON_STEPS {
+            let error = l0 + integrate(&speed, self.params[i], u) - s;
+            if error.abs() <= 4.0 * f64::EPSILON * l1 {
+                return u;
+            }
+            if error > 0.0 {
+                hi = u;
+            } else {
+                lo = u;
+            }
+
+            let v = speed(u);
+            let next = u - error / v;
+            let next = if v > 0.0 && next >= lo && next <= hi {
+                next
+            } else {
+                0.5 * (lo + hi)
+            };
+            if (next - u).abs() <= f64::EPSILON * u.abs().max(1.0) {
+                return next;
+            }
+            u = next;
+        }
+        u
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcLengthTable;
+
+    /// Length of the parabola `y = x²` from `x = 0` to `x`.
+    fn parabola_length(x: f64) -> f64 {
+        0.5 * x * (1.0 + 4.0 * x * x).sqrt() + 0.25 * (2.0 * x).asinh()
+    }
+
+    #[test]
+    fn matches_the_parabola_in_closed_form() {
+        let speed = |x: f64| (1.0 + 4.0 * x * x).sqrt();
+        let table = ArcLengthTable::new(speed, 0.0, 2.0, 16);
+
+        assert!((table.length() - parabola_length(2.0)).abs() < 1e-9);
+        for k in 0..=20 {
+            let x = 0.1 * k as f64;
+            let s = parabola_length(x);
+            assert!((table.length_at(speed, x) - s).abs() < 1e-9);
+            assert!((table.parameter_at(speed, s) - x).abs() < 1e-9, "x = {}", x);
+        }
+    }
+
+    #[test]
+    fn inverts_the_length_on_an_ellipse() {
+        // Ellipse with semi-axes 2 and 1; its perimeter is about 9.688448.
+        let speed = |t: f64| (4.0 * t.sin().powi(2) + t.cos().powi(2)).sqrt();
+        let table = ArcLengthTable::new(speed, 0.0, std::f64::consts::TAU, 32);
+        assert!((table.length() - 9.688_448_220_547_675).abs() < 1e-9);
+
+        for k in 0..=50 {
+            let s = table.length() * k as f64 / 50.0;
+            let u = table.parameter_at(speed, s);
+            assert!((table.length_at(speed, u) - s).abs() < 1e-10);
+        }
+        assert_eq!(table.parameter_at(speed, -1.0), 0.0);
+        assert_eq!(table.parameter_at(speed, 100.0), std::f64::consts::TAU);
+    }
+}
@@ -0,0 +1,774 @@
+//! Union, intersection and difference of table regions.
+//!
+//! The region of a [`TableSpec`] is the inside of its outer boundary minus
+//! the inside of its obstacles. Two regions are combined in three steps:
+//!
+//! 1. every boundary segment of each operand is split wherever it crosses
+//!    or touches the boundary of the other, so that no piece straddles it;
+//! 2. each piece is classified by its midpoint as inside the other region,
+//!    outside it, or on its boundary (running the same way or the opposite
+//!    way), and kept or dropped according to the operation;
+//! 3. the kept pieces are stitched end to end into closed loops. Loops
+//!    running counterclockwise bound the result from outside, clockwise
+//!    ones are holes, which become obstacles.
+//!
+//! Lines stay lines and arcs stay arcs, so a disk subtracted from a square
+//! leaves an exact circular bite. The result must again be a table: one
+//! connected region, with the checks of
+//! [`validate_table`](super::validation::validate_table) passing.
+
+use std::f64::consts::TAU;
+use std::fmt;
+use std::iter;
+
+use serde::{Deserialize, Serialize};
+
+use super::boundary::{encloses, signed_area};
+use super::primitives::Vec2;
+use super::segments::{BoundaryCondition, BoundarySegment, SegmentLabel};
+use super::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+use super::validation::validate_table;
+
+/// Distance within which two points are the same and a point lies on a
+/// segment.
+const TOLERANCE: f64 = 1e-9;
+
+/// Largest gap bridged when stitching pieces into loops. Pieces meet at
+/// points computed separately on each operand, which may differ by a few
+/// times [`TOLERANCE`].
+const JOIN_TOLERANCE: f64 = 1e-8;
+
+/// How two regions are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum BooleanOp {
+    /// Points in either region.
+    Union,
+
+    /// Points in both regions.
+    Intersection,
+
+    /// Points in the first region but not the second.
+    Difference,
+}
+
+/// Why two regions could not be combined into a table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BooleanError {
+    /// An operand (0 for the first, 1 for the second) is not a valid table;
+    /// see [`validate_table`].
+    InvalidOperand { operand: usize },
+
+    /// The result has no area.
+    Empty,
+
+    /// The result falls apart into `count` separate regions.
+    Disconnected { count: usize },
+
+    /// The result is not a valid table, e.g. because it pinches to a point
+    /// where its boundary touches itself.
+    InvalidResult,
+}
+
+impl fmt::Display for BooleanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BooleanError::InvalidOperand { operand } => {
+                write!(f, "operand {} is not a valid table", operand)
+            }
+            BooleanError::Empty => write!(f, "the result is empty"),
+            BooleanError::Disconnected { count } => {
+                write!(f, "the result has {} separate regions", count)
+            }
+            BooleanError::InvalidResult => write!(f, "the result is not a valid table"),
+        }
+    }
+}
+
+impl std::error::Error for BooleanError {}
+
+impl TableSpec {
+    /// The region covered by `self` or `other`.
+    pub fn union(&self, other: &TableSpec) -> Result<TableSpec, BooleanError> {
+        combine(self, other, BooleanOp::Union)
+    }
+
+    /// The region covered by both `self` and `other`.
+    pub fn intersection(&self, other: &TableSpec) -> Result<TableSpec, BooleanError> {
+        combine(self, other, BooleanOp::Intersection)
+    }
+
+    /// The region covered by `self` but not `other`.
+    pub fn difference(&self, other: &TableSpec) -> Result<TableSpec, BooleanError> {
+        combine(self, other, BooleanOp::Difference)
+    }
+}
+
+/// Combine the regions of `a` and `b`.
+///
+/// Segment conditions and labels are kept on the pieces that survive,
+/// except periodic pairings, which become reflecting walls since the
+/// segments they refer to no longer exist. A segment cut into several
+/// pieces lends its id to each of them, which validation reports as
+/// duplicates. Holes are dropped. Each component of the result is
+/// named after the component its first piece came from.
+pub fn combine(a: &TableSpec, b: &TableSpec, op: BooleanOp) -> Result<TableSpec, BooleanError> {
+    for (operand, spec) in [a, b].into_iter().enumerate() {
+        if !validate_table(spec).is_valid() {
+            return Err(BooleanError::InvalidOperand { operand });
+        }
+    }
+
+    let (first, second) = (edges(a, 0), edges(b, 1));
+    let mut kept = Vec::new();
+    for (edges, others) in [(&first, &second), (&second, &first)] {
+        let other_boundary: Vec<BoundarySegment> =
+            others.iter().map(|o| o.curve.to_segment()).collect();
+        for piece in split(edges, others) {
+            let keep = match (op, piece.operand, classify(&piece, others, &other_boundary)) {
+                (BooleanOp::Union, _, Side::Outside)
+                | (BooleanOp::Union, 0, Side::Same)
+                | (BooleanOp::Intersection, _, Side::Inside)
+                | (BooleanOp::Intersection, 0, Side::Same)
+                | (BooleanOp::Difference, 0, Side::Outside | Side::Opposite) => Some(piece),
+                (BooleanOp::Difference, 1, Side::Inside) => Some(piece.reversed()),
+                _ => None,
+            };
+            kept.extend(keep);
+        }
+    }
+
+    let mut outers = Vec::new();
+    let mut holes = Vec::new();
+    for pieces in stitch(kept)? {
+        let loop_segments: Vec<BoundarySegment> =
+            pieces.iter().map(|p| p.curve.to_segment()).collect();
+        let area = signed_area(&loop_segments);
+        if area > TOLERANCE {
+            outers.push(pieces);
+        } else if area < -TOLERANCE {
+            holes.push(pieces.iter().rev().map(Edge::reversed).collect());
+        }
+    }
+
+    let outer = match outers.len() {
+        0 => return Err(BooleanError::Empty),
+        1 => outers.pop().unwrap(),
+        count => return Err(BooleanError::Disconnected { count }),
+    };
+    let boundary = |pieces: Vec<Edge>| {
+        let component = |p: &Edge| {
+            let source = if p.operand == 0 { a } else { b };
+            iter::once(&source.outer)
+                .chain(&source.obstacles)
+                .nth(p.component)
+        };
+        let name = component(&pieces[0]).map_or_else(String::new, |c| c.name.clone());
+        BoundarySpec {
+            name,
+            segments: merged(pieces)
+                .into_iter()
+                .map(|p| {
+                    let label = component(&p)
+                        .map(|c| c.segments[p.segment].label().clone())
+                        .unwrap_or_default();
+                    p.curve.to_spec(p.condition, label)
+                })
+                .collect(),
+            ..Default::default()
+        }
+    };
+
+    let result = TableSpec {
+        outer: boundary(outer),
+        obstacles: holes.into_iter().map(boundary).collect(),
+        ..Default::default()
+    };
+    if validate_table(&result).is_valid() {
+        Ok(result)
+    } else {
+        Err(BooleanError::InvalidResult)
+    }
+}
+
+/// A segment parameterized by the fraction `f` of the way along it, from
+/// 0 at its start to 1 at its end.
+#[derive(Clone, Copy, Debug)]
+enum Curve {
+    Line {
+        start: Vec2,
+        end: Vec2,
+    },
+
+    /// Arc from `start_angle`, sweeping `sweep` radians (counterclockwise
+    /// when positive).
+    Arc {
+        center: Vec2,
+        radius: f64,
+        start_angle: f64,
+        sweep: f64,
+    },
+}
+
+impl Curve {
+    fn from_spec(segment: &SegmentSpec) -> Curve {
+        match *segment {
+            SegmentSpec::Line { start, end, .. } => Curve::Line { start, end },
+            SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+                ccw,
+                ..
+            } => {
+                let sweep = (end_angle - start_angle).abs();
+                Curve::Arc {
+                    center,
+                    radius,
+                    start_angle,
+                    sweep: if ccw { sweep } else { -sweep },
+                }
+            }
+        }
+    }
+
+    fn to_spec(self, condition: BoundaryCondition, label: SegmentLabel) -> SegmentSpec {
+        match self {
+            Curve::Line { start, end } => SegmentSpec::Line {
+                start,
+                end,
+                condition,
+                label,
+            },
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => {
+                // Keep the start angle in [0, 2π) like the built-in tables.
+                let start_angle = start_angle.rem_euclid(TAU);
+                SegmentSpec::CircularArc {
+                    center,
+                    radius,
+                    start_angle,
+                    end_angle: start_angle + sweep,
+                    ccw: sweep > 0.0,
+                    condition,
+                    label,
+                }
+            }
+        }
+    }
+
+    /// The curve as a reflecting boundary segment.
+    fn to_segment(self) -> BoundarySegment {
+        self.to_spec(BoundaryCondition::Reflect, SegmentLabel::default())
+            .to_boundary_segment()
+    }
+
+    fn length(&self) -> f64 {
+        match *self {
+            Curve::Line { start, end } => (end - start).length(),
+            Curve::Arc { radius, sweep, .. } => radius * sweep.abs(),
+        }
+    }
+
+    fn point(&self, f: f64) -> Vec2 {
+        match *self {
+            Curve::Line { start, end } => start + (end - start) * f,
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => {
+                let (sin, cos) = (start_angle + sweep * f).sin_cos();
+                center + Vec2::new(cos, sin) * radius
+            }
+        }
+    }
+
+    /// Unit tangent in the direction of travel.
+    fn tangent(&self, f: f64) -> Vec2 {
+        match *self {
+            Curve::Line { start, end } => (end - start).normalized(),
+            Curve::Arc {
+                start_angle, sweep, ..
+            } => {
+                let (sin, cos) = (start_angle + sweep * f).sin_cos();
+                Vec2::new(-sin, cos) * sweep.signum()
+            }
+        }
+    }
+
+    fn reversed(&self) -> Curve {
+        match *self {
+            Curve::Line { start, end } => Curve::Line {
+                start: end,
+                end: start,
+            },
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => Curve::Arc {
+                center,
+                radius,
+                start_angle: start_angle + sweep,
+                sweep: -sweep,
+            },
+        }
+    }
+
+    /// The part between fractions `from` and `to`.
+    fn slice(&self, from: f64, to: f64) -> Curve {
+        match *self {
+            Curve::Line { .. } => Curve::Line {
+                start: self.point(from),
+                end: self.point(to),
+            },
+            Curve::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => Curve::Arc {
+                center,
+                radius,
+                start_angle: start_angle + sweep * from,
+                sweep: sweep * (to - from),
+            },
+        }
+    }
+
+    /// Fraction of the closest point to `p`, and its distance from `p`.
+    fn project(&self, p: Vec2) -> (f64, f64) {
+        let f = match *self {
+            Curve::Line { start, end } => {
+                let d = end - start;
+                ((p - start).dot(d) / d.dot(d)).clamp(0.0, 1.0)
+            }
+            Curve::Arc {
+                center,
+                start_angle,
+                sweep,
+                ..
+            } => {
+                let m = p - center;
+                let angle = m.y.atan2(m.x);
+                let along = if sweep > 0.0 {
+                    (angle - start_angle).rem_euclid(TAU)
+                } else {
+                    (start_angle - angle).rem_euclid(TAU)
+                };
+                let f = along / sweep.abs();
+                // Beyond the end: one of the ends is closest.
+                [f.min(1.0), 0.0, 1.0]
+                    .into_iter()
+                    .min_by(|&u, &v| {
+                        (self.point(u) - p)
+                            .length()
+                            .total_cmp(&(self.point(v) - p).length())
+                    })
+                    .unwrap()
+            }
+        };
+        (f, (self.point(f) - p).length())
+    }
+
+    /// Points where the lines or circles carrying `self` and `other` cross;
+    /// empty when they are parallel or concentric. The points need not lie
+    /// on either curve.
+    fn crossings(&self, other: &Curve) -> Vec<Vec2> {
+        match (*self, *other) {
+            (Curve::Line { start: p, end: q }, Curve::Line { start: r, end: s }) => {
+                let (d, e) = (q - p, s - r);
+                let denom = d.cross(e);
+                if denom == 0.0 {
+                    Vec::new()
+                } else {
+                    vec![p + d * ((r - p).cross(e) / denom)]
+                }
+            }
+            (Curve::Line { start, end }, Curve::Arc { center, radius, .. })
+            | (Curve::Arc { center, radius, .. }, Curve::Line { start, end }) => {
+                let d = end - start;
+                let m = start - center;
+                let (a, b, c) = (d.dot(d), m.dot(d), m.dot(m) - radius * radius);
+                let discriminant = b * b - a * c;
+                if discriminant < -TOLERANCE * a {
+                    Vec::new()
+                } else {
+                    let root = discriminant.max(0.0).sqrt();
+                    vec![start + d * ((-b - root) / a), start + d * ((-b + root) / a)]
+                }
+            }
+            (
+                Curve::Arc {
+                    center: c1,
+                    radius: r1,
+                    ..
+                },
+                Curve::Arc {
+                    center: c2,
+                    radius: r2,
+                    ..
+                },
+            ) => {
+                let between = c2 - c1;
+                let dist = between.length();
+                if dist <= TOLERANCE
+                    || dist > r1 + r2 + TOLERANCE
+                    || dist < (r1 - r2).abs() - TOLERANCE
+                {
+                    Vec::new()
+                } else {
+                    let along = (r1 * r1 - r2 * r2 + dist * dist) / (2.0 * dist);
+                    let height = (r1 * r1 - along * along).max(0.0).sqrt();
+                    let (u, n) = (between / dist, between.perp() / dist);
+                    let foot = c1 + u * along;
+                    vec![foot + n * height, foot - n * height]
+                }
+            }
+        }
+    }
+}
+
+/// A piece of an operand's boundary, directed so that the operand's region
+/// lies on its left.
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    curve: Curve,
+    condition: BoundaryCondition,
+
+    /// 0 for the first operand, 1 for the second.
+    operand: usize,
+
+    /// Component (0 for the outer boundary) and segment the piece came from.
+    component: usize,
+    segment: usize,
+}
+
+impl Edge {
+    fn reversed(&self) -> Edge {
+        Edge {
+            curve: self.curve.reversed(),
+            ..*self
+        }
+    }
+}
+
+/// The boundary of `spec` with its region on the left: the outer boundary
+/// as it is, obstacles backwards.
+fn edges(spec: &TableSpec, operand: usize) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    for (component, boundary) in iter::once(&spec.outer).chain(&spec.obstacles).enumerate() {
+        let start = edges.len();
+        for (segment, spec) in boundary.segments.iter().enumerate() {
+            let condition = match spec.condition() {
+                BoundaryCondition::PeriodicPairedWith(_) => BoundaryCondition::Reflect,
+                other => other,
+            };
+            let edge = Edge {
+                curve: Curve::from_spec(spec),
+                condition,
+                operand,
+                component,
+                segment,
+            };
+            edges.push(if component == 0 {
+                edge
+            } else {
+                edge.reversed()
+            });
+        }
+        if component > 0 {
+            edges[start..].reverse();
+        }
+    }
+    edges
+}
+
+/// `edges` cut wherever they cross `others` or pass through one of their
+/// ends.
+fn split(edges: &[Edge], others: &[Edge]) -> Vec<Edge> {
+    let mut pieces = Vec::new();
+    for edge in edges {
+        let length = edge.curve.length();
+        let mut cuts: Vec<f64> = Vec::new();
+        for other in others {
+            let crossings = edge
+                .curve
+                .crossings(&other.curve)
+                .into_iter()
+                .filter(|&p| other.curve.project(p).1 <= TOLERANCE);
+            for p in crossings.chain(iter::once(other.curve.point(0.0))) {
+                let (f, distance) = edge.curve.project(p);
+                if distance <= TOLERANCE && f * length > TOLERANCE && (1.0 - f) * length > TOLERANCE
+                {
+                    cuts.push(f);
+                }
+            }
+        }
+        cuts.sort_by(f64::total_cmp);
+        cuts.dedup_by(|later, earlier| (*later - *earlier) * length <= TOLERANCE);
+
+        let bounds: Vec<f64> = iter::once(0.0).chain(cuts).chain(iter::once(1.0)).collect();
+        pieces.extend(bounds.windows(2).map(|w| Edge {
+            curve: edge.curve.slice(w[0], w[1]),
+            ..*edge
+        }));
+    }
+    pieces
+}
+
+/// Where a piece of one boundary lies relative to the other region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Inside,
+    Outside,
+
+    /// On the other boundary, running the same way.
+    Same,
+
+    /// On the other boundary, running the opposite way.
+    Opposite,
+}
+
+/// Where `piece` lies relative to the region bounded by `others`, which
+/// are also given as `boundary` segments.
+fn classify(piece: &Edge, others: &[Edge], boundary: &[BoundarySegment]) -> Side {
+    let midpoint = piece.curve.point(0.5);
+    for other in others {
+        let (f, distance) = other.curve.project(midpoint);
+        if distance <= TOLERANCE {
+            return if other.curve.tangent(f).dot(piece.curve.tangent(0.5)) > 0.0 {
+                Side::Same
+            } else {
+                Side::Opposite
+            };
+        }
+    }
+
+    if encloses(boundary, midpoint) {
+        Side::Inside
+    } else {
+        Side::Outside
+    }
+}
+
+/// Signed angle of the turn from direction `from` to direction `to`.
+fn turn(from: Vec2, to: Vec2) -> f64 {
+    from.cross(to).atan2(from.dot(to))
+}
+
+/// Chain `pieces` into closed loops.
+///
+/// Where several pieces leave the point a loop has reached (two parts of
+/// the result touching at a point), the loop takes the sharpest left turn,
+/// so that it hugs the region on its left and each part gets its own loop.
+fn stitch(pieces: Vec<Edge>) -> Result<Vec<Vec<Edge>>, BooleanError> {
+    let mut used = vec![false; pieces.len()];
+    let mut loops = Vec::new();
+    for first in 0..pieces.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let start = pieces[first].curve.point(0.0);
+        let mut chain = vec![pieces[first]];
+
+        loop {
+            let last = chain.last().unwrap().curve;
+            let end = last.point(1.0);
+            if (end - start).length() <= JOIN_TOLERANCE {
+                break;
+            }
+
+            let incoming = last.tangent(1.0);
+            let next = (0..pieces.len())
+                .filter(|&k| {
+                    !used[k] && (pieces[k].curve.point(0.0) - end).length() <= JOIN_TOLERANCE
+                })
+                .max_by(|&j, &k| {
+                    turn(incoming, pieces[j].curve.tangent(0.0))
+                        .total_cmp(&turn(incoming, pieces[k].curve.tangent(0.0)))
+                })
+                .ok_or(BooleanError::InvalidResult)?;
+            used[next] = true;
+            chain.push(pieces[next]);
+        }
+        loops.push(chain);
+    }
+    Ok(loops)
+}
+
+/// `pieces` of a closed loop with neighbouring pieces of the same original
+/// segment joined again, and the line ends snapped onto their neighbours.
+fn merged(pieces: Vec<Edge>) -> Vec<Edge> {
+    let same_origin = |a: &Edge, b: &Edge| {
+        (a.operand, a.component, a.segment) == (b.operand, b.component, b.segment)
+    };
+    let join = |a: &mut Edge, b: &Edge| match (&mut a.curve, b.curve) {
+        (Curve::Line { end, .. }, Curve::Line { end: next_end, .. }) => *end = next_end,
+        (Curve::Arc { sweep, .. }, Curve::Arc { sweep: next, .. }) => *sweep += next,
+        _ => unreachable!("pieces of one segment have its kind"),
+    };
+
+    let mut joined: Vec<Edge> = Vec::with_capacity(pieces.len());
+    for piece in pieces {
+        match joined.last_mut() {
+            Some(last) if same_origin(last, &piece) => join(last, &piece),
+            _ => joined.push(piece),
+        }
+    }
+    // A segment may also have been cut where the loop starts; an arc that
+    // was a full circle comes back whole.
+    if joined.len() > 1 && same_origin(&joined[joined.len() - 1], &joined[0]) {
+        let first = joined.remove(0);
+        join(joined.last_mut().unwrap(), &first);
+    }
+
+    let n = joined.len();
+    for i in 0..n {
+        let end = joined[i].curve.point(1.0);
+        let next = &mut joined[(i + 1) % n].curve;
+        if let Curve::Line { start, .. } = next {
+            *start = end;
+        }
+    }
+    for i in 0..n {
+        let start = joined[(i + 1) % n].curve.point(0.0);
+        if let Curve::Line { end, .. } = &mut joined[i].curve {
+            *end = start;
+        }
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BooleanError, BooleanOp, combine};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::SegmentLabel;
+    use crate::geometry::standard_tables::{circle, rectangle};
+    use crate::geometry::table_spec::{SegmentSpec, TableSpec};
+    use crate::geometry::transform::Transform;
+    use crate::geometry::validation::validate_table;
+    use std::f64::consts::PI;
+
+    /// Area of the region of a table.
+    fn area(spec: &TableSpec) -> f64 {
+        spec.to_billiard_table().domain_area()
+    }
+
+    fn square(x: f64, y: f64, side: f64) -> TableSpec {
+        rectangle(side, side).translated(Vec2::new(x, y))
+    }
+
+    #[test]
+    fn disks_cut_holes_and_bites() {
+        let disk = circle(0.2).translated(Vec2::new(0.5, 0.5));
+        let sinai = square(0.0, 0.0, 1.0).difference(&disk).unwrap();
+        assert_eq!(sinai.outer.segments.len(), 4);
+        assert_eq!(sinai.obstacles.len(), 1);
+        assert_eq!(sinai.obstacles[0].segments.len(), 1);
+        assert!((area(&sinai) - (1.0 - PI * 0.04)).abs() < 1e-12);
+
+        // A disk centered on the right wall takes a half-disk bite.
+        let bitten = square(0.0, 0.0, 1.0)
+            .difference(&circle(0.2).translated(Vec2::new(1.0, 0.5)))
+            .unwrap();
+        assert!(bitten.obstacles.is_empty());
+        assert_eq!(bitten.outer.segments.len(), 6);
+        assert!((area(&bitten) - (1.0 - 0.5 * PI * 0.04)).abs() < 1e-12);
+
+        let capped = square(0.0, 0.0, 1.0)
+            .union(&circle(0.2).translated(Vec2::new(1.0, 0.5)))
+            .unwrap();
+        assert!((area(&capped) - (1.0 + 0.5 * PI * 0.04)).abs() < 1e-12);
+        assert!(validate_table(&capped).is_valid());
+    }
+
+    #[test]
+    fn pieces_keep_the_labels_of_their_segments() {
+        let label = |segment: &mut SegmentSpec, id: u32, name: &str| {
+            let (SegmentSpec::Line { label, .. } | SegmentSpec::CircularArc { label, .. }) =
+                segment;
+            *label = SegmentLabel {
+                id: Some(id),
+                name: Some(name.into()),
+            };
+        };
+        let mut a = square(0.0, 0.0, 1.0);
+        label(&mut a.outer.segments[1], 1, "right wall");
+        let mut disk = circle(0.2).translated(Vec2::new(1.0, 0.5));
+        label(&mut disk.outer.segments[0], 2, "bite");
+
+        // The right wall is cut in two around the bite.
+        let bitten = a.difference(&disk).unwrap();
+        let names: Vec<_> = bitten
+            .outer
+            .segments
+            .iter()
+            .filter_map(|segment| segment.label().name.as_deref())
+            .collect();
+        assert_eq!(names, ["right wall", "bite", "right wall"]);
+    }
+
+    #[test]
+    fn overlapping_squares() {
+        let a = square(0.0, 0.0, 2.0);
+        let b = square(1.0, 1.0, 2.0);
+        let expected = [
+            (BooleanOp::Union, 7.0, 8),
+            (BooleanOp::Intersection, 1.0, 4),
+            (BooleanOp::Difference, 3.0, 6),
+        ];
+        for (op, expected_area, sides) in expected {
+            let result = combine(&a, &b, op).unwrap();
+            assert!((area(&result) - expected_area).abs() < 1e-12, "{:?}", op);
+            assert_eq!(result.outer.segments.len(), sides, "{:?}", op);
+        }
+
+        // Squares sharing an edge merge into a rectangle; its long sides
+        // stay in two pieces each.
+        let merged = square(0.0, 0.0, 1.0).union(&square(1.0, 0.0, 1.0)).unwrap();
+        assert_eq!(merged.outer.segments.len(), 6);
+        assert!((area(&merged) - 2.0).abs() < 1e-12);
+        assert_eq!(
+            square(0.0, 0.0, 1.0).difference(&square(0.0, 0.0, 1.0)),
+            Err(BooleanError::Empty)
+        );
+    }
+
+    #[test]
+    fn results_must_be_single_tables() {
+        let a = square(0.0, 0.0, 1.0);
+        assert_eq!(
+            a.union(&square(2.0, 0.0, 1.0)),
+            Err(BooleanError::Disconnected { count: 2 })
+        );
+        assert_eq!(
+            a.intersection(&square(2.0, 0.0, 1.0)),
+            Err(BooleanError::Empty)
+        );
+        // Cutting a vertical strip out of the middle splits the square.
+        let strip = rectangle(0.2, 3.0).translated(Vec2::new(0.4, -1.0));
+        assert_eq!(
+            a.difference(&strip),
+            Err(BooleanError::Disconnected { count: 2 })
+        );
+
+        let mut open = a.clone();
+        open.outer.segments.pop();
+        assert_eq!(
+            open.union(&a),
+            Err(BooleanError::InvalidOperand { operand: 0 })
+        );
+    }
+}
@@ -5,30 +5,189 @@
 //! - support arc-length parametrization,
 //! - distinguish outer boundary vs internal obstacles (Sinai billiards).
 
+use super::arc_length::integrate;
+use super::line_batch::LineBatch;
 use super::primitives::Vec2;
-use super::segments::BoundarySegment;
+use super::segments::{BoundaryCondition, BoundarySegment};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+use std::fmt;
 use std::iter;
 
-/// A closed boundary component built from an ordered list of segments.
+/// Junctions whose unit tangents satisfy `dot >= 1 - SMOOTH_JUNCTION_TOLERANCE`
+/// are treated as smooth rather than as corners.
+const SMOOTH_JUNCTION_TOLERANCE: f64 = 1e-9;
+
+/// Largest gap between consecutive segments accepted as a closed joint.
+pub const CLOSURE_TOLERANCE: f64 = 1e-9;
+
+/// Segments no longer than this are rejected as degenerate: their tangent
+/// is too short to normalize.
+pub const MIN_SEGMENT_LENGTH: f64 = 1e-10;
+
+/// Equal pieces an external curve is cut into before
+/// [`BoundaryComponent::discretize`] refines them, so that a wiggle whose
+/// midpoint happens to lie on the chord is not missed.
+const EXTERNAL_INITIAL_PIECES: usize = 8;
+
+/// Pieces an external curve is cut into for the quadrature in
+/// [`BoundaryComponent::signed_area`].
+const AREA_QUADRATURE_PIECES: usize = 64;
+
+/// Deepest bisection of an external curve in
+/// [`BoundaryComponent::discretize`]: at most 2^16 points per piece.
+const MAX_BISECTION_DEPTH: usize = 16;
+
+/// Direction of the ray used for inside tests; chosen so that it is
+/// unlikely to pass exactly through a vertex.
+const RAY_ANGLE: f64 = 0.618_033_988_7;
+
+/// Why a list of segments does not make a usable boundary component.
+///
+/// Segment indices refer to the component's own segment list.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum GeometryError {
+    /// The component has no segments.
+    EmptyComponent,
+
+    /// Segment `index` has a NaN or infinite coordinate, radius or angle.
+    NonFinite { index: usize },
+
+    /// Segment `index` is no longer than [`MIN_SEGMENT_LENGTH`].
+    DegenerateSegment { index: usize },
+
+    /// Arc `index` has a radius that is not positive.
+    ZeroRadius { index: usize },
+
+    /// Segment `index` ends `gap` away from the start of the next one.
+    NotClosed { index: usize, gap: f64 },
+
+    /// Segment `index` is periodic, paired with segment `paired`, which
+    /// does not exist.
+    InvalidPeriodicPair { index: usize, paired: usize },
+
+    /// Segment `index` vanishes when the table is shrunk for a ball of
+    /// finite radius: the ball is too large to fit along it.
+    BallTooLarge { index: usize },
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryError::EmptyComponent => write!(f, "boundary has no segments"),
+            GeometryError::NonFinite { index } => {
+                write!(f, "segment {} has a non-finite parameter", index)
+            }
+            GeometryError::DegenerateSegment { index } => {
+                write!(f, "segment {} has (nearly) zero length", index)
+            }
+            GeometryError::ZeroRadius { index } => {
+                write!(f, "segment {} has a non-positive radius", index)
+            }
+            GeometryError::NotClosed { index, gap } => write!(
+                f,
+                "segment {} ends {:.3e} away from the start of the next segment",
+                index, gap
+            ),
+            GeometryError::InvalidPeriodicPair { index, paired } => write!(
+                f,
+                "segment {} is paired with nonexistent segment {}",
+                index, paired
+            ),
+            GeometryError::BallTooLarge { index } => {
+                write!(f, "segment {} vanishes: the ball is too large", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+/// The role a boundary component plays in the billiard domain.
+///
+/// The role determines which side of the component the billiard domain
+/// lies on, and therefore the direction of the domain-inward normal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComponentRole {
+    /// The outer wall: the domain lies to the left of the CCW tangent.
+    Outer,
+    /// An internal scatterer: the domain lies to the right of the CCW tangent.
+    Obstacle,
+}
+
+/// An absorbing arc-length interval `[start, end]` on a boundary component.
 ///
-/// For now, this represents the **outer boundary** only.
+/// A particle whose collision lands in a hole leaves the table. If
+/// `start > end`, the hole wraps through `s = 0`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Hole {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Hole {
+    /// Whether arc-length `s` (already wrapped into `[0, length)`) lies in the hole.
+    pub fn contains(&self, s: f64) -> bool {
+        if self.start <= self.end {
+            (self.start..=self.end).contains(&s)
+        } else {
+            s >= self.start || s <= self.end
+        }
+    }
+}
+
+/// A non-smooth junction between two consecutive segments of a component.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Corner {
+    /// Index of the segment ending at the corner.
+    pub before: usize,
+
+    /// Index of the segment starting at the corner.
+    pub after: usize,
+
+    /// World-space position of the corner.
+    pub point: Vec2,
+
+    /// Unit normal bisecting the inward normals of the two segments.
+    ///
+    /// Falls back to the inward normal of `after` for a cusp, where the two
+    /// normals cancel.
+    pub bisector_normal: Vec2,
+}
+
+/// A closed boundary component built from an ordered list of segments.
 ///
 /// Assumptions at this stage:
 /// - Segments are provided in order and their endpoints match up,
-///   forming a closed loop (not yet validated).
-/// - Orientation is counterclockwise (CCW).
+///   forming a closed loop (checked only by
+///   [`BoundaryComponent::check_closed`]).
+/// - Orientation is counterclockwise (CCW), for the outer boundary and
+///   obstacles alike.
 pub struct BoundaryComponent {
     /// Human-readable label for this component.
     pub name: String,
 
+    /// Whether this is the outer wall or an internal obstacle; see
+    /// [`BoundaryComponent::role`].
+    pub(crate) role: ComponentRole,
+
     /// Ordered list of boundary segments.
     pub segments: Vec<BoundarySegment>,
 
+    /// Absorbing arc-length intervals (empty for a fully reflecting wall).
+    pub holes: Vec<Hole>,
+
     /// cumulative_lengths[i] = total length of segments[0..=i]
     cumulative_lengths: Vec<f64>,
 
     /// Total arc length of the component (sum of segment lengths).
     total_length: f64,
+
+    /// The line segments, laid out for batched ray tests.
+    lines: LineBatch,
 }
 
 impl BoundaryComponent {
@@ -37,36 +196,128 @@ impl BoundaryComponent {
     /// This constructor:
     /// - takes ownership of the segments,
     /// - precomputes cumulative arc-lengths,
-    /// - stores the total length.
+    /// - stores the total length,
+    /// - checks that periodic segments are paired with existing segments.
     ///
-    /// It does NOT yet:
-    /// - verify that the contour is closed,
+    /// It does NOT:
+    /// - verify that the contour is closed (see
+    ///   [`BoundaryComponent::check_closed`]),
     /// - check orientation,
-    /// - detect self-intersections.
+    /// - detect self-intersections (see
+    ///   [`validate_table`](crate::geometry::validation::validate_table)).
+    ///
+    /// The component is created as the outer wall, like
+    /// [`BoundaryComponent::outer`]; use [`BoundaryComponent::obstacle`]
+    /// for internal scatterers. [`BilliardTable::new`] sets the role of
+    /// each component from its place in the table either way.
+    ///
+    /// # Panics
+    /// Panics wherever [`BoundaryComponent::try_new`] would return an error.
     pub fn new(name: impl Into<String>, segments: Vec<BoundarySegment>) -> Self {
-        assert!(
-            !segments.is_empty(),
-            "BoundaryComponent must have at least one segment"
-        );
+        Self::try_new(name, segments).unwrap_or_else(|e| panic!("Invalid boundary: {}", e))
+    }
+
+    /// Fallible version of [`BoundaryComponent::new`]: rejects an empty
+    /// segment list, segments without a finite positive length, and
+    /// periodic pairings with nonexistent segments.
+    pub fn try_new(
+        name: impl Into<String>,
+        segments: Vec<BoundarySegment>,
+    ) -> Result<Self, GeometryError> {
+        if segments.is_empty() {
+            return Err(GeometryError::EmptyComponent);
+        }
 
         let mut cumulative_lengths = Vec::with_capacity(segments.len());
         let mut running = 0.0;
 
-        for &segment in &segments {
+        for (index, segment) in segments.iter().enumerate() {
             let len = segment.length();
-            assert!(len > 0.0, "Boundary segments must have positive length");
+            if !len.is_finite() {
+                return Err(GeometryError::NonFinite { index });
+            }
+            if len <= MIN_SEGMENT_LENGTH {
+                return Err(GeometryError::DegenerateSegment { index });
+            }
             running += len;
             cumulative_lengths.push(running);
         }
 
         let total_length = running;
 
-        Self {
+        for (index, segment) in segments.iter().enumerate() {
+            if let BoundaryCondition::PeriodicPairedWith(paired) = segment.condition()
+                && paired >= segments.len()
+            {
+                return Err(GeometryError::InvalidPeriodicPair { index, paired });
+            }
+        }
+
+        let lines = LineBatch::new(&segments);
+        Ok(Self {
             name: name.into(),
+            role: ComponentRole::Outer,
             segments,
+            holes: Vec::new(),
             cumulative_lengths,
             total_length,
+            lines,
+        })
+    }
+
+    /// Check that every segment ends (within [`CLOSURE_TOLERANCE`]) where
+    /// the next one starts.
+    pub fn check_closed(&self) -> Result<(), GeometryError> {
+        let n = self.segments.len();
+        for index in 0..n {
+            let segment = &self.segments[index];
+            let next = &self.segments[(index + 1) % n];
+            let gap = (next.point_at(0.0) - segment.point_at(segment.length())).length();
+            if gap > CLOSURE_TOLERANCE {
+                return Err(GeometryError::NotClosed { index, gap });
+            }
         }
+        Ok(())
+    }
+
+    /// Construct the outer wall of a table, with the
+    /// [`ComponentRole::Outer`] role.
+    ///
+    /// # Panics
+    /// Panics wherever [`BoundaryComponent::try_new`] would return an error.
+    pub fn outer(name: impl Into<String>, segments: Vec<BoundarySegment>) -> Self {
+        Self::new(name, segments).with_role(ComponentRole::Outer)
+    }
+
+    /// Construct an internal obstacle component, with the
+    /// [`ComponentRole::Obstacle`] role.
+    ///
+    /// # Panics
+    /// Panics wherever [`BoundaryComponent::try_new`] would return an error.
+    pub fn obstacle(name: impl Into<String>, segments: Vec<BoundarySegment>) -> Self {
+        Self::new(name, segments).with_role(ComponentRole::Obstacle)
+    }
+
+    /// Whether this is the outer wall or an internal obstacle.
+    pub fn role(&self) -> ComponentRole {
+        self.role
+    }
+
+    pub(crate) fn with_role(mut self, role: ComponentRole) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// Mark the given arc-length intervals as absorbing.
+    pub fn with_holes(mut self, holes: Vec<Hole>) -> Self {
+        self.holes = holes;
+        self
+    }
+
+    /// Whether the boundary point at arc-length `s` lies in a hole.
+    pub fn is_absorbing(&self, s: f64) -> bool {
+        let s = s.rem_euclid(self.total_length);
+        self.holes.iter().any(|hole| hole.contains(s))
     }
 
     /// Returns the total arc length of this boundary component.
@@ -74,6 +325,28 @@ impl BoundaryComponent {
         self.total_length
     }
 
+    /// The line segments of this component, for testing a ray against all
+    /// of them at once.
+    pub fn line_batch(&self) -> &LineBatch {
+        &self.lines
+    }
+
+    /// Area enclosed by the component, positive when it runs counterclockwise
+    /// (as it should) and negative otherwise.
+    ///
+    /// Computed by Green's theorem as half the integral of `x dy - y dx`,
+    /// which is exact for lines and arcs; external curves are integrated
+    /// numerically. The component is assumed closed and simple.
+    pub fn signed_area(&self) -> f64 {
+        signed_area(&self.segments)
+    }
+
+    /// Whether `p` lies in the region enclosed by the component, whichever
+    /// way it runs. Points on the component itself may go either way.
+    pub fn encloses(&self, p: Vec2) -> bool {
+        encloses(&self.segments, p)
+    }
+
     /// Maps a global arc-length parameter `s` to a segment index and local `t`.
     ///
     /// - `s` may be outside [0, length); it will be wrapped using Euclidean
@@ -116,6 +389,12 @@ impl BoundaryComponent {
         }
     }
 
+    /// Like [`BoundaryComponent::locate`], but `None` for a non-finite `s`
+    /// rather than a meaningless segment.
+    pub fn checked_locate(&self, s: f64) -> Option<(usize, f64)> {
+        s.is_finite().then(|| self.locate(s))
+    }
+
     /// Returns the world-space point and unit tangent at global arc-length `s`.
     ///
     /// - `s` may be outside [0, length); it will be wrapped using Euclidean
@@ -128,22 +407,152 @@ impl BoundaryComponent {
         (seg.point_at(local_t), seg.tangent_at(local_t))
     }
 
+    /// Returns the signed curvature at global arc-length `s` (wrapped like
+    /// [`point_and_tangent_at`](Self::point_and_tangent_at)).
+    ///
+    /// The sign follows the segment orientation: positive where the boundary
+    /// turns left in the direction of increasing `s`, so every arc of a CCW
+    /// circle has curvature `1/r`, whether it bounds the table or an obstacle.
+    pub fn curvature_at(&self, s: f64) -> f64 {
+        let (seg_idx, local_t) = self.locate(s);
+        self.segments[seg_idx].curvature_at(local_t)
+    }
+
     /// Returns the world-space point and inward-pointing unit normal
     /// at global arc-length `s`.
     ///
-    /// Assumes:
-    /// - This component is the **outer boundary** of the billiard table.
-    /// - The boundary is oriented **counterclockwise (CCW)**.
-    ///
-    /// Under these assumptions, the inward normal is obtained by rotating
-    /// the unit tangent +90 degrees ("left turn").
+    /// "Inward" means into the billiard domain. Assuming the component is
+    /// oriented **counterclockwise (CCW)**:
+    /// - for the outer boundary, the normal is the tangent rotated +90
+    ///   degrees ("left turn");
+    /// - for an obstacle, the domain lies outside the component, so the
+    ///   normal is the tangent rotated -90 degrees.
     pub fn point_and_inward_normal_at(&self, s: f64) -> (Vec2, Vec2) {
         let (point, tangent) = self.point_and_tangent_at(s);
-        let normal = tangent.perp();
-        let inward = normal
+        (point, self.inward_normal_from_tangent(tangent))
+    }
+
+    /// Rotate a CCW tangent into the domain-inward normal for this role.
+    fn inward_normal_from_tangent(&self, tangent: Vec2) -> Vec2 {
+        let normal = match self.role {
+            ComponentRole::Outer => tangent.perp(),
+            ComponentRole::Obstacle => -tangent.perp(),
+        };
+        normal
             .try_normalized()
-            .expect("Tangent should not be near-zero in a valid boundary.");
-        (point, inward)
+            .expect("Tangent should not be near-zero in a valid boundary.")
+    }
+
+    /// Returns the corner closest to local parameter `local_t` on segment
+    /// `segment_index`, if it lies within `tolerance` (in arc-length) of a
+    /// segment junction.
+    ///
+    /// Junctions where the tangent is continuous (e.g. a line meeting an arc
+    /// tangentially, as in a stadium) are not corners and yield `None`.
+    pub fn corner_near(
+        &self,
+        segment_index: usize,
+        local_t: f64,
+        tolerance: f64,
+    ) -> Option<Corner> {
+        let n = self.segments.len();
+        let seg_len = self.segments[segment_index].length();
+
+        let (before, after) = if local_t <= tolerance {
+            ((segment_index + n - 1) % n, segment_index)
+        } else if local_t >= seg_len - tolerance {
+            (segment_index, (segment_index + 1) % n)
+        } else {
+            return None;
+        };
+
+        let before_seg = &self.segments[before];
+        let after_seg = &self.segments[after];
+        let t_before = before_seg.tangent_at(before_seg.length());
+        let t_after = after_seg.tangent_at(0.0);
+
+        // Tangent-continuous junctions are smooth boundary points.
+        if t_before.dot(t_after) > 1.0 - SMOOTH_JUNCTION_TOLERANCE {
+            return None;
+        }
+
+        let n_before = self.inward_normal_from_tangent(t_before);
+        let n_after = self.inward_normal_from_tangent(t_after);
+        let bisector_normal = (n_before + n_after).try_normalized().unwrap_or(n_after);
+
+        Some(Corner {
+            before,
+            after,
+            point: after_seg.point_at(0.0),
+            bisector_normal,
+        })
+    }
+
+    /// Returns the arc-length `s` of the boundary point closest to `p`, and
+    /// the distance to it.
+    ///
+    /// Ties (e.g. a point equidistant from two sides) go to the earlier
+    /// segment.
+    pub fn closest_point(&self, p: Vec2) -> (f64, f64) {
+        let (segment_index, local_t, distance) = self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, seg)| {
+                let (t, d) = seg.closest_point(p);
+                (i, t, d)
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .expect("BoundaryComponent has at least one segment");
+        let s = self.global_s_from_segment_local(segment_index, local_t);
+        (s.rem_euclid(self.total_length), distance)
+    }
+
+    /// Points along the component making a closed polyline that stays
+    /// within `max_chord_error` of the boundary, e.g. for plotting.
+    ///
+    /// Every segment contributes its start point; lines need nothing more.
+    /// Arcs are cut into equal steps whose chords bulge by at most the
+    /// tolerance, and other curves are bisected until the midpoint of each
+    /// piece is that close to its chord. The first point is not repeated at
+    /// the end.
+    ///
+    /// # Panics
+    /// Panics if `max_chord_error` is not positive and finite.
+    pub fn discretize(&self, max_chord_error: f64) -> Vec<Vec2> {
+        assert!(
+            max_chord_error.is_finite() && max_chord_error > 0.0,
+            "Chord error must be positive and finite."
+        );
+
+        let mut points = Vec::new();
+        for segment in &self.segments {
+            let length = segment.length();
+            match segment {
+                BoundarySegment::Line(line) => points.push(line.start),
+                BoundarySegment::CircularArc(arc) => {
+                    // A chord spanning the angle Δ bulges by r (1 - cos(Δ / 2)).
+                    let step = 2.0 * (1.0 - max_chord_error / arc.radius).max(-1.0).acos();
+                    let sweep = length / arc.radius;
+                    let pieces = (sweep / step).ceil().max(1.0) as usize;
+                    points.extend(
+                        (0..pieces).map(|k| segment.point_at(length * k as f64 / pieces as f64)),
+                    );
+                }
+                BoundarySegment::External(_) => {
+                    let pieces = EXTERNAL_INITIAL_PIECES;
+                    for k in 0..pieces {
+                        let (a, b) = (
+                            length * k as f64 / pieces as f64,
+                            length * (k + 1) as f64 / pieces as f64,
+                        );
+                        points.push(segment.point_at(a));
+                        bisect_curve(segment, a, b, max_chord_error, 0, &mut points);
+                    }
+                }
+            }
+        }
+        points
     }
 
     /// Convert a local parameter on a given segment into the global arc-length `s`.
@@ -162,25 +571,163 @@ impl BoundaryComponent {
             self.cumulative_lengths[segment_index - 1] + local_t
         }
     }
+
+    /// Like [`BoundaryComponent::global_s_from_segment_local`], but `None`
+    /// unless `segment_index` exists and `local_t` lies in
+    /// `[0, segment_length]`.
+    pub fn checked_global_s(&self, segment_index: usize, local_t: f64) -> Option<f64> {
+        let segment = self.segments.get(segment_index)?;
+        (0.0..=segment.length())
+            .contains(&local_t)
+            .then(|| self.global_s_from_segment_local(segment_index, local_t))
+    }
+}
+
+/// Signed area enclosed by the closed loop of `segments`; see
+/// [`BoundaryComponent::signed_area`].
+pub(crate) fn signed_area(segments: &[BoundarySegment]) -> f64 {
+    segments.iter().map(area_term).sum()
+}
+
+/// The contribution of `segment` to [`signed_area`].
+fn area_term(segment: &BoundarySegment) -> f64 {
+    match segment {
+        BoundarySegment::Line(line) => 0.5 * line.start.cross(line.end),
+        BoundarySegment::CircularArc(arc) => {
+            let sweep = (arc.end_angle - arc.start_angle).abs();
+            let sweep = if arc.ccw { sweep } else { -sweep };
+            0.5 * (arc.center.cross(arc.end - arc.start) + arc.radius * arc.radius * sweep)
+        }
+        BoundarySegment::External(_) => {
+            let integrand = |t: f64| 0.5 * segment.point_at(t).cross(segment.tangent_at(t));
+            let step = segment.length() / AREA_QUADRATURE_PIECES as f64;
+            (0..AREA_QUADRATURE_PIECES)
+                .map(|k| integrate(&integrand, k as f64 * step, (k + 1) as f64 * step))
+                .sum()
+        }
+    }
+}
+
+/// Whether `p` is inside the closed, simple loop of `segments`, by the
+/// parity of the crossings of a ray from `p`.
+pub(crate) fn encloses(segments: &[BoundarySegment], p: Vec2) -> bool {
+    let d = Vec2::new(RAY_ANGLE.cos(), RAY_ANGLE.sin());
+    let crossings: usize = segments.iter().map(|s| ray_crossings(s, p, d)).sum();
+    crossings % 2 == 1
+}
+
+/// How many times the ray from `p` along the unit vector `d` crosses
+/// `segment`, counting its start but not its end, so that a ray through a
+/// joint counts once. External curves are taken as a fine polyline.
+fn ray_crossings(segment: &BoundarySegment, p: Vec2, d: Vec2) -> usize {
+    let line_crossing = |start: Vec2, end: Vec2| {
+        let e = end - start;
+        let denom = d.cross(e);
+        if denom == 0.0 {
+            return 0;
+        }
+        let w = start - p;
+        let (t, u) = (w.cross(e) / denom, w.cross(d) / denom);
+        usize::from(t > 0.0 && (0.0..1.0).contains(&u))
+    };
+    match segment {
+        BoundarySegment::Line(line) => line_crossing(line.start, line.end),
+        BoundarySegment::CircularArc(arc) => {
+            let m = p - arc.center;
+            let b = m.dot(d);
+            let discriminant = b * b - (m.dot(m) - arc.radius * arc.radius);
+            if discriminant <= 0.0 {
+                return 0;
+            }
+            let sweep = (arc.end_angle - arc.start_angle).abs();
+            let root = discriminant.sqrt();
+            [-b - root, -b + root]
+                .into_iter()
+                .filter(|&t| {
+                    let hit = m + d * t;
+                    let angle = hit.y.atan2(hit.x);
+                    let along = if arc.ccw {
+                        (angle - arc.start_angle).rem_euclid(TAU)
+                    } else {
+                        (arc.start_angle - angle).rem_euclid(TAU)
+                    };
+                    t > 0.0 && along < sweep
+                })
+                .count()
+        }
+        BoundarySegment::External(_) => {
+            let step = segment.length() / AREA_QUADRATURE_PIECES as f64;
+            (0..AREA_QUADRATURE_PIECES)
+                .map(|k| {
+                    line_crossing(
+                        segment.point_at(k as f64 * step),
+                        segment.point_at((k + 1) as f64 * step),
+                    )
+                })
+                .sum()
+        }
+    }
+}
+
+/// Push the interior points of the piece `a..b` of `segment` needed to
+/// keep each chord within `tolerance` of it, in order.
+fn bisect_curve(
+    segment: &BoundarySegment,
+    a: f64,
+    b: f64,
+    tolerance: f64,
+    depth: usize,
+    points: &mut Vec<Vec2>,
+) {
+    let mid = 0.5 * (a + b);
+    let (p, q, m) = (
+        segment.point_at(a),
+        segment.point_at(b),
+        segment.point_at(mid),
+    );
+    let chord = q - p;
+    let deviation = match chord.try_normalized() {
+        Some(u) => (m - p).dot(u.perp()).abs(),
+        None => (m - p).length(),
+    };
+    if deviation <= tolerance || depth >= MAX_BISECTION_DEPTH {
+        return;
+    }
+
+    bisect_curve(segment, a, mid, tolerance, depth + 1, points);
+    points.push(m);
+    bisect_curve(segment, mid, b, tolerance, depth + 1, points);
 }
 
 /// A full billiard table: an outer boundary plus zero or more internal obstacles.
-///
-/// At this stage:
-/// - Only the outer boundary is actually used.
-/// - Obstacles are present for future Sinai-style tables and may be empty.
 pub struct BilliardTable {
     pub outer: BoundaryComponent,
     pub obstacles: Vec<BoundaryComponent>,
 }
 
 impl BilliardTable {
+    /// Assemble a table, assigning the [`ComponentRole`] of each component
+    /// from its position (outer wall vs obstacle).
+    pub fn new(mut outer: BoundaryComponent, mut obstacles: Vec<BoundaryComponent>) -> Self {
+        outer.role = ComponentRole::Outer;
+        for obstacle in &mut obstacles {
+            obstacle.role = ComponentRole::Obstacle;
+        }
+        Self { outer, obstacles }
+    }
+
     /// Returns the total number of boundary components (outer + obstacles).
     pub fn component_count(&self) -> usize {
         // Concept: 1 (outer) + number of obstacles.
         1 + self.obstacles.len()
     }
 
+    /// The component with the given index: 0 for the outer boundary, then
+    /// the obstacles.
+    ///
+    /// # Panics
+    /// Panics if there is no such component; see
+    /// [`BilliardTable::get_component`].
     pub fn component(&self, index: usize) -> &BoundaryComponent {
         if index == 0 {
             &self.outer
@@ -189,6 +736,46 @@ impl BilliardTable {
         }
     }
 
+    /// The component with the given index, or `None` if there is none.
+    pub fn get_component(&self, index: usize) -> Option<&BoundaryComponent> {
+        match index {
+            0 => Some(&self.outer),
+            _ => self.obstacles.get(index - 1),
+        }
+    }
+
+    /// Area of the billiard domain: inside the outer boundary and outside
+    /// every obstacle. Obstacles are assumed disjoint and inside the outer
+    /// boundary.
+    pub fn domain_area(&self) -> f64 {
+        self.outer.signed_area() - self.obstacles.iter().map(|o| o.signed_area()).sum::<f64>()
+    }
+
+    /// Whether `p` lies in the billiard domain: inside the outer boundary
+    /// and outside every obstacle. Points on the boundary may go either
+    /// way.
+    pub fn contains(&self, p: Vec2) -> bool {
+        self.outer.encloses(p) && !self.obstacles.iter().any(|o| o.encloses(p))
+    }
+
+    /// Total length of the boundary of the domain, obstacles included.
+    pub fn perimeter(&self) -> f64 {
+        self.components().map(|c| c.length()).sum()
+    }
+
+    /// Returns `(component_index, s, distance)` for the boundary point
+    /// closest to `p` over all components.
+    pub fn closest_boundary_point(&self, p: Vec2) -> (usize, f64, f64) {
+        self.components()
+            .enumerate()
+            .map(|(i, component)| {
+                let (s, d) = component.closest_point(p);
+                (i, s, d)
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .expect("A table always has an outer boundary")
+    }
+
     /// Returns an iterator over all boundary components, starting with the outer one.
     pub fn components(&self) -> impl Iterator<Item = &BoundaryComponent> {
         // Concept: chain a single-element iterator over `outer` with an iterator over `obstacles`.
@@ -198,9 +785,10 @@ impl BilliardTable {
 
 #[cfg(test)]
 mod tests {
-    use super::BoundaryComponent;
+    use super::{BilliardTable, BoundaryComponent, ComponentRole};
     use crate::geometry::primitives::Vec2;
     use crate::geometry::segments::{BoundarySegment, LineSegment};
+    use proptest::prelude::*;
 
     #[test]
     fn locate_maps_s_to_correct_segment_and_local_t() {
@@ -224,6 +812,29 @@ mod tests {
         assert!((t1 - 1.0).abs() < 1e-12); // since we are 1 unit into segment 1
     }
 
+    #[test]
+    fn curvature_follows_segments() {
+        use crate::geometry::segments::CircularArcSegment;
+        use std::f64::consts::PI;
+
+        // Unit square edge followed by a CW half-circle of radius 0.5.
+        let line =
+            BoundarySegment::Line(LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)));
+        let arc = BoundarySegment::CircularArc(CircularArcSegment::new(
+            Vec2::new(0.5, 0.0),
+            0.5,
+            0.0,
+            -PI,
+            false,
+        ));
+        let bc = BoundaryComponent::new("test", vec![line, arc]);
+
+        assert_eq!(bc.curvature_at(0.5), 0.0);
+        assert_eq!(bc.curvature_at(1.5), -2.0);
+        // Wraps like every other arc-length query.
+        assert_eq!(bc.curvature_at(bc.length() + 0.5), 0.0);
+    }
+
     #[test]
     fn point_tangent_and_normal_have_expected_directions() {
         use crate::geometry::primitives::Vec2;
@@ -282,4 +893,192 @@ mod tests {
         assert!((n.x - 0.0).abs() < 1e-12);
         assert!((n.y - 1.0).abs() < 1e-12);
     }
+
+    #[test]
+    fn obstacle_inward_normal_points_away_from_obstacle() {
+        use crate::geometry::segments::CircularArcSegment;
+
+        let circle = BoundarySegment::CircularArc(CircularArcSegment::new(
+            Vec2::new(0.0, 0.0),
+            1.0,
+            0.0,
+            std::f64::consts::TAU,
+            true,
+        ));
+        let square = vec![
+            BoundarySegment::Line(LineSegment::new(
+                Vec2::new(-2.0, -2.0),
+                Vec2::new(2.0, -2.0),
+            )),
+            BoundarySegment::Line(LineSegment::new(Vec2::new(2.0, -2.0), Vec2::new(2.0, 2.0))),
+            BoundarySegment::Line(LineSegment::new(Vec2::new(2.0, 2.0), Vec2::new(-2.0, 2.0))),
+            BoundarySegment::Line(LineSegment::new(
+                Vec2::new(-2.0, 2.0),
+                Vec2::new(-2.0, -2.0),
+            )),
+        ];
+        let table = BilliardTable::new(
+            BoundaryComponent::new("outer", square),
+            vec![BoundaryComponent::new("disk", vec![circle])],
+        );
+
+        assert_eq!(table.outer.role, ComponentRole::Outer);
+        assert_eq!(table.obstacles[0].role, ComponentRole::Obstacle);
+
+        // At s = 0 the disk point is (1, 0); the domain lies in +x.
+        let (p, n) = table.obstacles[0].point_and_inward_normal_at(0.0);
+        assert!((p.x - 1.0).abs() < 1e-12);
+        assert!((n.x - 1.0).abs() < 1e-12);
+        assert!(n.y.abs() < 1e-12);
+
+        // Outer bottom edge midpoint: domain lies in +y.
+        let (_, n_outer) = table.outer.point_and_inward_normal_at(2.0);
+        assert!((n_outer.y - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn closest_boundary_point_searches_every_component() {
+        use crate::geometry::standard_tables::sinai;
+
+        let table = sinai(1.0, 0.2).to_billiard_table();
+
+        // Nearer the bottom wall than the disk.
+        let (component, s, distance) = table.closest_boundary_point(Vec2::new(0.5, 0.1));
+        assert_eq!(component, 0);
+        assert!((s - 0.5).abs() < 1e-12);
+        assert!((distance - 0.1).abs() < 1e-12);
+
+        // Just above the disk: the foot is its top point.
+        let (component, s, distance) = table.closest_boundary_point(Vec2::new(0.5, 0.75));
+        assert_eq!(component, 1);
+        assert!((distance - 0.05).abs() < 1e-12);
+        let (foot, _) = table.component(1).point_and_tangent_at(s);
+        assert!((foot - Vec2::new(0.5, 0.7)).length() < 1e-12);
+
+        // Past a corner the foot is the corner itself, at either end of the
+        // parametrization.
+        let (s, distance) = table.outer.closest_point(Vec2::new(-0.3, -0.4));
+        assert!(s.abs() < 1e-12 || (s - table.outer.length()).abs() < 1e-12);
+        assert!((distance - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn discretize_keeps_chords_close_to_the_boundary() {
+        use crate::geometry::standard_tables::stadium;
+
+        let table = stadium(1.0, 0.5).to_billiard_table();
+        let points = table.outer.discretize(1e-3);
+
+        // Two straight sides, and each half-disk in steps of at most
+        // 2 acos(1 - 1e-3 / 0.5) ≈ 0.1265 rad.
+        let per_cap = (std::f64::consts::PI / (2.0 * (1.0 - 2e-3_f64).acos())).ceil() as usize;
+        assert_eq!(points.len(), 2 + 2 * per_cap);
+
+        let n = points.len();
+        for i in 0..n {
+            let (p, q) = (points[i], points[(i + 1) % n]);
+            assert!(table.outer.closest_point(p).1 < 1e-12);
+            let (_, bulge) = table.outer.closest_point((p + q) * 0.5);
+            assert!(bulge <= 1e-3 + 1e-12, "chord {} bulges by {}", i, bulge);
+        }
+
+        // A square needs only its corners.
+        let square = crate::geometry::standard_tables::rectangle(1.0, 1.0).to_billiard_table();
+        assert_eq!(square.outer.discretize(1e-6).len(), 4);
+    }
+
+    #[test]
+    fn the_domain_excludes_obstacles_and_the_outside() {
+        use crate::geometry::standard_tables::{sinai, stadium};
+
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        assert!(table.contains(Vec2::new(0.1, 0.1)));
+        assert!(!table.contains(Vec2::new(0.5, 0.5)));
+        assert!(!table.contains(Vec2::new(1.1, 0.5)));
+
+        // Inside the half-disk ends, not just the rectangle between them.
+        let table = stadium(2.0, 0.5).to_billiard_table();
+        assert!(table.contains(Vec2::new(-1.4, 0.0)));
+        assert!(table.contains(Vec2::new(1.4, 0.1)));
+        assert!(!table.contains(Vec2::new(-1.4, 0.4)));
+    }
+
+    #[test]
+    fn areas_and_perimeters_of_standard_tables() {
+        use crate::geometry::standard_tables::{mushroom, sinai, stadium};
+        use std::f64::consts::PI;
+
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        assert!((table.outer.signed_area() - 1.0).abs() < 1e-12);
+        assert!((table.obstacles[0].signed_area() - PI * 0.04).abs() < 1e-12);
+        assert!((table.domain_area() - (1.0 - PI * 0.04)).abs() < 1e-12);
+        assert!((table.perimeter() - (4.0 + 2.0 * PI * 0.2)).abs() < 1e-12);
+
+        let table = stadium(2.0, 0.5).to_billiard_table();
+        assert!((table.domain_area() - (2.0 + PI * 0.25)).abs() < 1e-12);
+
+        // Half-disk cap of radius 1 on a 0.5 x 1 stem.
+        let table = mushroom(1.0, 0.5, 1.0).to_billiard_table();
+        assert!((table.domain_area() - (0.5 * PI + 0.5)).abs() < 1e-12);
+
+        // Traversed clockwise, a component encloses negative area.
+        let square = [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)].map(|(x, y)| Vec2::new(x, y));
+        let clockwise = BoundaryComponent::new(
+            "cw",
+            (0..4)
+                .map(|i| BoundarySegment::Line(LineSegment::new(square[i], square[(i + 1) % 4])))
+                .collect(),
+        );
+        assert!((clockwise.signed_area() + 1.0).abs() < 1e-12);
+        assert!(clockwise.encloses(Vec2::new(0.5, 0.5)));
+        assert!(!clockwise.encloses(Vec2::new(1.5, 0.5)));
+    }
+
+    #[test]
+    fn checked_lookups_reject_what_the_plain_ones_panic_on() {
+        let table = crate::geometry::standard_tables::sinai(1.0, 0.2).to_billiard_table();
+        assert!(table.get_component(1).is_some());
+        assert!(table.get_component(2).is_none());
+
+        let outer = &table.outer;
+        assert_eq!(outer.checked_locate(0.5), Some(outer.locate(0.5)));
+        assert_eq!(outer.checked_locate(f64::NAN), None);
+        assert_eq!(outer.checked_locate(f64::INFINITY), None);
+        assert_eq!(outer.checked_global_s(1, 0.25), Some(1.25));
+        assert_eq!(outer.checked_global_s(4, 0.0), None);
+        assert_eq!(outer.checked_global_s(1, 1.5), None);
+        assert_eq!(outer.checked_global_s(1, f64::NAN), None);
+    }
+
+    proptest! {
+        #[test]
+        fn locate_and_global_s_round_trip(
+            table in 0..4usize,
+            fraction in 0.0..1.0f64,
+            laps in -3..3i32,
+        ) {
+            use crate::geometry::standard_tables::{mushroom, regular_polygon, sinai, stadium};
+
+            let table = match table {
+                0 => stadium(2.0, 0.5),
+                1 => mushroom(1.0, 0.5, 1.0),
+                2 => sinai(1.0, 0.2),
+                _ => regular_polygon(7, 1.0),
+            }
+            .to_billiard_table();
+            for component in table.components() {
+                let length = component.length();
+                let s = fraction * length;
+                let (segment_index, local_t) =
+                    component.checked_locate(s + laps as f64 * length).unwrap();
+                let back = component.checked_global_s(segment_index, local_t);
+                prop_assert!(back.is_some(), "local_t {} off segment {}", local_t, segment_index);
+                prop_assert!((back.unwrap() - s).abs() < 1e-9 * length.max(1.0));
+
+                let segment = &component.segments[segment_index];
+                let (point, _) = component.point_and_tangent_at(s);
+                prop_assert!((segment.point_at(local_t) - point).length() < 1e-9);
+            }
+        }
+    }
 }
@@ -0,0 +1,308 @@
+//! A canonical form of [`TableSpec`]s, for comparing and hashing tables.
+//!
+//! Two specs can describe the same table and still differ: an arc from
+//! `-π/2` to `π/2` is the arc from `3π/2` to `5π/2`, `-0.0` is `0.0`, the
+//! holes of a boundary may come in any order and, if nothing refers to
+//! obstacles by index, so may the obstacles. Metadata does not change
+//! the table at all and is left out. Segment ids and names are kept:
+//! collisions report them, so tables that label their segments
+//! differently give different results. [`TableSpec::canonical`] picks one
+//! of them; its [`canonical_json`](TableSpec::canonical_json)
+//! has its fields in a fixed order and its numbers in the shortest form
+//! that reads back exactly, so equal tables give equal strings, and
+//! [`canonical_hash`](TableSpec::canonical_hash) hashes that string.
+//!
+//! The hash is FNV-1a, fixed here rather than left to the standard
+//! library, so it stays the same across processes, platforms and Rust
+//! versions and may be stored.
+
+use std::f64::consts::TAU;
+
+use crate::geometry::boundary::Hole;
+use crate::geometry::primitives::Vec2;
+use crate::geometry::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+/// Whether [`TableSpec::canonical`] may reorder the obstacles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ObstacleOrder {
+    /// Keep the obstacles in order: component indices, as in boundary
+    /// states, still name the same obstacle.
+    #[default]
+    Keep,
+
+    /// Sort the obstacles by their canonical JSON, so that tables listing
+    /// the same obstacles in a different order compare equal.
+    Sort,
+}
+
+/// `x` with `-0.0` turned into `0.0`.
+fn number(x: f64) -> f64 {
+    if x == 0.0 { 0.0 } else { x }
+}
+
+fn point(p: Vec2) -> Vec2 {
+    Vec2::new(number(p.x), number(p.y))
+}
+
+impl SegmentSpec {
+    /// The same segment with its numbers in canonical form: arcs are turned
+    /// by a whole number of turns so that `start_angle` lies in `[0, 2π)`.
+    pub fn canonical(&self) -> SegmentSpec {
+        match *self {
+            SegmentSpec::Line {
+                start,
+                end,
+                condition,
+                ref label,
+            } => SegmentSpec::Line {
+                start: point(start),
+                end: point(end),
+                condition,
+                label: label.clone(),
+            },
+            SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+                ccw,
+                condition,
+                ref label,
+            } => {
+                let turns = (start_angle / TAU).floor() * TAU;
+                let mut start = start_angle - turns;
+                let mut end = end_angle - turns;
+                // Rounding can land a start just below 2π on 2π itself.
+                if start >= TAU {
+                    start -= TAU;
+                    end -= TAU;
+                }
+                SegmentSpec::CircularArc {
+                    center: point(center),
+                    radius: number(radius),
+                    start_angle: number(start),
+                    end_angle: number(end),
+                    ccw,
+                    condition,
+                    label: label.clone(),
+                }
+            }
+        }
+    }
+}
+
+impl BoundarySpec {
+    /// The same boundary with canonical segments, in the same order, its
+    /// holes sorted and no metadata.
+    pub fn canonical(&self) -> BoundarySpec {
+        let mut holes: Vec<Hole> = self
+            .holes
+            .iter()
+            .map(|hole| Hole {
+                start: number(hole.start),
+                end: number(hole.end),
+            })
+            .collect();
+        holes.sort_by(|a, b| a.start.total_cmp(&b.start).then(a.end.total_cmp(&b.end)));
+        BoundarySpec {
+            name: self.name.clone(),
+            segments: self.segments.iter().map(SegmentSpec::canonical).collect(),
+            holes,
+            ..Default::default()
+        }
+    }
+}
+
+impl TableSpec {
+    /// The canonical form of this spec; see the
+    /// [module documentation](self). Segments keep their order, which fixes
+    /// where `s = 0` is on each boundary.
+    pub fn canonical(&self, obstacles: ObstacleOrder) -> TableSpec {
+        let mut canonical: Vec<(String, BoundarySpec)> = self
+            .obstacles
+            .iter()
+            .map(|obstacle| {
+                let obstacle = obstacle.canonical();
+                (to_json(&obstacle), obstacle)
+            })
+            .collect();
+        if obstacles == ObstacleOrder::Sort {
+            canonical.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        TableSpec {
+            outer: self.outer.canonical(),
+            obstacles: canonical
+                .into_iter()
+                .map(|(_, obstacle)| obstacle)
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// JSON of the canonical form, equal for equal tables.
+    pub fn canonical_json(&self, obstacles: ObstacleOrder) -> String {
+        to_json(&self.canonical(obstacles))
+    }
+
+    /// Stable 64-bit FNV-1a hash of the
+    /// [`canonical_json`](TableSpec::canonical_json).
+    pub fn canonical_hash(&self, obstacles: ObstacleOrder) -> u64 {
+        fnv1a(self.canonical_json(obstacles).as_bytes())
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("table specs serialize to JSON")
+}
+
+/// 64-bit FNV-1a hash of `bytes`.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+    use super::{ObstacleOrder, fnv1a};
+    use crate::geometry::boundary::Hole;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::SegmentLabel;
+    use crate::geometry::standard_tables::{lorentz_gas, sinai, stadium};
+    use crate::geometry::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+    fn disk(name: &str, x: f64, y: f64, start_angle: f64) -> BoundarySpec {
+        BoundarySpec {
+            name: name.to_string(),
+            segments: vec![SegmentSpec::CircularArc {
+                center: Vec2::new(x, y),
+                radius: 0.1,
+                start_angle,
+                end_angle: start_angle + TAU,
+                ccw: true,
+                condition: Default::default(),
+                label: SegmentLabel::default(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn equivalent_specs_have_one_canonical_form() {
+        let table = stadium(1.0, 0.5);
+        let json = serde_json::to_value(&table).unwrap();
+        // The same spec with its keys in reverse order.
+        let mut reversed = serde_json::Map::new();
+        for (key, value) in json.as_object().unwrap().iter().rev() {
+            reversed.insert(key.clone(), value.clone());
+        }
+        let reparsed: TableSpec =
+            serde_json::from_str(&serde_json::Value::Object(reversed).to_string()).unwrap();
+        assert_eq!(
+            reparsed.canonical_hash(ObstacleOrder::Keep),
+            table.canonical_hash(ObstacleOrder::Keep)
+        );
+
+        // Arcs a turn apart, with -0.0 for 0.0.
+        let mut turned = table.clone();
+        for segment in &mut turned.outer.segments {
+            if let SegmentSpec::CircularArc {
+                center,
+                start_angle,
+                end_angle,
+                ..
+            } = segment
+            {
+                *start_angle -= 2.0 * TAU;
+                *end_angle -= 2.0 * TAU;
+                center.y = -0.0;
+            }
+        }
+        assert_ne!(turned, table);
+        assert_eq!(
+            turned.canonical_hash(ObstacleOrder::Keep),
+            table.canonical_hash(ObstacleOrder::Keep)
+        );
+        let canonical = turned.canonical(ObstacleOrder::Keep);
+        for segment in &canonical.outer.segments {
+            if let SegmentSpec::CircularArc {
+                start_angle,
+                end_angle,
+                ..
+            } = *segment
+            {
+                assert!((0.0..TAU).contains(&start_angle));
+                assert!((end_angle - start_angle - PI).abs() < 1e-12);
+            }
+        }
+        assert_eq!(
+            canonical.canonical_json(ObstacleOrder::Keep),
+            serde_json::to_string(&canonical).unwrap()
+        );
+        assert!(
+            !canonical
+                .canonical_json(ObstacleOrder::Keep)
+                .contains("-0.0")
+        );
+        assert_eq!(canonical.canonical(ObstacleOrder::Keep), canonical);
+
+        // Metadata describes the table without changing it.
+        let mut titled = table.clone();
+        titled.metadata = Some(serde_json::Map::from_iter([(
+            "title".to_string(),
+            serde_json::Value::from("stadium"),
+        )]));
+        titled.outer.metadata = titled.metadata.clone();
+        assert_eq!(
+            titled.canonical_hash(ObstacleOrder::Keep),
+            table.canonical_hash(ObstacleOrder::Keep)
+        );
+    }
+
+    #[test]
+    fn obstacles_are_sorted_only_on_request() {
+        let mut table = sinai(1.0, 0.2);
+        table.outer.holes = vec![
+            Hole {
+                start: 3.0,
+                end: 3.5,
+            },
+            Hole {
+                start: 0.5,
+                end: 1.0,
+            },
+        ];
+        let mut a = table.clone();
+        a.obstacles = vec![disk("a", 0.3, 0.3, 0.0), disk("b", -0.3, 0.3, FRAC_PI_2)];
+        let mut b = table.clone();
+        b.obstacles = vec![
+            disk("b", -0.3, 0.3, FRAC_PI_2 - TAU),
+            disk("a", 0.3, 0.3, 0.0),
+        ];
+
+        assert_ne!(
+            a.canonical_hash(ObstacleOrder::Keep),
+            b.canonical_hash(ObstacleOrder::Keep)
+        );
+        assert_eq!(
+            a.canonical_hash(ObstacleOrder::Sort),
+            b.canonical_hash(ObstacleOrder::Sort)
+        );
+        assert_eq!(a.canonical(ObstacleOrder::Keep).outer.holes[0].start, 0.5);
+        assert_ne!(
+            lorentz_gas(1.0, 0.2).canonical_hash(ObstacleOrder::Sort),
+            lorentz_gas(1.0, 0.3).canonical_hash(ObstacleOrder::Sort)
+        );
+    }
+
+    #[test]
+    fn fnv1a_matches_the_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -0,0 +1,356 @@
+//! Rounding the corners of polygonal boundaries.
+//!
+//! A corner between two line segments is replaced by the circular arc of a
+//! given radius tangent to both: each line is cut back by
+//! `radius · tan(φ / 2)`, where `φ` is the angle the boundary turns through
+//! at the corner, and the arc turns through `φ` between the cut ends. The
+//! boundary stays closed and becomes C¹ there, so no trajectory can hit an
+//! exact corner.
+
+use super::segments::{BoundaryCondition, SegmentLabel};
+use super::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+use crate::geometry::boundary::Hole;
+
+/// Corners turning through less than this angle are already smooth.
+const SMOOTH_TOLERANCE: f64 = 1e-9;
+
+/// A line cut back to less than this length is dropped.
+const LENGTH_TOLERANCE: f64 = 1e-12;
+
+impl BoundarySpec {
+    /// This boundary with every corner between two line segments rounded by
+    /// a tangent arc of radius `radius`.
+    ///
+    /// Corners next to an arc or a periodic segment are left sharp: the
+    /// tangent circle of an arc has a different radius, and shortening a
+    /// periodic segment would break its pairing. Holes stay where they
+    /// were on the sides that are cut back, and an end on a cut-off corner
+    /// moves onto the arc that replaces it, in proportion. Periodic
+    /// pairings are renumbered to account for the inserted arcs (which
+    /// always reflect).
+    ///
+    /// # Panics
+    /// Panics if `radius` is negative or not finite, or so large that the
+    /// arcs at the two ends of a side would overlap.
+    pub fn fillet_corners(&self, radius: f64) -> BoundarySpec {
+        assert!(
+            radius.is_finite() && radius >= 0.0,
+            "Fillet radius must be non-negative and finite."
+        );
+        if radius == 0.0 {
+            return self.clone();
+        }
+
+        let n = self.segments.len();
+        let line = |i: usize| match self.segments[i] {
+            SegmentSpec::Line {
+                start,
+                end,
+                condition,
+                ..
+            } if !matches!(condition, BoundaryCondition::PeriodicPairedWith(_)) => {
+                Some((start, end))
+            }
+            _ => None,
+        };
+
+        // Junction i joins the end of segment i to the start of segment i + 1;
+        // `cut_start` and `cut_end` are how far each line is cut back.
+        let mut arcs = vec![None; n];
+        let mut cut_start = vec![0.0; n];
+        let mut cut_end = vec![0.0; n];
+        for (i, arc) in arcs.iter_mut().enumerate() {
+            let j = (i + 1) % n;
+            let (Some((a, corner)), Some((_, b))) = (line(i), line(j)) else {
+                continue;
+            };
+            let (d_in, d_out) = ((corner - a).normalized(), (b - corner).normalized());
+            let turn = d_in.cross(d_out).atan2(d_in.dot(d_out));
+            if turn.abs() < SMOOTH_TOLERANCE {
+                continue;
+            }
+
+            let cut = radius * (0.5 * turn.abs()).tan();
+            let from = corner - d_in * cut;
+            let center = from + d_in.perp() * (radius * turn.signum());
+            let start_angle = (from.y - center.y).atan2(from.x - center.x);
+            *arc = Some(SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle,
+                end_angle: start_angle + turn,
+                ccw: turn > 0.0,
+                condition: BoundaryCondition::Reflect,
+                label: SegmentLabel::default(),
+            });
+            cut_end[i] = cut;
+            cut_start[j] = cut;
+        }
+
+        // Cut the lines back, interleave the arcs and renumber periodic pairs.
+        // `line_at[i]` is where what is left of segment i starts in the new
+        // boundary, and its length; `arc_at[i]` the same for the arc at
+        // junction i.
+        let length = |segment: &SegmentSpec| segment.to_boundary_segment().length();
+        let mut new_index = Vec::with_capacity(n);
+        let mut segments = Vec::with_capacity(2 * n);
+        let mut line_at = vec![(0.0, 0.0); n];
+        let mut arc_at = vec![(0.0, 0.0); n];
+        let mut s = 0.0;
+        for (i, arc) in arcs.into_iter().enumerate() {
+            new_index.push(segments.len());
+            match self.segments[i] {
+                SegmentSpec::Line {
+                    start,
+                    end,
+                    condition,
+                    ref label,
+                } if cut_start[i] > 0.0 || cut_end[i] > 0.0 => {
+                    let length = (end - start).length();
+                    let left = length - cut_start[i] - cut_end[i];
+                    assert!(
+                        left > -LENGTH_TOLERANCE * length.max(1.0),
+                        "Fillet radius is too large: the corners of segment {} overlap.",
+                        i
+                    );
+                    line_at[i] = (s, 0.0);
+                    if left > LENGTH_TOLERANCE * length.max(1.0) {
+                        let d = (end - start) / length;
+                        segments.push(SegmentSpec::Line {
+                            start: start + d * cut_start[i],
+                            end: end - d * cut_end[i],
+                            condition,
+                            label: label.clone(),
+                        });
+                        line_at[i].1 = left;
+                    }
+                }
+                ref segment => {
+                    line_at[i] = (s, length(segment));
+                    segments.push(segment.clone());
+                }
+            }
+            s += line_at[i].1;
+            if let Some(arc) = arc {
+                arc_at[i] = (s, length(&arc));
+                s += arc_at[i].1;
+                segments.push(arc);
+            }
+        }
+        for segment in &mut segments {
+            if let SegmentSpec::Line { condition, .. } | SegmentSpec::CircularArc { condition, .. } =
+                segment
+                && let BoundaryCondition::PeriodicPairedWith(paired) = *condition
+            {
+                *condition = BoundaryCondition::PeriodicPairedWith(new_index[paired]);
+            }
+        }
+
+        let mut filleted = BoundarySpec {
+            name: self.name.clone(),
+            segments,
+            holes: Vec::new(),
+            metadata: self.metadata.clone(),
+        };
+        if !self.holes.is_empty() {
+            // A point on segment i, at `t` along it, in the new boundary.
+            let moved = |i: usize, t: f64| {
+                let (line_start, left) = line_at[i];
+                let cut_from = length(&self.segments[i]) - cut_end[i];
+                if t < cut_start[i] {
+                    // The second half of the arc before the segment.
+                    let (arc_start, arc_length) = arc_at[(i + n - 1) % n];
+                    arc_start + arc_length * (0.5 + 0.5 * t / cut_start[i])
+                } else if t > cut_from {
+                    // The first half of the arc after it.
+                    let (arc_start, arc_length) = arc_at[i];
+                    arc_start + arc_length * 0.5 * (t - cut_from) / cut_end[i]
+                } else {
+                    line_start + (t - cut_start[i]).min(left)
+                }
+            };
+            let map = |s: f64| {
+                let mut start = 0.0;
+                for i in 0..n - 1 {
+                    let end = start + length(&self.segments[i]);
+                    if s <= end {
+                        return moved(i, s - start);
+                    }
+                    start = end;
+                }
+                moved(n - 1, s - start)
+            };
+            filleted.holes = self
+                .holes
+                .iter()
+                .map(|h| Hole {
+                    start: map(h.start),
+                    end: map(h.end),
+                })
+                .collect();
+        }
+        filleted
+    }
+}
+
+impl TableSpec {
+    /// This table with the corners of every component rounded; see
+    /// [`BoundarySpec::fillet_corners`].
+    ///
+    /// # Panics
+    /// Panics if `radius` is negative or not finite, or too large for a
+    /// side of some component.
+    pub fn fillet_corners(&self, radius: f64) -> TableSpec {
+        TableSpec {
+            outer: self.outer.fillet_corners(radius),
+            obstacles: self
+                .obstacles
+                .iter()
+                .map(|b| b.fillet_corners(radius))
+                .collect(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geometry::boundary::Hole;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::BoundaryCondition;
+    use crate::geometry::standard_tables::{lorentz_gas, rectangle, regular_polygon, sinai};
+    use crate::geometry::table_spec::{SegmentSpec, TableSpec};
+    use crate::geometry::transform::Transform;
+    use crate::geometry::validation::validate_table;
+    use std::f64::consts::PI;
+
+    /// Unit tangents at the start and end of `segment`.
+    fn end_tangents(segment: &SegmentSpec) -> (Vec2, Vec2) {
+        let s = segment.to_boundary_segment();
+        (s.tangent_at(0.0), s.tangent_at(s.length()))
+    }
+
+    fn assert_c1(spec: &TableSpec) {
+        for boundary in std::iter::once(&spec.outer).chain(&spec.obstacles) {
+            let n = boundary.segments.len();
+            for i in 0..n {
+                let (_, out) = end_tangents(&boundary.segments[i]);
+                let (into, _) = end_tangents(&boundary.segments[(i + 1) % n]);
+                assert!((out - into).length() < 1e-9, "kink after segment {}", i);
+            }
+        }
+    }
+
+    #[test]
+    fn rounded_square_is_closed_and_smooth() {
+        let rounded = rectangle(1.0, 1.0).fillet_corners(0.1);
+        assert!(validate_table(&rounded).is_valid());
+        assert_c1(&rounded);
+        assert_eq!(rounded.outer.segments.len(), 8);
+
+        let length = rounded.to_billiard_table().outer.length();
+        assert!((length - (4.0 - 0.8 + 2.0 * PI * 0.1)).abs() < 1e-12);
+
+        // The largest radius turns the square into a disk.
+        let disk = rectangle(1.0, 1.0).fillet_corners(0.5);
+        assert!(validate_table(&disk).is_valid());
+        assert!(
+            disk.outer
+                .segments
+                .iter()
+                .all(|s| matches!(s, SegmentSpec::CircularArc { .. }))
+        );
+
+        // Obstacles round off the same way; the arc of a disk is untouched.
+        let hexagon = regular_polygon(6, 1.0)
+            .outer
+            .translated(Vec2::new(2.0, 2.0));
+        let table = TableSpec {
+            outer: rectangle(4.0, 4.0).outer,
+            obstacles: vec![hexagon],
+            ..Default::default()
+        }
+        .fillet_corners(0.2);
+        assert!(validate_table(&table).is_valid());
+        assert_c1(&table);
+        assert_eq!(
+            sinai(1.0, 0.2).fillet_corners(0.1).obstacles[0]
+                .segments
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn periodic_sides_keep_their_corners() {
+        let cell = lorentz_gas(1.0, 0.3);
+        assert_eq!(cell.fillet_corners(0.1), cell);
+
+        // A pentagon whose vertical sides are paired: only the apex between
+        // the two slanted sides is rounded.
+        let mut spec = TableSpec::from_polygon(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 1.0),
+            Vec2::new(1.0, 1.5),
+            Vec2::new(0.0, 1.0),
+        ])
+        .unwrap();
+        for (i, paired) in [(1, 4), (4, 1)] {
+            if let SegmentSpec::Line { condition, .. } = &mut spec.outer.segments[i] {
+                *condition = BoundaryCondition::PeriodicPairedWith(paired);
+            }
+        }
+
+        let rounded = spec.fillet_corners(0.2);
+        let segments = &rounded.outer.segments;
+        assert_eq!(segments.len(), 6);
+        assert!(matches!(segments[3], SegmentSpec::CircularArc { .. }));
+        assert_eq!(
+            segments[1].condition(),
+            BoundaryCondition::PeriodicPairedWith(5)
+        );
+        assert_eq!(
+            segments[5].condition(),
+            BoundaryCondition::PeriodicPairedWith(1)
+        );
+        assert!(validate_table(&rounded).is_valid());
+    }
+
+    #[test]
+    fn holes_stay_on_their_sides_and_corners() {
+        // A hole in the middle of the bottom side of a 2 × 1 rectangle, and
+        // one across its bottom-right corner.
+        let mut spec = rectangle(2.0, 1.0);
+        spec.outer.holes = vec![
+            Hole {
+                start: 0.5,
+                end: 1.5,
+            },
+            Hole {
+                start: 1.95,
+                end: 2.05,
+            },
+        ];
+        let rounded = spec.fillet_corners(0.1);
+        let component = rounded.outer.to_boundary_component();
+        let point = |s: f64| component.point_and_tangent_at(s).0;
+
+        let side = rounded.outer.holes[0];
+        assert!((point(side.start) - Vec2::new(0.5, 0.0)).length() < 1e-12);
+        assert!((point(side.end) - Vec2::new(1.5, 0.0)).length() < 1e-12);
+
+        // The arc replacing the corner starts where the bottom side, cut
+        // back by 0.1 at both ends, now ends; the hole covers its middle.
+        let corner = rounded.outer.holes[1];
+        let quarter = 0.25 * 0.5 * PI * 0.1;
+        assert!((corner.start - (1.8 + quarter)).abs() < 1e-12);
+        assert!((corner.end - (1.8 + 3.0 * quarter)).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_large_a_radius_panics() {
+        rectangle(1.0, 1.0).fillet_corners(0.6);
+    }
+}
@@ -0,0 +1,187 @@
+//! Intersecting one ray with many line segments at once.
+//!
+//! A [`LineBatch`] stores the line segments of a boundary component as
+//! structure-of-arrays lanes, four segments per `f64x4`, so that the ray
+//! test of [`Ray::intersect_line_segment`] runs on four segments per
+//! instruction. Polyline tables with hundreds of edges spend most of each
+//! step in that test. The arithmetic is the scalar test's, operation for
+//! operation, so the results are bit-for-bit the same.
+//!
+//! [`Ray::intersect_line_segment`]: crate::dynamics::intersection::Ray::intersect_line_segment
+
+use wide::{CmpGe, CmpGt, CmpLe, f64x4};
+
+use super::primitives::Vec2;
+use super::segments::BoundarySegment;
+
+const LANES: usize = 4;
+
+/// Segments closer to parallel with the ray than this (as the cross
+/// product of unit vectors) are never hit; as in the scalar test.
+const PARALLEL_EPSILON: f64 = 1e-12;
+
+/// The line segments of a component, by lanes; see the
+/// [module documentation](self).
+#[derive(Clone, Debug, Default)]
+pub struct LineBatch {
+    start_x: Vec<f64x4>,
+    start_y: Vec<f64x4>,
+
+    /// Unit direction from start to end.
+    unit_x: Vec<f64x4>,
+    unit_y: Vec<f64x4>,
+    length: Vec<f64x4>,
+
+    /// Index in the component of the segment in each lane.
+    indices: Vec<usize>,
+}
+
+impl LineBatch {
+    /// Batch the line segments among `segments`; other kinds are left out.
+    pub fn new(segments: &[BoundarySegment]) -> Self {
+        let lines: Vec<(usize, Vec2, Vec2)> = segments
+            .iter()
+            .enumerate()
+            .filter_map(|(i, segment)| match segment {
+                BoundarySegment::Line(line) => Some((i, line.start, line.end - line.start)),
+                _ => None,
+            })
+            .collect();
+
+        let mut batch = LineBatch {
+            indices: lines.iter().map(|&(i, ..)| i).collect(),
+            ..LineBatch::default()
+        };
+        // Padding lanes stay NaN, so every comparison on them fails.
+        for chunk in lines.chunks(LANES) {
+            let mut lanes = [[f64::NAN; LANES]; 5];
+            for (lane, &(_, start, v)) in chunk.iter().enumerate() {
+                let length = v.length();
+                let unit = v / length;
+                for (field, value) in lanes
+                    .iter_mut()
+                    .zip([start.x, start.y, unit.x, unit.y, length])
+                {
+                    field[lane] = value;
+                }
+            }
+            let [start_x, start_y, unit_x, unit_y, length] = lanes.map(f64x4::new);
+            batch.start_x.push(start_x);
+            batch.start_y.push(start_y);
+            batch.unit_x.push(unit_x);
+            batch.unit_y.push(unit_y);
+            batch.length.push(length);
+        }
+        batch
+    }
+
+    /// Number of line segments in the batch.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Nearest hit `(segment_index, ray_parameter, local_t)` of the ray from
+    /// `origin` along the unit vector `direction`, with the origin more than
+    /// `tolerance` from the segment's line, as [`Ray::intersect_line_segment`]
+    /// would find segment by segment; the lowest segment index wins a tie.
+    /// The segment `skip`, the one the ray leaves from, is never hit.
+    ///
+    /// [`Ray::intersect_line_segment`]: crate::dynamics::intersection::Ray::intersect_line_segment
+    pub fn nearest_hit(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        tolerance: f64,
+        skip: Option<usize>,
+    ) -> Option<(usize, f64, f64)> {
+        let (px, py) = (f64x4::splat(origin.x), f64x4::splat(origin.y));
+        let (rx, ry) = (f64x4::splat(direction.x), f64x4::splat(direction.y));
+        let tol = f64x4::splat(tolerance);
+        let zero = f64x4::splat(0.0);
+
+        let mut best: Option<(usize, f64, f64)> = None;
+        for chunk in 0..self.length.len() {
+            let (sx, sy) = (self.unit_x[chunk], self.unit_y[chunk]);
+            let length = self.length[chunk];
+            let denom = rx * sy - ry * sx;
+            let (wx, wy) = (self.start_x[chunk] - px, self.start_y[chunk] - py);
+            let offset = wx * sy - wy * sx;
+            let t = offset / denom;
+            let local_t = (wx * ry - wy * rx) / denom;
+
+            let hit = length.cmp_gt(tol)
+                & denom.abs().cmp_ge(f64x4::splat(PARALLEL_EPSILON))
+                & offset.abs().cmp_gt(tol)
+                & t.cmp_gt(zero)
+                & local_t.cmp_ge(zero)
+                & local_t.cmp_le(length);
+            let mask = hit.move_mask();
+            if mask == 0 {
+                continue;
+            }
+            let (t, local_t) = (t.to_array(), local_t.to_array());
+            for lane in (0..LANES).filter(|lane| mask & (1 << lane) != 0) {
+                let index = self.indices[chunk * LANES + lane];
+                if skip == Some(index) {
+                    continue;
+                }
+                if best.is_none_or(|(_, best_t, _)| t[lane] < best_t) {
+                    best = Some((index, t[lane], local_t[lane]));
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineBatch;
+    use crate::dynamics::intersection::Ray;
+    use crate::geometry::boundary::BoundaryComponent;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::BoundarySegment;
+    use crate::geometry::standard_tables::{regular_polygon, stadium};
+
+    /// The scalar reference: every segment through the ray test.
+    fn scalar_nearest(
+        component: &BoundaryComponent,
+        ray: &Ray,
+        epsilon: f64,
+    ) -> Option<(usize, f64, f64)> {
+        component
+            .segments
+            .iter()
+            .enumerate()
+            .filter_map(|(i, segment)| match segment {
+                BoundarySegment::Line(line) => ray
+                    .intersect_line_segment(line, epsilon)
+                    .map(|(t, local_t)| (i, t, local_t)),
+                _ => None,
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+    }
+
+    #[test]
+    fn matches_the_scalar_test_bit_for_bit() {
+        let polygon = regular_polygon(37, 1.0).to_billiard_table();
+        let stadium = stadium(1.0, 0.5).to_billiard_table();
+        for component in [polygon.component(0), stadium.component(0)] {
+            let batch = LineBatch::new(&component.segments);
+            for k in 0..200 {
+                let angle = k as f64 * 0.731;
+                let ray = Ray {
+                    origin: Vec2::new(0.1 * (k as f64).sin(), 0.05 * (k as f64).cos()),
+                    direction: Vec2::new(angle.cos(), angle.sin()),
+                };
+                let expected = scalar_nearest(component, &ray, 1e-10);
+                let got = batch.nearest_hit(ray.origin, ray.direction.normalized(), 1e-10, None);
+                assert_eq!(got, expected, "ray {}", k);
+            }
+        }
+    }
+}
@@ -1,6 +1,21 @@
 //! Geometry primitives and boundary representations.
 
+pub mod arc_length;
+pub mod boolean;
 pub mod boundary;
+pub mod canonical;
+pub mod fillet;
+pub mod line_batch;
+pub mod offset;
+pub mod polygon;
+pub mod presets;
 pub mod primitives;
+pub mod scalar;
 pub mod segments;
+pub mod standard_tables;
+pub mod table_builder;
 pub mod table_spec;
+pub mod table_template;
+pub mod table_version;
+pub mod transform;
+pub mod validation;
@@ -0,0 +1,561 @@
+//! Inward offsetting of tables for balls of finite radius.
+//!
+//! A ball of radius `r` bounces off a wall when its center comes within `r`
+//! of it, so its center moves exactly like a point particle in the table
+//! shrunk by `r`. The offset table is built segment by segment:
+//!
+//! - lines are shifted by `r` along their domain-side normal;
+//! - arcs keep their center, with the radius reduced by `r` when the domain
+//!   lies inside the circle and increased by `r` otherwise;
+//! - where the offset segments overlap (a corner pointing away from the
+//!   domain, such as the corners of a square table) both are trimmed to
+//!   their intersection;
+//! - where they separate (a corner pointing into the domain, such as the
+//!   corners of a square obstacle) a rounding arc of radius `r` around the
+//!   original corner is inserted.
+
+use std::f64::consts::TAU;
+
+use super::primitives::Vec2;
+use super::segments::{BoundaryCondition, BoundarySegment, SegmentLabel};
+use super::table_spec::{BoundarySpec, SegmentSpec, TableGeometryError, TableSpec};
+use crate::geometry::boundary::{GeometryError, Hole};
+
+/// Tangents closer to parallel than this are treated as a smooth junction.
+const SMOOTH_TOLERANCE: f64 = 1e-9;
+
+/// The table seen by the center of a ball of radius `ball_radius`.
+///
+/// Holes are rescaled in proportion to the change in each component's
+/// length, and periodic pairings are renumbered to account for the inserted
+/// rounding arcs (which always reflect).
+///
+/// # Panics
+/// Panics if `ball_radius` is negative or not finite, or wherever
+/// [`try_offset_for_ball`] fails.
+pub fn offset_for_ball(spec: &TableSpec, ball_radius: f64) -> TableSpec {
+    try_offset_for_ball(spec, ball_radius).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Fallible version of [`offset_for_ball`]: reports a segment that is
+/// degenerate to begin with, or one that vanishes because the ball is too
+/// large for the table as [`GeometryError::BallTooLarge`]. Indices refer
+/// to the segments of `spec`. As in
+/// [`BoundarySpec::try_to_boundary_component`], closure is not checked.
+///
+/// # Panics
+/// Panics if `ball_radius` is negative or not finite.
+pub fn try_offset_for_ball(
+    spec: &TableSpec,
+    ball_radius: f64,
+) -> Result<TableSpec, TableGeometryError> {
+    assert!(
+        ball_radius.is_finite() && ball_radius >= 0.0,
+        "Ball radius must be non-negative and finite."
+    );
+    let offset = |(component_index, boundary): (usize, &BoundarySpec)| {
+        let side = if component_index == 0 { 1.0 } else { -1.0 };
+        boundary
+            .try_to_boundary_component()
+            .and_then(|_| {
+                if ball_radius == 0.0 {
+                    Ok(boundary.clone())
+                } else {
+                    offset_boundary(boundary, ball_radius, side)
+                }
+            })
+            .map_err(|error| TableGeometryError {
+                component_index,
+                error,
+            })
+    };
+    let mut components = std::iter::once(&spec.outer)
+        .chain(&spec.obstacles)
+        .enumerate()
+        .map(offset)
+        .collect::<Result<Vec<_>, _>>()?;
+    let obstacles = components.split_off(1);
+    Ok(TableSpec {
+        outer: components.pop().expect("the outer boundary comes first"),
+        obstacles,
+        metadata: spec.metadata.clone(),
+    })
+}
+
+/// Offset one component by `r` toward the domain, which lies on the left of
+/// the segments when `side = 1` and on the right when `side = -1`.
+fn offset_boundary(
+    boundary: &BoundarySpec,
+    r: f64,
+    side: f64,
+) -> Result<BoundarySpec, GeometryError> {
+    let n = boundary.segments.len();
+    let mut shifted: Vec<SegmentSpec> = boundary
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(index, seg)| {
+            offset_segment(seg, r, side).ok_or(GeometryError::BallTooLarge { index })
+        })
+        .collect::<Result<_, _>>()?;
+
+    // Junction i joins the end of segment i to the start of segment i + 1.
+    let mut rounding = vec![None; n];
+    for (i, round) in rounding.iter_mut().enumerate() {
+        let j = (i + 1) % n;
+        let a = boundary.segments[i].to_boundary_segment();
+        let b = boundary.segments[j].to_boundary_segment();
+        let t_a = a.tangent_at(a.length());
+        let t_b = b.tangent_at(0.0);
+        let turn = t_a.cross(t_b);
+        if turn.abs() < SMOOTH_TOLERANCE && t_a.dot(t_b) > 0.0 {
+            continue;
+        }
+
+        if turn * side > 0.0 {
+            trim(&mut shifted, i, j)?;
+        } else {
+            let corner = a.point_at(a.length());
+            *round = Some(rounding_arc(
+                corner,
+                r,
+                end_point(&shifted[i]),
+                start_point(&shifted[j]),
+                turn > 0.0,
+            ));
+        }
+    }
+
+    // Interleave the rounding arcs and renumber periodic pairs.
+    let mut new_index = Vec::with_capacity(n);
+    let mut segments = Vec::with_capacity(2 * n);
+    for (seg, round) in shifted.into_iter().zip(rounding) {
+        new_index.push(segments.len());
+        segments.push(seg);
+        segments.extend(round);
+    }
+    for seg in &mut segments {
+        if let BoundaryCondition::PeriodicPairedWith(paired) = seg.condition() {
+            set_condition(
+                seg,
+                BoundaryCondition::PeriodicPairedWith(new_index[paired]),
+            );
+        }
+    }
+
+    let mut offset = BoundarySpec {
+        name: boundary.name.clone(),
+        segments,
+        holes: Vec::new(),
+        metadata: boundary.metadata.clone(),
+    };
+    // A trimmed side may be left too short to be a segment.
+    let component = offset.try_to_boundary_component().map_err(|error| {
+        let index = match error {
+            GeometryError::DegenerateSegment { index } => index,
+            _ => 0,
+        };
+        // The original segment the offset one came from, or the one
+        // before the rounding arc.
+        let index = new_index.iter().rposition(|&k| k <= index).unwrap_or(0);
+        GeometryError::BallTooLarge { index }
+    })?;
+    if !boundary.holes.is_empty() {
+        let scale = component.length() / boundary.to_boundary_component().length();
+        offset.holes = boundary
+            .holes
+            .iter()
+            .map(|h| Hole {
+                start: h.start * scale,
+                end: h.end * scale,
+            })
+            .collect();
+    }
+    Ok(offset)
+}
+
+/// `seg` shifted by `r` toward the domain, or `None` if an arc shrinks to
+/// nothing.
+fn offset_segment(seg: &SegmentSpec, r: f64, side: f64) -> Option<SegmentSpec> {
+    match *seg {
+        SegmentSpec::Line {
+            start,
+            end,
+            condition,
+            ref label,
+        } => {
+            let normal = (end - start).normalized().perp() * (side * r);
+            Some(SegmentSpec::Line {
+                start: start + normal,
+                end: end + normal,
+                condition,
+                label: label.clone(),
+            })
+        }
+        SegmentSpec::CircularArc {
+            center,
+            radius,
+            start_angle,
+            end_angle,
+            ccw,
+            condition,
+            ref label,
+        } => {
+            // The left normal of a CCW arc points at the center.
+            let domain_inside = ccw == (side > 0.0);
+            let radius = if domain_inside {
+                radius - r
+            } else {
+                radius + r
+            };
+            // The ball radius exceeds the radius of curvature of the arc.
+            if radius <= 0.0 {
+                return None;
+            }
+            Some(SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+                ccw,
+                condition,
+                label: label.clone(),
+            })
+        }
+    }
+}
+
+/// Arc of radius `r` around `corner` from `from` to `to`.
+fn rounding_arc(corner: Vec2, r: f64, from: Vec2, to: Vec2, ccw: bool) -> SegmentSpec {
+    let angle = |p: Vec2| (p.y - corner.y).atan2(p.x - corner.x);
+    let start_angle = angle(from);
+    let mut end_angle = angle(to);
+    if ccw && end_angle < start_angle {
+        end_angle += TAU;
+    } else if !ccw && end_angle > start_angle {
+        end_angle -= TAU;
+    }
+
+    SegmentSpec::CircularArc {
+        center: corner,
+        radius: r,
+        start_angle,
+        end_angle,
+        ccw,
+        condition: BoundaryCondition::Reflect,
+        label: SegmentLabel::default(),
+    }
+}
+
+/// Cut segments `i` and `j` back to where they cross, near their current
+/// (overlapping) junction. Fails if either would be cut away entirely, or
+/// if they no longer meet.
+fn trim(segments: &mut [SegmentSpec], i: usize, j: usize) -> Result<(), GeometryError> {
+    let near = (end_point(&segments[i]) + start_point(&segments[j])) * 0.5;
+    let p = crossings(&segments[i], &segments[j])
+        .into_iter()
+        .min_by(|a, b| (*a - near).length().total_cmp(&(*b - near).length()))
+        .ok_or(GeometryError::BallTooLarge { index: i })?;
+
+    if !set_end(&mut segments[i], p) {
+        return Err(GeometryError::BallTooLarge { index: i });
+    }
+    if !set_start(&mut segments[j], p) {
+        return Err(GeometryError::BallTooLarge { index: j });
+    }
+    Ok(())
+}
+
+/// Crossings of the full lines / circles supporting `a` and `b`.
+fn crossings(a: &SegmentSpec, b: &SegmentSpec) -> Vec<Vec2> {
+    match (a.to_boundary_segment(), b.to_boundary_segment()) {
+        (BoundarySegment::Line(l1), BoundarySegment::Line(l2)) => {
+            let (d1, d2) = (l1.end - l1.start, l2.end - l2.start);
+            let denom = d1.x * d2.y - d1.y * d2.x;
+            if denom == 0.0 {
+                return Vec::new();
+            }
+            let w = l2.start - l1.start;
+            let t = (w.x * d2.y - w.y * d2.x) / denom;
+            vec![l1.start + d1 * t]
+        }
+        (BoundarySegment::Line(line), BoundarySegment::CircularArc(arc))
+        | (BoundarySegment::CircularArc(arc), BoundarySegment::Line(line)) => {
+            let d = (line.end - line.start).normalized();
+            let m = line.start - arc.center;
+            let b = m.dot(d);
+            let disc = b * b - (m.dot(m) - arc.radius * arc.radius);
+            if disc < 0.0 {
+                return Vec::new();
+            }
+            let root = disc.sqrt();
+            vec![line.start + d * (-b - root), line.start + d * (-b + root)]
+        }
+        (BoundarySegment::External(_), _) | (_, BoundarySegment::External(_)) => {
+            unreachable!("specs hold only built-in segments")
+        }
+        (BoundarySegment::CircularArc(a1), BoundarySegment::CircularArc(a2)) => {
+            let between = a2.center - a1.center;
+            let dist = between.length();
+            if dist == 0.0 {
+                return Vec::new();
+            }
+            let (r1, r2) = (a1.radius, a2.radius);
+            let along = (dist * dist + r1 * r1 - r2 * r2) / (2.0 * dist);
+            let h2 = r1 * r1 - along * along;
+            if h2 < 0.0 {
+                return Vec::new();
+            }
+            let u = between / dist;
+            let base = a1.center + u * along;
+            let h = u.perp() * h2.sqrt();
+            vec![base + h, base - h]
+        }
+    }
+}
+
+fn start_point(seg: &SegmentSpec) -> Vec2 {
+    let s = seg.to_boundary_segment();
+    s.point_at(0.0)
+}
+
+fn end_point(seg: &SegmentSpec) -> Vec2 {
+    let s = seg.to_boundary_segment();
+    s.point_at(s.length())
+}
+
+/// Move the start of `seg` to `p`, unless that would reverse it.
+fn set_start(seg: &mut SegmentSpec, p: Vec2) -> bool {
+    match seg {
+        SegmentSpec::Line { start, end, .. } => {
+            if (*end - p).dot(*end - *start) <= 0.0 {
+                return false;
+            }
+            *start = p;
+        }
+        SegmentSpec::CircularArc {
+            center,
+            start_angle,
+            end_angle,
+            ccw,
+            ..
+        } => {
+            let angle = nearest_angle((p.y - center.y).atan2(p.x - center.x), *start_angle);
+            if (*end_angle - angle > 0.0) != *ccw {
+                return false;
+            }
+            *start_angle = angle;
+        }
+    }
+    true
+}
+
+/// Move the end of `seg` to `p`, unless that would reverse it.
+fn set_end(seg: &mut SegmentSpec, p: Vec2) -> bool {
+    match seg {
+        SegmentSpec::Line { start, end, .. } => {
+            if (p - *start).dot(*end - *start) <= 0.0 {
+                return false;
+            }
+            *end = p;
+        }
+        SegmentSpec::CircularArc {
+            center,
+            start_angle,
+            end_angle,
+            ccw,
+            ..
+        } => {
+            let angle = nearest_angle((p.y - center.y).atan2(p.x - center.x), *end_angle);
+            if (angle - *start_angle > 0.0) != *ccw {
+                return false;
+            }
+            *end_angle = angle;
+        }
+    }
+    true
+}
+
+fn set_condition(seg: &mut SegmentSpec, new: BoundaryCondition) {
+    match seg {
+        SegmentSpec::Line { condition, .. } | SegmentSpec::CircularArc { condition, .. } => {
+            *condition = new;
+        }
+    }
+}
+
+/// The angle congruent to `angle` (mod 2π) closest to `reference`.
+fn nearest_angle(angle: f64, reference: f64) -> f64 {
+    angle + TAU * ((reference - angle) / TAU).round()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{offset_for_ball, try_offset_for_ball};
+    use crate::geometry::boundary::{GeometryError, Hole};
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundaryCondition, SegmentLabel};
+    use crate::geometry::standard_tables::{mushroom, sinai, stadium};
+    use crate::geometry::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+    use std::f64::consts::PI;
+
+    /// Every segment should end where the next one starts.
+    fn assert_closed(spec: &BoundarySpec) {
+        let bc = spec.to_boundary_component();
+        let n = bc.segments.len();
+        for i in 0..n {
+            let end = bc.segments[i].point_at(bc.segments[i].length());
+            let start = bc.segments[(i + 1) % n].point_at(0.0);
+            assert!((end - start).length() < 1e-9, "gap after segment {}", i);
+        }
+    }
+
+    #[test]
+    fn square_with_disk_shrinks_and_grows() {
+        let r = 0.1;
+        let spec = offset_for_ball(&sinai(1.0, 0.2), r);
+        let table = spec.to_billiard_table();
+
+        // Outer square trimmed to side 0.8; obstacle radius grows to 0.3.
+        assert_closed(&spec.outer);
+        assert_eq!(spec.outer.segments.len(), 4);
+        assert!((table.outer.length() - 3.2).abs() < 1e-12);
+        let (p, _) = table.outer.point_and_tangent_at(0.0);
+        assert!((p - Vec2::new(r, r)).length() < 1e-12);
+
+        assert!((table.obstacles[0].length() - 2.0 * PI * 0.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn balls_too_large_for_the_table_are_errors() {
+        // The half-disk ends of the stadium have radius 0.5.
+        let err = try_offset_for_ball(&stadium(1.0, 0.5), 0.6).unwrap_err();
+        assert_eq!(err.component_index, 0);
+        assert!(matches!(err.error, GeometryError::BallTooLarge { .. }));
+
+        // The sides of the square are cut away before the disk gets in
+        // the way.
+        let err = try_offset_for_ball(&sinai(1.0, 0.2), 0.5).unwrap_err();
+        assert_eq!(err.component_index, 0);
+        assert!(matches!(err.error, GeometryError::BallTooLarge { .. }));
+
+        let table = sinai(1.0, 0.2).try_to_billiard_table_for_ball(0.1).unwrap();
+        assert!((table.outer.length() - 3.2).abs() < 1e-12);
+        assert!(sinai(1.0, 0.2).try_to_billiard_table_for_ball(0.7).is_err());
+    }
+
+    #[test]
+    fn square_obstacle_gets_rounded_corners() {
+        let r = 0.05;
+        let corner = |x: f64, y: f64| Vec2::new(x, y);
+        let line = |start, end| SegmentSpec::Line {
+            start,
+            end,
+            condition: BoundaryCondition::Reflect,
+            label: SegmentLabel::default(),
+        };
+        let square = vec![
+            line(corner(0.4, 0.4), corner(0.6, 0.4)),
+            line(corner(0.6, 0.4), corner(0.6, 0.6)),
+            line(corner(0.6, 0.6), corner(0.4, 0.6)),
+            line(corner(0.4, 0.6), corner(0.4, 0.4)),
+        ];
+        let mut spec = sinai(1.0, 0.1);
+        spec.obstacles[0].segments = square;
+
+        let offset = offset_for_ball(&spec, r);
+        let obstacle = &offset.obstacles[0];
+        assert_closed(obstacle);
+        assert_eq!(obstacle.segments.len(), 8);
+
+        // Four sides of 0.2 plus four quarter circles of radius r.
+        let length = obstacle.to_boundary_component().length();
+        assert!((length - (0.8 + 2.0 * PI * r)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn periodic_pairs_are_renumbered_around_rounding_arcs() {
+        let line = |start, end, condition| SegmentSpec::Line {
+            start,
+            end,
+            condition,
+            label: SegmentLabel::default(),
+        };
+        let mut spec = sinai(1.0, 0.1);
+        spec.obstacles[0].segments = vec![
+            line(
+                Vec2::new(0.4, 0.4),
+                Vec2::new(0.6, 0.4),
+                BoundaryCondition::PeriodicPairedWith(2),
+            ),
+            line(
+                Vec2::new(0.6, 0.4),
+                Vec2::new(0.6, 0.6),
+                BoundaryCondition::Reflect,
+            ),
+            line(
+                Vec2::new(0.6, 0.6),
+                Vec2::new(0.4, 0.6),
+                BoundaryCondition::PeriodicPairedWith(0),
+            ),
+            line(
+                Vec2::new(0.4, 0.6),
+                Vec2::new(0.4, 0.4),
+                BoundaryCondition::Reflect,
+            ),
+        ];
+
+        let offset = offset_for_ball(&spec, 0.05);
+        let segments = &offset.obstacles[0].segments;
+        assert_eq!(
+            segments[0].condition(),
+            BoundaryCondition::PeriodicPairedWith(4)
+        );
+        assert_eq!(
+            segments[4].condition(),
+            BoundaryCondition::PeriodicPairedWith(0)
+        );
+        assert_eq!(segments[1].condition(), BoundaryCondition::Reflect);
+    }
+
+    #[test]
+    fn stadium_and_mushroom_stay_closed() {
+        let stadium = offset_for_ball(&stadium(1.0, 0.5), 0.1);
+        assert_closed(&stadium.outer);
+        let length = stadium.to_billiard_table().outer.length();
+        assert!((length - (2.0 + 2.0 * PI * 0.4)).abs() < 1e-12);
+
+        // The stem junctions point into the domain and get rounded.
+        let original = mushroom(1.0, 0.4, 0.8);
+        let offset = offset_for_ball(&original, 0.05);
+        assert_closed(&offset.outer);
+        assert_eq!(
+            offset.outer.segments.len(),
+            original.outer.segments.len() + 2
+        );
+    }
+
+    #[test]
+    fn holes_are_rescaled_and_zero_radius_is_identity() {
+        let mut spec: TableSpec = sinai(1.0, 0.1);
+        spec.outer.holes = vec![Hole {
+            start: 0.4,
+            end: 0.6,
+        }];
+        let offset = offset_for_ball(&spec, 0.1);
+        let hole = offset.outer.holes[0];
+        assert!((hole.start - 0.32).abs() < 1e-12);
+        assert!((hole.end - 0.48).abs() < 1e-12);
+
+        let mut spec = sinai(1.0, 0.1);
+        spec.obstacles.clear();
+        let offset = offset_for_ball(&spec, 0.0);
+        assert_eq!(offset, spec);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ball_larger_than_table_panics() {
+        offset_for_ball(&sinai(1.0, 0.2), 0.6);
+    }
+}
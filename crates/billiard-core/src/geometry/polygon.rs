@@ -0,0 +1,257 @@
+//! Polygonal tables from a list of vertices.
+
+use std::fmt;
+
+use super::primitives::Vec2;
+use super::segments::{BoundaryCondition, SegmentLabel};
+use super::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+/// Why a vertex list does not describe a simple polygon.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolygonError {
+    /// Fewer than three distinct vertices.
+    TooFewVertices { count: usize },
+
+    /// Vertex `index` has a NaN or infinite coordinate.
+    NonFiniteVertex { index: usize },
+
+    /// Vertex `index` coincides with the one before it.
+    DuplicateVertex { index: usize },
+
+    /// Edges `first` and `second` touch or cross (edge `i` runs from vertex
+    /// `i` to vertex `i + 1`).
+    SelfIntersecting { first: usize, second: usize },
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolygonError::TooFewVertices { count } => {
+                write!(f, "polygon needs at least 3 vertices, got {}", count)
+            }
+            PolygonError::NonFiniteVertex { index } => {
+                write!(f, "vertex {} has a non-finite coordinate", index)
+            }
+            PolygonError::DuplicateVertex { index } => {
+                write!(f, "vertex {} repeats the previous vertex", index)
+            }
+            PolygonError::SelfIntersecting { first, second } => {
+                write!(f, "edges {} and {} intersect", first, second)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+/// Twice the signed area of the polygon; positive when counterclockwise.
+fn signed_double_area(vertices: &[Vec2]) -> f64 {
+    let n = vertices.len();
+    (0..n)
+        .map(|i| vertices[i].cross(vertices[(i + 1) % n]))
+        .sum()
+}
+
+/// Whether the closed segments `p1 p2` and `q1 q2` share a point.
+fn segments_touch(p1: Vec2, p2: Vec2, q1: Vec2, q2: Vec2) -> bool {
+    let d1 = (p2 - p1).cross(q1 - p1);
+    let d2 = (p2 - p1).cross(q2 - p1);
+    let d3 = (q2 - q1).cross(p1 - q1);
+    let d4 = (q2 - q1).cross(p2 - q1);
+
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+
+    // Collinear cases: an endpoint lying on the other segment.
+    let on_segment = |a: Vec2, b: Vec2, p: Vec2| {
+        p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+    };
+    (d1 == 0.0 && on_segment(p1, p2, q1))
+        || (d2 == 0.0 && on_segment(p1, p2, q2))
+        || (d3 == 0.0 && on_segment(q1, q2, p1))
+        || (d4 == 0.0 && on_segment(q1, q2, p2))
+}
+
+/// First pair of edges that intersect other than at a shared vertex.
+fn find_self_intersection(vertices: &[Vec2]) -> Option<(usize, usize)> {
+    let n = vertices.len();
+    let edge = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+
+    for i in 0..n {
+        let (a, b) = edge(i);
+
+        // Adjacent edges share vertex i + 1; they only overlap if the
+        // boundary doubles back on itself there.
+        let j = (i + 1) % n;
+        let (_, c) = edge(j);
+        if (b - a).cross(c - b) == 0.0 && (b - a).dot(c - b) < 0.0 {
+            return Some((i, j));
+        }
+
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (c, d) = edge(j);
+            if segments_touch(a, b, c, d) {
+                return Some((i, j));
+            }
+        }
+    }
+    None
+}
+
+impl TableSpec {
+    /// A table bounded by the simple polygon with the given vertices, one
+    /// reflecting line segment per edge.
+    ///
+    /// The polygon is closed automatically; a final vertex repeating the
+    /// first is ignored. Clockwise input is reversed so the outer boundary
+    /// is counterclockwise like every other table.
+    pub fn from_polygon(vertices: &[Vec2]) -> Result<TableSpec, PolygonError> {
+        let mut vertices = vertices.to_vec();
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+
+        if let Some(index) = vertices
+            .iter()
+            .position(|v| !(v.x.is_finite() && v.y.is_finite()))
+        {
+            return Err(PolygonError::NonFiniteVertex { index });
+        }
+        if vertices.len() < 3 {
+            return Err(PolygonError::TooFewVertices {
+                count: vertices.len(),
+            });
+        }
+        let n = vertices.len();
+        if let Some(index) = (0..n).find(|&i| vertices[i] == vertices[(i + n - 1) % n]) {
+            return Err(PolygonError::DuplicateVertex { index });
+        }
+
+        // A simple polygon has nonzero area, so the sign of the area is a
+        // reliable orientation test once self-intersections are ruled out.
+        if let Some((first, second)) = find_self_intersection(&vertices) {
+            return Err(PolygonError::SelfIntersecting { first, second });
+        }
+        if signed_double_area(&vertices) < 0.0 {
+            vertices.reverse();
+        }
+
+        let segments = (0..n)
+            .map(|i| SegmentSpec::Line {
+                start: vertices[i],
+                end: vertices[(i + 1) % n],
+                condition: BoundaryCondition::Reflect,
+                label: SegmentLabel::default(),
+            })
+            .collect();
+
+        Ok(TableSpec {
+            outer: BoundarySpec {
+                name: "polygon".to_string(),
+                segments,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PolygonError;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::table_spec::{SegmentSpec, TableSpec};
+
+    fn v(x: f64, y: f64) -> Vec2 {
+        Vec2::new(x, y)
+    }
+
+    #[test]
+    fn square_builds_a_closed_ccw_table() {
+        let spec =
+            TableSpec::from_polygon(&[v(0.0, 0.0), v(2.0, 0.0), v(2.0, 1.0), v(0.0, 1.0)]).unwrap();
+        let table = spec.to_billiard_table();
+        assert_eq!(table.outer.segments.len(), 4);
+        assert!((table.outer.length() - 6.0).abs() < 1e-12);
+        assert!(table.obstacles.is_empty());
+    }
+
+    #[test]
+    fn clockwise_input_is_reversed_and_closing_vertex_dropped() {
+        let spec =
+            TableSpec::from_polygon(&[v(0.0, 0.0), v(0.0, 1.0), v(1.0, 0.0), v(0.0, 0.0)]).unwrap();
+        assert_eq!(spec.outer.segments.len(), 3);
+
+        // Reversed order: (1, 0) -> (0, 1) -> (0, 0).
+        let SegmentSpec::Line { start, end, .. } = spec.outer.segments[0] else {
+            panic!("polygons are made of lines");
+        };
+        assert_eq!((start, end), (v(1.0, 0.0), v(0.0, 1.0)));
+
+        // Inward normal of the bottom edge points up.
+        let table = spec.to_billiard_table();
+        let s = table.outer.global_s_from_segment_local(2, 0.5);
+        let (_, normal) = table.outer.point_and_inward_normal_at(s);
+        assert!(normal.x.abs() < 1e-12 && normal.y > 0.0, "{:?}", normal);
+    }
+
+    #[test]
+    fn invalid_polygons_are_rejected() {
+        assert_eq!(
+            TableSpec::from_polygon(&[v(0.0, 0.0), v(1.0, 0.0)]),
+            Err(PolygonError::TooFewVertices { count: 2 })
+        );
+        assert_eq!(
+            TableSpec::from_polygon(&[v(0.0, 0.0), v(1.0, f64::NAN), v(0.0, 1.0)]),
+            Err(PolygonError::NonFiniteVertex { index: 1 })
+        );
+        assert_eq!(
+            TableSpec::from_polygon(&[v(0.0, 0.0), v(1.0, 0.0), v(1.0, 0.0), v(0.0, 1.0)]),
+            Err(PolygonError::DuplicateVertex { index: 2 })
+        );
+        // Collinear vertices fold back onto themselves.
+        assert!(matches!(
+            TableSpec::from_polygon(&[v(0.0, 0.0), v(1.0, 1.0), v(2.0, 2.0)]),
+            Err(PolygonError::SelfIntersecting { .. })
+        ));
+    }
+
+    #[test]
+    fn self_intersections_are_rejected() {
+        // Bow tie: edges 0 and 2 cross.
+        let bow_tie = [v(0.0, 0.0), v(1.0, 1.0), v(1.0, 0.0), v(0.0, 1.0)];
+        assert_eq!(
+            TableSpec::from_polygon(&bow_tie),
+            Err(PolygonError::SelfIntersecting {
+                first: 0,
+                second: 2
+            })
+        );
+
+        // A vertex touching a non-adjacent edge.
+        let pinched = [
+            v(0.0, 0.0),
+            v(2.0, 0.0),
+            v(2.0, 2.0),
+            v(1.0, 0.0),
+            v(0.0, 2.0),
+        ];
+        assert!(matches!(
+            TableSpec::from_polygon(&pinched),
+            Err(PolygonError::SelfIntersecting { .. })
+        ));
+
+        // A spike doubling back along its own edge.
+        let spike = [v(0.0, 0.0), v(2.0, 0.0), v(1.0, 0.0), v(1.0, 1.0)];
+        assert!(matches!(
+            TableSpec::from_polygon(&spike),
+            Err(PolygonError::SelfIntersecting { .. })
+        ));
+    }
+}
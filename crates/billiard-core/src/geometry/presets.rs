@@ -0,0 +1,331 @@
+//! Built-in table presets: the [standard tables](super::standard_tables)
+//! under short names, with named parameters and their default values.
+//!
+//! The CLI and the API both resolve preset names against [`PRESETS`], so
+//! they offer the same tables with the same defaults and checks.
+
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::fmt;
+
+use serde_json::Value;
+
+use super::standard_tables::{
+    annulus, circle, ellipse, fermi_ulam, l_shape, lorentz_gas, mushroom, rectangle,
+    sawtooth_channel, sinai, stadium,
+};
+use super::table_spec::{Metadata, TableSpec};
+
+/// A built-in table with named numeric parameters.
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Parameter names and default values, in the order `build` takes them.
+    pub parameters: &'static [(&'static str, f64)],
+    /// Check the parameter values and build the table.
+    build: fn(&[f64]) -> Result<TableSpec, &'static str>,
+}
+
+/// Why a preset could not be built with the values asked for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PresetError {
+    /// The preset has no parameter of this name.
+    UnknownParameter {
+        preset: &'static str,
+        parameter: String,
+    },
+
+    /// A parameter was given a value that is not a finite number.
+    NotFinite(String),
+
+    /// The values do not make a table of this kind; the message says why.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::UnknownParameter { preset, parameter } => {
+                write!(
+                    f,
+                    "unknown parameter {:?} for table {:?}",
+                    parameter, preset
+                )
+            }
+            PresetError::NotFinite(parameter) => {
+                write!(f, "{} must be a finite number", parameter)
+            }
+            PresetError::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+/// Every built-in preset, in the order they are listed.
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "square",
+        description: "square with corner at the origin",
+        parameters: &[("side", 1.0)],
+        build: |p| {
+            require(p[0] > 0.0, "side must be positive")?;
+            Ok(rectangle(p[0], p[0]))
+        },
+    },
+    Preset {
+        name: "circle",
+        description: "disk centered at the origin",
+        parameters: &[("radius", 1.0)],
+        build: |p| {
+            require(p[0] > 0.0, "radius must be positive")?;
+            Ok(circle(p[0]))
+        },
+    },
+    Preset {
+        name: "stadium",
+        description: "Bunimovich stadium: half-disks joined by straight edges",
+        parameters: &[("length", 1.0), ("radius", 0.5)],
+        build: |p| {
+            require(p[0] > 0.0, "length must be positive")?;
+            require(p[1] > 0.0, "radius must be positive")?;
+            Ok(stadium(p[0], p[1]))
+        },
+    },
+    Preset {
+        name: "sinai",
+        description: "square with a central disk scatterer",
+        parameters: &[("side", 1.0), ("radius", 0.2)],
+        build: |p| {
+            require(p[1] > 0.0, "radius must be positive")?;
+            require(2.0 * p[1] < p[0], "the disk must fit inside the square")?;
+            Ok(sinai(p[0], p[1]))
+        },
+    },
+    Preset {
+        name: "annulus",
+        description: "disk with a circular obstacle, off center along the x-axis",
+        parameters: &[
+            ("outer_radius", 1.0),
+            ("inner_radius", 0.3),
+            ("offset", 0.4),
+        ],
+        build: |p| {
+            require(p[1] > 0.0, "inner_radius must be positive")?;
+            require(
+                p[2].abs() + p[1] < p[0],
+                "the obstacle must fit inside the outer disk",
+            )?;
+            Ok(annulus(p[0], p[1], p[2]))
+        },
+    },
+    Preset {
+        name: "mushroom",
+        description: "Bunimovich mushroom: half-disk cap on a rectangular stem",
+        parameters: &[
+            ("cap_radius", 1.0),
+            ("stem_width", 0.5),
+            ("stem_height", 1.0),
+        ],
+        build: |p| {
+            require(p[1] > 0.0, "stem_width must be positive")?;
+            require(p[2] > 0.0, "stem_height must be positive")?;
+            require(p[1] < 2.0 * p[0], "the stem must be narrower than the cap")?;
+            Ok(mushroom(p[0], p[1], p[2]))
+        },
+    },
+    Preset {
+        name: "ellipse",
+        description: "ellipse centered at the origin (polygonal approximation)",
+        parameters: &[("a", 1.0), ("b", 0.6)],
+        build: |p| {
+            require(p[0] > 0.0 && p[1] > 0.0, "semi-axes must be positive")?;
+            Ok(ellipse(p[0], p[1]))
+        },
+    },
+    Preset {
+        name: "l_shape",
+        description: "rectangle with a rectangular notch cut from its top-right corner",
+        parameters: &[
+            ("width", 1.0),
+            ("height", 1.0),
+            ("cut_width", 0.5),
+            ("cut_height", 0.5),
+        ],
+        build: |p| {
+            require(
+                p[2] > 0.0 && p[2] < p[0],
+                "cut_width must be positive and less than width",
+            )?;
+            require(
+                p[3] > 0.0 && p[3] < p[1],
+                "cut_height must be positive and less than height",
+            )?;
+            Ok(l_shape(p[0], p[1], p[2], p[3]))
+        },
+    },
+    Preset {
+        name: "lorentz",
+        description: "periodic Lorentz gas cell with a central disk scatterer",
+        parameters: &[("cell", 1.0), ("radius", 0.4)],
+        build: |p| {
+            require(p[1] > 0.0, "radius must be positive")?;
+            require(2.0 * p[1] < p[0], "the disk must fit inside the cell")?;
+            Ok(lorentz_gas(p[0], p[1]))
+        },
+    },
+    Preset {
+        name: "sawtooth_channel",
+        description: "one period of an infinite channel between walls of wedge-shaped teeth",
+        parameters: &[
+            ("teeth", 3.0),
+            ("tooth_length", 1.0),
+            ("width", 1.0),
+            ("depth", 0.25),
+            ("apex", 0.5),
+        ],
+        build: |p| {
+            require(
+                p[0] >= 1.0 && p[0] <= 1000.0 && p[0].fract() == 0.0,
+                "teeth must be a whole number from 1 to 1000",
+            )?;
+            require(p[1] > 0.0, "tooth_length must be positive")?;
+            require(
+                p[3] > 0.0 && 2.0 * p[3] < p[2],
+                "depth must be positive and less than half the width",
+            )?;
+            require(
+                p[4] > 0.0 && p[4] < 1.0,
+                "apex must lie strictly between 0 and 1",
+            )?;
+            Ok(sawtooth_channel(p[0] as usize, p[1], p[2], p[3], p[4]))
+        },
+    },
+    Preset {
+        name: "fermi_ulam",
+        description: "rectangle whose top wall oscillates along its normal, for Fermi acceleration",
+        parameters: &[
+            ("width", 1.0),
+            ("height", 1.0),
+            ("velocity", 0.1),
+            ("frequency", TAU),
+        ],
+        build: |p| {
+            require(
+                p[0] > 0.0 && p[1] > 0.0,
+                "width and height must be positive",
+            )?;
+            Ok(fermi_ulam(p[0], p[1], p[2], p[3]))
+        },
+    },
+];
+
+fn require(condition: bool, message: &'static str) -> Result<(), &'static str> {
+    if condition { Ok(()) } else { Err(message) }
+}
+
+impl Preset {
+    /// Look up a preset by name.
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.name == name)
+    }
+
+    /// Names of the parameters, in order.
+    pub fn parameter_names(&self) -> impl Iterator<Item = &'static str> {
+        self.parameters.iter().map(|&(name, _)| name)
+    }
+
+    /// The table built from the default values.
+    pub fn default_table(&self) -> TableSpec {
+        self.table(&HashMap::new())
+            .expect("preset defaults are valid")
+    }
+
+    /// The table built from the defaults, with the parameters in
+    /// `overrides` replaced.
+    ///
+    /// Its metadata gives the preset name as its title, its description
+    /// and the parameters it was built with.
+    pub fn table(&self, overrides: &HashMap<String, f64>) -> Result<TableSpec, PresetError> {
+        let mut values: Vec<f64> = self.parameters.iter().map(|&(_, v)| v).collect();
+        for (key, &value) in overrides {
+            let index = self
+                .parameter_names()
+                .position(|name| name == key)
+                .ok_or_else(|| PresetError::UnknownParameter {
+                    preset: self.name,
+                    parameter: key.clone(),
+                })?;
+            if !value.is_finite() {
+                return Err(PresetError::NotFinite(key.clone()));
+            }
+            values[index] = value;
+        }
+
+        let mut table = (self.build)(&values).map_err(PresetError::Invalid)?;
+        table.metadata = Some(self.metadata(&values));
+        Ok(table)
+    }
+
+    /// Metadata of the table built from `values`.
+    fn metadata(&self, values: &[f64]) -> Metadata {
+        let parameters = self
+            .parameter_names()
+            .zip(values)
+            .map(|(name, &value)| (name.to_string(), Value::from(value)))
+            .collect::<Metadata>();
+        Metadata::from_iter([
+            ("title".to_string(), Value::from(self.name)),
+            ("description".to_string(), Value::from(self.description)),
+            ("parameters".to_string(), Value::Object(parameters)),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PRESETS, Preset, PresetError};
+    use crate::geometry::validation::validate_table;
+    use std::collections::HashMap;
+
+    #[test]
+    fn every_default_table_is_valid() {
+        for preset in PRESETS {
+            let report = validate_table(&preset.default_table());
+            assert!(report.is_valid(), "{}: {:?}", preset.name, report);
+        }
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults_and_are_recorded() {
+        let preset = Preset::find("sinai").unwrap();
+        let table = preset
+            .table(&HashMap::from([("radius".to_string(), 0.3)]))
+            .unwrap();
+        let metadata = table.metadata.unwrap();
+        assert_eq!(metadata["title"], "sinai");
+        assert_eq!(metadata["parameters"]["side"], 1.0);
+        assert_eq!(metadata["parameters"]["radius"], 0.3);
+    }
+
+    #[test]
+    fn bad_overrides_are_rejected() {
+        let preset = Preset::find("sinai").unwrap();
+        let table = |key: &str, value| preset.table(&HashMap::from([(key.to_string(), value)]));
+        assert!(matches!(
+            table("depth", 0.1),
+            Err(PresetError::UnknownParameter {
+                preset: "sinai",
+                ..
+            })
+        ));
+        assert_eq!(
+            table("side", f64::NAN).unwrap_err(),
+            PresetError::NotFinite("side".to_string())
+        );
+        assert_eq!(
+            table("radius", 0.5).unwrap_err(),
+            PresetError::Invalid("the disk must fit inside the square")
+        );
+    }
+}
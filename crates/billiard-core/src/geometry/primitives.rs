@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::ops::{Add, Div, Mul, Sub};
+use std::ops::{Add, Div, Mul, Neg, Sub};
 
 const LENGTH_LOWER_BOUND: f64 = 1e-10;
 
@@ -10,6 +10,7 @@ const LENGTH_LOWER_BOUND: f64 = 1e-10;
 /// - add more methods (e.g., `distance_to`, `angle`, etc.),
 /// - or swap this out for a library type (like `glam::DVec2`).
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,
@@ -39,6 +40,18 @@ impl Sub for Vec2 {
     }
 }
 
+impl Neg for Vec2 {
+    type Output = Self;
+
+    /// Component-wise negation.
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
 impl Mul<f64> for Vec2 {
     type Output = Self;
 
@@ -125,6 +138,12 @@ impl Vec2 {
         self.x * other.x + self.y * other.y
     }
 
+    /// The z-component of the cross product, `x₁y₂ - y₁x₂`: positive when
+    /// `other` lies counterclockwise of `self`.
+    pub fn cross(self, other: Self) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
     /// Returns a vector perpendicular to this one, rotated +90 degrees.
     ///
     /// Mathematically, this is (-y, x). This is the "left turn" of the vector,
@@ -137,9 +156,62 @@ impl Vec2 {
     }
 }
 
+/// Axis-aligned rectangle `[min.x, max.x] × [min.y, max.y]`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BoundingBox {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl BoundingBox {
+    /// Construct a box from its lower-left and upper-right corners.
+    ///
+    /// # Panics
+    /// Panics if `min` is not strictly below and to the left of `max`.
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        assert!(
+            min.x < max.x && min.y < max.y,
+            "Bounding box min must be strictly less than max."
+        );
+        Self { min, max }
+    }
+
+    /// Whether `p` lies inside the box or on its edge.
+    pub fn contains(&self, p: Vec2) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x) && (self.min.y..=self.max.y).contains(&p.y)
+    }
+
+    /// Point where the ray `origin + t·direction` (t ≥ 0) leaves the box.
+    ///
+    /// If the origin already lies outside the box and the ray moves away
+    /// from it, the origin itself is returned.
+    pub fn exit_point(&self, origin: Vec2, direction: Vec2) -> Vec2 {
+        let axis_exit = |o: f64, d: f64, lo: f64, hi: f64| {
+            if d > 0.0 {
+                (hi - o) / d
+            } else if d < 0.0 {
+                (lo - o) / d
+            } else {
+                f64::INFINITY
+            }
+        };
+
+        let t = axis_exit(origin.x, direction.x, self.min.x, self.max.x)
+            .min(axis_exit(origin.y, direction.y, self.min.y, self.max.y))
+            .max(0.0);
+
+        if t.is_finite() {
+            origin + direction * t
+        } else {
+            origin
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Vec2;
+    use super::{BoundingBox, Vec2};
 
     #[test]
     fn length_and_normalization_work() {
@@ -168,4 +240,19 @@ mod tests {
         assert!((p.x - 0.0).abs() < 1e-12);
         assert!((p.y - 1.0).abs() < 1e-12);
     }
+
+    #[test]
+    fn bounding_box_exit_point() {
+        let bbox = BoundingBox::new(Vec2::new(-1.0, -1.0), Vec2::new(1.0, 1.0));
+        assert!(bbox.contains(Vec2::new(0.5, -1.0)));
+        assert!(!bbox.contains(Vec2::new(1.5, 0.0)));
+
+        let exit = bbox.exit_point(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.5));
+        assert!((exit.x - 1.0).abs() < 1e-12);
+        assert!((exit.y - 0.5).abs() < 1e-12);
+
+        let exit = bbox.exit_point(Vec2::new(0.5, 0.0), Vec2::new(0.0, -2.0));
+        assert!((exit.x - 0.5).abs() < 1e-12);
+        assert!((exit.y + 1.0).abs() < 1e-12);
+    }
 }
@@ -0,0 +1,297 @@
+//! Number types for computation in other precisions than `f64`.
+//!
+//! Everything else in the crate works in `f64`. Long trajectories of
+//! chaotic tables lose all accuracy after a few hundred bounces, since
+//! roundoff is amplified exponentially; [`Scalar`] lets the elastic
+//! simulation in [`precise`](crate::dynamics::precise) run in
+//! [`DoubleDouble`] instead, which carries about 32 significant digits.
+//! It can also run in `f32`, for callers that want half the memory per
+//! collision and the same arithmetic as a GPU, and can live with about
+//! seven digits.
+
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::primitives::Vec2;
+
+/// A real number type the precise simulation can be run in.
+pub trait Scalar:
+    Copy
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// Conversion from `f64`; exact unless `Self` is less precise, in
+    /// which case it rounds to nearest.
+    fn from_f64(x: f64) -> Self;
+
+    /// Nearest `f64`.
+    fn to_f64(self) -> f64;
+
+    fn sqrt(self) -> Self;
+
+    fn zero() -> Self {
+        Self::from_f64(0.0)
+    }
+
+    fn abs(self) -> Self {
+        if self < Self::zero() { -self } else { self }
+    }
+}
+
+impl Scalar for f64 {
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+}
+
+impl Scalar for f32 {
+    fn from_f64(x: f64) -> Self {
+        x as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+}
+
+/// An unevaluated sum `hi + lo` of two `f64`s with `|lo| ≤ ulp(hi) / 2`,
+/// giving roughly twice the precision of `f64` (but not its range).
+///
+/// Arithmetic follows the classic error-free transformations (Dekker,
+/// Knuth); the comparison is lexicographic, which is correct because the
+/// representation is normalized.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+/// `a + b` as an exact sum `s + e`.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+/// `a + b` as an exact sum `s + e`, assuming `|a| ≥ |b|`.
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+/// `a * b` as an exact sum `p + e`.
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+impl DoubleDouble {
+    /// The number `hi + lo`, renormalized.
+    pub fn new(hi: f64, lo: f64) -> Self {
+        let (hi, lo) = two_sum(hi, lo);
+        DoubleDouble { hi, lo }
+    }
+
+    /// Leading and trailing parts.
+    pub fn parts(self) -> (f64, f64) {
+        (self.hi, self.lo)
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let (s, e) = two_sum(self.hi, rhs.hi);
+        let (t, f) = two_sum(self.lo, rhs.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        let (hi, lo) = quick_two_sum(s, e + f);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let (p, e) = two_prod(self.hi, rhs.hi);
+        let e = e + (self.hi * rhs.lo + self.lo * rhs.hi);
+        let (hi, lo) = quick_two_sum(p, e);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+
+    /// Long division: three `f64` quotient digits, each correcting the
+    /// remainder of the last.
+    fn div(self, rhs: Self) -> Self {
+        let q1 = self.hi / rhs.hi;
+        let r = self - rhs * DoubleDouble::from_f64(q1);
+        let q2 = r.hi / rhs.hi;
+        let r = r - rhs * DoubleDouble::from_f64(q2);
+        let q3 = r.hi / rhs.hi;
+
+        let (hi, lo) = quick_two_sum(q1, q2);
+        DoubleDouble { hi, lo } + DoubleDouble::from_f64(q3)
+    }
+}
+
+impl Scalar for DoubleDouble {
+    fn from_f64(x: f64) -> Self {
+        DoubleDouble { hi: x, lo: 0.0 }
+    }
+
+    fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    /// One Newton step from the `f64` square root doubles its precision.
+    fn sqrt(self) -> Self {
+        if self.hi <= 0.0 {
+            return DoubleDouble::from_f64(self.hi.sqrt());
+        }
+        let x = DoubleDouble::from_f64(self.hi.sqrt());
+        x + (self - x * x) / (DoubleDouble::from_f64(2.0) * x)
+    }
+}
+
+/// A 2D vector over any [`Scalar`]; the generic counterpart of
+/// [`Vec2`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vector<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Scalar> Vector<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Vector { x, y }
+    }
+
+    /// Conversion from a [`Vec2`], exact unless `T` is `f32`.
+    pub fn from_vec2(v: Vec2) -> Self {
+        Vector::new(T::from_f64(v.x), T::from_f64(v.y))
+    }
+
+    /// Nearest [`Vec2`].
+    pub fn to_vec2(self) -> Vec2 {
+        Vec2::new(self.x.to_f64(), self.y.to_f64())
+    }
+
+    pub fn dot(self, other: Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// z-component of the 3D cross product.
+    pub fn cross(self, other: Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(self) -> T {
+        self.dot(self).sqrt()
+    }
+
+    pub fn scale(self, k: T) -> Self {
+        Vector::new(self.x * k, self.y * k)
+    }
+
+    /// This vector divided by its length.
+    pub fn normalized(self) -> Self {
+        let length = self.length();
+        Vector::new(self.x / length, self.y / length)
+    }
+}
+
+impl<T: Scalar> Add for Vector<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Vector::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T: Scalar> Sub for Vector<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Vector::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DoubleDouble, Scalar};
+
+    fn dd(x: f64) -> DoubleDouble {
+        DoubleDouble::from_f64(x)
+    }
+
+    #[test]
+    fn keeps_digits_that_f64_drops() {
+        let tiny = dd(1e-20);
+        assert_eq!((dd(1.0) + tiny - dd(1.0)).to_f64(), 1e-20);
+        assert_eq!((1.0 + 1e-20) - 1.0, 0.0);
+
+        // 1/3 to about 32 digits: 3 * (1/3) - 1 vanishes to that order.
+        let third = dd(1.0) / dd(3.0);
+        assert!((third * dd(3.0) - dd(1.0)).to_f64().abs() < 1e-31);
+        assert!(third.parts().1 != 0.0);
+    }
+
+    #[test]
+    fn square_root_is_accurate_to_double_double_precision() {
+        for x in [2.0, 3.0, 0.1, 12345.678] {
+            let r = dd(x).sqrt();
+            let error = (r * r - dd(x)).to_f64();
+            assert!(error.abs() < 1e-30 * x, "sqrt({}) error {}", x, error);
+        }
+        assert_eq!(dd(0.0).sqrt().to_f64(), 0.0);
+    }
+
+    #[test]
+    fn ordering_uses_the_trailing_part() {
+        let one = dd(1.0);
+        let above = one + dd(1e-25);
+        assert!(above > one);
+        assert!(-above < -one);
+        assert_eq!((one - above).abs(), above - one);
+    }
+}
@@ -1,19 +1,130 @@
+use std::f64::consts::TAU;
+use std::fmt;
+use std::sync::Arc;
+
 use super::primitives::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// What happens to a particle that hits a boundary segment.
+///
+/// Only `PartialEq`, not `Eq`: heated and moving walls carry `f64`
+/// parameters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum BoundaryCondition {
+    /// Specular reflection: angle of incidence equals angle of reflection.
+    #[default]
+    Reflect,
+
+    /// The particle leaves the table through this segment.
+    Absorb,
+
+    /// The particle re-enters through the segment with the given index on
+    /// the same component, keeping its direction. Positions are matched in
+    /// opposite arc-length order, so paired edges of a CCW polygon line up
+    /// (e.g. bottom and top of a square give a torus).
+    PeriodicPairedWith(usize),
+
+    /// No-slip wall for a spinless point particle: both the normal and the
+    /// tangential velocity reverse, sending the particle back along its
+    /// incoming path. Spin is not tracked by the billiard state.
+    NoSlipRotate,
+
+    /// Wall held at `temperature`: under the
+    /// [`Thermal`](crate::dynamics::reflection::Thermal) law the particle
+    /// leaves with a velocity drawn afresh from the wall's Maxwellian, in
+    /// units where the particle's mass and Boltzmann's constant are 1.
+    /// Other laws reflect off it as off a `Reflect` segment.
+    Thermal { temperature: f64 },
+
+    /// Wall moving along its inward normal with velocity
+    /// `velocity · cos(frequency · t + phase)` at time `t`, kicking the
+    /// particles that hit it as in the Fermi–Ulam model. The wall stays
+    /// where it is drawn: its motion only enters the reflection, which is
+    /// mirror-like in the wall's frame.
+    Oscillating {
+        velocity: f64,
+        frequency: f64,
+        phase: f64,
+    },
+}
+
+impl BoundaryCondition {
+    /// Whether this is the default specular reflection.
+    pub fn is_reflect(&self) -> bool {
+        *self == BoundaryCondition::Reflect
+    }
+
+    /// Velocity of the wall along its inward normal at `time`: zero but
+    /// for [`Oscillating`](BoundaryCondition::Oscillating) walls.
+    pub fn wall_velocity(&self, time: f64) -> f64 {
+        match *self {
+            BoundaryCondition::Oscillating {
+                velocity,
+                frequency,
+                phase,
+            } => velocity * (frequency * time + phase).cos(),
+            _ => 0.0,
+        }
+    }
+
+    /// Whether a bounce off this wall can change the particle's energy, as
+    /// heated and moving walls do.
+    pub fn does_work(&self) -> bool {
+        matches!(
+            self,
+            BoundaryCondition::Thermal { .. } | BoundaryCondition::Oscillating { .. }
+        )
+    }
+}
+
+/// What a segment is called by the author of its table.
+///
+/// Segment indices change whenever the geometry is edited; an id or a name
+/// lets results say which wall was hit in terms that survive the edit.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SegmentLabel {
+    /// Identifier meant to stay the same from one version of the table to
+    /// the next.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+
+    /// Name for people to read, such as "left cushion".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub name: Option<Arc<str>>,
+}
+
+impl SegmentLabel {
+    /// Whether the segment has neither an id nor a name.
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none() && self.name.is_none()
+    }
+}
 
 /// A straight line segment from `start` to `end`.
 ///
 /// The segment is oriented: arc-length parameter increases from `start`
 /// toward `end`.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct LineSegment {
     pub start: Vec2,
     pub end: Vec2,
+    pub condition: BoundaryCondition,
+    pub label: SegmentLabel,
 }
 
 impl LineSegment {
     /// Constructs a new line segment from `start` to `end`.
     pub fn new(start: Vec2, end: Vec2) -> Self {
-        Self { start, end }
+        Self {
+            start,
+            end,
+            condition: BoundaryCondition::Reflect,
+            label: SegmentLabel::default(),
+        }
     }
 
     /// Returns the total arc length of this segment.
@@ -36,6 +147,20 @@ impl LineSegment {
     pub fn tangent_at(&self, _t: f64) -> Vec2 {
         (self.end - self.start).normalized()
     }
+
+    /// Returns the signed curvature at local parameter `t`, which is zero.
+    pub fn curvature_at(&self, _t: f64) -> f64 {
+        0.0
+    }
+
+    /// Returns the local parameter of the point of the segment closest to
+    /// `p`, and the distance to it.
+    pub fn closest_point(&self, p: Vec2) -> (f64, f64) {
+        let len = self.length();
+        let along = (p - self.start).dot(self.end - self.start) / len;
+        let t = along.clamp(0.0, len);
+        (t, (p - self.point_at(t)).length())
+    }
 }
 
 /// A circular arc segment between two angles on a circle.
@@ -47,7 +172,7 @@ impl LineSegment {
 /// - and an orientation (CCW vs CW).
 ///
 /// The parameter `t` for `point_at(t)` is arc-length [0, length()].
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct CircularArcSegment {
     pub center: Vec2,
     pub radius: f64,
@@ -56,6 +181,8 @@ pub struct CircularArcSegment {
     pub ccw: bool,
     pub start: Vec2,
     pub end: Vec2,
+    pub condition: BoundaryCondition,
+    pub label: SegmentLabel,
 }
 
 impl CircularArcSegment {
@@ -82,6 +209,8 @@ impl CircularArcSegment {
             ccw,
             start,
             end,
+            condition: BoundaryCondition::Reflect,
+            label: SegmentLabel::default(),
         }
     }
 
@@ -114,17 +243,135 @@ impl CircularArcSegment {
             Vec2::new(theta.sin(), -theta.cos())
         }
     }
+
+    /// Returns the signed curvature at local parameter `t`: `1/r` for a CCW
+    /// arc (turning left) and `-1/r` for a CW arc.
+    pub fn curvature_at(&self, _t: f64) -> f64 {
+        if self.ccw {
+            1.0 / self.radius
+        } else {
+            -1.0 / self.radius
+        }
+    }
+
+    /// Returns the local parameter of the point of the arc closest to `p`,
+    /// and the distance to it.
+    ///
+    /// Points in the arc's angular span project radially; others snap to
+    /// the nearer endpoint. The center itself projects to the start.
+    pub fn closest_point(&self, p: Vec2) -> (f64, f64) {
+        let rel = p - self.center;
+        let sweep = (self.end_angle - self.start_angle).abs();
+        let direction = if self.ccw { 1.0 } else { -1.0 };
+        // Angle from the start in the direction of travel, in [0, 2π).
+        let offset = (direction * (rel.y.atan2(rel.x) - self.start_angle)).rem_euclid(TAU);
+
+        if rel.length() > 0.0 && offset <= sweep {
+            return (self.radius * offset, (rel.length() - self.radius).abs());
+        }
+        let to_start = (p - self.start).length();
+        let to_end = (p - self.end).length();
+        if to_start <= to_end {
+            (0.0, to_start)
+        } else {
+            (self.length(), to_end)
+        }
+    }
+}
+
+/// A boundary curve of a type defined outside this crate.
+///
+/// Wrap an implementation in an [`ExternalSegment`] to put it on a table
+/// next to the built-in segments. Every parameter `t` is arc length from
+/// the start of the curve, in `0.0..=length()`, and the curve is oriented
+/// like the built-in ones: the table lies to the left of the direction of
+/// travel on the outer boundary.
+pub trait Segment: fmt::Debug + Send + Sync {
+    /// Total arc length of the curve.
+    fn length(&self) -> f64;
+
+    /// Point at arc-length parameter `t`.
+    fn point_at(&self, t: f64) -> Vec2;
+
+    /// Unit tangent at `t`, in the direction of increasing `t`.
+    fn tangent_at(&self, t: f64) -> Vec2;
+
+    /// Signed curvature at `t`, positive where the curve turns left.
+    fn curvature_at(&self, t: f64) -> f64;
+
+    /// First crossing of the ray `origin + d · direction`, for a unit
+    /// `direction`, at a distance `d > epsilon`; returns `(d, t)`.
+    fn ray_intersect(&self, origin: Vec2, direction: Vec2, epsilon: f64) -> Option<(f64, f64)>;
+
+    /// Parameter of the point of the curve closest to `p`, and the distance
+    /// to it.
+    ///
+    /// The default samples the curve and refines the best sample by
+    /// golden-section search, which finds the global minimum unless the
+    /// curve winds back on itself between samples.
+    fn closest_point(&self, p: Vec2) -> (f64, f64) {
+        const SAMPLES: usize = 64;
+        let length = self.length();
+        let distance = |t: f64| (self.point_at(t) - p).length();
+
+        let step = length / SAMPLES as f64;
+        let best = (0..=SAMPLES)
+            .map(|i| i as f64 * step)
+            .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(0.0);
+
+        let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+        let (mut a, mut b) = ((best - step).max(0.0), (best + step).min(length));
+        while b - a > 1e-10 * length.max(1.0) {
+            let c = b - ratio * (b - a);
+            let d = a + ratio * (b - a);
+            if distance(c) < distance(d) {
+                b = d;
+            } else {
+                a = c;
+            }
+        }
+        let t = 0.5 * (a + b);
+        (t, distance(t))
+    }
+}
+
+/// A segment whose geometry is supplied by a [`Segment`] implementation.
+///
+/// External segments take part in straight-line flights and corner
+/// detection like the built-in ones. They have no
+/// [`SegmentSpec`](crate::geometry::table_spec::SegmentSpec), so tables
+/// holding them cannot be validated, offset for a ball or sent over the
+/// API. Parabolic flights pass through them, and the extended-precision
+/// simulation and disk gases reject them.
+#[derive(Clone, Debug)]
+pub struct ExternalSegment {
+    pub curve: Arc<dyn Segment>,
+    pub condition: BoundaryCondition,
+    pub label: SegmentLabel,
+}
+
+impl ExternalSegment {
+    /// Wrap `curve` as a reflecting segment.
+    pub fn new(curve: impl Segment + 'static) -> Self {
+        Self {
+            curve: Arc::new(curve),
+            condition: BoundaryCondition::Reflect,
+            label: SegmentLabel::default(),
+        }
+    }
 }
 
 /// A boundary segment of any supported kind.
 ///
-/// For now we only support line segments. Later we can add circular arcs
-/// or other parametric curves.
-#[derive(Clone, Copy, Debug)]
+/// Lines and circular arcs are built in; any other curve can be plugged in
+/// as an [`ExternalSegment`]. That curve is shared behind an `Arc`, so
+/// segments are cheap to clone but not `Copy`.
+#[derive(Clone, Debug)]
 pub enum BoundarySegment {
     Line(LineSegment),
     CircularArc(CircularArcSegment),
-    // ...
+    External(ExternalSegment),
 }
 
 impl BoundarySegment {
@@ -133,6 +380,7 @@ impl BoundarySegment {
         match self {
             BoundarySegment::Line(seg) => seg.length(),
             BoundarySegment::CircularArc(seg) => seg.length(),
+            BoundarySegment::External(seg) => seg.curve.length(),
         }
     }
 
@@ -143,6 +391,7 @@ impl BoundarySegment {
         match self {
             BoundarySegment::Line(seg) => seg.point_at(t),
             BoundarySegment::CircularArc(seg) => seg.point_at(t),
+            BoundarySegment::External(seg) => seg.curve.point_at(t),
         }
     }
 
@@ -151,14 +400,75 @@ impl BoundarySegment {
         match self {
             BoundarySegment::Line(seg) => seg.tangent_at(t),
             BoundarySegment::CircularArc(seg) => seg.tangent_at(t),
+            BoundarySegment::External(seg) => seg.curve.tangent_at(t),
         }
     }
+
+    /// Returns the signed curvature at local parameter `t`, positive where
+    /// the segment turns left in its direction of travel.
+    pub fn curvature_at(&self, t: f64) -> f64 {
+        match self {
+            BoundarySegment::Line(seg) => seg.curvature_at(t),
+            BoundarySegment::CircularArc(seg) => seg.curvature_at(t),
+            BoundarySegment::External(seg) => seg.curve.curvature_at(t),
+        }
+    }
+
+    /// Returns the local parameter of the point of the segment closest to
+    /// `p`, and the distance to it.
+    pub fn closest_point(&self, p: Vec2) -> (f64, f64) {
+        match self {
+            BoundarySegment::Line(seg) => seg.closest_point(p),
+            BoundarySegment::CircularArc(seg) => seg.closest_point(p),
+            BoundarySegment::External(seg) => seg.curve.closest_point(p),
+        }
+    }
+
+    /// Returns the boundary condition applied to hits on this segment.
+    pub fn condition(&self) -> BoundaryCondition {
+        match self {
+            BoundarySegment::Line(seg) => seg.condition,
+            BoundarySegment::CircularArc(seg) => seg.condition,
+            BoundarySegment::External(seg) => seg.condition,
+        }
+    }
+
+    /// Returns a copy of this segment with the given boundary condition.
+    pub fn with_condition(mut self, condition: BoundaryCondition) -> Self {
+        match &mut self {
+            BoundarySegment::Line(seg) => seg.condition = condition,
+            BoundarySegment::CircularArc(seg) => seg.condition = condition,
+            BoundarySegment::External(seg) => seg.condition = condition,
+        }
+        self
+    }
+
+    /// Returns the id and name this segment was given, if any.
+    pub fn label(&self) -> &SegmentLabel {
+        match self {
+            BoundarySegment::Line(seg) => &seg.label,
+            BoundarySegment::CircularArc(seg) => &seg.label,
+            BoundarySegment::External(seg) => &seg.label,
+        }
+    }
+
+    /// Returns a copy of this segment with the given id and name.
+    pub fn with_label(mut self, label: SegmentLabel) -> Self {
+        match &mut self {
+            BoundarySegment::Line(seg) => seg.label = label,
+            BoundarySegment::CircularArc(seg) => seg.label = label,
+            BoundarySegment::External(seg) => seg.label = label,
+        }
+        self
+    }
 }
 
 #[cfg(test)]
 mod arc_tests {
-    use super::{BoundarySegment, CircularArcSegment};
+    use super::{BoundarySegment, CircularArcSegment, LineSegment};
     use crate::geometry::primitives::Vec2;
+    use proptest::prelude::*;
+    use std::f64::consts::PI;
 
     #[test]
     fn circular_arc_length_and_points() {
@@ -190,4 +500,69 @@ mod arc_tests {
         assert!((p1.x - 0.0).abs() < 1e-12);
         assert!((p1.y - 1.0).abs() < 1e-12);
     }
+
+    #[test]
+    fn closest_point_projects_onto_lines_and_arcs() {
+        let line = LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0));
+        assert_eq!(line.closest_point(Vec2::new(1.5, 2.0)), (1.5, 2.0));
+        assert_eq!(line.closest_point(Vec2::new(-3.0, 4.0)), (0.0, 5.0));
+
+        // CW half circle over the top, from angle π to 0.
+        let arc = CircularArcSegment::new(Vec2::new(0.0, 0.0), 2.0, PI, 0.0, false);
+        let (t, d) = arc.closest_point(Vec2::new(0.0, 3.0));
+        assert!((t - PI).abs() < 1e-12 && (d - 1.0).abs() < 1e-12);
+        let (t, d) = arc.closest_point(Vec2::new(1.0, 1.0));
+        assert!((t - 1.5 * PI).abs() < 1e-12);
+        assert!((d - (2.0 - 2.0_f64.sqrt())).abs() < 1e-12);
+
+        // Below the arc: nearest endpoint.
+        let (t, d) = arc.closest_point(Vec2::new(1.5, -1.0));
+        assert!((t - 2.0 * PI).abs() < 1e-12);
+        assert!((d - 0.5_f64.hypot(1.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn curvature_is_signed_by_orientation() {
+        let line =
+            BoundarySegment::Line(LineSegment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0)));
+        assert_eq!(line.curvature_at(0.5), 0.0);
+
+        let ccw = CircularArcSegment::new(Vec2::new(0.0, 0.0), 2.0, 0.0, 1.0, true);
+        let cw = CircularArcSegment::new(Vec2::new(0.0, 0.0), 2.0, 1.0, 0.0, false);
+        assert_eq!(BoundarySegment::CircularArc(ccw).curvature_at(0.3), 0.5);
+        assert_eq!(BoundarySegment::CircularArc(cw).curvature_at(0.3), -0.5);
+    }
+
+    fn point() -> impl Strategy<Value = Vec2> {
+        (-100.0..100.0f64, -100.0..100.0f64).prop_map(|(x, y)| Vec2::new(x, y))
+    }
+
+    proptest! {
+        #[test]
+        fn line_ends_at_its_length(start in point(), end in point()) {
+            prop_assume!((end - start).length() > 1e-6);
+            let line = BoundarySegment::Line(LineSegment::new(start, end));
+            prop_assert!((line.point_at(0.0) - start).length() < 1e-9);
+            prop_assert!((line.point_at(line.length()) - end).length() < 1e-9);
+        }
+
+        /// Holds when `ccw` agrees with the sign of the sweep; otherwise the
+        /// arc runs the other way round the circle.
+        #[test]
+        fn arc_ends_at_its_length(
+            center in point(),
+            radius in 1e-3..100.0f64,
+            start_angle in -10.0..10.0f64,
+            sweep in 1e-6..2.0 * PI,
+            ccw in any::<bool>(),
+        ) {
+            let end_angle = if ccw { start_angle + sweep } else { start_angle - sweep };
+            let arc = CircularArcSegment::new(center, radius, start_angle, end_angle, ccw);
+            let segment = BoundarySegment::CircularArc(arc.clone());
+            let scale = 1e-12 * (center.length() + radius);
+            prop_assert!((segment.point_at(0.0) - arc.start).length() < scale);
+            prop_assert!((segment.point_at(segment.length()) - arc.end).length() < scale);
+            prop_assert!((segment.tangent_at(0.5 * segment.length()).length() - 1.0).abs() < 1e-12);
+        }
+    }
 }
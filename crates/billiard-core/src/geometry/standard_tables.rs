@@ -0,0 +1,706 @@
+//! Parametric constructors for the standard billiard tables.
+//!
+//! Every builder returns a [`TableSpec`] so the result can be serialized,
+//! stored, or converted into a `BilliardTable` with
+//! [`TableSpec::to_billiard_table`]. All outer boundaries are oriented
+//! counterclockwise.
+
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use super::primitives::Vec2;
+use super::segments::{BoundaryCondition, SegmentLabel};
+use super::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+/// Number of line segments used to approximate an ellipse.
+///
+/// There is no elliptical segment kind yet, so ellipses are represented by
+/// an inscribed polygon with this many vertices.
+pub const ELLIPSE_SEGMENTS: usize = 256;
+
+fn line(start: Vec2, end: Vec2) -> SegmentSpec {
+    SegmentSpec::Line {
+        start,
+        end,
+        condition: BoundaryCondition::Reflect,
+        label: SegmentLabel::default(),
+    }
+}
+
+fn ccw_arc(center: Vec2, radius: f64, start_angle: f64, end_angle: f64) -> SegmentSpec {
+    SegmentSpec::CircularArc {
+        center,
+        radius,
+        start_angle,
+        end_angle,
+        ccw: true,
+        condition: BoundaryCondition::Reflect,
+        label: SegmentLabel::default(),
+    }
+}
+
+/// Axis-aligned rectangle occupying `[0, width] x [0, height]`.
+///
+/// # Panics
+/// Panics if `width` or `height` is not strictly positive.
+pub fn rectangle(width: f64, height: f64) -> TableSpec {
+    assert!(width > 0.0, "Rectangle width must be positive.");
+    assert!(height > 0.0, "Rectangle height must be positive.");
+
+    TableSpec::from_polygon(&[
+        Vec2::new(0.0, 0.0),
+        Vec2::new(width, 0.0),
+        Vec2::new(width, height),
+        Vec2::new(0.0, height),
+    ])
+    .expect("rectangles are simple")
+}
+
+/// Fermi–Ulam table: the rectangle `[0, width] x [0, height]` with its top
+/// wall [oscillating](BoundaryCondition::Oscillating) at normal velocity
+/// `velocity · cos(frequency · t)`. A particle bouncing between the floor
+/// and the moving wall gains and loses speed as in Fermi acceleration.
+///
+/// # Panics
+/// Panics if `width` or `height` is not strictly positive.
+pub fn fermi_ulam(width: f64, height: f64, velocity: f64, frequency: f64) -> TableSpec {
+    let mut spec = rectangle(width, height);
+    spec.outer.segments[2] = SegmentSpec::Line {
+        start: Vec2::new(width, height),
+        end: Vec2::new(0.0, height),
+        condition: BoundaryCondition::Oscillating {
+            velocity,
+            frequency,
+            phase: 0.0,
+        },
+        label: SegmentLabel::default(),
+    };
+    spec
+}
+
+/// Circular table of radius `r` centered at the origin, as a single arc
+/// starting at `(r, 0)`.
+///
+/// # Panics
+/// Panics if `r` is not strictly positive.
+pub fn circle(r: f64) -> TableSpec {
+    assert!(r > 0.0, "Circle radius must be positive.");
+
+    TableSpec {
+        outer: BoundarySpec {
+            name: "circle".to_string(),
+            segments: vec![ccw_arc(Vec2::new(0.0, 0.0), r, 0.0, TAU)],
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// L-shaped table: the rectangle `[0, width] x [0, height]` with a
+/// `cut_width` by `cut_height` rectangle removed from its top-right corner.
+///
+/// # Panics
+/// Panics unless `0 < cut_width < width` and `0 < cut_height < height`.
+pub fn l_shape(width: f64, height: f64, cut_width: f64, cut_height: f64) -> TableSpec {
+    assert!(
+        cut_width > 0.0 && cut_width < width,
+        "L-shape cut must be narrower than the table."
+    );
+    assert!(
+        cut_height > 0.0 && cut_height < height,
+        "L-shape cut must be lower than the table."
+    );
+
+    let (x, y) = (width - cut_width, height - cut_height);
+    TableSpec::from_polygon(&[
+        Vec2::new(0.0, 0.0),
+        Vec2::new(width, 0.0),
+        Vec2::new(width, y),
+        Vec2::new(x, y),
+        Vec2::new(x, height),
+        Vec2::new(0.0, height),
+    ])
+    .expect("an L-shape with a valid cut is simple")
+}
+
+/// Bunimovich stadium: two half-disks of radius `r` joined by straight
+/// edges of length `l`.
+///
+/// The table is centered at the origin with the straight edges parallel
+/// to the x-axis at `y = ±r`.
+///
+/// # Panics
+/// Panics if `l` or `r` is not strictly positive.
+pub fn stadium(l: f64, r: f64) -> TableSpec {
+    assert!(l > 0.0, "Stadium straight length must be positive.");
+    assert!(r > 0.0, "Stadium radius must be positive.");
+
+    let h = 0.5 * l;
+    let segments = vec![
+        line(Vec2::new(-h, -r), Vec2::new(h, -r)),
+        ccw_arc(Vec2::new(h, 0.0), r, -FRAC_PI_2, FRAC_PI_2),
+        line(Vec2::new(h, r), Vec2::new(-h, r)),
+        ccw_arc(Vec2::new(-h, 0.0), r, FRAC_PI_2, 3.0 * FRAC_PI_2),
+    ];
+
+    TableSpec {
+        outer: BoundarySpec {
+            name: "stadium".to_string(),
+            segments,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Bunimovich mushroom: a half-disk cap of radius `cap_r` sitting on a
+/// rectangular stem of width `stem_w` and height `stem_h`.
+///
+/// The cap is centered at the origin (occupying `y >= 0`) and the stem
+/// hangs below it, centered on the y-axis.
+///
+/// # Panics
+/// Panics if any parameter is not strictly positive or if the stem is not
+/// narrower than the cap (`stem_w < 2 * cap_r`).
+pub fn mushroom(cap_r: f64, stem_w: f64, stem_h: f64) -> TableSpec {
+    assert!(cap_r > 0.0, "Mushroom cap radius must be positive.");
+    assert!(stem_w > 0.0, "Mushroom stem width must be positive.");
+    assert!(stem_h > 0.0, "Mushroom stem height must be positive.");
+    assert!(
+        stem_w < 2.0 * cap_r,
+        "Mushroom stem must be narrower than the cap."
+    );
+
+    let w = 0.5 * stem_w;
+    let segments = vec![
+        line(Vec2::new(-w, -stem_h), Vec2::new(w, -stem_h)),
+        line(Vec2::new(w, -stem_h), Vec2::new(w, 0.0)),
+        line(Vec2::new(w, 0.0), Vec2::new(cap_r, 0.0)),
+        ccw_arc(Vec2::new(0.0, 0.0), cap_r, 0.0, PI),
+        line(Vec2::new(-cap_r, 0.0), Vec2::new(-w, 0.0)),
+        line(Vec2::new(-w, 0.0), Vec2::new(-w, -stem_h)),
+    ];
+
+    TableSpec {
+        outer: BoundarySpec {
+            name: "mushroom".to_string(),
+            segments,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Sinai billiard: a square of side `square` with a circular scatterer of
+/// radius `r` at its center.
+///
+/// The square occupies `[0, square] x [0, square]`.
+///
+/// # Panics
+/// Panics if the parameters are not strictly positive or if the disk does
+/// not fit strictly inside the square (`2 * r < square`).
+pub fn sinai(square: f64, r: f64) -> TableSpec {
+    assert!(square > 0.0, "Sinai square side must be positive.");
+    assert!(r > 0.0, "Sinai obstacle radius must be positive.");
+    assert!(
+        2.0 * r < square,
+        "Sinai obstacle must fit strictly inside the square."
+    );
+
+    let c = 0.5 * square;
+    let outer = BoundarySpec {
+        name: "outer".to_string(),
+        segments: vec![
+            line(Vec2::new(0.0, 0.0), Vec2::new(square, 0.0)),
+            line(Vec2::new(square, 0.0), Vec2::new(square, square)),
+            line(Vec2::new(square, square), Vec2::new(0.0, square)),
+            line(Vec2::new(0.0, square), Vec2::new(0.0, 0.0)),
+        ],
+        ..Default::default()
+    };
+    let obstacle = BoundarySpec {
+        name: "sinai".to_string(),
+        segments: vec![ccw_arc(Vec2::new(c, c), r, 0.0, TAU)],
+        ..Default::default()
+    };
+
+    TableSpec {
+        outer,
+        obstacles: vec![obstacle],
+        ..Default::default()
+    }
+}
+
+/// Annulus: a disk of radius `outer_r` centered at the origin with a
+/// circular obstacle of radius `inner_r` centered at `(offset, 0)`.
+///
+/// With `offset = 0` the annulus is concentric and integrable; off center
+/// it is the eccentric annulus, whose phase space mixes regular whispering
+/// orbits with a chaotic sea around the obstacle (see
+/// [`annulus`](crate::dynamics::annulus)).
+///
+/// # Panics
+/// Panics if the radii are not strictly positive or if the obstacle does
+/// not fit strictly inside the disk (`|offset| + inner_r < outer_r`).
+pub fn annulus(outer_r: f64, inner_r: f64, offset: f64) -> TableSpec {
+    assert!(outer_r > 0.0, "Annulus outer radius must be positive.");
+    assert!(inner_r > 0.0, "Annulus inner radius must be positive.");
+    assert!(
+        offset.abs() + inner_r < outer_r,
+        "Annulus obstacle must fit strictly inside the outer disk."
+    );
+
+    TableSpec {
+        outer: BoundarySpec {
+            name: "outer".to_string(),
+            segments: vec![ccw_arc(Vec2::new(0.0, 0.0), outer_r, 0.0, TAU)],
+            ..Default::default()
+        },
+        obstacles: vec![BoundarySpec {
+            name: "inner".to_string(),
+            segments: vec![ccw_arc(Vec2::new(offset, 0.0), inner_r, 0.0, TAU)],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+/// Unit cell of the periodic Lorentz gas: a square of side `cell` with a
+/// circular scatterer of radius `r` at its center, whose opposite edges are
+/// identified.
+///
+/// The cell occupies `[0, cell] x [0, cell]`. Bottom and top edges are
+/// paired, as are right and left, so a particle leaving through one edge
+/// re-enters through the other; see
+/// [`lattice`](crate::dynamics::lattice) for following it through the
+/// infinite lattice. The horizon is never finite: corridors of width
+/// `cell - 2r` run between the rows and columns of scatterers, so a
+/// flight along them never ends. Once `r > cell / (2√2)` they are the only
+/// ones; the diagonal corridors are closed.
+///
+/// # Panics
+/// Panics if the parameters are not strictly positive or if the disk does
+/// not fit strictly inside the cell (`2 * r < cell`).
+pub fn lorentz_gas(cell: f64, r: f64) -> TableSpec {
+    assert!(cell > 0.0, "Lorentz gas cell side must be positive.");
+    assert!(r > 0.0, "Lorentz gas scatterer radius must be positive.");
+    assert!(
+        2.0 * r < cell,
+        "Lorentz gas scatterer must fit strictly inside the cell."
+    );
+
+    let corners = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(cell, 0.0),
+        Vec2::new(cell, cell),
+        Vec2::new(0.0, cell),
+    ];
+    let segments = (0..4)
+        .map(|i| SegmentSpec::Line {
+            start: corners[i],
+            end: corners[(i + 1) % 4],
+            condition: BoundaryCondition::PeriodicPairedWith((i + 2) % 4),
+            label: SegmentLabel::default(),
+        })
+        .collect();
+
+    let c = 0.5 * cell;
+    TableSpec {
+        outer: BoundarySpec {
+            name: "cell".to_string(),
+            segments,
+            ..Default::default()
+        },
+        obstacles: vec![BoundarySpec {
+            name: "scatterer".to_string(),
+            segments: vec![ccw_arc(Vec2::new(c, c), r, 0.0, TAU)],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+/// One period of an infinite channel along the x-axis, walled below by the
+/// polyline `bottom` and above by `top`, whose open ends are identified so
+/// that the particle leaving through one re-enters through the other.
+///
+/// Both walls are given left to right, from `x = 0` to the same `x = L`,
+/// the length of the period, and each must be as high at its right end as
+/// at its left so that the channel repeats. Segments `0..bottom.len() - 1`
+/// are the bottom wall, followed by the right end, the top wall from right
+/// to left, and the left end. Walls that cross each other between the ends
+/// are left for [`validate_table`](super::validation::validate_table) to
+/// report.
+///
+/// # Panics
+/// Panics if either wall has fewer than two vertices, does not run
+/// strictly left to right from `x = 0`, or does not end at the height it
+/// started; if the walls end at different `x`; or if the top wall is not
+/// above the bottom one at the ends.
+pub fn polygonal_channel(bottom: &[Vec2], top: &[Vec2]) -> TableSpec {
+    for wall in [bottom, top] {
+        assert!(wall.len() >= 2, "Channel walls need at least two vertices.");
+        assert!(wall[0].x == 0.0, "Channel walls must start at x = 0.");
+        assert!(
+            wall.windows(2).all(|w| w[0].x < w[1].x),
+            "Channel walls must run strictly left to right."
+        );
+        assert!(
+            wall[0].y == wall[wall.len() - 1].y,
+            "Channel walls must end at the height they start."
+        );
+    }
+    let (bottom_end, top_end) = (bottom[bottom.len() - 1], top[top.len() - 1]);
+    assert!(
+        bottom_end.x == top_end.x,
+        "Channel walls must end at the same x."
+    );
+    assert!(
+        bottom[0].y < top[0].y,
+        "The top wall must be above the bottom one."
+    );
+
+    let right_end = bottom.len() - 1;
+    let left_end = right_end + top.len();
+    let end = |start, end, paired| SegmentSpec::Line {
+        start,
+        end,
+        condition: BoundaryCondition::PeriodicPairedWith(paired),
+        label: SegmentLabel::default(),
+    };
+    let mut segments: Vec<_> = bottom.windows(2).map(|w| line(w[0], w[1])).collect();
+    segments.push(end(bottom_end, top_end, left_end));
+    segments.extend(top.windows(2).rev().map(|w| line(w[1], w[0])));
+    segments.push(end(top[0], bottom[0], right_end));
+
+    TableSpec {
+        outer: BoundarySpec {
+            name: "channel".to_string(),
+            segments,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Channel of width `width` between sawtooth walls, repeating every
+/// `teeth` teeth of length `tooth_length`; see [`polygonal_channel`].
+///
+/// Each tooth is a wedge of height `depth` rising from the wall into the
+/// channel, with its tip a fraction `apex` of the way along the tooth: 0.5
+/// gives symmetric teeth, and values near 0 or 1 steep ratchet-like ones.
+/// The top wall is the mirror image of the bottom one, so the narrowest
+/// gap, between opposite tips, is `width - 2 * depth`.
+///
+/// # Panics
+/// Panics if `teeth` is zero, if the lengths are not strictly positive, if
+/// `apex` is outside `(0, 1)`, or if opposite tips touch
+/// (`2 * depth >= width`).
+pub fn sawtooth_channel(
+    teeth: usize,
+    tooth_length: f64,
+    width: f64,
+    depth: f64,
+    apex: f64,
+) -> TableSpec {
+    assert!(teeth > 0, "A sawtooth channel needs at least one tooth.");
+    assert!(
+        tooth_length > 0.0,
+        "Sawtooth tooth length must be positive."
+    );
+    assert!(depth > 0.0, "Sawtooth tooth depth must be positive.");
+    assert!(
+        apex > 0.0 && apex < 1.0,
+        "Sawtooth apex must lie in (0, 1)."
+    );
+    assert!(
+        2.0 * depth < width,
+        "Opposite sawtooth tips must not touch."
+    );
+
+    let wall = |base: f64, tip: f64| -> Vec<Vec2> {
+        let mut vertices = vec![Vec2::new(0.0, base)];
+        for k in 0..teeth {
+            let x = k as f64 * tooth_length;
+            vertices.push(Vec2::new(x + apex * tooth_length, tip));
+            vertices.push(Vec2::new(x + tooth_length, base));
+        }
+        vertices
+    };
+    polygonal_channel(&wall(0.0, depth), &wall(width, width - depth))
+}
+
+/// Regular polygon with `n` sides inscribed in a circle of radius
+/// `circumradius` centered at the origin, with a horizontal bottom edge.
+///
+/// # Panics
+/// Panics if `n < 3` or `circumradius` is not strictly positive.
+pub fn regular_polygon(n: usize, circumradius: f64) -> TableSpec {
+    assert!(n >= 3, "A polygon needs at least 3 sides.");
+    assert!(circumradius > 0.0, "Polygon circumradius must be positive.");
+
+    let vertices: Vec<Vec2> = (0..n)
+        .map(|k| {
+            let phi = -FRAC_PI_2 - PI / n as f64 + TAU * k as f64 / n as f64;
+            Vec2::new(circumradius * phi.cos(), circumradius * phi.sin())
+        })
+        .collect();
+    TableSpec::from_polygon(&vertices).expect("regular polygons are simple")
+}
+
+/// Triangle with interior angles `alpha` at `(0, 0)` and `beta` at
+/// `(1, 0)`; the third angle is `π - alpha - beta`.
+///
+/// # Panics
+/// Panics unless both angles are positive and `alpha + beta < π`.
+pub fn triangle(alpha: f64, beta: f64) -> TableSpec {
+    assert!(
+        alpha > 0.0 && beta > 0.0 && alpha + beta < PI,
+        "Triangle angles must be positive and sum to less than π."
+    );
+
+    // Law of sines: the side from (0, 0) to the apex has length
+    // sin β / sin(α + β) when the base has length 1.
+    let side = beta.sin() / (alpha + beta).sin();
+    let apex = Vec2::new(side * alpha.cos(), side * alpha.sin());
+    TableSpec::from_polygon(&[Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), apex])
+        .expect("a triangle with valid angles is simple")
+}
+
+/// Elliptical table with semi-axes `a` (along x) and `b` (along y),
+/// centered at the origin.
+///
+/// The boundary is the inscribed polygon with [`ELLIPSE_SEGMENTS`]
+/// vertices, sampled uniformly in the eccentric anomaly.
+///
+/// # Panics
+/// Panics if `a` or `b` is not strictly positive.
+pub fn ellipse(a: f64, b: f64) -> TableSpec {
+    assert!(a > 0.0, "Ellipse semi-axis a must be positive.");
+    assert!(b > 0.0, "Ellipse semi-axis b must be positive.");
+
+    let vertex = |i: usize| {
+        let phi = TAU * (i % ELLIPSE_SEGMENTS) as f64 / ELLIPSE_SEGMENTS as f64;
+        Vec2::new(a * phi.cos(), b * phi.sin())
+    };
+    let segments = (0..ELLIPSE_SEGMENTS)
+        .map(|i| line(vertex(i), vertex(i + 1)))
+        .collect();
+
+    TableSpec {
+        outer: BoundarySpec {
+            name: "ellipse".to_string(),
+            segments,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        annulus, circle, ellipse, l_shape, lorentz_gas, mushroom, polygonal_channel, rectangle,
+        regular_polygon, sawtooth_channel, sinai, stadium, triangle,
+    };
+    use crate::geometry::boundary::BoundaryComponent;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::BoundaryCondition;
+    use crate::geometry::validation::validate_table;
+    use std::f64::consts::PI;
+
+    /// Every segment should end where the next one starts.
+    fn assert_closed(bc: &BoundaryComponent) {
+        let n = bc.segments.len();
+        for i in 0..n {
+            let seg = &bc.segments[i];
+            let next = &bc.segments[(i + 1) % n];
+            let end = seg.point_at(seg.length());
+            let start = next.point_at(0.0);
+            assert!(
+                (end - start).length() < 1e-12,
+                "Gap between segment {} and {}: {:?} vs {:?}",
+                i,
+                (i + 1) % n,
+                end,
+                start
+            );
+        }
+    }
+
+    #[test]
+    fn rectangle_circle_and_l_shape_have_expected_perimeters() {
+        let table = rectangle(2.0, 0.5).to_billiard_table();
+        assert_closed(&table.outer);
+        assert!((table.outer.length() - 5.0).abs() < 1e-12);
+
+        let table = circle(0.5).to_billiard_table();
+        assert_closed(&table.outer);
+        assert!((table.outer.length() - PI).abs() < 1e-12);
+
+        // Removing a corner rectangle leaves the perimeter unchanged.
+        let table = l_shape(2.0, 1.0, 0.5, 0.25).to_billiard_table();
+        assert_closed(&table.outer);
+        assert_eq!(table.outer.segments.len(), 6);
+        assert!((table.outer.length() - 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn l_shape_rejects_cut_as_wide_as_table() {
+        l_shape(1.0, 1.0, 1.0, 0.5);
+    }
+
+    #[test]
+    fn stadium_is_closed_with_expected_perimeter() {
+        let table = stadium(2.0, 0.5).to_billiard_table();
+        assert_closed(&table.outer);
+        assert!((table.outer.length() - (4.0 + PI)).abs() < 1e-12);
+        assert!(table.obstacles.is_empty());
+    }
+
+    #[test]
+    fn mushroom_is_closed_with_expected_perimeter() {
+        let (cap_r, stem_w, stem_h) = (1.0, 0.4, 0.8);
+        let table = mushroom(cap_r, stem_w, stem_h).to_billiard_table();
+        assert_closed(&table.outer);
+
+        // Cap arc + two cap undersides + stem (two sides + bottom).
+        let expected = PI * cap_r + (2.0 * cap_r - stem_w) + 2.0 * stem_h + stem_w;
+        assert!((table.outer.length() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sinai_has_square_outer_and_centered_disk() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        assert_closed(&table.outer);
+        assert!((table.outer.length() - 4.0).abs() < 1e-12);
+
+        assert_eq!(table.obstacles.len(), 1);
+        let disk = &table.obstacles[0];
+        assert!((disk.length() - 2.0 * PI * 0.2).abs() < 1e-12);
+
+        let (p, _) = disk.point_and_tangent_at(0.0);
+        assert!((p.x - 0.7).abs() < 1e-12);
+        assert!((p.y - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn annulus_has_an_offset_inner_disk() {
+        let table = annulus(1.0, 0.3, 0.4).to_billiard_table();
+        assert_closed(&table.outer);
+        assert!((table.outer.length() - 2.0 * PI).abs() < 1e-12);
+
+        assert_eq!(table.obstacles.len(), 1);
+        let disk = &table.obstacles[0];
+        assert_closed(disk);
+        assert!((disk.length() - 2.0 * PI * 0.3).abs() < 1e-12);
+        let (p, _) = disk.point_and_tangent_at(0.0);
+        assert!((p.x - 0.7).abs() < 1e-12 && p.y.abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn annulus_rejects_an_obstacle_touching_the_wall() {
+        annulus(1.0, 0.5, 0.5);
+    }
+
+    #[test]
+    fn lorentz_gas_pairs_opposite_edges() {
+        let table = lorentz_gas(2.0, 0.5).to_billiard_table();
+        assert_closed(&table.outer);
+        assert!((table.outer.length() - 8.0).abs() < 1e-12);
+
+        let paired: Vec<_> = table
+            .outer
+            .segments
+            .iter()
+            .map(|seg| seg.condition())
+            .collect();
+        assert_eq!(
+            paired,
+            [2, 3, 0, 1].map(BoundaryCondition::PeriodicPairedWith)
+        );
+        assert!((table.obstacles[0].length() - PI).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sawtooth_channel_pairs_its_open_ends() {
+        let spec = sawtooth_channel(3, 1.0, 1.0, 0.25, 0.5);
+        assert!(validate_table(&spec).is_valid());
+        let table = spec.to_billiard_table();
+        assert_closed(&table.outer);
+
+        // Six wedge faces per wall, each the hypotenuse of a 0.5 x 0.25
+        // right triangle, and two ends of height 1.
+        let segments = &table.outer.segments;
+        assert_eq!(segments.len(), 14);
+        let face = (0.5_f64.powi(2) + 0.25_f64.powi(2)).sqrt();
+        assert!((table.outer.length() - (12.0 * face + 2.0)).abs() < 1e-12);
+        assert_eq!(
+            segments[6].condition(),
+            BoundaryCondition::PeriodicPairedWith(13)
+        );
+        assert_eq!(
+            segments[13].condition(),
+            BoundaryCondition::PeriodicPairedWith(6)
+        );
+        assert!(
+            segments[..6]
+                .iter()
+                .all(|s| s.condition() == BoundaryCondition::Reflect)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn polygonal_channel_rejects_walls_that_do_not_repeat() {
+        polygonal_channel(
+            &[Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.2)],
+            &[Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0)],
+        );
+    }
+
+    #[test]
+    fn regular_polygon_has_expected_perimeter() {
+        let table = regular_polygon(6, 1.0).to_billiard_table();
+        assert_closed(&table.outer);
+        assert_eq!(table.outer.segments.len(), 6);
+        assert!((table.outer.length() - 6.0).abs() < 1e-12);
+
+        // Bottom edge is horizontal.
+        let (_, tangent) = table.outer.point_and_tangent_at(0.5);
+        assert!(tangent.y.abs() < 1e-12 && tangent.x > 0.0);
+    }
+
+    #[test]
+    fn triangle_has_requested_angles() {
+        let (alpha, beta) = (PI / 4.0, PI / 3.0);
+        let table = triangle(alpha, beta).to_billiard_table();
+        assert_closed(&table.outer);
+
+        let segs = &table.outer.segments;
+        let edge = |i: usize| segs[i].point_at(segs[i].length()) - segs[i].point_at(0.0);
+        let angle = |u: Vec2, v: Vec2| (-u.dot(v) / (u.length() * v.length())).acos();
+        assert!((angle(edge(2), edge(0)) - alpha).abs() < 1e-12);
+        assert!((angle(edge(0), edge(1)) - beta).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn triangle_rejects_angles_summing_past_pi() {
+        triangle(2.0, 1.5);
+    }
+
+    #[test]
+    fn ellipse_polygon_approximates_circle_perimeter() {
+        let table = ellipse(1.0, 1.0).to_billiard_table();
+        assert_closed(&table.outer);
+        assert!((table.outer.length() - 2.0 * PI).abs() < 1e-3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mushroom_rejects_stem_wider_than_cap() {
+        mushroom(1.0, 2.5, 1.0);
+    }
+}
@@ -0,0 +1,465 @@
+//! Assembling an outer boundary piece by piece.
+//!
+//! A [`TableBuilder`] follows a path around the table from a starting
+//! point, so each piece only needs to say where it goes next: a line to a
+//! point, an arc about a center, or segments imported from elsewhere. Every
+//! piece is named, and errors name the piece at fault rather than a segment
+//! index.
+//!
+//! Where an imported piece starts, or the path ends, within the snapping
+//! tolerance of where it should, the joint is closed exactly by moving the
+//! end of a line segment on one side of it. Arcs are never moved, so a gap
+//! between two arcs must already be within
+//! [`CLOSURE_TOLERANCE`](super::boundary::CLOSURE_TOLERANCE).
+
+use std::fmt;
+
+use super::boundary::{CLOSURE_TOLERANCE, GeometryError};
+use super::primitives::Vec2;
+use super::segments::{BoundaryCondition, SegmentLabel};
+use super::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+/// Largest gap [`TableBuilder`] closes by default.
+pub const DEFAULT_SNAP_TOLERANCE: f64 = 1e-6;
+
+/// Why a [`TableBuilder`] could not produce a table.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// No pieces were added.
+    Empty,
+
+    /// The snapping tolerance is negative or not finite.
+    InvalidTolerance(f64),
+
+    /// Piece `piece` starts `gap` away from where the path had reached.
+    Gap { piece: String, gap: f64 },
+
+    /// The path ends `gap` away from where it started.
+    NotClosed { gap: f64 },
+
+    /// A segment of piece `piece` is unusable; the index in `error` counts
+    /// segments of the whole boundary.
+    Geometry { piece: String, error: GeometryError },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Empty => write!(f, "table has no pieces"),
+            BuildError::InvalidTolerance(tolerance) => {
+                write!(
+                    f,
+                    "snapping tolerance {} is not a non-negative number",
+                    tolerance
+                )
+            }
+            BuildError::Gap { piece, gap } => write!(
+                f,
+                "piece {:?} starts {:.3e} away from the end of the previous piece",
+                piece, gap
+            ),
+            BuildError::NotClosed { gap } => {
+                write!(f, "boundary ends {:.3e} away from its start", gap)
+            }
+            BuildError::Geometry { piece, error } => write!(f, "piece {:?}: {}", piece, error),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::Geometry { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Start and end points of `segment`, without the checks of
+/// [`SegmentSpec::to_boundary_segment`].
+fn endpoints(segment: &SegmentSpec) -> (Vec2, Vec2) {
+    match *segment {
+        SegmentSpec::Line { start, end, .. } => (start, end),
+        SegmentSpec::CircularArc {
+            center,
+            radius,
+            start_angle,
+            end_angle,
+            ..
+        } => {
+            let at = |angle: f64| center + Vec2::new(angle.cos(), angle.sin()) * radius;
+            (at(start_angle), at(end_angle))
+        }
+    }
+}
+
+fn set_start(segment: &mut SegmentSpec, point: Vec2) {
+    if let SegmentSpec::Line { start, .. } = segment {
+        *start = point;
+    }
+}
+
+fn set_end(segment: &mut SegmentSpec, point: Vec2) {
+    if let SegmentSpec::Line { end, .. } = segment {
+        *end = point;
+    }
+}
+
+/// Builder of a [`TableSpec`] whose outer boundary is a path of named
+/// pieces; see the [module documentation](self).
+///
+/// Like every outer boundary, the path should run counterclockwise. The
+/// first error is kept and returned by [`TableBuilder::build`], so pieces
+/// can be chained without checking each one.
+#[derive(Clone, Debug)]
+pub struct TableBuilder {
+    name: String,
+    start: Vec2,
+    tolerance: f64,
+    segments: Vec<SegmentSpec>,
+    /// Names of the pieces, and the index of the first segment of each.
+    pieces: Vec<(String, usize)>,
+    obstacles: Vec<BoundarySpec>,
+    error: Option<BuildError>,
+}
+
+impl TableBuilder {
+    /// Start an outer boundary called `name` at `start`.
+    pub fn new(name: impl Into<String>, start: Vec2) -> Self {
+        TableBuilder {
+            name: name.into(),
+            start,
+            tolerance: DEFAULT_SNAP_TOLERANCE,
+            segments: Vec::new(),
+            pieces: Vec::new(),
+            obstacles: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Close gaps up to `tolerance`; defaults to [`DEFAULT_SNAP_TOLERANCE`].
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Where the path has reached: the end of the last piece.
+    pub fn position(&self) -> Vec2 {
+        self.segments
+            .last()
+            .map_or(self.start, |segment| endpoints(segment).1)
+    }
+
+    /// A line from the current position to `end`.
+    pub fn line_to(self, piece: &str, end: Vec2) -> Self {
+        let start = self.position();
+        self.push(
+            piece,
+            vec![SegmentSpec::Line {
+                start,
+                end,
+                condition: BoundaryCondition::Reflect,
+                label: SegmentLabel::default(),
+            }],
+        )
+    }
+
+    /// Lines from the current position through each of `points` in turn.
+    pub fn polyline(self, piece: &str, points: &[Vec2]) -> Self {
+        let mut from = self.position();
+        let segments = points
+            .iter()
+            .map(|&to| {
+                let line = SegmentSpec::Line {
+                    start: from,
+                    end: to,
+                    condition: BoundaryCondition::Reflect,
+                    label: SegmentLabel::default(),
+                };
+                from = to;
+                line
+            })
+            .collect();
+        self.push(piece, segments)
+    }
+
+    /// An arc about `center` from the current position, turning through
+    /// `sweep` radians: counterclockwise if positive.
+    pub fn arc(self, piece: &str, center: Vec2, sweep: f64) -> Self {
+        let offset = self.position() - center;
+        let start_angle = offset.y.atan2(offset.x);
+        self.push(
+            piece,
+            vec![SegmentSpec::CircularArc {
+                center,
+                radius: offset.length(),
+                start_angle,
+                end_angle: start_angle + sweep,
+                ccw: sweep > 0.0,
+                condition: BoundaryCondition::Reflect,
+                label: SegmentLabel::default(),
+            }],
+        )
+    }
+
+    /// Segments made elsewhere, such as part of another table, which should
+    /// start at the current position. Periodic pairings among them, counted
+    /// from 0 at the first of them, are renumbered to follow them.
+    pub fn piece(self, piece: &str, segments: &[SegmentSpec]) -> Self {
+        let offset = self.segments.len();
+        let segments = segments
+            .iter()
+            .cloned()
+            .map(|mut segment| {
+                if let SegmentSpec::Line { condition, .. }
+                | SegmentSpec::CircularArc { condition, .. } = &mut segment
+                    && let BoundaryCondition::PeriodicPairedWith(paired) = *condition
+                {
+                    *condition = BoundaryCondition::PeriodicPairedWith(paired + offset);
+                }
+                segment
+            })
+            .collect();
+        self.push(piece, segments)
+    }
+
+    /// A line from the current position back to the start.
+    pub fn close(self, piece: &str) -> Self {
+        let start = self.start;
+        self.line_to(piece, start)
+    }
+
+    /// Give every segment of the last piece `condition`.
+    pub fn condition(mut self, condition: BoundaryCondition) -> Self {
+        let first = self.pieces.last().map_or(0, |&(_, first)| first);
+        for segment in &mut self.segments[first..] {
+            match segment {
+                SegmentSpec::Line { condition: c, .. }
+                | SegmentSpec::CircularArc { condition: c, .. } => *c = condition,
+            }
+        }
+        self
+    }
+
+    /// Add an obstacle, as it is.
+    pub fn obstacle(mut self, obstacle: BoundarySpec) -> Self {
+        self.obstacles.push(obstacle);
+        self
+    }
+
+    /// Append `segments` as the piece `piece`, snapping its start to the
+    /// current position.
+    fn push(mut self, piece: &str, mut segments: Vec<SegmentSpec>) -> Self {
+        if self.error.is_some() || segments.is_empty() {
+            return self;
+        }
+        let position = self.position();
+        let start = endpoints(&segments[0]).0;
+        match self.snap(position, start, segments.first_mut()) {
+            Ok(()) => {
+                self.pieces.push((piece.to_string(), self.segments.len()));
+                self.segments.append(&mut segments);
+            }
+            Err(gap) => {
+                self.error = Some(BuildError::Gap {
+                    piece: piece.to_string(),
+                    gap,
+                });
+            }
+        }
+        self
+    }
+
+    /// Join the path, which has reached `position`, to `next` starting at
+    /// `start`: by moving the start of `next` if it is a line, or else the
+    /// end of the last segment (or the starting point) to `start`. Returns
+    /// the gap if it cannot be closed.
+    fn snap(
+        &mut self,
+        position: Vec2,
+        start: Vec2,
+        next: Option<&mut SegmentSpec>,
+    ) -> Result<(), f64> {
+        let gap = (start - position).length();
+        if gap <= CLOSURE_TOLERANCE {
+            return Ok(());
+        }
+        if gap > self.tolerance {
+            return Err(gap);
+        }
+        match (next, self.segments.last_mut()) {
+            (Some(next @ SegmentSpec::Line { .. }), _) => set_start(next, position),
+            (_, Some(last @ SegmentSpec::Line { .. })) => set_end(last, start),
+            (_, None) => self.start = start,
+            (_, Some(_)) => return Err(gap),
+        }
+        Ok(())
+    }
+
+    /// The piece that segment `index` belongs to.
+    fn piece_of(&self, index: usize) -> String {
+        let at = self.pieces.partition_point(|&(_, first)| first <= index);
+        self.pieces[at - 1].0.clone()
+    }
+
+    /// Close the path and return the table.
+    pub fn build(mut self) -> Result<TableSpec, BuildError> {
+        if !(self.tolerance.is_finite() && self.tolerance >= 0.0) {
+            return Err(BuildError::InvalidTolerance(self.tolerance));
+        }
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        if self.segments.is_empty() {
+            return Err(BuildError::Empty);
+        }
+
+        // Closing the path joins its end to the start of the first segment,
+        // which may be moved like the start of any other piece.
+        let position = self.position();
+        let mut first = self.segments[0].clone();
+        let start = endpoints(&first).0;
+        self.snap(position, start, Some(&mut first))
+            .map_err(|gap| BuildError::NotClosed { gap })?;
+        self.segments[0] = first;
+
+        let outer = BoundarySpec {
+            name: self.name.clone(),
+            segments: self.segments.clone(),
+            ..Default::default()
+        };
+        let geometry = |error: GeometryError| {
+            let index = match error {
+                GeometryError::NonFinite { index }
+                | GeometryError::DegenerateSegment { index }
+                | GeometryError::ZeroRadius { index }
+                | GeometryError::NotClosed { index, .. }
+                | GeometryError::InvalidPeriodicPair { index, .. }
+                | GeometryError::BallTooLarge { index } => index,
+                GeometryError::EmptyComponent => 0,
+            };
+            BuildError::Geometry {
+                piece: self.piece_of(index),
+                error,
+            }
+        };
+        outer
+            .try_to_boundary_component()
+            .and_then(|component| component.check_closed())
+            .map_err(geometry)?;
+
+        Ok(TableSpec {
+            outer,
+            obstacles: self.obstacles,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuildError, TableBuilder};
+    use crate::geometry::boundary::GeometryError;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundaryCondition, SegmentLabel};
+    use crate::geometry::standard_tables::{lorentz_gas, stadium};
+    use crate::geometry::table_spec::SegmentSpec;
+    use crate::geometry::validation::validate_table;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn stadium_from_pieces_matches_the_standard_one() {
+        let spec = TableBuilder::new("stadium", Vec2::new(-1.0, -0.5))
+            .line_to("bottom", Vec2::new(1.0, -0.5))
+            .arc("right cap", Vec2::new(1.0, 0.0), PI)
+            .line_to("top", Vec2::new(-1.0, 0.5))
+            .arc("left cap", Vec2::new(-1.0, 0.0), PI)
+            .build()
+            .unwrap();
+        assert!(validate_table(&spec).is_valid());
+
+        let built = spec.to_billiard_table().outer;
+        let standard = stadium(2.0, 0.5).to_billiard_table().outer;
+        assert_eq!(built.segments.len(), 4);
+        assert!((built.length() - standard.length()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn nearly_joined_pieces_are_snapped_together() {
+        // The rest of a Lorentz cell, shifted slightly off the end of the
+        // first piece, with its sides paired within the piece.
+        let cell = lorentz_gas(1.0, 0.2);
+        let mut imported = cell.outer.segments[1..].to_vec();
+        for (i, segment) in imported.iter_mut().enumerate() {
+            if let SegmentSpec::Line {
+                start,
+                end,
+                condition,
+                ..
+            } = segment
+            {
+                *start = *start + Vec2::new(3e-7, 0.0);
+                *end = *end + Vec2::new(3e-7, 0.0);
+                *condition = match i {
+                    1 => BoundaryCondition::Reflect,
+                    _ => BoundaryCondition::PeriodicPairedWith(2 - i),
+                };
+            }
+        }
+        let spec = TableBuilder::new("cell", Vec2::new(0.0, 0.0))
+            .line_to("bottom", Vec2::new(1.0, 0.0))
+            .piece("rest", &imported)
+            .build()
+            .unwrap();
+
+        let outer = spec.outer.to_boundary_component();
+        outer.check_closed().unwrap();
+        let point = |i: usize| outer.segments[i].point_at(0.0);
+        assert_eq!(point(1), Vec2::new(1.0, 0.0));
+        assert_eq!(point(0), Vec2::new(3e-7, 0.0));
+        assert_eq!(
+            outer.segments[1].condition(),
+            BoundaryCondition::PeriodicPairedWith(3)
+        );
+    }
+
+    #[test]
+    fn errors_name_the_piece_at_fault() {
+        let square = |top: Vec2| {
+            TableBuilder::new("square", Vec2::new(0.0, 0.0))
+                .line_to("bottom", Vec2::new(1.0, 0.0))
+                .line_to("right", Vec2::new(1.0, 1.0))
+                .piece(
+                    "top",
+                    &[SegmentSpec::Line {
+                        start: top,
+                        end: Vec2::new(0.0, 1.0),
+                        condition: BoundaryCondition::Reflect,
+                        label: SegmentLabel::default(),
+                    }],
+                )
+        };
+
+        match square(Vec2::new(1.0, 1.1)).close("left").build() {
+            Err(BuildError::Gap { piece, gap }) => {
+                assert_eq!(piece, "top");
+                assert!((gap - 0.1).abs() < 1e-12);
+            }
+            other => panic!("expected a gap, got {:?}", other),
+        }
+        assert_eq!(
+            square(Vec2::new(1.0, 1.0))
+                .line_to("nothing", Vec2::new(0.0, 1.0))
+                .close("left")
+                .build(),
+            Err(BuildError::Geometry {
+                piece: "nothing".to_string(),
+                error: GeometryError::DegenerateSegment { index: 3 },
+            })
+        );
+        match square(Vec2::new(1.0, 1.0)).build() {
+            Err(BuildError::NotClosed { gap }) => assert!((gap - 1.0).abs() < 1e-12),
+            other => panic!("expected an open boundary, got {:?}", other),
+        }
+    }
+}
@@ -1,17 +1,40 @@
+use std::fmt;
+use std::iter;
+
 use super::primitives::Vec2;
-use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
-use crate::geometry::segments::{BoundarySegment, CircularArcSegment, LineSegment};
+use crate::geometry::boundary::{BilliardTable, BoundaryComponent, GeometryError, Hole};
+use crate::geometry::offset::{offset_for_ball, try_offset_for_ball};
+use crate::geometry::segments::{
+    BoundaryCondition, BoundarySegment, CircularArcSegment, LineSegment, SegmentLabel,
+};
 use serde::{Deserialize, Serialize};
 
+/// Free-form metadata describing a table or a boundary, such as its
+/// `title`, `author`, `description`, `tags` or `units`.
+///
+/// It is kept through serialization but never read by the simulation.
+pub type Metadata = serde_json::Map<String, serde_json::Value>;
+
 /// Serializable description of a single boundary segment.
 ///
 /// This mirrors your internal `BoundarySegment` but is structured to be
-/// JSON-friendly for the frontend and database.
+/// JSON-friendly for the frontend and database. The optional `id` and
+/// `name` of its [`SegmentLabel`] sit next to the other fields and label
+/// the segment in simulation results.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum SegmentSpec {
     /// Straight line between two points.
-    Line { start: Vec2, end: Vec2 },
+    Line {
+        start: Vec2,
+        end: Vec2,
+        #[serde(default, skip_serializing_if = "BoundaryCondition::is_reflect")]
+        condition: BoundaryCondition,
+        /// Id and name, reported with every hit on the segment.
+        #[serde(flatten)]
+        label: SegmentLabel,
+    },
 
     /// Circular arc on a circle defined by center + radius.
     ///
@@ -23,27 +46,146 @@ pub enum SegmentSpec {
         start_angle: f64,
         end_angle: f64,
         ccw: bool,
+        #[serde(default, skip_serializing_if = "BoundaryCondition::is_reflect")]
+        condition: BoundaryCondition,
+        #[serde(flatten)]
+        label: SegmentLabel,
     },
 }
 
 /// Serializable description of a closed boundary component.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BoundarySpec {
     pub name: String,
     pub segments: Vec<SegmentSpec>,
+
+    /// Absorbing arc-length intervals on this boundary.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holes: Vec<Hole>,
+
+    /// Description of this boundary alone, e.g. what a scatterer stands for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub metadata: Option<Metadata>,
 }
 
 /// A serializable description of a billiard table.
 ///
 /// This is the shape you'll send from the frontend / store in the DB.
 /// It can be converted into a `BilliardTable` using a helper function.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Its JSON records the format version, and older versions are migrated
+/// when read; see [`table_version`](crate::geometry::table_version).
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct TableSpec {
     /// The outer boundary.
     pub outer: BoundarySpec,
 
     /// Internal obstacles.
     pub obstacles: Vec<BoundarySpec>,
+
+    /// Title, author and so on of the whole table.
+    pub metadata: Option<Metadata>,
+}
+
+/// A [`GeometryError`] in one component of a table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TableGeometryError {
+    /// 0 for the outer boundary, then the obstacles in order.
+    pub component_index: usize,
+
+    #[serde(flatten)]
+    pub error: GeometryError,
+}
+
+impl fmt::Display for TableGeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "component {}: {}", self.component_index, self.error)
+    }
+}
+
+impl std::error::Error for TableGeometryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl SegmentSpec {
+    /// Convert this spec into the corresponding internal `BoundarySegment`.
+    ///
+    /// # Panics
+    /// Panics on degenerate geometry (e.g. a non-positive arc radius).
+    pub fn to_boundary_segment(&self) -> BoundarySegment {
+        let segment = match self {
+            SegmentSpec::Line { start, end, .. } => {
+                BoundarySegment::Line(LineSegment::new(*start, *end))
+            }
+            SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+                ccw,
+                ..
+            } => BoundarySegment::CircularArc(CircularArcSegment::new(
+                *center,
+                *radius,
+                *start_angle,
+                *end_angle,
+                *ccw,
+            )),
+        };
+        segment
+            .with_condition(self.condition())
+            .with_label(self.label().clone())
+    }
+
+    /// Check the parameters that [`SegmentSpec::to_boundary_segment`] would
+    /// panic on or silently turn into NaN; `index` is used in the error.
+    fn check(&self, index: usize) -> Result<(), GeometryError> {
+        let finite = match *self {
+            SegmentSpec::Line { start, end, .. } => [start.x, start.y, end.x, end.y]
+                .iter()
+                .all(|v| v.is_finite()),
+            SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+                ..
+            } => {
+                let finite = [center.x, center.y, radius, start_angle, end_angle]
+                    .iter()
+                    .all(|v| v.is_finite());
+                if finite && radius <= 0.0 {
+                    return Err(GeometryError::ZeroRadius { index });
+                }
+                finite
+            }
+        };
+        if finite {
+            Ok(())
+        } else {
+            Err(GeometryError::NonFinite { index })
+        }
+    }
+
+    /// Boundary condition attached to this segment.
+    pub fn condition(&self) -> BoundaryCondition {
+        match self {
+            SegmentSpec::Line { condition, .. } | SegmentSpec::CircularArc { condition, .. } => {
+                *condition
+            }
+        }
+    }
+
+    /// Id and name given to this segment.
+    pub fn label(&self) -> &SegmentLabel {
+        match self {
+            SegmentSpec::Line { label, .. } | SegmentSpec::CircularArc { label, .. } => label,
+        }
+    }
 }
 
 impl BoundarySpec {
@@ -52,31 +194,30 @@ impl BoundarySpec {
     /// Each `SegmentSpec` variant is mapped to the corresponding `BoundarySegment`.
     ///
     /// # Panics
-    /// Panics if the segments do not form a closed loop or contain degenerate geometry.
+    /// Panics on degenerate geometry; closure is not checked. See
+    /// [`BoundarySpec::try_to_boundary_component`].
     pub fn to_boundary_component(&self) -> BoundaryComponent {
         let bdry_segments: Vec<BoundarySegment> = self
             .segments
             .iter()
-            .map(|seg| match seg {
-                SegmentSpec::Line { start, end } => {
-                    BoundarySegment::Line(LineSegment::new(*start, *end))
-                }
-                SegmentSpec::CircularArc {
-                    center,
-                    radius,
-                    start_angle,
-                    end_angle,
-                    ccw,
-                } => BoundarySegment::CircularArc(CircularArcSegment::new(
-                    *center,
-                    *radius,
-                    *start_angle,
-                    *end_angle,
-                    *ccw,
-                )),
-            })
+            .map(SegmentSpec::to_boundary_segment)
             .collect();
-        BoundaryComponent::new(self.name.clone(), bdry_segments)
+        BoundaryComponent::new(self.name.clone(), bdry_segments).with_holes(self.holes.clone())
+    }
+
+    /// Fallible version of [`BoundarySpec::to_boundary_component`], which
+    /// also rejects non-finite parameters. Closure is not checked.
+    pub fn try_to_boundary_component(&self) -> Result<BoundaryComponent, GeometryError> {
+        let bdry_segments = self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(index, seg)| seg.check(index).map(|()| seg.to_boundary_segment()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(
+            BoundaryComponent::try_new(self.name.clone(), bdry_segments)?
+                .with_holes(self.holes.clone()),
+        )
     }
 }
 
@@ -89,17 +230,72 @@ impl TableSpec {
             .iter()
             .map(|bdry| bdry.to_boundary_component())
             .collect();
-        BilliardTable {
-            outer: outer_bc,
-            obstacles: obstacles_bc,
-        }
+        BilliardTable::new(outer_bc, obstacles_bc)
+    }
+
+    /// Convert this `TableSpec` into a `BilliardTable`, or report the first
+    /// component that is degenerate or not closed.
+    ///
+    /// This catches everything that would make
+    /// [`TableSpec::to_billiard_table`] panic; for a full check
+    /// (orientation, intersections, containment) see
+    /// [`validate_table`](crate::geometry::validation::validate_table).
+    pub fn try_to_billiard_table(&self) -> Result<BilliardTable, TableGeometryError> {
+        self.try_build(true)
+    }
+
+    /// Like [`TableSpec::try_to_billiard_table`], but allowing gaps between
+    /// segments, for open tables the particle escapes from.
+    pub fn try_to_open_billiard_table(&self) -> Result<BilliardTable, TableGeometryError> {
+        self.try_build(false)
+    }
+
+    fn try_build(&self, closed: bool) -> Result<BilliardTable, TableGeometryError> {
+        let mut components = iter::once(&self.outer)
+            .chain(&self.obstacles)
+            .enumerate()
+            .map(|(component_index, bdry)| {
+                bdry.try_to_boundary_component()
+                    .and_then(|bc| {
+                        if closed {
+                            bc.check_closed()?;
+                        }
+                        Ok(bc)
+                    })
+                    .map_err(|error| TableGeometryError {
+                        component_index,
+                        error,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let obstacles_bc = components.split_off(1);
+        let outer_bc = components.pop().expect("the outer boundary comes first");
+        Ok(BilliardTable::new(outer_bc, obstacles_bc))
+    }
+
+    /// Build the table traced out by the center of a ball of radius
+    /// `ball_radius`; see [`offset_for_ball`](crate::geometry::offset::offset_for_ball).
+    pub fn to_billiard_table_for_ball(&self, ball_radius: f64) -> BilliardTable {
+        offset_for_ball(self, ball_radius).to_billiard_table()
+    }
+
+    /// Fallible version of [`TableSpec::to_billiard_table_for_ball`]; see
+    /// [`try_offset_for_ball`](crate::geometry::offset::try_offset_for_ball)
+    /// and [`TableSpec::try_to_billiard_table`].
+    pub fn try_to_billiard_table_for_ball(
+        &self,
+        ball_radius: f64,
+    ) -> Result<BilliardTable, TableGeometryError> {
+        try_offset_for_ball(self, ball_radius)?.try_to_billiard_table()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{BoundarySpec, SegmentSpec, TableSpec};
-    use crate::geometry::boundary::{BilliardTable, BoundaryComponent};
+    use super::{
+        BoundaryCondition, BoundarySpec, SegmentLabel, SegmentSpec, TableGeometryError, TableSpec,
+    };
+    use crate::geometry::boundary::{BilliardTable, BoundaryComponent, GeometryError};
     use crate::geometry::primitives::Vec2;
     use serde_json;
     use std::f64::consts::PI;
@@ -113,20 +309,29 @@ mod tests {
                 SegmentSpec::Line {
                     start: Vec2::new(0.0, 0.0),
                     end: Vec2::new(1.0, 0.0),
+                    condition: BoundaryCondition::Reflect,
+                    label: SegmentLabel::default(),
                 },
                 SegmentSpec::Line {
                     start: Vec2::new(1.0, 0.0),
                     end: Vec2::new(1.0, 1.0),
+                    condition: BoundaryCondition::Reflect,
+                    label: SegmentLabel::default(),
                 },
                 SegmentSpec::Line {
                     start: Vec2::new(1.0, 1.0),
                     end: Vec2::new(0.0, 1.0),
+                    condition: BoundaryCondition::Reflect,
+                    label: SegmentLabel::default(),
                 },
                 SegmentSpec::Line {
                     start: Vec2::new(0.0, 1.0),
                     end: Vec2::new(0.0, 0.0),
+                    condition: BoundaryCondition::Reflect,
+                    label: SegmentLabel::default(),
                 },
             ],
+            ..Default::default()
         }
     }
 
@@ -172,7 +377,10 @@ mod tests {
                 start_angle: 0.0,
                 end_angle: FRAC_PI_2,
                 ccw: true,
+                condition: BoundaryCondition::Reflect,
+                label: SegmentLabel::default(),
             }],
+            ..Default::default()
         };
 
         let bc: BoundaryComponent = spec.to_boundary_component();
@@ -207,7 +415,11 @@ mod tests {
         let outer = unit_square_boundary_spec("outer");
         let obstacles = Vec::<BoundarySpec>::new();
 
-        let spec = TableSpec { outer, obstacles };
+        let spec = TableSpec {
+            outer,
+            obstacles,
+            ..Default::default()
+        };
 
         let table: BilliardTable = spec.to_billiard_table();
         let bc: &BoundaryComponent = &table.outer;
@@ -236,12 +448,16 @@ mod tests {
                 start_angle: 0.0,
                 end_angle: 2.0 * PI,
                 ccw: true,
+                condition: BoundaryCondition::Reflect,
+                label: SegmentLabel::default(),
             }],
+            ..Default::default()
         };
 
         let spec = TableSpec {
             outer,
             obstacles: vec![obstacle],
+            ..Default::default()
         };
 
         let table: BilliardTable = spec.to_billiard_table();
@@ -266,13 +482,15 @@ mod tests {
         let line = SegmentSpec::Line {
             start: Vec2::new(0.0, 0.0),
             end: Vec2::new(1.0, 0.0),
+            condition: BoundaryCondition::Reflect,
+            label: SegmentLabel::default(),
         };
 
         let json_line = serde_json::to_string(&line).expect("serialize line");
         let line_back: SegmentSpec = serde_json::from_str(&json_line).expect("deserialize line");
 
         match line_back {
-            SegmentSpec::Line { start, end } => {
+            SegmentSpec::Line { start, end, .. } => {
                 assert!((start.x - 0.0).abs() < 1e-12);
                 assert!((start.y - 0.0).abs() < 1e-12);
                 assert!((end.x - 1.0).abs() < 1e-12);
@@ -288,6 +506,8 @@ mod tests {
             start_angle: 0.0,
             end_angle: PI,
             ccw: true,
+            condition: BoundaryCondition::Reflect,
+            label: SegmentLabel::default(),
         };
 
         let json_arc = serde_json::to_string(&arc).expect("serialize arc");
@@ -300,6 +520,7 @@ mod tests {
                 start_angle,
                 end_angle,
                 ccw,
+                ..
             } => {
                 assert!((center.x - 0.5).abs() < 1e-12);
                 assert!((center.y - 0.5).abs() < 1e-12);
@@ -312,6 +533,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn segment_ids_and_names_reach_the_boundary_segment() {
+        let json = r#"{"kind":"line","start":{"x":0,"y":0},"end":{"x":1,"y":0},"id":7,"name":"left cushion"}"#;
+        let spec: SegmentSpec = serde_json::from_str(json).unwrap();
+        let label = spec.to_boundary_segment().label().clone();
+        assert_eq!(label.id, Some(7));
+        assert_eq!(label.name.as_deref(), Some("left cushion"));
+        let back: SegmentSpec =
+            serde_json::from_str(&serde_json::to_string(&spec).unwrap()).unwrap();
+        assert_eq!(back, spec);
+
+        // Unlabelled segments say nothing about it.
+        let plain = unit_square_boundary_spec("outer").segments[0].clone();
+        let json = serde_json::to_string(&plain).unwrap();
+        assert!(!json.contains("id") && !json.contains("name"), "{}", json);
+        assert!(plain.to_boundary_segment().label().is_empty());
+    }
+
     #[test]
     fn boundary_spec_serde_roundtrip() {
         let spec = unit_square_boundary_spec("outer");
@@ -335,12 +574,16 @@ mod tests {
                 start_angle: 0.0,
                 end_angle: 2.0 * PI,
                 ccw: true,
+                condition: BoundaryCondition::Reflect,
+                label: SegmentLabel::default(),
             }],
+            ..Default::default()
         };
 
         let spec = TableSpec {
             outer,
             obstacles: vec![obstacle],
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&spec).expect("serialize table spec");
@@ -349,4 +592,76 @@ mod tests {
         assert_eq!(spec_back.obstacles.len(), 1);
         assert_eq!(spec_back.obstacles[0].name, "circle_obstacle");
     }
+
+    // --- Fallible conversion ---
+
+    #[test]
+    fn try_to_billiard_table_reports_the_bad_component_and_segment() {
+        let mut spec = TableSpec {
+            outer: unit_square_boundary_spec("outer"),
+            obstacles: vec![BoundarySpec {
+                name: "disk".to_string(),
+                segments: vec![SegmentSpec::CircularArc {
+                    center: Vec2::new(0.5, 0.5),
+                    radius: 0.0,
+                    start_angle: 0.0,
+                    end_angle: 2.0 * PI,
+                    ccw: true,
+                    condition: BoundaryCondition::Reflect,
+                    label: SegmentLabel::default(),
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let err = spec.try_to_billiard_table().err().expect("zero radius");
+        assert_eq!(
+            err,
+            TableGeometryError {
+                component_index: 1,
+                error: GeometryError::ZeroRadius { index: 0 },
+            }
+        );
+
+        spec.obstacles.clear();
+        if let SegmentSpec::Line { end, .. } = &mut spec.outer.segments[1] {
+            *end = Vec2::new(1.0, 0.0);
+        }
+        let err = spec.try_to_billiard_table().err().expect("degenerate side");
+        assert_eq!(err.error, GeometryError::DegenerateSegment { index: 1 });
+
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["component_index"], 0);
+        assert_eq!(json["kind"], "degenerate_segment");
+        assert_eq!(json["index"], 1);
+    }
+
+    #[test]
+    fn try_to_billiard_table_rejects_open_contours() {
+        let mut outer = unit_square_boundary_spec("outer");
+        outer.segments.pop();
+        let spec = TableSpec {
+            outer,
+            ..Default::default()
+        };
+
+        let err = spec.try_to_billiard_table().err().expect("open square");
+        match err.error {
+            GeometryError::NotClosed { index, gap } => {
+                assert_eq!(index, 2);
+                assert!((gap - 1.0).abs() < 1e-12);
+            }
+            other => panic!("expected NotClosed, got {:?}", other),
+        }
+        assert!(spec.try_to_open_billiard_table().is_ok());
+
+        assert!(
+            TableSpec {
+                outer: unit_square_boundary_spec("outer"),
+                ..Default::default()
+            }
+            .try_to_billiard_table()
+            .is_ok()
+        );
+    }
 }
@@ -0,0 +1,258 @@
+//! Families of tables: [`TableSpec`]s with named parameters in place of
+//! numbers.
+//!
+//! A template is the JSON of a table spec in which any number may be
+//! replaced by a parameter reference,
+//!
+//! ```json
+//! { "param": "l", "scale": -0.5, "offset": 0.0 }
+//! ```
+//!
+//! standing for `offset + scale · l`; `scale` defaults to 1 and `offset` to
+//! 0. A stadium with straight edges of length `l`, say, has its arc centers
+//! at `x = ±l/2`. [`TableTemplate::instantiate`] substitutes values for
+//! the parameters and parses the result as a [`TableSpec`].
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::table_spec::TableSpec;
+
+/// A [`TableSpec`] with parameter references; see the
+/// [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(value_type = Object))]
+pub struct TableTemplate(Value);
+
+/// Why a template could not be turned into a table spec.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateError {
+    /// A reference to a parameter that was given no value.
+    MissingParameter(String),
+
+    /// An object with a `param` key that is not a well-formed reference.
+    InvalidReference(String),
+
+    /// The template, with every reference substituted, is not a table spec.
+    InvalidSpec(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::MissingParameter(name) => {
+                write!(f, "no value given for parameter {:?}", name)
+            }
+            TemplateError::InvalidReference(reason) => {
+                write!(f, "invalid parameter reference: {}", reason)
+            }
+            TemplateError::InvalidSpec(reason) => {
+                write!(f, "template does not describe a table: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A parsed `{ "param", "scale", "offset" }` object.
+struct Reference<'a> {
+    name: &'a str,
+    scale: f64,
+    offset: f64,
+}
+
+impl<'a> Reference<'a> {
+    /// The reference `object` is, `None` if it is an ordinary object
+    /// (without a `param` key).
+    fn parse(object: &'a Map<String, Value>) -> Option<Result<Self, TemplateError>> {
+        let name = object.get("param")?;
+        let invalid = |reason: String| Some(Err(TemplateError::InvalidReference(reason)));
+        let Some(name) = name.as_str() else {
+            return invalid(format!("parameter name {} is not a string", name));
+        };
+        if let Some(key) = object
+            .keys()
+            .find(|key| !matches!(key.as_str(), "param" | "scale" | "offset"))
+        {
+            return invalid(format!("unexpected key {:?} next to {:?}", key, name));
+        }
+        let number = |key: &str, default: f64| match object.get(key) {
+            None => Ok(default),
+            Some(value) => value.as_f64().ok_or_else(|| {
+                TemplateError::InvalidReference(format!("{} of {:?} is not a number", key, name))
+            }),
+        };
+        Some(number("scale", 1.0).and_then(|scale| {
+            Ok(Reference {
+                name,
+                scale,
+                offset: number("offset", 0.0)?,
+            })
+        }))
+    }
+}
+
+impl TableTemplate {
+    /// A template from its JSON; it is only checked when instantiated.
+    pub fn new(value: Value) -> Self {
+        TableTemplate(value)
+    }
+
+    /// The template of `spec` alone, without parameters.
+    pub fn from_spec(spec: &TableSpec) -> Self {
+        TableTemplate(serde_json::to_value(spec).expect("table specs serialize to JSON"))
+    }
+
+    /// Names of the parameters the template refers to.
+    pub fn parameters(&self) -> Result<BTreeSet<String>, TemplateError> {
+        fn collect(value: &Value, names: &mut BTreeSet<String>) -> Result<(), TemplateError> {
+            match value {
+                Value::Object(object) => match Reference::parse(object) {
+                    Some(reference) => {
+                        names.insert(reference?.name.to_string());
+                    }
+                    None => object.values().try_for_each(|v| collect(v, names))?,
+                },
+                Value::Array(items) => items.iter().try_for_each(|v| collect(v, names))?,
+                _ => {}
+            }
+            Ok(())
+        }
+
+        let mut names = BTreeSet::new();
+        collect(&self.0, &mut names)?;
+        Ok(names)
+    }
+
+    /// The table spec with each reference replaced by its value under
+    /// `values`. Values for parameters the template does not use are
+    /// ignored.
+    pub fn instantiate(&self, values: &HashMap<String, f64>) -> Result<TableSpec, TemplateError> {
+        fn substitute(
+            value: &Value,
+            values: &HashMap<String, f64>,
+        ) -> Result<Value, TemplateError> {
+            match value {
+                Value::Object(object) => match Reference::parse(object) {
+                    Some(reference) => {
+                        let reference = reference?;
+                        let x = values.get(reference.name).ok_or_else(|| {
+                            TemplateError::MissingParameter(reference.name.to_string())
+                        })?;
+                        let result = reference.offset + reference.scale * x;
+                        // JSON has no NaN or infinity; the spec check
+                        // reports them once they are parsed back.
+                        Ok(serde_json::Number::from_f64(result).map_or(Value::Null, Value::Number))
+                    }
+                    None => object
+                        .iter()
+                        .map(|(k, v)| Ok((k.clone(), substitute(v, values)?)))
+                        .collect::<Result<Map<_, _>, _>>()
+                        .map(Value::Object),
+                },
+                Value::Array(items) => items
+                    .iter()
+                    .map(|v| substitute(v, values))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array),
+                other => Ok(other.clone()),
+            }
+        }
+
+        serde_json::from_value(substitute(&self.0, values)?)
+            .map_err(|e| TemplateError::InvalidSpec(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::{TableTemplate, TemplateError};
+    use crate::geometry::standard_tables::{circle, stadium};
+
+    /// The stadium of [`stadium`] with the straight length as parameter `l`.
+    fn stadium_template(r: f64) -> TableTemplate {
+        let half = |sign: f64| json!({ "param": "l", "scale": 0.5 * sign });
+        let point = |x: serde_json::Value, y: f64| json!({ "x": x, "y": y });
+        TableTemplate::new(json!({
+            "outer": {
+                "name": "stadium",
+                "segments": [
+                    { "kind": "line", "start": point(half(-1.0), -r), "end": point(half(1.0), -r) },
+                    {
+                        "kind": "circular_arc", "center": point(half(1.0), 0.0), "radius": r,
+                        "start_angle": -std::f64::consts::FRAC_PI_2,
+                        "end_angle": std::f64::consts::FRAC_PI_2, "ccw": true,
+                    },
+                    { "kind": "line", "start": point(half(1.0), r), "end": point(half(-1.0), r) },
+                    {
+                        "kind": "circular_arc", "center": point(half(-1.0), 0.0), "radius": r,
+                        "start_angle": std::f64::consts::FRAC_PI_2,
+                        "end_angle": 3.0 * std::f64::consts::FRAC_PI_2, "ccw": true,
+                    },
+                ],
+            },
+            "obstacles": [],
+        }))
+    }
+
+    #[test]
+    fn instances_match_the_preset() {
+        let template = stadium_template(0.5);
+        assert_eq!(
+            template
+                .parameters()
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["l"]
+        );
+        for l in [0.5, 1.0, 2.0] {
+            let spec = template
+                .instantiate(&HashMap::from([("l".to_string(), l)]))
+                .unwrap();
+            assert_eq!(spec, stadium(l, 0.5));
+        }
+    }
+
+    #[test]
+    fn specs_without_parameters_come_back_unchanged() {
+        let spec = circle(2.0);
+        let template = TableTemplate::from_spec(&spec);
+        assert!(template.parameters().unwrap().is_empty());
+        assert_eq!(template.instantiate(&HashMap::new()).unwrap(), spec);
+    }
+
+    #[test]
+    fn bad_references_and_missing_values_are_errors() {
+        let template = stadium_template(0.5);
+        assert_eq!(
+            template.instantiate(&HashMap::new()),
+            Err(TemplateError::MissingParameter("l".to_string()))
+        );
+
+        let mut value = serde_json::to_value(circle(1.0)).unwrap();
+        value["outer"]["segments"][0]["radius"] = json!({ "param": "r", "factor": 2 });
+        assert!(matches!(
+            TableTemplate::new(value.clone()).parameters(),
+            Err(TemplateError::InvalidReference(_))
+        ));
+
+        value["outer"]["segments"][0]["radius"] = json!({ "param": "r" });
+        value["outer"]["segments"][0]["ccw"] = json!({ "param": "r" });
+        let template = TableTemplate::new(value);
+        assert!(matches!(
+            template.instantiate(&HashMap::from([("r".to_string(), 1.0)])),
+            Err(TemplateError::InvalidSpec(_))
+        ));
+    }
+}
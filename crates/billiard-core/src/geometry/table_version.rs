@@ -0,0 +1,258 @@
+//! Versions of the JSON form of a [`TableSpec`], and migration between
+//! them.
+//!
+//! A serialized table carries the version of its format,
+//!
+//! ```json
+//! { "version": 1, "outer": { ... }, "obstacles": [ ... ] }
+//! ```
+//!
+//! and documents without one, written before versions were recorded, are
+//! version 1. Deserializing a `TableSpec` reads any version up to
+//! [`TABLE_SPEC_VERSION`] and brings it up to date, so stored tables and
+//! payloads from older frontends keep loading; it always writes the
+//! current version.
+//!
+//! Each version has its own top-level type, `TableSpecV1` and so on.
+//! Only that level is frozen: `TableSpecV1` reads its boundaries as the
+//! live [`BoundarySpec`], so boundaries and segments may only change in
+//! ways that still read every version 1 document, such as fields with
+//! serde defaults (the boundary condition of a segment, say). Any other
+//! change needs a new version: copies of the old boundary and segment
+//! types move here for `TableSpecV1` to keep reading, and the new version
+//! gets a type, a `From` conversion from the one before it, a case in
+//! [`migrate`] and a bump of [`TABLE_SPEC_VERSION`].
+
+use std::fmt;
+
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use super::table_spec::{BoundarySpec, Metadata, TableSpec};
+
+/// Version of the format [`TableSpec`]s are written in.
+pub const TABLE_SPEC_VERSION: u32 = 1;
+
+/// Why a document could not be read as a [`TableSpec`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MigrationError {
+    /// The `version` is not a positive integer.
+    InvalidVersion(String),
+
+    /// The document is from a version this build does not know, most
+    /// likely written by a newer one.
+    UnsupportedVersion { version: u64 },
+
+    /// The document does not match the format of its version; `reason`
+    /// starts with the path of the offending key, as in
+    /// `obstacles[0].segments[2]`.
+    InvalidDocument { version: u32, reason: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::InvalidVersion(version) => {
+                write!(f, "table version {} is not a positive integer", version)
+            }
+            MigrationError::UnsupportedVersion { version } => write!(
+                f,
+                "table version {} is not supported; the newest is {}",
+                version, TABLE_SPEC_VERSION
+            ),
+            MigrationError::InvalidDocument { version, reason } => {
+                write!(f, "invalid version {} table: {}", version, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// A table in format version 1, the first.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TableSpecV1 {
+    /// Format version, 1; may be left out.
+    #[serde(default = "version_1")]
+    pub version: u32,
+
+    /// The outer boundary.
+    pub outer: BoundarySpec,
+
+    /// Internal obstacles.
+    pub obstacles: Vec<BoundarySpec>,
+
+    /// Title, author and so on; see [`Metadata`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub metadata: Option<Metadata>,
+}
+
+fn version_1() -> u32 {
+    1
+}
+
+impl From<TableSpecV1> for TableSpec {
+    fn from(v1: TableSpecV1) -> Self {
+        TableSpec {
+            outer: v1.outer,
+            obstacles: v1.obstacles,
+            metadata: v1.metadata,
+        }
+    }
+}
+
+/// Read `document`, a table in any supported version, as a current
+/// [`TableSpec`].
+pub fn migrate(document: Value) -> Result<TableSpec, MigrationError> {
+    let version = match document.get("version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .filter(|&version| version > 0)
+            .ok_or_else(|| MigrationError::InvalidVersion(version.to_string()))?,
+    };
+    match version {
+        1 => parse::<TableSpecV1>(document, 1).map(TableSpec::from),
+        version => Err(MigrationError::UnsupportedVersion { version }),
+    }
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(document: Value, version: u32) -> Result<T, MigrationError> {
+    serde_path_to_error::deserialize(document).map_err(|e| MigrationError::InvalidDocument {
+        version,
+        reason: e.to_string(),
+    })
+}
+
+impl Serialize for TableSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("TableSpec", 4)?;
+        state.serialize_field("version", &TABLE_SPEC_VERSION)?;
+        state.serialize_field("outer", &self.outer)?;
+        state.serialize_field("obstacles", &self.obstacles)?;
+        match &self.metadata {
+            Some(metadata) => state.serialize_field("metadata", metadata)?,
+            None => state.skip_field("metadata")?,
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TableSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        migrate(Value::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The schema of the current version.
+#[cfg(feature = "openapi")]
+impl utoipa::PartialSchema for TableSpec {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        <TableSpecV1 as utoipa::PartialSchema>::schema()
+    }
+}
+
+#[cfg(feature = "openapi")]
+impl utoipa::ToSchema for TableSpec {
+    fn schemas(
+        schemas: &mut Vec<(
+            String,
+            utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>,
+        )>,
+    ) {
+        <TableSpecV1 as utoipa::ToSchema>::schemas(schemas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{MigrationError, TABLE_SPEC_VERSION, TableSpecV1, migrate};
+    use crate::geometry::standard_tables::sinai;
+    use crate::geometry::table_spec::TableSpec;
+
+    #[test]
+    fn tables_are_written_with_their_version_and_read_back() {
+        let table = sinai(1.0, 0.2);
+        let json = serde_json::to_value(&table).unwrap();
+        assert_eq!(json["version"], json!(TABLE_SPEC_VERSION));
+        assert_eq!(serde_json::from_value::<TableSpec>(json).unwrap(), table);
+    }
+
+    #[test]
+    fn metadata_is_kept_as_written() {
+        let metadata = json!({
+            "title": "Sinai billiard",
+            "author": "Ya. G. Sinai",
+            "tags": ["dispersing", "ergodic"],
+            "units": { "length": "m" },
+        });
+        let mut json = serde_json::to_value(sinai(1.0, 0.2)).unwrap();
+        assert!(json.get("metadata").is_none());
+        json["metadata"] = metadata.clone();
+        json["obstacles"][0]["metadata"] = json!({ "description": "the scatterer" });
+
+        let table: TableSpec = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(table.metadata.as_ref().unwrap()["tags"][1], "ergodic");
+        assert!(table.outer.metadata.is_none());
+        assert_eq!(serde_json::to_value(&table).unwrap(), json);
+    }
+
+    #[test]
+    fn unversioned_documents_are_version_1() {
+        let table = sinai(1.0, 0.2);
+        let mut json = serde_json::to_value(&table).unwrap();
+        json.as_object_mut().unwrap().remove("version");
+        assert_eq!(migrate(json.clone()).unwrap(), table);
+
+        let v1: TableSpecV1 = serde_json::from_value(json).unwrap();
+        assert_eq!(v1.version, 1);
+        assert_eq!(TableSpec::from(v1), table);
+    }
+
+    #[test]
+    fn unknown_versions_and_bad_documents_are_errors() {
+        let mut json = serde_json::to_value(sinai(1.0, 0.2)).unwrap();
+        json["version"] = json!(TABLE_SPEC_VERSION + 1);
+        assert_eq!(
+            migrate(json.clone()),
+            Err(MigrationError::UnsupportedVersion {
+                version: u64::from(TABLE_SPEC_VERSION) + 1
+            })
+        );
+        let message = serde_json::from_value::<TableSpec>(json.clone())
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("not supported"), "{}", message);
+
+        for version in [json!(0), json!("1"), json!(1.5)] {
+            json["version"] = version;
+            assert!(matches!(
+                migrate(json.clone()),
+                Err(MigrationError::InvalidVersion(_))
+            ));
+        }
+
+        json["version"] = json!(1);
+        json["obstacles"][0]["segments"][0]["radius"] = json!("wide");
+        let Err(MigrationError::InvalidDocument { version: 1, reason }) = migrate(json.clone())
+        else {
+            panic!("a bad radius was accepted");
+        };
+        assert!(
+            reason.starts_with("obstacles[0].segments[0]: "),
+            "{}",
+            reason
+        );
+
+        json.as_object_mut().unwrap().remove("outer");
+        assert!(matches!(
+            migrate(json),
+            Err(MigrationError::InvalidDocument { version: 1, .. })
+        ));
+    }
+}
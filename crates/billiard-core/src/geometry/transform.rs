@@ -0,0 +1,634 @@
+//! Moving, turning, resizing and mirroring tables and their pieces.
+//!
+//! Only similarity transforms are offered: the affine maps that take
+//! circles to circles, so a transformed arc is still a
+//! [`CircularArcSegment`]. Any similarity is a composition of the basic
+//! ones built by [`Similarity`]'s constructors (see [`Similarity::then`]).
+//!
+//! A mirror image reverses the direction in which a closed loop is
+//! traversed. Whole components are therefore traversed backwards after a
+//! mirroring [`Transform`], so that every component stays counterclockwise;
+//! holes and periodic pairings are moved along with their segments. A
+//! single [`SegmentSpec`] keeps its start and end.
+
+use std::f64::consts::TAU;
+use std::sync::Arc;
+
+use super::primitives::Vec2;
+use crate::geometry::boundary::{BilliardTable, BoundaryComponent, Hole};
+use crate::geometry::segments::{
+    BoundaryCondition, BoundarySegment, CircularArcSegment, ExternalSegment, LineSegment, Segment,
+    SegmentLabel,
+};
+use crate::geometry::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+/// The map `p ↦ translation + scale · R(angle) · M p`, where `M` reflects
+/// across the x-axis if `mirrored` and is the identity otherwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Similarity {
+    scale: f64,
+    angle: f64,
+    mirrored: bool,
+    translation: Vec2,
+}
+
+impl Default for Similarity {
+    fn default() -> Self {
+        Similarity::identity()
+    }
+}
+
+impl Similarity {
+    pub fn identity() -> Self {
+        Similarity {
+            scale: 1.0,
+            angle: 0.0,
+            mirrored: false,
+            translation: Vec2::new(0.0, 0.0),
+        }
+    }
+
+    pub fn translation(offset: Vec2) -> Self {
+        Similarity {
+            translation: offset,
+            ..Similarity::identity()
+        }
+    }
+
+    /// Counterclockwise rotation by `angle` radians about the origin.
+    pub fn rotation(angle: f64) -> Self {
+        Similarity {
+            angle,
+            ..Similarity::identity()
+        }
+    }
+
+    /// Counterclockwise rotation by `angle` radians about `center`.
+    pub fn rotation_about(center: Vec2, angle: f64) -> Self {
+        Similarity::translation(-center)
+            .then(&Similarity::rotation(angle))
+            .then(&Similarity::translation(center))
+    }
+
+    /// Uniform scaling by `factor` about the origin.
+    ///
+    /// # Panics
+    /// Panics if `factor` is not positive and finite.
+    pub fn scaling(factor: f64) -> Self {
+        assert!(
+            factor.is_finite() && factor > 0.0,
+            "Scale factor must be positive and finite."
+        );
+        Similarity {
+            scale: factor,
+            ..Similarity::identity()
+        }
+    }
+
+    /// Reflection across the line through `point` along `direction`.
+    ///
+    /// # Panics
+    /// Panics if `direction` is (nearly) zero.
+    pub fn mirror(point: Vec2, direction: Vec2) -> Self {
+        let direction = direction
+            .try_normalized()
+            .expect("Mirror axis direction must be nonzero.");
+        // Reflecting across a line at angle φ through the origin is
+        // reflecting across the x-axis, then rotating by 2φ.
+        let across = Similarity {
+            angle: 2.0 * direction.y.atan2(direction.x),
+            mirrored: true,
+            ..Similarity::identity()
+        };
+        Similarity::translation(-point)
+            .then(&across)
+            .then(&Similarity::translation(point))
+    }
+
+    /// The map applying `self` first, then `next`.
+    pub fn then(&self, next: &Similarity) -> Similarity {
+        Similarity {
+            scale: self.scale * next.scale,
+            angle: next.angle
+                + if next.mirrored {
+                    -self.angle
+                } else {
+                    self.angle
+                },
+            mirrored: self.mirrored != next.mirrored,
+            translation: next.apply(self.translation),
+        }
+    }
+
+    /// The map undoing `self`.
+    pub fn inverse(&self) -> Similarity {
+        let linear = Similarity {
+            scale: 1.0 / self.scale,
+            angle: if self.mirrored {
+                self.angle
+            } else {
+                -self.angle
+            },
+            mirrored: self.mirrored,
+            translation: Vec2::new(0.0, 0.0),
+        };
+        Similarity {
+            translation: linear.apply_vector(-self.translation),
+            ..linear
+        }
+    }
+
+    /// Image of the point `p`.
+    pub fn apply(&self, p: Vec2) -> Vec2 {
+        self.translation + self.apply_vector(p)
+    }
+
+    /// Image of the displacement `v`: the map without its translation.
+    pub fn apply_vector(&self, v: Vec2) -> Vec2 {
+        let v = if self.mirrored {
+            Vec2::new(v.x, -v.y)
+        } else {
+            v
+        };
+        let (sin, cos) = self.angle.sin_cos();
+        Vec2::new(cos * v.x - sin * v.y, sin * v.x + cos * v.y) * self.scale
+    }
+
+    /// Image of the polar angle `theta` of a direction.
+    pub fn apply_angle(&self, theta: f64) -> f64 {
+        self.angle + if self.mirrored { -theta } else { theta }
+    }
+
+    /// Factor by which lengths are multiplied.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale
+    }
+
+    /// Whether the map reverses orientation.
+    pub fn is_mirror(&self) -> bool {
+        self.mirrored
+    }
+}
+
+/// Geometry that can be moved by a [`Similarity`].
+///
+/// Only [`Transform::transformed`] needs implementing; the rest are
+/// shorthands for the basic maps.
+pub trait Transform: Sized {
+    /// Image of `self` under `map`.
+    fn transformed(&self, map: &Similarity) -> Self;
+
+    fn translated(&self, offset: Vec2) -> Self {
+        self.transformed(&Similarity::translation(offset))
+    }
+
+    /// Rotated counterclockwise by `angle` radians about `center`.
+    fn rotated(&self, center: Vec2, angle: f64) -> Self {
+        self.transformed(&Similarity::rotation_about(center, angle))
+    }
+
+    /// Scaled by `factor` about `center`.
+    ///
+    /// # Panics
+    /// Panics if `factor` is not positive and finite.
+    fn scaled(&self, center: Vec2, factor: f64) -> Self {
+        let map = Similarity::translation(-center)
+            .then(&Similarity::scaling(factor))
+            .then(&Similarity::translation(center));
+        self.transformed(&map)
+    }
+
+    /// Mirrored across the line through `point` along `direction`.
+    ///
+    /// # Panics
+    /// Panics if `direction` is (nearly) zero.
+    fn mirrored(&self, point: Vec2, direction: Vec2) -> Self {
+        self.transformed(&Similarity::mirror(point, direction))
+    }
+}
+
+/// Arc angles under `map`, shifted together so the start lies in `[0, 2π)`.
+fn arc_angles(map: &Similarity, start_angle: f64, end_angle: f64) -> (f64, f64) {
+    let start = map.apply_angle(start_angle);
+    let end = map.apply_angle(end_angle);
+    let shift = start.rem_euclid(TAU) - start;
+    (start + shift, end + shift)
+}
+
+impl Transform for SegmentSpec {
+    fn transformed(&self, map: &Similarity) -> Self {
+        match *self {
+            SegmentSpec::Line {
+                start,
+                end,
+                condition,
+                ref label,
+            } => SegmentSpec::Line {
+                start: map.apply(start),
+                end: map.apply(end),
+                condition,
+                label: label.clone(),
+            },
+            SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+                ccw,
+                condition,
+                ref label,
+            } => {
+                let (start_angle, end_angle) = arc_angles(map, start_angle, end_angle);
+                SegmentSpec::CircularArc {
+                    center: map.apply(center),
+                    radius: radius * map.scale,
+                    start_angle,
+                    end_angle,
+                    ccw: ccw != map.mirrored,
+                    condition,
+                    label: label.clone(),
+                }
+            }
+        }
+    }
+}
+
+/// Condition of a segment moved from index `i` to `n - 1 - i`, with its
+/// periodic partner moved likewise.
+fn reversed_condition(condition: BoundaryCondition, n: usize) -> BoundaryCondition {
+    match condition {
+        BoundaryCondition::PeriodicPairedWith(j) => {
+            BoundaryCondition::PeriodicPairedWith(n - 1 - j)
+        }
+        other => other,
+    }
+}
+
+/// Segment `segment` of a component of `n` segments, traversed from its
+/// end to its start as the component is traversed backwards.
+fn reversed_spec(segment: &SegmentSpec, n: usize) -> SegmentSpec {
+    match *segment {
+        SegmentSpec::Line {
+            start,
+            end,
+            condition,
+            ref label,
+        } => SegmentSpec::Line {
+            start: end,
+            end: start,
+            condition: reversed_condition(condition, n),
+            label: label.clone(),
+        },
+        SegmentSpec::CircularArc {
+            center,
+            radius,
+            start_angle,
+            end_angle,
+            ccw,
+            condition,
+            ref label,
+        } => SegmentSpec::CircularArc {
+            center,
+            radius,
+            start_angle: end_angle,
+            end_angle: start_angle,
+            ccw: !ccw,
+            condition: reversed_condition(condition, n),
+            label: label.clone(),
+        },
+    }
+}
+
+/// Holes of a component scaled by `scale`; if `reversed_length` is given,
+/// also measured backwards from the end of a component of that (scaled)
+/// length.
+fn transformed_holes(holes: &[Hole], scale: f64, reversed_length: Option<f64>) -> Vec<Hole> {
+    holes
+        .iter()
+        .map(|hole| {
+            let (start, end) = (hole.start * scale, hole.end * scale);
+            match reversed_length {
+                Some(total) => Hole {
+                    start: total - end,
+                    end: total - start,
+                },
+                None => Hole { start, end },
+            }
+        })
+        .collect()
+}
+
+impl Transform for BoundarySpec {
+    fn transformed(&self, map: &Similarity) -> Self {
+        let segments: Vec<SegmentSpec> = self.segments.iter().map(|s| s.transformed(map)).collect();
+        if !map.mirrored {
+            return BoundarySpec {
+                name: self.name.clone(),
+                segments,
+                holes: transformed_holes(&self.holes, map.scale, None),
+                metadata: self.metadata.clone(),
+            };
+        }
+
+        let n = segments.len();
+        let length: f64 = segments
+            .iter()
+            .map(|s| s.to_boundary_segment().length())
+            .sum();
+        BoundarySpec {
+            name: self.name.clone(),
+            segments: segments.iter().rev().map(|s| reversed_spec(s, n)).collect(),
+            holes: transformed_holes(&self.holes, map.scale, Some(length)),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+impl Transform for TableSpec {
+    fn transformed(&self, map: &Similarity) -> Self {
+        TableSpec {
+            outer: self.outer.transformed(map),
+            obstacles: self.obstacles.iter().map(|b| b.transformed(map)).collect(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+/// An external curve seen through a similarity, and possibly traversed
+/// backwards.
+#[derive(Debug)]
+struct TransformedCurve {
+    curve: Arc<dyn Segment>,
+    map: Similarity,
+    inverse: Similarity,
+    reversed: bool,
+}
+
+impl TransformedCurve {
+    /// Parameter on the original curve of the point at `t` on this one.
+    fn inner_t(&self, t: f64) -> f64 {
+        let t = if self.reversed { self.length() - t } else { t };
+        t / self.map.scale
+    }
+
+    /// Parameter on this curve of the point at `inner_t` on the original.
+    fn outer_t(&self, inner_t: f64) -> f64 {
+        let t = inner_t * self.map.scale;
+        if self.reversed { self.length() - t } else { t }
+    }
+}
+
+impl Segment for TransformedCurve {
+    fn length(&self) -> f64 {
+        self.curve.length() * self.map.scale
+    }
+
+    fn point_at(&self, t: f64) -> Vec2 {
+        self.map.apply(self.curve.point_at(self.inner_t(t)))
+    }
+
+    fn tangent_at(&self, t: f64) -> Vec2 {
+        let tangent = self
+            .map
+            .apply_vector(self.curve.tangent_at(self.inner_t(t)))
+            / self.map.scale;
+        if self.reversed { -tangent } else { tangent }
+    }
+
+    fn curvature_at(&self, t: f64) -> f64 {
+        let curvature = self.curve.curvature_at(self.inner_t(t)) / self.map.scale;
+        if self.map.mirrored != self.reversed {
+            -curvature
+        } else {
+            curvature
+        }
+    }
+
+    fn ray_intersect(&self, origin: Vec2, direction: Vec2, epsilon: f64) -> Option<(f64, f64)> {
+        let scale = self.map.scale;
+        let inner_origin = self.inverse.apply(origin);
+        let inner_direction = self.inverse.apply_vector(direction) * scale;
+        self.curve
+            .ray_intersect(inner_origin, inner_direction, epsilon / scale)
+            .map(|(distance, t)| (distance * scale, self.outer_t(t)))
+    }
+
+    fn closest_point(&self, p: Vec2) -> (f64, f64) {
+        let (t, distance) = self.curve.closest_point(self.inverse.apply(p));
+        (self.outer_t(t), distance * self.map.scale)
+    }
+}
+
+/// Image of `segment` under `map`, traversed backwards if `reversed`.
+fn transformed_segment(
+    segment: &BoundarySegment,
+    map: &Similarity,
+    reversed: bool,
+) -> BoundarySegment {
+    let transformed = match segment {
+        BoundarySegment::Line(line) => {
+            let (start, end) = (map.apply(line.start), map.apply(line.end));
+            let (start, end) = if reversed { (end, start) } else { (start, end) };
+            BoundarySegment::Line(LineSegment::new(start, end))
+        }
+        BoundarySegment::CircularArc(arc) => {
+            let (start, end) = arc_angles(map, arc.start_angle, arc.end_angle);
+            let ccw = arc.ccw != map.mirrored;
+            let (start, end, ccw) = if reversed {
+                (end, start, !ccw)
+            } else {
+                (start, end, ccw)
+            };
+            BoundarySegment::CircularArc(CircularArcSegment::new(
+                map.apply(arc.center),
+                arc.radius * map.scale,
+                start,
+                end,
+                ccw,
+            ))
+        }
+        BoundarySegment::External(external) => BoundarySegment::External(ExternalSegment {
+            curve: Arc::new(TransformedCurve {
+                curve: Arc::clone(&external.curve),
+                map: *map,
+                inverse: map.inverse(),
+                reversed,
+            }),
+            condition: external.condition,
+            label: SegmentLabel::default(),
+        }),
+    };
+    transformed
+        .with_condition(segment.condition())
+        .with_label(segment.label().clone())
+}
+
+fn transformed_component(component: &BoundaryComponent, map: &Similarity) -> BoundaryComponent {
+    let n = component.segments.len();
+    let segments = if map.mirrored {
+        component
+            .segments
+            .iter()
+            .rev()
+            .map(|s| {
+                transformed_segment(s, map, true)
+                    .with_condition(reversed_condition(s.condition(), n))
+            })
+            .collect()
+    } else {
+        component
+            .segments
+            .iter()
+            .map(|s| transformed_segment(s, map, false))
+            .collect()
+    };
+    let reversed_length = map.mirrored.then(|| component.length() * map.scale);
+
+    BoundaryComponent::new(component.name.clone(), segments)
+        .with_holes(transformed_holes(
+            &component.holes,
+            map.scale,
+            reversed_length,
+        ))
+        .with_role(component.role)
+}
+
+impl Transform for BilliardTable {
+    fn transformed(&self, map: &Similarity) -> Self {
+        BilliardTable::new(
+            transformed_component(&self.outer, map),
+            self.obstacles
+                .iter()
+                .map(|c| transformed_component(c, map))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Similarity, Transform};
+    use crate::dynamics::simulation::{BilliardMap, StepOptions};
+    use crate::dynamics::state::BoundaryState;
+    use crate::geometry::boundary::Hole;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::BoundaryCondition;
+    use crate::geometry::standard_tables::{mushroom, rectangle, sinai};
+    use crate::geometry::table_spec::{SegmentSpec, TableSpec};
+    use crate::geometry::validation::validate_table;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        (a - b).length() < 1e-12
+    }
+
+    #[test]
+    fn composition_and_inverse() {
+        let maps = [
+            Similarity::rotation_about(Vec2::new(1.0, 2.0), 0.7),
+            Similarity::scaling(2.5),
+            Similarity::mirror(Vec2::new(0.5, -1.0), Vec2::new(1.0, 3.0)),
+            Similarity::translation(Vec2::new(-3.0, 0.25)),
+        ];
+        let p = Vec2::new(0.3, -0.8);
+
+        let composed = maps
+            .iter()
+            .fold(Similarity::identity(), |acc, m| acc.then(m));
+        let stepwise = maps.iter().fold(p, |q, m| m.apply(q));
+        assert!(close(composed.apply(p), stepwise));
+        assert!(close(composed.inverse().apply(composed.apply(p)), p));
+        assert!(composed.is_mirror());
+        assert!((composed.scale_factor() - 2.5).abs() < 1e-12);
+
+        let mirror = Similarity::mirror(Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0));
+        assert!(close(
+            mirror.apply(Vec2::new(1.0, 0.0)),
+            Vec2::new(-1.0, 2.0)
+        ));
+        assert!(close(
+            mirror.apply(Vec2::new(2.0, 3.0)),
+            Vec2::new(2.0, 3.0)
+        ));
+    }
+
+    #[test]
+    fn transformed_specs_stay_valid_and_counterclockwise() {
+        for spec in [sinai(1.0, 0.2), mushroom(1.0, 0.5, 1.0)] {
+            let moved = spec
+                .rotated(Vec2::new(0.3, 0.1), 1.1)
+                .scaled(Vec2::new(0.0, 0.0), 3.0)
+                .translated(Vec2::new(-2.0, 5.0));
+            let mirrored = moved.mirrored(Vec2::new(1.0, 1.0), Vec2::new(0.2, 1.0));
+            for table in [&moved, &mirrored] {
+                let report = validate_table(table);
+                assert!(report.is_valid(), "{:?}", report.issues);
+            }
+
+            let length = |t: &TableSpec| t.to_billiard_table().outer.length();
+            assert!((length(&mirrored) - 3.0 * length(&spec)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn mirroring_moves_holes_and_periodic_pairs() {
+        let mut spec = rectangle(2.0, 1.0);
+        if let SegmentSpec::Line { condition, .. } = &mut spec.outer.segments[0] {
+            *condition = BoundaryCondition::PeriodicPairedWith(2);
+        }
+        spec.outer.holes = vec![Hole {
+            start: 2.5,
+            end: 2.75,
+        }];
+
+        let mirrored = spec.mirrored(Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0));
+        let segments = &mirrored.outer.segments;
+        assert_eq!(
+            segments[3].condition(),
+            BoundaryCondition::PeriodicPairedWith(1)
+        );
+        assert_eq!(
+            mirrored.outer.holes,
+            vec![Hole {
+                start: 3.25,
+                end: 3.5,
+            }]
+        );
+
+        // The hole covers the same stretch of wall, mirrored.
+        let original = spec.to_billiard_table();
+        let table = mirrored.to_billiard_table();
+        let (p, _) = original.outer.point_and_tangent_at(2.6);
+        let (q, _) = table.outer.point_and_tangent_at(6.0 - 2.6);
+        assert!(close(q, Vec2::new(2.0 - p.x, p.y)));
+    }
+
+    #[test]
+    fn trajectories_follow_the_table() {
+        let table = sinai(1.0, 0.2).to_billiard_table();
+        let map = Similarity::rotation(FRAC_PI_2)
+            .then(&Similarity::scaling(2.0))
+            .then(&Similarity::mirror(
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 0.0),
+            ));
+        let image = table.transformed(&map);
+        let options = StepOptions::default();
+
+        let initial = BoundaryState::new(0, 0.3, 1.0);
+        let length = table.outer.length();
+        let mirrored_initial = BoundaryState::new(0, 2.0 * (length - 0.3), PI - 1.0);
+        let a = BilliardMap::new(&table, 1e-8, options)
+            .simulate(&initial, 20)
+            .unwrap();
+        let b = BilliardMap::new(&image, 1e-8, options)
+            .simulate(&mirrored_initial, 20)
+            .unwrap();
+
+        for (p, q) in a.collisions.iter().zip(&b.collisions) {
+            assert_eq!(p.component_index, q.component_index);
+            assert!((map.apply(p.hit_point) - q.hit_point).length() < 1e-9);
+            assert!((p.theta - (PI - q.theta)).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,965 @@
+//! Geometric checks on a [`TableSpec`] before it is simulated.
+//!
+//! [`TableSpec::to_billiard_table`] trusts its input: it panics on
+//! degenerate segments and quietly accepts open, clockwise or
+//! self-intersecting boundaries, which then produce meaningless
+//! trajectories. [`validate_table`] looks for all of these without building
+//! the table and reports every problem with the segments involved.
+
+use std::collections::HashMap;
+use std::iter;
+
+use serde::{Deserialize, Serialize};
+
+use super::boundary::{CLOSURE_TOLERANCE, MIN_SEGMENT_LENGTH, encloses, signed_area};
+use super::primitives::Vec2;
+use super::segments::{BoundaryCondition, BoundarySegment};
+use super::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+/// Distance within which a point counts as lying on a segment.
+const ON_SEGMENT_TOLERANCE: f64 = 1e-9;
+
+/// Crossings this close to the joint of two consecutive segments are the
+/// joint itself. Tangential joints (a line running into an arc) put
+/// spurious roots up to about `√ε` away from it.
+const JOINT_TOLERANCE: f64 = 1e-6;
+
+/// How serious a [`ValidationIssue`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum Severity {
+    /// The table cannot be simulated, or trajectories on it are meaningless.
+    Error,
+
+    /// The table can be simulated but is probably not what was intended.
+    Warning,
+}
+
+/// What is wrong with a table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum IssueKind {
+    /// A component has no segments.
+    EmptyComponent,
+
+    /// A segment has a NaN or infinite coordinate, radius or angle.
+    NonFinite,
+
+    /// A segment is no longer than [`MIN_SEGMENT_LENGTH`].
+    DegenerateSegment,
+
+    /// An arc has a radius that is not positive.
+    NonPositiveRadius,
+
+    /// A segment does not end where the next one starts.
+    NotClosed,
+
+    /// A component runs clockwise; every component must run
+    /// counterclockwise for the normals to point into the table.
+    Clockwise,
+
+    /// Two segments of the same component cross, touch or overlap.
+    SelfIntersection,
+
+    /// Segments of two different components cross, touch or overlap.
+    ComponentsIntersect,
+
+    /// An obstacle lies outside the outer boundary.
+    ObstacleOutside,
+
+    /// An obstacle lies inside another obstacle.
+    ObstacleInsideObstacle,
+
+    /// A periodic segment is paired with a segment that does not exist.
+    InvalidPeriodicPair,
+
+    /// Paired periodic segments do not point back at each other or differ
+    /// in length.
+    MismatchedPeriodicPair,
+
+    /// A hole has a non-finite end or reaches past the component.
+    InvalidHole,
+
+    /// A heated wall has a temperature that is not positive and finite.
+    InvalidTemperature,
+
+    /// A moving wall has a non-finite velocity, frequency or phase.
+    InvalidWallMotion,
+
+    /// Two segments of the table share an id, so results naming it are
+    /// ambiguous.
+    DuplicateSegmentId,
+}
+
+/// A segment of a table: component 0 is the outer boundary, then the
+/// obstacles in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SegmentLocation {
+    pub component_index: usize,
+    pub segment_index: usize,
+}
+
+/// One problem found by [`validate_table`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+
+    /// Component the issue belongs to.
+    pub component_index: usize,
+
+    /// Segments involved, possibly in other components; empty for issues
+    /// about a component as a whole.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<SegmentLocation>,
+
+    /// Human-readable description.
+    pub message: String,
+}
+
+/// Everything [`validate_table`] found, errors and warnings mixed, in the
+/// order the checks ran.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether the table has no errors (it may still have warnings).
+    pub fn is_valid(&self) -> bool {
+        self.issues
+            .iter()
+            .all(|issue| issue.severity != Severity::Error)
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        kind: IssueKind,
+        component_index: usize,
+        segments: &[usize],
+        message: String,
+    ) {
+        self.push_across(
+            severity,
+            kind,
+            component_index,
+            segments
+                .iter()
+                .map(|&segment_index| SegmentLocation {
+                    component_index,
+                    segment_index,
+                })
+                .collect(),
+            message,
+        );
+    }
+
+    fn push_across(
+        &mut self,
+        severity: Severity,
+        kind: IssueKind,
+        component_index: usize,
+        segments: Vec<SegmentLocation>,
+        message: String,
+    ) {
+        self.issues.push(ValidationIssue {
+            severity,
+            kind,
+            component_index,
+            segments,
+            message,
+        });
+    }
+}
+
+/// Check `spec` for everything that would make it unusable as a table.
+///
+/// Checks that need a well-formed component (closure, orientation,
+/// intersections, containment) are skipped for components that already
+/// failed a more basic one, so each problem is reported once.
+pub fn validate_table(spec: &TableSpec) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let specs: Vec<&BoundarySpec> = iter::once(&spec.outer).chain(&spec.obstacles).collect();
+
+    // Built segments of each component whose segments are all well-formed.
+    let built: Vec<Option<Vec<BoundarySegment>>> = specs
+        .iter()
+        .enumerate()
+        .map(|(c, boundary)| build_segments(c, boundary, &mut report))
+        .collect();
+
+    // Components that are closed and simple: the only ones with an inside.
+    let mut simple = vec![false; specs.len()];
+    for (c, segments) in built.iter().enumerate() {
+        let Some(segments) = segments else { continue };
+        check_periodic_pairs(c, segments, &mut report);
+        check_walls(c, segments, &mut report);
+        check_holes(c, specs[c], segments, &mut report);
+
+        if check_closure(c, segments, &mut report)
+            && check_self_intersection(c, segments, &mut report)
+        {
+            simple[c] = true;
+            if signed_area(segments) < 0.0 {
+                report.push(
+                    Severity::Error,
+                    IssueKind::Clockwise,
+                    c,
+                    &[],
+                    format!("component {}: boundary runs clockwise", c),
+                );
+            }
+        }
+    }
+
+    check_segment_ids(&specs, &mut report);
+
+    let n = built.len();
+    let mut crossed = vec![vec![false; n]; n];
+    for a in 0..n {
+        for b in a + 1..n {
+            if let (Some(first), Some(second)) = (&built[a], &built[b])
+                && !check_crossings(a, first, b, second, &mut report)
+            {
+                crossed[a][b] = true;
+                crossed[b][a] = true;
+            }
+        }
+    }
+
+    // Without crossings, one point decides which side of a simple
+    // component an obstacle is on.
+    for (c, segments) in built.iter().enumerate().skip(1) {
+        let Some(segments) = segments else { continue };
+        let probe = segments[0].point_at(0.5 * segments[0].length());
+
+        for (k, container) in built.iter().enumerate() {
+            let Some(container) = container.as_ref().filter(|_| simple[k]) else {
+                continue;
+            };
+            if k == c || crossed[c][k] {
+                continue;
+            }
+
+            if k == 0 && !encloses(container, probe) {
+                report.push(
+                    Severity::Error,
+                    IssueKind::ObstacleOutside,
+                    c,
+                    &[],
+                    format!("component {}: obstacle lies outside the outer boundary", c),
+                );
+            } else if k > 0 && encloses(container, probe) {
+                report.push(
+                    Severity::Error,
+                    IssueKind::ObstacleInsideObstacle,
+                    c,
+                    &[],
+                    format!("component {}: obstacle lies inside component {}", c, k),
+                );
+            }
+        }
+    }
+
+    report
+}
+
+/// Report every segment that reuses the id of an earlier one, anywhere in
+/// the table.
+fn check_segment_ids(specs: &[&BoundarySpec], report: &mut ValidationReport) {
+    let mut first = HashMap::new();
+    for (c, boundary) in specs.iter().enumerate() {
+        for (i, segment) in boundary.segments.iter().enumerate() {
+            let Some(id) = segment.label().id else {
+                continue;
+            };
+            let here = SegmentLocation {
+                component_index: c,
+                segment_index: i,
+            };
+            let Some(&earlier) = first.get(&id) else {
+                first.insert(id, here);
+                continue;
+            };
+            let SegmentLocation {
+                component_index: ec,
+                segment_index: ei,
+            } = earlier;
+            report.push_across(
+                Severity::Warning,
+                IssueKind::DuplicateSegmentId,
+                c,
+                vec![earlier, here],
+                format!(
+                    "component {}: segment {} has id {}, as does segment {} of component {}",
+                    c, i, id, ei, ec
+                ),
+            );
+        }
+    }
+}
+
+/// Build the segments of component `c`, reporting any that are malformed.
+fn build_segments(
+    c: usize,
+    boundary: &BoundarySpec,
+    report: &mut ValidationReport,
+) -> Option<Vec<BoundarySegment>> {
+    if boundary.segments.is_empty() {
+        report.push(
+            Severity::Error,
+            IssueKind::EmptyComponent,
+            c,
+            &[],
+            format!("component {}: boundary has no segments", c),
+        );
+        return None;
+    }
+
+    let mut well_formed = true;
+    for (i, segment) in boundary.segments.iter().enumerate() {
+        let problem = match *segment {
+            SegmentSpec::Line { start, end, .. } => {
+                if ![start.x, start.y, end.x, end.y]
+                    .iter()
+                    .all(|v| v.is_finite())
+                {
+                    Some((IssueKind::NonFinite, "has a non-finite endpoint"))
+                } else if (end - start).length() <= MIN_SEGMENT_LENGTH {
+                    Some((IssueKind::DegenerateSegment, "has (nearly) zero length"))
+                } else {
+                    None
+                }
+            }
+            SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+                ..
+            } => {
+                if ![center.x, center.y, radius, start_angle, end_angle]
+                    .iter()
+                    .all(|v| v.is_finite())
+                {
+                    Some((IssueKind::NonFinite, "has a non-finite parameter"))
+                } else if radius <= 0.0 {
+                    Some((IssueKind::NonPositiveRadius, "has a non-positive radius"))
+                } else if radius * (end_angle - start_angle).abs() <= MIN_SEGMENT_LENGTH {
+                    Some((IssueKind::DegenerateSegment, "has (nearly) zero length"))
+                } else if (end_angle - start_angle).abs() > std::f64::consts::TAU {
+                    Some((IssueKind::SelfIntersection, "wraps around more than once"))
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some((kind, what)) = problem {
+            report.push(
+                Severity::Error,
+                kind,
+                c,
+                &[i],
+                format!("component {}: segment {} {}", c, i, what),
+            );
+            well_formed = false;
+        }
+    }
+
+    well_formed.then(|| {
+        boundary
+            .segments
+            .iter()
+            .map(SegmentSpec::to_boundary_segment)
+            .collect()
+    })
+}
+
+fn start_point(segment: &BoundarySegment) -> Vec2 {
+    segment.point_at(0.0)
+}
+
+fn end_point(segment: &BoundarySegment) -> Vec2 {
+    segment.point_at(segment.length())
+}
+
+/// Report every gap between consecutive segments; `true` if there is none.
+fn check_closure(c: usize, segments: &[BoundarySegment], report: &mut ValidationReport) -> bool {
+    let n = segments.len();
+    let mut closed = true;
+    for i in 0..n {
+        let next = (i + 1) % n;
+        let gap = (start_point(&segments[next]) - end_point(&segments[i])).length();
+        if gap > CLOSURE_TOLERANCE {
+            report.push(
+                Severity::Error,
+                IssueKind::NotClosed,
+                c,
+                &[i, next],
+                format!(
+                    "component {}: segment {} ends {:.3e} away from the start of segment {}",
+                    c, i, gap, next
+                ),
+            );
+            closed = false;
+        }
+    }
+    closed
+}
+
+fn check_periodic_pairs(c: usize, segments: &[BoundarySegment], report: &mut ValidationReport) {
+    for (i, segment) in segments.iter().enumerate() {
+        let BoundaryCondition::PeriodicPairedWith(j) = segment.condition() else {
+            continue;
+        };
+        if j >= segments.len() {
+            report.push(
+                Severity::Error,
+                IssueKind::InvalidPeriodicPair,
+                c,
+                &[i],
+                format!(
+                    "component {}: segment {} is paired with nonexistent segment {}",
+                    c, i, j
+                ),
+            );
+        } else if segments[j].condition() != BoundaryCondition::PeriodicPairedWith(i) {
+            report.push(
+                Severity::Warning,
+                IssueKind::MismatchedPeriodicPair,
+                c,
+                &[i, j],
+                format!(
+                    "component {}: segment {} is paired with segment {}, which is not paired back",
+                    c, i, j
+                ),
+            );
+        } else if i < j && (segments[i].length() - segments[j].length()).abs() > CLOSURE_TOLERANCE {
+            report.push(
+                Severity::Warning,
+                IssueKind::MismatchedPeriodicPair,
+                c,
+                &[i, j],
+                format!(
+                    "component {}: paired segments {} and {} differ in length",
+                    c, i, j
+                ),
+            );
+        }
+    }
+}
+
+/// Check the parameters of heated and moving walls.
+fn check_walls(c: usize, segments: &[BoundarySegment], report: &mut ValidationReport) {
+    for (i, segment) in segments.iter().enumerate() {
+        match segment.condition() {
+            BoundaryCondition::Thermal { temperature }
+                if !(temperature.is_finite() && temperature > 0.0) =>
+            {
+                report.push(
+                    Severity::Error,
+                    IssueKind::InvalidTemperature,
+                    c,
+                    &[i],
+                    format!(
+                        "component {}: segment {} has temperature {}, which is not positive",
+                        c, i, temperature
+                    ),
+                );
+            }
+            BoundaryCondition::Oscillating {
+                velocity,
+                frequency,
+                phase,
+            } if ![velocity, frequency, phase].iter().all(|x| x.is_finite()) => {
+                report.push(
+                    Severity::Error,
+                    IssueKind::InvalidWallMotion,
+                    c,
+                    &[i],
+                    format!("component {}: segment {} moves at a non-finite rate", c, i),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_holes(
+    c: usize,
+    boundary: &BoundarySpec,
+    segments: &[BoundarySegment],
+    report: &mut ValidationReport,
+) {
+    let length: f64 = segments.iter().map(BoundarySegment::length).sum();
+    for (h, hole) in boundary.holes.iter().enumerate() {
+        if !(hole.start.is_finite() && hole.end.is_finite()) {
+            report.push(
+                Severity::Error,
+                IssueKind::InvalidHole,
+                c,
+                &[],
+                format!("component {}: hole {} has a non-finite end", c, h),
+            );
+        } else if [hole.start, hole.end]
+            .iter()
+            .any(|&s| !(0.0..=length).contains(&s))
+        {
+            report.push(
+                Severity::Warning,
+                IssueKind::InvalidHole,
+                c,
+                &[],
+                format!(
+                    "component {}: hole {} reaches outside [0, {}]",
+                    c, h, length
+                ),
+            );
+        }
+    }
+}
+
+/// Report every pair of segments of one component that meet anywhere but
+/// at their shared joint; `true` if there is none.
+fn check_self_intersection(
+    c: usize,
+    segments: &[BoundarySegment],
+    report: &mut ValidationReport,
+) -> bool {
+    let n = segments.len();
+    let mut simple = true;
+    for i in 0..n {
+        for j in i + 1..n {
+            // Joints between i and j: the end of one at the start of the other.
+            let mut joints = Vec::with_capacity(2);
+            if j == i + 1 {
+                joints.push(end_point(&segments[i]));
+            }
+            if i == 0 && j == n - 1 {
+                joints.push(start_point(&segments[i]));
+            }
+
+            let meets = common_points(&segments[i], &segments[j])
+                .into_iter()
+                .any(|p| joints.iter().all(|&q| (p - q).length() > JOINT_TOLERANCE));
+            if meets {
+                report.push(
+                    Severity::Error,
+                    IssueKind::SelfIntersection,
+                    c,
+                    &[i, j],
+                    format!("component {}: segments {} and {} intersect", c, i, j),
+                );
+                simple = false;
+            }
+        }
+    }
+    simple
+}
+
+/// Report every pair of segments of components `a` and `b` that meet;
+/// `true` if there is none.
+fn check_crossings(
+    a: usize,
+    first: &[BoundarySegment],
+    b: usize,
+    second: &[BoundarySegment],
+    report: &mut ValidationReport,
+) -> bool {
+    let mut separate = true;
+    for (i, s) in first.iter().enumerate() {
+        for (j, t) in second.iter().enumerate() {
+            if !common_points(s, t).is_empty() {
+                report.push_across(
+                    Severity::Error,
+                    IssueKind::ComponentsIntersect,
+                    a,
+                    vec![
+                        SegmentLocation {
+                            component_index: a,
+                            segment_index: i,
+                        },
+                        SegmentLocation {
+                            component_index: b,
+                            segment_index: j,
+                        },
+                    ],
+                    format!(
+                        "segment {} of component {} meets segment {} of component {}",
+                        i, a, j, b
+                    ),
+                );
+                separate = false;
+            }
+        }
+    }
+    separate
+}
+
+/// Axis-aligned box `(min, max)` containing the segment.
+fn bounds(segment: &BoundarySegment) -> (Vec2, Vec2) {
+    match segment {
+        BoundarySegment::Line(line) => (
+            Vec2::new(line.start.x.min(line.end.x), line.start.y.min(line.end.y)),
+            Vec2::new(line.start.x.max(line.end.x), line.start.y.max(line.end.y)),
+        ),
+        BoundarySegment::CircularArc(arc) => {
+            let r = Vec2::new(arc.radius, arc.radius);
+            (arc.center - r, arc.center + r)
+        }
+        BoundarySegment::External(_) => unreachable!("specs hold only built-in segments"),
+    }
+}
+
+fn lies_on(segment: &BoundarySegment, p: Vec2) -> bool {
+    segment.closest_point(p).1 <= ON_SEGMENT_TOLERANCE
+}
+
+/// Points on both segments: their isolated crossings, or a few sample
+/// points of the common part if they overlap.
+fn common_points(s: &BoundarySegment, t: &BoundarySegment) -> Vec<Vec2> {
+    let ((s_min, s_max), (t_min, t_max)) = (bounds(s), bounds(t));
+    let tol = ON_SEGMENT_TOLERANCE;
+    if s_min.x > t_max.x + tol
+        || t_min.x > s_max.x + tol
+        || s_min.y > t_max.y + tol
+        || t_min.y > s_max.y + tol
+    {
+        return Vec::new();
+    }
+
+    let samples =
+        |seg: &BoundarySegment| [0.0, 0.25, 0.5, 0.75, 1.0].map(|f| seg.point_at(f * seg.length()));
+
+    let candidates: Vec<Vec2> = match (s, t) {
+        (BoundarySegment::Line(a), BoundarySegment::Line(b)) => {
+            let (d, e) = (a.end - a.start, b.end - b.start);
+            let denom = d.cross(e);
+            if denom != 0.0 {
+                vec![a.start + d * ((b.start - a.start).cross(e) / denom)]
+            } else {
+                // Parallel: only collinear segments can share points.
+                samples(s).into_iter().chain(samples(t)).collect()
+            }
+        }
+        (BoundarySegment::Line(line), BoundarySegment::CircularArc(arc))
+        | (BoundarySegment::CircularArc(arc), BoundarySegment::Line(line)) => {
+            let d = line.end - line.start;
+            let m = line.start - arc.center;
+            let (a, b, c) = (d.dot(d), m.dot(d), m.dot(m) - arc.radius * arc.radius);
+            let discriminant = b * b - a * c;
+            if discriminant < -tol * a {
+                Vec::new()
+            } else {
+                let root = discriminant.max(0.0).sqrt();
+                [(-b - root) / a, (-b + root) / a]
+                    .into_iter()
+                    .map(|u| line.start + d * u)
+                    .collect()
+            }
+        }
+        (BoundarySegment::External(_), _) | (_, BoundarySegment::External(_)) => {
+            unreachable!("specs hold only built-in segments")
+        }
+        (BoundarySegment::CircularArc(a), BoundarySegment::CircularArc(b)) => {
+            let between = b.center - a.center;
+            let dist = between.length();
+            if dist <= tol && (a.radius - b.radius).abs() <= tol {
+                samples(s).into_iter().chain(samples(t)).collect()
+            } else if dist <= tol
+                || dist > a.radius + b.radius + tol
+                || dist < (a.radius - b.radius).abs() - tol
+            {
+                Vec::new()
+            } else {
+                let along =
+                    (a.radius * a.radius - b.radius * b.radius + dist * dist) / (2.0 * dist);
+                let height = (a.radius * a.radius - along * along).max(0.0).sqrt();
+                let (u, n) = (between / dist, between.perp() / dist);
+                let foot = a.center + u * along;
+                vec![foot + n * height, foot - n * height]
+            }
+        }
+    };
+
+    candidates
+        .into_iter()
+        .filter(|&p| lies_on(s, p) && lies_on(t, p))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IssueKind, SegmentLocation, Severity, validate_table};
+    use crate::geometry::boundary::Hole;
+    use crate::geometry::primitives::Vec2;
+    use crate::geometry::segments::{BoundaryCondition, SegmentLabel};
+    use crate::geometry::standard_tables::{
+        circle, ellipse, fermi_ulam, l_shape, lorentz_gas, mushroom, sinai, stadium,
+    };
+    use crate::geometry::table_spec::{BoundarySpec, SegmentSpec, TableSpec};
+
+    fn kinds(spec: &TableSpec) -> Vec<IssueKind> {
+        validate_table(spec).issues.iter().map(|i| i.kind).collect()
+    }
+
+    /// Closed polyline through `vertices`, with no checks at all.
+    fn polyline(vertices: &[Vec2]) -> TableSpec {
+        let n = vertices.len();
+        TableSpec {
+            outer: BoundarySpec {
+                name: "outer".to_string(),
+                segments: (0..n)
+                    .map(|i| SegmentSpec::Line {
+                        start: vertices[i],
+                        end: vertices[(i + 1) % n],
+                        condition: BoundaryCondition::Reflect,
+                        label: SegmentLabel::default(),
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn disk(center: Vec2, radius: f64) -> BoundarySpec {
+        BoundarySpec {
+            name: "disk".to_string(),
+            segments: vec![SegmentSpec::CircularArc {
+                center,
+                radius,
+                start_angle: 0.0,
+                end_angle: std::f64::consts::TAU,
+                ccw: true,
+                condition: BoundaryCondition::Reflect,
+                label: SegmentLabel::default(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn standard_tables_are_valid() {
+        for spec in [
+            sinai(1.0, 0.2),
+            stadium(1.0, 0.5),
+            mushroom(1.0, 0.5, 1.0),
+            ellipse(1.0, 0.6),
+            circle(1.0),
+            l_shape(1.0, 1.0, 0.5, 0.5),
+            lorentz_gas(1.0, 0.4),
+        ] {
+            let report = validate_table(&spec);
+            assert!(report.issues.is_empty(), "{:?}", report.issues);
+        }
+    }
+
+    #[test]
+    fn malformed_segments_are_located() {
+        let mut spec = sinai(1.0, 0.2);
+        spec.outer.segments[1] = SegmentSpec::Line {
+            start: Vec2::new(1.0, 0.0),
+            end: Vec2::new(1.0, 0.0),
+            condition: BoundaryCondition::Reflect,
+            label: SegmentLabel::default(),
+        };
+        if let SegmentSpec::CircularArc { radius, .. } = &mut spec.obstacles[0].segments[0] {
+            *radius = 0.0;
+        }
+
+        let report = validate_table(&spec);
+        assert!(!report.is_valid());
+        assert_eq!(report.issues.len(), 2);
+        assert_eq!(report.issues[0].kind, IssueKind::DegenerateSegment);
+        assert_eq!(
+            report.issues[0].segments,
+            [SegmentLocation {
+                component_index: 0,
+                segment_index: 1
+            }]
+        );
+        assert_eq!(report.issues[1].kind, IssueKind::NonPositiveRadius);
+        assert_eq!(report.issues[1].component_index, 1);
+    }
+
+    #[test]
+    fn gaps_orientation_and_self_intersections_are_errors() {
+        // Stretching the bottom edge opens its joint with the right edge.
+        let mut spec = sinai(1.0, 0.2);
+        if let SegmentSpec::Line { end, .. } = &mut spec.outer.segments[0] {
+            *end = Vec2::new(1.1, 0.0);
+        }
+        assert_eq!(kinds(&spec), [IssueKind::NotClosed]);
+
+        // A clockwise square.
+        let mut spec = sinai(1.0, 0.2);
+        spec.outer.segments.reverse();
+        for segment in &mut spec.outer.segments {
+            if let SegmentSpec::Line { start, end, .. } = segment {
+                std::mem::swap(start, end);
+            }
+        }
+        assert_eq!(kinds(&spec), [IssueKind::Clockwise]);
+
+        // A bow tie: the bottom and top edges are joined by crossing diagonals.
+        let bow_tie = polyline(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 1.0),
+        ]);
+        let report = validate_table(&bow_tie);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, IssueKind::SelfIntersection);
+        assert_eq!(
+            report.issues[0]
+                .segments
+                .iter()
+                .map(|s| s.segment_index)
+                .collect::<Vec<_>>(),
+            [1, 3]
+        );
+    }
+
+    #[test]
+    fn obstacles_must_sit_inside_the_table_and_apart() {
+        let mut spec = sinai(1.0, 0.2);
+        spec.obstacles.push(disk(Vec2::new(3.0, 0.5), 0.2));
+        spec.obstacles.push(disk(Vec2::new(0.5, 0.5), 0.1));
+        spec.obstacles.push(disk(Vec2::new(0.2, 0.8), 0.15));
+        spec.obstacles.push(disk(Vec2::new(0.9, 0.5), 0.15));
+
+        let report = validate_table(&spec);
+        let found: Vec<_> = report
+            .issues
+            .iter()
+            .map(|i| (i.kind, i.component_index))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (IssueKind::ComponentsIntersect, 0),
+                (IssueKind::ObstacleOutside, 2),
+                (IssueKind::ObstacleInsideObstacle, 3),
+            ]
+        );
+        // The crossing names a segment on each side: the right edge and the disk.
+        assert_eq!(
+            report.issues[0].segments,
+            [
+                SegmentLocation {
+                    component_index: 0,
+                    segment_index: 1
+                },
+                SegmentLocation {
+                    component_index: 5,
+                    segment_index: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn holes_and_periodic_pairs_give_warnings() {
+        let mut spec = lorentz_gas(1.0, 0.4);
+        spec.outer.segments[0] = SegmentSpec::Line {
+            start: Vec2::new(0.0, 0.0),
+            end: Vec2::new(1.0, 0.0),
+            condition: BoundaryCondition::PeriodicPairedWith(1),
+            label: SegmentLabel::default(),
+        };
+        spec.obstacles[0].holes.push(Hole {
+            start: 0.5,
+            end: 9.0,
+        });
+
+        let report = validate_table(&spec);
+        assert!(report.is_valid());
+        assert!(
+            report
+                .issues
+                .iter()
+                .all(|i| i.severity == Severity::Warning)
+        );
+        // Both segment 0 and segment 2 now point at a segment that does
+        // not point back.
+        assert_eq!(
+            kinds(&spec),
+            [
+                IssueKind::MismatchedPeriodicPair,
+                IssueKind::MismatchedPeriodicPair,
+                IssueKind::InvalidHole
+            ]
+        );
+    }
+
+    #[test]
+    fn heated_walls_need_a_positive_temperature() {
+        let mut spec = lorentz_gas(1.0, 0.4);
+        let heat = |spec: &mut TableSpec, temperature| {
+            let (SegmentSpec::Line { condition, .. } | SegmentSpec::CircularArc { condition, .. }) =
+                &mut spec.obstacles[0].segments[0];
+            *condition = BoundaryCondition::Thermal { temperature };
+        };
+        heat(&mut spec, 1.5);
+        assert!(validate_table(&spec).issues.is_empty());
+
+        for temperature in [0.0, -1.0, f64::NAN] {
+            heat(&mut spec, temperature);
+            assert_eq!(kinds(&spec), [IssueKind::InvalidTemperature]);
+        }
+    }
+
+    #[test]
+    fn moving_walls_need_a_finite_motion() {
+        let mut spec = fermi_ulam(1.0, 1.0, 0.1, 1.0);
+        assert!(validate_table(&spec).issues.is_empty());
+
+        let (SegmentSpec::Line { condition, .. } | SegmentSpec::CircularArc { condition, .. }) =
+            &mut spec.outer.segments[2];
+        *condition = BoundaryCondition::Oscillating {
+            velocity: 0.1,
+            frequency: f64::INFINITY,
+            phase: 0.0,
+        };
+        assert_eq!(kinds(&spec), [IssueKind::InvalidWallMotion]);
+    }
+
+    #[test]
+    fn segment_ids_must_be_unique_across_the_table() {
+        let mut spec = sinai(1.0, 0.2);
+        let label = |segment: &mut SegmentSpec, id: u32| {
+            let (SegmentSpec::Line { label, .. } | SegmentSpec::CircularArc { label, .. }) =
+                segment;
+            label.id = Some(id);
+        };
+        label(&mut spec.outer.segments[0], 1);
+        label(&mut spec.outer.segments[2], 2);
+        label(&mut spec.obstacles[0].segments[0], 1);
+
+        let report = validate_table(&spec);
+        assert_eq!(kinds(&spec), [IssueKind::DuplicateSegmentId]);
+        assert_eq!(report.issues[0].severity, Severity::Warning);
+        assert_eq!(
+            report.issues[0].segments,
+            [
+                SegmentLocation {
+                    component_index: 0,
+                    segment_index: 0,
+                },
+                SegmentLocation {
+                    component_index: 1,
+                    segment_index: 0,
+                },
+            ]
+        );
+    }
+}
@@ -6,3 +6,15 @@ pub mod dynamics;
 pub mod geometry;
 
 pub use geometry::table_spec::{BoundarySpec, TableSpec};
+
+/// Version of this crate.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Cargo features this build of the crate was compiled with.
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "openapi") {
+        features.push("openapi");
+    }
+    features
+}
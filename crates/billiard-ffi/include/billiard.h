@@ -0,0 +1,127 @@
+/*
+ * C interface to the bouncers billiard simulator.
+ *
+ * Link against libbilliard (built by `cargo build -p billiard-ffi
+ * --release` as a shared and a static library). Tables are opaque handles
+ * built from TableSpec JSON, the same format the CLI and the API read.
+ * Every fallible function returns a BilliardStatus; on failure,
+ * billiard_last_error() describes what went wrong.
+ *
+ *     BilliardTable *table;
+ *     if (billiard_table_from_json(json, &table) != BILLIARD_OK) {
+ *         fprintf(stderr, "%s\n", billiard_last_error());
+ *         return 1;
+ *     }
+ *     BilliardConfig config;
+ *     billiard_config_default(&config);
+ *     BilliardState initial = {0, 0.5, 0.8, 1.0};
+ *     BilliardCollision collisions[100];
+ *     size_t written;
+ *     BilliardTermination termination;
+ *     billiard_simulate(table, &initial, &config, collisions, 100,
+ *                       &written, &termination);
+ *     billiard_table_free(table);
+ */
+
+#ifndef BILLIARD_H
+#define BILLIARD_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    BILLIARD_OK = 0,
+    BILLIARD_NULL_POINTER = 1,
+    BILLIARD_INVALID_UTF8 = 2,
+    BILLIARD_INVALID_JSON = 3,
+    BILLIARD_INVALID_TABLE = 4,
+    BILLIARD_INVALID_CONFIG = 5,
+    BILLIARD_INVALID_STATE = 6,
+    BILLIARD_SIMULATION_FAILED = 7,
+    BILLIARD_PANIC = 8
+} BilliardStatus;
+
+/* Why a trajectory ended. */
+typedef enum {
+    BILLIARD_MAX_STEPS = 0,
+    BILLIARD_CORNER = 1,
+    BILLIARD_ABSORBED = 2,
+    BILLIARD_ESCAPED = 3,
+    BILLIARD_NO_COLLISION = 4,
+    BILLIARD_STOP_CONDITION = 5,
+    BILLIARD_MAX_TIME = 6,
+    BILLIARD_INVARIANT_VIOLATION = 7,
+    BILLIARD_WHISPERING_GALLERY = 8
+} BilliardTermination;
+
+/* Values of BilliardConfig.corner_policy. */
+#define BILLIARD_CORNER_REPORT 0
+#define BILLIARD_CORNER_TERMINATE 1
+#define BILLIARD_CORNER_REFLECT_BISECTOR 2
+
+typedef struct BilliardTable BilliardTable;
+
+/* A starting state: arc length s on a boundary component and the outgoing
+ * angle theta to its tangent, in radians. */
+typedef struct {
+    size_t component_index;
+    double s;
+    double theta;
+    double speed;
+} BilliardState;
+
+/* Simulation settings; fill with billiard_config_default() first. */
+typedef struct {
+    size_t max_steps;
+    double epsilon;
+    double restitution;
+    double gravity;  /* 0 for straight flights */
+    double max_time; /* 0 for no limit */
+    int32_t corner_policy;
+    uint64_t seed;
+} BilliardConfig;
+
+typedef struct {
+    size_t component_index;
+    size_t segment_index;
+    double s;
+    double theta;
+    double speed;
+    double x;
+    double y;
+    double chord;
+    double path_length;
+    double time;
+    int32_t corner;
+} BilliardCollision;
+
+/* Description of the last error on this thread, or NULL. Valid until the
+ * next failing call on the same thread. */
+const char *billiard_last_error(void);
+
+BilliardStatus billiard_config_default(BilliardConfig *config);
+
+BilliardStatus billiard_table_from_json(const char *json, BilliardTable **out);
+void billiard_table_free(BilliardTable *table);
+double billiard_table_perimeter(const BilliardTable *table);
+size_t billiard_table_component_count(const BilliardTable *table);
+
+/* Simulate at most `capacity` collisions (and at most config->max_steps)
+ * into `collisions`. `termination` may be NULL. */
+BilliardStatus billiard_simulate(const BilliardTable *table,
+                                 const BilliardState *initial,
+                                 const BilliardConfig *config,
+                                 BilliardCollision *collisions,
+                                 size_t capacity,
+                                 size_t *written,
+                                 BilliardTermination *termination);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BILLIARD_H */
@@ -0,0 +1,493 @@
+//! C interface to billiard-core, for embedding in C and C++ programs and in
+//! MATLAB through mex.
+//!
+//! The declarations are in `include/billiard.h`. Tables are opaque handles
+//! built from `TableSpec` JSON and freed with [`billiard_table_free`];
+//! trajectories are written into caller-provided arrays of
+//! [`BilliardCollision`]. Every fallible function returns a
+//! [`BilliardStatus`] code, and [`billiard_last_error`] describes the last
+//! failure on the calling thread. Panics never cross the boundary; they
+//! come back as [`BilliardStatus::Panic`].
+//!
+//! The layout of the `#[repr(C)]` types and the values of the codes are
+//! part of the ABI: add new codes at the end and never reorder fields.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+
+use billiard_core::dynamics::config::{DEFAULT_MAX_STEPS, SimulationConfig};
+use billiard_core::dynamics::simulation::{
+    BilliardMap, CollisionResult, CornerPolicy, DEFAULT_EPSILON, FlightModel, Termination,
+};
+use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::geometry::boundary::BilliardTable;
+use billiard_core::geometry::table_spec::TableSpec;
+
+/// Result of a call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BilliardStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    InvalidJson = 3,
+    InvalidTable = 4,
+    InvalidConfig = 5,
+    InvalidState = 6,
+    SimulationFailed = 7,
+    Panic = 8,
+}
+
+/// Why a trajectory ended; the same reasons as the API's `termination`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BilliardTermination {
+    MaxSteps = 0,
+    Corner = 1,
+    Absorbed = 2,
+    Escaped = 3,
+    NoCollision = 4,
+    StopCondition = 5,
+    MaxTime = 6,
+    InvariantViolation = 7,
+    WhisperingGallery = 8,
+}
+
+impl BilliardTermination {
+    fn from_core(termination: &Termination) -> Self {
+        match termination {
+            Termination::MaxSteps => BilliardTermination::MaxSteps,
+            Termination::Corner => BilliardTermination::Corner,
+            Termination::Absorbed => BilliardTermination::Absorbed,
+            Termination::Escaped(_) => BilliardTermination::Escaped,
+            Termination::NoCollision => BilliardTermination::NoCollision,
+            Termination::StopCondition => BilliardTermination::StopCondition,
+            Termination::MaxTime => BilliardTermination::MaxTime,
+            Termination::InvariantViolation => BilliardTermination::InvariantViolation,
+            Termination::WhisperingGallery => BilliardTermination::WhisperingGallery,
+        }
+    }
+}
+
+/// Values of [`BilliardConfig::corner_policy`]; see [`CornerPolicy`].
+pub const BILLIARD_CORNER_REPORT: i32 = 0;
+pub const BILLIARD_CORNER_TERMINATE: i32 = 1;
+pub const BILLIARD_CORNER_REFLECT_BISECTOR: i32 = 2;
+
+/// A starting state on the boundary.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct BilliardState {
+    pub component_index: usize,
+    pub s: f64,
+    pub theta: f64,
+    pub speed: f64,
+}
+
+/// Simulation settings; start from [`billiard_config_default`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct BilliardConfig {
+    pub max_steps: usize,
+    pub epsilon: f64,
+    pub restitution: f64,
+
+    /// Downward acceleration during flights; 0 for straight lines.
+    pub gravity: f64,
+
+    /// Time after which to stop; 0 for no limit.
+    pub max_time: f64,
+
+    /// One of the `BILLIARD_CORNER_*` constants. A plain integer rather
+    /// than an enum, so that an out-of-range value from C is an error
+    /// instead of undefined behavior.
+    pub corner_policy: i32,
+    pub seed: u64,
+}
+
+impl BilliardConfig {
+    fn to_core(self) -> Result<SimulationConfig, String> {
+        let corner_policy = match self.corner_policy {
+            BILLIARD_CORNER_REPORT => CornerPolicy::Report,
+            BILLIARD_CORNER_TERMINATE => CornerPolicy::Terminate,
+            BILLIARD_CORNER_REFLECT_BISECTOR => CornerPolicy::ReflectBisector,
+            other => return Err(format!("unknown corner policy {}", other)),
+        };
+        let flight = if self.gravity == 0.0 {
+            FlightModel::Straight
+        } else {
+            FlightModel::Gravity { g: self.gravity }
+        };
+        SimulationConfig::builder()
+            .max_steps(self.max_steps)
+            .epsilon(self.epsilon)
+            .restitution(self.restitution)
+            .flight(flight)
+            .max_time((self.max_time != 0.0).then_some(self.max_time))
+            .corner_policy(corner_policy)
+            .seed(self.seed)
+            .build()
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// One collision of a trajectory.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct BilliardCollision {
+    pub component_index: usize,
+    pub segment_index: usize,
+
+    /// Arc length of the hit point on its component.
+    pub s: f64,
+
+    /// Outgoing angle to the tangent.
+    pub theta: f64,
+    pub speed: f64,
+
+    /// Hit point in the table.
+    pub x: f64,
+    pub y: f64,
+
+    /// Length of the flight ending here, and the totals so far.
+    pub chord: f64,
+    pub path_length: f64,
+    pub time: f64,
+
+    /// 1 if the hit was at a corner, else 0.
+    pub corner: i32,
+}
+
+impl BilliardCollision {
+    fn from_core(c: &CollisionResult) -> Self {
+        BilliardCollision {
+            component_index: c.component_index,
+            segment_index: c.segment_index,
+            s: c.s,
+            theta: c.theta,
+            speed: c.speed,
+            x: c.hit_point.x,
+            y: c.hit_point.y,
+            chord: c.chord,
+            path_length: c.path_length,
+            time: c.time,
+            corner: i32::from(c.corner.is_some()),
+        }
+    }
+}
+
+/// A billiard table; opaque to C.
+pub struct BilliardTableHandle {
+    table: BilliardTable,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Record `message` for [`billiard_last_error`] and return `status`.
+fn fail(status: BilliardStatus, message: impl Into<String>) -> BilliardStatus {
+    // Interior NULs would truncate the message; drop them.
+    let message: Vec<u8> = message
+        .into()
+        .into_bytes()
+        .into_iter()
+        .filter(|&b| b != 0)
+        .collect();
+    let message = CString::new(message).expect("NUL bytes were removed");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    status
+}
+
+/// Run `f`, turning a panic into [`BilliardStatus::Panic`].
+fn guard(f: impl FnOnce() -> BilliardStatus) -> BilliardStatus {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        fail(BilliardStatus::Panic, format!("panic: {}", message))
+    })
+}
+
+/// Description of the last error on this thread, or NULL if there was
+/// none. The string stays valid until the next failing call on this
+/// thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn billiard_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
+
+/// Fill `config` with the defaults: 1000 steps, the default epsilon,
+/// elastic straight flights, corners reported, no time limit and seed 0.
+///
+/// # Safety
+/// `config` must be NULL or point to writable memory for a
+/// `BilliardConfig`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn billiard_config_default(config: *mut BilliardConfig) -> BilliardStatus {
+    if config.is_null() {
+        return fail(BilliardStatus::NullPointer, "config is NULL");
+    }
+    let default = BilliardConfig {
+        max_steps: DEFAULT_MAX_STEPS,
+        epsilon: DEFAULT_EPSILON,
+        restitution: 1.0,
+        gravity: 0.0,
+        max_time: 0.0,
+        corner_policy: BILLIARD_CORNER_REPORT,
+        seed: 0,
+    };
+    // SAFETY: checked for NULL above; the caller guarantees the rest.
+    unsafe { config.write(default) };
+    BilliardStatus::Ok
+}
+
+/// Build a table from a NUL-terminated `TableSpec` JSON string and store
+/// its handle in `*out`; free it with [`billiard_table_free`].
+///
+/// # Safety
+/// `json` must be NULL or a NUL-terminated string, and `out` NULL or
+/// writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn billiard_table_from_json(
+    json: *const c_char,
+    out: *mut *mut BilliardTableHandle,
+) -> BilliardStatus {
+    if json.is_null() || out.is_null() {
+        return fail(BilliardStatus::NullPointer, "json and out must not be NULL");
+    }
+    guard(|| {
+        // SAFETY: checked for NULL above; the caller guarantees the rest.
+        let json = match unsafe { CStr::from_ptr(json) }.to_str() {
+            Ok(json) => json,
+            Err(e) => return fail(BilliardStatus::InvalidUtf8, e.to_string()),
+        };
+        let spec: TableSpec = match serde_json::from_str(json) {
+            Ok(spec) => spec,
+            Err(e) => return fail(BilliardStatus::InvalidJson, e.to_string()),
+        };
+        let table = match spec.try_to_billiard_table() {
+            Ok(table) => table,
+            Err(e) => return fail(BilliardStatus::InvalidTable, e.to_string()),
+        };
+        let handle = Box::into_raw(Box::new(BilliardTableHandle { table }));
+        // SAFETY: as above.
+        unsafe { out.write(handle) };
+        BilliardStatus::Ok
+    })
+}
+
+/// Free a table. Does nothing if `table` is NULL.
+///
+/// # Safety
+/// `table` must be NULL or a handle from [`billiard_table_from_json`] not
+/// yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn billiard_table_free(table: *mut BilliardTableHandle) {
+    if !table.is_null() {
+        // SAFETY: the handle came from `Box::into_raw` and is freed once.
+        drop(unsafe { Box::from_raw(table) });
+    }
+}
+
+/// Total length of the boundary of `table`, or NaN if it is NULL.
+///
+/// # Safety
+/// `table` must be NULL or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn billiard_table_perimeter(table: *const BilliardTableHandle) -> f64 {
+    // SAFETY: the caller guarantees a live handle or NULL.
+    unsafe { table.as_ref() }.map_or(f64::NAN, |t| t.table.perimeter())
+}
+
+/// Number of boundary components of `table` (the outer boundary and the
+/// obstacles), or 0 if it is NULL.
+///
+/// # Safety
+/// `table` must be NULL or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn billiard_table_component_count(
+    table: *const BilliardTableHandle,
+) -> usize {
+    // SAFETY: the caller guarantees a live handle or NULL.
+    unsafe { table.as_ref() }.map_or(0, |t| t.table.component_count())
+}
+
+/// Simulate a trajectory from `initial` and write its collisions to
+/// `collisions`.
+///
+/// At most `capacity` collisions are simulated, however large
+/// `config->max_steps`. The number written goes to `*written` and the
+/// reason the trajectory ended to `*termination`; `termination` may be
+/// NULL. On failure nothing is written.
+///
+/// # Safety
+/// `table` must be a live handle, `initial` and `config` readable,
+/// `collisions` writable for `capacity` elements (or NULL if `capacity` is
+/// 0), `written` writable and `termination` NULL or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn billiard_simulate(
+    table: *const BilliardTableHandle,
+    initial: *const BilliardState,
+    config: *const BilliardConfig,
+    collisions: *mut BilliardCollision,
+    capacity: usize,
+    written: *mut usize,
+    termination: *mut BilliardTermination,
+) -> BilliardStatus {
+    if table.is_null()
+        || initial.is_null()
+        || config.is_null()
+        || written.is_null()
+        || (collisions.is_null() && capacity > 0)
+    {
+        return fail(
+            BilliardStatus::NullPointer,
+            "table, initial, config, written and collisions must not be NULL",
+        );
+    }
+    guard(|| {
+        // SAFETY: checked for NULL above; the caller guarantees the rest.
+        let (table, initial, config) = unsafe { (&(*table).table, *initial, *config) };
+        let config = match config.to_core() {
+            Ok(config) => config.with_max_steps(config.max_steps().min(capacity)),
+            Err(e) => return fail(BilliardStatus::InvalidConfig, e),
+        };
+        if initial.component_index >= table.component_count() {
+            return fail(
+                BilliardStatus::InvalidState,
+                format!(
+                    "component_index {} is out of range for a table with {} components",
+                    initial.component_index,
+                    table.component_count()
+                ),
+            );
+        }
+        if !(initial.speed.is_finite() && initial.speed > 0.0) {
+            return fail(
+                BilliardStatus::InvalidState,
+                "speed must be positive and finite",
+            );
+        }
+        let initial = BoundaryState {
+            component_index: initial.component_index,
+            s: initial.s,
+            theta: initial.theta,
+            speed: initial.speed,
+        };
+
+        let mut map = BilliardMap::from_config(table, &config);
+        let trajectory = match config.max_time() {
+            Some(max_time) => map.simulate_for(&initial, config.max_steps(), max_time),
+            None => map.simulate(&initial, config.max_steps()),
+        };
+        let trajectory = match trajectory {
+            Ok(trajectory) => trajectory,
+            Err(e) => return fail(BilliardStatus::SimulationFailed, e.to_string()),
+        };
+
+        for (k, c) in trajectory.collisions.iter().enumerate() {
+            // SAFETY: there are at most `capacity` collisions.
+            unsafe { collisions.add(k).write(BilliardCollision::from_core(c)) };
+        }
+        // SAFETY: as above.
+        unsafe {
+            written.write(trajectory.collisions.len());
+            if !termination.is_null() {
+                termination.write(BilliardTermination::from_core(&trajectory.termination));
+            }
+        }
+        BilliardStatus::Ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::mem::MaybeUninit;
+
+    const SQUARE: &str = r#"{"outer": {"name": "square", "segments": [
+        {"kind": "line", "start": {"x": 0, "y": 0}, "end": {"x": 1, "y": 0}},
+        {"kind": "line", "start": {"x": 1, "y": 0}, "end": {"x": 1, "y": 1}},
+        {"kind": "line", "start": {"x": 1, "y": 1}, "end": {"x": 0, "y": 1}},
+        {"kind": "line", "start": {"x": 0, "y": 1}, "end": {"x": 0, "y": 0}}
+    ]}, "obstacles": []}"#;
+
+    #[test]
+    fn simulates_into_a_caller_buffer() {
+        let json = CString::new(SQUARE).unwrap();
+        let mut table = ptr::null_mut();
+        let mut config = MaybeUninit::uninit();
+        unsafe {
+            assert_eq!(
+                billiard_table_from_json(json.as_ptr(), &mut table),
+                BilliardStatus::Ok
+            );
+            assert!((billiard_table_perimeter(table) - 4.0).abs() < 1e-12);
+            assert_eq!(
+                billiard_config_default(config.as_mut_ptr()),
+                BilliardStatus::Ok
+            );
+        }
+        let config = unsafe { config.assume_init() };
+        let initial = BilliardState {
+            component_index: 0,
+            s: 0.5,
+            theta: std::f64::consts::FRAC_PI_4,
+            speed: 1.0,
+        };
+
+        // The buffer caps the run below max_steps.
+        let mut buffer = vec![MaybeUninit::<BilliardCollision>::uninit(); 8];
+        let (mut written, mut termination) = (0, BilliardTermination::Corner);
+        let status = unsafe {
+            billiard_simulate(
+                table,
+                &initial,
+                &config,
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                &mut written,
+                &mut termination,
+            )
+        };
+        assert_eq!(status, BilliardStatus::Ok);
+        assert_eq!(written, 8);
+        assert_eq!(termination, BilliardTermination::MaxSteps);
+        let first = unsafe { buffer[0].assume_init() };
+        assert!((first.x - 1.0).abs() < 1e-9 && (first.y - 0.5).abs() < 1e-9);
+
+        // Errors come back as codes with a message.
+        let bad = BilliardState {
+            component_index: 3,
+            ..initial
+        };
+        let status = unsafe {
+            billiard_simulate(
+                table,
+                &bad,
+                &config,
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                &mut written,
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, BilliardStatus::InvalidState);
+        let message = unsafe { CStr::from_ptr(billiard_last_error()) };
+        assert!(message.to_str().unwrap().contains("component_index 3"));
+
+        let invalid = CString::new("{").unwrap();
+        let mut other = ptr::null_mut();
+        let status = unsafe { billiard_table_from_json(invalid.as_ptr(), &mut other) };
+        assert_eq!(status, BilliardStatus::InvalidJson);
+        assert!(other.is_null());
+
+        unsafe { billiard_table_free(table) };
+    }
+}
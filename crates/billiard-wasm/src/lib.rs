@@ -0,0 +1,370 @@
+//! JavaScript bindings for billiard-core, built with wasm-bindgen.
+//!
+//! They let a web page run simulations in the browser instead of asking
+//! the API for every change of parameters. Tables and options are plain
+//! objects with the same snake_case fields as the API's requests, and
+//! collisions come back in the same shape as its responses:
+//!
+//! ```js
+//! import init, { Table } from "billiard-wasm";
+//!
+//! await init();
+//! const table = Table.fromJson(specJson);
+//! const trajectory = table.runTrajectory({
+//!   initial: { component_index: 0, s: 0.1, theta: 0.9 },
+//!   max_steps: 500,
+//! });
+//! trajectory.collisions(); // [{ step, s, theta, x, y, ... }, ...]
+//! trajectory.phaseSpace(); // Float64Array [s0, p0, s1, p1, ...]
+//! ```
+//!
+//! Build with `wasm-pack build crates/billiard-wasm --target web`.
+
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use billiard_core::dynamics::config::{DEFAULT_MAX_STEPS, SimulationConfig};
+use billiard_core::dynamics::invariants::{InvariantCheck, InvariantReport};
+use billiard_core::dynamics::lattice::{trajectory_to_polyline, unfold_trajectory};
+use billiard_core::dynamics::phase_space::{PhaseSpaceHistogram, phase_space_points};
+use billiard_core::dynamics::simulation::{
+    BilliardMap, CollisionResult, CornerPolicy, DEFAULT_EPSILON, FlightModel, Termination,
+};
+use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::geometry::boundary::BilliardTable;
+use billiard_core::geometry::offset::try_offset_for_ball;
+use billiard_core::geometry::primitives::BoundingBox;
+use billiard_core::geometry::table_spec::TableSpec;
+
+/// Convert a Rust value to a plain JavaScript object.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// A validated billiard table.
+#[wasm_bindgen]
+pub struct Table {
+    table: Rc<BilliardTable>,
+    /// Whether every component is closed; trajectories on an open table
+    /// need an escape box.
+    closed: bool,
+}
+
+#[wasm_bindgen]
+impl Table {
+    /// Build a table from a `TableSpec` object, as seen by the center of a
+    /// ball of radius `ball_radius` (a point particle if omitted). The
+    /// table may be open, with gaps between segments, as long as every
+    /// trajectory on it has an `escape_box`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(spec: JsValue, ball_radius: Option<f64>) -> Result<Table, JsError> {
+        let spec: TableSpec = serde_wasm_bindgen::from_value(spec)?;
+        Table::from_spec(&spec, ball_radius)
+    }
+
+    /// Build a table from a `TableSpec` in JSON.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str, ball_radius: Option<f64>) -> Result<Table, JsError> {
+        let spec: TableSpec = serde_json::from_str(json)?;
+        Table::from_spec(&spec, ball_radius)
+    }
+
+    /// Total length of the boundary, obstacles included.
+    pub fn perimeter(&self) -> f64 {
+        self.table.perimeter()
+    }
+
+    /// Number of boundary components: the outer boundary, then the
+    /// obstacles.
+    #[wasm_bindgen(js_name = componentCount)]
+    pub fn component_count(&self) -> usize {
+        self.table.component_count()
+    }
+
+    /// Points of each boundary component, as arrays of `[x, y]` pairs, at
+    /// most `max_chord_error` from the true curve; for drawing.
+    pub fn outline(&self, max_chord_error: f64) -> Result<JsValue, JsError> {
+        if !(max_chord_error.is_finite() && max_chord_error > 0.0) {
+            return Err(JsError::new("max_chord_error must be positive and finite"));
+        }
+        let outlines: Vec<Vec<[f64; 2]>> = self
+            .table
+            .components()
+            .map(|c| {
+                c.discretize(max_chord_error)
+                    .iter()
+                    .map(|p| [p.x, p.y])
+                    .collect()
+            })
+            .collect();
+        to_js(&outlines)
+    }
+
+    /// Simulate a trajectory as described by a [`TrajectoryOptions`]
+    /// object.
+    #[wasm_bindgen(js_name = runTrajectory)]
+    pub fn run_trajectory(&self, options: JsValue) -> Result<Trajectory, JsError> {
+        let options: TrajectoryOptions = serde_wasm_bindgen::from_value(options)?;
+        if options.max_steps == 0 {
+            return Err(JsError::new("max_steps must be greater than 0"));
+        }
+        if !self.closed && options.escape_box.is_none() {
+            return Err(JsError::new(
+                "the table is not closed; set escape_box to simulate an open table",
+            ));
+        }
+        let initial = options.initial.into_core()?;
+        let config = options.config()?;
+        let mut map = BilliardMap::from_config(&self.table, &config);
+        let trajectory = match config.max_time() {
+            Some(max_time) => map.simulate_for(&initial, config.max_steps(), max_time)?,
+            None => map.simulate(&initial, config.max_steps())?,
+        };
+        Ok(Trajectory {
+            table: Rc::clone(&self.table),
+            initial,
+            collisions: trajectory.collisions,
+            termination: trajectory.termination,
+            invariants: trajectory.invariants,
+        })
+    }
+}
+
+impl Table {
+    fn from_spec(spec: &TableSpec, ball_radius: Option<f64>) -> Result<Table, JsError> {
+        let ball_radius = ball_radius.unwrap_or(0.0);
+        if !(ball_radius.is_finite() && ball_radius >= 0.0) {
+            return Err(JsError::new("ball_radius must be non-negative and finite"));
+        }
+        let table = try_offset_for_ball(spec, ball_radius)?.to_billiard_table();
+        let closed = table.components().all(|c| c.check_closed().is_ok());
+        Ok(Table {
+            table: Rc::new(table),
+            closed,
+        })
+    }
+}
+
+/// Options for [`Table::run_trajectory`]; everything but `initial` may be
+/// left out.
+#[derive(Debug, Deserialize)]
+pub struct TrajectoryOptions {
+    pub initial: InitialState,
+    #[serde(default = "default_max_steps")]
+    pub max_steps: usize,
+    #[serde(default)]
+    pub max_time: Option<f64>,
+    #[serde(default = "default_epsilon")]
+    pub epsilon: f64,
+    #[serde(default)]
+    pub corner_policy: CornerPolicy,
+    #[serde(default)]
+    pub escape_box: Option<BoundingBox>,
+    #[serde(default = "default_one")]
+    pub restitution: f64,
+    #[serde(default)]
+    pub flight_model: FlightModel,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub invariants: Option<InvariantCheck>,
+}
+
+impl TrajectoryOptions {
+    fn config(&self) -> Result<SimulationConfig, JsError> {
+        Ok(SimulationConfig::builder()
+            .max_steps(self.max_steps)
+            .max_time(self.max_time)
+            .epsilon(self.epsilon)
+            .corner_policy(self.corner_policy)
+            .escape_box(self.escape_box)
+            .restitution(self.restitution)
+            .flight(self.flight_model)
+            .seed(self.seed)
+            .invariants(self.invariants)
+            .build()?)
+    }
+}
+
+/// Starting state on the boundary; `speed` defaults to 1.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct InitialState {
+    pub component_index: usize,
+    pub s: f64,
+    pub theta: f64,
+    #[serde(default = "default_one")]
+    pub speed: f64,
+}
+
+impl InitialState {
+    fn into_core(self) -> Result<BoundaryState, JsError> {
+        if !(self.speed.is_finite() && self.speed > 0.0) {
+            return Err(JsError::new("initial.speed must be positive and finite"));
+        }
+        Ok(BoundaryState {
+            component_index: self.component_index,
+            s: self.s,
+            theta: self.theta,
+            speed: self.speed,
+        })
+    }
+}
+
+fn default_max_steps() -> usize {
+    DEFAULT_MAX_STEPS
+}
+
+fn default_epsilon() -> f64 {
+    DEFAULT_EPSILON
+}
+
+fn default_one() -> f64 {
+    1.0
+}
+
+/// A simulated trajectory, kept on the Rust side so that views of it can
+/// be taken without running it again.
+#[wasm_bindgen]
+pub struct Trajectory {
+    table: Rc<BilliardTable>,
+    initial: BoundaryState,
+    collisions: Vec<CollisionResult>,
+    termination: Termination,
+    invariants: Option<InvariantReport>,
+}
+
+#[wasm_bindgen]
+impl Trajectory {
+    /// Number of collisions.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.collisions.len()
+    }
+
+    /// The collisions, as objects shaped like the API's.
+    pub fn collisions(&self) -> Result<JsValue, JsError> {
+        let collisions: Vec<Collision> = unfold_trajectory(&self.table, &self.collisions)
+            .iter()
+            .enumerate()
+            .map(|(step, u)| {
+                let c = &u.collision;
+                Collision {
+                    step,
+                    component_index: c.component_index,
+                    segment_index: c.segment_index,
+                    s: c.s,
+                    theta: c.theta,
+                    speed: c.speed,
+                    x: c.hit_point.x,
+                    y: c.hit_point.y,
+                    abs_x: u.absolute_position.x,
+                    abs_y: u.absolute_position.y,
+                    chord: c.chord,
+                    path_length: c.path_length,
+                    time: c.time,
+                    corner: c.corner.is_some(),
+                }
+            })
+            .collect();
+        to_js(&collisions)
+    }
+
+    /// Why the trajectory ended: `{ reason, escape? }` as in the API.
+    pub fn termination(&self) -> Result<JsValue, JsError> {
+        let (reason, escape) = match &self.termination {
+            Termination::MaxSteps => ("max_steps", None),
+            Termination::Corner => ("corner", None),
+            Termination::Absorbed => ("absorbed", None),
+            Termination::Escaped(e) => (
+                "escaped",
+                Some(Escape {
+                    x: e.position.x,
+                    y: e.position.y,
+                    dx: e.direction.x,
+                    dy: e.direction.y,
+                }),
+            ),
+            Termination::NoCollision => ("no_collision", None),
+            Termination::StopCondition => ("stop_condition", None),
+            Termination::MaxTime => ("max_time", None),
+            Termination::InvariantViolation => ("invariant_violation", None),
+            Termination::WhisperingGallery => ("whispering_gallery", None),
+        };
+        to_js(&TerminationInfo { reason, escape })
+    }
+
+    /// The worst violations of the invariants, if `invariants` was set in
+    /// the options; `null` otherwise.
+    pub fn invariants(&self) -> Result<JsValue, JsError> {
+        to_js(&self.invariants)
+    }
+
+    /// Bounces on the Poincaré section as `[s0, p0, s1, p1, ...]`, with `s`
+    /// the total arc length and `p = cos θ`; passes through periodic
+    /// segments are left out.
+    #[wasm_bindgen(js_name = phaseSpace)]
+    pub fn phase_space(&self) -> Vec<f64> {
+        phase_space_points(&self.table, &self.collisions)
+            .into_iter()
+            .flat_map(|(s, p)| [s, p])
+            .collect()
+    }
+
+    /// Bounce counts on an `s_bins × p_bins` grid of the Poincaré section.
+    #[wasm_bindgen(js_name = phaseSpaceHistogram)]
+    pub fn phase_space_histogram(&self, s_bins: usize, p_bins: usize) -> Result<JsValue, JsError> {
+        if s_bins == 0 || p_bins == 0 {
+            return Err(JsError::new("s_bins and p_bins must be greater than 0"));
+        }
+        let mut histogram = PhaseSpaceHistogram::new(s_bins, p_bins);
+        histogram.add(&self.table, &self.collisions);
+        to_js(&histogram)
+    }
+
+    /// The path as `[x0, y0, x1, y1, ...]` from the starting point, unfolded
+    /// across periodic edges; for drawing.
+    pub fn polyline(&self) -> Vec<f64> {
+        trajectory_to_polyline(&self.table, &self.initial, &self.collisions)
+            .into_iter()
+            .flat_map(|p| [p.x, p.y])
+            .collect()
+    }
+}
+
+/// One collision; see `CollisionDto` in the API.
+#[derive(Serialize)]
+struct Collision {
+    step: usize,
+    component_index: usize,
+    segment_index: usize,
+    s: f64,
+    theta: f64,
+    speed: f64,
+    x: f64,
+    y: f64,
+    abs_x: f64,
+    abs_y: f64,
+    chord: f64,
+    path_length: f64,
+    time: f64,
+    corner: bool,
+}
+
+#[derive(Serialize)]
+struct TerminationInfo {
+    reason: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    escape: Option<Escape>,
+}
+
+#[derive(Serialize)]
+struct Escape {
+    x: f64,
+    y: f64,
+    dx: f64,
+    dy: f64,
+}
@@ -0,0 +1,51 @@
+//! Arbitrary bytes as `TableSpec` JSON, through validation and into short
+//! trajectories from every component.
+//!
+//! Anything the parser and `try_to_billiard_table` accept must simulate
+//! without panicking; failures have to come back as errors.
+//!
+//! Run with `cargo +nightly fuzz run table_spec` from this directory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use billiard_core::dynamics::simulation::{BilliardMap, StepOptions};
+use billiard_core::dynamics::state::BoundaryState;
+use billiard_core::geometry::table_spec::TableSpec;
+use billiard_core::geometry::validation::validate_table;
+
+/// Launch angles tried from each starting point.
+const THETAS: [f64; 3] = [0.3, 1.2, 2.9];
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(spec) = serde_json::from_slice::<TableSpec>(data) else {
+        return;
+    };
+    validate_table(&spec);
+    let Ok(table) = spec.try_to_billiard_table() else {
+        return;
+    };
+
+    let mut map = BilliardMap::new(&table, 1e-8, StepOptions::default());
+    for (component_index, component) in table.components().enumerate() {
+        let length = component.length();
+        for (k, theta) in THETAS.into_iter().enumerate() {
+            let s = length * (k as f64 + 0.5) / THETAS.len() as f64;
+            let initial = BoundaryState::new(component_index, s, theta);
+            let Ok(trajectory) = map.simulate(&initial, 64) else {
+                continue;
+            };
+            for c in &trajectory.collisions {
+                let hit = table
+                    .get_component(c.component_index)
+                    .expect("collision on a component of the table");
+                assert!(hit.checked_locate(c.s).is_some(), "non-finite s {}", c.s);
+            }
+        }
+    }
+
+    // Out-of-range states are errors too.
+    let beyond = BoundaryState::new(table.component_count(), 0.0, 1.0);
+    assert!(map.simulate(&beyond, 1).is_err());
+});